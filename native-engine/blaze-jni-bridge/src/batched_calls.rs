@@ -0,0 +1,70 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::common::Result;
+
+/// accumulates high-frequency events (like rss pushes or metric updates) and
+/// hands them to the jvm side in one call, amortizing jni transition overhead
+/// on small-batch workloads.
+///
+/// events are flushed when either `max_events` or `max_bytes` is reached, or
+/// explicitly by calling `flush()`. pending events are NOT flushed on drop,
+/// callers must flush them before the underlying java object is closed.
+pub struct JniCallBatcher<T> {
+    pending: Vec<T>,
+    pending_bytes: usize,
+    max_events: usize,
+    max_bytes: usize,
+    flush_fn: Box<dyn FnMut(Vec<T>) -> Result<()> + Send>,
+}
+
+impl<T> JniCallBatcher<T> {
+    pub fn new(
+        max_events: usize,
+        max_bytes: usize,
+        flush_fn: impl FnMut(Vec<T>) -> Result<()> + Send + 'static,
+    ) -> Self {
+        Self {
+            pending: vec![],
+            pending_bytes: 0,
+            max_events: max_events.max(1),
+            max_bytes,
+            flush_fn: Box::new(flush_fn),
+        }
+    }
+
+    /// adds an event, triggers flushing if batch limits are reached.
+    pub fn push(&mut self, event: T, num_bytes: usize) -> Result<()> {
+        self.pending.push(event);
+        self.pending_bytes += num_bytes;
+        if self.pending.len() >= self.max_events || self.pending_bytes >= self.max_bytes {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// sends all pending events in one call.
+    pub fn flush(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let events = std::mem::take(&mut self.pending);
+        self.pending_bytes = 0;
+        (self.flush_fn)(events)
+    }
+
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+}
@@ -93,6 +93,22 @@ macro_rules! jni_map_error_with_env {
                 let ex = $env.exception_occurred().unwrap();
                 $env.exception_describe().unwrap();
                 $env.exception_clear().unwrap();
+                let class_name = $env
+                    .get_object_class(ex)
+                    .and_then(|class| {
+                        $env.call_method_unchecked(
+                            class,
+                            $crate::jni_bridge::JavaClasses::get().cClass.method_getName,
+                            $crate::jni_bridge::JavaClasses::get()
+                                .cClass
+                                .method_getName_ret
+                                .clone(),
+                            &[],
+                        )
+                    })
+                    .and_then(|name| name.l())
+                    .and_then(|name| $env.get_string(name.into()).map(|s| String::from(s)))
+                    .unwrap_or_default();
                 let message_obj = $env
                     .call_method_unchecked(
                         ex,
@@ -116,9 +132,10 @@ macro_rules! jni_map_error_with_env {
                     Err(
                         $crate::jni_bridge::datafusion::error::DataFusionError::External(
                             format!(
-                                "Java exception thrown at {}:{}: {}",
+                                "Java exception thrown at {}:{}: {}: {}",
                                 file!(),
                                 line!(),
+                                class_name,
                                 message
                             )
                             .into(),
@@ -128,9 +145,10 @@ macro_rules! jni_map_error_with_env {
                     Err(
                         $crate::jni_bridge::datafusion::error::DataFusionError::External(
                             format!(
-                                "Java exception thrown at {}:{}: (no message)",
+                                "Java exception thrown at {}:{}: {} (no message)",
                                 file!(),
-                                line!()
+                                line!(),
+                                class_name
                             )
                             .into(),
                         ),
@@ -378,6 +396,7 @@ pub struct JavaClasses<'a> {
     pub cClass: JavaClass<'a>,
     pub cJavaThrowable: JavaThrowable<'a>,
     pub cJavaRuntimeException: JavaRuntimeException<'a>,
+    pub cJavaTimeoutException: JavaTimeoutException<'a>,
     pub cJavaChannels: JavaChannels<'a>,
     pub cJavaReadableByteChannel: JavaReadableByteChannel<'a>,
     pub cJavaBoolean: JavaBoolean<'a>,
@@ -398,6 +417,7 @@ pub struct JavaClasses<'a> {
     pub cHadoopFileSystem: HadoopFileSystem<'a>,
     pub cHadoopPath: HadoopPath<'a>,
     pub cHadoopFSDataInputStream: HadoopFSDataInputStream<'a>,
+    pub cHadoopFSDataOutputStream: HadoopFSDataOutputStream<'a>,
 
     pub cSparkFileSegment: SparkFileSegment<'a>,
     pub cSparkSQLMetric: SparkSQLMetric<'a>,
@@ -407,6 +427,7 @@ pub struct JavaClasses<'a> {
     pub cBlazeRssPartitionWriterBase: BlazeRssPartitionWriterBase<'a>,
     pub cBlazeCallNativeWrapper: BlazeCallNativeWrapper<'a>,
     pub cBlazeOnHeapSpillManager: BlazeOnHeapSpillManager<'a>,
+    pub cBlazeNativeMemoryConsumer: BlazeNativeMemoryConsumer<'a>,
 }
 
 #[allow(clippy::non_send_fields_in_send_ty)]
@@ -441,6 +462,7 @@ impl JavaClasses<'static> {
                 cClass: JavaClass::new(env).unwrap(),
                 cJavaThrowable: JavaThrowable::new(env).unwrap(),
                 cJavaRuntimeException: JavaRuntimeException::new(env).unwrap(),
+                cJavaTimeoutException: JavaTimeoutException::new(env).unwrap(),
                 cJavaChannels: JavaChannels::new(env).unwrap(),
                 cJavaReadableByteChannel: JavaReadableByteChannel::new(env).unwrap(),
                 cJavaBoolean: JavaBoolean::new(env).unwrap(),
@@ -461,6 +483,7 @@ impl JavaClasses<'static> {
                 cHadoopFileSystem: HadoopFileSystem::new(env).unwrap(),
                 cHadoopPath: HadoopPath::new(env).unwrap(),
                 cHadoopFSDataInputStream: HadoopFSDataInputStream::new(env).unwrap(),
+                cHadoopFSDataOutputStream: HadoopFSDataOutputStream::new(env).unwrap(),
 
                 cSparkFileSegment: SparkFileSegment::new(env).unwrap(),
                 cSparkSQLMetric: SparkSQLMetric::new(env).unwrap(),
@@ -470,6 +493,7 @@ impl JavaClasses<'static> {
                 cBlazeRssPartitionWriterBase: BlazeRssPartitionWriterBase::new(env).unwrap(),
                 cBlazeCallNativeWrapper: BlazeCallNativeWrapper::new(env).unwrap(),
                 cBlazeOnHeapSpillManager: BlazeOnHeapSpillManager::new(env).unwrap(),
+                cBlazeNativeMemoryConsumer: BlazeNativeMemoryConsumer::new(env).unwrap(),
             };
             log::info!("Initializing JavaClasses finished");
             java_classes
@@ -503,10 +527,20 @@ pub struct JniBridge<'a> {
     pub method_getTaskContext_ret: ReturnType,
     pub method_getTaskOnHeapSpillManager: JStaticMethodID,
     pub method_getTaskOnHeapSpillManager_ret: ReturnType,
+    pub method_getTaskNativeMemoryConsumer: JStaticMethodID,
+    pub method_getTaskNativeMemoryConsumer_ret: ReturnType,
+    pub method_getLocalDirs: JStaticMethodID,
+    pub method_getLocalDirs_ret: ReturnType,
     pub method_isTaskRunning: JStaticMethodID,
     pub method_isTaskRunning_ret: ReturnType,
     pub method_isDriverSide: JStaticMethodID,
     pub method_isDriverSide_ret: ReturnType,
+    pub method_getPartitionId: JStaticMethodID,
+    pub method_getPartitionId_ret: ReturnType,
+    pub method_getNativeLogLevel: JStaticMethodID,
+    pub method_getNativeLogLevel_ret: ReturnType,
+    pub method_nativeLog: JStaticMethodID,
+    pub method_nativeLog_ret: ReturnType,
 }
 impl<'a> JniBridge<'a> {
     pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/JniBridge";
@@ -551,10 +585,36 @@ impl<'a> JniBridge<'a> {
                 "()Lorg/apache/spark/sql/blaze/memory/OnHeapSpillManager;",
             )?,
             method_getTaskOnHeapSpillManager_ret: ReturnType::Object,
+            method_getTaskNativeMemoryConsumer: env.get_static_method_id(
+                class,
+                "getTaskNativeMemoryConsumer",
+                "()Lorg/apache/spark/sql/blaze/memory/NativeMemoryConsumer;",
+            )?,
+            method_getTaskNativeMemoryConsumer_ret: ReturnType::Object,
+            method_getLocalDirs: env.get_static_method_id(
+                class,
+                "getLocalDirs",
+                "()Ljava/lang/String;",
+            )?,
+            method_getLocalDirs_ret: ReturnType::Object,
             method_isTaskRunning: env.get_static_method_id(class, "isTaskRunning", "()Z")?,
             method_isTaskRunning_ret: ReturnType::Primitive(Primitive::Boolean),
             method_isDriverSide: env.get_static_method_id(class, "isDriverSide", "()Z")?,
             method_isDriverSide_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_getPartitionId: env.get_static_method_id(class, "getPartitionId", "()I")?,
+            method_getPartitionId_ret: ReturnType::Primitive(Primitive::Int),
+            method_getNativeLogLevel: env.get_static_method_id(
+                class,
+                "getNativeLogLevel",
+                "()I",
+            )?,
+            method_getNativeLogLevel_ret: ReturnType::Primitive(Primitive::Int),
+            method_nativeLog: env.get_static_method_id(
+                class,
+                "nativeLog",
+                "(ILjava/lang/String;Ljava/lang/String;)V",
+            )?,
+            method_nativeLog_ret: ReturnType::Primitive(Primitive::Void),
         })
     }
 }
@@ -649,6 +709,23 @@ impl<'a> JavaRuntimeException<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct JavaTimeoutException<'a> {
+    pub class: JClass<'a>,
+    pub ctor: JMethodID,
+}
+impl<'a> JavaTimeoutException<'a> {
+    pub const SIG_TYPE: &'static str = "java/util/concurrent/TimeoutException";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<JavaTimeoutException<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(JavaTimeoutException {
+            class,
+            ctor: env.get_method_id(class, "<init>", "(Ljava/lang/String;)V")?,
+        })
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct JavaChannels<'a> {
     pub class: JClass<'a>,
@@ -1043,6 +1120,29 @@ impl<'a> HadoopFSDataInputStream<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct HadoopFSDataOutputStream<'a> {
+    pub class: JClass<'a>,
+    pub method_hflush: JMethodID,
+    pub method_hflush_ret: ReturnType,
+    pub method_hsync: JMethodID,
+    pub method_hsync_ret: ReturnType,
+}
+impl<'a> HadoopFSDataOutputStream<'a> {
+    pub const SIG_TYPE: &'static str = "org/apache/hadoop/fs/FSDataOutputStream";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<HadoopFSDataOutputStream<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(HadoopFSDataOutputStream {
+            class,
+            method_hflush: env.get_method_id(class, "hflush", "()V")?,
+            method_hflush_ret: ReturnType::Primitive(Primitive::Void),
+            method_hsync: env.get_method_id(class, "hsync", "()V")?,
+            method_hsync_ret: ReturnType::Primitive(Primitive::Void),
+        })
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct SparkFileSegment<'a> {
     pub class: JClass<'a>,
@@ -1096,6 +1196,8 @@ pub struct SparkMetricNode<'a> {
     pub method_getChild_ret: ReturnType,
     pub method_add: JMethodID,
     pub method_add_ret: ReturnType,
+    pub method_addAll: JMethodID,
+    pub method_addAll_ret: ReturnType,
 }
 impl<'a> SparkMetricNode<'a> {
     pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/MetricNode";
@@ -1116,6 +1218,10 @@ impl<'a> SparkMetricNode<'a> {
                 .get_method_id(class, "add", "(Ljava/lang/String;J)V")
                 .unwrap(),
             method_add_ret: ReturnType::Primitive(Primitive::Void),
+            method_addAll: env
+                .get_method_id(class, "addAll", "(Ljava/nio/ByteBuffer;)V")
+                .unwrap(),
+            method_addAll_ret: ReturnType::Primitive(Primitive::Void),
         })
     }
 }
@@ -1139,6 +1245,68 @@ pub struct BlazeConf<'a> {
     pub method_enableInputBatchStatistics_ret: ReturnType,
     pub method_ignoreCorruptedFiles: JStaticMethodID,
     pub method_ignoreCorruptedFiles_ret: ReturnType,
+    pub method_parquetSinkMaxOpenWriters: JStaticMethodID,
+    pub method_parquetSinkMaxOpenWriters_ret: ReturnType,
+    pub method_parquetEnablePageFiltering: JStaticMethodID,
+    pub method_parquetEnablePageFiltering_ret: ReturnType,
+    pub method_parquetEnableFilterPushdown: JStaticMethodID,
+    pub method_parquetEnableFilterPushdown_ret: ReturnType,
+    pub method_parquetEnableBloomFilter: JStaticMethodID,
+    pub method_parquetEnableBloomFilter_ret: ReturnType,
+    pub method_parquetScanParallelism: JStaticMethodID,
+    pub method_parquetScanParallelism_ret: ReturnType,
+    pub method_parquetScanParallelOrdered: JStaticMethodID,
+    pub method_parquetScanParallelOrdered_ret: ReturnType,
+    pub method_parquetMetadataCacheSize: JStaticMethodID,
+    pub method_parquetMetadataCacheSize_ret: ReturnType,
+    pub method_fsReadAheadSize: JStaticMethodID,
+    pub method_fsReadAheadSize_ret: ReturnType,
+    pub method_parquetReadRangesCoalesceGap: JStaticMethodID,
+    pub method_parquetReadRangesCoalesceGap_ret: ReturnType,
+    pub method_fsRetryMaxAttempts: JStaticMethodID,
+    pub method_fsRetryMaxAttempts_ret: ReturnType,
+    pub method_fsRetryInitialBackoffMs: JStaticMethodID,
+    pub method_fsRetryInitialBackoffMs_ret: ReturnType,
+    pub method_fsRetryMaxBackoffMs: JStaticMethodID,
+    pub method_fsRetryMaxBackoffMs_ret: ReturnType,
+    pub method_sparkManagedMemory: JStaticMethodID,
+    pub method_sparkManagedMemory_ret: ReturnType,
+    pub method_operatorMemoryFraction: JStaticMethodID,
+    pub method_operatorMemoryFraction_ret: ReturnType,
+    pub method_tokioWorkerThreads: JStaticMethodID,
+    pub method_tokioWorkerThreads_ret: ReturnType,
+    pub method_tokioThreadNamePrefix: JStaticMethodID,
+    pub method_tokioThreadNamePrefix_ret: ReturnType,
+    pub method_tokioPinCores: JStaticMethodID,
+    pub method_tokioPinCores_ret: ReturnType,
+    pub method_enableBatchValidation: JStaticMethodID,
+    pub method_enableBatchValidation_ret: ReturnType,
+    pub method_enableMetricsSummary: JStaticMethodID,
+    pub method_enableMetricsSummary_ret: ReturnType,
+    pub method_enableNativeColumnarToRow: JStaticMethodID,
+    pub method_enableNativeColumnarToRow_ret: ReturnType,
+    pub method_broadcastCacheMemSize: JStaticMethodID,
+    pub method_broadcastCacheMemSize_ret: ReturnType,
+    pub method_shuffleCompressionCodec: JStaticMethodID,
+    pub method_shuffleCompressionCodec_ret: ReturnType,
+    pub method_shuffleCompressionZstdLevel: JStaticMethodID,
+    pub method_shuffleCompressionZstdLevel_ret: ReturnType,
+    pub method_caseSensitive: JStaticMethodID,
+    pub method_caseSensitive_ret: ReturnType,
+    pub method_parquetSinkFileIndexEnabled: JStaticMethodID,
+    pub method_parquetSinkFileIndexEnabled_ret: ReturnType,
+    pub method_debugFlightPort: JStaticMethodID,
+    pub method_debugFlightPort_ret: ReturnType,
+    pub method_debugFlightHost: JStaticMethodID,
+    pub method_debugFlightHost_ret: ReturnType,
+    pub method_debugFlightOperator: JStaticMethodID,
+    pub method_debugFlightOperator_ret: ReturnType,
+    pub method_windowParallelism: JStaticMethodID,
+    pub method_windowParallelism_ret: ReturnType,
+    pub method_windowParallelMemoryLimit: JStaticMethodID,
+    pub method_windowParallelMemoryLimit_ret: ReturnType,
+    pub method_enableAdaptiveFilterOrder: JStaticMethodID,
+    pub method_enableAdaptiveFilterOrder_ret: ReturnType,
 }
 
 impl<'a> BlazeConf<'_> {
@@ -1178,6 +1346,130 @@ impl<'a> BlazeConf<'_> {
                 .get_static_method_id(class, "ignoreCorruptedFiles", "()Z")
                 .unwrap(),
             method_ignoreCorruptedFiles_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetSinkMaxOpenWriters: env
+                .get_static_method_id(class, "parquetSinkMaxOpenWriters", "()I")
+                .unwrap(),
+            method_parquetSinkMaxOpenWriters_ret: ReturnType::Primitive(Primitive::Int),
+            method_parquetEnablePageFiltering: env
+                .get_static_method_id(class, "parquetEnablePageFiltering", "()Z")
+                .unwrap(),
+            method_parquetEnablePageFiltering_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetEnableFilterPushdown: env
+                .get_static_method_id(class, "parquetEnableFilterPushdown", "()Z")
+                .unwrap(),
+            method_parquetEnableFilterPushdown_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetEnableBloomFilter: env
+                .get_static_method_id(class, "parquetEnableBloomFilter", "()Z")
+                .unwrap(),
+            method_parquetEnableBloomFilter_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetScanParallelism: env
+                .get_static_method_id(class, "parquetScanParallelism", "()I")
+                .unwrap(),
+            method_parquetScanParallelism_ret: ReturnType::Primitive(Primitive::Int),
+            method_parquetScanParallelOrdered: env
+                .get_static_method_id(class, "parquetScanParallelOrdered", "()Z")
+                .unwrap(),
+            method_parquetScanParallelOrdered_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetMetadataCacheSize: env
+                .get_static_method_id(class, "parquetMetadataCacheSize", "()I")
+                .unwrap(),
+            method_parquetMetadataCacheSize_ret: ReturnType::Primitive(Primitive::Int),
+            method_fsReadAheadSize: env
+                .get_static_method_id(class, "fsReadAheadSize", "()I")
+                .unwrap(),
+            method_fsReadAheadSize_ret: ReturnType::Primitive(Primitive::Int),
+            method_parquetReadRangesCoalesceGap: env
+                .get_static_method_id(class, "parquetReadRangesCoalesceGap", "()I")
+                .unwrap(),
+            method_parquetReadRangesCoalesceGap_ret: ReturnType::Primitive(Primitive::Int),
+            method_fsRetryMaxAttempts: env
+                .get_static_method_id(class, "fsRetryMaxAttempts", "()I")
+                .unwrap(),
+            method_fsRetryMaxAttempts_ret: ReturnType::Primitive(Primitive::Int),
+            method_fsRetryInitialBackoffMs: env
+                .get_static_method_id(class, "fsRetryInitialBackoffMs", "()I")
+                .unwrap(),
+            method_fsRetryInitialBackoffMs_ret: ReturnType::Primitive(Primitive::Int),
+            method_fsRetryMaxBackoffMs: env
+                .get_static_method_id(class, "fsRetryMaxBackoffMs", "()I")
+                .unwrap(),
+            method_fsRetryMaxBackoffMs_ret: ReturnType::Primitive(Primitive::Int),
+            method_sparkManagedMemory: env
+                .get_static_method_id(class, "sparkManagedMemory", "()Z")
+                .unwrap(),
+            method_sparkManagedMemory_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_operatorMemoryFraction: env
+                .get_static_method_id(class, "operatorMemoryFraction", "()D")
+                .unwrap(),
+            method_operatorMemoryFraction_ret: ReturnType::Primitive(Primitive::Double),
+            method_tokioWorkerThreads: env
+                .get_static_method_id(class, "tokioWorkerThreads", "()I")
+                .unwrap(),
+            method_tokioWorkerThreads_ret: ReturnType::Primitive(Primitive::Int),
+            method_tokioThreadNamePrefix: env
+                .get_static_method_id(class, "tokioThreadNamePrefix", "()Ljava/lang/String;")
+                .unwrap(),
+            method_tokioThreadNamePrefix_ret: ReturnType::Object,
+            method_tokioPinCores: env
+                .get_static_method_id(class, "tokioPinCores", "()Z")
+                .unwrap(),
+            method_tokioPinCores_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableBatchValidation: env
+                .get_static_method_id(class, "enableBatchValidation", "()Z")
+                .unwrap(),
+            method_enableBatchValidation_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableMetricsSummary: env
+                .get_static_method_id(class, "enableMetricsSummary", "()Z")
+                .unwrap(),
+            method_enableMetricsSummary_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_enableNativeColumnarToRow: env
+                .get_static_method_id(class, "enableNativeColumnarToRow", "()Z")
+                .unwrap(),
+            method_enableNativeColumnarToRow_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_broadcastCacheMemSize: env
+                .get_static_method_id(class, "broadcastCacheMemSize", "()J")
+                .unwrap(),
+            method_broadcastCacheMemSize_ret: ReturnType::Primitive(Primitive::Long),
+            method_shuffleCompressionCodec: env
+                .get_static_method_id(class, "shuffleCompressionCodec", "()Ljava/lang/String;")
+                .unwrap(),
+            method_shuffleCompressionCodec_ret: ReturnType::Object,
+            method_shuffleCompressionZstdLevel: env
+                .get_static_method_id(class, "shuffleCompressionZstdLevel", "()I")
+                .unwrap(),
+            method_shuffleCompressionZstdLevel_ret: ReturnType::Primitive(Primitive::Int),
+            method_caseSensitive: env
+                .get_static_method_id(class, "caseSensitive", "()Z")
+                .unwrap(),
+            method_caseSensitive_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_parquetSinkFileIndexEnabled: env
+                .get_static_method_id(class, "parquetSinkFileIndexEnabled", "()Z")
+                .unwrap(),
+            method_parquetSinkFileIndexEnabled_ret: ReturnType::Primitive(Primitive::Boolean),
+            method_debugFlightPort: env
+                .get_static_method_id(class, "debugFlightPort", "()I")
+                .unwrap(),
+            method_debugFlightPort_ret: ReturnType::Primitive(Primitive::Int),
+            method_debugFlightHost: env
+                .get_static_method_id(class, "debugFlightHost", "()Ljava/lang/String;")
+                .unwrap(),
+            method_debugFlightHost_ret: ReturnType::Object,
+            method_debugFlightOperator: env
+                .get_static_method_id(class, "debugFlightOperator", "()Ljava/lang/String;")
+                .unwrap(),
+            method_debugFlightOperator_ret: ReturnType::Object,
+            method_windowParallelism: env
+                .get_static_method_id(class, "windowParallelism", "()I")
+                .unwrap(),
+            method_windowParallelism_ret: ReturnType::Primitive(Primitive::Int),
+            method_windowParallelMemoryLimit: env
+                .get_static_method_id(class, "windowParallelMemoryLimit", "()I")
+                .unwrap(),
+            method_windowParallelMemoryLimit_ret: ReturnType::Primitive(Primitive::Int),
+            method_enableAdaptiveFilterOrder: env
+                .get_static_method_id(class, "enableAdaptiveFilterOrder", "()Z")
+                .unwrap(),
+            method_enableAdaptiveFilterOrder_ret: ReturnType::Primitive(Primitive::Boolean),
         })
     }
 }
@@ -1187,6 +1479,8 @@ pub struct BlazeRssPartitionWriterBase<'a> {
     pub class: JClass<'a>,
     pub method_write: JMethodID,
     pub method_write_ret: ReturnType,
+    pub method_writeBatched: JMethodID,
+    pub method_writeBatched_ret: ReturnType,
     pub method_flush: JMethodID,
     pub method_flush_ret: ReturnType,
     pub method_close: JMethodID,
@@ -1205,6 +1499,14 @@ impl<'a> BlazeRssPartitionWriterBase<'_> {
                 .get_method_id(class, "write", "(ILjava/nio/ByteBuffer;I)V")
                 .unwrap(),
             method_write_ret: ReturnType::Primitive(Primitive::Void),
+            method_writeBatched: env
+                .get_method_id(
+                    class,
+                    "writeBatched",
+                    "(Ljava/nio/ByteBuffer;Ljava/nio/ByteBuffer;)V",
+                )
+                .unwrap(),
+            method_writeBatched_ret: ReturnType::Primitive(Primitive::Void),
             method_flush: env.get_method_id(class, "flush", "()V").unwrap(),
             method_flush_ret: ReturnType::Primitive(Primitive::Void),
             method_close: env.get_method_id(class, "close", "()V").unwrap(),
@@ -1245,6 +1547,8 @@ pub struct BlazeCallNativeWrapper<'a> {
     pub method_setArrowFFIStreamPtr_ret: ReturnType,
     pub method_setError: JMethodID,
     pub method_setError_ret: ReturnType,
+    pub method_setMetricsSummary: JMethodID,
+    pub method_setMetricsSummary_ret: ReturnType,
 }
 impl<'a> BlazeCallNativeWrapper<'a> {
     pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/BlazeCallNativeWrapper";
@@ -1273,6 +1577,10 @@ impl<'a> BlazeCallNativeWrapper<'a> {
                 .get_method_id(class, "setError", "(Ljava/lang/Throwable;)V")
                 .unwrap(),
             method_setError_ret: ReturnType::Primitive(Primitive::Void),
+            method_setMetricsSummary: env
+                .get_method_id(class, "setMetricsSummary", "(Ljava/lang/String;)V")
+                .unwrap(),
+            method_setMetricsSummary_ret: ReturnType::Primitive(Primitive::Void),
         })
     }
 }
@@ -1322,6 +1630,35 @@ impl<'a> BlazeOnHeapSpillManager<'a> {
     }
 }
 
+#[allow(non_snake_case)]
+pub struct BlazeNativeMemoryConsumer<'a> {
+    pub class: JClass<'a>,
+    pub method_acquire: JMethodID,
+    pub method_acquire_ret: ReturnType,
+    pub method_release: JMethodID,
+    pub method_release_ret: ReturnType,
+    pub method_takeRequestedSpillSize: JMethodID,
+    pub method_takeRequestedSpillSize_ret: ReturnType,
+}
+impl<'a> BlazeNativeMemoryConsumer<'a> {
+    pub const SIG_TYPE: &'static str = "org/apache/spark/sql/blaze/memory/NativeMemoryConsumer";
+
+    pub fn new(env: &JNIEnv<'a>) -> JniResult<BlazeNativeMemoryConsumer<'a>> {
+        let class = get_global_jclass(env, Self::SIG_TYPE)?;
+        Ok(BlazeNativeMemoryConsumer {
+            class,
+            method_acquire: env.get_method_id(class, "acquire", "(J)J").unwrap(),
+            method_acquire_ret: ReturnType::Primitive(Primitive::Long),
+            method_release: env.get_method_id(class, "release", "(J)V").unwrap(),
+            method_release_ret: ReturnType::Primitive(Primitive::Void),
+            method_takeRequestedSpillSize: env
+                .get_method_id(class, "takeRequestedSpillSize", "()J")
+                .unwrap(),
+            method_takeRequestedSpillSize_ret: ReturnType::Primitive(Primitive::Long),
+        })
+    }
+}
+
 fn get_global_jclass(env: &JNIEnv<'_>, cls: &str) -> JniResult<JClass<'static>> {
     let local_jclass = env.find_class(cls)?;
     Ok(get_global_ref_jobject(env, local_jclass.into())?.into())
@@ -14,9 +14,10 @@
 
 use datafusion::common::Result;
 use jni::objects::GlobalRef;
-use jni::sys::{jboolean, JNI_FALSE, JNI_TRUE};
+use jni::sys::{jboolean, jint, JNI_FALSE, JNI_TRUE};
 use once_cell::sync::OnceCell;
 
+pub mod batched_calls;
 pub mod jni_bridge;
 
 pub fn is_jni_bridge_inited() -> bool {
@@ -34,6 +35,16 @@ pub fn is_task_running() -> bool {
     is_task_running_impl().expect("calling JniBridge.isTaskRunning() error")
 }
 
+/// returns the partition id of the current spark task, or 0 if not running in
+/// a spark task (driver side or jni bridge not initialized).
+pub fn current_partition_id() -> Result<usize> {
+    if !is_jni_bridge_inited() {
+        return Ok(0);
+    }
+    let partition_id = jni_call_static!(JniBridge.getPartitionId() -> jint)?;
+    Ok(partition_id as usize)
+}
+
 pub fn java_true() -> &'static GlobalRef {
     static OBJ_TRUE: OnceCell<GlobalRef> = OnceCell::new();
     OBJ_TRUE.get_or_init(|| {
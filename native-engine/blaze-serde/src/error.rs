@@ -28,13 +28,19 @@ pub type Result<T> = result::Result<T, PlanSerDeError>;
 #[derive(Debug)]
 pub enum PlanSerDeError {
     NotImplemented(String),
+    /// plan uses features unknown to this native engine, the jvm side should
+    /// fall back to spark execution
+    Unsupported(String),
     General(String),
     Internal(String),
     ArrowError(ArrowError),
     DataFusionError(DataFusionError),
     IoError(io::Error),
     MissingRequiredField(String),
-    UnknownEnumVariant { name: String, value: i32 },
+    UnknownEnumVariant {
+        name: String,
+        value: i32,
+    },
 }
 
 #[allow(clippy::from_over_into)]
@@ -78,6 +84,9 @@ impl Display for PlanSerDeError {
             PlanSerDeError::NotImplemented(ref desc) => {
                 write!(f, "Not implemented: {}", desc)
             }
+            PlanSerDeError::Unsupported(ref desc) => {
+                write!(f, "Unsupported feature, please fall back: {}", desc)
+            }
             PlanSerDeError::General(ref desc) => write!(f, "General error: {}", desc),
             PlanSerDeError::ArrowError(ref desc) => write!(f, "Arrow error: {}", desc),
             PlanSerDeError::DataFusionError(ref desc) => {
@@ -103,6 +112,10 @@ impl PlanSerDeError {
     pub(crate) fn required(field: impl Into<String>) -> PlanSerDeError {
         PlanSerDeError::MissingRequiredField(field.into())
     }
+
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, PlanSerDeError::Unsupported(_))
+    }
 }
 
 /// An extension trait that adds the methods `optional` and `required` to any
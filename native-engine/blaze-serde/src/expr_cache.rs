@@ -0,0 +1,242 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-level cache of deserialized expressions.
+//!
+//! all tasks of a stage carry the same expressions, while their plans differ
+//! in per-task parts (file groups, shuffle output files, etc.). expressions
+//! are cached by their encoded protobuf and input schema, so they are parsed
+//! and constructed only once per executor. only expressions known to be pure
+//! are cached, expressions carrying per-task states (like rand() or spark
+//! udfs) and unknown expressions are always parsed again.
+//!
+//! whole plans are not cached: execution plans carry per-task metrics and
+//! execution states, so each task still builds its own operator tree and only
+//! shares the expressions in it.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use datafusion::physical_expr::expressions::{LikeExpr, SCAndExpr, SCOrExpr};
+use datafusion::physical_expr::ScalarFunctionExpr;
+use datafusion::physical_plan::expressions::{
+    BinaryExpr, CaseExpr, CastExpr, Column, InListExpr, IsNotNullExpr, IsNullExpr, Literal,
+    NegativeExpr, NotExpr,
+};
+use datafusion::physical_plan::PhysicalExpr;
+use datafusion_ext_exprs::assert_true::AssertTrueExpr;
+use datafusion_ext_exprs::cast::TryCastExpr;
+use datafusion_ext_exprs::complex_in_list::ComplexInListExpr;
+use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
+use datafusion_ext_exprs::get_map_value::GetMapValueExpr;
+use datafusion_ext_exprs::named_struct::NamedStructExpr;
+use datafusion_ext_exprs::raise_error::RaiseErrorExpr;
+use datafusion_ext_exprs::string_contains::StringContainsExpr;
+use datafusion_ext_exprs::string_ends_with::StringEndsWithExpr;
+use datafusion_ext_exprs::string_like_multi::StringLikeMultiExpr;
+use datafusion_ext_exprs::string_starts_with::StringStartsWithExpr;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use prost::Message;
+
+use crate::error::PlanSerDeError;
+use crate::protobuf;
+
+const MAX_CACHED_EXPRS: usize = 4096;
+
+/// cached expressions grouped by input schema, and keyed by encoded protobuf
+/// within each schema, so that lookups need no owned keys.
+#[derive(Default)]
+struct ExprCache {
+    exprs: HashMap<SchemaRef, HashMap<Vec<u8>, Arc<dyn PhysicalExpr>>>,
+    num_exprs: usize,
+}
+
+static EXPR_CACHE: Lazy<Mutex<ExprCache>> = Lazy::new(|| Mutex::new(ExprCache::default()));
+
+thread_local! {
+    // reused buffer for encoding lookup keys
+    static ENCODE_BUF: RefCell<Vec<u8>> = RefCell::new(vec![]);
+
+    // only top-level expressions are cached, sub-expressions are parsed
+    // within their parents
+    static PARSING: Cell<bool> = Cell::new(false);
+    static CACHEABLE: Cell<bool> = Cell::new(true);
+}
+
+/// marks parsing of a top-level expression in current thread, the mark is
+/// reset on dropping, even if parsing fails or panics.
+struct ParsingGuard;
+
+impl ParsingGuard {
+    fn enter() -> Self {
+        PARSING.with(|parsing| parsing.set(true));
+        CACHEABLE.with(|cacheable| cacheable.set(true));
+        Self
+    }
+}
+
+impl Drop for ParsingGuard {
+    fn drop(&mut self) {
+        PARSING.with(|parsing| parsing.set(false));
+    }
+}
+
+/// returns the cached expression, or parses and caches it
+pub(crate) fn get_or_parse(
+    expr: &protobuf::PhysicalExprNode,
+    input_schema: &SchemaRef,
+    parse: impl FnOnce() -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError>,
+) -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
+    if PARSING.with(|parsing| parsing.get()) {
+        return parse();
+    }
+
+    let key = ENCODE_BUF.with(|buf| {
+        let mut buf = buf.borrow_mut();
+        buf.clear();
+        expr.encode(&mut *buf)
+            .expect("encoding into vec never fails");
+        EXPR_CACHE
+            .lock()
+            .exprs
+            .get(input_schema)
+            .and_then(|exprs| exprs.get(buf.as_slice()))
+            .cloned()
+            .ok_or_else(|| buf.clone())
+    });
+    let key = match key {
+        Ok(cached) => return Ok(cached),
+        Err(key) => key,
+    };
+
+    let parsed = {
+        let _guard = ParsingGuard::enter();
+        parse()?
+    };
+
+    if CACHEABLE.with(|cacheable| cacheable.get()) && is_pure(&parsed) {
+        let mut cache = EXPR_CACHE.lock();
+        if cache.num_exprs >= MAX_CACHED_EXPRS {
+            cache.exprs.clear();
+            cache.num_exprs = 0;
+        }
+        let exprs = cache.exprs.entry(input_schema.clone()).or_default();
+        if exprs.insert(key, parsed.clone()).is_none() {
+            cache.num_exprs += 1;
+        }
+    }
+    Ok(parsed)
+}
+
+/// marks the expression being parsed as uncacheable, used for expressions
+/// whose states cannot be told from their types (like extension expressions
+/// and volatile scalar functions)
+pub(crate) fn mark_uncacheable() {
+    CACHEABLE.with(|cacheable| cacheable.set(false));
+}
+
+/// returns true if the expression and all its children are known to be pure,
+/// that is, they carry no per-task states and can be shared among tasks.
+fn is_pure(expr: &Arc<dyn PhysicalExpr>) -> bool {
+    let expr_any = expr.as_any();
+    let pure = expr_any.is::<Column>()
+        || expr_any.is::<Literal>()
+        || expr_any.is::<BinaryExpr>()
+        || expr_any.is::<IsNullExpr>()
+        || expr_any.is::<IsNotNullExpr>()
+        || expr_any.is::<NotExpr>()
+        || expr_any.is::<NegativeExpr>()
+        || expr_any.is::<InListExpr>()
+        || expr_any.is::<ComplexInListExpr>()
+        || expr_any.is::<CaseExpr>()
+        || expr_any.is::<CastExpr>()
+        || expr_any.is::<TryCastExpr>()
+        || expr_any.is::<ScalarFunctionExpr>() // volatile functions are marked uncacheable
+        || expr_any.is::<GetIndexedFieldExpr>()
+        || expr_any.is::<GetMapValueExpr>()
+        || expr_any.is::<StringStartsWithExpr>()
+        || expr_any.is::<StringEndsWithExpr>()
+        || expr_any.is::<StringContainsExpr>()
+        || expr_any.is::<StringLikeMultiExpr>()
+        || expr_any.is::<SCAndExpr>()
+        || expr_any.is::<SCOrExpr>()
+        || expr_any.is::<LikeExpr>()
+        || expr_any.is::<NamedStructExpr>()
+        || expr_any.is::<RaiseErrorExpr>()
+        || expr_any.is::<AssertTrueExpr>();
+    pure && expr.children().iter().all(is_pure)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::PlanSerDeError;
+    use crate::expr_cache::get_or_parse;
+    use crate::protobuf;
+    use crate::protobuf::physical_expr_node::ExprType;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::expressions::Column;
+    use datafusion::physical_plan::PhysicalExpr;
+    use datafusion_ext_exprs::rand::RandExpr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_expr_cache() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let column_node = protobuf::PhysicalExprNode {
+            expr_type: Some(ExprType::Column(protobuf::PhysicalColumn {
+                name: "a".to_string(),
+                index: 0,
+            })),
+        };
+        let parse_column = || Ok(Arc::new(Column::new("a", 0)) as Arc<dyn PhysicalExpr>);
+        let e1 = get_or_parse(&column_node, &schema, parse_column).unwrap();
+        let e2 = get_or_parse(&column_node, &schema, || unreachable!()).unwrap();
+        assert!(Arc::ptr_eq(&e1, &e2));
+
+        // stateful expressions are not cached
+        let rand_node = protobuf::PhysicalExprNode {
+            expr_type: Some(ExprType::RandExpr(protobuf::PhysicalRandExprNode {
+                seed: 0,
+                gaussian: false,
+            })),
+        };
+        let parse_rand = || Ok(Arc::new(RandExpr::new(0, false)) as Arc<dyn PhysicalExpr>);
+        let e1 = get_or_parse(&rand_node, &schema, parse_rand).unwrap();
+        let e2 = get_or_parse(&rand_node, &schema, parse_rand).unwrap();
+        assert!(!Arc::ptr_eq(&e1, &e2));
+    }
+
+    #[test]
+    fn test_expr_cache_after_parsing_error() {
+        let schema = Arc::new(Schema::new(vec![Field::new("b", DataType::Int32, true)]));
+        let column_node = protobuf::PhysicalExprNode {
+            expr_type: Some(ExprType::Column(protobuf::PhysicalColumn {
+                name: "b".to_string(),
+                index: 0,
+            })),
+        };
+
+        // parsing state is reset after an error, later expressions are still cached
+        let parse_error = || Err(PlanSerDeError::General("parsing error".to_string()));
+        assert!(get_or_parse(&column_node, &schema, parse_error).is_err());
+
+        let parse_column = || Ok(Arc::new(Column::new("b", 0)) as Arc<dyn PhysicalExpr>);
+        let e1 = get_or_parse(&column_node, &schema, parse_column).unwrap();
+        let e2 = get_or_parse(&column_node, &schema, || unreachable!()).unwrap();
+        assert!(Arc::ptr_eq(&e1, &e2));
+    }
+}
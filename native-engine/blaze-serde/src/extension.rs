@@ -0,0 +1,173 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registries of third-party plan nodes, so external crates can plug their
+//! own operators and expressions into blaze without modifying blaze-serde.
+//!
+//! builders are registered by name at engine startup, and invoked with the
+//! opaque payload of ExtensionExecNode/PhysicalExtensionExprNode when
+//! deserializing plans.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use datafusion::common::Result;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+
+use crate::error::PlanSerDeError;
+use crate::unsupported_error;
+
+/// builds an execution plan from the opaque payload and deserialized inputs
+pub type ExtensionPlanBuilder =
+    Arc<dyn Fn(&[u8], Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> + Send + Sync>;
+
+/// builds an expression from the opaque payload, deserialized children and
+/// the input schema
+pub type ExtensionExprBuilder = Arc<
+    dyn Fn(&[u8], Vec<Arc<dyn PhysicalExpr>>, &SchemaRef) -> Result<Arc<dyn PhysicalExpr>>
+        + Send
+        + Sync,
+>;
+
+static PLAN_BUILDERS: Lazy<RwLock<HashMap<String, ExtensionPlanBuilder>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+static EXPR_BUILDERS: Lazy<RwLock<HashMap<String, ExtensionExprBuilder>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// registers a plan builder, replacing the previous one with the same name
+pub fn register_extension_plan(name: &str, builder: ExtensionPlanBuilder) {
+    log::info!("registering extension plan: {}", name);
+    PLAN_BUILDERS.write().insert(name.to_string(), builder);
+}
+
+pub(crate) fn build_extension_plan(
+    name: &str,
+    payload: &[u8],
+    inputs: Vec<Arc<dyn ExecutionPlan>>,
+) -> std::result::Result<Arc<dyn ExecutionPlan>, PlanSerDeError> {
+    let builder = PLAN_BUILDERS
+        .read()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| unsupported_error(format!("extension plan not registered: {}", name)))?;
+    Ok(builder(payload, inputs)?)
+}
+
+/// registers an expr builder, replacing the previous one with the same name
+pub fn register_extension_expr(name: &str, builder: ExtensionExprBuilder) {
+    log::info!("registering extension expr: {}", name);
+    EXPR_BUILDERS.write().insert(name.to_string(), builder);
+}
+
+pub(crate) fn build_extension_expr(
+    name: &str,
+    payload: &[u8],
+    children: Vec<Arc<dyn PhysicalExpr>>,
+    input_schema: &SchemaRef,
+) -> std::result::Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
+    let builder = EXPR_BUILDERS
+        .read()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| unsupported_error(format!("extension expr not registered: {}", name)))?;
+    Ok(builder(payload, children, input_schema)?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::extension::{register_extension_expr, register_extension_plan};
+    use crate::protobuf::physical_expr_node::ExprType;
+    use crate::protobuf::physical_plan_node::PhysicalPlanType;
+    use crate::protobuf::{
+        ExtensionExecNode, FilterExecNode, PhysicalColumn, PhysicalExprNode,
+        PhysicalExtensionExprNode, PhysicalPlanNode,
+    };
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::expressions::IsNotNullExpr;
+    use datafusion::physical_plan::{displayable, ExecutionPlan};
+    use datafusion_ext_plans::empty_partitions_exec::EmptyPartitionsExec;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_extension_plan() {
+        register_extension_plan(
+            "test_empty",
+            Arc::new(|payload, inputs| {
+                assert!(inputs.is_empty());
+                let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+                Ok(Arc::new(EmptyPartitionsExec::new(
+                    schema,
+                    payload[0] as usize,
+                )))
+            }),
+        );
+
+        let extension_node = |name: &str| PhysicalPlanNode {
+            physical_plan_type: Some(PhysicalPlanType::Extension(ExtensionExecNode {
+                name: name.to_string(),
+                payload: vec![3],
+                inputs: vec![],
+            })),
+        };
+        let plan: Arc<dyn ExecutionPlan> = (&extension_node("test_empty")).try_into().unwrap();
+        assert_eq!(plan.output_partitioning().partition_count(), 3);
+
+        let result: Result<Arc<dyn ExecutionPlan>, _> =
+            (&extension_node("not_registered")).try_into();
+        assert!(result.unwrap_err().is_unsupported());
+    }
+
+    #[test]
+    fn test_extension_expr() {
+        register_extension_expr(
+            "test_not_null",
+            Arc::new(|_payload, mut children, _input_schema| {
+                Ok(Arc::new(IsNotNullExpr::new(children.remove(0))))
+            }),
+        );
+
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let filter_node = PhysicalPlanNode {
+            physical_plan_type: Some(PhysicalPlanType::Filter(Box::new(FilterExecNode {
+                input: Some(Box::new(PhysicalPlanNode {
+                    physical_plan_type: Some(PhysicalPlanType::EmptyPartitions(
+                        crate::protobuf::EmptyPartitionsExecNode {
+                            schema: Some(schema.as_ref().try_into().unwrap()),
+                            num_partitions: 1,
+                        },
+                    )),
+                })),
+                expr: vec![PhysicalExprNode {
+                    expr_type: Some(ExprType::ExtensionExpr(PhysicalExtensionExprNode {
+                        name: "test_not_null".to_string(),
+                        payload: vec![],
+                        children: vec![PhysicalExprNode {
+                            expr_type: Some(ExprType::Column(PhysicalColumn {
+                                name: "a".to_string(),
+                                index: 0,
+                            })),
+                        }],
+                    })),
+                }],
+            }))),
+        };
+        let plan: Arc<dyn ExecutionPlan> = (&filter_node).try_into().unwrap();
+        let plan_str = displayable(plan.as_ref()).one_line().to_string();
+        assert!(plan_str.contains("a@0 IS NOT NULL"), "{}", plan_str);
+    }
+}
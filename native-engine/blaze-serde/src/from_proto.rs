@@ -14,19 +14,21 @@
 
 //! Serde code to convert from protocol buffers to Rust data structures.
 
+use std::cell::Cell;
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 
 use arrow::datatypes::{FieldRef, SchemaRef};
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use base64::Engine;
-use chrono::DateTime;
+use chrono::{TimeZone, Utc};
+use datafusion::common::ScalarValue;
 use datafusion::datasource::listing::{FileRange, PartitionedFile};
 use datafusion::datasource::object_store::ObjectStoreUrl;
 use datafusion::datasource::physical_plan::FileScanConfig;
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::ExecutionProps;
-use datafusion::logical_expr::{BuiltinScalarFunction, Operator};
+use datafusion::logical_expr::{BuiltinScalarFunction, Operator, Volatility};
 use datafusion::physical_expr::expressions::{LikeExpr, SCAndExpr, SCOrExpr};
 use datafusion::physical_expr::{functions, ScalarFunctionExpr};
 use datafusion::physical_plan::joins::utils::{ColumnIndex, JoinFilter};
@@ -42,23 +44,31 @@ use datafusion::physical_plan::{
 };
 use datafusion::physical_plan::{ColumnStatistics, ExecutionPlan, PhysicalExpr, Statistics};
 
+use datafusion_ext_commons::datetime_rebase::RebaseMode;
+use datafusion_ext_commons::io::IpcCompressionCodec;
 use datafusion_ext_commons::streams::ipc_stream::IpcReadMode;
 use datafusion_ext_plans::agg::{
     create_agg, AggExecMode, AggExpr, AggFunction, AggMode, GroupingExpr,
 };
 use datafusion_ext_plans::agg_exec::AggExec;
 use datafusion_ext_plans::broadcast_join_exec::BroadcastJoinExec;
-use datafusion_ext_plans::debug_exec::DebugExec;
+use datafusion_ext_plans::collect_stats_exec::{CollectStatsExec, CollectStatsMode};
+use datafusion_ext_plans::debug_exec::{DebugDump, DebugDumpFormat, DebugExec};
 use datafusion_ext_plans::empty_partitions_exec::EmptyPartitionsExec;
 use datafusion_ext_plans::expand_exec::ExpandExec;
+use datafusion_ext_plans::ffi_exporter_exec::FFIExporterExec;
 use datafusion_ext_plans::ffi_reader_exec::FFIReaderExec;
 use datafusion_ext_plans::filter_exec::FilterExec;
 use datafusion_ext_plans::ipc_reader_exec::IpcReaderExec;
-use datafusion_ext_plans::ipc_writer_exec::IpcWriterExec;
-use datafusion_ext_plans::limit_exec::LimitExec;
-use datafusion_ext_plans::parquet_exec::ParquetExec;
+use datafusion_ext_plans::ipc_writer_exec::{
+    IpcWriterExec, DEFAULT_MAX_INFLIGHT_CHUNKS, DEFAULT_TARGET_CHUNK_SIZE,
+};
+use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+use datafusion_ext_plans::parquet_exec::{
+    dictionary_encode_partition_columns, ParquetExec, SchemaMatchMode,
+};
 use datafusion_ext_plans::project_exec::ProjectExec;
-use datafusion_ext_plans::rename_columns_exec::RenameColumnsExec;
+use datafusion_ext_plans::rename_columns_exec::{NestedFieldRename, RenameColumnsExec};
 use datafusion_ext_plans::rss_shuffle_writer_exec::RssShuffleWriterExec;
 use datafusion_ext_plans::shuffle_writer_exec::ShuffleWriterExec;
 use datafusion_ext_plans::sort_exec::SortExec;
@@ -67,27 +77,82 @@ use object_store::path::Path;
 use object_store::ObjectMeta;
 
 use crate::error::PlanSerDeError;
+use crate::expr_cache;
+use crate::extension::{build_extension_expr, build_extension_plan};
 use crate::protobuf::physical_expr_node::ExprType;
 use crate::protobuf::physical_plan_node::PhysicalPlanType;
 use crate::protobuf::GenerateFunction;
 use crate::{convert_box_required, convert_required, into_required, protobuf, Schema};
-use crate::{from_proto_binary_op, proto_error};
+use crate::{from_proto_binary_op, proto_error, unsupported_error};
+use datafusion_ext_exprs::assert_true::AssertTrueExpr;
 use datafusion_ext_exprs::cast::TryCastExpr;
+use datafusion_ext_exprs::complex_in_list::ComplexInListExpr;
 use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
 use datafusion_ext_exprs::get_map_value::GetMapValueExpr;
+use datafusion_ext_exprs::input_file_name::InputFileNameExpr;
+use datafusion_ext_exprs::monotonically_increasing_id::MonotonicallyIncreasingIdExpr;
 use datafusion_ext_exprs::named_struct::NamedStructExpr;
+use datafusion_ext_exprs::raise_error::RaiseErrorExpr;
+use datafusion_ext_exprs::rand::RandExpr;
+use datafusion_ext_exprs::spark_partition_id::SparkPartitionIdExpr;
 use datafusion_ext_exprs::spark_scalar_subquery_wrapper::SparkScalarSubqueryWrapperExpr;
 use datafusion_ext_exprs::spark_udf_wrapper::SparkUDFWrapperExpr;
 use datafusion_ext_exprs::string_contains::StringContainsExpr;
 use datafusion_ext_exprs::string_ends_with::StringEndsWithExpr;
+use datafusion_ext_exprs::string_like_multi::StringLikeMultiExpr;
 use datafusion_ext_exprs::string_starts_with::StringStartsWithExpr;
+use datafusion_ext_exprs::uuid::UuidExpr;
 use datafusion_ext_plans::broadcast_nested_loop_join_exec::BroadcastNestedLoopJoinExec;
 use datafusion_ext_plans::generate::create_generator;
 use datafusion_ext_plans::generate_exec::GenerateExec;
-use datafusion_ext_plans::parquet_sink_exec::ParquetSinkExec;
+use datafusion_ext_plans::parquet_sink_exec::{
+    BucketHashFunction, ParquetSinkExec, SinkBucketSpec,
+};
+use datafusion_ext_plans::text_sink_exec::{
+    CsvWriteOptions, JsonWriteOptions, TextSinkExec, TextSinkFormat,
+};
 use datafusion_ext_plans::window::{WindowExpr, WindowFunction, WindowRankType};
 use datafusion_ext_plans::window_exec::WindowExec;
 
+thread_local! {
+    static CASE_SENSITIVE: Cell<bool> = Cell::new(true);
+}
+
+/// sets whether column names are resolved case-sensitively when binding
+/// plans deserialized in the current thread (spark.sql.caseSensitive)
+pub fn set_case_sensitive(case_sensitive: bool) {
+    CASE_SENSITIVE.with(|cell| cell.set(case_sensitive));
+}
+
+/// resolves a column by name, falls back to a unique case-insensitive match
+/// if no exact match exists and case-insensitive resolution is enabled
+fn column_with_schema(name: &str, schema: &Schema) -> Result<Column, DataFusionError> {
+    let case_sensitive = CASE_SENSITIVE.with(|cell| cell.get());
+    resolve_column(name, schema, case_sensitive)
+}
+
+fn resolve_column(
+    name: &str,
+    schema: &Schema,
+    case_sensitive: bool,
+) -> Result<Column, DataFusionError> {
+    match Column::new_with_schema(name, schema) {
+        Ok(column) => Ok(column),
+        Err(err) if case_sensitive => Err(err),
+        Err(err) => {
+            let mut matched = schema
+                .fields()
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| field.name().eq_ignore_ascii_case(name));
+            match (matched.next(), matched.next()) {
+                (Some((index, field)), None) => Ok(Column::new(field.name(), index)),
+                _ => Err(err),
+            }
+        }
+    }
+}
+
 fn bind(
     expr_in: Arc<dyn PhysicalExpr>,
     input_schema: &Arc<Schema>,
@@ -98,10 +163,7 @@ fn bind(
         if expr.name() == "__bound_reference__" {
             Ok(Arc::new(expr.clone()))
         } else {
-            Ok(Arc::new(Column::new_with_schema(
-                expr.name(),
-                input_schema,
-            )?))
+            Ok(Arc::new(column_with_schema(expr.name(), input_schema)?))
         }
     } else {
         let new_children = expr_in
@@ -117,9 +179,10 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
     type Error = PlanSerDeError;
 
     fn try_into(self) -> Result<Arc<dyn ExecutionPlan>, Self::Error> {
+        // unknown plan types from newer jvm sides are decoded as empty
         let plan = self.physical_plan_type.as_ref().ok_or_else(|| {
-            proto_error(format!(
-                "physical_plan::from_proto() Unsupported physical plan '{:?}'",
+            unsupported_error(format!(
+                "physical_plan::from_proto() Unknown physical plan '{:?}'",
                 self
             ))
         })?;
@@ -157,7 +220,10 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 Ok(Arc::new(FilterExec::try_new(predicates, input)?))
             }
             PhysicalPlanType::ParquetScan(scan) => {
-                let conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                let mut conf: FileScanConfig = scan.base_conf.as_ref().unwrap().try_into()?;
+                if scan.dictionary_partition_columns {
+                    conf = dictionary_encode_partition_columns(conf);
+                }
                 let predicate = scan
                     .pruning_predicates
                     .iter()
@@ -167,11 +233,33 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     .fold(phys_expr::lit(true), |a, b| {
                         Arc::new(BinaryExpr::new(a, Operator::And, b))
                     });
-                Ok(Arc::new(ParquetExec::new(
+                let schema_match_mode = match scan.schema_match_mode() {
+                    protobuf::ParquetSchemaMatchMode::Name => SchemaMatchMode::Name,
+                    protobuf::ParquetSchemaMatchMode::CaseInsensitiveName => {
+                        SchemaMatchMode::CaseInsensitiveName
+                    }
+                    protobuf::ParquetSchemaMatchMode::FieldId => SchemaMatchMode::FieldId(
+                        scan.field_ids
+                            .iter()
+                            .map(|&field_id| (field_id >= 0).then_some(field_id))
+                            .collect(),
+                    ),
+                };
+                let mut parquet_exec = ParquetExec::new(
                     conf,
                     scan.fs_resource_id.clone(),
                     Some(predicate),
-                )))
+                    schema_match_mode,
+                    scan.datetime_rebase_mode().into(),
+                    scan.int96_rebase_mode().into(),
+                );
+                if let Some(bucket_partitioning) = parse_protobuf_hash_partitioning(
+                    Arc::new(parquet_exec.clone()),
+                    scan.bucket_partitioning.as_ref(),
+                )? {
+                    parquet_exec = parquet_exec.with_bucket_partitioning(bucket_partitioning)?;
+                }
+                Ok(Arc::new(parquet_exec))
             }
             PhysicalPlanType::SortMergeJoin(sort_merge_join) => {
                 let left: Arc<dyn ExecutionPlan> = convert_box_required!(sort_merge_join.left)?;
@@ -182,10 +270,10 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     .map(|col| {
                         let left_col: Column = into_required!(col.left)?;
                         let left_col_binded: Column =
-                            Column::new_with_schema(left_col.name(), &left.schema())?;
+                            column_with_schema(left_col.name(), &left.schema())?;
                         let right_col: Column = into_required!(col.right)?;
                         let right_col_binded: Column =
-                            Column::new_with_schema(right_col.name(), &right.schema())?;
+                            column_with_schema(right_col.name(), &right.schema())?;
                         Ok((left_col_binded, right_col_binded))
                     })
                     .collect::<Result<_, Self::Error>>()?;
@@ -274,9 +362,40 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
             PhysicalPlanType::IpcWriter(ipc_writer) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(ipc_writer.input)?;
 
+                let codec =
+                    parse_ipc_compression_codec(ipc_writer.codec, ipc_writer.compression_level)
+                        .unwrap_or(IpcCompressionCodec::from_compress(true));
+                let target_chunk_size = match ipc_writer.target_chunk_size {
+                    0 => DEFAULT_TARGET_CHUNK_SIZE,
+                    size => size as usize,
+                };
+                let max_inflight_chunks = match ipc_writer.max_inflight_chunks {
+                    0 => DEFAULT_MAX_INFLIGHT_CHUNKS,
+                    n => n as usize,
+                };
                 Ok(Arc::new(IpcWriterExec::new(
                     input,
                     ipc_writer.ipc_consumer_resource_id.clone(),
+                    codec,
+                    target_chunk_size,
+                    max_inflight_chunks,
+                    ipc_writer.checksum,
+                )))
+            }
+            PhysicalPlanType::CollectStats(collect_stats) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(collect_stats.input)?;
+                let mode = match collect_stats.mode() {
+                    protobuf::CollectStatsMode::Partial => CollectStatsMode::Partial,
+                    protobuf::CollectStatsMode::Final => CollectStatsMode::Final,
+                };
+                Ok(Arc::new(CollectStatsExec::try_new(input, mode)?))
+            }
+            PhysicalPlanType::FfiExporter(ffi_exporter) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(ffi_exporter.input)?;
+
+                Ok(Arc::new(FFIExporterExec::new(
+                    input,
+                    ffi_exporter.exporter_resource_id.clone(),
                 )))
             }
             PhysicalPlanType::IpcReader(ipc_reader) => {
@@ -288,16 +407,34 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                         IpcReadMode::ChannelAndFileSegment
                     }
                 };
+                let codec = parse_ipc_compression_codec(ipc_reader.codec, 0);
                 Ok(Arc::new(IpcReaderExec::new(
                     ipc_reader.num_partitions as usize,
                     ipc_reader.ipc_provider_resource_id.clone(),
                     schema,
                     mode,
+                    codec,
+                    ipc_reader.checksum,
                 )))
             }
             PhysicalPlanType::Debug(debug) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(debug.input)?;
-                Ok(Arc::new(DebugExec::new(input, debug.debug_id.clone())))
+                let dump = match protobuf::DebugDumpFormat::from_i32(debug.dump_format).unwrap() {
+                    protobuf::DebugDumpFormat::NoDump => None,
+                    protobuf::DebugDumpFormat::DumpIpc => Some(DebugDump {
+                        format: DebugDumpFormat::Ipc,
+                        dir: debug.dump_dir.clone(),
+                    }),
+                    protobuf::DebugDumpFormat::DumpParquet => Some(DebugDump {
+                        format: DebugDumpFormat::Parquet,
+                        dir: debug.dump_dir.clone(),
+                    }),
+                };
+                Ok(Arc::new(DebugExec::new(
+                    input,
+                    debug.debug_id.clone(),
+                    dump,
+                )))
             }
             PhysicalPlanType::Sort(sort) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(sort.input)?;
@@ -356,10 +493,10 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     .map(|col| {
                         let left_col: Column = into_required!(col.left)?;
                         let left_col_binded: Column =
-                            Column::new_with_schema(left_col.name(), &left.schema())?;
+                            column_with_schema(left_col.name(), &left.schema())?;
                         let right_col: Column = into_required!(col.right)?;
                         let right_col_binded: Column =
-                            Column::new_with_schema(right_col.name(), &right.schema())?;
+                            column_with_schema(right_col.name(), &right.schema())?;
                         Ok((left_col_binded, right_col_binded))
                     })
                     .collect::<Result<_, Self::Error>>()?;
@@ -405,6 +542,7 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     on,
                     join_type.into(),
                     join_filter,
+                    Some(broadcast_join.cached_build_id.clone()).filter(|id| !id.is_empty()),
                 )?))
             }
             PhysicalPlanType::BroadcastNestedLoopJoin(bnlj) => {
@@ -467,9 +605,19 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
             }
             PhysicalPlanType::RenameColumns(rename_columns) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(rename_columns.input)?;
-                Ok(Arc::new(RenameColumnsExec::try_new(
+                let nested_renames = rename_columns
+                    .nested_renames
+                    .iter()
+                    .map(|nested_rename| NestedFieldRename {
+                        column_index: nested_rename.column_index as usize,
+                        field_path: nested_rename.field_path.clone(),
+                        new_name: nested_rename.new_name.clone(),
+                    })
+                    .collect();
+                Ok(Arc::new(RenameColumnsExec::try_new_with_nested_renames(
                     input,
                     rename_columns.renamed_column_names.clone(),
+                    nested_renames,
                 )?))
             }
             PhysicalPlanType::Agg(agg) => {
@@ -532,7 +680,7 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
 
                         let agg_function = protobuf::AggFunction::from_i32(agg_node.agg_function)
                             .ok_or_else(|| {
-                            proto_error(format!(
+                            unsupported_error(format!(
                                 "Received an unknown aggregate function: {}",
                                 agg_node.agg_function
                             ))
@@ -568,7 +716,13 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
             }
             PhysicalPlanType::Limit(limit) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(limit.input)?;
-                Ok(Arc::new(LimitExec::new(input, limit.limit)))
+                let mode = protobuf::LimitMode::from_i32(limit.mode)
+                    .ok_or_else(|| proto_error(format!("invalid LimitMode {}", limit.mode)))
+                    .map(|mode| match mode {
+                        protobuf::LimitMode::Global => LimitMode::Global,
+                        protobuf::LimitMode::Local => LimitMode::Local,
+                    })?;
+                Ok(Arc::new(LimitExec::new(input, limit.limit, mode)))
             }
             PhysicalPlanType::FfiReader(ffi_reader) => {
                 let schema = Arc::new(convert_required!(ffi_reader.schema)?);
@@ -576,11 +730,16 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                     ffi_reader.num_partitions as usize,
                     ffi_reader.export_iter_provider_resource_id.clone(),
                     schema,
+                    ffi_reader.arrow_c_stream,
                 )))
             }
             PhysicalPlanType::CoalesceBatches(coalesce_batches) => {
                 let input: Arc<dyn ExecutionPlan> = convert_box_required!(coalesce_batches.input)?;
-                Ok(Arc::new(LimitExec::new(input, coalesce_batches.batch_size)))
+                Ok(Arc::new(LimitExec::new(
+                    input,
+                    coalesce_batches.batch_size,
+                    LimitMode::Global,
+                )))
             }
             PhysicalPlanType::Expand(expand) => {
                 let schema = Arc::new(convert_required!(expand.schema)?);
@@ -665,6 +824,9 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                                 protobuf::AggFunction::FirstIgnoresNull => {
                                     WindowFunction::Agg(AggFunction::FirstIgnoresNull)
                                 }
+                                protobuf::AggFunction::HistogramNumeric => {
+                                    WindowFunction::Agg(AggFunction::HistogramNumeric)
+                                }
                             },
                         };
                         Ok::<_, Self::Error>(WindowExpr::new(window_func, children, field))
@@ -766,7 +928,7 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 let required_child_output_cols = generate
                     .required_child_output
                     .iter()
-                    .map(|name| Ok(Column::new_with_schema(name, &input_schema)?))
+                    .map(|name| Ok(column_with_schema(name, &input_schema)?))
                     .collect::<Result<_, PlanSerDeError>>()?;
 
                 Ok(Arc::new(GenerateExec::try_new(
@@ -778,17 +940,121 @@ impl TryInto<Arc<dyn ExecutionPlan>> for &protobuf::PhysicalPlanNode {
                 )?))
             }
             PhysicalPlanType::ParquetSink(parquet_sink) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(parquet_sink.input)?;
                 let mut props: Vec<(String, String)> = vec![];
                 for prop in &parquet_sink.prop {
                     props.push((prop.key.clone(), prop.value.clone()));
                 }
+                let bucket_spec = parquet_sink
+                    .bucket_spec
+                    .as_ref()
+                    .map(|bucket_spec| {
+                        let bucket_exprs = bucket_spec
+                            .bucket_expr
+                            .iter()
+                            .map(|expr| {
+                                bind(
+                                    try_parse_physical_expr(expr, &input.schema())?,
+                                    &input.schema(),
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let sort_exprs = bucket_spec
+                            .sort_expr
+                            .iter()
+                            .map(|expr| try_parse_physical_sort_expr(&input, expr))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let hash_function = match bucket_spec.hash_function() {
+                            protobuf::BucketHashFunction::Murmur3 => BucketHashFunction::Murmur3,
+                            protobuf::BucketHashFunction::HiveHash => BucketHashFunction::HiveHash,
+                        };
+                        Ok::<_, PlanSerDeError>(SinkBucketSpec {
+                            bucket_exprs,
+                            num_buckets: bucket_spec.num_buckets as usize,
+                            hash_function,
+                            sort_exprs,
+                        })
+                    })
+                    .transpose()?;
                 Ok(Arc::new(ParquetSinkExec::new(
-                    convert_box_required!(parquet_sink.input)?,
+                    input,
                     parquet_sink.fs_resource_id.clone(),
                     parquet_sink.path.clone(),
                     props,
+                    parquet_sink.dyn_part_col.clone(),
+                    bucket_spec,
+                    parquet_sink.partition_consumer_resource_id.clone(),
+                    parquet_sink.datetime_rebase_mode().into(),
+                    parquet_sink
+                        .key_value_metadata
+                        .iter()
+                        .map(|kv| (kv.key.clone(), kv.value.clone()))
+                        .collect(),
+                )))
+            }
+            PhysicalPlanType::TextSink(text_sink) => {
+                let input: Arc<dyn ExecutionPlan> = convert_box_required!(text_sink.input)?;
+                let single_char = |name: &str, value: &str| {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Ok(Some(c)),
+                        (None, _) => Ok(None),
+                        _ => Err(proto_error(format!(
+                            "TextSink: {name} must be a single character: {value}"
+                        ))),
+                    }
+                };
+                let format = match text_sink.format.as_ref() {
+                    Some(protobuf::text_sink_exec_node::Format::Csv(csv)) => {
+                        TextSinkFormat::Csv(CsvWriteOptions {
+                            delimiter: csv.delimiter.clone(),
+                            quote: single_char("quote", &csv.quote)?,
+                            escape: single_char("escape", &csv.escape)?.unwrap_or('\\'),
+                            quote_all: csv.quote_all,
+                            escape_quotes: csv.escape_quotes,
+                            header: csv.header,
+                            null_value: csv.null_value.clone(),
+                            empty_value: csv.empty_value.clone(),
+                            ignore_leading_white_space: csv.ignore_leading_white_space,
+                            ignore_trailing_white_space: csv.ignore_trailing_white_space,
+                        })
+                    }
+                    Some(protobuf::text_sink_exec_node::Format::Json(json)) => {
+                        TextSinkFormat::Json(JsonWriteOptions {
+                            ignore_null_fields: json.ignore_null_fields,
+                        })
+                    }
+                    None => return Err(proto_error("TextSink: missing output format")),
+                };
+                Ok(Arc::new(TextSinkExec::new(
+                    input,
+                    text_sink.fs_resource_id.clone(),
+                    text_sink.path.clone(),
+                    format,
+                    text_sink.line_sep.clone(),
+                    text_sink.date_format.clone(),
+                    text_sink.timestamp_format.clone(),
+                    text_sink.timezone.clone(),
                 )))
             }
+            PhysicalPlanType::Extension(extension) => {
+                let inputs: Vec<Arc<dyn ExecutionPlan>> = extension
+                    .inputs
+                    .iter()
+                    .map(|i| i.try_into())
+                    .collect::<Result<Vec<_>, _>>()?;
+                build_extension_plan(&extension.name, &extension.payload, inputs)
+            }
+        }
+    }
+}
+
+impl From<protobuf::ParquetRebaseMode> for RebaseMode {
+    fn from(mode: protobuf::ParquetRebaseMode) -> RebaseMode {
+        match mode {
+            protobuf::ParquetRebaseMode::Corrected => RebaseMode::Corrected,
+            protobuf::ParquetRebaseMode::Exception => RebaseMode::Exception,
+            protobuf::ParquetRebaseMode::Legacy => RebaseMode::Legacy,
         }
     }
 }
@@ -880,14 +1146,51 @@ impl From<&protobuf::ScalarFunction> for BuiltinScalarFunction {
     }
 }
 
+fn try_parse_physical_sort_expr(
+    input: &Arc<dyn ExecutionPlan>,
+    expr: &protobuf::PhysicalExprNode,
+) -> Result<PhysicalSortExpr, PlanSerDeError> {
+    match &expr.expr_type {
+        Some(ExprType::Sort(sort_expr)) => {
+            let expr = sort_expr
+                .expr
+                .as_ref()
+                .ok_or_else(|| proto_error(format!("Unexpected sort expr {:?}", sort_expr)))?;
+            Ok(PhysicalSortExpr {
+                expr: bind(
+                    try_parse_physical_expr(expr, &input.schema())?,
+                    &input.schema(),
+                )?,
+                options: SortOptions {
+                    descending: !sort_expr.asc,
+                    nulls_first: sort_expr.nulls_first,
+                },
+            })
+        }
+        _ => Err(PlanSerDeError::General(format!(
+            "Unexpected sort expr {:?}",
+            expr
+        ))),
+    }
+}
+
 fn try_parse_physical_expr(
     expr: &protobuf::PhysicalExprNode,
     input_schema: &SchemaRef,
+) -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
+    expr_cache::get_or_parse(expr, input_schema, || {
+        try_parse_physical_expr_uncached(expr, input_schema)
+    })
+}
+
+fn try_parse_physical_expr_uncached(
+    expr: &protobuf::PhysicalExprNode,
+    input_schema: &SchemaRef,
 ) -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
     let expr_type = expr
         .expr_type
         .as_ref()
-        .ok_or_else(|| proto_error("Unexpected empty physical expression"))?;
+        .ok_or_else(|| unsupported_error("Unknown physical expression"))?;
 
     let pexpr: Arc<dyn PhysicalExpr> = match expr_type {
         ExprType::Column(c) => {
@@ -929,15 +1232,32 @@ fn try_parse_physical_expr(
             &e.expr,
             input_schema,
         )?)),
-        ExprType::InList(e) => Arc::new(InListExpr::new(
-            try_parse_physical_expr_box_required(&e.expr, input_schema)?,
-            e.list
+        ExprType::InList(e) => {
+            let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
+            let list = e
+                .list
                 .iter()
                 .map(|x| try_parse_physical_expr(x, input_schema))
-                .collect::<Result<Vec<_>, _>>()?,
-            e.negated,
-            None,
-        )),
+                .collect::<Result<Vec<_>, _>>()?;
+            let literal_list = list
+                .iter()
+                .map(|x| {
+                    x.as_any()
+                        .downcast_ref::<Literal>()
+                        .map(|l| l.value().clone())
+                })
+                .collect::<Option<Vec<_>>>();
+
+            // complex-typed values are not supported in datafusion's InListExpr
+            match literal_list {
+                Some(literal_list)
+                    if ComplexInListExpr::is_supported_type(&expr.data_type(input_schema)?) =>
+                {
+                    Arc::new(ComplexInListExpr::try_new(expr, literal_list, e.negated)?)
+                }
+                _ => Arc::new(InListExpr::new(expr, list, e.negated, None)),
+            }
+        }
         ExprType::Case(e) => Arc::new(CaseExpr::try_new(
             e.expr
                 .as_ref()
@@ -969,7 +1289,7 @@ fn try_parse_physical_expr(
         }
         ExprType::ScalarFunction(e) => {
             let scalar_function = protobuf::ScalarFunction::from_i32(e.fun).ok_or_else(|| {
-                proto_error(format!("Received an unknown scalar function: {}", e.fun,))
+                unsupported_error(format!("Received an unknown scalar function: {}", e.fun,))
             })?;
 
             let args = e
@@ -982,7 +1302,11 @@ fn try_parse_physical_expr(
             let fun_expr = if scalar_function == protobuf::ScalarFunction::SparkExtFunctions {
                 datafusion_ext_functions::create_spark_ext_function(&e.name)?
             } else {
-                functions::create_physical_fun(&(&scalar_function).into(), &execution_props)?
+                let fun: BuiltinScalarFunction = (&scalar_function).into();
+                if fun.volatility() != Volatility::Immutable {
+                    expr_cache::mark_uncacheable();
+                }
+                functions::create_physical_fun(&fun, &execution_props)?
             };
 
             Arc::new(ScalarFunctionExpr::new(
@@ -1011,7 +1335,12 @@ fn try_parse_physical_expr(
         ExprType::GetIndexedFieldExpr(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
             let key = convert_required!(e.key)?;
-            Arc::new(GetIndexedFieldExpr::new(expr, key))
+            Arc::new(GetIndexedFieldExpr::new(
+                expr,
+                key,
+                e.allow_negative_index,
+                e.fail_on_error,
+            ))
         }
         ExprType::GetMapValueExpr(e) => {
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
@@ -1030,6 +1359,16 @@ fn try_parse_physical_expr(
             let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
             Arc::new(StringContainsExpr::new(expr, e.infix.clone()))
         }
+        ExprType::StringLikeMultiExpr(e) => {
+            let expr = try_parse_physical_expr_box_required(&e.expr, input_schema)?;
+            Arc::new(StringLikeMultiExpr::try_new(
+                expr,
+                e.patterns.clone(),
+                e.has_null_pattern,
+                e.is_all,
+                e.negated,
+            )?)
+        }
         ExprType::ScAndExpr(e) => {
             let l = try_parse_physical_expr_box_required(&e.left, input_schema)?;
             let r = try_parse_physical_expr_box_required(&e.right, input_schema)?;
@@ -1040,12 +1379,18 @@ fn try_parse_physical_expr(
             let r = try_parse_physical_expr_box_required(&e.right, input_schema)?;
             Arc::new(SCOrExpr::new(l, r))
         }
-        ExprType::LikeExpr(e) => Arc::new(LikeExpr::new(
-            e.negated,
-            e.case_insensitive,
-            try_parse_physical_expr_box_required(&e.expr, input_schema)?,
-            try_parse_physical_expr_box_required(&e.pattern, input_schema)?,
-        )),
+        ExprType::LikeExpr(e) => {
+            let mut pattern = try_parse_physical_expr_box_required(&e.pattern, input_schema)?;
+            if !e.escape_char.is_empty() && e.escape_char != "\\" {
+                pattern = rewrite_like_pattern_escape(&pattern, &e.escape_char)?;
+            }
+            Arc::new(LikeExpr::new(
+                e.negated,
+                e.case_insensitive,
+                try_parse_physical_expr_box_required(&e.expr, input_schema)?,
+                pattern,
+            ))
+        }
 
         ExprType::NamedStruct(e) => {
             let data_type = convert_required!(e.return_type)?;
@@ -1057,6 +1402,32 @@ fn try_parse_physical_expr(
                 data_type,
             )?)
         }
+        ExprType::RaiseErrorExpr(e) => Arc::new(RaiseErrorExpr::new(
+            try_parse_physical_expr_box_required(&e.message, input_schema)?,
+        )),
+        ExprType::AssertTrueExpr(e) => Arc::new(AssertTrueExpr::new(
+            try_parse_physical_expr_box_required(&e.condition, input_schema)?,
+            try_parse_physical_expr_box_required(&e.message, input_schema)?,
+        )),
+        ExprType::RandExpr(e) => Arc::new(RandExpr::new(e.seed, e.gaussian)),
+        ExprType::UuidExpr(e) => Arc::new(UuidExpr::new(e.seed)),
+        ExprType::MonotonicallyIncreasingIdExpr(_) => {
+            Arc::new(MonotonicallyIncreasingIdExpr::new())
+        }
+        ExprType::SparkPartitionIdExpr(_) => Arc::new(SparkPartitionIdExpr::new()),
+        ExprType::InputFileNameExpr(_) => Arc::new(InputFileNameExpr::new()),
+        ExprType::ExtensionExpr(e) => {
+            expr_cache::mark_uncacheable();
+            build_extension_expr(
+                &e.name,
+                &e.payload,
+                e.children
+                    .iter()
+                    .map(|x| try_parse_physical_expr(x, input_schema))
+                    .collect::<Result<Vec<_>, _>>()?,
+                input_schema,
+            )?
+        }
     };
 
     Ok(pexpr)
@@ -1084,6 +1455,61 @@ fn try_parse_physical_expr_box_required(
     }
 }
 
+// rewrites a literal like pattern with custom escape char to the default escape
+// char '\\', following spark's StringUtils.escapeLikeRegex()
+fn rewrite_like_pattern_escape(
+    pattern: &Arc<dyn PhysicalExpr>,
+    escape_char: &str,
+) -> Result<Arc<dyn PhysicalExpr>, PlanSerDeError> {
+    let escape_char = escape_char.chars().next().unwrap_or('\\');
+    let pattern_str = match pattern
+        .as_any()
+        .downcast_ref::<Literal>()
+        .map(|l| l.value())
+    {
+        Some(ScalarValue::Utf8(Some(pattern_str))) => pattern_str,
+        Some(ScalarValue::Utf8(None)) => return Ok(pattern.clone()),
+        _ => {
+            return Err(unsupported_error(
+                "like with custom escape char only supports literal patterns",
+            ));
+        }
+    };
+
+    let mut rewritten = String::with_capacity(pattern_str.len());
+    let push_literal = |rewritten: &mut String, c: char| {
+        if matches!(c, '\\' | '_' | '%') {
+            rewritten.push('\\');
+        }
+        rewritten.push(c);
+    };
+    let mut chars = pattern_str.chars();
+    while let Some(c) = chars.next() {
+        if c == escape_char {
+            match chars.next() {
+                Some(escaped) if escaped == '_' || escaped == '%' || escaped == escape_char => {
+                    push_literal(&mut rewritten, escaped);
+                }
+                Some(escaped) => {
+                    return Err(proto_error(format!(
+                        "the escape character is not allowed to precede '{escaped}'"
+                    )));
+                }
+                None => {
+                    return Err(proto_error(
+                        "it is not allowed to end with the escape character",
+                    ));
+                }
+            }
+        } else if c == '\\' {
+            push_literal(&mut rewritten, c);
+        } else {
+            rewritten.push(c);
+        }
+    }
+    Ok(Arc::new(Literal::new(ScalarValue::Utf8(Some(rewritten)))))
+}
+
 pub fn parse_protobuf_hash_partitioning(
     input: Arc<dyn ExecutionPlan>,
     partitioning: Option<&protobuf::PhysicalHashRepartition>,
@@ -1108,6 +1534,19 @@ pub fn parse_protobuf_hash_partitioning(
     }
 }
 
+/// returns None for DEFAULT_CODEC
+fn parse_ipc_compression_codec(codec: i32, compression_level: i32) -> Option<IpcCompressionCodec> {
+    match protobuf::IpcCompressionCodec::from_i32(codec).unwrap() {
+        protobuf::IpcCompressionCodec::DefaultCodec => None,
+        protobuf::IpcCompressionCodec::Uncompressed => Some(IpcCompressionCodec::Uncompressed),
+        protobuf::IpcCompressionCodec::Lz4 => Some(IpcCompressionCodec::Lz4),
+        protobuf::IpcCompressionCodec::Zstd => Some(match compression_level {
+            0 => IpcCompressionCodec::Zstd(IpcCompressionCodec::DEFAULT_ZSTD_LEVEL),
+            level => IpcCompressionCodec::Zstd(level),
+        }),
+    }
+}
+
 impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
     type Error = PlanSerDeError;
 
@@ -1116,7 +1555,7 @@ impl TryFrom<&protobuf::PartitionedFile> for PartitionedFile {
             object_meta: ObjectMeta {
                 location: Path::from(format!("/{}", BASE64_URL_SAFE_NO_PAD.encode(&val.path))),
                 size: val.size as usize,
-                last_modified: DateTime::default(),
+                last_modified: Utc.timestamp_nanos(val.last_modified_ns as i64),
                 e_tag: None,
             },
             partition_values: val
@@ -1235,3 +1674,55 @@ impl TryInto<FileScanConfig> for &protobuf::FileScanExecConf {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::from_proto::{resolve_column, rewrite_like_pattern_escape};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::common::ScalarValue;
+    use datafusion::physical_expr::expressions::Literal;
+    use datafusion::physical_expr::PhysicalExpr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_resolve_column() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("Name", DataType::Utf8, true),
+            Field::new("dup", DataType::Utf8, true),
+            Field::new("DUP", DataType::Utf8, true),
+        ]);
+        assert_eq!(resolve_column("Name", &schema, true).unwrap().index(), 1);
+        assert!(resolve_column("name", &schema, true).is_err());
+        let column = resolve_column("name", &schema, false).unwrap();
+        assert_eq!((column.name(), column.index()), ("Name", 1));
+
+        // ambiguous case-insensitive matches are not resolved
+        assert_eq!(resolve_column("DUP", &schema, false).unwrap().index(), 3);
+        assert!(resolve_column("Dup", &schema, false).is_err());
+        assert!(resolve_column("missing", &schema, false).is_err());
+    }
+
+    #[test]
+    fn test_rewrite_like_pattern_escape() {
+        let rewrite = |pattern: &str, escape_char: &str| {
+            let pattern: Arc<dyn PhysicalExpr> = Arc::new(Literal::new(ScalarValue::from(pattern)));
+            rewrite_like_pattern_escape(&pattern, escape_char).map(|rewritten| {
+                match rewritten
+                    .as_any()
+                    .downcast_ref::<Literal>()
+                    .unwrap()
+                    .value()
+                {
+                    ScalarValue::Utf8(Some(rewritten)) => rewritten.clone(),
+                    other => panic!("unexpected rewritten pattern: {other:?}"),
+                }
+            })
+        };
+        assert_eq!(rewrite("a/_b/%c%", "/").unwrap(), "a\\_b\\%c%");
+        assert_eq!(rewrite("a//b\\c", "/").unwrap(), "a/b\\\\c");
+        assert_eq!(rewrite("a##_%", "#").unwrap(), "a#_%");
+        assert!(rewrite("a/b", "/").is_err());
+        assert!(rewrite("ab/", "/").is_err());
+    }
+}
@@ -28,12 +28,38 @@ pub mod protobuf {
 }
 
 pub mod error;
+mod expr_cache;
+pub mod extension;
 pub mod from_proto;
+pub mod plan_optimizer;
+pub mod plan_render;
+pub mod to_proto;
+
+/// version of the plan protocol, increased when new plan/expr nodes are added
+/// to blaze.proto. plans from newer jvm sides may contain unknown nodes and
+/// are rejected as unsupported.
+pub const PLAN_PROTOCOL_VERSION: u32 = 7;
 
 pub(crate) fn proto_error<S: Into<String>>(message: S) -> PlanSerDeError {
     PlanSerDeError::General(message.into())
 }
 
+pub(crate) fn unsupported_error<S: Into<String>>(message: S) -> PlanSerDeError {
+    PlanSerDeError::Unsupported(message.into())
+}
+
+/// checks the plan protocol version of the jvm side, unversioned plans (0)
+/// are always accepted
+pub fn check_plan_version(plan_version: u32) -> Result<(), PlanSerDeError> {
+    if plan_version > PLAN_PROTOCOL_VERSION {
+        return Err(unsupported_error(format!(
+            "plan protocol version {} is newer than native engine's version {}",
+            plan_version, PLAN_PROTOCOL_VERSION,
+        )));
+    }
+    Ok(())
+}
+
 #[macro_export]
 macro_rules! convert_required {
     ($PB:expr) => {{
@@ -94,7 +120,41 @@ pub fn from_proto_binary_op(op: &str) -> Result<Operator, PlanSerDeError> {
         "RegexNotIMatch" => Ok(Operator::RegexNotIMatch),
         "RegexNotMatch" => Ok(Operator::RegexNotMatch),
         "StringConcat" => Ok(Operator::StringConcat),
-        other => Err(proto_error(format!(
+        other => Err(unsupported_error(format!(
+            "Unsupported binary operator '{:?}'",
+            other
+        ))),
+    }
+}
+
+pub fn to_proto_binary_op(op: &Operator) -> Result<&'static str, PlanSerDeError> {
+    match op {
+        Operator::And => Ok("And"),
+        Operator::Or => Ok("Or"),
+        Operator::Eq => Ok("Eq"),
+        Operator::NotEq => Ok("NotEq"),
+        Operator::LtEq => Ok("LtEq"),
+        Operator::Lt => Ok("Lt"),
+        Operator::Gt => Ok("Gt"),
+        Operator::GtEq => Ok("GtEq"),
+        Operator::Plus => Ok("Plus"),
+        Operator::Minus => Ok("Minus"),
+        Operator::Multiply => Ok("Multiply"),
+        Operator::Divide => Ok("Divide"),
+        Operator::Modulo => Ok("Modulo"),
+        Operator::IsDistinctFrom => Ok("IsDistinctFrom"),
+        Operator::IsNotDistinctFrom => Ok("IsNotDistinctFrom"),
+        Operator::BitwiseAnd => Ok("BitwiseAnd"),
+        Operator::BitwiseOr => Ok("BitwiseOr"),
+        Operator::BitwiseXor => Ok("BitwiseXor"),
+        Operator::BitwiseShiftLeft => Ok("BitwiseShiftLeft"),
+        Operator::BitwiseShiftRight => Ok("BitwiseShiftRight"),
+        Operator::RegexIMatch => Ok("RegexIMatch"),
+        Operator::RegexMatch => Ok("RegexMatch"),
+        Operator::RegexNotIMatch => Ok("RegexNotIMatch"),
+        Operator::RegexNotMatch => Ok("RegexNotMatch"),
+        Operator::StringConcat => Ok("StringConcat"),
+        other => Err(PlanSerDeError::NotImplemented(format!(
             "Unsupported binary operator '{:?}'",
             other
         ))),
@@ -135,6 +195,7 @@ impl From<protobuf::AggFunction> for AggFunction {
             protobuf::AggFunction::CollectSet => AggFunction::CollectSet,
             protobuf::AggFunction::First => AggFunction::First,
             protobuf::AggFunction::FirstIgnoresNull => AggFunction::FirstIgnoresNull,
+            protobuf::AggFunction::HistogramNumeric => AggFunction::HistogramNumeric,
         }
     }
 }
@@ -507,6 +568,7 @@ impl TryInto<datafusion::scalar::ScalarValue> for &protobuf::ScalarValue {
                     Arc::new(Field::new("items", scalar_type, true)),
                 )
             }
+            protobuf::scalar_value::Value::StructValue(v) => v.try_into()?,
             protobuf::scalar_value::Value::NullValue(v) => {
                 let datatype = v
                     .datatype
@@ -604,6 +666,7 @@ impl TryInto<datafusion::scalar::ScalarValue> for &protobuf::scalar_value::Value
                 ScalarValue::TimestampNanosecond(Some(*v), None)
             }
             protobuf::scalar_value::Value::ListValue(v) => v.try_into()?,
+            protobuf::scalar_value::Value::StructValue(v) => v.try_into()?,
             protobuf::scalar_value::Value::NullValue(v) => {
                 match v
                     .datatype
@@ -662,6 +725,30 @@ impl TryInto<ScalarValue> for &protobuf::ScalarListValue {
     }
 }
 
+impl TryInto<ScalarValue> for &protobuf::ScalarStructValue {
+    type Error = PlanSerDeError;
+    fn try_into(self) -> Result<ScalarValue, Self::Error> {
+        let fields: Vec<Field> = self
+            .fields
+            .iter()
+            .map(|field| field.try_into())
+            .collect::<Result<_, Self::Error>>()?;
+        let values: Vec<ScalarValue> = self
+            .values
+            .iter()
+            .map(|value| value.try_into())
+            .collect::<Result<_, Self::Error>>()?;
+        if fields.len() != values.len() {
+            return Err(proto_error(format!(
+                "struct value has {} fields but {} values",
+                fields.len(),
+                values.len()
+            )));
+        }
+        Ok(ScalarValue::Struct(Some(values), fields.into()))
+    }
+}
+
 impl TryInto<DataType> for &protobuf::ScalarListType {
     type Error = PlanSerDeError;
     fn try_into(self) -> Result<DataType, Self::Error> {
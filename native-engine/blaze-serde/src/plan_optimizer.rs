@@ -0,0 +1,669 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rewrites applied to deserialized plans before execution.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow::datatypes::Schema;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::PhysicalExprRef;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
+use datafusion_ext_exprs::input_file_name::InputFileNameExpr;
+use datafusion_ext_exprs::named_struct::NamedStructExpr;
+use datafusion_ext_plans::filter_exec::FilterExec;
+use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+use datafusion_ext_plans::parquet_exec::{ParquetExec, INPUT_FILE_NAME_COLUMN};
+use datafusion_ext_plans::project_exec::ProjectExec;
+use datafusion_ext_plans::rename_columns_exec::RenameColumnsExec;
+
+/// optimizes a plan converted from protobuf
+pub fn optimize_plan(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    // flattening first, which may turn projections into pure column references
+    let plan = flatten_struct_field_access(plan)?;
+    let plan = resolve_input_file_names(plan)?;
+    let plan = fuse_scan_projections(plan)?;
+    push_local_limits_into_scans(plan)
+}
+
+/// resolves field accesses of named_struct(...) in projections and filters to
+/// the field value expressions, without materializing the intermediate struct
+/// arrays. nested accesses like `named_struct(..., named_struct(...)).f1.f0`
+/// are flattened bottom-up. accesses of struct columns (like `col.a.b`) already
+/// reference child arrays directly and are kept.
+fn flatten_struct_field_access(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let new_children = children
+            .into_iter()
+            .map(flatten_struct_field_access)
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(new_children)?
+    };
+
+    if let Some(project) = plan.as_any().downcast_ref::<ProjectExec>() {
+        let input = project.children()[0].clone();
+        let input_schema = input.schema();
+        let mut flattened = false;
+        let exprs = project
+            .exprs()
+            .iter()
+            .map(|(expr, name)| {
+                let new_expr = flatten_struct_field_expr(expr, &input_schema)?;
+                flattened |= !Arc::ptr_eq(&new_expr, expr);
+                Ok((new_expr, name.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if flattened {
+            return Ok(Arc::new(ProjectExec::try_new(exprs, input)?));
+        }
+    }
+    if let Some(filter) = plan.as_any().downcast_ref::<FilterExec>() {
+        let input = filter.children()[0].clone();
+        let input_schema = input.schema();
+        let mut flattened = false;
+        let predicates = filter
+            .predicates()
+            .iter()
+            .map(|pred| {
+                let new_pred = flatten_struct_field_expr(pred, &input_schema)?;
+                flattened |= !Arc::ptr_eq(&new_pred, pred);
+                Ok(new_pred)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if flattened {
+            return Ok(Arc::new(FilterExec::try_new(predicates, input)?));
+        }
+    }
+    Ok(plan)
+}
+
+fn flatten_struct_field_expr(
+    expr: &PhysicalExprRef,
+    input_schema: &Schema,
+) -> Result<PhysicalExprRef> {
+    expr.clone().transform_up(&|expr: PhysicalExprRef| {
+        let Some(get_field) = expr.as_any().downcast_ref::<GetIndexedFieldExpr>() else {
+            return Ok(Transformed::No(expr));
+        };
+        let (Some(named_struct), Some(field_index)) = (
+            get_field.arg().as_any().downcast_ref::<NamedStructExpr>(),
+            get_field.struct_field_index(),
+        ) else {
+            return Ok(Transformed::No(expr));
+        };
+
+        // values are casted to the struct field types when evaluating, so only
+        // values already having the field types can be referenced directly
+        match named_struct.field_value(field_index) {
+            Some((value, field)) if &value.data_type(input_schema)? == field.data_type() => {
+                Ok(Transformed::Yes(value.clone()))
+            }
+            _ => Ok(Transformed::No(expr)),
+        }
+    })
+}
+
+/// resolves input_file_name() in projections and filters to the input file
+/// column of the parquet scan below. the column is added to the scan and
+/// passed through the row-preserving operators in between. the conversion
+/// fails if no scan is reachable (like above shuffles or joins), the jvm side
+/// falls back to spark for such plans before they are sent to native.
+fn resolve_input_file_names(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let new_children = children
+            .into_iter()
+            .map(resolve_input_file_names)
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(new_children)?
+    };
+
+    if let Some(project) = plan.as_any().downcast_ref::<ProjectExec>() {
+        if project
+            .exprs()
+            .iter()
+            .any(|(expr, _)| contains_input_file_name(expr))
+        {
+            let input = project.children()[0].clone();
+            let Some(input) = with_input_file_column(&input)? else {
+                return Err(unresolved_input_file_name_err());
+            };
+            let exprs = project
+                .exprs()
+                .iter()
+                .map(|(expr, name)| Ok((resolve_input_file_name(expr, &input)?, name.clone())))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Arc::new(ProjectExec::try_new(exprs, input)?));
+        }
+    }
+    if let Some(filter) = plan.as_any().downcast_ref::<FilterExec>() {
+        if filter.predicates().iter().any(contains_input_file_name) {
+            let input = filter.children()[0].clone();
+            let Some(input) = with_input_file_column(&input)? else {
+                return Err(unresolved_input_file_name_err());
+            };
+            let predicates = filter
+                .predicates()
+                .iter()
+                .map(|pred| resolve_input_file_name(pred, &input))
+                .collect::<Result<Vec<_>>>()?;
+            let filtered: Arc<dyn ExecutionPlan> =
+                Arc::new(FilterExec::try_new(predicates, input)?);
+
+            // removes the input file column from output
+            let exprs = plan
+                .schema()
+                .fields()
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let column: PhysicalExprRef = Arc::new(Column::new(field.name(), i));
+                    (column, field.name().clone())
+                })
+                .collect();
+            return Ok(Arc::new(ProjectExec::try_new(exprs, filtered)?));
+        }
+    }
+    Ok(plan)
+}
+
+/// returns the plan with an additional trailing input file column, or None if
+/// input files are unknown
+fn with_input_file_column(plan: &Arc<dyn ExecutionPlan>) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    if let Some(scan) = plan.as_any().downcast_ref::<ParquetExec>() {
+        return Ok(Some(Arc::new(scan.with_input_file_name_column()?)));
+    }
+    let plan_any = plan.as_any();
+    if !(plan_any.is::<ProjectExec>()
+        || plan_any.is::<FilterExec>()
+        || plan_any.is::<RenameColumnsExec>()
+        || plan_any.is::<LimitExec>())
+    {
+        return Ok(None);
+    }
+    let Some(input) = with_input_file_column(&plan.children()[0])? else {
+        return Ok(None);
+    };
+
+    if let Some(project) = plan_any.downcast_ref::<ProjectExec>() {
+        let mut exprs = project
+            .exprs()
+            .iter()
+            .map(|(expr, name)| Ok((resolve_input_file_name(expr, &input)?, name.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        exprs.push((
+            input_file_column(&input),
+            INPUT_FILE_NAME_COLUMN.to_string(),
+        ));
+        return Ok(Some(Arc::new(ProjectExec::try_new(exprs, input)?)));
+    }
+    if let Some(rename) = plan_any.downcast_ref::<RenameColumnsExec>() {
+        let mut names = rename
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<Vec<_>>();
+        names.push(INPUT_FILE_NAME_COLUMN.to_string());
+        return Ok(Some(Arc::new(
+            RenameColumnsExec::try_new_with_nested_renames(
+                input,
+                names,
+                rename.nested_renames().to_vec(),
+            )?,
+        )));
+    }
+    // filters and limits pass through all input columns
+    Ok(Some(plan.clone().with_new_children(vec![input])?))
+}
+
+fn unresolved_input_file_name_err() -> DataFusionError {
+    DataFusionError::Plan(
+        "input_file_name() is only supported above parquet scans through projects, \
+         filters, limits and renames"
+            .to_string(),
+    )
+}
+
+fn input_file_column(plan: &Arc<dyn ExecutionPlan>) -> PhysicalExprRef {
+    Arc::new(Column::new(
+        INPUT_FILE_NAME_COLUMN,
+        plan.schema().fields().len() - 1,
+    ))
+}
+
+fn contains_input_file_name(expr: &PhysicalExprRef) -> bool {
+    expr.as_any().is::<InputFileNameExpr>() || expr.children().iter().any(contains_input_file_name)
+}
+
+fn resolve_input_file_name(
+    expr: &PhysicalExprRef,
+    input: &Arc<dyn ExecutionPlan>,
+) -> Result<PhysicalExprRef> {
+    let column = input_file_column(input);
+    expr.clone().transform_up(&|expr: PhysicalExprRef| {
+        Ok(match expr.as_any().is::<InputFileNameExpr>() {
+            true => Transformed::Yes(column.clone()),
+            false => Transformed::No(expr),
+        })
+    })
+}
+
+/// folds projections of pure column references sitting directly on parquet
+/// scans into the scans' projections. renamed columns are kept with a
+/// (zero-copy) RenameColumnsExec.
+fn fuse_scan_projections(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let new_children = children
+            .into_iter()
+            .map(fuse_scan_projections)
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(new_children)?
+    };
+
+    if let Some(project) = plan.as_any().downcast_ref::<ProjectExec>() {
+        if let Some(fused) = try_fuse_scan_projection(project)? {
+            return Ok(fused);
+        }
+    }
+    Ok(plan)
+}
+
+fn try_fuse_scan_projection(project: &ProjectExec) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let input = project.children()[0].clone();
+    let Some(scan) = input.as_any().downcast_ref::<ParquetExec>() else {
+        return Ok(None);
+    };
+    if project.exprs().is_empty() {
+        return Ok(None);
+    }
+
+    // only distinct column references are fused
+    let mut column_indices = vec![];
+    for (expr, _) in project.exprs() {
+        match expr.as_any().downcast_ref::<Column>() {
+            Some(column) => column_indices.push(column.index()),
+            None => return Ok(None),
+        }
+    }
+    if column_indices.iter().collect::<HashSet<_>>().len() != column_indices.len() {
+        return Ok(None);
+    }
+
+    let scan_projection = scan.projection();
+    let fused_projection = column_indices
+        .iter()
+        .map(|&i| scan_projection[i])
+        .collect::<Vec<_>>();
+    let fused_scan: Arc<dyn ExecutionPlan> = Arc::new(scan.with_projection(fused_projection));
+
+    let fused_names = fused_scan
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect::<Vec<_>>();
+    let names = project
+        .exprs()
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    if fused_names == names {
+        return Ok(Some(fused_scan));
+    }
+    Ok(Some(Arc::new(RenameColumnsExec::try_new(
+        fused_scan, names,
+    )?)))
+}
+
+/// pushes local limits into parquet scans through row-preserving operators
+/// (projections and renames), so that scans stop opening files and decoding
+/// row groups once enough rows are read. the limits are kept because rows of
+/// different files are not limited as a whole when scanning in parallel.
+fn push_local_limits_into_scans(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let new_children = children
+            .into_iter()
+            .map(push_local_limits_into_scans)
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(new_children)?
+    };
+
+    if let Some(limit) = plan.as_any().downcast_ref::<LimitExec>() {
+        if limit.mode() == LimitMode::Local {
+            let input = limit.children()[0].clone();
+            if let Some(limited) = try_push_limit_into_scan(&input, limit.limit() as usize)? {
+                return Ok(Arc::new(LimitExec::new(
+                    limited,
+                    limit.limit(),
+                    LimitMode::Local,
+                )));
+            }
+        }
+    }
+    Ok(plan)
+}
+
+fn try_push_limit_into_scan(
+    plan: &Arc<dyn ExecutionPlan>,
+    limit: usize,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    if let Some(scan) = plan.as_any().downcast_ref::<ParquetExec>() {
+        return Ok(Some(Arc::new(scan.with_limit(limit))));
+    }
+    if plan.as_any().is::<ProjectExec>() || plan.as_any().is::<RenameColumnsExec>() {
+        let input = plan.children()[0].clone();
+        if let Some(limited) = try_push_limit_into_scan(&input, limit)? {
+            return Ok(Some(plan.clone().with_new_children(vec![limited])?));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plan_optimizer::optimize_plan;
+    use arrow::datatypes::{DataType, Field, Fields, Schema};
+    use datafusion::common::ScalarValue;
+    use datafusion::datasource::object_store::ObjectStoreUrl;
+    use datafusion::datasource::physical_plan::FileScanConfig;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{binary, lit, Column, NotExpr};
+    use datafusion::physical_expr::PhysicalExprRef;
+    use datafusion::physical_plan::empty::EmptyExec;
+    use datafusion::physical_plan::{ExecutionPlan, Statistics};
+    use datafusion_ext_commons::datetime_rebase::RebaseMode;
+    use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
+    use datafusion_ext_exprs::input_file_name::InputFileNameExpr;
+    use datafusion_ext_exprs::named_struct::NamedStructExpr;
+    use datafusion_ext_plans::filter_exec::FilterExec;
+    use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+    use datafusion_ext_plans::parquet_exec::{
+        ParquetExec, SchemaMatchMode, INPUT_FILE_NAME_COLUMN,
+    };
+    use datafusion_ext_plans::project_exec::ProjectExec;
+    use datafusion_ext_plans::rename_columns_exec::RenameColumnsExec;
+    use std::sync::Arc;
+
+    fn scan(projection: Option<Vec<usize>>) -> Arc<dyn ExecutionPlan> {
+        let file_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Boolean, true),
+        ]));
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema,
+            file_groups: vec![vec![]],
+            statistics: Statistics::default(),
+            projection,
+            limit: None,
+            table_partition_cols: vec![("p".to_string(), DataType::Utf8)],
+            output_ordering: vec![],
+            infinite_source: false,
+        };
+        Arc::new(ParquetExec::new(
+            base_config,
+            "fs".to_string(),
+            None,
+            SchemaMatchMode::Name,
+            RebaseMode::Corrected,
+            RebaseMode::Corrected,
+        ))
+    }
+
+    fn field_names(plan: &Arc<dyn ExecutionPlan>) -> Vec<String> {
+        plan.schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_fuse_scan_projections() {
+        // projection of columns (c, p) on scan of (a, c, p)
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![
+                    (Arc::new(Column::new("c", 1)), "c".to_string()),
+                    (Arc::new(Column::new("p", 2)), "p".to_string()),
+                ],
+                scan(Some(vec![0, 2, 3])),
+            )
+            .unwrap(),
+        );
+        let optimized = optimize_plan(project).unwrap();
+        let fused = optimized.as_any().downcast_ref::<ParquetExec>().unwrap();
+        assert_eq!(fused.projection(), vec![2, 3]);
+        assert_eq!(field_names(&optimized), vec!["c", "p"]);
+
+        // renamed columns
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![
+                    (Arc::new(Column::new("b", 1)), "x".to_string()),
+                    (Arc::new(Column::new("a", 0)), "y".to_string()),
+                ],
+                scan(None),
+            )
+            .unwrap(),
+        );
+        let optimized = optimize_plan(project).unwrap();
+        assert!(optimized.as_any().is::<RenameColumnsExec>());
+        assert_eq!(field_names(&optimized), vec!["x", "y"]);
+        let fused = optimized.children()[0].clone();
+        let fused = fused.as_any().downcast_ref::<ParquetExec>().unwrap();
+        assert_eq!(fused.projection(), vec![1, 0]);
+
+        // projections with non-column expressions are kept
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![(
+                    Arc::new(NotExpr::new(Arc::new(Column::new("c", 2)))),
+                    "not_c".to_string(),
+                )],
+                scan(None),
+            )
+            .unwrap(),
+        );
+        let optimized = optimize_plan(project).unwrap();
+        assert!(optimized.as_any().is::<ProjectExec>());
+    }
+
+    #[test]
+    fn test_flatten_struct_field_access() {
+        // named_struct(a, named_struct(b, c)).f1.f0 => b
+        let inner_type = DataType::Struct(Fields::from(vec![
+            Field::new("f0", DataType::Utf8, true),
+            Field::new("f1", DataType::Boolean, true),
+        ]));
+        let outer_type = DataType::Struct(Fields::from(vec![
+            Field::new("f0", DataType::Int32, true),
+            Field::new("f1", inner_type.clone(), false),
+        ]));
+        let inner = Arc::new(
+            NamedStructExpr::try_new(
+                vec![
+                    Arc::new(Column::new("b", 1)) as PhysicalExprRef,
+                    Arc::new(Column::new("c", 2)),
+                ],
+                inner_type,
+            )
+            .unwrap(),
+        );
+        let outer = Arc::new(
+            NamedStructExpr::try_new(
+                vec![Arc::new(Column::new("a", 0)), inner as PhysicalExprRef],
+                outer_type,
+            )
+            .unwrap(),
+        );
+        let get_f1 = Arc::new(GetIndexedFieldExpr::new(
+            outer,
+            ScalarValue::Int32(Some(1)),
+            false,
+            false,
+        ));
+        let get_f1_f0 = Arc::new(GetIndexedFieldExpr::new(
+            get_f1,
+            ScalarValue::Int32(Some(0)),
+            false,
+            false,
+        ));
+        let project: Arc<dyn ExecutionPlan> =
+            Arc::new(ProjectExec::try_new(vec![(get_f1_f0, "x".to_string())], scan(None)).unwrap());
+
+        // flattened to a column reference, which is then fused into the scan
+        let optimized = optimize_plan(project).unwrap();
+        assert!(optimized.as_any().is::<RenameColumnsExec>());
+        assert_eq!(field_names(&optimized), vec!["x"]);
+        let fused = optimized.children()[0].clone();
+        let fused = fused.as_any().downcast_ref::<ParquetExec>().unwrap();
+        assert_eq!(fused.projection(), vec![1]);
+
+        // values not matching field types are kept
+        let struct_type =
+            DataType::Struct(Fields::from(vec![Field::new("f0", DataType::Int64, true)]));
+        let named_struct = Arc::new(
+            NamedStructExpr::try_new(vec![Arc::new(Column::new("a", 0))], struct_type).unwrap(),
+        );
+        let get_f0 = Arc::new(GetIndexedFieldExpr::new(
+            named_struct,
+            ScalarValue::Int32(Some(0)),
+            false,
+            false,
+        ));
+        let project: Arc<dyn ExecutionPlan> =
+            Arc::new(ProjectExec::try_new(vec![(get_f0, "x".to_string())], scan(None)).unwrap());
+        let optimized = optimize_plan(project).unwrap();
+        assert!(optimized.as_any().is::<ProjectExec>());
+    }
+
+    #[test]
+    fn test_push_local_limits_into_scans() {
+        let scan_limit = |plan: &Arc<dyn ExecutionPlan>| {
+            let mut plan = plan.clone();
+            while !plan.children().is_empty() {
+                plan = plan.children()[0].clone();
+            }
+            let scan = plan.as_any().downcast_ref::<ParquetExec>().unwrap();
+            scan.limit()
+        };
+
+        // pushed through projections
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![(
+                    Arc::new(NotExpr::new(Arc::new(Column::new("c", 2)))),
+                    "not_c".to_string(),
+                )],
+                scan(None),
+            )
+            .unwrap(),
+        );
+        let limit: Arc<dyn ExecutionPlan> =
+            Arc::new(LimitExec::new(project.clone(), 10, LimitMode::Local));
+        let optimized = optimize_plan(limit).unwrap();
+        assert!(optimized.as_any().is::<LimitExec>());
+        assert_eq!(scan_limit(&optimized), Some(10));
+
+        // global limits are not pushed
+        let limit: Arc<dyn ExecutionPlan> =
+            Arc::new(LimitExec::new(project, 10, LimitMode::Global));
+        let optimized = optimize_plan(limit).unwrap();
+        assert_eq!(scan_limit(&optimized), None);
+
+        // not pushed through filters
+        let filter: Arc<dyn ExecutionPlan> =
+            Arc::new(FilterExec::try_new(vec![Arc::new(Column::new("c", 2))], scan(None)).unwrap());
+        let limit: Arc<dyn ExecutionPlan> = Arc::new(LimitExec::new(filter, 10, LimitMode::Local));
+        let optimized = optimize_plan(limit).unwrap();
+        assert_eq!(scan_limit(&optimized), None);
+    }
+
+    #[test]
+    fn test_resolve_input_file_names() {
+        // select b, input_file_name() from t where c
+        let filter: Arc<dyn ExecutionPlan> =
+            Arc::new(FilterExec::try_new(vec![Arc::new(Column::new("c", 2))], scan(None)).unwrap());
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![
+                    (Arc::new(Column::new("b", 1)), "b".to_string()),
+                    (Arc::new(InputFileNameExpr::new()), "f".to_string()),
+                ],
+                filter,
+            )
+            .unwrap(),
+        );
+        let optimized = optimize_plan(project).unwrap();
+        assert_eq!(field_names(&optimized), vec!["b", "f"]);
+        let project = optimized.as_any().downcast_ref::<ProjectExec>().unwrap();
+        let column = project.exprs()[1]
+            .0
+            .as_any()
+            .downcast_ref::<Column>()
+            .unwrap();
+        assert_eq!(column.index(), 4);
+        let filter = project.children()[0].clone();
+        assert!(filter.as_any().is::<FilterExec>());
+        assert_eq!(
+            field_names(&filter.children()[0]),
+            vec!["a", "b", "c", "p", INPUT_FILE_NAME_COLUMN]
+        );
+
+        // filter by input_file_name(), the input file column is projected out
+        let schema = scan(None).schema();
+        let predicate = binary(
+            Arc::new(InputFileNameExpr::new()),
+            Operator::Eq,
+            lit("x"),
+            &schema,
+        )
+        .unwrap();
+        let filter: Arc<dyn ExecutionPlan> =
+            Arc::new(FilterExec::try_new(vec![predicate], scan(None)).unwrap());
+        let optimized = optimize_plan(filter).unwrap();
+        assert_eq!(field_names(&optimized), vec!["a", "b", "c", "p"]);
+        let filter = optimized.children()[0].clone();
+        let filter = filter.as_any().downcast_ref::<FilterExec>().unwrap();
+        assert!(!format!("{}", filter.predicates()[0]).contains("InputFileName"));
+
+        // input files are unknown without scans
+        let project: Arc<dyn ExecutionPlan> = Arc::new(
+            ProjectExec::try_new(
+                vec![(Arc::new(InputFileNameExpr::new()), "f".to_string())],
+                Arc::new(EmptyExec::new(false, schema)),
+            )
+            .unwrap(),
+        );
+        assert!(optimize_plan(project).is_err());
+    }
+}
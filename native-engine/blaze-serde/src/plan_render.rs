@@ -0,0 +1,180 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders deserialized execution plans into JSON or graphviz DOT, so users
+//! can inspect exactly what the native engine will run.
+
+use crate::error::PlanSerDeError;
+use datafusion::physical_plan::{displayable, ExecutionPlan};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanRenderFormat {
+    Json,
+    Dot,
+}
+
+impl FromStr for PlanRenderFormat {
+    type Err = PlanSerDeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(PlanRenderFormat::Json),
+            "dot" | "graphviz" => Ok(PlanRenderFormat::Dot),
+            other => Err(PlanSerDeError::General(format!(
+                "unsupported plan render format: {}",
+                other
+            ))),
+        }
+    }
+}
+
+pub fn render_plan(plan: &Arc<dyn ExecutionPlan>, format: PlanRenderFormat) -> String {
+    match format {
+        PlanRenderFormat::Json => render_plan_json(plan),
+        PlanRenderFormat::Dot => render_plan_dot(plan),
+    }
+}
+
+/// renders plan tree into json, nodes are numbered in pre-order
+pub fn render_plan_json(plan: &Arc<dyn ExecutionPlan>) -> String {
+    fn to_json(plan: &Arc<dyn ExecutionPlan>, next_id: &mut usize) -> Value {
+        let id = *next_id;
+        *next_id += 1;
+
+        let schema = plan.schema();
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                json!({
+                    "name": field.name(),
+                    "data_type": field.data_type().to_string(),
+                    "nullable": field.is_nullable(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let ordering = plan.output_ordering().map(|ordering| {
+            ordering
+                .iter()
+                .map(|sort_expr| sort_expr.to_string())
+                .collect::<Vec<_>>()
+        });
+        let children = plan
+            .children()
+            .iter()
+            .map(|child| to_json(child, next_id))
+            .collect::<Vec<_>>();
+
+        json!({
+            "id": id,
+            "name": plan_name(plan),
+            "description": plan_description(plan),
+            "schema": fields,
+            "output_partitioning": format!("{:?}", plan.output_partitioning()),
+            "output_ordering": ordering,
+            "children": children,
+        })
+    }
+    let mut next_id = 0;
+    let json = to_json(plan, &mut next_id);
+    serde_json::to_string_pretty(&json).expect("error serializing plan to json")
+}
+
+/// renders plan tree into graphviz dot, edges point from parents to children
+pub fn render_plan_dot(plan: &Arc<dyn ExecutionPlan>) -> String {
+    fn write_node(plan: &Arc<dyn ExecutionPlan>, next_id: &mut usize, dot: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let label = format!(
+            "{}\npartitions={}",
+            plan_description(plan),
+            plan.output_partitioning().partition_count(),
+        );
+        writeln!(dot, "  node{} [label=\"{}\"];", id, escape_dot(&label)).unwrap();
+
+        for child in plan.children() {
+            let child_id = write_node(&child, next_id, dot);
+            writeln!(dot, "  node{} -> node{};", id, child_id).unwrap();
+        }
+        id
+    }
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph plan {{").unwrap();
+    writeln!(dot, "  node [shape=box];").unwrap();
+    write_node(plan, &mut 0, &mut dot);
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn plan_description(plan: &Arc<dyn ExecutionPlan>) -> String {
+    displayable(plan.as_ref())
+        .one_line()
+        .to_string()
+        .trim()
+        .to_string()
+}
+
+fn plan_name(plan: &Arc<dyn ExecutionPlan>) -> String {
+    let description = plan_description(plan);
+    description
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::plan_render::{render_plan, PlanRenderFormat};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::empty::EmptyExec;
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_render_plan() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(LimitExec::new(
+            Arc::new(EmptyExec::new(false, schema)),
+            10,
+            LimitMode::Global,
+        ));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_plan(&plan, PlanRenderFormat::Json)).unwrap();
+        assert_eq!(json["id"], 0);
+        assert_eq!(json["name"], "LimitExec");
+        assert_eq!(json["schema"][0]["name"], "a");
+        assert_eq!(json["children"][0]["id"], 1);
+        assert_eq!(json["children"][0]["name"], "EmptyExec");
+
+        let dot = render_plan(&plan, PlanRenderFormat::Dot);
+        assert!(dot.starts_with("digraph plan {"));
+        assert!(dot.contains("node0 [label=\"LimitExec(limit=10)\\npartitions=1\"];"));
+        assert!(dot.contains("node0 -> node1;"));
+    }
+}
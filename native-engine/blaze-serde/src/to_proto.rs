@@ -0,0 +1,805 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serde code to convert Rust data structures back to protocol buffers.
+//!
+//! only plans/exprs which can be fully described by their protobuf nodes are
+//! supported, others (like scans and joins) return a NotImplemented error.
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::physical_expr::expressions::{LikeExpr, SCAndExpr, SCOrExpr};
+use datafusion::physical_expr::ScalarFunctionExpr;
+use datafusion::physical_plan::expressions::{
+    BinaryExpr, CaseExpr, CastExpr, Column, InListExpr, IsNotNullExpr, IsNullExpr, Literal,
+    NegativeExpr, NotExpr, PhysicalSortExpr,
+};
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
+use datafusion::scalar::ScalarValue;
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use datafusion_ext_commons::streams::ipc_stream::IpcReadMode;
+use datafusion_ext_exprs::assert_true::AssertTrueExpr;
+use datafusion_ext_exprs::cast::TryCastExpr;
+use datafusion_ext_exprs::complex_in_list::ComplexInListExpr;
+use datafusion_ext_exprs::get_indexed_field::GetIndexedFieldExpr;
+use datafusion_ext_exprs::get_map_value::GetMapValueExpr;
+use datafusion_ext_exprs::input_file_name::InputFileNameExpr;
+use datafusion_ext_exprs::monotonically_increasing_id::MonotonicallyIncreasingIdExpr;
+use datafusion_ext_exprs::named_struct::NamedStructExpr;
+use datafusion_ext_exprs::raise_error::RaiseErrorExpr;
+use datafusion_ext_exprs::rand::RandExpr;
+use datafusion_ext_exprs::spark_partition_id::SparkPartitionIdExpr;
+use datafusion_ext_exprs::spark_scalar_subquery_wrapper::SparkScalarSubqueryWrapperExpr;
+use datafusion_ext_exprs::spark_udf_wrapper::SparkUDFWrapperExpr;
+use datafusion_ext_exprs::string_contains::StringContainsExpr;
+use datafusion_ext_exprs::string_ends_with::StringEndsWithExpr;
+use datafusion_ext_exprs::string_like_multi::StringLikeMultiExpr;
+use datafusion_ext_exprs::string_starts_with::StringStartsWithExpr;
+use datafusion_ext_exprs::uuid::UuidExpr;
+use datafusion_ext_plans::batch_validation_exec::BatchValidationExec;
+use datafusion_ext_plans::collect_stats_exec::{CollectStatsExec, CollectStatsMode};
+use datafusion_ext_plans::debug_exec::{DebugDumpFormat, DebugExec};
+use datafusion_ext_plans::empty_partitions_exec::EmptyPartitionsExec;
+use datafusion_ext_plans::expand_exec::ExpandExec;
+use datafusion_ext_plans::ffi_exporter_exec::FFIExporterExec;
+use datafusion_ext_plans::ffi_reader_exec::FFIReaderExec;
+use datafusion_ext_plans::filter_exec::FilterExec;
+use datafusion_ext_plans::ipc_reader_exec::IpcReaderExec;
+use datafusion_ext_plans::ipc_writer_exec::IpcWriterExec;
+use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+use datafusion_ext_plans::project_exec::ProjectExec;
+use datafusion_ext_plans::rename_columns_exec::RenameColumnsExec;
+use datafusion_ext_plans::sort_exec::SortExec;
+
+use crate::error::PlanSerDeError;
+use crate::protobuf::physical_expr_node::ExprType;
+use crate::protobuf::physical_plan_node::PhysicalPlanType;
+use crate::{protobuf, to_proto_binary_op};
+
+type Result<T> = std::result::Result<T, PlanSerDeError>;
+
+fn not_implemented<T>(desc: String) -> Result<T> {
+    Err(PlanSerDeError::NotImplemented(format!(
+        "to_proto: {}",
+        desc
+    )))
+}
+
+impl TryFrom<&Arc<dyn ExecutionPlan>> for protobuf::PhysicalPlanNode {
+    type Error = PlanSerDeError;
+
+    fn try_from(plan: &Arc<dyn ExecutionPlan>) -> Result<Self> {
+        let plan_any = plan.as_any();
+        let input = || -> Result<Option<Box<protobuf::PhysicalPlanNode>>> {
+            Ok(Some(Box::new((&plan.children()[0]).try_into()?)))
+        };
+
+        // batch validation exec is transparent, it is added when executing
+        if let Some(exec) = plan_any.downcast_ref::<BatchValidationExec>() {
+            return exec.input().try_into();
+        }
+
+        let plan_type = if let Some(exec) = plan_any.downcast_ref::<ProjectExec>() {
+            PhysicalPlanType::Projection(Box::new(protobuf::ProjectionExecNode {
+                input: input()?,
+                expr: exec
+                    .exprs()
+                    .iter()
+                    .map(|(expr, _)| expr.try_into())
+                    .collect::<Result<_>>()?,
+                expr_name: exec.exprs().iter().map(|(_, name)| name.clone()).collect(),
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<FilterExec>() {
+            PhysicalPlanType::Filter(Box::new(protobuf::FilterExecNode {
+                input: input()?,
+                expr: exec
+                    .predicates()
+                    .iter()
+                    .map(|expr| expr.try_into())
+                    .collect::<Result<_>>()?,
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<LimitExec>() {
+            PhysicalPlanType::Limit(Box::new(protobuf::LimitExecNode {
+                input: input()?,
+                limit: exec.limit(),
+                mode: match exec.mode() {
+                    LimitMode::Global => protobuf::LimitMode::Global,
+                    LimitMode::Local => protobuf::LimitMode::Local,
+                } as i32,
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<SortExec>() {
+            PhysicalPlanType::Sort(Box::new(protobuf::SortExecNode {
+                input: input()?,
+                expr: exec
+                    .exprs()
+                    .iter()
+                    .map(|expr| expr.try_into())
+                    .collect::<Result<_>>()?,
+                fetch_limit: exec.fetch().map(|fetch| fetch as u64),
+            }))
+        } else if plan_any.downcast_ref::<UnionExec>().is_some() {
+            PhysicalPlanType::Union(protobuf::UnionExecNode {
+                children: plan
+                    .children()
+                    .iter()
+                    .map(|child| child.try_into())
+                    .collect::<Result<_>>()?,
+            })
+        } else if plan_any.downcast_ref::<EmptyPartitionsExec>().is_some() {
+            PhysicalPlanType::EmptyPartitions(protobuf::EmptyPartitionsExecNode {
+                schema: Some(plan.schema().as_ref().try_into()?),
+                num_partitions: plan.output_partitioning().partition_count() as u32,
+            })
+        } else if let Some(exec) = plan_any.downcast_ref::<RenameColumnsExec>() {
+            PhysicalPlanType::RenameColumns(Box::new(protobuf::RenameColumnsExecNode {
+                input: input()?,
+                renamed_column_names: plan
+                    .schema()
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().clone())
+                    .collect(),
+                nested_renames: exec
+                    .nested_renames()
+                    .iter()
+                    .map(|nested_rename| protobuf::NestedFieldRename {
+                        column_index: nested_rename.column_index as u32,
+                        field_path: nested_rename.field_path.clone(),
+                        new_name: nested_rename.new_name.clone(),
+                    })
+                    .collect(),
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<DebugExec>() {
+            PhysicalPlanType::Debug(Box::new(protobuf::DebugExecNode {
+                input: input()?,
+                debug_id: exec.debug_id().to_string(),
+                dump_format: match exec.dump().map(|dump| dump.format) {
+                    None => protobuf::DebugDumpFormat::NoDump,
+                    Some(DebugDumpFormat::Ipc) => protobuf::DebugDumpFormat::DumpIpc,
+                    Some(DebugDumpFormat::Parquet) => protobuf::DebugDumpFormat::DumpParquet,
+                } as i32,
+                dump_dir: exec.dump().map(|dump| dump.dir.clone()).unwrap_or_default(),
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<ExpandExec>() {
+            PhysicalPlanType::Expand(Box::new(protobuf::ExpandExecNode {
+                input: input()?,
+                schema: Some(plan.schema().as_ref().try_into()?),
+                projections: exec
+                    .projections()
+                    .iter()
+                    .map(|projection| {
+                        Ok(protobuf::ExpandProjection {
+                            expr: projection
+                                .iter()
+                                .map(|expr| expr.try_into())
+                                .collect::<Result<_>>()?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<IpcReaderExec>() {
+            PhysicalPlanType::IpcReader(protobuf::IpcReaderExecNode {
+                num_partitions: exec.num_partitions as u32,
+                schema: Some(exec.schema.as_ref().try_into()?),
+                mode: match exec.mode {
+                    IpcReadMode::ChannelUncompressed => protobuf::IpcReadMode::ChannelUncompressed,
+                    IpcReadMode::Channel => protobuf::IpcReadMode::Channel,
+                    IpcReadMode::ChannelAndFileSegment => {
+                        protobuf::IpcReadMode::ChannelAndFileSegment
+                    }
+                } as i32,
+                ipc_provider_resource_id: exec.ipc_provider_resource_id.clone(),
+                codec: match exec.codec {
+                    Some(codec) => ipc_compression_codec(codec).0,
+                    None => protobuf::IpcCompressionCodec::DefaultCodec,
+                } as i32,
+                checksum: exec.checksum,
+            })
+        } else if let Some(exec) = plan_any.downcast_ref::<IpcWriterExec>() {
+            let (codec, compression_level) = ipc_compression_codec(exec.codec());
+            PhysicalPlanType::IpcWriter(Box::new(protobuf::IpcWriterExecNode {
+                input: input()?,
+                ipc_consumer_resource_id: exec.ipc_consumer_resource_id().to_string(),
+                codec: codec as i32,
+                compression_level,
+                target_chunk_size: exec.target_chunk_size() as u64,
+                max_inflight_chunks: exec.max_inflight_chunks() as u32,
+                checksum: exec.checksum(),
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<FFIExporterExec>() {
+            PhysicalPlanType::FfiExporter(Box::new(protobuf::FfiExporterExecNode {
+                input: input()?,
+                exporter_resource_id: exec.exporter_resource_id().to_string(),
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<CollectStatsExec>() {
+            PhysicalPlanType::CollectStats(Box::new(protobuf::CollectStatsExecNode {
+                input: input()?,
+                mode: match exec.mode() {
+                    CollectStatsMode::Partial => protobuf::CollectStatsMode::Partial,
+                    CollectStatsMode::Final => protobuf::CollectStatsMode::Final,
+                } as i32,
+            }))
+        } else if let Some(exec) = plan_any.downcast_ref::<FFIReaderExec>() {
+            PhysicalPlanType::FfiReader(protobuf::FfiReaderExecNode {
+                num_partitions: plan.output_partitioning().partition_count() as u32,
+                schema: Some(plan.schema().as_ref().try_into()?),
+                export_iter_provider_resource_id: exec
+                    .export_iter_provider_resource_id()
+                    .to_string(),
+                arrow_c_stream: exec.arrow_c_stream(),
+            })
+        } else {
+            return not_implemented(format!("unsupported execution plan: {:?}", plan));
+        };
+
+        Ok(protobuf::PhysicalPlanNode {
+            physical_plan_type: Some(plan_type),
+        })
+    }
+}
+
+impl TryFrom<&PhysicalSortExpr> for protobuf::PhysicalExprNode {
+    type Error = PlanSerDeError;
+
+    fn try_from(sort_expr: &PhysicalSortExpr) -> Result<Self> {
+        Ok(protobuf::PhysicalExprNode {
+            expr_type: Some(ExprType::Sort(Box::new(protobuf::PhysicalSortExprNode {
+                expr: Some(Box::new((&sort_expr.expr).try_into()?)),
+                asc: !sort_expr.options.descending,
+                nulls_first: sort_expr.options.nulls_first,
+            }))),
+        })
+    }
+}
+
+impl TryFrom<&Arc<dyn PhysicalExpr>> for protobuf::PhysicalExprNode {
+    type Error = PlanSerDeError;
+
+    fn try_from(expr: &Arc<dyn PhysicalExpr>) -> Result<Self> {
+        let expr_any = expr.as_any();
+        let boxed = |expr: &Arc<dyn PhysicalExpr>| -> Result<Option<Box<Self>>> {
+            Ok(Some(Box::new(expr.try_into()?)))
+        };
+        let repeated = |exprs: &[Arc<dyn PhysicalExpr>]| -> Result<Vec<Self>> {
+            exprs.iter().map(|expr| expr.try_into()).collect()
+        };
+
+        let expr_type = if let Some(e) = expr_any.downcast_ref::<Column>() {
+            if e.name() == "__bound_reference__" {
+                ExprType::BoundReference(protobuf::BoundReference {
+                    index: e.index() as u64,
+                    data_type: None,
+                    nullable: true,
+                })
+            } else {
+                ExprType::Column(protobuf::PhysicalColumn {
+                    name: e.name().to_string(),
+                    index: e.index() as u32,
+                })
+            }
+        } else if let Some(e) = expr_any.downcast_ref::<Literal>() {
+            ExprType::Literal(e.value().try_into()?)
+        } else if let Some(e) = expr_any.downcast_ref::<BinaryExpr>() {
+            ExprType::BinaryExpr(Box::new(protobuf::PhysicalBinaryExprNode {
+                l: boxed(e.left())?,
+                r: boxed(e.right())?,
+                op: to_proto_binary_op(e.op())?.to_string(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<IsNullExpr>() {
+            ExprType::IsNullExpr(Box::new(protobuf::PhysicalIsNull {
+                expr: boxed(e.arg())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<IsNotNullExpr>() {
+            ExprType::IsNotNullExpr(Box::new(protobuf::PhysicalIsNotNull {
+                expr: boxed(e.arg())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<NotExpr>() {
+            ExprType::NotExpr(Box::new(protobuf::PhysicalNot {
+                expr: boxed(e.arg())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<NegativeExpr>() {
+            ExprType::Negative(Box::new(protobuf::PhysicalNegativeNode {
+                expr: boxed(e.arg())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<InListExpr>() {
+            ExprType::InList(Box::new(protobuf::PhysicalInListNode {
+                expr: boxed(e.expr())?,
+                list: repeated(e.list())?,
+                negated: e.negated(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<ComplexInListExpr>() {
+            ExprType::InList(Box::new(protobuf::PhysicalInListNode {
+                expr: boxed(e.expr())?,
+                list: e
+                    .list()
+                    .iter()
+                    .map(|value| {
+                        Ok(protobuf::PhysicalExprNode {
+                            expr_type: Some(ExprType::Literal(value.try_into()?)),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                negated: e.negated(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<CaseExpr>() {
+            ExprType::Case(Box::new(protobuf::PhysicalCaseNode {
+                expr: e.expr().map(boxed).transpose()?.flatten(),
+                when_then_expr: e
+                    .when_then_expr()
+                    .iter()
+                    .map(|(when_expr, then_expr)| {
+                        Ok(protobuf::PhysicalWhenThen {
+                            when_expr: Some(when_expr.try_into()?),
+                            then_expr: Some(then_expr.try_into()?),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                else_expr: e.else_expr().map(boxed).transpose()?.flatten(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<CastExpr>() {
+            ExprType::Cast(Box::new(protobuf::PhysicalCastNode {
+                expr: boxed(e.expr())?,
+                arrow_type: Some(e.cast_type().try_into()?),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<TryCastExpr>() {
+            ExprType::TryCast(Box::new(protobuf::PhysicalTryCastNode {
+                expr: boxed(&e.expr)?,
+                arrow_type: Some((&e.cast_type).try_into()?),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<LikeExpr>() {
+            ExprType::LikeExpr(Box::new(protobuf::PhysicalLikeExprNode {
+                negated: e.negated(),
+                case_insensitive: e.case_insensitive(),
+                expr: boxed(e.expr())?,
+                pattern: boxed(e.pattern())?,
+                escape_char: String::new(),
+            }))
+        } else if expr_any.downcast_ref::<SCAndExpr>().is_some() {
+            let children = expr.children();
+            ExprType::ScAndExpr(Box::new(protobuf::PhysicalScAndExprNode {
+                left: boxed(&children[0])?,
+                right: boxed(&children[1])?,
+            }))
+        } else if expr_any.downcast_ref::<SCOrExpr>().is_some() {
+            let children = expr.children();
+            ExprType::ScOrExpr(Box::new(protobuf::PhysicalScOrExprNode {
+                left: boxed(&children[0])?,
+                right: boxed(&children[1])?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<ScalarFunctionExpr>() {
+            let fun = scalar_function(e.name());
+            ExprType::ScalarFunction(protobuf::PhysicalScalarFunctionNode {
+                name: e.name().to_string(),
+                fun: fun as i32,
+                args: repeated(e.args())?,
+                return_type: Some(e.return_type().try_into()?),
+            })
+        } else if let Some(e) = expr_any.downcast_ref::<SparkUDFWrapperExpr>() {
+            ExprType::SparkUdfWrapperExpr(protobuf::PhysicalSparkUdfWrapperExprNode {
+                serialized: e.serialized.clone(),
+                return_type: Some((&e.return_type).try_into()?),
+                return_nullable: e.return_nullable,
+                params: repeated(&e.params)?,
+            })
+        } else if let Some(e) = expr_any.downcast_ref::<SparkScalarSubqueryWrapperExpr>() {
+            ExprType::SparkScalarSubqueryWrapperExpr(
+                protobuf::PhysicalSparkScalarSubqueryWrapperExprNode {
+                    serialized: e.serialized.clone(),
+                    return_type: Some((&e.return_type).try_into()?),
+                    return_nullable: e.return_nullable,
+                },
+            )
+        } else if let Some(e) = expr_any.downcast_ref::<GetIndexedFieldExpr>() {
+            ExprType::GetIndexedFieldExpr(Box::new(protobuf::PhysicalGetIndexedFieldExprNode {
+                expr: boxed(e.arg())?,
+                key: Some(e.key().try_into()?),
+                allow_negative_index: e.allow_negative_index(),
+                fail_on_error: e.fail_on_error(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<GetMapValueExpr>() {
+            ExprType::GetMapValueExpr(Box::new(protobuf::PhysicalGetMapValueExprNode {
+                expr: boxed(e.arg())?,
+                key: Some(e.key().try_into()?),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<NamedStructExpr>() {
+            ExprType::NamedStruct(protobuf::PhysicalNamedStructExprNode {
+                values: repeated(e.values())?,
+                return_type: Some(e.return_type().try_into()?),
+            })
+        } else if let Some(e) = expr_any.downcast_ref::<RaiseErrorExpr>() {
+            ExprType::RaiseErrorExpr(Box::new(protobuf::PhysicalRaiseErrorExprNode {
+                message: boxed(e.message())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<AssertTrueExpr>() {
+            ExprType::AssertTrueExpr(Box::new(protobuf::PhysicalAssertTrueExprNode {
+                condition: boxed(e.condition())?,
+                message: boxed(e.message())?,
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<RandExpr>() {
+            ExprType::RandExpr(protobuf::PhysicalRandExprNode {
+                seed: e.seed(),
+                gaussian: e.gaussian(),
+            })
+        } else if let Some(e) = expr_any.downcast_ref::<UuidExpr>() {
+            ExprType::UuidExpr(protobuf::PhysicalUuidExprNode { seed: e.seed() })
+        } else if expr_any
+            .downcast_ref::<MonotonicallyIncreasingIdExpr>()
+            .is_some()
+        {
+            ExprType::MonotonicallyIncreasingIdExpr(
+                protobuf::PhysicalMonotonicallyIncreasingIdExprNode {},
+            )
+        } else if expr_any.downcast_ref::<SparkPartitionIdExpr>().is_some() {
+            ExprType::SparkPartitionIdExpr(protobuf::PhysicalSparkPartitionIdExprNode {})
+        } else if expr_any.downcast_ref::<InputFileNameExpr>().is_some() {
+            ExprType::InputFileNameExpr(protobuf::PhysicalInputFileNameExprNode {})
+        } else if let Some(e) = expr_any.downcast_ref::<StringStartsWithExpr>() {
+            ExprType::StringStartsWithExpr(Box::new(protobuf::StringStartsWithExprNode {
+                expr: boxed(e.expr())?,
+                prefix: e.prefix().to_string(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<StringEndsWithExpr>() {
+            ExprType::StringEndsWithExpr(Box::new(protobuf::StringEndsWithExprNode {
+                expr: boxed(e.expr())?,
+                suffix: e.suffix().to_string(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<StringContainsExpr>() {
+            ExprType::StringContainsExpr(Box::new(protobuf::StringContainsExprNode {
+                expr: boxed(e.expr())?,
+                infix: e.infix().to_string(),
+            }))
+        } else if let Some(e) = expr_any.downcast_ref::<StringLikeMultiExpr>() {
+            ExprType::StringLikeMultiExpr(Box::new(protobuf::StringLikeMultiExprNode {
+                expr: boxed(e.expr())?,
+                patterns: e.patterns().to_vec(),
+                has_null_pattern: e.has_null_pattern(),
+                is_all: e.is_all(),
+                negated: e.negated(),
+            }))
+        } else {
+            return not_implemented(format!("unsupported physical expr: {:?}", expr));
+        };
+
+        Ok(protobuf::PhysicalExprNode {
+            expr_type: Some(expr_type),
+        })
+    }
+}
+
+impl TryFrom<&Schema> for protobuf::Schema {
+    type Error = PlanSerDeError;
+
+    fn try_from(schema: &Schema) -> Result<Self> {
+        Ok(protobuf::Schema {
+            columns: schema
+                .fields()
+                .iter()
+                .map(|field| field.as_ref().try_into())
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<&Field> for protobuf::Field {
+    type Error = PlanSerDeError;
+
+    fn try_from(field: &Field) -> Result<Self> {
+        Ok(protobuf::Field {
+            name: field.name().clone(),
+            arrow_type: Some(Box::new(field.data_type().try_into()?)),
+            nullable: field.is_nullable(),
+            children: vec![],
+        })
+    }
+}
+
+impl TryFrom<&DataType> for protobuf::ArrowType {
+    type Error = PlanSerDeError;
+
+    fn try_from(data_type: &DataType) -> Result<Self> {
+        use protobuf::arrow_type::ArrowTypeEnum;
+        let empty = protobuf::EmptyMessage {};
+        let list = |field: &Field| -> Result<Box<protobuf::List>> {
+            Ok(Box::new(protobuf::List {
+                field_type: Some(Box::new(field.try_into()?)),
+            }))
+        };
+
+        let arrow_type_enum = match data_type {
+            DataType::Null => ArrowTypeEnum::None(empty),
+            DataType::Boolean => ArrowTypeEnum::Bool(empty),
+            DataType::UInt8 => ArrowTypeEnum::Uint8(empty),
+            DataType::Int8 => ArrowTypeEnum::Int8(empty),
+            DataType::UInt16 => ArrowTypeEnum::Uint16(empty),
+            DataType::Int16 => ArrowTypeEnum::Int16(empty),
+            DataType::UInt32 => ArrowTypeEnum::Uint32(empty),
+            DataType::Int32 => ArrowTypeEnum::Int32(empty),
+            DataType::UInt64 => ArrowTypeEnum::Uint64(empty),
+            DataType::Int64 => ArrowTypeEnum::Int64(empty),
+            DataType::Float16 => ArrowTypeEnum::Float16(empty),
+            DataType::Float32 => ArrowTypeEnum::Float32(empty),
+            DataType::Float64 => ArrowTypeEnum::Float64(empty),
+            DataType::Utf8 => ArrowTypeEnum::Utf8(empty),
+            DataType::LargeUtf8 => ArrowTypeEnum::LargeUtf8(empty),
+            DataType::Binary => ArrowTypeEnum::Binary(empty),
+            DataType::FixedSizeBinary(size) => ArrowTypeEnum::FixedSizeBinary(*size),
+            DataType::LargeBinary => ArrowTypeEnum::LargeBinary(empty),
+            DataType::Date32 => ArrowTypeEnum::Date32(empty),
+            DataType::Date64 => ArrowTypeEnum::Date64(empty),
+            DataType::Duration(time_unit) => {
+                ArrowTypeEnum::Duration(protobuf::TimeUnit::from_arrow_time_unit(time_unit) as i32)
+            }
+            DataType::Timestamp(time_unit, timezone) => {
+                ArrowTypeEnum::Timestamp(protobuf::Timestamp {
+                    time_unit: protobuf::TimeUnit::from_arrow_time_unit(time_unit) as i32,
+                    timezone: timezone.as_deref().unwrap_or_default().to_string(),
+                })
+            }
+            DataType::Time32(time_unit) => {
+                ArrowTypeEnum::Time32(protobuf::TimeUnit::from_arrow_time_unit(time_unit) as i32)
+            }
+            DataType::Time64(time_unit) => {
+                ArrowTypeEnum::Time64(protobuf::TimeUnit::from_arrow_time_unit(time_unit) as i32)
+            }
+            DataType::Interval(interval_unit) => ArrowTypeEnum::Interval(
+                protobuf::IntervalUnit::from_arrow_interval_unit(interval_unit) as i32,
+            ),
+            DataType::Decimal128(precision, scale) => ArrowTypeEnum::Decimal(protobuf::Decimal {
+                whole: *precision as u64,
+                fractional: *scale as i64,
+            }),
+            DataType::List(field) => ArrowTypeEnum::List(list(field)?),
+            DataType::LargeList(field) => ArrowTypeEnum::LargeList(list(field)?),
+            DataType::FixedSizeList(field, list_size) => {
+                ArrowTypeEnum::FixedSizeList(Box::new(protobuf::FixedSizeList {
+                    field_type: Some(Box::new(field.as_ref().try_into()?)),
+                    list_size: *list_size,
+                }))
+            }
+            DataType::Struct(fields) => ArrowTypeEnum::Struct(protobuf::Struct {
+                sub_field_types: fields
+                    .iter()
+                    .map(|field| field.as_ref().try_into())
+                    .collect::<Result<_>>()?,
+            }),
+            DataType::Map(field, _sorted) => match field.data_type() {
+                DataType::Struct(kv_fields) if kv_fields.len() == 2 => {
+                    ArrowTypeEnum::Map(Box::new(protobuf::Map {
+                        key_type: Some(Box::new(kv_fields[0].as_ref().try_into()?)),
+                        value_type: Some(Box::new(kv_fields[1].as_ref().try_into()?)),
+                    }))
+                }
+                other => return not_implemented(format!("unsupported map entries: {}", other)),
+            },
+            DataType::Dictionary(key_type, value_type) => {
+                ArrowTypeEnum::Dictionary(Box::new(protobuf::Dictionary {
+                    key: Some(Box::new(key_type.as_ref().try_into()?)),
+                    value: Some(Box::new(value_type.as_ref().try_into()?)),
+                }))
+            }
+            other => return not_implemented(format!("unsupported data type: {}", other)),
+        };
+        Ok(protobuf::ArrowType {
+            arrow_type_enum: Some(arrow_type_enum),
+        })
+    }
+}
+
+impl TryFrom<&ScalarValue> for protobuf::ScalarValue {
+    type Error = PlanSerDeError;
+
+    fn try_from(scalar: &ScalarValue) -> Result<Self> {
+        use protobuf::scalar_value::Value;
+
+        // typed nulls are encoded as null values of their scalar types
+        if scalar.is_null() {
+            return Ok(protobuf::ScalarValue {
+                value: Some(Value::NullValue(scalar_type(&scalar.get_datatype())?)),
+            });
+        }
+
+        let value = match scalar {
+            ScalarValue::Boolean(Some(v)) => Value::BoolValue(*v),
+            ScalarValue::Utf8(Some(v)) => Value::Utf8Value(v.clone()),
+            ScalarValue::LargeUtf8(Some(v)) => Value::LargeUtf8Value(v.clone()),
+            ScalarValue::Int8(Some(v)) => Value::Int8Value(*v as i32),
+            ScalarValue::Int16(Some(v)) => Value::Int16Value(*v as i32),
+            ScalarValue::Int32(Some(v)) => Value::Int32Value(*v),
+            ScalarValue::Int64(Some(v)) => Value::Int64Value(*v),
+            ScalarValue::UInt8(Some(v)) => Value::Uint8Value(*v as u32),
+            ScalarValue::UInt16(Some(v)) => Value::Uint16Value(*v as u32),
+            ScalarValue::UInt32(Some(v)) => Value::Uint32Value(*v),
+            ScalarValue::UInt64(Some(v)) => Value::Uint64Value(*v),
+            ScalarValue::Float32(Some(v)) => Value::Float32Value(*v),
+            ScalarValue::Float64(Some(v)) => Value::Float64Value(*v),
+            ScalarValue::Date32(Some(v)) => Value::Date32Value(*v),
+            ScalarValue::TimestampSecond(Some(v), None) => Value::TimestampSecondValue(*v),
+            ScalarValue::TimestampMillisecond(Some(v), None) => {
+                Value::TimestampMillisecondValue(*v)
+            }
+            ScalarValue::TimestampMicrosecond(Some(v), None) => {
+                Value::TimestampMicrosecondValue(*v)
+            }
+            ScalarValue::TimestampNanosecond(Some(v), None) => Value::TimestampNanosecondValue(*v),
+            ScalarValue::Decimal128(Some(v), precision, scale) => {
+                // protobuf only carries i64 unscaled values
+                let long_value = i64::try_from(*v).or_else(|_| {
+                    not_implemented(format!("decimal value exceeds i64: {}", scalar))
+                })?;
+                Value::DecimalValue(protobuf::ScalarDecimalValue {
+                    decimal: Some(protobuf::Decimal {
+                        whole: *precision as u64,
+                        fractional: *scale as i64,
+                    }),
+                    long_value,
+                })
+            }
+            ScalarValue::List(Some(values), field) => Value::ListValue(protobuf::ScalarListValue {
+                datatype: Some(scalar_type(field.data_type())?),
+                values: values
+                    .iter()
+                    .map(|value| value.try_into())
+                    .collect::<Result<_>>()?,
+            }),
+            ScalarValue::Struct(Some(values), fields) => {
+                Value::StructValue(protobuf::ScalarStructValue {
+                    fields: fields
+                        .iter()
+                        .map(|field| field.as_ref().try_into())
+                        .collect::<Result<_>>()?,
+                    values: values
+                        .iter()
+                        .map(|value| value.try_into())
+                        .collect::<Result<_>>()?,
+                })
+            }
+            other => return not_implemented(format!("unsupported scalar value: {:?}", other)),
+        };
+        Ok(protobuf::ScalarValue { value: Some(value) })
+    }
+}
+
+/// builtin functions are named after their protobuf enum names (like `Sqrt`)
+/// or in snake case (like `starts_with`), other names are spark ext functions
+fn scalar_function(name: &str) -> protobuf::ScalarFunction {
+    let camel_case_name = name
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    protobuf::ScalarFunction::from_str_name(name)
+        .or_else(|| protobuf::ScalarFunction::from_str_name(&camel_case_name))
+        .unwrap_or(protobuf::ScalarFunction::SparkExtFunctions)
+}
+
+/// returns protobuf codec and compression level
+fn ipc_compression_codec(codec: IpcCompressionCodec) -> (protobuf::IpcCompressionCodec, i32) {
+    match codec {
+        IpcCompressionCodec::Uncompressed => (protobuf::IpcCompressionCodec::Uncompressed, 0),
+        IpcCompressionCodec::Lz4 => (protobuf::IpcCompressionCodec::Lz4, 0),
+        IpcCompressionCodec::Zstd(level) => (protobuf::IpcCompressionCodec::Zstd, level),
+    }
+}
+
+fn scalar_type(data_type: &DataType) -> Result<protobuf::ScalarType> {
+    use arrow::datatypes::{IntervalUnit, TimeUnit};
+    use protobuf::scalar_type::Datatype;
+    use protobuf::PrimitiveScalarType;
+
+    let primitive = match data_type {
+        DataType::Boolean => PrimitiveScalarType::Bool,
+        DataType::UInt8 => PrimitiveScalarType::Uint8,
+        DataType::Int8 => PrimitiveScalarType::Int8,
+        DataType::UInt16 => PrimitiveScalarType::Uint16,
+        DataType::Int16 => PrimitiveScalarType::Int16,
+        DataType::UInt32 => PrimitiveScalarType::Uint32,
+        DataType::Int32 => PrimitiveScalarType::Int32,
+        DataType::UInt64 => PrimitiveScalarType::Uint64,
+        DataType::Int64 => PrimitiveScalarType::Int64,
+        DataType::Float32 => PrimitiveScalarType::Float32,
+        DataType::Float64 => PrimitiveScalarType::Float64,
+        DataType::Utf8 => PrimitiveScalarType::Utf8,
+        DataType::LargeUtf8 => PrimitiveScalarType::LargeUtf8,
+        DataType::Date32 => PrimitiveScalarType::Date32,
+        DataType::Date64 => PrimitiveScalarType::Date64,
+        DataType::Null => PrimitiveScalarType::Null,
+        DataType::Decimal128(..) => PrimitiveScalarType::Decimal128,
+        DataType::Timestamp(TimeUnit::Second, None) => PrimitiveScalarType::TimestampSecond,
+        DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            PrimitiveScalarType::TimestampMillisecond
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            PrimitiveScalarType::TimestampMicrosecond
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, None) => PrimitiveScalarType::TimestampNanosecond,
+        DataType::Interval(IntervalUnit::YearMonth) => PrimitiveScalarType::IntervalYearmonth,
+        DataType::Interval(IntervalUnit::DayTime) => PrimitiveScalarType::IntervalDaytime,
+        DataType::List(field) => {
+            return Ok(protobuf::ScalarType {
+                datatype: Some(Datatype::List(Box::new(protobuf::ScalarListType {
+                    element_type: Some(Box::new(scalar_type(field.data_type())?)),
+                }))),
+            });
+        }
+        other => return not_implemented(format!("unsupported scalar type: {}", other)),
+    };
+    Ok(protobuf::ScalarType {
+        datatype: Some(Datatype::Scalar(primitive as i32)),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::protobuf;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_plan::expressions::{binary, col, lit, PhysicalSortExpr};
+    use datafusion::physical_plan::{displayable, ExecutionPlan};
+    use datafusion_ext_plans::empty_partitions_exec::EmptyPartitionsExec;
+    use datafusion_ext_plans::filter_exec::FilterExec;
+    use datafusion_ext_plans::limit_exec::{LimitExec, LimitMode};
+    use datafusion_ext_plans::project_exec::ProjectExec;
+    use datafusion_ext_plans::sort_exec::SortExec;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_plan_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(EmptyPartitionsExec::new(schema.clone(), 3));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(
+            vec![binary(col("a", &schema)?, Operator::Gt, lit(1i32), &schema)?],
+            plan,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(ProjectExec::try_new(
+            vec![
+                (col("b", &schema)?, "b".to_string()),
+                (
+                    binary(col("a", &schema)?, Operator::Plus, lit(1i32), &schema)?,
+                    "a_plus_1".to_string(),
+                ),
+            ],
+            plan,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(SortExec::new(
+            plan.clone(),
+            vec![PhysicalSortExpr {
+                expr: col("a_plus_1", &plan.schema())?,
+                options: Default::default(),
+            }],
+            Some(10),
+        ));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(LimitExec::new(plan, 10, LimitMode::Local));
+
+        let plan_node = protobuf::PhysicalPlanNode::try_from(&plan)?;
+        let roundtrip_plan: Arc<dyn ExecutionPlan> = (&plan_node).try_into()?;
+        assert_eq!(
+            displayable(roundtrip_plan.as_ref())
+                .indent(true)
+                .to_string(),
+            displayable(plan.as_ref()).indent(true).to_string(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// proptest strategies generating random schemas, batches and expression
+// protos, for property-based testing of serde and plan conversion.
+
+use arrow::array::*;
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::*;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use blaze_serde::protobuf::physical_expr_node::ExprType;
+use blaze_serde::protobuf::scalar_value::Value;
+use blaze_serde::protobuf::{
+    ArrowType, PhysicalBinaryExprNode, PhysicalCastNode, PhysicalColumn, PhysicalExprNode,
+    PhysicalInListNode, PhysicalIsNotNull, PhysicalIsNull, PhysicalNegativeNode, PhysicalNot,
+    ScalarValue,
+};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+use proptest::sample::select;
+use std::sync::Arc;
+
+const MAX_NESTING_DEPTH: u32 = 3;
+
+/// random data types supported by batch serde, including nested
+/// list/struct/map types
+pub fn data_type_strategy() -> BoxedStrategy<DataType> {
+    let primitive = select(vec![
+        DataType::Null,
+        DataType::Boolean,
+        DataType::Int8,
+        DataType::Int16,
+        DataType::Int32,
+        DataType::Int64,
+        DataType::UInt8,
+        DataType::UInt16,
+        DataType::UInt32,
+        DataType::UInt64,
+        DataType::Float32,
+        DataType::Float64,
+        DataType::Utf8,
+        DataType::Binary,
+        DataType::Date32,
+        DataType::Date64,
+        DataType::Timestamp(TimeUnit::Microsecond, None),
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+    ]);
+    let decimal = (1u8..=38, 0i8..=10).prop_map(|(p, s)| DataType::Decimal128(p, s.min(p as i8)));
+    let leaf = prop_oneof![4 => primitive, 1 => decimal];
+
+    leaf.prop_recursive(MAX_NESTING_DEPTH, 16, 4, |inner| {
+        prop_oneof![
+            inner
+                .clone()
+                .prop_map(|dt| DataType::List(Arc::new(Field::new("item", dt, true)))),
+            vec(inner.clone(), 1..4).prop_map(|dts| DataType::Struct(nested_fields(dts))),
+            inner.prop_map(|value_type| {
+                let entries = Fields::from(vec![
+                    Field::new("key", DataType::Int32, false),
+                    Field::new("value", value_type, true),
+                ]);
+                let entries_field = Field::new("entries", DataType::Struct(entries), false);
+                DataType::Map(Arc::new(entries_field), false)
+            }),
+        ]
+    })
+    .boxed()
+}
+
+/// random nullable arrays of the data type
+pub fn array_strategy(data_type: DataType, len: usize) -> BoxedStrategy<ArrayRef> {
+    macro_rules! primitive {
+        ($arrow_type:ty) => {{
+            vec(
+                option::of(any::<<$arrow_type as ArrowPrimitiveType>::Native>()),
+                len,
+            )
+            .prop_map(|values| Arc::new(PrimitiveArray::<$arrow_type>::from(values)) as ArrayRef)
+            .boxed()
+        }};
+    }
+
+    match data_type {
+        DataType::Null => Just(new_null_array(&DataType::Null, len)).boxed(),
+        DataType::Boolean => vec(option::of(any::<bool>()), len)
+            .prop_map(|values| Arc::new(BooleanArray::from(values)) as ArrayRef)
+            .boxed(),
+        DataType::Int8 => primitive!(Int8Type),
+        DataType::Int16 => primitive!(Int16Type),
+        DataType::Int32 => primitive!(Int32Type),
+        DataType::Int64 => primitive!(Int64Type),
+        DataType::UInt8 => primitive!(UInt8Type),
+        DataType::UInt16 => primitive!(UInt16Type),
+        DataType::UInt32 => primitive!(UInt32Type),
+        DataType::UInt64 => primitive!(UInt64Type),
+        DataType::Float32 => primitive!(Float32Type),
+        DataType::Float64 => primitive!(Float64Type),
+        DataType::Date32 => primitive!(Date32Type),
+        DataType::Date64 => primitive!(Date64Type),
+        DataType::Timestamp(TimeUnit::Microsecond, None) => primitive!(TimestampMicrosecondType),
+        DataType::Decimal128(precision, scale) => {
+            let max = 10i128.pow(precision as u32) - 1;
+            vec(option::of(-max..=max), len)
+                .prop_map(move |values| {
+                    let array = Decimal128Array::from(values)
+                        .with_precision_and_scale(precision, scale)
+                        .expect("invalid decimal type");
+                    Arc::new(array) as ArrayRef
+                })
+                .boxed()
+        }
+        DataType::Utf8 => vec(option::of("\\PC{0,8}"), len)
+            .prop_map(|values| Arc::new(StringArray::from(values)) as ArrayRef)
+            .boxed(),
+        DataType::Binary => vec(option::of(vec(any::<u8>(), 0..8)), len)
+            .prop_map(|values| Arc::new(BinaryArray::from_iter(values)) as ArrayRef)
+            .boxed(),
+        DataType::Dictionary(key_type, value_type)
+            if *key_type == DataType::Int32 && *value_type == DataType::Utf8 =>
+        {
+            vec("\\PC{0,4}", 1..4)
+                .prop_flat_map(move |values| {
+                    let num_values = values.len() as i32;
+                    vec(option::of(0..num_values), len).prop_map(move |keys| {
+                        let values = Arc::new(StringArray::from(values.clone()));
+                        let dict = DictionaryArray::<Int32Type>::try_new(keys.into(), values)
+                            .expect("invalid dictionary");
+                        Arc::new(dict) as ArrayRef
+                    })
+                })
+                .boxed()
+        }
+        DataType::List(field) => vec(option::of(0usize..4), len)
+            .prop_flat_map(move |lengths| {
+                let field = field.clone();
+                let num_values = lengths.iter().flatten().sum();
+                array_strategy(field.data_type().clone(), num_values).prop_map(move |values| {
+                    let (offsets, nulls) = offsets_and_nulls(&lengths);
+                    Arc::new(ListArray::new(field.clone(), offsets, values, Some(nulls)))
+                        as ArrayRef
+                })
+            })
+            .boxed(),
+        DataType::Struct(fields) => {
+            let children = fields
+                .iter()
+                .map(|field| array_strategy(field.data_type().clone(), len))
+                .collect::<Vec<_>>();
+            (children, vec(any::<bool>(), len))
+                .prop_map(move |(children, valids)| {
+                    let nulls = NullBuffer::from(valids);
+                    Arc::new(StructArray::new(fields.clone(), children, Some(nulls))) as ArrayRef
+                })
+                .boxed()
+        }
+        DataType::Map(entries_field, ordered) => vec(option::of(0usize..4), len)
+            .prop_flat_map(move |lengths| {
+                let entries_field = entries_field.clone();
+                let entries_fields = match entries_field.data_type() {
+                    DataType::Struct(fields) => fields.clone(),
+                    other => unreachable!("invalid map entries type: {other}"),
+                };
+                let num_entries = lengths.iter().flatten().sum();
+                (
+                    vec(any::<i32>(), num_entries),
+                    array_strategy(entries_fields[1].data_type().clone(), num_entries),
+                )
+                    .prop_map(move |(keys, values)| {
+                        let keys = Arc::new(Int32Array::from(keys)) as ArrayRef;
+                        let entries =
+                            StructArray::new(entries_fields.clone(), vec![keys, values], None);
+                        let (offsets, nulls) = offsets_and_nulls(&lengths);
+                        let map = MapArray::new(
+                            entries_field.clone(),
+                            offsets,
+                            entries,
+                            Some(nulls),
+                            ordered,
+                        );
+                        Arc::new(map) as ArrayRef
+                    })
+            })
+            .boxed(),
+        // types not generated by data_type_strategy(), values are rejected so
+        // that proptest skips the case and reports the reason
+        other => Just(())
+            .prop_filter_map(format!("unsupported data type: {other}"), |_| {
+                None::<ArrayRef>
+            })
+            .boxed(),
+    }
+}
+
+/// random batches of 1-4 columns (named c0, c1, ...) and 0-15 rows
+pub fn batch_strategy() -> BoxedStrategy<RecordBatch> {
+    (vec(data_type_strategy(), 1..5), 0usize..16)
+        .prop_flat_map(|(data_types, num_rows)| {
+            let schema = Arc::new(Schema::new(nested_fields(data_types.clone())));
+            let columns = data_types
+                .into_iter()
+                .map(|dt| array_strategy(dt, num_rows))
+                .collect::<Vec<_>>();
+            columns.prop_map(move |columns| {
+                let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+                RecordBatch::try_new_with_options(schema.clone(), columns, &options)
+                    .expect("invalid batch")
+            })
+        })
+        .boxed()
+}
+
+/// random expression trees over columns c0..c{num_columns-1}. generated trees
+/// are not necessarily valid: columns may be missing, types may mismatch,
+/// operators may be unknown and required fields may be absent.
+pub fn expr_strategy(num_columns: usize) -> BoxedStrategy<PhysicalExprNode> {
+    let column = (0..num_columns as u32 + 2).prop_map(|index| PhysicalColumn {
+        name: format!("c{index}"),
+        index,
+    });
+    let leaf = prop_oneof![
+        4 => column.prop_map(|col| expr_node(ExprType::Column(col))),
+        4 => scalar_value_strategy().prop_map(|value| expr_node(ExprType::Literal(value))),
+        1 => Just(PhysicalExprNode { expr_type: None }),
+    ];
+
+    leaf.prop_recursive(4, 32, 4, |inner| {
+        let boxed = |expr: PhysicalExprNode| Some(Box::new(expr));
+        let op = select(vec![
+            "And",
+            "Or",
+            "Eq",
+            "NotEq",
+            "Lt",
+            "LtEq",
+            "Gt",
+            "GtEq",
+            "Plus",
+            "Minus",
+            "Multiply",
+            "Divide",
+            "Modulo",
+            "BitwiseAnd",
+            "StringConcat",
+            "Unknown",
+        ]);
+        let arrow_type = option::of(data_type_strategy())
+            .prop_map(|dt| dt.and_then(|dt| ArrowType::try_from(&dt).ok()));
+        prop_oneof![
+            (inner.clone(), inner.clone(), op).prop_map(move |(l, r, op)| {
+                expr_node(ExprType::BinaryExpr(Box::new(PhysicalBinaryExprNode {
+                    l: boxed(l),
+                    r: boxed(r),
+                    op: op.to_string(),
+                })))
+            }),
+            inner.clone().prop_map(move |e| {
+                expr_node(ExprType::IsNullExpr(Box::new(PhysicalIsNull {
+                    expr: boxed(e),
+                })))
+            }),
+            inner.clone().prop_map(move |e| {
+                expr_node(ExprType::IsNotNullExpr(Box::new(PhysicalIsNotNull {
+                    expr: boxed(e),
+                })))
+            }),
+            inner.clone().prop_map(move |e| {
+                expr_node(ExprType::NotExpr(Box::new(PhysicalNot { expr: boxed(e) })))
+            }),
+            inner.clone().prop_map(move |e| {
+                expr_node(ExprType::Negative(Box::new(PhysicalNegativeNode {
+                    expr: boxed(e),
+                })))
+            }),
+            (inner.clone(), arrow_type).prop_map(move |(e, arrow_type)| {
+                expr_node(ExprType::Cast(Box::new(PhysicalCastNode {
+                    expr: boxed(e),
+                    arrow_type,
+                })))
+            }),
+            (inner.clone(), vec(inner, 0..3), any::<bool>()).prop_map(move |(e, list, negated)| {
+                expr_node(ExprType::InList(Box::new(PhysicalInListNode {
+                    expr: boxed(e),
+                    list,
+                    negated,
+                })))
+            }),
+        ]
+    })
+    .boxed()
+}
+
+/// random literal values, including empty values
+pub fn scalar_value_strategy() -> BoxedStrategy<ScalarValue> {
+    let value = prop_oneof![
+        any::<bool>().prop_map(Value::BoolValue),
+        "\\PC{0,8}".prop_map(Value::Utf8Value),
+        any::<i32>().prop_map(Value::Int8Value),
+        any::<i32>().prop_map(Value::Int32Value),
+        any::<i64>().prop_map(Value::Int64Value),
+        any::<u32>().prop_map(Value::Uint8Value),
+        any::<u64>().prop_map(Value::Uint64Value),
+        any::<f64>().prop_map(Value::Float64Value),
+        any::<i32>().prop_map(Value::Date32Value),
+        any::<i64>().prop_map(Value::TimestampMicrosecondValue),
+    ];
+    option::of(value)
+        .prop_map(|value| ScalarValue { value })
+        .boxed()
+}
+
+fn expr_node(expr_type: ExprType) -> PhysicalExprNode {
+    PhysicalExprNode {
+        expr_type: Some(expr_type),
+    }
+}
+
+fn nested_fields(data_types: Vec<DataType>) -> Fields {
+    data_types
+        .into_iter()
+        .enumerate()
+        .map(|(i, dt)| Field::new(format!("c{i}"), dt, true))
+        .collect()
+}
+
+fn offsets_and_nulls(lengths: &[Option<usize>]) -> (OffsetBuffer<i32>, NullBuffer) {
+    let offsets = OffsetBuffer::from_lengths(lengths.iter().map(|len| len.unwrap_or(0)));
+    let nulls = NullBuffer::from_iter(lengths.iter().map(|len| len.is_some()));
+    (offsets, nulls)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::generators::{batch_strategy, data_type_strategy, expr_strategy};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use blaze_serde::protobuf;
+    use blaze_serde::protobuf::physical_plan_node::PhysicalPlanType;
+    use blaze_serde::protobuf::{
+        EmptyPartitionsExecNode, FilterExecNode, PhysicalPlanNode, ProjectionExecNode,
+    };
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion_ext_commons::io::batch_serde::{read_batch, write_batch};
+    use datafusion_ext_commons::io::{name_batch, IpcCompressionCodec};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use prost::Message;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    fn codec_strategy() -> impl Strategy<Value = IpcCompressionCodec> {
+        prop_oneof![
+            Just(IpcCompressionCodec::Uncompressed),
+            Just(IpcCompressionCodec::Lz4),
+            Just(IpcCompressionCodec::Zstd(1)),
+        ]
+    }
+
+    fn empty_input(data_types: Vec<DataType>) -> PhysicalPlanNode {
+        let fields = data_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, dt)| Field::new(format!("c{i}"), dt, true))
+            .collect::<Vec<_>>();
+        PhysicalPlanNode {
+            physical_plan_type: Some(PhysicalPlanType::EmptyPartitions(EmptyPartitionsExecNode {
+                schema: protobuf::Schema::try_from(&Schema::new(fields)).ok(),
+                num_partitions: 1,
+            })),
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_batch_serde_roundtrip(batch in batch_strategy(), codec in codec_strategy()) {
+            let mut buf = vec![];
+            write_batch(&batch, &mut buf, codec, None).unwrap();
+            let decoded = read_batch(&mut Cursor::new(buf), codec).unwrap();
+            prop_assert_eq!(name_batch(decoded, &batch.schema()).unwrap(), batch.clone());
+
+            // sliced batches have non-zero offsets
+            if batch.num_rows() > 1 {
+                let sliced = batch.slice(1, batch.num_rows() - 1);
+                let mut buf = vec![];
+                write_batch(&sliced, &mut buf, codec, None).unwrap();
+                let decoded = read_batch(&mut Cursor::new(buf), codec).unwrap();
+                prop_assert_eq!(name_batch(decoded, &sliced.schema()).unwrap(), sliced);
+            }
+        }
+
+        #[test]
+        fn test_from_proto_exprs_no_panic(
+            data_types in vec(data_type_strategy(), 3),
+            exprs in vec(expr_strategy(3), 1..4),
+        ) {
+            let input = empty_input(data_types);
+            let projection = PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::Projection(Box::new(
+                    ProjectionExecNode {
+                        input: Some(Box::new(input.clone())),
+                        expr_name: (0..exprs.len()).map(|i| format!("e{i}")).collect(),
+                        expr: exprs.clone(),
+                    },
+                ))),
+            };
+            let filter = PhysicalPlanNode {
+                physical_plan_type: Some(PhysicalPlanType::Filter(Box::new(FilterExecNode {
+                    input: Some(Box::new(input)),
+                    expr: exprs,
+                }))),
+            };
+
+            // malformed plans must be rejected with errors instead of panics
+            let _ = TryInto::<Arc<dyn ExecutionPlan>>::try_into(&projection);
+            let _ = TryInto::<Arc<dyn ExecutionPlan>>::try_into(&filter);
+        }
+
+        #[test]
+        fn test_from_proto_bytes_no_panic(bytes in vec(any::<u8>(), 0..256)) {
+            if let Ok(plan) = PhysicalPlanNode::decode(bytes.as_slice()) {
+                let _ = TryInto::<Arc<dyn ExecutionPlan>>::try_into(&plan);
+            }
+        }
+    }
+}
@@ -0,0 +1,535 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// golden-file plan execution tests. each case is a directory containing:
+//  * task.pb: encoded TaskDefinition, executed natively for its partition.
+//  * inputs/{resource_id}.arrow: arrow ipc files feeding IpcReader/FFIReader
+//    nodes of the plan with the same resource id, replacing the jvm side.
+//    inputs/{resource_id}.json (line-delimited json rows) can be used instead,
+//    which is read with the output schema of the reader node.
+//  * expected.arrow or expected.json (line-delimited json rows): expected
+//    output of the task.
+//  * tolerance.json (optional): comparison rules, like
+//    `{"ignore_order": true, "float_abs_tolerance": 1e-9, "float_rel_tolerance": 1e-6}`.
+// with BLAZE_GOLDEN_BLESS=1, actual outputs are written into expected.arrow
+// instead of being compared. checked-in cases are under blaze-tests/golden.
+
+use arrow::array::*;
+use arrow::compute::{cast, concat_batches, take};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use blaze_serde::check_plan_version;
+use blaze_serde::error::PlanSerDeError;
+use blaze_serde::plan_optimizer::optimize_plan;
+use blaze_serde::protobuf::TaskDefinition;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::{common, ExecutionPlan};
+use datafusion::prelude::SessionContext;
+use datafusion_ext_plans::common::memory_manager::MemManager;
+use datafusion_ext_plans::ffi_reader_exec::FFIReaderExec;
+use datafusion_ext_plans::ipc_reader_exec::IpcReaderExec;
+use prost::Message;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const TASK_FILE: &str = "task.pb";
+const INPUTS_DIR: &str = "inputs";
+const EXPECTED_ARROW_FILE: &str = "expected.arrow";
+const EXPECTED_JSON_FILE: &str = "expected.json";
+const TOLERANCE_FILE: &str = "tolerance.json";
+
+/// rules of comparing actual outputs with expected outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tolerance {
+    /// compares rows as multisets instead of sequences
+    pub ignore_order: bool,
+    pub float_abs_tolerance: f64,
+    pub float_rel_tolerance: f64,
+}
+
+impl Tolerance {
+    pub fn try_from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|err| DataFusionError::Execution(format!("invalid tolerance: {err}")))?;
+        let mut tolerance = Tolerance::default();
+        if let Some(ignore_order) = value.get("ignore_order") {
+            tolerance.ignore_order = ignore_order.as_bool().unwrap_or(false);
+        }
+        if let Some(float_abs_tolerance) = value.get("float_abs_tolerance") {
+            tolerance.float_abs_tolerance = float_abs_tolerance.as_f64().unwrap_or(0.0);
+        }
+        if let Some(float_rel_tolerance) = value.get("float_rel_tolerance") {
+            tolerance.float_rel_tolerance = float_rel_tolerance.as_f64().unwrap_or(0.0);
+        }
+        Ok(tolerance)
+    }
+
+    fn floats_equal(&self, a: f64, b: f64) -> bool {
+        if a == b || a.is_nan() && b.is_nan() {
+            return true;
+        }
+        let diff = (a - b).abs();
+        diff <= self.float_abs_tolerance || diff <= self.float_rel_tolerance * a.abs().max(b.abs())
+    }
+}
+
+/// a golden-file test case loaded from its directory
+pub struct GoldenCase {
+    dir: PathBuf,
+    task: TaskDefinition,
+    inputs: HashMap<String, PathBuf>,
+    tolerance: Tolerance,
+}
+
+impl GoldenCase {
+    pub fn try_load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let task = TaskDefinition::decode(std::fs::read(dir.join(TASK_FILE))?.as_slice())
+            .map_err(|err| DataFusionError::Execution(format!("cannot decode task: {err}")))?;
+
+        let mut inputs = HashMap::new();
+        let inputs_dir = dir.join(INPUTS_DIR);
+        if inputs_dir.is_dir() {
+            for entry in std::fs::read_dir(inputs_dir)? {
+                let path = entry?.path();
+                if !matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("arrow" | "json")
+                ) {
+                    continue;
+                }
+                let resource_id = path.file_stem().unwrap().to_string_lossy().to_string();
+                inputs.insert(resource_id, path);
+            }
+        }
+
+        let tolerance = match std::fs::read_to_string(dir.join(TOLERANCE_FILE)) {
+            Ok(json) => Tolerance::try_from_json(&json)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Tolerance::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            dir,
+            task,
+            inputs,
+            tolerance,
+        })
+    }
+
+    /// writes a case (without expected outputs) into the directory, expected
+    /// outputs can then be written by running the case in bless mode
+    pub fn write(
+        dir: impl AsRef<Path>,
+        task: &TaskDefinition,
+        inputs: &[(&str, SchemaRef, Vec<RecordBatch>)],
+    ) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir.join(INPUTS_DIR))?;
+        std::fs::write(dir.join(TASK_FILE), task.encode_to_vec())?;
+        for (resource_id, schema, batches) in inputs {
+            let path = dir.join(INPUTS_DIR).join(format!("{resource_id}.arrow"));
+            write_ipc_file(&path, schema, batches)?;
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> String {
+        self.dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// executes the task natively, returning output schema and batches
+    pub async fn execute(&self) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let plan_serde_error = |err: PlanSerDeError| {
+            DataFusionError::Plan(format!("cannot create execution plan: {err}"))
+        };
+        check_plan_version(self.task.plan_version).map_err(plan_serde_error)?;
+        let partition = self
+            .task
+            .task_id
+            .as_ref()
+            .map(|task_id| task_id.partition_id as usize)
+            .unwrap_or(0);
+        let plan = self
+            .task
+            .plan
+            .as_ref()
+            .ok_or_else(|| DataFusionError::Plan("plan is empty".to_string()))?;
+
+        let plan: Arc<dyn ExecutionPlan> = plan.try_into().map_err(plan_serde_error)?;
+        let plan = optimize_plan(plan)?;
+        let plan = self.replace_readers(plan, partition)?;
+
+        MemManager::init(1 << 30);
+        let task_ctx = SessionContext::new().task_ctx();
+        let output = plan.execute(partition, task_ctx)?;
+        Ok((plan.schema(), common::collect(output).await?))
+    }
+
+    /// executes the task and compares with expected outputs, or writes the
+    /// expected outputs in bless mode
+    pub async fn run(&self) -> Result<()> {
+        let (schema, actual) = self.execute().await?;
+        if std::env::var("BLAZE_GOLDEN_BLESS").as_deref() == Ok("1") {
+            log::info!("blessing golden case: {}", self.name());
+            return write_ipc_file(&self.dir.join(EXPECTED_ARROW_FILE), &schema, &actual);
+        }
+
+        let expected = if self.dir.join(EXPECTED_ARROW_FILE).exists() {
+            read_ipc_file(&self.dir.join(EXPECTED_ARROW_FILE))?.1
+        } else {
+            read_json_file(&self.dir.join(EXPECTED_JSON_FILE), &schema)?
+        };
+        compare_batches(&schema, &actual, &expected, &self.tolerance)
+    }
+
+    // replaces readers of jvm side data with the case's input data
+    fn replace_readers(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        partition: usize,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let resource_id = if let Some(exec) = plan.as_any().downcast_ref::<IpcReaderExec>() {
+            Some(exec.ipc_provider_resource_id.as_str())
+        } else if let Some(exec) = plan.as_any().downcast_ref::<FFIReaderExec>() {
+            Some(exec.export_iter_provider_resource_id())
+        } else {
+            None
+        };
+
+        if let Some(resource_id) = resource_id {
+            let path = self.inputs.get(resource_id).ok_or_else(|| {
+                DataFusionError::Execution(format!("missing input data: {resource_id}"))
+            })?;
+            let batches = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => read_json_file(path, &plan.schema())?,
+                _ => read_ipc_file(path)?.1,
+            };
+            let num_partitions = plan.output_partitioning().partition_count();
+            let mut partitions = vec![vec![]; num_partitions.max(partition + 1)];
+            partitions[partition] = batches;
+            return Ok(Arc::new(MemoryExec::try_new(
+                &partitions,
+                plan.schema(),
+                None,
+            )?));
+        }
+
+        let children = plan.children();
+        if children.is_empty() {
+            return Ok(plan);
+        }
+        let new_children = children
+            .into_iter()
+            .map(|child| self.replace_readers(child, partition))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(new_children)
+    }
+}
+
+/// runs all cases in subdirectories of the directory, failing with names of
+/// all failed cases
+pub async fn run_golden_cases(dir: impl AsRef<Path>) -> Result<()> {
+    let mut case_dirs = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    case_dirs.retain(|path| path.join(TASK_FILE).exists());
+    case_dirs.sort();
+
+    let mut failures = vec![];
+    for case_dir in case_dirs {
+        let result = match GoldenCase::try_load(&case_dir) {
+            Ok(case) => case.run().await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            failures.push(format!("{}: {err}", case_dir.display()));
+        }
+    }
+    if !failures.is_empty() {
+        return Err(DataFusionError::Execution(format!(
+            "{} golden case(s) failed:\n{}",
+            failures.len(),
+            failures.join("\n"),
+        )));
+    }
+    Ok(())
+}
+
+/// compares actual outputs with expected outputs under the tolerance rules.
+/// float columns are compared with float tolerances, other columns (including
+/// floats nested in other types) must be exactly equal.
+pub fn compare_batches(
+    schema: &SchemaRef,
+    actual: &[RecordBatch],
+    expected: &[RecordBatch],
+    tolerance: &Tolerance,
+) -> Result<()> {
+    let mismatch = |msg: String| {
+        Err(DataFusionError::Execution(format!(
+            "golden mismatch: {msg}"
+        )))
+    };
+    let actual = concat_batches(schema, actual)?;
+    let expected = match expected.first() {
+        Some(first) => concat_batches(&first.schema(), expected)?,
+        None => RecordBatch::new_empty(schema.clone()),
+    };
+
+    let actual_types = actual
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    let expected_types = expected
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    if actual_types != expected_types {
+        return mismatch(format!(
+            "output types: {actual_types:?}, expected: {expected_types:?}"
+        ));
+    }
+    if actual.num_rows() != expected.num_rows() {
+        return mismatch(format!(
+            "output rows: {}, expected: {}",
+            actual.num_rows(),
+            expected.num_rows()
+        ));
+    }
+
+    let (actual, expected) = if tolerance.ignore_order {
+        (sort_rows(&actual)?, sort_rows(&expected)?)
+    } else {
+        (actual, expected)
+    };
+
+    for (col_idx, field) in schema.fields().iter().enumerate() {
+        let actual_col = actual.column(col_idx);
+        let expected_col = expected.column(col_idx);
+        let is_float = matches!(
+            field.data_type(),
+            DataType::Float16 | DataType::Float32 | DataType::Float64
+        );
+        if !is_float && actual_col == expected_col {
+            continue;
+        }
+
+        let actual_floats = is_float
+            .then(|| cast(actual_col, &DataType::Float64))
+            .transpose()?;
+        let expected_floats = is_float
+            .then(|| cast(expected_col, &DataType::Float64))
+            .transpose()?;
+        for row_idx in 0..actual.num_rows() {
+            let equal = match (&actual_floats, &expected_floats) {
+                (Some(a), Some(e)) => {
+                    let a = as_primitive_array::<arrow::datatypes::Float64Type>(a);
+                    let e = as_primitive_array::<arrow::datatypes::Float64Type>(e);
+                    match (a.is_valid(row_idx), e.is_valid(row_idx)) {
+                        (true, true) => tolerance.floats_equal(a.value(row_idx), e.value(row_idx)),
+                        (a_valid, e_valid) => a_valid == e_valid,
+                    }
+                }
+                _ => actual_col.slice(row_idx, 1) == expected_col.slice(row_idx, 1),
+            };
+            if !equal {
+                let options = FormatOptions::default().with_null("NULL");
+                return mismatch(format!(
+                    "column {} row {row_idx}: {}, expected: {}",
+                    field.name(),
+                    ArrayFormatter::try_new(actual_col.as_ref(), &options)?.value(row_idx),
+                    ArrayFormatter::try_new(expected_col.as_ref(), &options)?.value(row_idx),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sort_rows(batch: &RecordBatch) -> Result<RecordBatch> {
+    let converter = RowConverter::new(
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = converter.convert_columns(batch.columns())?;
+    let mut indices = (0..batch.num_rows() as u32).collect::<Vec<_>>();
+    indices.sort_by(|&i, &j| rows.row(i as usize).cmp(&rows.row(j as usize)));
+
+    let indices = UInt32Array::from(indices);
+    let sorted_cols = batch
+        .columns()
+        .iter()
+        .map(|col| Ok(take(col, &indices, None)?))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), sorted_cols)?)
+}
+
+fn read_ipc_file(path: &Path) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let reader = FileReader::try_new(File::open(path)?, None)?;
+    let schema = reader.schema();
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+fn read_json_file(path: &Path, schema: &SchemaRef) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path)?;
+    let batches = arrow::json::ReaderBuilder::new(schema.clone())
+        .build(BufReader::new(file))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(batches)
+}
+
+fn write_ipc_file(path: &Path, schema: &SchemaRef, batches: &[RecordBatch]) -> Result<()> {
+    let mut writer = FileWriter::try_new(File::create(path)?, schema)?;
+    for batch in batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::golden::{compare_batches, run_golden_cases, GoldenCase, Tolerance};
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use blaze_serde::protobuf::{PartitionId, PhysicalPlanNode, TaskDefinition};
+    use blaze_serde::PLAN_PROTOCOL_VERSION;
+    use datafusion::common::Result;
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_plan::expressions::{binary, col, lit};
+    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion_ext_commons::streams::ipc_stream::IpcReadMode;
+    use datafusion_ext_plans::filter_exec::FilterExec;
+    use datafusion_ext_plans::ipc_reader_exec::IpcReaderExec;
+    use datafusion_ext_plans::project_exec::ProjectExec;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_checked_in_golden_cases() -> Result<()> {
+        run_golden_cases(Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")).await
+    }
+
+    #[tokio::test]
+    async fn test_golden_case() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Float64, true),
+        ]));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(IpcReaderExec::new(
+            1,
+            "input0".to_string(),
+            schema.clone(),
+            IpcReadMode::ChannelUncompressed,
+            None,
+            false,
+        ));
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(
+            vec![binary(col("a", &schema)?, Operator::Gt, lit(1i32), &schema)?],
+            plan,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(ProjectExec::try_new(
+            vec![
+                (col("a", &schema)?, "a".to_string()),
+                (
+                    binary(col("b", &schema)?, Operator::Multiply, lit(3.0f64), &schema)?,
+                    "b3".to_string(),
+                ),
+            ],
+            plan,
+        )?);
+        let task = TaskDefinition {
+            task_id: Some(PartitionId::default()),
+            plan: Some(PhysicalPlanNode::try_from(&plan).unwrap()),
+            plan_version: PLAN_PROTOCOL_VERSION,
+            ..Default::default()
+        };
+        let input = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(Float64Array::from(vec![Some(0.1), Some(0.1), None])),
+            ],
+        )?;
+
+        let cases_dir = tempfile::tempdir()?;
+        let case_dir = cases_dir.path().join("filter_project");
+        GoldenCase::write(&case_dir, &task, &[("input0", schema, vec![input])])?;
+        std::fs::write(
+            case_dir.join("expected.json"),
+            "{\"a\": 3, \"b3\": null}\n{\"a\": 2, \"b3\": 0.3}\n",
+        )?;
+
+        // 0.1 * 3.0 != 0.3 and rows are not in order
+        assert!(run_golden_cases(cases_dir.path()).await.is_err());
+
+        std::fs::write(
+            case_dir.join("tolerance.json"),
+            "{\"ignore_order\": true, \"float_abs_tolerance\": 1e-9}",
+        )?;
+        run_golden_cases(cases_dir.path()).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_batches() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("a", Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef),
+            (
+                "b",
+                Arc::new(Float32Array::from(vec![1.0, f32::NAN])) as ArrayRef,
+            ),
+        ])?;
+        let schema = batch.schema();
+        let tolerance = Tolerance::default();
+        compare_batches(&schema, &[batch.clone()], &[batch.clone()], &tolerance)?;
+
+        let reordered = batch.slice(1, 1);
+        let reordered = vec![reordered, batch.slice(0, 1)];
+        assert!(compare_batches(&schema, &[batch.clone()], &reordered, &tolerance).is_err());
+        let tolerance = Tolerance {
+            ignore_order: true,
+            ..Tolerance::default()
+        };
+        compare_batches(&schema, &[batch.clone()], &reordered, &tolerance)?;
+
+        let other = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec!["x", "z"])), batch.column(1).clone()],
+        )?;
+        let err = compare_batches(&schema, &[batch], &[other], &tolerance).unwrap_err();
+        assert!(err.to_string().contains("column a row 1: y, expected: z"));
+        Ok(())
+    }
+}
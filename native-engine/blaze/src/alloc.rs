@@ -0,0 +1,91 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// global allocator is selected by cargo features, mimalloc takes precedence
+// over the default jemalloc. system allocator is used if neither is enabled.
+
+#[cfg(feature = "mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+/// memory statistics reported by the native allocator
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NativeMemStats {
+    /// bytes allocated by the application
+    pub allocated: usize,
+
+    /// bytes in physically resident pages mapped by the allocator
+    pub resident: usize,
+}
+
+/// returns memory statistics of the current allocator, or None if the
+/// allocator does not support statistics
+#[cfg(all(feature = "jemalloc", not(feature = "mimalloc")))]
+pub fn native_mem_stats() -> Option<NativeMemStats> {
+    use jemalloc_ctl::{epoch, stats};
+
+    // statistics are cached by jemalloc, advance epoch to refresh them
+    let read_stats = || -> Result<NativeMemStats, jemalloc_ctl::Error> {
+        epoch::advance()?;
+        Ok(NativeMemStats {
+            allocated: stats::allocated::read()?,
+            resident: stats::resident::read()?,
+        })
+    };
+    read_stats()
+        .map_err(|err| log::warn!("error reading jemalloc stats: {}", err))
+        .ok()
+}
+
+#[cfg(feature = "mimalloc")]
+pub fn native_mem_stats() -> Option<NativeMemStats> {
+    let mut elapsed_msecs = 0;
+    let mut user_msecs = 0;
+    let mut system_msecs = 0;
+    let mut current_rss = 0;
+    let mut peak_rss = 0;
+    let mut current_commit = 0;
+    let mut peak_commit = 0;
+    let mut page_faults = 0;
+
+    // safety: all pointers are valid for writing
+    unsafe {
+        libmimalloc_sys::mi_process_info(
+            &mut elapsed_msecs,
+            &mut user_msecs,
+            &mut system_msecs,
+            &mut current_rss,
+            &mut peak_rss,
+            &mut current_commit,
+            &mut peak_commit,
+            &mut page_faults,
+        );
+    }
+
+    // mimalloc does not track allocated bytes without its stats build,
+    // committed memory is the closest approximation
+    Some(NativeMemStats {
+        allocated: current_commit,
+        resident: current_rss,
+    })
+}
+
+#[cfg(not(any(feature = "jemalloc", feature = "mimalloc")))]
+pub fn native_mem_stats() -> Option<NativeMemStats> {
+    None
+}
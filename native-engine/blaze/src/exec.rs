@@ -12,41 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::alloc::native_mem_stats;
+use crate::flight_debug::enable_flight_debug;
+use crate::logging::{init_jvm_log_level, init_logging};
 use crate::rt::NativeExecutionRuntime;
+use crate::trace::init_tracing;
 use crate::{handle_unwinded_scope, SESSION};
 use blaze_jni_bridge::jni_bridge::JavaClasses;
 use blaze_jni_bridge::*;
-use blaze_serde::protobuf::TaskDefinition;
+use blaze_serde::error::PlanSerDeError;
+use blaze_serde::from_proto::set_case_sensitive;
+use blaze_serde::plan_optimizer::optimize_plan;
+use blaze_serde::plan_render::{render_plan, PlanRenderFormat};
+use blaze_serde::protobuf::{PhysicalPlanNode, TaskDefinition};
+use blaze_serde::{check_plan_version, PLAN_PROTOCOL_VERSION};
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
+use datafusion::execution::context::TaskContext;
 use datafusion::execution::disk_manager::DiskManagerConfig;
 use datafusion::execution::runtime_env::{RuntimeConfig, RuntimeEnv};
 use datafusion::physical_plan::{displayable, ExecutionPlan};
 use datafusion::prelude::{SessionConfig, SessionContext};
-use datafusion_ext_plans::common::memory_manager::MemManager;
+use datafusion_ext_commons::cancellation::{CancellationToken, TaskDeadline};
+use datafusion_ext_plans::batch_validation_exec::BatchValidationExec;
+use datafusion_ext_plans::common::broadcast_cache::release_broadcast;
+use datafusion_ext_plans::common::memory_manager::{MemManager, MemManagerOptions};
 use jni::objects::JClass;
 use jni::objects::JObject;
+use jni::objects::JString;
+use jni::sys::jstring;
 use jni::JNIEnv;
-use log::LevelFilter;
-use once_cell::sync::OnceCell;
 use prost::Message;
-use simplelog::{ColorChoice, ConfigBuilder, TermLogger, TerminalMode, ThreadLogMode};
+use std::collections::HashMap;
 use std::sync::Arc;
-
-fn init_logging() {
-    static LOGGING_INIT: OnceCell<()> = OnceCell::new();
-    LOGGING_INIT.get_or_init(|| {
-        TermLogger::init(
-            LevelFilter::Info,
-            ConfigBuilder::new()
-                .set_thread_mode(ThreadLogMode::Both)
-                .build(),
-            TerminalMode::Stderr,
-            ColorChoice::Never,
-        )
-        .unwrap();
-    });
-}
+use std::time::{Duration, UNIX_EPOCH};
 
 #[allow(non_snake_case)]
 #[allow(clippy::single_match)]
@@ -59,16 +58,29 @@ pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_initNative(
     handle_unwinded_scope(|| -> Result<()> {
         // init logging
         init_logging();
+        init_tracing();
 
-        // init jni java classes
+        // init jni java classes, native logs are forwarded to jvm since then
         JavaClasses::init(&env);
+        init_jvm_log_level()?;
+
+        // register native udfs before any plan is created
+        datafusion_ext_functions::native_udf::run_registration_hooks()?;
 
         // init datafusion session context
         SESSION.get_or_try_init(|| {
             let max_memory = executor_memory_overhead as usize;
             let memory_fraction = jni_call_static!(BlazeConf.memoryFraction() -> f64)?;
             let batch_size = jni_call_static!(BlazeConf.batchSize() -> i32)? as usize;
-            MemManager::init((max_memory as f64 * memory_fraction) as usize);
+            MemManager::init_with_options(
+                (max_memory as f64 * memory_fraction) as usize,
+                MemManagerOptions {
+                    spark_managed: jni_call_static!(BlazeConf.sparkManagedMemory() -> bool)?,
+                    operator_mem_fraction: jni_call_static!(
+                        BlazeConf.operatorMemoryFraction() -> f64
+                    )?,
+                },
+            );
 
             let session_config = SessionConfig::new().with_batch_size(batch_size);
             let runtime_config =
@@ -101,14 +113,40 @@ pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_callNative(
         )
         .map_err(|err| DataFusionError::Plan(format!("cannot decode execution plan: {:?}", err)))?;
 
+        check_plan_version(task_definition.plan_version).map_err(plan_serde_error)?;
         let task_id = &task_definition.task_id.expect("task_id is empty");
         let plan = &task_definition.plan.expect("plan is empty");
+        let deadline = match task_definition.deadline_millis {
+            0 => None,
+            deadline_millis => Some(UNIX_EPOCH + Duration::from_millis(deadline_millis)),
+        };
+        let operator_timeout = match task_definition.operator_timeout_millis {
+            0 => None,
+            timeout_millis => Some(Duration::from_millis(timeout_millis)),
+        };
         drop(raw_task_definition);
 
         // get execution plan
-        let execution_plan: Arc<dyn ExecutionPlan> = plan.try_into().map_err(|err| {
-            DataFusionError::Plan(format!("cannot create execution plan: {:?}", err))
-        })?;
+        set_case_sensitive(jni_call_static!(BlazeConf.caseSensitive() -> bool)?);
+        let mut execution_plan: Arc<dyn ExecutionPlan> =
+            plan.try_into().map_err(plan_serde_error)?;
+        execution_plan = optimize_plan(execution_plan)?;
+        if jni_call_static!(BlazeConf.enableBatchValidation() -> bool)? {
+            execution_plan = BatchValidationExec::wrap_all(execution_plan)?;
+        }
+        let debug_flight_operator = jni_call_static!(BlazeConf.debugFlightOperator() -> JObject)?;
+        let debug_flight_operator = jni_get_string!(debug_flight_operator.as_obj().into())?;
+        if !debug_flight_operator.is_empty() {
+            let debug_flight_host = jni_call_static!(BlazeConf.debugFlightHost() -> JObject)?;
+            let debug_flight_host = jni_get_string!(debug_flight_host.as_obj().into())?;
+            execution_plan = enable_flight_debug(
+                execution_plan,
+                &debug_flight_host,
+                jni_call_static!(BlazeConf.debugFlightPort() -> i32)? as u16,
+                &debug_flight_operator,
+                &format!("{}-{}", task_id.stage_id, task_id.partition_id),
+            )?;
+        }
         let execution_plan_displayable = displayable(execution_plan.as_ref())
             .indent(true)
             .to_string();
@@ -116,13 +154,37 @@ pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_callNative(
         log::info!("  task_id={:?}", task_id);
         log::info!("  execution plan:\n{}", execution_plan_displayable);
 
-        // execute to stream
-        let runtime = Box::new(NativeExecutionRuntime::start(
-            native_wrapper,
-            execution_plan,
-            task_id.partition_id as usize,
-            SESSION.get().unwrap().task_ctx(),
-        )?);
+        // create task context carrying a cancellation token, which is
+        // cancelled when the task is finished/killed, and deadlines of operators
+        let session = SESSION.get().unwrap();
+        let task_context = Arc::new(TaskContext::new(
+            None,
+            session.session_id(),
+            session
+                .copied_config()
+                .with_extension(Arc::new(CancellationToken::new()))
+                .with_extension(Arc::new(TaskDeadline::new(operator_timeout))),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            session.runtime_env(),
+        ));
+
+        // execute to stream, spans of all operators are nested in the task span
+        let task_span = tracing::info_span!(
+            "native_task",
+            stage_id = task_id.stage_id,
+            partition = task_id.partition_id,
+        );
+        let runtime = Box::new(task_span.in_scope(|| {
+            NativeExecutionRuntime::start(
+                native_wrapper,
+                execution_plan,
+                task_id.partition_id as usize,
+                task_context,
+                deadline,
+            )
+        })?);
         log::info!("Blaze native thread created");
 
         // returns runtime raw pointer
@@ -140,3 +202,84 @@ pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_finalizeNative(
     let runtime = unsafe { Box::from_raw(rtw_ptr as usize as *mut NativeExecutionRuntime) };
     runtime.finalize();
 }
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_getNativeAllocatedMemory(
+    _: JNIEnv,
+    _: JClass,
+) -> i64 {
+    native_mem_stats()
+        .map(|stats| stats.allocated as i64)
+        .unwrap_or(-1)
+}
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_getNativeResidentMemory(
+    _: JNIEnv,
+    _: JClass,
+) -> i64 {
+    native_mem_stats()
+        .map(|stats| stats.resident as i64)
+        .unwrap_or(-1)
+}
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_renderNativePlan(
+    env: JNIEnv,
+    _: JClass,
+    raw_plan: JObject,
+    format: JString,
+) -> jstring {
+    handle_unwinded_scope(|| -> Result<usize> {
+        let format: PlanRenderFormat = jni_get_string!(format)?
+            .parse()
+            .map_err(|err| DataFusionError::Plan(format!("{:?}", err)))?;
+        let plan = PhysicalPlanNode::decode(jni_convert_byte_array!(&raw_plan)?.as_slice())
+            .map_err(|err| {
+                DataFusionError::Plan(format!("cannot decode execution plan: {:?}", err))
+            })?;
+        set_case_sensitive(jni_call_static!(BlazeConf.caseSensitive() -> bool)?);
+        let execution_plan: Arc<dyn ExecutionPlan> =
+            (&plan).try_into().map_err(plan_serde_error)?;
+
+        // the local reference is returned to jvm, so it must not be released here
+        let rendered = env
+            .new_string(render_plan(&execution_plan, format))
+            .map_err(|err| DataFusionError::External(Box::new(err)))?;
+        Ok(JObject::from(rendered).into_inner() as usize)
+    }) as jstring
+}
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_getPlanProtocolVersion(
+    _: JNIEnv,
+    _: JClass,
+) -> i32 {
+    PLAN_PROTOCOL_VERSION as i32
+}
+
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "system" fn Java_org_apache_spark_sql_blaze_JniBridge_releaseCachedBroadcast(
+    _: JNIEnv,
+    _: JClass,
+    id: JString,
+) {
+    handle_unwinded_scope(|| -> Result<()> {
+        release_broadcast(&jni_get_string!(id)?)?;
+        Ok(())
+    });
+}
+
+// unsupported features are reported as NotImplemented, so the jvm side can
+// tell them from other planning errors
+fn plan_serde_error(err: PlanSerDeError) -> DataFusionError {
+    if err.is_unsupported() {
+        return DataFusionError::NotImplemented(format!("cannot create execution plan: {}", err));
+    }
+    DataFusionError::Plan(format!("cannot create execution plan: {:?}", err))
+}
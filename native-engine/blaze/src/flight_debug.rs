@@ -0,0 +1,244 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// arrow flight debug server, exposing output batches of native operators
+// selected by spark.blaze.debug.flight.operator as flight streams (requires
+// feature `flight-debug`). each wrapped operator is listed as a flight with
+// path/ticket `{stage_id}-{partition_id}-{operator}-{index}`, which can be
+// fetched with any arrow flight client (like pyarrow.flight). the server
+// listens on spark.blaze.debug.flight.host, loopback by default.
+
+use datafusion::common::Result;
+use datafusion::physical_plan::ExecutionPlan;
+use std::sync::Arc;
+
+/// starts the debug server (once per executor) and wraps matching operators
+/// of the plan to capture their output batches.
+#[cfg(feature = "flight-debug")]
+pub fn enable_flight_debug(
+    plan: Arc<dyn ExecutionPlan>,
+    host: &str,
+    port: u16,
+    operator_name: &str,
+    debug_id_prefix: &str,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    use datafusion_ext_plans::debug_exec::DebugExec;
+
+    server::start_once(host, port);
+    DebugExec::wrap_matching(plan, operator_name, debug_id_prefix)
+}
+
+#[cfg(not(feature = "flight-debug"))]
+pub fn enable_flight_debug(
+    plan: Arc<dyn ExecutionPlan>,
+    _host: &str,
+    _port: u16,
+    _operator_name: &str,
+    _debug_id_prefix: &str,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    log::warn!("flight debug server is not enabled, requires feature `flight-debug`");
+    Ok(plan)
+}
+
+#[cfg(feature = "flight-debug")]
+mod server {
+    use arrow::ipc::writer::IpcWriteOptions;
+    use arrow_flight::encode::FlightDataEncoderBuilder;
+    use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+    use arrow_flight::{
+        Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint,
+        FlightInfo, HandshakeRequest, HandshakeResponse, PutResult, SchemaAsIpc, SchemaResult,
+        Ticket,
+    };
+    use datafusion_ext_plans::debug_exec::DebugCaptures;
+    use futures::stream::BoxStream;
+    use futures::{StreamExt, TryStreamExt};
+    use once_cell::sync::OnceCell;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Status, Streaming};
+
+    // server is driven by its own runtime living through the process
+    static FLIGHT_DEBUG_RUNTIME: OnceCell<tokio::runtime::Runtime> = OnceCell::new();
+
+    pub fn start_once(host: &str, port: u16) {
+        FLIGHT_DEBUG_RUNTIME.get_or_init(|| {
+            let rt = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("blaze-flight-debug")
+                .enable_all()
+                .build()
+                .expect("error creating flight debug runtime");
+
+            let host = host.to_string();
+            rt.spawn(async move {
+                let listener = match tokio::net::TcpListener::bind((host.as_str(), port)).await {
+                    Ok(listener) => listener,
+                    Err(err) => {
+                        log::error!("error binding flight debug server: {err}");
+                        return;
+                    }
+                };
+                if let Ok(addr) = listener.local_addr() {
+                    log::info!("flight debug server listening on {addr}");
+                }
+                let incoming = futures::stream::unfold(listener, |listener| async move {
+                    let conn = listener.accept().await.map(|(stream, _)| stream);
+                    Some((conn, listener))
+                });
+                if let Err(err) = Server::builder()
+                    .add_service(FlightServiceServer::new(DebugFlightService))
+                    .serve_with_incoming(incoming)
+                    .await
+                {
+                    log::error!("flight debug server exited with error: {err}");
+                }
+            });
+            rt
+        });
+    }
+
+    struct DebugFlightService;
+
+    impl DebugFlightService {
+        fn debug_id(descriptor: &FlightDescriptor) -> Result<String, Status> {
+            match descriptor.path.as_slice() {
+                [debug_id] => Ok(debug_id.clone()),
+                _ => Err(Status::invalid_argument(
+                    "flight descriptor path must be a single debug id",
+                )),
+            }
+        }
+
+        fn flight_info(debug_id: &str) -> Result<FlightInfo, Status> {
+            let captures = DebugCaptures::get().lock();
+            let (schema, num_rows) = captures
+                .list()
+                .into_iter()
+                .find(|(id, ..)| id == debug_id)
+                .map(|(_, schema, num_rows)| (schema, num_rows))
+                .ok_or_else(|| Status::not_found(format!("no capture of {debug_id}")))?;
+            drop(captures);
+
+            Ok(FlightInfo::new()
+                .try_with_schema(&schema)
+                .map_err(|err| Status::internal(err.to_string()))?
+                .with_descriptor(FlightDescriptor::new_path(vec![debug_id.to_string()]))
+                .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(debug_id.to_string())))
+                .with_total_records(num_rows as i64))
+        }
+    }
+
+    #[tonic::async_trait]
+    impl FlightService for DebugFlightService {
+        type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+        type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+        type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+        type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+        type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+        type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+        type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+        async fn handshake(
+            &self,
+            _request: Request<Streaming<HandshakeRequest>>,
+        ) -> Result<Response<Self::HandshakeStream>, Status> {
+            Err(Status::unimplemented("handshake"))
+        }
+
+        async fn list_flights(
+            &self,
+            _request: Request<Criteria>,
+        ) -> Result<Response<Self::ListFlightsStream>, Status> {
+            let debug_ids = DebugCaptures::get()
+                .lock()
+                .list()
+                .into_iter()
+                .map(|(debug_id, ..)| debug_id)
+                .collect::<Vec<_>>();
+            let flights = debug_ids
+                .iter()
+                .map(|debug_id| Self::flight_info(debug_id))
+                .collect::<Vec<_>>();
+            Ok(Response::new(futures::stream::iter(flights).boxed()))
+        }
+
+        async fn get_flight_info(
+            &self,
+            request: Request<FlightDescriptor>,
+        ) -> Result<Response<FlightInfo>, Status> {
+            let debug_id = Self::debug_id(request.get_ref())?;
+            Ok(Response::new(Self::flight_info(&debug_id)?))
+        }
+
+        async fn get_schema(
+            &self,
+            request: Request<FlightDescriptor>,
+        ) -> Result<Response<SchemaResult>, Status> {
+            let debug_id = Self::debug_id(request.get_ref())?;
+            let (schema, _) = DebugCaptures::get()
+                .lock()
+                .batches(&debug_id)
+                .ok_or_else(|| Status::not_found(format!("no capture of {debug_id}")))?;
+            let schema_result = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+                .try_into()
+                .map_err(|err: arrow::error::ArrowError| Status::internal(err.to_string()))?;
+            Ok(Response::new(schema_result))
+        }
+
+        async fn do_get(
+            &self,
+            request: Request<Ticket>,
+        ) -> Result<Response<Self::DoGetStream>, Status> {
+            let debug_id = String::from_utf8(request.into_inner().ticket.to_vec())
+                .map_err(|_| Status::invalid_argument("ticket must be a utf8 debug id"))?;
+            let (schema, batches) = DebugCaptures::get()
+                .lock()
+                .batches(&debug_id)
+                .ok_or_else(|| Status::not_found(format!("no capture of {debug_id}")))?;
+            let stream = FlightDataEncoderBuilder::new()
+                .with_schema(schema)
+                .build(futures::stream::iter(batches.into_iter().map(Ok)))
+                .map_err(Status::from);
+            Ok(Response::new(stream.boxed()))
+        }
+
+        async fn do_put(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoPutStream>, Status> {
+            Err(Status::unimplemented("do_put"))
+        }
+
+        async fn do_action(
+            &self,
+            _request: Request<Action>,
+        ) -> Result<Response<Self::DoActionStream>, Status> {
+            Err(Status::unimplemented("do_action"))
+        }
+
+        async fn list_actions(
+            &self,
+            _request: Request<Empty>,
+        ) -> Result<Response<Self::ListActionsStream>, Status> {
+            Err(Status::unimplemented("list_actions"))
+        }
+
+        async fn do_exchange(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoExchangeStream>, Status> {
+            Err(Status::unimplemented("do_exchange"))
+        }
+    }
+}
@@ -21,12 +21,13 @@ use std::error::Error;
 use std::fmt::Debug;
 use std::panic::AssertUnwindSafe;
 
+mod alloc;
 mod exec;
+mod flight_debug;
+mod logging;
 mod metrics;
 mod rt;
-
-#[global_allocator]
-static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+mod trace;
 
 static SESSION: OnceCell<SessionContext> = OnceCell::new();
 
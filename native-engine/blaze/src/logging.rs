@@ -0,0 +1,127 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// native logger forwarding `log` records (and `tracing` events when no tracing
+// subscriber is enabled) to JniBridge.nativeLog(), so native logs are written
+// by the executor's log4j appenders. the jvm side attaches stage/partition/task
+// attempt id of the logging thread's task context as MDC fields.
+// records are printed to stderr until the jni bridge is initialized.
+
+use blaze_jni_bridge::{
+    is_jni_bridge_inited, jni_call_static, jni_exception_check, jni_new_string,
+};
+use datafusion::common::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::OnceCell;
+use std::cell::Cell;
+
+static JNI_LOGGER: JniLogger = JniLogger;
+
+pub fn init_logging() {
+    static LOGGING_INIT: OnceCell<()> = OnceCell::new();
+    LOGGING_INIT.get_or_init(|| {
+        log::set_logger(&JNI_LOGGER).expect("error setting native logger");
+        log::set_max_level(LevelFilter::Info);
+    });
+}
+
+/// follows the level enabled by the jvm side logger, called after the jni
+/// bridge is initialized.
+pub fn init_jvm_log_level() -> Result<()> {
+    let max_level = match jni_call_static!(JniBridge.getNativeLogLevel() -> i32)? {
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        _ => LevelFilter::Off,
+    };
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+struct JniLogger;
+
+thread_local! {
+    // set while forwarding a record, logs emitted by the jni calls themselves
+    // are not forwarded again
+    static FORWARDING: Cell<bool> = Cell::new(false);
+}
+
+impl JniLogger {
+    fn forward(record: &Record) -> Result<()> {
+        let level = match record.level() {
+            Level::Error => 1,
+            Level::Warn => 2,
+            Level::Info => 3,
+            Level::Debug => 4,
+            Level::Trace => 5,
+        };
+        let target = jni_new_string!(record.target())?;
+        let message = jni_new_string!(record.args().to_string())?;
+        jni_call_static!(
+            JniBridge.nativeLog(level, target.as_obj(), message.as_obj()) -> ()
+        )?;
+        Ok(())
+    }
+
+    fn print_stderr(record: &Record) {
+        eprintln!(
+            "{} [{}] {}: {}",
+            record.level(),
+            std::thread::current().name().unwrap_or("<unnamed>"),
+            record.target(),
+            record.args(),
+        );
+    }
+}
+
+impl Log for JniLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if !is_jni_bridge_inited() {
+            return Self::print_stderr(record);
+        }
+        let reentrant = FORWARDING
+            .try_with(|forwarding| forwarding.replace(true))
+            .unwrap_or(true);
+        if reentrant {
+            return;
+        }
+
+        // jni calls are not allowed with a pending exception, which will be
+        // thrown to jvm later
+        let result = match jni_exception_check!() {
+            Ok(false) => Self::forward(record),
+            _ => {
+                Self::print_stderr(record);
+                Ok(())
+            }
+        };
+        FORWARDING.with(|forwarding| forwarding.set(false));
+
+        if let Err(err) = result {
+            eprintln!("error forwarding native log to jvm: {}", err);
+            Self::print_stderr(record);
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use blaze_jni_bridge::{jni_call, jni_new_string};
+use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::common::Result;
-use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::{displayable, ExecutionPlan};
+use datafusion_ext_plans::batch_validation_exec::BatchValidationExec;
 use jni::objects::JObject;
+use std::fmt::Write;
 use std::sync::Arc;
 
 pub fn update_spark_metric_node(
@@ -26,6 +28,14 @@ pub fn update_spark_metric_node(
         return Ok(());
     }
 
+    // batch validation exec is transparent, its input shares the same node
+    if let Some(validation) = execution_plan
+        .as_any()
+        .downcast_ref::<BatchValidationExec>()
+    {
+        return update_spark_metric_node(metric_node, validation.input().clone());
+    }
+
     // update current node
     update_metrics(
         metric_node,
@@ -49,9 +59,58 @@ pub fn update_spark_metric_node(
 }
 
 fn update_metrics(metric_node: JObject, metric_values: &[(&str, i64)]) -> Result<()> {
+    if metric_values.is_empty() {
+        return Ok(());
+    }
+
+    // all metrics of the node are pushed in one jni call, encoded as
+    // (name_len: i32, name: utf8, value: i64) entries in little endian
+    let mut buf = vec![];
     for &(name, value) in metric_values {
-        let jname = jni_new_string!(&name)?;
-        jni_call!(SparkMetricNode(metric_node).add(jname.as_obj(), value) -> ())?;
+        buf.extend_from_slice(&(name.len() as i32).to_le_bytes());
+        buf.extend_from_slice(name.as_bytes());
+        buf.extend_from_slice(&value.to_le_bytes());
     }
+    let jbuf = jni_new_direct_byte_buffer!(&buf)?;
+    jni_call!(SparkMetricNode(metric_node).addAll(jbuf.as_obj()) -> ())?;
     Ok(())
 }
+
+/// formats per-operator metrics of the executed plan into an indented tree,
+/// like datafusion's `EXPLAIN ANALYZE` output
+pub fn format_metrics_summary(execution_plan: &Arc<dyn ExecutionPlan>) -> String {
+    fn format_node(execution_plan: &Arc<dyn ExecutionPlan>, indent: usize, summary: &mut String) {
+        // batch validation exec is transparent, its input takes its place
+        if let Some(validation) = execution_plan
+            .as_any()
+            .downcast_ref::<BatchValidationExec>()
+        {
+            return format_node(validation.input(), indent, summary);
+        }
+
+        let description = displayable(execution_plan.as_ref()).one_line().to_string();
+        let metrics = execution_plan
+            .metrics()
+            .unwrap_or_default()
+            .aggregate_by_name()
+            .sorted_for_display()
+            .timestamps_removed();
+        writeln!(
+            summary,
+            "{:indent$}{}, metrics=[{}]",
+            "",
+            description.trim(),
+            metrics,
+            indent = indent * 2,
+        )
+        .unwrap();
+
+        for child_plan in execution_plan.children() {
+            format_node(&child_plan, indent + 1, summary);
+        }
+    }
+
+    let mut summary = String::new();
+    format_node(execution_plan, 0, &mut summary);
+    summary
+}
@@ -13,27 +13,40 @@
 // limitations under the License.
 
 use crate::handle_unwinded_scope;
-use crate::metrics::update_spark_metric_node;
+use crate::metrics::{format_metrics_summary, update_spark_metric_node};
+use crate::trace::flush_tracing;
 use arrow::ffi_stream::FFI_ArrowArrayStream;
 use blaze_jni_bridge::is_task_running;
 use blaze_jni_bridge::jni_bridge::JavaClasses;
 use blaze_jni_bridge::{
-    jni_call, jni_call_static, jni_exception_check, jni_exception_occurred, jni_new_global_ref,
-    jni_new_object, jni_new_string,
+    jni_call, jni_call_static, jni_exception_check, jni_exception_occurred, jni_get_string,
+    jni_new_global_ref, jni_new_object, jni_new_string,
 };
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::Time;
-use datafusion::physical_plan::{ExecutionPlan, RecordBatchStream};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{ExecutionPlan, RecordBatchStream, SendableRecordBatchStream};
+use datafusion_ext_commons::cancellation::{
+    set_thread_cancellation_token, task_cancellation_token, task_deadline, CancellationToken,
+};
+use datafusion_ext_commons::cast::{decode_dictionary_columns, decoded_dictionary_schema};
 use datafusion_ext_commons::ffi::MpscBatchReader;
+use datafusion_ext_commons::streams::cancellable_stream::CancellableStream;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
+use datafusion_ext_commons::unsafe_row::{batch_to_unsafe_rows, unsafe_rows_schema};
+use datafusion_ext_exprs::raise_error::extract_raised_error_message;
 use datafusion_ext_plans::common::output::WrappedRecordBatchSender;
 use futures::{FutureExt, StreamExt};
 use jni::objects::{GlobalRef, JObject};
 use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::runtime::Runtime;
+use tracing::Instrument;
 
 pub struct NativeExecutionRuntime {
     native_wrapper: GlobalRef,
@@ -42,6 +55,7 @@ pub struct NativeExecutionRuntime {
     partition: usize,
     rt: Runtime,
     ffi_stream: Box<FFI_ArrowArrayStream>,
+    cancellation_token: CancellationToken,
 }
 
 impl NativeExecutionRuntime {
@@ -50,20 +64,47 @@ impl NativeExecutionRuntime {
         plan: Arc<dyn ExecutionPlan>,
         partition: usize,
         context: Arc<TaskContext>,
+        deadline: Option<SystemTime>,
     ) -> Result<Self> {
         let batch_size = context.session_config().batch_size();
 
-        // execute plan to output stream
-        let stream = plan.execute(partition, context.clone())?;
+        // execute plan to output stream, which stops once the task is cancelled
+        let cancellation_token = task_cancellation_token(&context);
+        let stream = Box::pin(CancellableStream::new(
+            plan.execute(partition, context.clone())?,
+            cancellation_token.clone(),
+        ));
 
         // coalesce
         let coalesce_compute_time = Time::new();
-        let mut stream = Box::pin(CoalesceStream::new(
+        let mut stream: SendableRecordBatchStream = Box::pin(CoalesceStream::new(
             stream,
             batch_size,
             coalesce_compute_time,
         ));
 
+        // decode dictionary columns (like partition columns of scans), which are
+        // not read by jvm side
+        let decoded_schema = decoded_dictionary_schema(&stream.schema());
+        if decoded_schema != stream.schema() {
+            stream = Box::pin(RecordBatchStreamAdapter::new(
+                decoded_schema.clone(),
+                stream.map(move |batch| {
+                    batch.and_then(|batch| decode_dictionary_columns(batch, &decoded_schema))
+                }),
+            ));
+        }
+
+        // convert output batches to UnsafeRows, which are read by jvm side without
+        // per-row conversion
+        if jni_call_static!(BlazeConf.enableNativeColumnarToRow() -> bool)? {
+            let num_fields = stream.schema().fields().len();
+            stream = Box::pin(RecordBatchStreamAdapter::new(
+                unsafe_rows_schema(num_fields),
+                stream.map(|batch| batch.and_then(|batch| batch_to_unsafe_rows(&batch))),
+            ));
+        }
+
         // create mpsc channel for collecting batches
         let (sender, receiver) = std::sync::mpsc::sync_channel(1);
 
@@ -83,7 +124,20 @@ impl NativeExecutionRuntime {
         // propagate classloader and task context to spawned children threads
         let spark_task_context = jni_call_static!(JniBridge.getTaskContext() -> JObject)?;
         let spark_task_context_global = jni_new_global_ref!(spark_task_context.as_obj())?;
-        let rt = tokio::runtime::Builder::new_multi_thread()
+        let rt_conf = RuntimeConf::try_new_from_conf()?;
+        let mut rt_builder = tokio::runtime::Builder::new_multi_thread();
+        if rt_conf.worker_threads > 0 {
+            rt_builder.worker_threads(rt_conf.worker_threads);
+        }
+        let thread_name_prefix = rt_conf.thread_name_prefix;
+        let thread_id = AtomicUsize::new(0);
+        let pin_cores = rt_conf.pin_cores;
+        let thread_cancellation_token = cancellation_token.clone();
+        let rt = rt_builder
+            .thread_name_fn(move || {
+                let id = thread_id.fetch_add(1, SeqCst);
+                format!("{}-{}-{}", thread_name_prefix, partition, id)
+            })
             .on_thread_start(move || {
                 let classloader = JavaClasses::get().classloader;
                 let _ = jni_call_static!(
@@ -92,7 +146,12 @@ impl NativeExecutionRuntime {
                 let _ = jni_call_static!(
                     JniBridge.setTaskContext(spark_task_context_global.as_obj()) -> ()
                 );
+                set_thread_cancellation_token(thread_cancellation_token.clone());
+                if pin_cores {
+                    pin_current_thread();
+                }
             })
+            .enable_time()
             .build()?;
 
         let nrt = Self {
@@ -102,8 +161,34 @@ impl NativeExecutionRuntime {
             rt,
             ffi_stream,
             task_context: context,
+            cancellation_token: cancellation_token.clone(),
         };
 
+        // cancel the task once the deadline is exceeded, operators exceeding their
+        // own deadlines also cancel the task through the task deadline
+        let task_deadline = task_deadline(&nrt.task_context);
+        if let Some(deadline) = deadline {
+            let timeout = deadline
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            let cancellation_token = cancellation_token.clone();
+            let task_deadline = task_deadline.clone();
+            nrt.rt.spawn(async move {
+                let cancelled = cancellation_token.cancelled();
+                if tokio::time::timeout(timeout, cancelled).await.is_err() {
+                    log::warn!(
+                        "native execution [partition={}] exceeded deadline, cancelling",
+                        partition,
+                    );
+                    task_deadline.set_exceeded(format!(
+                        "native execution [partition={}] timed out: exceeded deadline",
+                        partition,
+                    ));
+                    cancellation_token.cancel();
+                }
+            });
+        }
+
         // spawn batch producer
         let consume_stream = move || async move {
             while let Some(batch) = AssertUnwindSafe(stream.next())
@@ -132,10 +217,21 @@ impl NativeExecutionRuntime {
             log::info!("native execution [partition={}] finished", partition);
             Ok::<_, DataFusionError>(())
         };
+        let task_span = tracing::Span::current();
         nrt.rt.spawn(async move {
-            let result = consume_stream().await;
+            let result = consume_stream().instrument(task_span).await;
             result.unwrap_or_else(|err| handle_unwinded_scope(|| -> Result<()> {
-                let task_running = is_task_running();
+                if let Some(err_message) = task_deadline.exceeded() {
+                    let err_message = err_message.to_owned();
+                    log::error!("{}", err_message);
+                    let cause = jni_new_object!(JavaTimeoutException(
+                        jni_new_string!(err_message.clone())?.as_obj()
+                    ))?;
+                    set_error(&native_wrapper, &err_message, Some(cause.as_obj()))?;
+                    return Ok(());
+                }
+
+                let task_running = !cancellation_token.is_cancelled() && is_task_running();
                 log::warn!(
                     "native execution [partition={}] broken (task_running: {}): {}",
                     partition,
@@ -150,6 +246,19 @@ impl NativeExecutionRuntime {
                     return Ok(());
                 }
 
+                // errors raised by user (raise_error/assert_true) are thrown with
+                // exactly the user-supplied message, like spark does
+                let err_message = err.to_string();
+                if let Some(raised_message) = extract_raised_error_message(&err_message) {
+                    log::error!(
+                        "native execution [partition={}] raised an error: {}",
+                        partition,
+                        raised_message,
+                    );
+                    set_error(&native_wrapper, raised_message, None)?;
+                    return Ok(());
+                }
+
                 let cause =
                     if jni_exception_check!()? {
                         log::error!(
@@ -188,10 +297,13 @@ impl NativeExecutionRuntime {
     pub fn finalize(self) {
         log::info!("native execution [partition={}] finalizing", self.partition);
         let _ = self.update_metrics();
+        let _ = self.update_metrics_summary();
+        self.cancellation_token.cancel(); // stop all running streams
         drop(self.ffi_stream);
         drop(self.plan);
         WrappedRecordBatchSender::cancel_task(&self.task_context); // cancel all pending streams
         self.rt.shutdown_background();
+        flush_tracing();
         log::info!("native execution [partition={}] finalized", self.partition);
     }
 
@@ -202,6 +314,95 @@ impl NativeExecutionRuntime {
         update_spark_metric_node(metrics.as_obj(), self.plan.clone())?;
         Ok(())
     }
+
+    fn update_metrics_summary(&self) -> Result<()> {
+        if !jni_call_static!(BlazeConf.enableMetricsSummary() -> bool)? {
+            return Ok(());
+        }
+        let summary = jni_new_string!(format_metrics_summary(&self.plan))?;
+        jni_call!(
+            BlazeCallNativeWrapper(self.native_wrapper.as_obj())
+                .setMetricsSummary(summary.as_obj()) -> ()
+        )?;
+        Ok(())
+    }
+}
+
+/// configuration of the tokio runtime driving native streams
+struct RuntimeConf {
+    worker_threads: usize,
+    thread_name_prefix: String,
+    pin_cores: bool,
+}
+
+impl RuntimeConf {
+    fn try_new_from_conf() -> Result<Self> {
+        let thread_name_prefix = jni_call_static!(BlazeConf.tokioThreadNamePrefix() -> JObject)?;
+        Ok(Self {
+            worker_threads: jni_call_static!(BlazeConf.tokioWorkerThreads() -> i32)?.max(0)
+                as usize,
+            thread_name_prefix: jni_get_string!(thread_name_prefix.as_obj().into())?,
+            pin_cores: jni_call_static!(BlazeConf.tokioPinCores() -> bool)?,
+        })
+    }
+}
+
+/// pins current thread to a cpu core, cores allowed for the process (like
+/// restricted by cpusets) are assigned in round-robin order across all native
+/// worker threads of the executor
+#[cfg(target_os = "linux")]
+fn pin_current_thread() {
+    static NEXT_CORE_ID: AtomicUsize = AtomicUsize::new(0);
+    static ALLOWED_CORE_IDS: once_cell::sync::OnceCell<Vec<usize>> =
+        once_cell::sync::OnceCell::new();
+
+    // read from the process instead of the current thread, which may be spawned
+    // by a pinned thread and inherit its single-core affinity
+    let allowed_core_ids = ALLOWED_CORE_IDS.get_or_init(|| {
+        // safety: cpu_set is zero-initialized and written by sched_getaffinity
+        unsafe {
+            let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+            let ret = libc::sched_getaffinity(
+                libc::getpid(),
+                std::mem::size_of::<libc::cpu_set_t>(),
+                &mut cpu_set,
+            );
+            if ret != 0 {
+                log::warn!(
+                    "error getting cpu affinity, native worker threads are not pinned: {}",
+                    std::io::Error::last_os_error(),
+                );
+                return vec![];
+            }
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|&core_id| libc::CPU_ISSET(core_id, &cpu_set))
+                .collect()
+        }
+    });
+    if allowed_core_ids.is_empty() {
+        return;
+    }
+    let core_id = allowed_core_ids[NEXT_CORE_ID.fetch_add(1, SeqCst) % allowed_core_ids.len()];
+
+    // safety: cpu_set is initialized by CPU_ZERO before use
+    let ret = unsafe {
+        let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_ZERO(&mut cpu_set);
+        libc::CPU_SET(core_id, &mut cpu_set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+    };
+    if ret != 0 {
+        log::warn!(
+            "error pinning native worker thread to core {}: {}",
+            core_id,
+            std::io::Error::last_os_error(),
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread() {
+    log::warn!("pinning native worker threads is only supported on linux");
 }
 
 fn set_error(native_wrapper: &GlobalRef, message: &str, cause: Option<JObject>) -> Result<()> {
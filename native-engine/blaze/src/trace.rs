@@ -0,0 +1,115 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// tracing subscriber of native execution, configured by environment variables
+// of the executor process:
+//  * BLAZE_TRACE: output of traced spans, one of `log`, `chrome` (requires
+//    feature `tracing-chrome`) or `otlp` (requires feature `tracing-otlp`).
+//    tracing is disabled if not set.
+//  * BLAZE_TRACE_FILTER: span filter directives, defaults to `info`.
+//  * BLAZE_TRACE_CHROME_FILE: output file of chrome traces, defaults to
+//    `blaze-trace-{pid}.json` in the working directory.
+// otlp exporter is configured by the standard OTEL_EXPORTER_OTLP_* variables.
+
+use once_cell::sync::OnceCell;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+#[cfg(feature = "tracing-chrome")]
+static CHROME_FLUSH_GUARD: std::sync::Mutex<Option<tracing_chrome::FlushGuard>> =
+    std::sync::Mutex::new(None);
+
+pub fn init_tracing() {
+    static TRACING_INIT: OnceCell<()> = OnceCell::new();
+    TRACING_INIT.get_or_init(|| {
+        let output = match std::env::var("BLAZE_TRACE") {
+            Ok(output) => output,
+            Err(_) => return,
+        };
+        let filter = EnvFilter::try_from_env("BLAZE_TRACE_FILTER")
+            .unwrap_or_else(|_| EnvFilter::new("info"));
+        let registry = tracing_subscriber::registry().with(filter);
+
+        let result = match output.as_str() {
+            "log" => registry
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_writer(std::io::stderr)
+                        .with_span_events(FmtSpan::CLOSE),
+                )
+                .try_init(),
+
+            #[cfg(feature = "tracing-chrome")]
+            "chrome" => {
+                let file = std::env::var("BLAZE_TRACE_CHROME_FILE")
+                    .unwrap_or_else(|_| format!("blaze-trace-{}.json", std::process::id()));
+                let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                    .file(file)
+                    .include_args(true)
+                    .build();
+                *CHROME_FLUSH_GUARD.lock().unwrap() = Some(guard);
+                registry.with(layer).try_init()
+            }
+
+            #[cfg(feature = "tracing-otlp")]
+            "otlp" => {
+                // exporter requires a tokio runtime living through the process
+                static OTLP_RUNTIME: OnceCell<tokio::runtime::Runtime> = OnceCell::new();
+                let rt = OTLP_RUNTIME.get_or_init(|| {
+                    tokio::runtime::Builder::new_multi_thread()
+                        .worker_threads(1)
+                        .thread_name("blaze-otlp")
+                        .enable_all()
+                        .build()
+                        .expect("error creating otlp runtime")
+                });
+                let _rt_guard = rt.enter();
+                let tracer = match opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+                    .install_simple()
+                {
+                    Ok(tracer) => tracer,
+                    Err(err) => {
+                        log::warn!("error creating otlp tracer: {}", err);
+                        return;
+                    }
+                };
+                registry
+                    .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                    .try_init()
+            }
+
+            other => {
+                log::warn!("unsupported tracing output: {}", other);
+                return;
+            }
+        };
+
+        match result {
+            Ok(()) => log::info!("native tracing enabled, output: {}", output),
+            Err(err) => log::warn!("error initializing native tracing: {}", err),
+        }
+    });
+}
+
+/// flushes buffered trace events, called on finishing each task
+pub fn flush_tracing() {
+    #[cfg(feature = "tracing-chrome")]
+    if let Some(guard) = CHROME_FLUSH_GUARD.lock().unwrap().as_ref() {
+        guard.flush();
+    }
+}
@@ -0,0 +1,85 @@
+#![feature(new_uninit)]
+#![feature(io_error_other)]
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+use arrow::array::*;
+use arrow::record_batch::RecordBatch;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use datafusion_ext_commons::io::batch_serde::{read_batch, write_batch};
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::io::Cursor;
+use std::sync::Arc;
+
+const NUM_ROWS: usize = 10000;
+
+// batch with typical columns of shuffled data: ids, amounts, names and
+// nested tags
+fn build_batch() -> RecordBatch {
+    let mut rng = StdRng::seed_from_u64(0);
+    let id: ArrayRef = Arc::new(Int64Array::from_iter_values(0..NUM_ROWS as i64));
+    let amount: ArrayRef = Arc::new(Float64Array::from_iter(
+        (0..NUM_ROWS).map(|_| rng.gen_bool(0.9).then(|| rng.gen_range(0.0..10000.0))),
+    ));
+    let name: ArrayRef = Arc::new(StringArray::from_iter((0..NUM_ROWS).map(|_| {
+        rng.gen_bool(0.9)
+            .then(|| format!("name-{}", rng.gen_range(0..1000)))
+    })));
+    let mut tags = ListBuilder::new(Int32Builder::new());
+    for _ in 0..NUM_ROWS {
+        for _ in 0..rng.gen_range(0..4) {
+            tags.values().append_value(rng.gen_range(0..100));
+        }
+        tags.append(true);
+    }
+    let tags: ArrayRef = Arc::new(tags.finish());
+    RecordBatch::try_from_iter_with_nullable(vec![
+        ("id", id, false),
+        ("amount", amount, true),
+        ("name", name, true),
+        ("tags", tags, true),
+    ])
+    .unwrap()
+}
+
+fn bench_batch_serde(c: &mut Criterion) {
+    let batch = build_batch();
+    let codecs = [
+        ("uncompressed", IpcCompressionCodec::Uncompressed),
+        ("lz4", IpcCompressionCodec::Lz4),
+        ("zstd", IpcCompressionCodec::Zstd(1)),
+    ];
+
+    let mut group = c.benchmark_group("batch_serde");
+    group.throughput(Throughput::Bytes(batch.get_array_memory_size() as u64));
+    for (name, codec) in codecs {
+        group.bench_function(format!("write_batch/{name}"), |b| {
+            let mut buf = vec![];
+            b.iter(|| {
+                buf.clear();
+                write_batch(black_box(&batch), &mut buf, codec, None).unwrap();
+            })
+        });
+
+        let mut buf = vec![];
+        write_batch(&batch, &mut buf, codec, None).unwrap();
+        group.bench_function(format!("read_batch/{name}"), |b| {
+            b.iter(|| read_batch(&mut Cursor::new(black_box(&buf)), codec).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_serde);
+criterion_main!(benches);
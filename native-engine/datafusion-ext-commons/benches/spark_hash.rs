@@ -0,0 +1,63 @@
+#![feature(new_uninit)]
+#![feature(io_error_other)]
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+use arrow::array::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use datafusion_ext_commons::spark_hash::create_hashes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+const NUM_ROWS: usize = 10000;
+
+fn bench_create_hashes(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let int64: ArrayRef = Arc::new(Int64Array::from_iter_values(
+        (0..NUM_ROWS).map(|_| rng.gen::<i64>()),
+    ));
+    let nullable_int32: ArrayRef = Arc::new(Int32Array::from_iter(
+        (0..NUM_ROWS).map(|_| rng.gen_bool(0.9).then(|| rng.gen::<i32>())),
+    ));
+    let utf8: ArrayRef = Arc::new(StringArray::from_iter_values(
+        (0..NUM_ROWS).map(|_| format!("str-{:012}", rng.gen_range(0..1000000))),
+    ));
+    let decimal: ArrayRef = Arc::new(
+        Decimal128Array::from_iter_values((0..NUM_ROWS).map(|_| rng.gen_range(0..1000000000)))
+            .with_precision_and_scale(18, 2)
+            .unwrap(),
+    );
+
+    let mut group = c.benchmark_group("create_hashes");
+    group.throughput(Throughput::Elements(NUM_ROWS as u64));
+    let cases = [
+        ("int64", vec![int64.clone()]),
+        ("nullable_int32", vec![nullable_int32.clone()]),
+        ("utf8", vec![utf8.clone()]),
+        ("decimal128", vec![decimal.clone()]),
+        ("int64_utf8_decimal128", vec![int64, utf8, decimal]),
+    ];
+    for (name, arrays) in cases {
+        let mut hashes = vec![42u32; NUM_ROWS];
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                hashes.fill(42);
+                create_hashes(black_box(&arrays), &mut hashes).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_create_hashes);
+criterion_main!(benches);
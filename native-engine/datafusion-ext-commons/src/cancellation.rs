@@ -0,0 +1,175 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::common::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use once_cell::sync::OnceCell;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
+pub use tokio_util::sync::CancellationToken;
+
+thread_local! {
+    static THREAD_CANCELLATION_TOKEN: RefCell<Option<CancellationToken>> = RefCell::new(None);
+}
+
+/// binds the cancellation token of a task to current thread, called on
+/// starting native worker threads of the task
+pub fn set_thread_cancellation_token(token: CancellationToken) {
+    THREAD_CANCELLATION_TOKEN.with(|t| *t.borrow_mut() = Some(token));
+}
+
+/// returns the cancellation token bound to current thread, or a token which
+/// is never cancelled if current thread does not belong to a task
+pub fn thread_cancellation_token() -> CancellationToken {
+    THREAD_CANCELLATION_TOKEN.with(|t| t.borrow().clone().unwrap_or_default())
+}
+
+/// returns the cancellation token carried in the task context, falls back to
+/// the token bound to current thread
+pub fn task_cancellation_token(context: &TaskContext) -> CancellationToken {
+    context
+        .session_config()
+        .get_extension::<CancellationToken>()
+        .map(|token| token.as_ref().clone())
+        .unwrap_or_else(thread_cancellation_token)
+}
+
+/// deadlines of a task and its operators, carried in the task context. the
+/// first exceeded deadline is recorded before cancelling the task, so that the
+/// task fails with a timeout error instead of a cancellation.
+#[derive(Debug, Default)]
+pub struct TaskDeadline {
+    operator_timeout: Option<Duration>,
+    exceeded: OnceCell<String>,
+}
+
+impl TaskDeadline {
+    pub fn new(operator_timeout: Option<Duration>) -> Self {
+        Self {
+            operator_timeout,
+            exceeded: OnceCell::new(),
+        }
+    }
+
+    /// wall-clock timeout of each operator, from starting to finishing
+    /// producing its output. it is only enforced on operators outputting with
+    /// `output_with_sender()`, streaming operators are polled by their
+    /// consumers and only bounded by the deadline of the whole task.
+    pub fn operator_timeout(&self) -> Option<Duration> {
+        self.operator_timeout
+    }
+
+    /// records an exceeded deadline, only the first one is kept
+    pub fn set_exceeded(&self, message: String) {
+        let _ = self.exceeded.set(message);
+    }
+
+    /// returns the message of the first exceeded deadline
+    pub fn exceeded(&self) -> Option<&str> {
+        self.exceeded.get().map(|message| message.as_str())
+    }
+}
+
+/// returns the deadline carried in the task context, or a deadline which is
+/// never exceeded if absent
+pub fn task_deadline(context: &TaskContext) -> Arc<TaskDeadline> {
+    context
+        .session_config()
+        .get_extension::<TaskDeadline>()
+        .unwrap_or_default()
+}
+
+pub fn cancelled_error() -> DataFusionError {
+    DataFusionError::Execution("task completed/cancelled".to_string())
+}
+
+/// returns an error if the token is cancelled
+pub fn check_cancelled(token: &CancellationToken) -> Result<()> {
+    if token.is_cancelled() {
+        return Err(cancelled_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cancellation::{
+        check_cancelled, set_thread_cancellation_token, task_cancellation_token, task_deadline,
+        thread_cancellation_token, CancellationToken, TaskDeadline,
+    };
+    use datafusion::execution::context::TaskContext;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_task_cancellation_token() {
+        let session = SessionContext::new();
+        let token = CancellationToken::new();
+        let task_context = TaskContext::new(
+            None,
+            session.session_id(),
+            SessionConfig::new().with_extension(Arc::new(token.clone())),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            session.runtime_env(),
+        );
+
+        let task_token = task_cancellation_token(&task_context);
+        assert!(check_cancelled(&task_token).is_ok());
+        token.cancel();
+        assert!(check_cancelled(&task_token).is_err());
+
+        // tokens are bound to threads
+        std::thread::spawn(move || {
+            assert!(!thread_cancellation_token().is_cancelled());
+            set_thread_cancellation_token(token);
+            assert!(thread_cancellation_token().is_cancelled());
+            assert!(task_cancellation_token(&SessionContext::new().task_ctx()).is_cancelled());
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_task_deadline() {
+        let session = SessionContext::new();
+        assert_eq!(task_deadline(&session.task_ctx()).operator_timeout(), None);
+
+        let deadline = Arc::new(TaskDeadline::new(Some(Duration::from_secs(1))));
+        let task_context = TaskContext::new(
+            None,
+            session.session_id(),
+            SessionConfig::new().with_extension(deadline.clone()),
+            HashMap::new(),
+            HashMap::new(),
+            HashMap::new(),
+            session.runtime_env(),
+        );
+        let task_deadline = task_deadline(&task_context);
+        assert_eq!(
+            task_deadline.operator_timeout(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(task_deadline.exceeded(), None);
+
+        // only the first exceeded deadline is kept
+        task_deadline.set_exceeded("operator timed out".to_string());
+        task_deadline.set_exceeded("task timed out".to_string());
+        assert_eq!(deadline.exceeded(), Some("operator timed out"));
+    }
+}
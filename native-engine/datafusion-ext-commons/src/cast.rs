@@ -14,10 +14,11 @@
 
 use arrow::array::*;
 use arrow::datatypes::*;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use bigdecimal::{FromPrimitive, ToPrimitive};
 use datafusion::common::cast::{as_float32_array, as_float64_array};
 use datafusion::common::{DataFusionError, Result};
-use num::{cast::AsPrimitive, Bounded, Integer, Signed};
+use num::{cast::AsPrimitive, Bounded, Integer, Signed, Zero};
 use paste::paste;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -30,6 +31,56 @@ pub fn cast_scan_input_array(array: &dyn Array, cast_type: &DataType) -> Result<
     return cast_impl(array, cast_type, true);
 }
 
+/// returns the schema with dictionary columns replaced by their value types
+pub fn decoded_dictionary_schema(schema: &SchemaRef) -> SchemaRef {
+    if !schema
+        .fields()
+        .iter()
+        .any(|field| matches!(field.data_type(), DataType::Dictionary(_, _)))
+    {
+        return schema.clone();
+    }
+    Arc::new(Schema::new(
+        schema
+            .fields()
+            .iter()
+            .map(|field| match field.data_type() {
+                DataType::Dictionary(_, value_type) => Arc::new(
+                    field
+                        .as_ref()
+                        .clone()
+                        .with_data_type(value_type.as_ref().clone()),
+                ),
+                _ => field.clone(),
+            })
+            .collect::<Fields>(),
+    ))
+}
+
+/// replaces dictionary columns with their values, used for batches exported
+/// to the jvm side, which does not read dictionary arrays
+pub fn decode_dictionary_columns(
+    batch: RecordBatch,
+    decoded_schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(decoded_schema.fields())
+        .map(|(column, field)| {
+            if column.data_type() == field.data_type() {
+                return Ok(column.clone());
+            }
+            cast(column, field.data_type())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new_with_options(
+        decoded_schema.clone(),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    )?)
+}
+
 pub fn cast_impl(
     array: &dyn Array,
     cast_type: &DataType,
@@ -37,6 +88,7 @@ pub fn cast_impl(
 ) -> Result<ArrayRef> {
     Ok(match (&array.data_type(), cast_type) {
         (_, &DataType::Null) => Arc::new(NullArray::new(array.len())),
+        (&DataType::Null, _) => new_null_array(cast_type, array.len()),
 
         // float to int
         (&DataType::Float32, &DataType::Int8) => Arc::new(cast_float_to_integer::<_, Int8Type>(
@@ -71,14 +123,53 @@ pub fn cast_impl(
             // spark compatible string to integer cast
             try_cast_string_array_to_integer(array, cast_type)?
         }
+        (&DataType::Utf8, &DataType::Float32) | (&DataType::Utf8, &DataType::Float64) => {
+            // spark compatible string to float cast
+            try_cast_string_array_to_float(array, cast_type)?
+        }
         (&DataType::Utf8, &DataType::Decimal128(_, _)) => {
             // spark compatible string to decimal cast
             try_cast_string_array_to_decimal(array, cast_type)?
         }
+        (&DataType::Utf8, &DataType::Boolean) => {
+            // spark compatible string to boolean cast
+            try_cast_string_array_to_boolean(array)?
+        }
+        (
+            &DataType::Int8
+            | &DataType::Int16
+            | &DataType::Int32
+            | &DataType::Int64
+            | &DataType::Float32
+            | &DataType::Float64,
+            &DataType::Decimal128(_, _),
+        ) => {
+            // spark compatible numeric to decimal cast
+            try_cast_numeric_array_to_decimal(array, cast_type)?
+        }
         (&DataType::Decimal128(_, _), DataType::Utf8) => {
             // spark compatible decimal to string cast
             try_cast_decimal_array_to_string(array, cast_type)?
         }
+        (&DataType::Timestamp(from_unit, _), &DataType::Timestamp(to_unit, _)) => {
+            // timestamps are utc instants, only units are converted and timezones
+            // are relabeled. finer units are floored, so parquet INT96 timestamps
+            // (read as nanoseconds) are same as spark's julian day + nanos conversion
+            cast_timestamp_unit(array, from_unit, &to_unit, cast_type)?
+        }
+        (&DataType::Decimal128(_, from_scale), &DataType::Decimal128(to_precision, to_scale)) => {
+            // spark compatible decimal rescaling, also used for reading files
+            // written with older decimal types
+            let decimals = as_primitive_array::<Decimal128Type>(array);
+            let from_scale = *from_scale;
+            Arc::new(
+                decimals
+                    .unary_opt::<_, Decimal128Type>(|v| {
+                        rescale_decimal(v, from_scale, to_precision, to_scale)
+                    })
+                    .with_precision_and_scale(to_precision, to_scale)?,
+            )
+        }
         (&DataType::Timestamp(_, _), DataType::Float64) => {
             // timestamp to f64 = timestamp to i64 to f64, only used in agg.sum()
             arrow::compute::cast(
@@ -93,99 +184,69 @@ pub fn cast_impl(
         (&DataType::List(_), DataType::List(to_field)) => {
             let list = as_list_array(array);
             let casted_items = cast_impl(list.values(), to_field.data_type(), match_struct_fields)?;
-            make_array(ArrayData::try_new(
-                DataType::List(to_field.clone()),
-                list.len(),
-                list.nulls().map(|nb| nb.buffer().clone()),
-                list.offset(),
-                list.to_data().buffers().to_vec(),
-                vec![casted_items.into_data()],
+            // nullability of nested fields may be relaxed when matching struct fields
+            let casted_field = to_field
+                .as_ref()
+                .clone()
+                .with_data_type(casted_items.data_type().clone());
+            Arc::new(ListArray::try_new(
+                Arc::new(casted_field),
+                list.offsets().clone(),
+                casted_items,
+                list.nulls().cloned(),
             )?)
         }
         (&DataType::Struct(_), DataType::Struct(to_fields)) => {
             let struct_ = as_struct_array(array);
-
-            if !match_struct_fields {
-                if to_fields.len() != struct_.num_columns() {
-                    return Err(DataFusionError::Execution(
-                        "cannot cast structs with different numbers of fields".to_string(),
-                    ));
-                }
-
-                let casted_arrays = struct_
-                    .columns()
-                    .iter()
-                    .zip(to_fields)
-                    .map(|(column, to_field)| {
-                        cast_impl(column, to_field.data_type(), match_struct_fields)
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-
-                make_array(ArrayData::try_new(
-                    DataType::Struct(to_fields.clone()),
-                    struct_.len(),
-                    struct_.nulls().map(|nb| nb.buffer().clone()),
-                    struct_.offset(),
-                    struct_.to_data().buffers().to_vec(),
-                    casted_arrays
-                        .into_iter()
-                        .map(|array| array.into_data())
-                        .collect(),
-                )?)
-            } else {
-                let mut null_column_name = vec![];
-                let casted_array = to_fields
-                    .iter()
-                    .map(|field: &FieldRef| {
-                        let col = struct_.column_by_name(field.name().as_str());
-                        if col.is_some() {
-                            cast_impl(col.unwrap(), field.data_type(), match_struct_fields)
-                        } else {
-                            null_column_name.push(field.name().clone());
-                            Ok(new_null_array(field.data_type(), struct_.len()))
-                        }
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-
-                let casted_fields = to_fields
-                    .iter()
-                    .map(|field: &FieldRef| {
-                        if null_column_name.contains(field.name()) {
-                            Arc::new(Field::new(field.name(), field.data_type().clone(), true))
-                        } else {
-                            field.clone()
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                make_array(ArrayData::try_new(
-                    DataType::Struct(Fields::from(casted_fields)),
-                    struct_.len(),
-                    struct_.nulls().map(|nb| nb.buffer().clone()),
-                    struct_.offset(),
-                    struct_.to_data().buffers().to_vec(),
-                    casted_array
-                        .into_iter()
-                        .map(|array| array.into_data())
-                        .collect(),
-                )?)
-            }
+            Arc::new(cast_struct(struct_, to_fields, match_struct_fields)?)
         }
         (&DataType::Map(_, _), &DataType::Map(ref to_entries_field, to_sorted)) => {
+            // keys and values are always casted by position, since entry field names
+            // differ between writers (like key/value and keys/values)
             let map = as_map_array(array);
-            let casted_entries = cast_impl(
-                map.entries(),
-                to_entries_field.data_type(),
+            let to_entries_fields = match to_entries_field.data_type() {
+                DataType::Struct(fields) if fields.len() == 2 => fields,
+                other => {
+                    return Err(DataFusionError::Execution(format!(
+                        "invalid map entries type: {other}"
+                    )));
+                }
+            };
+            let casted_keys = cast_impl(
+                map.keys(),
+                to_entries_fields[0].data_type(),
                 match_struct_fields,
             )?;
-
-            make_array(ArrayData::try_new(
-                DataType::Map(to_entries_field.clone(), to_sorted),
-                map.len(),
-                map.nulls().map(|nb| nb.buffer().clone()),
-                map.offset(),
-                map.to_data().buffers().to_vec(),
-                vec![casted_entries.into_data()],
+            let casted_values = cast_impl(
+                map.values(),
+                to_entries_fields[1].data_type(),
+                match_struct_fields,
+            )?;
+            let casted_entries_fields = Fields::from(vec![
+                to_entries_fields[0]
+                    .as_ref()
+                    .clone()
+                    .with_data_type(casted_keys.data_type().clone()),
+                to_entries_fields[1]
+                    .as_ref()
+                    .clone()
+                    .with_data_type(casted_values.data_type().clone()),
+            ]);
+            let casted_entries_field = to_entries_field
+                .as_ref()
+                .clone()
+                .with_data_type(DataType::Struct(casted_entries_fields.clone()));
+            let casted_entries = StructArray::try_new(
+                casted_entries_fields,
+                vec![casted_keys, casted_values],
+                None,
+            )?;
+            Arc::new(MapArray::try_new(
+                Arc::new(casted_entries_field),
+                map.offsets().clone(),
+                casted_entries,
+                map.nulls().cloned(),
+                to_sorted,
             )?)
         }
         _ => {
@@ -195,6 +256,103 @@ pub fn cast_impl(
     })
 }
 
+/// casts struct fields recursively, by position (spark's struct cast), or by
+/// name (schema evolution of scan input) with missing fields filled with nulls
+fn cast_struct(
+    struct_: &StructArray,
+    to_fields: &Fields,
+    match_struct_fields: bool,
+) -> Result<StructArray> {
+    if !match_struct_fields {
+        if to_fields.len() != struct_.num_columns() {
+            return Err(DataFusionError::Execution(
+                "cannot cast structs with different numbers of fields".to_string(),
+            ));
+        }
+        let casted_columns = struct_
+            .columns()
+            .iter()
+            .zip(to_fields)
+            .map(|(column, to_field)| cast_impl(column, to_field.data_type(), match_struct_fields))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(StructArray::try_new(
+            to_fields.clone(),
+            casted_columns,
+            struct_.nulls().cloned(),
+        )?);
+    }
+
+    let mut casted_fields = vec![];
+    let mut casted_columns = vec![];
+    for to_field in to_fields {
+        match struct_.column_by_name(to_field.name()) {
+            Some(column) => {
+                casted_fields.push(to_field.clone());
+                casted_columns.push(cast_impl(
+                    column,
+                    to_field.data_type(),
+                    match_struct_fields,
+                )?);
+            }
+            None => {
+                casted_fields.push(Arc::new(to_field.as_ref().clone().with_nullable(true)));
+                casted_columns.push(new_null_array(to_field.data_type(), struct_.len()));
+            }
+        }
+    }
+    Ok(StructArray::try_new(
+        Fields::from(casted_fields),
+        casted_columns,
+        struct_.nulls().cloned(),
+    )?)
+}
+
+fn cast_timestamp_unit(
+    array: &dyn Array,
+    from_unit: &TimeUnit,
+    to_unit: &TimeUnit,
+    cast_type: &DataType,
+) -> Result<ArrayRef> {
+    fn unit_nanos(unit: &TimeUnit) -> i64 {
+        match unit {
+            TimeUnit::Second => 1_000_000_000,
+            TimeUnit::Millisecond => 1_000_000,
+            TimeUnit::Microsecond => 1_000,
+            TimeUnit::Nanosecond => 1,
+        }
+    }
+    let values = arrow::compute::cast(array, &DataType::Int64)?;
+    let values = as_primitive_array::<Int64Type>(&values);
+    let (from_nanos, to_nanos) = (unit_nanos(from_unit), unit_nanos(to_unit));
+    let casted: Int64Array = if from_nanos <= to_nanos {
+        values.unary(|v| v.div_euclid(to_nanos / from_nanos))
+    } else {
+        values.unary_opt(|v| v.checked_mul(from_nanos / to_nanos))
+    };
+    Ok(arrow::compute::cast(&casted, cast_type)?)
+}
+
+// rescales with ROUND_HALF_UP and returns None if the value overflows the
+// precision, same as spark's Decimal.changePrecision()
+fn rescale_decimal(value: i128, from_scale: i8, to_precision: u8, to_scale: i8) -> Option<i128> {
+    let rescaled = if to_scale >= from_scale {
+        value.checked_mul(10i128.checked_pow((to_scale as i32 - from_scale as i32) as u32)?)?
+    } else {
+        match 10i128.checked_pow((from_scale as i32 - to_scale as i32) as u32) {
+            Some(divisor) => {
+                let (quotient, remainder) = (value / divisor, value % divisor);
+                if remainder.unsigned_abs() * 2 >= divisor as u128 {
+                    quotient + value.signum()
+                } else {
+                    quotient
+                }
+            }
+            None => 0, // divisor is larger than any decimal128 value
+        }
+    };
+    Some(rescaled).filter(|&v| fits_decimal_precision(v, to_precision))
+}
+
 fn try_cast_string_array_to_integer(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     macro_rules! cast {
         ($target_type:ident) => {{
@@ -244,6 +402,67 @@ fn try_cast_string_array_to_decimal(array: &dyn Array, cast_type: &DataType) ->
     unreachable!("cast_type must be DataType::Decimal")
 }
 
+fn try_cast_string_array_to_float(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    Ok(match cast_type {
+        DataType::Float32 => Arc::new(
+            array
+                .iter()
+                .map(|v| v.and_then(to_float::<f32>))
+                .collect::<Float32Array>(),
+        ),
+        DataType::Float64 => Arc::new(
+            array
+                .iter()
+                .map(|v| v.and_then(to_float::<f64>))
+                .collect::<Float64Array>(),
+        ),
+        _ => unreachable!("cast_type must be DataType::Float32/Float64"),
+    })
+}
+
+fn try_cast_string_array_to_boolean(array: &dyn Array) -> Result<ArrayRef> {
+    let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+    Ok(Arc::new(
+        array
+            .iter()
+            .map(|v| v.and_then(to_boolean))
+            .collect::<BooleanArray>(),
+    ))
+}
+
+fn try_cast_numeric_array_to_decimal(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
+    macro_rules! cast_integer {
+        ($from_type:ident, $precision:expr, $scale:expr) => {{
+            as_primitive_array::<$from_type>(array).unary_opt::<_, Decimal128Type>(|v| {
+                integer_to_decimal(v.into(), $precision, $scale)
+            })
+        }};
+    }
+    macro_rules! cast_float {
+        ($from_type:ident, $precision:expr, $scale:expr) => {{
+            as_primitive_array::<$from_type>(array)
+                .unary_opt::<_, Decimal128Type>(|v| float_to_decimal(v.into(), $precision, $scale))
+        }};
+    }
+
+    if let &DataType::Decimal128(precision, scale) = cast_type {
+        let decimals = match array.data_type() {
+            DataType::Int8 => cast_integer!(Int8Type, precision, scale),
+            DataType::Int16 => cast_integer!(Int16Type, precision, scale),
+            DataType::Int32 => cast_integer!(Int32Type, precision, scale),
+            DataType::Int64 => cast_integer!(Int64Type, precision, scale),
+            DataType::Float32 => cast_float!(Float32Type, precision, scale),
+            DataType::Float64 => cast_float!(Float64Type, precision, scale),
+            _ => unreachable!("array must be integer or float"),
+        };
+        return Ok(Arc::new(
+            decimals.with_precision_and_scale(precision, scale)?,
+        ));
+    }
+    unreachable!("cast_type must be DataType::Decimal")
+}
+
 fn try_cast_decimal_array_to_string(array: &dyn Array, cast_type: &DataType) -> Result<ArrayRef> {
     if let &DataType::Utf8 = cast_type {
         let array = array.as_any().downcast_ref::<Decimal128Array>().unwrap();
@@ -284,7 +503,7 @@ where
 
 // this implementation is original copied from spark UTF8String.scala
 fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(input: &str) -> Option<T> {
-    let bytes = input.as_bytes();
+    let bytes = trim_spark_whitespaces(input).as_bytes();
 
     if bytes.is_empty() {
         return None;
@@ -358,20 +577,112 @@ fn to_integer<T: Bounded + FromPrimitive + Integer + Signed + Copy>(input: &str)
 }
 
 fn to_decimal(input: &str, precision: u8, scale: i8) -> Option<i128> {
-    let precision = precision as u64;
+    let decimal = bigdecimal::BigDecimal::from_str(trim_spark_whitespaces(input)).ok()?;
+    bigdecimal_to_decimal(decimal, precision, scale)
+}
+
+fn integer_to_decimal(value: i128, precision: u8, scale: i8) -> Option<i128> {
+    if scale < 0 {
+        let decimal = bigdecimal::BigDecimal::new(value.into(), 0);
+        return bigdecimal_to_decimal(decimal, precision, scale);
+    }
+    value
+        .checked_mul(10i128.checked_pow(scale as u32)?)
+        .filter(|&v| fits_decimal_precision(v, precision))
+}
+
+fn float_to_decimal(value: f64, precision: u8, scale: i8) -> Option<i128> {
+    if !value.is_finite() {
+        return None;
+    }
+    // spark converts doubles to decimals by their shortest string representations
+    let decimal = bigdecimal::BigDecimal::from_str(&value.to_string()).ok()?;
+    bigdecimal_to_decimal(decimal, precision, scale)
+}
+
+// converts to unscaled value with the same rounding/overflow behavior as
+// rescale_decimal()
+fn bigdecimal_to_decimal(
+    decimal: bigdecimal::BigDecimal,
+    precision: u8,
+    scale: i8,
+) -> Option<i128> {
+    let (digits, exp) = decimal.as_bigint_and_exponent(); // value = digits * 10^(-exp)
     let scale = scale as i64;
-    bigdecimal::BigDecimal::from_str(input)
-        .ok()
-        .map(|decimal| decimal.with_prec(precision).with_scale(scale))
-        .and_then(|decimal| {
-            let (bigint, _exp) = decimal.as_bigint_and_exponent();
-            bigint.to_i128()
-        })
+    if digits.is_zero() {
+        return Some(0);
+    }
+
+    let unscaled = if exp <= scale {
+        let shift = scale - exp;
+        if shift > 38 {
+            return None;
+        }
+        digits * num::BigInt::from(10).pow(shift as u32)
+    } else {
+        let shift = exp - scale;
+        if shift > digits.to_string().len() as i64 {
+            return Some(0); // |value| < 0.1 after rescaling
+        }
+        let divisor = num::BigInt::from(10).pow(shift as u32);
+        let (quotient, remainder) = digits.div_rem(&divisor);
+        if remainder.abs() * num::BigInt::from(2) >= divisor {
+            quotient + digits.signum()
+        } else {
+            quotient
+        }
+    };
+    unscaled
+        .to_i128()
+        .filter(|&v| fits_decimal_precision(v, precision))
+}
+
+fn fits_decimal_precision(unscaled: i128, precision: u8) -> bool {
+    unscaled.unsigned_abs() < 10u128.pow(precision as u32)
+}
+
+// supports special literals (like spark's Cast) and trailing d/f type suffixes
+// (like java's Double.parseDouble())
+fn to_float<T: num::Float + FromStr>(input: &str) -> Option<T> {
+    let input = trim_spark_whitespaces(input);
+    match input.to_ascii_lowercase().as_str() {
+        "inf" | "+inf" | "infinity" | "+infinity" => return Some(T::infinity()),
+        "-inf" | "-infinity" => return Some(T::neg_infinity()),
+        "nan" => return Some(T::nan()),
+        _ => {}
+    }
+
+    let input = input
+        .strip_suffix(|c: char| matches!(c, 'd' | 'D' | 'f' | 'F'))
+        .unwrap_or(input);
+    if input.is_empty()
+        || !input
+            .bytes()
+            .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+    {
+        return None;
+    }
+    input.parse().ok()
+}
+
+// same as spark's StringUtils.isTrueString/isFalseString
+fn to_boolean(input: &str) -> Option<bool> {
+    match trim_spark_whitespaces(input).to_ascii_lowercase().as_str() {
+        "t" | "true" | "y" | "yes" | "1" => Some(true),
+        "f" | "false" | "n" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+// trims whitespaces and ISO control characters, same as spark's UTF8String.trimAll()
+fn trim_spark_whitespaces(input: &str) -> &str {
+    input.trim_matches(|c: char| c <= ' ' || c == '\x7f')
 }
 
 #[cfg(test)]
 mod test {
     use crate::cast::*;
+    use arrow::buffer::{NullBuffer, OffsetBuffer};
     use datafusion::common::cast::as_int32_array;
 
     #[test]
@@ -403,4 +714,375 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn test_int96_nanos_to_micros() {
+        let nanos_array: ArrayRef = Arc::new(TimestampNanosecondArray::from_iter(vec![
+            None,
+            Some(1_600_000_000_123_456_789),
+            Some(-1_999),
+            Some(-1_000),
+            Some(0),
+        ]));
+        let casted = cast_scan_input_array(
+            &nanos_array,
+            &DataType::Timestamp(TimeUnit::Microsecond, None),
+        )
+        .unwrap();
+        let micros_array = as_primitive_array::<TimestampMicrosecondType>(&casted);
+
+        assert_eq!(
+            micros_array,
+            &TimestampMicrosecondArray::from_iter(vec![
+                None,
+                Some(1_600_000_000_123_456),
+                Some(-2),
+                Some(-1),
+                Some(0),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_decode_dictionary_columns() {
+        let dict_array: ArrayRef = Arc::new(
+            vec![Some("a"), None, Some("a")]
+                .into_iter()
+                .collect::<DictionaryArray<Int32Type>>(),
+        );
+        let int_array: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let batch =
+            RecordBatch::try_from_iter(vec![("dict", dict_array), ("int", int_array.clone())])
+                .unwrap();
+
+        let decoded_schema = decoded_dictionary_schema(&batch.schema());
+        assert_eq!(decoded_schema.field(0).data_type(), &DataType::Utf8);
+        assert_eq!(decoded_schema.field(1).data_type(), &DataType::Int32);
+
+        let decoded = decode_dictionary_columns(batch, &decoded_schema).unwrap();
+        assert_eq!(
+            decoded.column(0).as_ref(),
+            &StringArray::from(vec![Some("a"), None, Some("a")]) as &dyn Array
+        );
+        assert_eq!(decoded.column(1), &int_array);
+    }
+
+    #[test]
+    fn test_string_to_numeric() {
+        // expected values are outputs of spark's try_cast
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some(" 123 "),
+            Some("\t-45.67\n"),
+            Some("+8"),
+            Some("1e2"),
+            Some("2.5d"),
+            Some("-Infinity"),
+            Some("NaN"),
+            Some("1 2"),
+            Some("abc"),
+            Some(""),
+        ]));
+
+        let casted = cast(&string_array, &DataType::Int32).unwrap();
+        assert_eq!(
+            as_int32_array(&casted).unwrap(),
+            &Int32Array::from_iter(vec![
+                None,
+                Some(123),
+                Some(-45),
+                Some(8),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ])
+        );
+
+        let casted = cast(&string_array, &DataType::Float64).unwrap();
+        let f64_array = as_float64_array(&casted).unwrap();
+        assert_eq!(
+            f64_array.iter().take(7).collect::<Vec<_>>(),
+            vec![
+                None,
+                Some(123.0),
+                Some(-45.67),
+                Some(8.0),
+                Some(100.0),
+                Some(2.5),
+                Some(f64::NEG_INFINITY),
+            ]
+        );
+        assert!(f64_array.value(7).is_nan());
+        assert_eq!(f64_array.null_count(), 4);
+
+        let casted = cast(&string_array, &DataType::Decimal128(5, 1)).unwrap();
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&casted),
+            &Decimal128Array::from_iter(vec![
+                None,
+                Some(1230),
+                Some(-457),
+                Some(80),
+                Some(1000),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ])
+            .with_precision_and_scale(5, 1)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_string_to_boolean() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("true"),
+            Some(" T "),
+            Some("Yes"),
+            Some("1"),
+            Some("FALSE"),
+            Some("n"),
+            Some("0"),
+            Some("2"),
+            Some("truee"),
+        ]));
+        let casted = cast(&string_array, &DataType::Boolean).unwrap();
+        assert_eq!(
+            as_boolean_array(&casted),
+            &BooleanArray::from(vec![
+                None,
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                None,
+                None,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_numeric_to_decimal() {
+        let i64_array: ArrayRef = Arc::new(Int64Array::from(vec![
+            None,
+            Some(123),
+            Some(-999),
+            Some(1000),
+            Some(i64::MAX),
+        ]));
+        let casted = cast(&i64_array, &DataType::Decimal128(5, 2)).unwrap();
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&casted),
+            &Decimal128Array::from_iter(vec![None, Some(12300), Some(-99900), None, None])
+                .with_precision_and_scale(5, 2)
+                .unwrap()
+        );
+
+        let f64_array: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(1.005),
+            Some(-2.345),
+            Some(0.004),
+            Some(1000.0),
+            Some(f64::NAN),
+            Some(f64::INFINITY),
+        ]));
+        let casted = cast(&f64_array, &DataType::Decimal128(5, 2)).unwrap();
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&casted),
+            &Decimal128Array::from_iter(vec![
+                None,
+                Some(101),
+                Some(-235),
+                Some(0),
+                None,
+                None,
+                None
+            ])
+            .with_precision_and_scale(5, 2)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nested_cast() {
+        // list<struct<a: int, b: string>>, with the first row sliced off
+        let struct_fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let structs = StructArray::new(
+            struct_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["1", "x", "3", "4"])),
+            ],
+            None,
+        );
+        let item_field = Arc::new(Field::new("item", DataType::Struct(struct_fields), true));
+        let list = ListArray::new(
+            item_field,
+            OffsetBuffer::new(vec![0, 1, 1, 3, 4].into()),
+            Arc::new(structs),
+            Some(NullBuffer::from(vec![true, false, true, true])),
+        );
+        let list: ArrayRef = Arc::new(list.slice(1, 3));
+
+        // by position: struct<x: string, y: int>
+        let to_struct_fields = Fields::from(vec![
+            Field::new("x", DataType::Utf8, true),
+            Field::new("y", DataType::Int32, true),
+        ]);
+        let to_type = DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(to_struct_fields.clone()),
+            true,
+        )));
+        let casted = cast(&list, &to_type).unwrap();
+        let casted_list = as_list_array(&casted);
+        assert_eq!(casted_list.len(), 3);
+        assert!(casted_list.is_null(0));
+        let casted_items = casted_list.value(1);
+        let casted_structs = as_struct_array(&casted_items);
+        assert_eq!(
+            casted_structs.column(0).as_ref(),
+            &StringArray::from(vec!["2", "3"]) as &dyn Array
+        );
+        assert_eq!(
+            casted_structs.column(1).as_ref(),
+            &Int32Array::from(vec![None, Some(3)]) as &dyn Array
+        );
+
+        // by name: struct<b: string, c: int>, missing field c is filled with nulls
+        let to_struct_fields = Fields::from(vec![
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Int32, false),
+        ]);
+        let to_type = DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(to_struct_fields),
+            true,
+        )));
+        let casted = cast_scan_input_array(&list, &to_type).unwrap();
+        let casted_list = as_list_array(&casted);
+        let casted_items = casted_list.value(2);
+        let casted_structs = as_struct_array(&casted_items);
+        assert_eq!(
+            casted_structs.column(0).as_ref(),
+            &StringArray::from(vec!["4"]) as &dyn Array
+        );
+        assert_eq!(casted_structs.column(1).null_count(), 1);
+
+        // map<string, int> with entries named keys/values => map<string, string>
+        let map: ArrayRef = Arc::new(
+            MapArray::new_from_strings(
+                ["k1", "k2", "k3"].into_iter(),
+                &Int32Array::from(vec![1, 2, 3]),
+                &[0, 2, 3],
+            )
+            .unwrap(),
+        );
+        let to_type = DataType::Map(
+            Arc::new(Field::new(
+                "entries",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("key", DataType::Utf8, false),
+                    Field::new("value", DataType::Utf8, true),
+                ])),
+                false,
+            )),
+            false,
+        );
+        let casted = cast_scan_input_array(&map, &to_type).unwrap();
+        let casted_map = as_map_array(&casted);
+        assert_eq!(casted_map.data_type(), &to_type);
+        assert_eq!(
+            casted_map.values().as_ref(),
+            &StringArray::from(vec!["1", "2", "3"]) as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_scan_input_schema_adaptation() {
+        // decimal(5, 3) => decimal(5, 1) with half-up rounding
+        let decimal_array: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![Some(12345), Some(-12355), Some(49), None])
+                .with_precision_and_scale(5, 3)
+                .unwrap(),
+        );
+        let casted = cast_scan_input_array(&decimal_array, &DataType::Decimal128(5, 1)).unwrap();
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&casted),
+            &Decimal128Array::from(vec![Some(123), Some(-124), Some(0), None])
+                .with_precision_and_scale(5, 1)
+                .unwrap()
+        );
+
+        // decimal(5, 3) => decimal(4, 2), decimal(4, 3) overflows
+        let casted = cast_scan_input_array(&decimal_array, &DataType::Decimal128(4, 2)).unwrap();
+        assert_eq!(
+            as_primitive_array::<Decimal128Type>(&casted),
+            &Decimal128Array::from(vec![Some(1235), Some(-1236), Some(5), None])
+                .with_precision_and_scale(4, 2)
+                .unwrap()
+        );
+        let casted = cast_scan_input_array(&decimal_array, &DataType::Decimal128(4, 3)).unwrap();
+        assert_eq!(casted.null_count(), 3);
+
+        // timestamp(ms) => timestamp(us, UTC)
+        let millis_array: ArrayRef = Arc::new(TimestampMillisecondArray::from(vec![
+            Some(1_600_000_000_123),
+            Some(-1),
+            None,
+        ]));
+        let to_type = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+        let casted = cast_scan_input_array(&millis_array, &to_type).unwrap();
+        assert_eq!(casted.data_type(), &to_type);
+        assert_eq!(
+            as_primitive_array::<TimestampMicrosecondType>(&casted)
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(1_600_000_000_123_000), Some(-1_000), None]
+        );
+
+        // list<struct<a: int>> => list<struct<a: bigint, b: double>>
+        let structs = StructArray::from(vec![(
+            Arc::new(Field::new("a", DataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )]);
+        let list: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new("item", structs.data_type().clone(), true)),
+            OffsetBuffer::new(vec![0, 2, 3].into()),
+            Arc::new(structs),
+            None,
+        ));
+        let to_type = DataType::List(Arc::new(Field::new(
+            "item",
+            DataType::Struct(Fields::from(vec![
+                Field::new("a", DataType::Int64, true),
+                Field::new("b", DataType::Float64, true),
+            ])),
+            true,
+        )));
+        let casted = cast_scan_input_array(&list, &to_type).unwrap();
+        assert_eq!(casted.data_type(), &to_type);
+        let casted_structs = as_list_array(&casted).values().clone();
+        let casted_structs = as_struct_array(&casted_structs);
+        assert_eq!(
+            casted_structs.column(0).as_ref(),
+            &Int64Array::from(vec![1, 2, 3]) as &dyn Array
+        );
+        assert_eq!(casted_structs.column(1).null_count(), 3);
+    }
 }
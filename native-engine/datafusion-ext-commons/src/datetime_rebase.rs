@@ -0,0 +1,278 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! spark compatible rebasing of dates/timestamps between the hybrid julian +
+//! gregorian calendar (used by spark 2.x and hive) and the proleptic gregorian
+//! calendar (used by spark 3.x and arrow).
+//!
+//! timestamps are rebased by their utc dates, which is the same as spark when
+//! the session time zone is UTC.
+
+use arrow::array::*;
+use arrow::compute::cast;
+use arrow::datatypes::*;
+use datafusion::common::Result;
+use std::sync::Arc;
+
+/// first day of the gregorian calendar (1582-10-15) in days since epoch, dates
+/// before it are different in the hybrid calendar.
+pub const GREGORIAN_START_DAY: i32 = -141427;
+
+/// 1900-01-01T00:00:00Z in seconds since epoch, spark considers timestamps before
+/// it ambiguous when reading/writing in EXCEPTION mode.
+pub const LAST_SWITCH_TS_SECONDS: i64 = -2208988800;
+
+const JULIAN_DAY_OF_EPOCH: i64 = 2440588;
+
+/// rebase mode of dates/timestamps in parquet files, like spark's
+/// LegacyBehaviorPolicy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseMode {
+    /// fails if there are ambiguous dates/timestamps
+    Exception,
+    /// reads/writes values as is
+    Corrected,
+    /// rebases values between the hybrid and proleptic gregorian calendars
+    Legacy,
+}
+
+/// converts days in the hybrid calendar to days in the proleptic gregorian
+/// calendar with the same local date.
+pub fn rebase_julian_to_gregorian_days(days: i32) -> i32 {
+    if days >= GREGORIAN_START_DAY {
+        return days;
+    }
+    let (year, month, day) = julian_date_from_days(days as i64);
+    gregorian_days_from_date(year, month, day) as i32
+}
+
+/// converts days in the proleptic gregorian calendar to days in the hybrid
+/// calendar with the same local date.
+pub fn rebase_gregorian_to_julian_days(days: i32) -> i32 {
+    if days >= GREGORIAN_START_DAY {
+        return days;
+    }
+    let (year, month, day) = gregorian_date_from_days(days as i64);
+    julian_days_from_date(year, month, day) as i32
+}
+
+/// rebases all date/timestamp values in the array (including nested values)
+/// from the hybrid calendar to the proleptic gregorian calendar.
+pub fn rebase_julian_to_gregorian_array(array: &ArrayRef) -> Result<ArrayRef> {
+    map_datetime_values(array, &rebase_julian_to_gregorian_days)
+}
+
+/// rebases all date/timestamp values in the array (including nested values)
+/// from the proleptic gregorian calendar to the hybrid calendar.
+pub fn rebase_gregorian_to_julian_array(array: &ArrayRef) -> Result<ArrayRef> {
+    map_datetime_values(array, &rebase_gregorian_to_julian_days)
+}
+
+/// returns true if the array contains dates before 1582-10-15 or timestamps
+/// before 1900-01-01T00:00:00Z, which are ambiguous between calendars.
+pub fn has_ancient_datetime(array: &dyn Array) -> bool {
+    match array.data_type() {
+        DataType::Date32 => as_primitive_array::<Date32Type>(array)
+            .iter()
+            .flatten()
+            .any(|days| days < GREGORIAN_START_DAY),
+        DataType::Timestamp(unit, _) => {
+            let threshold = LAST_SWITCH_TS_SECONDS * units_per_second(unit);
+            let ts = cast(array, &DataType::Int64).expect("timestamp to int64");
+            as_primitive_array::<Int64Type>(&ts)
+                .iter()
+                .flatten()
+                .any(|ts| ts < threshold)
+        }
+        _ => array
+            .to_data()
+            .child_data()
+            .iter()
+            .any(|child| has_ancient_datetime(&make_array(child.clone()))),
+    }
+}
+
+/// returns true if the data type contains dates/timestamps.
+pub fn contains_datetime(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Date32 | DataType::Timestamp(_, _) => true,
+        DataType::List(field) | DataType::LargeList(field) | DataType::Map(field, _) => {
+            contains_datetime(field.data_type())
+        }
+        DataType::Struct(fields) => fields.iter().any(|f| contains_datetime(f.data_type())),
+        _ => false,
+    }
+}
+
+fn map_datetime_values(array: &ArrayRef, rebase_days: &dyn Fn(i32) -> i32) -> Result<ArrayRef> {
+    Ok(match array.data_type() {
+        DataType::Date32 => Arc::new(
+            as_primitive_array::<Date32Type>(array)
+                .unary::<_, Date32Type>(|days| rebase_days(days)),
+        ),
+        DataType::Timestamp(unit, _) => {
+            // rebase the date part and keep the time of the day
+            let units_per_day = units_per_second(unit) * 86400;
+            let rebase_ts = |ts: i64| {
+                let days = ts.div_euclid(units_per_day);
+                let time_of_day = ts.rem_euclid(units_per_day);
+                rebase_days(days as i32) as i64 * units_per_day + time_of_day
+            };
+            let ts = cast(array, &DataType::Int64)?;
+            let rebased = as_primitive_array::<Int64Type>(&ts).unary::<_, Int64Type>(rebase_ts);
+            cast(&rebased, array.data_type())?
+        }
+        data_type if contains_datetime(data_type) => {
+            let data = array.to_data();
+            let child_data = data
+                .child_data()
+                .iter()
+                .map(|child| {
+                    Ok(map_datetime_values(&make_array(child.clone()), rebase_days)?.into_data())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            make_array(data.into_builder().child_data(child_data).build()?)
+        }
+        _ => array.clone(),
+    })
+}
+
+fn units_per_second(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1000,
+        TimeUnit::Microsecond => 1000000,
+        TimeUnit::Nanosecond => 1000000000,
+    }
+}
+
+fn julian_date_from_days(days: i64) -> (i64, i64, i64) {
+    let c = days + JULIAN_DAY_OF_EPOCH + 32082;
+    let d = (4 * c + 3).div_euclid(1461);
+    let e = c - (1461 * d).div_euclid(4);
+    let m = (5 * e + 2).div_euclid(153);
+    let day = e - (153 * m + 2).div_euclid(5) + 1;
+    let month = m + 3 - 12 * m.div_euclid(10);
+    let year = d - 4800 + m.div_euclid(10);
+    (year, month, day)
+}
+
+fn julian_days_from_date(year: i64, month: i64, day: i64) -> i64 {
+    let a = (14 - month).div_euclid(12);
+    let y = year + 4800 - a;
+    let m = month + 12 * a - 3;
+    let jdn = day + (153 * m + 2).div_euclid(5) + 365 * y + y.div_euclid(4) - 32083;
+    jdn - JULIAN_DAY_OF_EPOCH
+}
+
+fn gregorian_date_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+// invalid dates (like 1000-02-29) are shifted to the following valid dates,
+// same as spark
+fn gregorian_days_from_date(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod test {
+    use crate::datetime_rebase::*;
+    use arrow::buffer::OffsetBuffer;
+
+    #[test]
+    fn test_rebase_days() {
+        // 1582-10-15 is the same in both calendars
+        assert_eq!(rebase_julian_to_gregorian_days(-141427), -141427);
+        assert_eq!(rebase_gregorian_to_julian_days(-141427), -141427);
+
+        // julian 1582-10-04 is the day before 1582-10-15
+        assert_eq!(rebase_julian_to_gregorian_days(-141428), -141438);
+        assert_eq!(rebase_gregorian_to_julian_days(-141438), -141428);
+
+        // 0001-01-01 is -719162 days in the proleptic gregorian calendar, and
+        // -719164 days in the hybrid calendar
+        assert_eq!(rebase_julian_to_gregorian_days(-719164), -719162);
+        assert_eq!(rebase_gregorian_to_julian_days(-719162), -719164);
+
+        // julian 1000-02-29 doesn't exist in the gregorian calendar, shifted to 1000-03-01
+        let julian_1000_02_29 = julian_days_from_date(1000, 2, 29) as i32;
+        assert_eq!(
+            rebase_julian_to_gregorian_days(julian_1000_02_29),
+            gregorian_days_from_date(1000, 3, 1) as i32,
+        );
+
+        for days in -800000..GREGORIAN_START_DAY {
+            let (y, m, d) = gregorian_date_from_days(days as i64);
+            assert_eq!(gregorian_days_from_date(y, m, d), days as i64);
+            let (y, m, d) = julian_date_from_days(days as i64);
+            assert_eq!(julian_days_from_date(y, m, d), days as i64);
+        }
+    }
+
+    #[test]
+    fn test_rebase_array() -> Result<()> {
+        let micros_per_day = 86400 * 1000000;
+        let ts: ArrayRef = Arc::new(TimestampMicrosecondArray::from(vec![
+            Some(-719164 * micros_per_day + 3600 * 1000000),
+            None,
+            Some(0),
+        ]));
+        let rebased = rebase_julian_to_gregorian_array(&ts)?;
+        assert_eq!(
+            as_primitive_array::<TimestampMicrosecondType>(&rebased),
+            &TimestampMicrosecondArray::from(vec![
+                Some(-719162 * micros_per_day + 3600 * 1000000),
+                None,
+                Some(0),
+            ]),
+        );
+        assert_eq!(
+            rebase_gregorian_to_julian_array(&rebased)?.as_ref(),
+            ts.as_ref()
+        );
+        assert!(has_ancient_datetime(&ts));
+        assert!(!has_ancient_datetime(&ts.slice(1, 2)));
+
+        let dates = Arc::new(Date32Array::from(vec![Some(-719164), Some(0)]));
+        let list: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new("item", DataType::Date32, true)),
+            OffsetBuffer::new(vec![0, 1, 2].into()),
+            dates,
+            None,
+        ));
+        let rebased = rebase_julian_to_gregorian_array(&list)?;
+        assert_eq!(
+            as_primitive_array::<Date32Type>(as_list_array(&rebased).values()),
+            &Date32Array::from(vec![Some(-719162), Some(0)]),
+        );
+        assert!(has_ancient_datetime(&list));
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// error context is formatted as `{operator} {key}={value} ...: {error}`, like
+// `ParquetScan file=hdfs://a.parquet batch=3: ...`, so failures can be
+// traced back to the operator, input file or expression causing them.
+
+use arrow::error::ArrowError;
+use datafusion::common::{DataFusionError, Result};
+use std::fmt::Display;
+
+/// context of an error, built from a description (usually the operator name)
+/// and key-value pairs
+#[derive(Clone, Debug)]
+pub struct ErrorContext {
+    desc: String,
+}
+
+impl ErrorContext {
+    pub fn new(desc: &str) -> Self {
+        Self {
+            desc: desc.to_string(),
+        }
+    }
+
+    pub fn with(mut self, key: &str, value: impl Display) -> Self {
+        self.desc.push_str(&format!(" {}={}", key, value));
+        self
+    }
+
+    pub fn wrap(&self, err: DataFusionError) -> DataFusionError {
+        DataFusionError::Execution(format!("{}: {}", self.desc, err))
+    }
+
+    pub fn wrap_arrow(&self, err: ArrowError) -> ArrowError {
+        ArrowError::ExternalError(Box::new(self.wrap(DataFusionError::ArrowError(err))))
+    }
+}
+
+pub trait WithErrorContext<T> {
+    /// wraps the error with context, the context is built only on errors
+    fn with_error_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T>;
+}
+
+impl<T> WithErrorContext<T> for Result<T> {
+    fn with_error_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T> {
+        self.map_err(|err| context().wrap(err))
+    }
+}
+
+impl<T> WithErrorContext<T> for std::result::Result<T, ArrowError> {
+    fn with_error_context(self, context: impl FnOnce() -> ErrorContext) -> Result<T> {
+        self.map_err(|err| context().wrap(DataFusionError::ArrowError(err)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error_context::{ErrorContext, WithErrorContext};
+    use arrow::error::ArrowError;
+    use datafusion::common::{DataFusionError, Result};
+
+    #[test]
+    fn test_error_context() {
+        let result: Result<()> = Err(DataFusionError::Execution("bad data".to_string()));
+        let err = result
+            .with_error_context(|| {
+                ErrorContext::new("ParquetScan")
+                    .with("file", "a.parquet")
+                    .with("batch", 3)
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: ParquetScan file=a.parquet batch=3: Execution error: bad data"
+        );
+
+        let err = ErrorContext::new("Filter")
+            .with("expr", "a > 1")
+            .wrap_arrow(ArrowError::ComputeError("overflow".to_string()));
+        assert!(err
+            .to_string()
+            .contains("Filter expr=a > 1: Arrow error: Compute error: overflow"));
+    }
+}
@@ -16,100 +16,605 @@ use blaze_jni_bridge::{
     jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref, jni_new_object,
     jni_new_string,
 };
-use datafusion::error::Result;
+use datafusion::error::{DataFusionError, Result};
 use datafusion::physical_plan::metrics::Time;
 use jni::objects::{GlobalRef, JObject};
+use std::io::Write;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// retry policy of filesystem operations, operations failed with transient
+/// errors (like datanode failures) are retried with exponential backoff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn try_new_from_conf() -> Result<Self> {
+        let millis = |ms: i32| Duration::from_millis(ms.max(0) as u64);
+        Ok(Self {
+            max_attempts: jni_call_static!(BlazeConf.fsRetryMaxAttempts() -> i32)?.max(1) as usize,
+            initial_backoff: millis(jni_call_static!(BlazeConf.fsRetryInitialBackoffMs() -> i32)?),
+            max_backoff: millis(jni_call_static!(BlazeConf.fsRetryMaxBackoffMs() -> i32)?),
+        })
+    }
+
+    /// runs the operation, retrying on retryable errors until max attempts reached.
+    pub fn retry<T>(&self, op_name: &str, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(err) if attempt < self.max_attempts && is_retryable_error(&err) => {
+                    let backoff = self.backoff(attempt);
+                    log::warn!(
+                        "{op_name} failed (attempt {attempt}/{}), retrying in {backoff:?}: {err}",
+                        self.max_attempts,
+                    );
+                    sleep_blocking(backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let factor = 1u32 << (attempt - 1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// sleeps the current thread. operations run on tokio workers, so the worker is
+/// handed off to other tasks while sleeping.
+fn sleep_blocking(duration: Duration) {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| std::thread::sleep(duration))
+        }
+        _ => std::thread::sleep(duration),
+    }
+}
+
+/// errors which are not transient and never succeed on retrying.
+const NON_RETRYABLE_ERRORS: &[&str] = &[
+    "java.io.FileNotFoundException",
+    "java.io.EOFException",
+    "org.apache.hadoop.fs.FileAlreadyExistsException",
+    "org.apache.hadoop.fs.ParentNotDirectoryException",
+    "org.apache.hadoop.fs.UnsupportedFileSystemException",
+    "org.apache.hadoop.security.AccessControlException",
+    "Filesystem closed",
+];
+
+/// transient errors of hadoop filesystem.
+const RETRYABLE_ERRORS: &[&str] = &[
+    "java.net.ConnectException",
+    "java.net.NoRouteToHostException",
+    "java.net.SocketException",
+    "java.net.SocketTimeoutException",
+    "java.net.UnknownHostException",
+    "org.apache.hadoop.fs.ChecksumException",
+    "org.apache.hadoop.hdfs.BlockMissingException",
+    "org.apache.hadoop.hdfs.server.namenode.SafeModeException",
+    "org.apache.hadoop.ipc.RetriableException",
+    "org.apache.hadoop.ipc.StandbyException",
+    "org.apache.hadoop.net.ConnectTimeoutException",
+];
+
+/// transient failures thrown as plain IOExceptions by the hdfs client, other
+/// IOExceptions are not retried.
+const RETRYABLE_IO_ERROR_MESSAGES: &[&str] = &[
+    "All datanodes",
+    "Broken pipe",
+    "Connection reset by peer",
+    "Could not get block locations",
+    "does not have enough number of replicas",
+];
+
+/// classifies errors of filesystem operations by the thrown java exceptions.
+pub fn is_retryable_error(err: &DataFusionError) -> bool {
+    let message = err.to_string();
+    let is_retryable_io_error = message.contains("java.io.IOException")
+        && RETRYABLE_IO_ERROR_MESSAGES
+            .iter()
+            .any(|e| message.contains(e));
+    !is_token_expired_error(err)
+        && !NON_RETRYABLE_ERRORS.iter().any(|e| message.contains(e))
+        && (is_retryable_io_error || RETRYABLE_ERRORS.iter().any(|e| message.contains(e)))
+}
+
+/// errors caused by expired (or cancelled) delegation tokens, which are thrown
+/// as InvalidToken or wrapped in RemoteException by hdfs.
+const TOKEN_EXPIRED_ERRORS: &[&str] = &["is expired", "can't be found in cache"];
+
+pub fn is_token_expired_error(err: &DataFusionError) -> bool {
+    let message = err.to_string();
+    message.contains("org.apache.hadoop.security.token.SecretManager$InvalidToken")
+        || (message.contains("token") && TOKEN_EXPIRED_ERRORS.iter().any(|e| message.contains(e)))
+}
 
 pub struct Fs {
-    fs: GlobalRef,
+    fs: FsHandle,
     io_time: Time,
+    retry_policy: RetryPolicy,
 }
 
 impl Fs {
     pub fn new(fs: GlobalRef, io_time_metric: &Time) -> Self {
         Self {
-            fs,
+            fs: FsHandle {
+                fs: Arc::new(Mutex::new(fs)),
+                provider: None,
+            },
             io_time: io_time_metric.clone(),
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     pub fn open(&self, path: &str) -> Result<FsDataInputStream> {
         let _timer = self.io_time.timer();
-        let path_str = jni_new_string!(path)?;
-        let path_uri = jni_new_object!(JavaURI(path_str.as_obj()))?;
-        let path = jni_new_object!(HadoopPath(path_uri.as_obj()))?;
-        let fin = jni_call!(
-            HadoopFileSystem(self.fs.as_obj()).open(path.as_obj()) -> JObject
-        )?;
-
+        let fin = self.run("open", |fs| open_stream(fs, path))?;
         Ok(FsDataInputStream {
-            stream: jni_new_global_ref!(fin.as_obj())?,
+            stream: Mutex::new(fin),
             io_time: self.io_time.clone(),
+            retry_policy: self.retry_policy,
+            reopen: Some((self.fs.clone(), path.to_string())),
+            read_ahead: None,
         })
     }
 
+    /// creates (or overwrites) a file. only creating is retried, a failed write
+    /// to the created stream cannot be retried without rewriting the whole file.
     pub fn create(&self, path: &str) -> Result<FsDataOutputStream> {
         let _timer = self.io_time.timer();
-        let path_str = jni_new_string!(path)?;
-        let path_uri = jni_new_object!(JavaURI(path_str.as_obj()))?;
-        let path = jni_new_object!(HadoopPath(path_uri.as_obj()))?;
-        let fin = jni_call!(
-            HadoopFileSystem(self.fs.as_obj()).create(path.as_obj()) -> JObject
-        )?;
-
-        Ok(FsDataOutputStream {
-            stream: jni_new_global_ref!(fin.as_obj())?,
-            io_time: self.io_time.clone(),
-        })
+        let fout = self.run("create", |fs| create_stream(fs, path))?;
+        Ok(FsDataOutputStream::new(fout, self.io_time.clone()))
+    }
+
+    /// runs the operation with retry policy. if delegation tokens are expired, the
+    /// filesystem is re-acquired from its provider and the operation is run again.
+    fn run<T>(&self, op_name: &str, mut op: impl FnMut(&GlobalRef) -> Result<T>) -> Result<T> {
+        match self.retry_policy.retry(op_name, || op(&self.fs.get())) {
+            Err(err) if self.fs.refresh_on_token_expired(op_name, &err)? => {
+                self.retry_policy.retry(op_name, || op(&self.fs.get()))
+            }
+            result => result,
+        }
+    }
+}
+
+/// a hadoop FileSystem object shared by a filesystem and its opened streams, the
+/// object is re-acquired from the provider after delegation tokens are expired.
+#[derive(Clone)]
+struct FsHandle {
+    fs: Arc<Mutex<GlobalRef>>,
+    provider: Option<(GlobalRef, String)>,
+}
+
+impl FsHandle {
+    fn get(&self) -> GlobalRef {
+        self.fs.lock().unwrap().clone()
     }
+
+    /// re-acquires the filesystem if err is caused by expired delegation tokens,
+    /// returns whether the failed operation can be run again.
+    fn refresh_on_token_expired(&self, op_name: &str, err: &DataFusionError) -> Result<bool> {
+        let (fs_provider, path) = match &self.provider {
+            Some(provider) if is_token_expired_error(err) => provider,
+            _ => return Ok(false),
+        };
+        log::warn!(
+            "{op_name} failed with expired delegation token, re-acquiring filesystem: {err}"
+        );
+        *self.fs.lock().unwrap() = provide_fs(fs_provider, path)?;
+        Ok(true)
+    }
+}
+
+fn provide_fs(fs_provider: &GlobalRef, path: &str) -> Result<GlobalRef> {
+    let fs = jni_call!(
+        ScalaFunction1(fs_provider.as_obj()).apply(jni_new_string!(path)?.as_obj()) -> JObject
+    )?;
+    jni_new_global_ref!(fs.as_obj())
+}
+
+fn open_stream(fs: &GlobalRef, path: &str) -> Result<GlobalRef> {
+    let path_str = jni_new_string!(path)?;
+    let path_uri = jni_new_object!(JavaURI(path_str.as_obj()))?;
+    let path = jni_new_object!(HadoopPath(path_uri.as_obj()))?;
+    let fin = jni_call!(HadoopFileSystem(fs.as_obj()).open(path.as_obj()) -> JObject)?;
+    jni_new_global_ref!(fin.as_obj())
+}
+
+fn create_stream(fs: &GlobalRef, path: &str) -> Result<GlobalRef> {
+    let path_str = jni_new_string!(path)?;
+    let path_uri = jni_new_object!(JavaURI(path_str.as_obj()))?;
+    let path = jni_new_object!(HadoopPath(path_uri.as_obj()))?;
+    let fout = jni_call!(HadoopFileSystem(fs.as_obj()).create(path.as_obj()) -> JObject)?;
+    jni_new_global_ref!(fout.as_obj())
 }
 
 pub struct FsDataInputStream {
-    stream: GlobalRef,
+    stream: Mutex<GlobalRef>,
     io_time: Time,
+    retry_policy: RetryPolicy,
+    reopen: Option<(FsHandle, String)>,
+    read_ahead: Option<Mutex<ReadAhead>>,
 }
 
 impl FsDataInputStream {
+    /// enables reading ahead read_ahead_size bytes in background on sequential
+    /// access, subsequent reads are served from the read-ahead buffer.
+    pub fn with_read_ahead(mut self, file_len: u64, read_ahead_size: usize) -> Self {
+        if read_ahead_size > 0 {
+            self.read_ahead = Some(Mutex::new(ReadAhead {
+                file_len,
+                read_ahead_size,
+                last_read_end: 0,
+                buffer_pos: 0,
+                buffer: vec![],
+                pending: None,
+            }));
+        }
+        self
+    }
+
     pub fn read_fully(&self, pos: u64, buf: &mut [u8]) -> Result<()> {
-        let _timer = self.io_time.timer();
-        let buf = jni_new_direct_byte_buffer!(buf)?;
+        match &self.read_ahead {
+            Some(read_ahead) => read_ahead.lock().unwrap().read_fully(self, pos, buf),
+            None => {
+                let _timer = self.io_time.timer();
+                self.read_from_stream(pos, buf)
+            }
+        }
+    }
 
-        jni_call_static!(JniUtil.readFullyFromFSDataInputStream(
-            self.stream.as_obj(), pos as i64, buf.as_obj()) -> ()
-        )?;
+    fn stream(&self) -> GlobalRef {
+        self.stream.lock().unwrap().clone()
+    }
+
+    /// reads with retry policy. if delegation tokens are expired, the file is
+    /// reopened with the re-acquired filesystem and read again.
+    fn read_from_stream(&self, pos: u64, buf: &mut [u8]) -> Result<()> {
+        let read = |buf: &mut [u8]| {
+            self.retry_policy
+                .retry("read", || read_fully_from_stream(&self.stream(), pos, buf))
+        };
+        match read(buf) {
+            Err(err) => match &self.reopen {
+                Some((fs, path)) if fs.refresh_on_token_expired("read", &err)? => {
+                    let stream = self
+                        .retry_policy
+                        .retry("open", || open_stream(&fs.get(), path))?;
+                    let old_stream = std::mem::replace(&mut *self.stream.lock().unwrap(), stream);
+                    close_input_stream(&old_stream);
+                    read(buf)
+                }
+                _ => Err(err),
+            },
+            Ok(()) => Ok(()),
+        }
+    }
+}
+
+fn read_fully_from_stream(stream: &GlobalRef, pos: u64, buf: &mut [u8]) -> Result<()> {
+    let buf = jni_new_direct_byte_buffer!(buf)?;
+    jni_call_static!(JniUtil.readFullyFromFSDataInputStream(
+        stream.as_obj(), pos as i64, buf.as_obj()) -> ()
+    )?;
+    Ok(())
+}
+
+fn close_input_stream(stream: &GlobalRef) {
+    if let Err(e) = jni_call!(JavaAutoCloseable(stream.as_obj()).close() -> ()) {
+        log::warn!("error closing hadoop FSDataInputStream: {:?}", e);
+    }
+}
+
+struct ReadAhead {
+    file_len: u64,
+    read_ahead_size: usize,
+    last_read_end: u64,
+    buffer_pos: u64,
+    buffer: Vec<u8>,
+    pending: Option<PendingRead>,
+}
+
+struct PendingRead {
+    pos: u64,
+    len: usize,
+    result: Receiver<Result<Vec<u8>>>,
+}
+
+impl ReadAhead {
+    fn read_fully(&mut self, input: &FsDataInputStream, pos: u64, buf: &mut [u8]) -> Result<()> {
+        let end = pos + buf.len() as u64;
+
+        // forward reads with small gaps (like skipped column chunks) are also
+        // considered sequential
+        let sequential =
+            pos >= self.last_read_end && pos - self.last_read_end <= self.read_ahead_size as u64;
+        self.last_read_end = end;
+
+        // take the pending read-ahead as buffer if it contains the requested range,
+        // otherwise the pending read-ahead is discarded
+        if !self.buffer_contains(pos, end) {
+            if let Some(pending) = self.pending.take() {
+                if pending.pos <= pos && end <= pending.pos + pending.len as u64 {
+                    let _timer = input.io_time.timer();
+                    // failed read-ahead is ignored and the range is read again
+                    if let Ok(Ok(bytes)) = pending.result.recv() {
+                        self.buffer_pos = pending.pos;
+                        self.buffer = bytes;
+                    }
+                }
+            }
+        }
+
+        if self.buffer_contains(pos, end) {
+            let offset = (pos - self.buffer_pos) as usize;
+            buf.copy_from_slice(&self.buffer[offset..][..buf.len()]);
+        } else {
+            let _timer = input.io_time.timer();
+            input.read_from_stream(pos, buf)?;
+        }
+
+        // start reading ahead the following range in background
+        if sequential && self.pending.is_none() {
+            let buffer_end = self.buffer_pos + self.buffer.len() as u64;
+            let ahead_pos = if self.buffer_contains(pos, end) {
+                buffer_end
+            } else {
+                end
+            };
+            let ahead_len = self
+                .file_len
+                .saturating_sub(ahead_pos)
+                .min(self.read_ahead_size as u64) as usize;
+            if ahead_len > 0 {
+                self.pending = spawn_read(input.stream(), ahead_pos, ahead_len);
+            }
+        }
         Ok(())
     }
+
+    fn buffer_contains(&self, pos: u64, end: u64) -> bool {
+        self.buffer_pos <= pos && end <= self.buffer_pos + self.buffer.len() as u64
+    }
+}
+
+fn spawn_read(stream: GlobalRef, pos: u64, len: usize) -> Option<PendingRead> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let (tx, rx) = sync_channel(1);
+    runtime.spawn_blocking(move || {
+        let mut bytes = vec![0u8; len];
+        let result = read_fully_from_stream(&stream, pos, &mut bytes).map(|_| bytes);
+        let _ = tx.send(result); // reader may have discarded this read-ahead
+    });
+    Some(PendingRead {
+        pos,
+        len,
+        result: rx,
+    })
 }
 
 impl Drop for FsDataInputStream {
     fn drop(&mut self) {
         let _timer = self.io_time.timer();
-        if let Err(e) = jni_call!(JavaAutoCloseable(self.stream.as_obj()).close() -> ()) {
-            log::warn!("error closing hadoop FSDataInputStream: {:?}", e);
-        }
+        close_input_stream(&self.stream());
     }
 }
 
+/// default size of buffered data before written to the underlying stream
+pub const DEFAULT_WRITE_BUFFER_SIZE: usize = 1048576;
+
+/// a buffered hadoop FSDataOutputStream. buffered data is written to the
+/// underlying stream in background, with at most one write in flight so the
+/// written data keeps in order. the stream is cheaply cloneable, so sinks can
+/// close it explicitly after handing it to a file writer.
+#[derive(Clone)]
 pub struct FsDataOutputStream {
+    inner: Arc<Mutex<OutputStreamInner>>,
+}
+
+struct OutputStreamInner {
     stream: GlobalRef,
     io_time: Time,
+    buffer: Vec<u8>,
+    buffer_size: usize,
+    pending: Option<Receiver<Result<()>>>,
+    num_bytes: usize,
+    closed: bool,
 }
 
 impl FsDataOutputStream {
+    fn new(stream: GlobalRef, io_time: Time) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(OutputStreamInner {
+                stream,
+                io_time,
+                buffer: vec![],
+                buffer_size: DEFAULT_WRITE_BUFFER_SIZE,
+                pending: None,
+                num_bytes: 0,
+                closed: false,
+            })),
+        }
+    }
+
+    /// sets the size of buffered data before written to the underlying stream,
+    /// zero disables buffering and data is written synchronously.
+    pub fn with_buffer_size(self, buffer_size: usize) -> Self {
+        self.inner.lock().unwrap().buffer_size = buffer_size;
+        self
+    }
+
+    /// number of bytes written into this stream.
+    pub fn num_bytes(&self) -> usize {
+        self.inner.lock().unwrap().num_bytes
+    }
+
     pub fn write_fully(&self, buf: &[u8]) -> Result<()> {
-        let _timer = self.io_time.timer();
-        let buf = jni_new_direct_byte_buffer!(buf)?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.check_open()?;
+        inner.num_bytes += buf.len();
+        if inner.buffer_size == 0 {
+            inner.wait_pending()?;
+            let _timer = inner.io_time.timer();
+            return write_fully_to_stream(&inner.stream, buf);
+        }
+        inner.buffer.extend_from_slice(buf);
+        if inner.buffer.len() >= inner.buffer_size {
+            inner.write_buffer()?;
+        }
+        Ok(())
+    }
+
+    /// writes all buffered data to the underlying stream.
+    pub fn flush(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.check_open()?;
+        inner.write_buffer()?;
+        inner.wait_pending()
+    }
+
+    /// flushes all written data out to the datanodes, see FSDataOutputStream.hflush().
+    pub fn hflush(&self) -> Result<()> {
+        self.flush()?;
+        let inner = self.inner.lock().unwrap();
+        let _timer = inner.io_time.timer();
+        jni_call!(HadoopFSDataOutputStream(inner.stream.as_obj()).hflush() -> ())?;
+        Ok(())
+    }
 
-        jni_call_static!(JniUtil.writeFullyToFSDataOutputStream(
-            self.stream.as_obj(), buf.as_obj()) -> ()
-        )?;
+    /// flushes all written data to the disks of datanodes, see FSDataOutputStream.hsync().
+    pub fn hsync(&self) -> Result<()> {
+        self.flush()?;
+        let inner = self.inner.lock().unwrap();
+        let _timer = inner.io_time.timer();
+        jni_call!(HadoopFSDataOutputStream(inner.stream.as_obj()).hsync() -> ())?;
         Ok(())
     }
+
+    /// writes all buffered data and closes the underlying stream. sinks must close
+    /// the stream explicitly, errors are only logged if the stream is closed on drop.
+    pub fn close(&self) -> Result<()> {
+        self.inner.lock().unwrap().close()
+    }
+}
+
+impl Write for FsDataOutputStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_fully(buf)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        FsDataOutputStream::flush(self)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
+    }
+}
+
+impl OutputStreamInner {
+    fn check_open(&self) -> Result<()> {
+        if self.closed {
+            return Err(DataFusionError::Execution(
+                "writing to closed hadoop FSDataOutputStream".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_buffer(&mut self) -> Result<()> {
+        self.wait_pending()?;
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let buffer = std::mem::take(&mut self.buffer);
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let stream = self.stream.clone();
+                let (tx, rx) = sync_channel(1);
+                runtime.spawn_blocking(move || {
+                    let _ = tx.send(write_fully_to_stream(&stream, &buffer));
+                });
+                self.pending = Some(rx);
+            }
+            Err(_) => {
+                let _timer = self.io_time.timer();
+                write_fully_to_stream(&self.stream, &buffer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_pending(&mut self) -> Result<()> {
+        if let Some(pending) = self.pending.take() {
+            let _timer = self.io_time.timer();
+            pending.recv().map_err(|_| {
+                DataFusionError::Execution(
+                    "background write of hadoop FSDataOutputStream is cancelled".to_string(),
+                )
+            })??;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        let flushed = self.write_buffer().and_then(|_| self.wait_pending());
+        let _timer = self.io_time.timer();
+        jni_call!(JavaAutoCloseable(self.stream.as_obj()).close() -> ())?;
+        flushed
+    }
+}
+
+impl Drop for OutputStreamInner {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            log::warn!("error closing hadoop FSDataOutputStream: {:?}", e);
+        }
+    }
+}
+
+fn write_fully_to_stream(stream: &GlobalRef, buf: &[u8]) -> Result<()> {
+    let buf = jni_new_direct_byte_buffer!(buf)?;
+    jni_call_static!(JniUtil.writeFullyToFSDataOutputStream(
+        stream.as_obj(), buf.as_obj()) -> ()
+    )?;
+    Ok(())
 }
 
 pub struct FsProvider {
     fs_provider: GlobalRef,
     io_time: Time,
+    retry_policy: RetryPolicy,
 }
 
 impl FsProvider {
@@ -117,16 +622,146 @@ impl FsProvider {
         Self {
             fs_provider,
             io_time: io_time_metric.clone(),
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    /// sets retry policy of providing filesystems and operations of provided filesystems.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     pub fn provide(&self, path: &str) -> Result<Fs> {
         let _timer = self.io_time.timer();
-        let fs = jni_call!(
-            ScalaFunction1(self.fs_provider.as_obj()).apply(
-                jni_new_string!(path)?.as_obj()
-            ) -> JObject
-        )?;
-        Ok(Fs::new(jni_new_global_ref!(fs.as_obj())?, &self.io_time))
+        let fs = self
+            .retry_policy
+            .retry("get filesystem", || provide_fs(&self.fs_provider, path))?;
+        let mut fs = Fs::new(fs, &self.io_time).with_retry_policy(self.retry_policy);
+        fs.fs.provider = Some((self.fs_provider.clone(), path.to_string()));
+        Ok(fs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hadoop_fs::{is_retryable_error, is_token_expired_error, RetryPolicy};
+    use datafusion::common::{DataFusionError, Result};
+    use std::time::Duration;
+
+    fn java_error(class_name: &str, message: &str) -> DataFusionError {
+        DataFusionError::External(
+            format!("Java exception thrown at hadoop_fs.rs:1: {class_name}: {message}").into(),
+        )
+    }
+
+    #[test]
+    fn test_is_retryable_error() {
+        assert!(is_retryable_error(&java_error(
+            "org.apache.hadoop.hdfs.BlockMissingException",
+            "Could not obtain block"
+        )));
+        assert!(is_retryable_error(&java_error(
+            "java.net.SocketTimeoutException",
+            "read timed out"
+        )));
+        assert!(is_retryable_error(&java_error(
+            "java.io.IOException",
+            "All datanodes are bad"
+        )));
+        assert!(!is_retryable_error(&java_error(
+            "java.io.FileNotFoundException",
+            "File does not exist"
+        )));
+        assert!(!is_retryable_error(&java_error(
+            "java.io.IOException",
+            "Filesystem closed"
+        )));
+        assert!(!is_retryable_error(&java_error(
+            "java.io.IOException",
+            "Stream is closed!"
+        )));
+        assert!(!is_retryable_error(&DataFusionError::Execution(
+            "invalid parquet file".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_is_token_expired_error() {
+        let expired = java_error(
+            "org.apache.hadoop.ipc.RemoteException",
+            "token (HDFS_DELEGATION_TOKEN token 42 for spark) is expired",
+        );
+        assert!(is_token_expired_error(&expired));
+        assert!(!is_retryable_error(&expired));
+        assert!(is_token_expired_error(&java_error(
+            "org.apache.hadoop.security.token.SecretManager$InvalidToken",
+            "token (HDFS_DELEGATION_TOKEN token 42 for spark) can't be found in cache",
+        )));
+        assert!(!is_token_expired_error(&java_error(
+            "java.io.IOException",
+            "All datanodes are bad"
+        )));
+    }
+
+    #[test]
+    fn test_retry_policy() -> Result<()> {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(1));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2));
+        assert_eq!(policy.backoff(10), Duration::from_millis(2));
+
+        // succeeds on the last attempt
+        let mut attempts = 0;
+        let result = policy.retry("test", || {
+            attempts += 1;
+            if attempts < 3 {
+                return Err(java_error("java.net.SocketTimeoutException", "transient"));
+            }
+            Ok(attempts)
+        })?;
+        assert_eq!(result, 3);
+
+        // non-retryable errors fail immediately
+        let mut attempts = 0;
+        let result: Result<()> = policy.retry("test", || {
+            attempts += 1;
+            Err(java_error("java.io.FileNotFoundException", "not found"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+
+        // fails after max attempts
+        let mut attempts = 0;
+        let result: Result<()> = policy.retry("test", || {
+            attempts += 1;
+            Err(java_error("java.io.IOException", "All datanodes are bad"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_retry_policy_on_runtime() -> Result<()> {
+        let policy = RetryPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+        let mut attempts = 0;
+        let result = policy.retry("test", || {
+            attempts += 1;
+            if attempts < 2 {
+                return Err(java_error("java.net.SocketTimeoutException", "transient"));
+            }
+            Ok(attempts)
+        })?;
+        assert_eq!(result, 2);
+        Ok(())
     }
 }
@@ -0,0 +1,146 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use datafusion::common::{DataFusionError, Result};
+
+use crate::spark_hash::create_hashes;
+
+// seeds of the two murmur3 hashes combined into a 64-bit hash
+const HASH_SEED_HI: u32 = 42;
+const HASH_SEED_LO: u32 = 0x9747b28c;
+
+/// hyperloglog sketch for estimating number of distinct values, with
+/// 2^precision one-byte registers. relative error is about 1.04/sqrt(2^precision).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    precision: u32,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u32) -> Self {
+        assert!(
+            (4..=18).contains(&precision),
+            "invalid hll precision: {precision}"
+        );
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// restores a sketch from its registers, see `registers()`
+    pub fn try_from_registers(registers: Vec<u8>) -> Result<Self> {
+        let precision = registers.len().trailing_zeros();
+        if !registers.len().is_power_of_two() || !(4..=18).contains(&precision) {
+            return Err(DataFusionError::Execution(format!(
+                "invalid hll registers length: {}",
+                registers.len()
+            )));
+        }
+        Ok(Self {
+            precision,
+            registers,
+        })
+    }
+
+    /// returns the registers, which are the serialized form of the sketch
+    pub fn registers(&self) -> &[u8] {
+        &self.registers
+    }
+
+    pub fn add_hash(&mut self, hash: u64) {
+        let idx = (hash >> (64 - self.precision)) as usize;
+        let w = hash << self.precision | 1 << (self.precision - 1);
+        let rank = w.leading_zeros() as u8 + 1;
+        self.registers[idx] = self.registers[idx].max(rank);
+    }
+
+    /// adds all non-null values of the array
+    pub fn update(&mut self, values: &ArrayRef) -> Result<()> {
+        let num_rows = values.len();
+        let mut hashes_hi = vec![HASH_SEED_HI; num_rows];
+        let mut hashes_lo = vec![HASH_SEED_LO; num_rows];
+        create_hashes(&[values.clone()], &mut hashes_hi)?;
+        create_hashes(&[values.clone()], &mut hashes_lo)?;
+
+        for (i, (&hi, &lo)) in hashes_hi.iter().zip(&hashes_lo).enumerate() {
+            if values.is_valid(i) {
+                self.add_hash((hi as u64) << 32 | lo as u64);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(self.precision, other.precision);
+        for (r, &other_r) in self.registers.iter_mut().zip(&other.registers) {
+            *r = (*r).max(other_r);
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let estimate = alpha * m * m / sum;
+
+        // linear counting for small cardinalities
+        let num_zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if estimate <= 2.5 * m && num_zeros > 0 {
+            return (m * (m / num_zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hyperloglog::HyperLogLog;
+    use arrow::array::*;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_hyperloglog() -> Result<()> {
+        let mut hll = HyperLogLog::new(12);
+        assert_eq!(hll.estimate(), 0);
+
+        let values: ArrayRef = Arc::new(Int64Array::from_iter(
+            (0..100000).map(|i| (i % 3 != 0).then_some(i % 10000)),
+        ));
+        hll.update(&values)?;
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 10000.0).abs() / 10000.0 < 0.05,
+            "estimate={estimate}"
+        );
+
+        // merging with sketch of the same values does not change the estimate
+        let mut merged = HyperLogLog::new(12);
+        merged.update(&values.slice(0, 50000))?;
+        merged.merge(&hll);
+        assert_eq!(merged.estimate(), hll.estimate());
+
+        let restored = HyperLogLog::try_from_registers(hll.registers().to_vec())?;
+        assert_eq!(restored, hll);
+        assert!(HyperLogLog::try_from_registers(vec![0; 1000]).is_err());
+
+        let mut small = HyperLogLog::new(12);
+        small.update(&(Arc::new(StringArray::from(vec!["a", "b", "a", "c"])) as ArrayRef))?;
+        assert_eq!(small.estimate(), 3);
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::io::{read_bytes_slice, read_len, write_len};
+use crate::io::{read_bytes_slice, read_len, read_u8, write_len, write_u8, IpcCompressionCodec};
 use arrow::array::*;
 use arrow::buffer::{Buffer, MutableBuffer};
 use arrow::datatypes::*;
@@ -27,7 +27,7 @@ use std::sync::Arc;
 pub fn write_batch<W: Write>(
     batch: &RecordBatch,
     output: &mut W,
-    compress: bool,
+    codec: IpcCompressionCodec,
     uncompressed_size: Option<&mut usize>,
 ) -> Result<()> {
     struct CountWriter<W: Write> {
@@ -47,27 +47,23 @@ pub fn write_batch<W: Write>(
     }
 
     let num_bytes_written_uncompressed = Arc::new(AtomicUsize::new(0));
-    let mut output: Box<dyn Write> = if compress {
-        let w = zstd::Encoder::new(output, 1)?.auto_finish();
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
+    let w: Box<dyn Write> = match codec {
+        IpcCompressionCodec::Uncompressed => Box::new(BufWriter::new(output)),
+        IpcCompressionCodec::Lz4 => {
+            Box::new(lz4_flex::frame::FrameEncoder::new(output).auto_finish())
         }
-    } else {
-        let w = BufWriter::new(output);
-        if uncompressed_size.is_some() {
-            Box::new(CountWriter {
-                num_bytes_written: num_bytes_written_uncompressed.clone(),
-                inner: w,
-            })
-        } else {
-            Box::new(w)
+        IpcCompressionCodec::Zstd(level) => {
+            Box::new(zstd::Encoder::new(output, level)?.auto_finish())
         }
     };
+    let mut output: Box<dyn Write> = if uncompressed_size.is_some() {
+        Box::new(CountWriter {
+            num_bytes_written: num_bytes_written_uncompressed.clone(),
+            inner: w,
+        })
+    } else {
+        w
+    };
 
     let schema = batch.schema();
 
@@ -107,11 +103,13 @@ pub fn write_batch<W: Write>(
     Ok(())
 }
 
-pub fn read_batch<R: Read>(input: &mut R, compress: bool) -> Result<RecordBatch> {
-    let mut input: Box<dyn Read> = if compress {
-        Box::new(BufReader::new(zstd::Decoder::new(input)?))
-    } else {
-        Box::new(BufReader::new(input))
+pub fn read_batch<R: Read>(input: &mut R, codec: IpcCompressionCodec) -> Result<RecordBatch> {
+    let mut input: Box<dyn Read> = match codec {
+        IpcCompressionCodec::Uncompressed => Box::new(BufReader::new(input)),
+        IpcCompressionCodec::Lz4 => {
+            Box::new(BufReader::new(lz4_flex::frame::FrameDecoder::new(input)))
+        }
+        IpcCompressionCodec::Zstd(_) => Box::new(BufReader::new(zstd::Decoder::new(input)?)),
     };
 
     // read number of columns and rows
@@ -180,6 +178,7 @@ pub fn write_array<W: Write>(array: &dyn Array, output: &mut W) -> Result<()> {
         DataType::UInt16 => write_primitive!(UInt16),
         DataType::UInt32 => write_primitive!(UInt32),
         DataType::UInt64 => write_primitive!(UInt64),
+        DataType::Float16 => write_primitive!(Float16),
         DataType::Float32 => write_primitive!(Float32),
         DataType::Float64 => write_primitive!(Float64),
         DataType::Decimal128(_, _) => write_primitive!(Decimal128),
@@ -194,6 +193,7 @@ pub fn write_array<W: Write>(array: &dyn Array, output: &mut W) -> Result<()> {
         DataType::List(_field) => write_list_array(as_list_array(array), output)?,
         DataType::Map(_, _) => write_map_array(as_map_array(array), output)?,
         DataType::Struct(_) => write_struct_array(as_struct_array(array), output)?,
+        DataType::Dictionary(key_type, _) => write_dictionary_array(array, key_type, output)?,
         other => {
             return Err(DataFusionError::NotImplemented(format!(
                 "unsupported data type: {}",
@@ -225,6 +225,7 @@ pub fn read_array<R: Read>(
         DataType::UInt16 => read_primitive!(UInt16),
         DataType::UInt32 => read_primitive!(UInt32),
         DataType::UInt64 => read_primitive!(UInt64),
+        DataType::Float16 => read_primitive!(Float16),
         DataType::Float32 => read_primitive!(Float32),
         DataType::Float64 => read_primitive!(Float64),
         DataType::Decimal128(prec, scale) => Arc::new(
@@ -245,6 +246,9 @@ pub fn read_array<R: Read>(
             read_map_array(num_rows, input, map_field, *is_sorted)?
         }
         DataType::Struct(fields) => read_struct_array(num_rows, input, fields)?,
+        DataType::Dictionary(key_type, value_type) => {
+            read_dictionary_array(num_rows, input, key_type, value_type)?
+        }
         other => {
             return Err(DataFusionError::NotImplemented(format!(
                 "unsupported data type: {}",
@@ -365,20 +369,16 @@ fn read_primitive_array<R: Read, PT: ArrowPrimitiveType>(
 
 fn write_list_array<W: Write>(array: &ListArray, output: &mut W) -> Result<()> {
     if let Some(null_buffer) = array.to_data().nulls() {
-        write_len(1, output)?;
+        write_len(HAS_NULL_BUFFER | PACKED_OFFSETS, output)?;
         write_bits_buffer(null_buffer.buffer(), array.offset(), array.len(), output)?;
     } else {
-        write_len(0, output)?;
+        write_len(PACKED_OFFSETS, output)?;
     }
 
     let first_offset = array.value_offsets().first().cloned().unwrap_or_default();
-    let mut cur_offset = first_offset;
-    for &offset in array.value_offsets().iter().skip(1) {
-        let len = offset - cur_offset;
-        write_len(len as usize, output)?;
-        cur_offset = offset;
-    }
-    let values_len = cur_offset - first_offset;
+    let last_offset = array.value_offsets().last().cloned().unwrap_or_default();
+    write_packed_offsets(array.value_offsets(), output)?;
+    let values_len = last_offset - first_offset;
     let values = array
         .values()
         .slice(first_offset as usize, values_len as usize);
@@ -391,23 +391,18 @@ fn read_list_array<R: Read>(
     input: &mut R,
     list_field: &FieldRef,
 ) -> Result<ArrayRef> {
-    let has_null_buffer = read_len(input)? == 1;
-    let null_buffer: Option<Buffer> = if has_null_buffer {
+    let flags = read_len(input)?;
+    let null_buffer: Option<Buffer> = if flags & HAS_NULL_BUFFER != 0 {
         Some(read_bits_buffer(input, num_rows)?)
     } else {
         None
     };
 
-    let mut cur_offset = 0;
-    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
-    offsets_buffer.push(0u32);
-    for _ in 0..num_rows {
-        let len = read_len(input)?;
-        let offset = cur_offset + len;
-        offsets_buffer.push(offset as u32);
-        cur_offset = offset;
-    }
-    let offsets_buffer: Buffer = offsets_buffer.into();
+    let (offsets_buffer, cur_offset) = if flags & PACKED_OFFSETS != 0 {
+        read_packed_offsets(num_rows, input)?
+    } else {
+        read_legacy_offsets(num_rows, input)?
+    };
     let values_len = cur_offset;
     let values = read_array(input, list_field.data_type(), values_len)?;
 
@@ -425,20 +420,16 @@ fn read_list_array<R: Read>(
 fn write_map_array<W: Write>(array: &MapArray, output: &mut W) -> Result<()> {
     let array_data = array.to_data();
     if let Some(null_buffer) = array_data.nulls() {
-        write_len(1, output)?;
+        write_len(HAS_NULL_BUFFER | PACKED_OFFSETS, output)?;
         write_bits_buffer(null_buffer.buffer(), array.offset(), array.len(), output)?;
     } else {
-        write_len(0, output)?;
+        write_len(PACKED_OFFSETS, output)?;
     }
 
     let first_offset = array.value_offsets().first().cloned().unwrap_or_default();
-    let mut cur_offset = first_offset;
-    for &offset in array.value_offsets().iter().skip(1) {
-        let len = offset - cur_offset;
-        write_len(len as usize, output)?;
-        cur_offset = offset;
-    }
-    let entries_len = cur_offset - first_offset;
+    let last_offset = array.value_offsets().last().cloned().unwrap_or_default();
+    write_packed_offsets(array.value_offsets(), output)?;
+    let entries_len = last_offset - first_offset;
     let keys = array
         .keys()
         .slice(first_offset as usize, entries_len as usize);
@@ -456,23 +447,18 @@ fn read_map_array<R: Read>(
     map_field: &FieldRef,
     is_sorted: bool,
 ) -> Result<ArrayRef> {
-    let has_null_buffer = read_len(input)? == 1;
-    let null_buffer: Option<Buffer> = if has_null_buffer {
+    let flags = read_len(input)?;
+    let null_buffer: Option<Buffer> = if flags & HAS_NULL_BUFFER != 0 {
         Some(read_bits_buffer(input, num_rows)?)
     } else {
         None
     };
 
-    let mut cur_offset = 0;
-    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
-    offsets_buffer.push(0u32);
-    for _ in 0..num_rows {
-        let len = read_len(input)?;
-        let offset = cur_offset + len;
-        offsets_buffer.push(offset as u32);
-        cur_offset = offset;
-    }
-    let offsets_buffer: Buffer = offsets_buffer.into();
+    let (offsets_buffer, cur_offset) = if flags & PACKED_OFFSETS != 0 {
+        read_packed_offsets(num_rows, input)?
+    } else {
+        read_legacy_offsets(num_rows, input)?
+    };
     let values_len = cur_offset;
 
     // build inner struct
@@ -544,6 +530,48 @@ fn read_struct_array<R: Read>(num_rows: usize, input: &mut R, fields: &Fields) -
     Ok(make_array(array_data))
 }
 
+fn write_dictionary_array<W: Write>(
+    array: &dyn Array,
+    key_type: &DataType,
+    output: &mut W,
+) -> Result<()> {
+    let array_data = array.to_data();
+    let keys = make_array(
+        array_data
+            .clone()
+            .into_builder()
+            .data_type(key_type.clone())
+            .child_data(vec![])
+            .build()?,
+    );
+    let values = make_array(array_data.child_data()[0].clone());
+    write_array(&keys, output)?;
+    write_len(values.len(), output)?;
+    write_array(&values, output)?;
+    Ok(())
+}
+
+fn read_dictionary_array<R: Read>(
+    num_rows: usize,
+    input: &mut R,
+    key_type: &DataType,
+    value_type: &DataType,
+) -> Result<ArrayRef> {
+    let keys = read_array(input, key_type, num_rows)?;
+    let num_values = read_len(input)?;
+    let values = read_array(input, value_type, num_values)?;
+    let array_data = keys
+        .into_data()
+        .into_builder()
+        .data_type(DataType::Dictionary(
+            Box::new(key_type.clone()),
+            Box::new(value_type.clone()),
+        ))
+        .child_data(vec![values.into_data()])
+        .build()?;
+    Ok(make_array(array_data))
+}
+
 fn write_boolean_array<W: Write>(array: &BooleanArray, output: &mut W) -> Result<()> {
     let array_data = array.to_data();
     if let Some(null_buffer) = array_data.nulls() {
@@ -585,20 +613,16 @@ fn write_bytes_array<T: ByteArrayType<Offset = i32>, W: Write>(
     output: &mut W,
 ) -> Result<()> {
     if let Some(null_buffer) = array.to_data().nulls() {
-        write_len(1, output)?;
+        write_len(HAS_NULL_BUFFER | PACKED_OFFSETS, output)?;
         write_bits_buffer(null_buffer.buffer(), array.offset(), array.len(), output)?;
     } else {
-        write_len(0, output)?;
+        write_len(PACKED_OFFSETS, output)?;
     }
 
     let first_offset = array.value_offsets().first().cloned().unwrap_or_default();
-    let mut cur_offset = first_offset;
-    for &offset in array.value_offsets().iter().skip(1) {
-        let len = offset - cur_offset;
-        write_len(len as usize, output)?;
-        cur_offset = offset;
-    }
-    output.write_all(&array.value_data()[first_offset as usize..cur_offset as usize])?;
+    let last_offset = array.value_offsets().last().cloned().unwrap_or_default();
+    write_packed_offsets(array.value_offsets(), output)?;
+    output.write_all(&array.value_data()[first_offset as usize..last_offset as usize])?;
     Ok(())
 }
 
@@ -607,23 +631,18 @@ fn read_bytes_array<R: Read>(
     input: &mut R,
     data_type: DataType,
 ) -> Result<ArrayRef> {
-    let has_null_buffer = read_len(input)? == 1;
-    let null_buffer: Option<Buffer> = if has_null_buffer {
+    let flags = read_len(input)?;
+    let null_buffer: Option<Buffer> = if flags & HAS_NULL_BUFFER != 0 {
         Some(read_bits_buffer(input, num_rows)?)
     } else {
         None
     };
 
-    let mut cur_offset = 0;
-    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
-    offsets_buffer.push(0u32);
-    for _ in 0..num_rows {
-        let len = read_len(input)?;
-        let offset = cur_offset + len;
-        offsets_buffer.push(offset as u32);
-        cur_offset = offset;
-    }
-    let offsets_buffer: Buffer = offsets_buffer.into();
+    let (offsets_buffer, cur_offset) = if flags & PACKED_OFFSETS != 0 {
+        read_packed_offsets(num_rows, input)?
+    } else {
+        read_legacy_offsets(num_rows, input)?
+    };
 
     let data_len = cur_offset;
     let data_buffer = Buffer::from(read_bytes_slice(input, data_len)?);
@@ -638,11 +657,99 @@ fn read_bytes_array<R: Read>(
     Ok(make_array(array_data))
 }
 
+/// null buffer is present
+const HAS_NULL_BUFFER: usize = 1;
+
+/// offsets are written with `write_packed_offsets`, otherwise they are written
+/// as one varint length per row (legacy format)
+const PACKED_OFFSETS: usize = 2;
+
+/// writes offsets as frame-of-reference bit-packed value lengths:
+///  [min_len: varint][bit_width: u8][bit-packed (len - min_len) values]
+fn write_packed_offsets<W: Write>(offsets: &[i32], output: &mut W) -> Result<()> {
+    let lens = || offsets.windows(2).map(|w| (w[1] - w[0]) as u32);
+    let min_len = lens().min().unwrap_or(0);
+    let max_len = lens().max().unwrap_or(0);
+    let bit_width = 32 - (max_len - min_len).leading_zeros() as usize;
+    write_len(min_len as usize, output)?;
+    write_u8(bit_width as u8, output)?;
+
+    if bit_width > 0 {
+        let mut packed = Vec::with_capacity((offsets.len() * bit_width + 7) / 8);
+        let mut acc = 0u64;
+        let mut acc_bits = 0;
+        for len in lens() {
+            acc |= ((len - min_len) as u64) << acc_bits;
+            acc_bits += bit_width;
+            while acc_bits >= 8 {
+                packed.push(acc as u8);
+                acc >>= 8;
+                acc_bits -= 8;
+            }
+        }
+        if acc_bits > 0 {
+            packed.push(acc as u8);
+        }
+        output.write_all(&packed)?;
+    }
+    Ok(())
+}
+
+/// reads offsets written by `write_packed_offsets`, returns the offsets buffer
+/// and the last offset
+fn read_packed_offsets<R: Read>(num_rows: usize, input: &mut R) -> Result<(Buffer, usize)> {
+    let min_len = read_len(input)?;
+    let bit_width = read_u8(input)? as usize;
+    if bit_width > 32 {
+        return Err(DataFusionError::Execution(format!(
+            "batch_serde: invalid offsets bit width: {bit_width}"
+        )));
+    }
+    let packed = read_bytes_slice(input, (num_rows * bit_width + 7) / 8)?;
+    let mut packed_iter = packed.iter();
+    let mask = (1u64 << bit_width) - 1;
+
+    let mut cur_offset = 0;
+    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
+    offsets_buffer.push(0u32);
+    let mut acc = 0u64;
+    let mut acc_bits = 0;
+    for _ in 0..num_rows {
+        while acc_bits < bit_width {
+            acc |= (*packed_iter.next().unwrap() as u64) << acc_bits;
+            acc_bits += 8;
+        }
+        let len = min_len + (acc & mask) as usize;
+        acc >>= bit_width;
+        acc_bits -= bit_width;
+
+        cur_offset += len;
+        offsets_buffer.push(cur_offset as u32);
+    }
+    Ok((offsets_buffer.into(), cur_offset))
+}
+
+/// reads offsets written as one varint length per row
+fn read_legacy_offsets<R: Read>(num_rows: usize, input: &mut R) -> Result<(Buffer, usize)> {
+    let mut cur_offset = 0;
+    let mut offsets_buffer = MutableBuffer::new((num_rows + 1) * 4);
+    offsets_buffer.push(0u32);
+    for _ in 0..num_rows {
+        let len = read_len(input)?;
+        let offset = cur_offset + len;
+        offsets_buffer.push(offset as u32);
+        cur_offset = offset;
+    }
+    Ok((offsets_buffer.into(), cur_offset))
+}
+
 #[cfg(test)]
 mod test {
-    use crate::io::batch_serde::{read_batch, write_batch};
+    use crate::io::batch_serde::{read_array, read_batch, write_array, write_batch};
     use crate::io::name_batch;
+    use crate::io::{write_len, IpcCompressionCodec};
     use arrow::array::*;
+    use arrow::buffer::Buffer;
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
     use std::io::Cursor;
@@ -677,17 +784,73 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(&batch, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(&sliced, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
+        assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
+    }
+
+    #[test]
+    fn test_write_and_read_batch_with_codecs() {
+        let array1: ArrayRef = Arc::new(Int64Array::from_iter([Some(1), None, Some(3)]));
+        let array2: ArrayRef = Arc::new(Float16Array::from(
+            ArrayData::builder(DataType::Float16)
+                .len(3)
+                .add_buffer(Buffer::from(&[0x3c00u16, 0, 0xc000].to_byte_slice()))
+                .null_bit_buffer(Some(Buffer::from(&[0b101])))
+                .build()
+                .unwrap(),
+        ));
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("i64", array1, true),
+            ("f16", array2, true),
+        ])
+        .unwrap();
+
+        for codec in [
+            IpcCompressionCodec::Uncompressed,
+            IpcCompressionCodec::Lz4,
+            IpcCompressionCodec::Zstd(9),
+        ] {
+            let mut buf = vec![];
+            write_batch(&batch, &mut buf, codec, None).unwrap();
+            let mut cursor = Cursor::new(buf);
+            let decoded_batch = read_batch(&mut cursor, codec).unwrap();
+            assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
+        }
+    }
+
+    #[test]
+    fn test_write_and_read_batch_for_dictionary() {
+        let dict_array: ArrayRef = Arc::new(
+            vec![Some("a"), Some("a"), None, Some("b"), Some("a")]
+                .into_iter()
+                .collect::<DictionaryArray<Int32Type>>(),
+        );
+        let batch =
+            RecordBatch::try_from_iter_with_nullable(vec![("dict", dict_array, true)]).unwrap();
+
+        // test read after write
+        let mut buf = vec![];
+        write_batch(&batch, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
+        let mut cursor = Cursor::new(buf);
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
+        assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
+
+        // test read after write sliced
+        let sliced = batch.slice(1, 3);
+        let mut buf = vec![];
+        write_batch(&sliced, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
+        let mut cursor = Cursor::new(buf);
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
 
@@ -709,17 +872,17 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(&batch, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(&sliced, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
 
@@ -751,17 +914,17 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(&batch, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(&sliced, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
 
@@ -783,17 +946,56 @@ mod test {
 
         // test read after write
         let mut buf = vec![];
-        write_batch(&batch, &mut buf, true, None).unwrap();
+        write_batch(&batch, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &batch.schema()).unwrap(), batch);
 
         // test read after write sliced
         let sliced = batch.slice(1, 2);
         let mut buf = vec![];
-        write_batch(&sliced, &mut buf, true, None).unwrap();
+        write_batch(&sliced, &mut buf, IpcCompressionCodec::Zstd(1), None).unwrap();
         let mut cursor = Cursor::new(buf);
-        let decoded_batch = read_batch(&mut cursor, true).unwrap();
+        let decoded_batch = read_batch(&mut cursor, IpcCompressionCodec::Zstd(1)).unwrap();
         assert_eq!(name_batch(decoded_batch, &sliced.schema()).unwrap(), sliced);
     }
+
+    #[test]
+    fn test_write_and_read_packed_offsets() {
+        let strings = [
+            vec!["", "", ""],
+            vec!["same", "size", "here"],
+            vec!["a", "", "bcdefghijklmnopqrstuvwxyz", "0123456789"],
+        ];
+        for strings in strings {
+            let array: ArrayRef = Arc::new(StringArray::from(strings));
+            let mut buf = vec![];
+            write_array(&array, &mut buf).unwrap();
+            let decoded = read_array(&mut Cursor::new(buf), &DataType::Utf8, array.len()).unwrap();
+            assert_eq!(&decoded, &array);
+        }
+
+        // large lengths
+        let long_string = "x".repeat(100000);
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["", &long_string, "y"]));
+        let mut buf = vec![];
+        write_array(&array.slice(1, 2), &mut buf).unwrap();
+        let decoded = read_array(&mut Cursor::new(buf), &DataType::Utf8, 2).unwrap();
+        assert_eq!(&decoded, &array.slice(1, 2));
+    }
+
+    #[test]
+    fn test_read_legacy_offsets() {
+        // legacy format: no null buffer, one varint length per row, then data
+        let mut buf = vec![];
+        write_len(0, &mut buf).unwrap();
+        for len in [3, 0, 5] {
+            write_len(len, &mut buf).unwrap();
+        }
+        buf.extend_from_slice(b"abcdefgh");
+
+        let decoded = read_array(&mut Cursor::new(buf), &DataType::Utf8, 3).unwrap();
+        let expected: ArrayRef = Arc::new(StringArray::from(vec!["abc", "", "defgh"]));
+        assert_eq!(&decoded, &expected);
+    }
 }
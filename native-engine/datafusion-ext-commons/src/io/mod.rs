@@ -14,21 +14,56 @@
 
 use arrow::array::StructArray;
 
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use arrow::datatypes::{DataType, SchemaRef};
 use arrow::record_batch::RecordBatch;
 pub use batch_serde::{read_array, read_data_type, write_array, write_data_type};
 use datafusion::common::cast::as_struct_array;
-use datafusion::common::Result;
+use datafusion::common::{DataFusionError, Result};
 
 mod batch_serde;
 
+/// compression codec of serialized batches, readers must use the same codec
+/// as the writer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpcCompressionCodec {
+    Uncompressed,
+    Lz4,
+    Zstd(i32),
+}
+
+impl IpcCompressionCodec {
+    pub const DEFAULT_ZSTD_LEVEL: i32 = 1;
+
+    pub fn from_compress(compress: bool) -> Self {
+        if compress {
+            Self::Zstd(Self::DEFAULT_ZSTD_LEVEL)
+        } else {
+            Self::Uncompressed
+        }
+    }
+}
+
 pub fn write_one_batch<W: Write + Seek>(
     batch: &RecordBatch,
     output: &mut W,
     compress: bool,
     uncompressed_size: Option<&mut usize>,
+) -> Result<usize> {
+    write_one_batch_with_codec(
+        batch,
+        output,
+        IpcCompressionCodec::from_compress(compress),
+        uncompressed_size,
+    )
+}
+
+pub fn write_one_batch_with_codec<W: Write + Seek>(
+    batch: &RecordBatch,
+    output: &mut W,
+    codec: IpcCompressionCodec,
+    uncompressed_size: Option<&mut usize>,
 ) -> Result<usize> {
     if batch.num_rows() == 0 {
         return Ok(0);
@@ -38,7 +73,7 @@ pub fn write_one_batch<W: Write + Seek>(
     output.write_all(&[0u8; 8])?;
 
     // write
-    batch_serde::write_batch(batch, output, compress, uncompressed_size)?;
+    batch_serde::write_batch(batch, output, codec, uncompressed_size)?;
     let end_pos = output.stream_position()?;
     let ipc_length = end_pos - start_pos - 8;
 
@@ -53,20 +88,24 @@ pub fn read_one_batch<R: Read>(
     input: &mut R,
     schema: Option<SchemaRef>,
     compress: bool,
+) -> Result<Option<RecordBatch>> {
+    read_one_batch_with_codec(input, schema, IpcCompressionCodec::from_compress(compress))
+}
+
+pub fn read_one_batch_with_codec<R: Read>(
+    input: &mut R,
+    schema: Option<SchemaRef>,
+    codec: IpcCompressionCodec,
 ) -> Result<Option<RecordBatch>> {
     // read ipc length
-    let mut ipc_length_buf = [0u8; 8];
-    if let Err(e) = input.read_exact(&mut ipc_length_buf) {
-        if e.kind() == std::io::ErrorKind::UnexpectedEof {
-            return Ok(None);
-        }
-        return Err(e.into());
-    }
-    let ipc_length = u64::from_le_bytes(ipc_length_buf);
+    let ipc_length = match read_ipc_length(input)? {
+        Some(ipc_length) => ipc_length,
+        None => return Ok(None),
+    };
     let mut input = Box::new(input.take(ipc_length));
 
     // read
-    let nameless_batch = batch_serde::read_batch(&mut input, compress)?;
+    let nameless_batch = batch_serde::read_batch(&mut input, codec)?;
 
     // consume trailing bytes
     std::io::copy(&mut input, &mut std::io::sink())?;
@@ -78,6 +117,55 @@ pub fn read_one_batch<R: Read>(
     Ok(Some(nameless_batch))
 }
 
+/// appends crc32 checksum of the payload to an ipc frame written by
+/// `write_one_batch`
+pub fn append_ipc_checksum(frame: &mut Vec<u8>) {
+    let checksum = crc32fast::hash(&frame[8..]);
+    frame.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// reads a batch from an ipc frame with trailing checksum (see
+/// `append_ipc_checksum`), the payload is verified before decoding
+pub fn read_one_batch_with_checksum<R: Read>(
+    input: &mut R,
+    schema: Option<SchemaRef>,
+    codec: IpcCompressionCodec,
+) -> Result<Option<RecordBatch>> {
+    let ipc_length = match read_ipc_length(input)? {
+        Some(ipc_length) => ipc_length,
+        None => return Ok(None),
+    };
+    let mut payload = vec![0u8; ipc_length as usize];
+    input.read_exact(&mut payload)?;
+    let mut checksum_buf = [0u8; 4];
+    input.read_exact(&mut checksum_buf)?;
+
+    let expected_checksum = u32::from_le_bytes(checksum_buf);
+    let checksum = crc32fast::hash(&payload);
+    if checksum != expected_checksum {
+        return Err(DataFusionError::Execution(format!(
+            "ipc checksum mismatch: expected {expected_checksum:#010x}, got {checksum:#010x}"
+        )));
+    }
+    let nameless_batch = batch_serde::read_batch(&mut Cursor::new(payload), codec)?;
+
+    if let Some(schema) = schema.as_ref() {
+        return Ok(Some(name_batch(nameless_batch, schema)?));
+    }
+    Ok(Some(nameless_batch))
+}
+
+fn read_ipc_length<R: Read>(input: &mut R) -> Result<Option<u64>> {
+    let mut ipc_length_buf = [0u8; 8];
+    if let Err(e) = input.read_exact(&mut ipc_length_buf) {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(e.into());
+    }
+    Ok(Some(u64::from_le_bytes(ipc_length_buf)))
+}
+
 pub fn name_batch(batch: RecordBatch, name_schema: &SchemaRef) -> Result<RecordBatch> {
     Ok(RecordBatch::from(as_struct_array(&crate::cast::cast(
         &StructArray::from(batch),
@@ -127,3 +215,38 @@ pub fn read_bytes_slice<R: Read>(input: &mut R, len: usize) -> Result<Box<[u8]>>
     input.read_exact(byte_slice.as_mut())?;
     Ok(byte_slice)
 }
+
+#[cfg(test)]
+mod test {
+    use crate::io::{
+        append_ipc_checksum, read_one_batch_with_checksum, write_one_batch_with_codec,
+        IpcCompressionCodec,
+    };
+    use arrow::array::*;
+    use arrow::record_batch::RecordBatch;
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_batch_with_checksum() {
+        let array: ArrayRef = Arc::new(StringArray::from_iter([Some("a"), None, Some("ccc")]));
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![("str", array, true)]).unwrap();
+        let codec = IpcCompressionCodec::Lz4;
+
+        let mut frame = vec![];
+        write_one_batch_with_codec(&batch, &mut Cursor::new(&mut frame), codec, None).unwrap();
+        append_ipc_checksum(&mut frame);
+
+        let mut cursor = Cursor::new(&frame);
+        let decoded = read_one_batch_with_checksum(&mut cursor, Some(batch.schema()), codec);
+        assert_eq!(decoded.unwrap(), Some(batch.clone()));
+        let decoded = read_one_batch_with_checksum(&mut cursor, Some(batch.schema()), codec);
+        assert_eq!(decoded.unwrap(), None);
+
+        // corrupted payload
+        frame[10] ^= 0xff;
+        let mut cursor = Cursor::new(&frame);
+        let err = read_one_batch_with_checksum(&mut cursor, Some(batch.schema()), codec);
+        assert!(err.unwrap_err().to_string().contains("checksum mismatch"));
+    }
+}
@@ -21,14 +21,20 @@ use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use log::trace;
 
 pub mod array_builder;
+pub mod cancellation;
 pub mod cast;
+pub mod datetime_rebase;
+pub mod error_context;
 pub mod ffi;
 pub mod hadoop_fs;
+pub mod hyperloglog;
 pub mod io;
 pub mod loser_tree;
 pub mod spark_hash;
+pub mod spark_random;
 pub mod streams;
 pub mod uda;
+pub mod unsafe_row;
 
 /// Concatenates an array of `RecordBatch` into one batch
 pub fn concat_batches(
@@ -18,13 +18,13 @@ use std::sync::Arc;
 
 use arrow::array::*;
 use arrow::datatypes::{
-    ArrowDictionaryKeyType, ArrowNativeType, DataType, Int16Type, Int32Type, Int64Type, Int8Type,
-    TimeUnit,
+    ArrowDictionaryKeyType, ArrowNativeType, DataType, Float16Type, Float32Type, Int16Type,
+    Int32Type, Int64Type, Int8Type, TimeUnit,
 };
 use datafusion::error::{DataFusionError, Result};
 
 #[inline]
-fn spark_compatible_murmur3_hash<T: AsRef<[u8]>>(data: T, seed: u32) -> u32 {
+pub(crate) fn spark_compatible_murmur3_hash<T: AsRef<[u8]>>(data: T, seed: u32) -> u32 {
     #[inline]
     fn mix_k1(mut k1: i32) -> i32 {
         k1 *= 0xcc9e2d51u32 as i32;
@@ -167,18 +167,42 @@ macro_rules! hash_list_primitive {
     };
 }
 
+/// spark hashes decimals with precision <= 18 as unscaled long values, and
+/// larger decimals as the bytes of `BigInteger.toByteArray()` of the unscaled
+/// value (minimal big-endian two's complement)
+#[inline]
+fn spark_compatible_decimal_hash(value: i128, precision: u8, seed: u32) -> u32 {
+    if precision <= 18 {
+        return spark_compatible_murmur3_hash((value as i64).to_le_bytes(), seed);
+    }
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = match bytes[start] {
+            0x00 => bytes[start + 1] & 0x80 == 0,
+            0xff => bytes[start + 1] & 0x80 != 0,
+            _ => false,
+        };
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    spark_compatible_murmur3_hash(&bytes[start..], seed)
+}
+
 macro_rules! hash_array_decimal {
-    ($array_type:ident, $column: ident, $hashes: ident) => {
+    ($array_type:ident, $column: ident, $precision: expr, $hashes: ident) => {
         let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
 
         if array.null_count() == 0 {
             for (i, hash) in $hashes.iter_mut().enumerate() {
-                *hash = spark_compatible_murmur3_hash(array.value(i).to_le_bytes(), *hash);
+                *hash = spark_compatible_decimal_hash(array.value(i), $precision, *hash);
             }
         } else {
             for (i, hash) in $hashes.iter_mut().enumerate() {
                 if !array.is_null(i) {
-                    *hash = spark_compatible_murmur3_hash(array.value(i).to_le_bytes(), *hash);
+                    *hash = spark_compatible_decimal_hash(array.value(i), $precision, *hash);
                 }
             }
         }
@@ -186,23 +210,28 @@ macro_rules! hash_array_decimal {
 }
 
 macro_rules! hash_list_decimal {
-    ($array_type:ident, $column: ident, $hash: ident) => {
+    ($array_type:ident, $column: ident, $precision: expr, $hash: ident) => {
         let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
 
         if array.null_count() == 0 {
             for i in 0..array.len() {
-                *$hash = spark_compatible_murmur3_hash(array.value(i).to_le_bytes(), *$hash);
+                *$hash = spark_compatible_decimal_hash(array.value(i), $precision, *$hash);
             }
         } else {
             for i in 0..array.len() {
                 if !array.is_null(i) {
-                    *$hash = spark_compatible_murmur3_hash(array.value(i).to_le_bytes(), *$hash);
+                    *$hash = spark_compatible_decimal_hash(array.value(i), $precision, *$hash);
                 }
             }
         }
     };
 }
 
+/// spark has no half float type, float16 values are hashed as widened float32
+fn widen_float16(array: &dyn Array) -> ArrayRef {
+    Arc::new(as_primitive_array::<Float16Type>(array).unary::<_, Float32Type>(|v| v.to_f32()))
+}
+
 /// Hash the values in a dictionary array
 fn create_hashes_dictionary<K: ArrowDictionaryKeyType>(
     array: &ArrayRef,
@@ -276,6 +305,10 @@ pub fn create_hashes<'a>(
             DataType::Int64 => {
                 hash_array_primitive!(Int64Array, col, i64, hashes_buffer);
             }
+            DataType::Float16 => {
+                let col = widen_float16(col);
+                hash_array_primitive!(Float32Array, col, f32, hashes_buffer);
+            }
             DataType::Float32 => {
                 hash_array_primitive!(Float32Array, col, f32, hashes_buffer);
             }
@@ -312,8 +345,8 @@ pub fn create_hashes<'a>(
             DataType::LargeUtf8 => {
                 hash_array!(LargeStringArray, col, hashes_buffer);
             }
-            DataType::Decimal128(_, _) => {
-                hash_array_decimal!(Decimal128Array, col, hashes_buffer);
+            DataType::Decimal128(precision, _) => {
+                hash_array_decimal!(Decimal128Array, col, *precision, hashes_buffer);
             }
             DataType::Dictionary(index_type, _) => match **index_type {
                 DataType::Int8 => {
@@ -374,6 +407,10 @@ pub fn create_hashes<'a>(
                         DataType::Int64 => {
                             hash_list_primitive!(Int64Array, sub_array, i64, hash);
                         }
+                        DataType::Float16 => {
+                            let sub_array = widen_float16(sub_array);
+                            hash_list_primitive!(Float32Array, sub_array, f32, hash);
+                        }
                         DataType::Float32 => {
                             hash_list_primitive!(Float32Array, sub_array, f32, hash);
                         }
@@ -410,8 +447,8 @@ pub fn create_hashes<'a>(
                         DataType::LargeUtf8 => {
                             hash_list!(LargeStringArray, sub_array, hash);
                         }
-                        DataType::Decimal128(_, _) => {
-                            hash_list_decimal!(Decimal128Array, sub_array, hash);
+                        DataType::Decimal128(precision, _) => {
+                            hash_list_decimal!(Decimal128Array, sub_array, *precision, hash);
                         }
                         _ => {
                             return Err(DataFusionError::Internal(format!(
@@ -472,9 +509,9 @@ macro_rules! hash_map_binary {
 }
 
 macro_rules! hash_map_decimal {
-    ($array_type:ident, $column: ident, $hash: ident, $idx: ident) => {
+    ($array_type:ident, $column: ident, $precision: expr, $hash: ident, $idx: ident) => {
         let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
-        *$hash = spark_compatible_murmur3_hash(array.value($idx as usize).to_le_bytes(), *$hash);
+        *$hash = spark_compatible_decimal_hash(array.value($idx as usize), $precision, *$hash);
     };
 }
 
@@ -505,6 +542,13 @@ fn update_map_hashes(array: &ArrayRef, idx: i32, hash: &mut u32) -> Result<()> {
             DataType::Int64 => {
                 hash_map_primitive!(Int64Array, array, i64, hash, idx);
             }
+            DataType::Float16 => {
+                let array = as_primitive_array::<Float16Type>(array);
+                *hash = spark_compatible_murmur3_hash(
+                    array.value(idx as usize).to_f32().to_le_bytes(),
+                    *hash,
+                );
+            }
             DataType::Float32 => {
                 hash_map_primitive!(Float32Array, array, f32, hash, idx);
             }
@@ -541,8 +585,8 @@ fn update_map_hashes(array: &ArrayRef, idx: i32, hash: &mut u32) -> Result<()> {
             DataType::LargeUtf8 => {
                 hash_map_binary!(LargeStringArray, array, hash, idx);
             }
-            DataType::Decimal128(_, _) => {
-                hash_map_decimal!(Decimal128Array, array, hash, idx);
+            DataType::Decimal128(precision, _) => {
+                hash_map_decimal!(Decimal128Array, array, *precision, hash, idx);
             }
             _ => {
                 return Err(DataFusionError::Internal(format!(
@@ -555,6 +599,113 @@ fn update_map_hashes(array: &ArrayRef, idx: i32, hash: &mut u32) -> Result<()> {
     Ok(())
 }
 
+/// Creates hive-compatible hash values (like spark's HiveHash) for every row,
+/// based on the values of all the columns. null values are hashed to 0.
+pub fn create_hive_hashes<'a>(
+    arrays: &[ArrayRef],
+    hashes_buffer: &'a mut Vec<i32>,
+) -> Result<&'a mut Vec<i32>> {
+    macro_rules! hive_hash_array {
+        ($array_type:ident, $column:ident, $hash_fn:expr) => {{
+            let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+            for (i, hash) in hashes_buffer.iter_mut().enumerate() {
+                let value_hash = if array.is_valid(i) {
+                    $hash_fn(array.value(i))
+                } else {
+                    0
+                };
+                *hash = hash.wrapping_mul(31).wrapping_add(value_hash);
+            }
+        }};
+    }
+
+    for col in arrays {
+        match col.data_type() {
+            DataType::Null => {
+                for hash in hashes_buffer.iter_mut() {
+                    *hash = hash.wrapping_mul(31);
+                }
+            }
+            DataType::Boolean => hive_hash_array!(BooleanArray, col, |v: bool| v as i32),
+            DataType::Int8 => hive_hash_array!(Int8Array, col, |v: i8| v as i32),
+            DataType::Int16 => hive_hash_array!(Int16Array, col, |v: i16| v as i32),
+            DataType::Int32 => hive_hash_array!(Int32Array, col, |v: i32| v),
+            DataType::Int64 => hive_hash_array!(Int64Array, col, hive_hash_long),
+            DataType::Date32 => hive_hash_array!(Date32Array, col, |v: i32| v),
+            DataType::Float32 => hive_hash_array!(Float32Array, col, |v: f32| {
+                if v == 0.0 {
+                    0 // -0.0 and 0.0 are hashed to the same value
+                } else {
+                    v.to_bits() as i32
+                }
+            }),
+            DataType::Float64 => hive_hash_array!(Float64Array, col, |v: f64| {
+                if v == 0.0 {
+                    0
+                } else {
+                    hive_hash_long(v.to_bits() as i64)
+                }
+            }),
+            DataType::Utf8 => {
+                hive_hash_array!(StringArray, col, |v: &str| hive_hash_bytes(v.as_bytes()))
+            }
+            DataType::LargeUtf8 => {
+                hive_hash_array!(LargeStringArray, col, |v: &str| hive_hash_bytes(
+                    v.as_bytes()
+                ))
+            }
+            DataType::Binary => hive_hash_array!(BinaryArray, col, hive_hash_bytes),
+            DataType::LargeBinary => hive_hash_array!(LargeBinaryArray, col, hive_hash_bytes),
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                hive_hash_array!(TimestampMicrosecondArray, col, hive_hash_timestamp)
+            }
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported data type in hive hasher: {}",
+                    col.data_type()
+                )));
+            }
+        }
+    }
+    Ok(hashes_buffer)
+}
+
+fn hive_hash_long(v: i64) -> i32 {
+    (v ^ ((v as u64) >> 32) as i64) as i32
+}
+
+fn hive_hash_bytes(bytes: &[u8]) -> i32 {
+    bytes.iter().fold(0i32, |hash, &b| {
+        hash.wrapping_mul(31).wrapping_add(b as i8 as i32)
+    })
+}
+
+fn hive_hash_timestamp(micros: i64) -> i32 {
+    let seconds = micros / 1_000_000;
+    let nanos = (micros % 1_000_000) * 1000;
+    let result = (seconds << 30) | nanos;
+    hive_hash_long(result)
+}
+
+/// default seed of spark's Murmur3Hash, also used by HashPartitioning
+pub const SPARK_MURMUR3_DEFAULT_SEED: u32 = 42;
+
+/// Creates spark murmur3 hash values with the default seed for every row
+pub fn create_murmur3_hashes(arrays: &[ArrayRef], num_rows: usize) -> Result<Vec<u32>> {
+    let mut hashes = vec![SPARK_MURMUR3_DEFAULT_SEED; num_rows];
+    create_hashes(arrays, &mut hashes)?;
+    Ok(hashes)
+}
+
+/// Maps hash values to partition ids, identical to spark's HashPartitioning:
+/// `pmod(hash, num_partitions)`
+pub fn create_partition_ids(hashes: &[u32], num_partitions: usize) -> Vec<u32> {
+    hashes
+        .iter()
+        .map(|&hash| pmod(hash, num_partitions) as u32)
+        .collect()
+}
+
 pub fn pmod(hash: u32, n: usize) -> usize {
     let hash = hash as i32;
     let n = n as i32;
@@ -567,10 +718,13 @@ pub fn pmod(hash: u32, n: usize) -> usize {
 mod tests {
     use std::sync::Arc;
 
-    use crate::spark_hash::{create_hashes, pmod, spark_compatible_murmur3_hash};
+    use crate::spark_hash::{
+        create_hashes, create_hive_hashes, create_murmur3_hashes, create_partition_ids, pmod,
+        spark_compatible_murmur3_hash,
+    };
     use arrow::array::{
-        make_array, Array, ArrayData, ArrayRef, Int32Array, Int64Array, Int8Array, MapArray,
-        StringArray, StructArray, UInt32Array,
+        make_array, Array, ArrayData, ArrayRef, Float16Array, Float32Array, Int32Array, Int64Array,
+        Int8Array, MapArray, StringArray, StructArray, UInt32Array,
     };
     use arrow::buffer::Buffer;
     use arrow::datatypes::{DataType, Field, ToByteSlice};
@@ -633,6 +787,73 @@ mod tests {
         assert_eq!(hashes, expected);
     }
 
+    #[test]
+    fn test_f16() {
+        // half float bits of [1.5, 0.0, null, -65504.0, inf]
+        let f16_data = ArrayData::builder(DataType::Float16)
+            .len(5)
+            .add_buffer(Buffer::from(
+                &[0x3e00u16, 0, 0, 0xfbff, 0x7c00].to_byte_slice(),
+            ))
+            .null_bit_buffer(Some(Buffer::from(&[0b11011])))
+            .build()
+            .unwrap();
+        let f16_array = Arc::new(Float16Array::from(f16_data)) as ArrayRef;
+        let f32_array = Arc::new(Float32Array::from(vec![
+            Some(1.5),
+            Some(0.0),
+            None,
+            Some(-65504.0),
+            Some(f32::INFINITY),
+        ])) as ArrayRef;
+
+        let mut hashes = vec![42; 5];
+        create_hashes(&[f16_array], &mut hashes).unwrap();
+        let mut expected = vec![42; 5];
+        create_hashes(&[f32_array], &mut expected).unwrap();
+        assert_eq!(hashes, expected);
+    }
+
+    #[test]
+    fn test_decimal() {
+        // precision <= 18, hashed as unscaled long values like spark
+        let values = [Some(1), Some(0), Some(-1), Some(i64::MAX as i128), Some(i64::MIN as i128)];
+        let d = Arc::new(
+            Decimal128Array::from(values.to_vec())
+                .with_precision_and_scale(18, 2)
+                .unwrap(),
+        ) as ArrayRef;
+        let mut hashes = vec![42; 5];
+        create_hashes(&[d], &mut hashes).unwrap();
+
+        // same as test_i64
+        let expected = vec![0x99f0149d, 0x9c67b85d, 0xc8008529, 0xa05b5d7b, 0xcd1e64fb];
+        assert_eq!(hashes, expected);
+
+        // precision > 18, hashed as bytes of BigInteger.toByteArray() like spark
+        let values = [
+            Some(1),
+            Some(0),
+            Some(-1),
+            Some(10i128.pow(37)),
+            Some(-10i128.pow(37)),
+            Some(1i128 << 63),
+            None,
+        ];
+        let d = Arc::new(
+            Decimal128Array::from(values.to_vec())
+                .with_precision_and_scale(38, 10)
+                .unwrap(),
+        ) as ArrayRef;
+        let mut hashes = vec![42; 7];
+        create_hashes(&[d], &mut hashes).unwrap();
+
+        // generated with Spark Murmur3_x86_32
+        let expected =
+            vec![0xe8f30d16, 0xd1497b27, 0x535b391c, 0x0ce5d0a0, 0x5718398d, 0x582a6e38, 42];
+        assert_eq!(hashes, expected);
+    }
+
     #[test]
     fn test_str() {
         let i = Arc::new(StringArray::from(vec!["hello", "bar", "", "😁", "天地"]));
@@ -654,6 +875,22 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_partition_ids() {
+        let i = Arc::new(Int64Array::from(vec![
+            Some(1),
+            Some(0),
+            Some(-1),
+            Some(i64::MAX),
+            Some(i64::MIN),
+        ])) as ArrayRef;
+        let hashes = create_murmur3_hashes(&[i], 5).unwrap();
+        let partition_ids = create_partition_ids(&hashes, 200);
+
+        // expected partition from Spark with n=200
+        assert_eq!(partition_ids, vec![69, 5, 193, 171, 115]);
+    }
+
     #[test]
     fn test_map_array() {
         // Construct key and values
@@ -766,4 +1003,26 @@ mod tests {
                 .unwrap()
         );
     }
+    #[test]
+    fn test_hive_hash() {
+        let i = Arc::new(Int32Array::from(vec![Some(1), None, Some(-1)])) as ArrayRef;
+        let j = Arc::new(Int64Array::from(vec![Some(1), Some(i64::MAX), Some(-1)])) as ArrayRef;
+        let s = Arc::new(StringArray::from(vec![Some("hello"), Some(""), None])) as ArrayRef;
+
+        let mut hashes = vec![0; 3];
+        create_hive_hashes(&[i.clone()], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![1, 0, -1]);
+
+        let mut hashes = vec![0; 3];
+        create_hive_hashes(&[j.clone()], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![1, -2147483648, 0]);
+
+        let mut hashes = vec![0; 3];
+        create_hive_hashes(&[s.clone()], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![99162322, 0, 0]);
+
+        let mut hashes = vec![0; 3];
+        create_hive_hashes(&[i, j, s], &mut hashes).unwrap();
+        assert_eq!(hashes, vec![99163314, -2147483648, -961]);
+    }
 }
@@ -0,0 +1,239 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Spark-compatible random number generators
+
+use crate::spark_hash::spark_compatible_murmur3_hash;
+
+/// scala.util.hashing.MurmurHash3.arraySeed
+const MURMUR3_ARRAY_SEED: u32 = 0x3c074a61;
+
+/// port of spark's XORShiftRandom (including java.util.Random's derived
+/// methods), generates exactly the same sequence as spark with the same seed.
+#[derive(Debug, Clone)]
+pub struct XorShiftRandom {
+    seed: i64,
+    next_next_gaussian: Option<f64>,
+}
+
+impl XorShiftRandom {
+    pub fn new(init: i64) -> Self {
+        Self {
+            seed: Self::hash_seed(init),
+            next_next_gaussian: None,
+        }
+    }
+
+    fn hash_seed(seed: i64) -> i64 {
+        // scala's MurmurHash3.bytesHash() is identical to spark's murmur3 hash
+        // for 8-byte inputs
+        let bytes = seed.to_be_bytes();
+        let low_bits = spark_compatible_murmur3_hash(bytes, MURMUR3_ARRAY_SEED);
+        let high_bits = spark_compatible_murmur3_hash(bytes, low_bits);
+        ((high_bits as i64) << 32) | (low_bits as i64 & 0xFFFFFFFF)
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        let mut next_seed = self.seed ^ (self.seed << 21);
+        next_seed ^= ((next_seed as u64) >> 35) as i64;
+        next_seed ^= next_seed << 4;
+        self.seed = next_seed;
+        (next_seed & ((1i64 << bits) - 1)) as i32
+    }
+
+    pub fn next_long(&mut self) -> i64 {
+        ((self.next(32) as i64) << 32).wrapping_add(self.next(32) as i64)
+    }
+
+    pub fn next_double(&mut self) -> f64 {
+        let bits = ((self.next(26) as i64) << 27) + self.next(27) as i64;
+        bits as f64 * (1.0 / (1i64 << 53) as f64)
+    }
+
+    pub fn next_gaussian(&mut self) -> f64 {
+        if let Some(next_next_gaussian) = self.next_next_gaussian.take() {
+            return next_next_gaussian;
+        }
+        loop {
+            let v1 = 2.0 * self.next_double() - 1.0;
+            let v2 = 2.0 * self.next_double() - 1.0;
+            let s = v1 * v1 + v2 * v2;
+            if s < 1.0 && s != 0.0 {
+                let multiplier = (-2.0 * s.ln() / s).sqrt();
+                self.next_next_gaussian = Some(v2 * multiplier);
+                return v1 * multiplier;
+            }
+        }
+    }
+}
+
+/// port of java.util.Random, used where spark relies on its exact sequence.
+#[derive(Debug, Clone)]
+pub struct JavaRandom {
+    seed: i64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: i64 = 0x5DEECE66D;
+    const ADDEND: i64 = 0xB;
+    const MASK: i64 = (1 << 48) - 1;
+
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = (self
+            .seed
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::ADDEND))
+            & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    pub fn next_double(&mut self) -> f64 {
+        let bits = ((self.next(26) as i64) << 27) + self.next(27) as i64;
+        bits as f64 * (1.0 / (1i64 << 53) as f64)
+    }
+}
+
+/// port of commons-math3's MersenneTwister (MT19937), used by spark's
+/// RandomUUIDGenerator.
+#[derive(Clone)]
+pub struct MersenneTwister {
+    mt: [u32; Self::N],
+    mti: usize,
+}
+
+impl MersenneTwister {
+    const N: usize = 624;
+    const M: usize = 397;
+    const MAG01: [u32; 2] = [0, 0x9908b0df];
+
+    /// same as `new MersenneTwister(long seed)`, which seeds the generator
+    /// with the int array `{seed >>> 32, seed & 0xffffffff}`
+    pub fn new(seed: i64) -> Self {
+        let seed = seed as u64;
+        Self::new_with_key(&[(seed >> 32) as u32, seed as u32])
+    }
+
+    /// same as `setSeed(int[])` (init_by_array in the reference implementation)
+    pub fn new_with_key(key: &[u32]) -> Self {
+        let mut rng = Self::new_with_int_seed(19650218);
+        let mt = &mut rng.mt;
+        let mut i = 1;
+        let mut j = 0;
+        for _ in 0..Self::N.max(key.len()) {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1664525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= Self::N {
+                mt[0] = mt[Self::N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..Self::N - 1 {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1566083941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= Self::N {
+                mt[0] = mt[Self::N - 1];
+                i = 1;
+            }
+        }
+        mt[0] = 0x80000000;
+        rng
+    }
+
+    fn new_with_int_seed(seed: u32) -> Self {
+        let mut mt = [0u32; Self::N];
+        mt[0] = seed;
+        for i in 1..Self::N {
+            mt[i] = 1812433253u32
+                .wrapping_mul(mt[i - 1] ^ (mt[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Self { mt, mti: Self::N }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.mti >= Self::N {
+            let mt = &mut self.mt;
+            for k in 0..Self::N {
+                let y = (mt[k] & 0x80000000) | (mt[(k + 1) % Self::N] & 0x7fffffff);
+                mt[k] = mt[(k + Self::M) % Self::N] ^ (y >> 1) ^ Self::MAG01[(y & 1) as usize];
+            }
+            self.mti = 0;
+        }
+        let mut y = self.mt[self.mti];
+        self.mti += 1;
+
+        // tempering
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c5680;
+        y ^= (y << 15) & 0xefc60000;
+        y ^= y >> 18;
+        y
+    }
+
+    /// same as BitsStreamGenerator.nextLong()
+    pub fn next_long(&mut self) -> i64 {
+        let high = self.next_u32() as u64;
+        let low = self.next_u32() as u64;
+        ((high << 32) | low) as i64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_random::{JavaRandom, MersenneTwister, XorShiftRandom};
+
+    #[test]
+    fn test_xorshift_random() {
+        // results of spark: select rand(0), randn(0)
+        assert_eq!(XorShiftRandom::new(0).next_double(), 0.7604953758285915);
+        assert_eq!(XorShiftRandom::new(0).next_gaussian(), 1.6034991609278433);
+        assert_eq!(XorShiftRandom::new(42).next_double(), 0.619189370225301);
+    }
+
+    #[test]
+    fn test_java_random() {
+        // result of java: new Random(42).nextDouble()
+        assert_eq!(JavaRandom::new(42).next_double(), 0.7275636800328681);
+
+        // result of java: new Random(Long.MIN_VALUE).nextDouble() x 3
+        let mut random = JavaRandom::new(i64::MIN);
+        assert_eq!(random.next_double(), 0.730967787376657);
+        assert_eq!(random.next_double(), 0.24053641567148587);
+        assert_eq!(random.next_double(), 0.6374174253501083);
+    }
+
+    #[test]
+    fn test_mersenne_twister() {
+        // results of the reference implementation (mt19937ar.out)
+        let mut rng = MersenneTwister::new_with_key(&[0x123, 0x234, 0x345, 0x456]);
+        let values = (0..5).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![1067595299, 955945823, 477289528, 4107218783, 4228976476]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cancellation::{cancelled_error, CancellationToken};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::Result;
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::future::BoxFuture;
+use futures::{FutureExt, Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream which fails with a cancelled error once the token is cancelled,
+/// the inner stream is dropped immediately to release its resources.
+pub struct CancellableStream {
+    schema: SchemaRef,
+    inner: Option<SendableRecordBatchStream>,
+    cancelled: BoxFuture<'static, ()>,
+}
+
+impl CancellableStream {
+    pub fn new(inner: SendableRecordBatchStream, token: CancellationToken) -> Self {
+        Self {
+            schema: inner.schema(),
+            inner: Some(inner),
+            cancelled: async move { token.cancelled().await }.boxed(),
+        }
+    }
+}
+
+impl RecordBatchStream for CancellableStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Stream for CancellableStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.inner.is_none() {
+            return Poll::Ready(None);
+        }
+        if self.cancelled.poll_unpin(cx).is_ready() {
+            self.inner = None;
+            return Poll::Ready(Some(Err(cancelled_error())));
+        }
+        self.inner.as_mut().unwrap().poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cancellation::CancellationToken;
+    use crate::streams::cancellable_stream::CancellableStream;
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use futures::StreamExt;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_cancellable_stream() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])?;
+        let inner =
+            MemoryStream::try_new(vec![batch.clone(), batch.clone()], batch.schema(), None)?;
+        let token = CancellationToken::new();
+        let mut stream = CancellableStream::new(Box::pin(inner), token.clone());
+
+        assert_eq!(stream.next().await.transpose()?, Some(batch));
+        token.cancel();
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+        Ok(())
+    }
+}
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::unsafe_row::{is_unsafe_rows_schema, unsafe_rows_to_batch};
 use arrow::array::StructArray;
 use arrow::datatypes::SchemaRef;
 use arrow::ffi::{from_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::ffi_stream::ArrowArrayStreamReader;
 use arrow::record_batch::RecordBatch;
 use blaze_jni_bridge::{jni_call, jni_new_object};
 use datafusion::error::Result;
@@ -96,9 +98,67 @@ impl FFIReaderStream {
 
         let imported = from_ffi(ffi_arrow_array, &ffi_arrow_schema)?;
         let struct_array = StructArray::from(imported);
-        let batch = RecordBatch::from(struct_array);
+        let batch = read_unsafe_rows_if_needed(RecordBatch::from(struct_array), &self.schema)?;
 
         self.size_counter.add(batch.get_array_memory_size());
         Ok(Some(batch))
     }
 }
+
+/// reads batches from an ArrowArrayStream exported by the jvm side. unlike
+/// FFIReaderStream, schema and batches are fetched through the stream's own
+/// callbacks, so no extra jni calls are made per batch.
+pub struct FFIArrowStreamReaderStream {
+    schema: SchemaRef,
+    reader: ArrowArrayStreamReader,
+    baseline_metrics: BaselineMetrics,
+    size_counter: Count,
+}
+
+impl FFIArrowStreamReaderStream {
+    pub fn new(
+        schema: SchemaRef,
+        reader: ArrowArrayStreamReader,
+        baseline_metrics: BaselineMetrics,
+        size_counter: Count,
+    ) -> Self {
+        Self {
+            schema,
+            reader,
+            baseline_metrics,
+            size_counter,
+        }
+    }
+}
+
+impl RecordBatchStream for FFIArrowStreamReaderStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Stream for FFIArrowStreamReaderStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.reader.next() {
+            Some(Ok(batch)) => {
+                let batch = read_unsafe_rows_if_needed(batch, &self.schema)?;
+                self.size_counter.add(batch.get_array_memory_size());
+                self.baseline_metrics
+                    .record_poll(Poll::Ready(Some(Ok(batch))))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// jvm side may export rows in UnsafeRow format instead of arrow columns, which
+/// are parsed natively into the expected schema
+fn read_unsafe_rows_if_needed(batch: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
+    if is_unsafe_rows_schema(&batch.schema()) {
+        return unsafe_rows_to_batch(batch.column(0), schema.clone());
+    }
+    Ok(batch)
+}
@@ -14,25 +14,30 @@
 
 use std::fmt::Debug;
 
-use crate::io::read_one_batch;
+use crate::io::{read_one_batch_with_checksum, read_one_batch_with_codec, IpcCompressionCodec};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use blaze_jni_bridge::{
     jni_call, jni_get_object_class, jni_get_string, jni_new_direct_byte_buffer, jni_new_global_ref,
 };
-use datafusion::error::Result;
+use datafusion::error::{DataFusionError, Result};
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
 use datafusion::physical_plan::RecordBatchStream;
 use futures::Stream;
 use jni::objects::{GlobalRef, JObject};
 use jni::sys::{jboolean, jint, jlong, JNI_TRUE};
+use memmap2::MmapOptions;
 use std::fs::File;
-use std::io::{BufReader, Read, SeekFrom};
+use std::io::{BufReader, Cursor, Read, SeekFrom};
 use std::io::{Error as IoError, Seek};
 use std::pin::Pin;
 use std::task::Context;
 use std::task::Poll;
 
+/// min size of file segments to be read with mmap, smaller segments are read
+/// with normal file reads
+const MMAP_MIN_SEGMENT_SIZE: usize = 1 << 20;
+
 #[derive(Debug, Clone, Copy)]
 pub enum IpcReadMode {
     /// for ConvertToNative
@@ -45,9 +50,23 @@ pub enum IpcReadMode {
     ChannelAndFileSegment,
 }
 
+impl IpcReadMode {
+    /// codec used when the codec is not explicitly specified
+    pub fn default_codec(&self) -> IpcCompressionCodec {
+        match self {
+            IpcReadMode::ChannelUncompressed => IpcCompressionCodec::Uncompressed,
+            IpcReadMode::Channel | IpcReadMode::ChannelAndFileSegment => {
+                IpcCompressionCodec::from_compress(true)
+            }
+        }
+    }
+}
+
 pub struct IpcReaderStream {
     schema: SchemaRef,
     mode: IpcReadMode,
+    codec: IpcCompressionCodec,
+    checksum_stream_id: Option<String>,
     segments: GlobalRef,
     reader: Option<RecordBatchReader>,
     baseline_metrics: BaselineMetrics,
@@ -60,12 +79,16 @@ impl IpcReaderStream {
         schema: SchemaRef,
         segments: GlobalRef,
         mode: IpcReadMode,
+        codec: IpcCompressionCodec,
+        checksum_stream_id: Option<String>,
         baseline_metrics: BaselineMetrics,
         size_counter: Count,
     ) -> IpcReaderStream {
         IpcReaderStream {
             schema,
             mode,
+            codec,
+            checksum_stream_id,
             segments,
             reader: None,
             baseline_metrics,
@@ -86,11 +109,15 @@ impl IpcReaderStream {
         )?;
 
         let schema = self.schema.clone();
+        let codec = self.codec;
         self.reader = Some(match self.mode {
-            IpcReadMode::ChannelUncompressed => {
-                get_channel_reader(Some(schema), segment.as_obj(), false)?
+            IpcReadMode::ChannelUncompressed | IpcReadMode::Channel => {
+                let reader = get_channel_reader(Some(schema), segment.as_obj(), codec)?;
+                match &self.checksum_stream_id {
+                    Some(stream_id) => reader.with_checksum(stream_id.clone()),
+                    None => reader,
+                }
             }
-            IpcReadMode::Channel => get_channel_reader(Some(schema), segment.as_obj(), true)?,
             IpcReadMode::ChannelAndFileSegment => {
                 let segment_class = jni_get_object_class!(segment.as_obj())?;
                 let segment_classname_obj =
@@ -98,9 +125,9 @@ impl IpcReaderStream {
                 let segment_classname = jni_get_string!(segment_classname_obj.as_obj().into())?;
 
                 if segment_classname == "org.apache.spark.storage.FileSegment" {
-                    get_file_segment_reader(Some(schema), segment.as_obj())?
+                    get_file_segment_reader(Some(schema), segment.as_obj(), codec)?
                 } else {
-                    get_channel_reader(Some(schema), segment.as_obj(), true)?
+                    get_channel_reader(Some(schema), segment.as_obj(), codec)?
                 }
             }
         });
@@ -111,7 +138,7 @@ impl IpcReaderStream {
 pub fn get_channel_reader(
     schema: Option<SchemaRef>,
     channel: JObject,
-    compressed: bool,
+    codec: IpcCompressionCodec,
 ) -> Result<RecordBatchReader> {
     let global_ref = jni_new_global_ref!(channel)?;
     let channel_reader = ReadableByteChannelReader::new(global_ref);
@@ -119,13 +146,14 @@ pub fn get_channel_reader(
     Ok(RecordBatchReader::new(
         Box::new(BufReader::with_capacity(65536, channel_reader)),
         schema,
-        compressed,
+        codec,
     ))
 }
 
 pub fn get_file_segment_reader(
     schema: Option<SchemaRef>,
     file_segment: JObject,
+    codec: IpcCompressionCodec,
 ) -> Result<RecordBatchReader> {
     let file = jni_call!(SparkFileSegment(file_segment).file() -> JObject)?;
     let path = jni_call!(JavaFile(file.as_obj()).getPath() -> JObject)?;
@@ -134,12 +162,31 @@ pub fn get_file_segment_reader(
     let length = jni_call!(SparkFileSegment(file_segment).length() -> jlong)?;
 
     let mut file = File::open(path)?;
-    file.seek(SeekFrom::Start(offset as u64))?;
 
+    // large segments are mapped into memory and decoded from the mapped pages,
+    // saving the read syscalls of filling buffers from the file. this is not
+    // zero-copy: batches are still decompressed and decoded into newly allocated
+    // arrow buffers
+    if length as usize >= MMAP_MIN_SEGMENT_SIZE {
+        // safety: shuffle/broadcast files are not modified while being read
+        let mmap = unsafe {
+            MmapOptions::new()
+                .offset(offset as u64)
+                .len(length as usize)
+                .map(&file)?
+        };
+        return Ok(RecordBatchReader::new(
+            Box::new(Cursor::new(mmap)),
+            schema,
+            codec,
+        ));
+    }
+
+    file.seek(SeekFrom::Start(offset as u64))?;
     Ok(RecordBatchReader::new(
         Box::new(file.take(length as u64)),
         schema,
-        true,
+        codec,
     ))
 }
 
@@ -232,19 +279,42 @@ impl Drop for ReadableByteChannelReader {
 pub struct RecordBatchReader {
     input: Box<dyn Read>,
     schema: Option<SchemaRef>,
-    compress: bool,
+    codec: IpcCompressionCodec,
+    checksum_stream_id: Option<String>,
 }
 
 impl RecordBatchReader {
-    pub fn new(input: Box<dyn Read>, schema: Option<SchemaRef>, compress: bool) -> Self {
+    pub fn new(
+        input: Box<dyn Read>,
+        schema: Option<SchemaRef>,
+        codec: IpcCompressionCodec,
+    ) -> Self {
         Self {
             input,
             schema,
-            compress,
+            codec,
+            checksum_stream_id: None,
         }
     }
 
+    /// verifies checksums of frames written with `append_ipc_checksum`,
+    /// stream_id is reported in errors
+    pub fn with_checksum(mut self, stream_id: String) -> Self {
+        self.checksum_stream_id = Some(stream_id);
+        self
+    }
+
     pub fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
-        read_one_batch(&mut self.input, self.schema.clone(), self.compress)
+        match &self.checksum_stream_id {
+            Some(stream_id) => {
+                read_one_batch_with_checksum(&mut self.input, self.schema.clone(), self.codec)
+                    .map_err(|err| {
+                        DataFusionError::Execution(format!(
+                            "error reading ipc stream {stream_id}: {err}"
+                        ))
+                    })
+            }
+            None => read_one_batch_with_codec(&mut self.input, self.schema.clone(), self.codec),
+        }
     }
 }
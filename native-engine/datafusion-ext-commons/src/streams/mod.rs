@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod cancellable_stream;
 pub mod coalesce_stream;
 pub mod ffi_stream;
 pub mod ipc_stream;
@@ -0,0 +1,686 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use arrow::buffer::{Buffer, MutableBuffer};
+use arrow::datatypes::ArrowPrimitiveType;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use datafusion::common::{DataFusionError, Result};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// metadata key of the converted binary field, holding number of fields of
+/// the original schema, which is required by jvm side to read UnsafeRows
+pub const UNSAFE_ROW_NUM_FIELDS_KEY: &str = "blaze.unsafe_row.num_fields";
+
+/// max precision of decimals stored in the fixed-length region
+const MAX_LONG_DIGITS: u8 = 18;
+
+/// schema of batches converted by `batch_to_unsafe_rows`
+pub fn unsafe_rows_schema(num_fields: usize) -> SchemaRef {
+    let metadata = HashMap::from([(
+        UNSAFE_ROW_NUM_FIELDS_KEY.to_string(),
+        num_fields.to_string(),
+    )]);
+    Arc::new(Schema::new(vec![Field::new(
+        "unsafe_row",
+        DataType::Binary,
+        false,
+    )
+    .with_metadata(metadata)]))
+}
+
+/// converts a batch into a batch of a single binary column, in which each value
+/// is a row in spark's UnsafeRow format. all rows are written in one aligned
+/// buffer so jvm side can point UnsafeRows to it without per-row conversion.
+pub fn batch_to_unsafe_rows(batch: &RecordBatch) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    for field in schema.fields() {
+        check_supported(field.data_type())?;
+    }
+
+    let mut buf = MutableBuffer::new(0);
+    let mut offsets: Vec<i32> = Vec::with_capacity(batch.num_rows() + 1);
+    offsets.push(0);
+    for row_idx in 0..batch.num_rows() {
+        write_row(batch.columns(), row_idx, &mut buf)?;
+        offsets.push(i32::try_from(buf.len()).map_err(|_| {
+            DataFusionError::Execution("unsafe rows exceed 2GB in one batch".to_string())
+        })?);
+    }
+
+    let rows = make_array(
+        ArrayData::builder(DataType::Binary)
+            .len(batch.num_rows())
+            .add_buffer(Buffer::from_slice_ref(&offsets))
+            .add_buffer(buf.into())
+            .build()?,
+    );
+    Ok(RecordBatch::try_new(
+        unsafe_rows_schema(batch.num_columns()),
+        vec![rows],
+    )?)
+}
+
+fn check_supported(dt: &DataType) -> Result<()> {
+    match dt {
+        DataType::Null
+        | DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Date32
+        | DataType::Timestamp(TimeUnit::Microsecond, _)
+        | DataType::Decimal128(_, _)
+        | DataType::Utf8
+        | DataType::Binary => Ok(()),
+        DataType::List(field) => check_supported(field.data_type()),
+        DataType::Struct(fields) => fields
+            .iter()
+            .try_for_each(|field| check_supported(field.data_type())),
+        DataType::Map(field, _) => check_supported(field.data_type()),
+        other => Err(DataFusionError::NotImplemented(format!(
+            "unsupported data type in unsafe row conversion: {}",
+            other
+        ))),
+    }
+}
+
+fn round_to_word(len: usize) -> usize {
+    (len + 7) / 8 * 8
+}
+
+fn bitset_width(num_fields: usize) -> usize {
+    (num_fields + 63) / 64 * 8
+}
+
+fn set_bit(buf: &mut MutableBuffer, bitset_pos: usize, i: usize) {
+    buf.as_slice_mut()[bitset_pos + i / 8] |= 1 << (i % 8);
+}
+
+fn write_slot(buf: &mut MutableBuffer, pos: usize, bytes: &[u8]) {
+    buf.as_slice_mut()[pos..][..bytes.len()].copy_from_slice(bytes);
+}
+
+/// size of each element in the fixed-length region of UnsafeArrayData
+fn element_size(dt: &DataType) -> usize {
+    match dt {
+        DataType::Boolean | DataType::Int8 => 1,
+        DataType::Int16 => 2,
+        DataType::Int32 | DataType::Float32 | DataType::Date32 => 4,
+        _ => 8,
+    }
+}
+
+/// writes an UnsafeRow at the end of buf
+fn write_row(columns: &[ArrayRef], row_idx: usize, buf: &mut MutableBuffer) -> Result<()> {
+    let base = buf.len();
+    let fixed_pos = base + bitset_width(columns.len());
+    buf.resize(fixed_pos + columns.len() * 8, 0);
+
+    for (i, column) in columns.iter().enumerate() {
+        let slot_pos = fixed_pos + i * 8;
+        if column.is_null(row_idx) {
+            set_bit(buf, base, i);
+
+            // spark always reserves 16 bytes for nullable big decimals in rows
+            if let DataType::Decimal128(prec, _) = column.data_type() {
+                if *prec > MAX_LONG_DIGITS {
+                    let cursor = buf.len();
+                    buf.resize(cursor + 16, 0);
+                    write_slot(
+                        buf,
+                        slot_pos,
+                        &(((cursor - base) as u64) << 32).to_le_bytes(),
+                    );
+                }
+            }
+            continue;
+        }
+        write_value(column, row_idx, buf, base, slot_pos, true)?;
+    }
+    Ok(())
+}
+
+/// writes an UnsafeArrayData with elements values[offset..offset + len] at the
+/// end of buf
+fn write_array(
+    values: &ArrayRef,
+    offset: usize,
+    len: usize,
+    buf: &mut MutableBuffer,
+) -> Result<()> {
+    let base = buf.len();
+    let elem_size = element_size(values.data_type());
+    let fixed_pos = base + 8 + bitset_width(len);
+    buf.resize(fixed_pos + round_to_word(len * elem_size), 0);
+    write_slot(buf, base, &(len as i64).to_le_bytes());
+
+    for i in 0..len {
+        if values.is_null(offset + i) {
+            set_bit(buf, base + 8, i);
+            continue;
+        }
+        write_value(
+            values,
+            offset + i,
+            buf,
+            base,
+            fixed_pos + i * elem_size,
+            false,
+        )?;
+    }
+    Ok(())
+}
+
+/// writes a non-null value into the slot at slot_pos. variable-length values
+/// are appended to the end of buf, the slot then holds offset (relative to
+/// base of the enclosing row/array) and size
+fn write_value(
+    array: &ArrayRef,
+    idx: usize,
+    buf: &mut MutableBuffer,
+    base: usize,
+    slot_pos: usize,
+    in_row: bool,
+) -> Result<()> {
+    macro_rules! write_primitive {
+        ($arrowty:ident) => {{
+            let value = as_primitive_array::<arrow::datatypes::$arrowty>(array).value(idx);
+            write_slot(buf, slot_pos, &value.to_le_bytes());
+        }};
+    }
+    macro_rules! write_variable {
+        ($bytes:expr) => {{
+            let bytes: &[u8] = $bytes;
+            let cursor = buf.len();
+            buf.extend_from_slice(bytes);
+            buf.resize(cursor + round_to_word(bytes.len()), 0);
+            let offset_and_size = (((cursor - base) as u64) << 32) | bytes.len() as u64;
+            write_slot(buf, slot_pos, &offset_and_size.to_le_bytes());
+        }};
+    }
+    macro_rules! write_nested {
+        ($write:expr) => {{
+            let cursor = buf.len();
+            $write;
+            let size = buf.len() - cursor;
+            let offset_and_size = (((cursor - base) as u64) << 32) | size as u64;
+            write_slot(buf, slot_pos, &offset_and_size.to_le_bytes());
+        }};
+    }
+
+    match array.data_type() {
+        DataType::Null => {}
+        DataType::Boolean => {
+            let value = as_boolean_array(array).value(idx);
+            write_slot(buf, slot_pos, &[value as u8]);
+        }
+        DataType::Int8 => write_primitive!(Int8Type),
+        DataType::Int16 => write_primitive!(Int16Type),
+        DataType::Int32 => write_primitive!(Int32Type),
+        DataType::Int64 => write_primitive!(Int64Type),
+        DataType::Float32 => {
+            // spark normalizes NaNs when writing UnsafeRows
+            let value = as_primitive_array::<arrow::datatypes::Float32Type>(array).value(idx);
+            let value = if value.is_nan() { f32::NAN } else { value };
+            write_slot(buf, slot_pos, &value.to_le_bytes());
+        }
+        DataType::Float64 => {
+            let value = as_primitive_array::<arrow::datatypes::Float64Type>(array).value(idx);
+            let value = if value.is_nan() { f64::NAN } else { value };
+            write_slot(buf, slot_pos, &value.to_le_bytes());
+        }
+        DataType::Date32 => write_primitive!(Date32Type),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            write_primitive!(TimestampMicrosecondType)
+        }
+        DataType::Decimal128(prec, _) => {
+            let value = as_primitive_array::<arrow::datatypes::Decimal128Type>(array).value(idx);
+            if *prec <= MAX_LONG_DIGITS {
+                write_slot(buf, slot_pos, &(value as i64).to_le_bytes());
+            } else {
+                // minimal big-endian two's complement bytes, like BigInteger.toByteArray()
+                let bytes = value.to_be_bytes();
+                let sign_byte = if value < 0 { 0xff } else { 0x00 };
+                let mut start = 0;
+                while start < 15
+                    && bytes[start] == sign_byte
+                    && (bytes[start + 1] & 0x80) == (sign_byte & 0x80)
+                {
+                    start += 1;
+                }
+                if in_row {
+                    // spark always reserves 16 bytes for big decimals in rows
+                    let cursor = buf.len();
+                    buf.resize(cursor + 16, 0);
+                    write_slot(buf, cursor, &bytes[start..]);
+                    let offset_and_size = (((cursor - base) as u64) << 32) | (16 - start) as u64;
+                    write_slot(buf, slot_pos, &offset_and_size.to_le_bytes());
+                } else {
+                    write_variable!(&bytes[start..]);
+                }
+            }
+        }
+        DataType::Utf8 => write_variable!(as_string_array(array).value(idx).as_bytes()),
+        DataType::Binary => write_variable!(as_generic_binary_array::<i32>(array).value(idx)),
+        DataType::List(_) => {
+            let list = as_list_array(array);
+            let offsets = list.value_offsets();
+            let start = offsets[idx] as usize;
+            let len = offsets[idx + 1] as usize - start;
+            write_nested!(write_array(list.values(), start, len, buf)?);
+        }
+        DataType::Struct(_) => {
+            let columns = as_struct_array(array).columns().to_vec();
+            write_nested!(write_row(&columns, idx, buf)?);
+        }
+        DataType::Map(_, _) => {
+            // map is written as: [key array size][key array][value array]
+            let map = as_map_array(array);
+            let offsets = map.value_offsets();
+            let start = offsets[idx] as usize;
+            let len = offsets[idx + 1] as usize - start;
+            write_nested!({
+                let map_base = buf.len();
+                buf.resize(map_base + 8, 0);
+                write_array(map.keys(), start, len, buf)?;
+                let key_array_size = buf.len() - map_base - 8;
+                write_slot(buf, map_base, &(key_array_size as i64).to_le_bytes());
+                write_array(map.values(), start, len, buf)?;
+            });
+        }
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "unsupported data type in unsafe row conversion: {}",
+                other
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// checks whether the batch schema is of rows converted by `batch_to_unsafe_rows`
+/// (or by jvm side in the same format)
+pub fn is_unsafe_rows_schema(schema: &Schema) -> bool {
+    schema.fields().len() == 1
+        && *schema.field(0).data_type() == DataType::Binary
+        && schema
+            .field(0)
+            .metadata()
+            .contains_key(UNSAFE_ROW_NUM_FIELDS_KEY)
+}
+
+/// parses rows in spark's UnsafeRow format (each value of the binary column is
+/// a row) into a batch of the given schema
+pub fn unsafe_rows_to_batch(rows: &ArrayRef, schema: SchemaRef) -> Result<RecordBatch> {
+    let rows = as_generic_binary_array::<i32>(rows);
+    let num_fields = schema.fields().len();
+    let fixed_pos = bitset_width(num_fields);
+
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let items = (0..rows.len())
+                .map(|row_idx| {
+                    let row = rows.value(row_idx);
+                    (!is_bit_set(row, 0, i)).then_some(ValueRef {
+                        data: row,
+                        slot: fixed_pos + i * 8,
+                    })
+                })
+                .collect::<Vec<_>>();
+            read_array(field.data_type(), &items)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(RecordBatch::try_new_with_options(
+        schema,
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(rows.len())),
+    )?)
+}
+
+/// a non-null value located in the slot of an enclosing row/array, data starts
+/// at base of the enclosing row/array
+#[derive(Clone, Copy)]
+struct ValueRef<'a> {
+    data: &'a [u8],
+    slot: usize,
+}
+
+impl<'a> ValueRef<'a> {
+    fn fixed<const N: usize>(&self) -> [u8; N] {
+        self.data[self.slot..][..N].try_into().unwrap()
+    }
+
+    fn variable(&self) -> &'a [u8] {
+        let offset_and_size = u64::from_le_bytes(self.fixed::<8>());
+        let offset = (offset_and_size >> 32) as usize;
+        let size = (offset_and_size & 0xffffffff) as usize;
+        &self.data[offset..][..size]
+    }
+}
+
+fn is_bit_set(data: &[u8], bitset_pos: usize, i: usize) -> bool {
+    data[bitset_pos + i / 8] & (1 << (i % 8)) != 0
+}
+
+/// collects elements of an UnsafeArrayData, returns number of elements
+fn push_array_elements<'a>(
+    array: &'a [u8],
+    elem_size: usize,
+    items: &mut Vec<Option<ValueRef<'a>>>,
+) -> usize {
+    let len = i64::from_le_bytes(array[0..8].try_into().unwrap()) as usize;
+    let fixed_pos = 8 + bitset_width(len);
+    items.extend((0..len).map(|i| {
+        (!is_bit_set(array, 8, i)).then_some(ValueRef {
+            data: array,
+            slot: fixed_pos + i * elem_size,
+        })
+    }));
+    len
+}
+
+fn read_array(dt: &DataType, items: &[Option<ValueRef>]) -> Result<ArrayRef> {
+    macro_rules! read_primitive {
+        ($arrowty:ident, $size:expr) => {{
+            type T = arrow::datatypes::$arrowty;
+            let array: PrimitiveArray<T> = items
+                .iter()
+                .map(|v| {
+                    v.map(|v| <T as ArrowPrimitiveType>::Native::from_le_bytes(v.fixed::<$size>()))
+                })
+                .collect();
+            Arc::new(array.with_data_type(dt.clone()))
+        }};
+    }
+    let validity = || Buffer::from_iter(items.iter().map(|v| v.is_some()));
+
+    Ok(match dt {
+        DataType::Null => Arc::new(NullArray::new(items.len())),
+        DataType::Boolean => Arc::new(
+            items
+                .iter()
+                .map(|v| v.map(|v| v.fixed::<1>()[0] != 0))
+                .collect::<BooleanArray>(),
+        ),
+        DataType::Int8 => read_primitive!(Int8Type, 1),
+        DataType::Int16 => read_primitive!(Int16Type, 2),
+        DataType::Int32 => read_primitive!(Int32Type, 4),
+        DataType::Int64 => read_primitive!(Int64Type, 8),
+        DataType::Float32 => read_primitive!(Float32Type, 4),
+        DataType::Float64 => read_primitive!(Float64Type, 8),
+        DataType::Date32 => read_primitive!(Date32Type, 4),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            read_primitive!(TimestampMicrosecondType, 8)
+        }
+        DataType::Decimal128(prec, _) => {
+            let array: Decimal128Array = items
+                .iter()
+                .map(|v| {
+                    v.map(|v| {
+                        if *prec <= MAX_LONG_DIGITS {
+                            return i64::from_le_bytes(v.fixed::<8>()) as i128;
+                        }
+                        // big-endian two's complement bytes, sign extended to 16 bytes
+                        let bytes = v.variable();
+                        let sign_byte = match bytes.first() {
+                            Some(b) if *b & 0x80 != 0 => 0xff,
+                            _ => 0x00,
+                        };
+                        let mut extended = [sign_byte; 16];
+                        extended[16 - bytes.len()..].copy_from_slice(bytes);
+                        i128::from_be_bytes(extended)
+                    })
+                })
+                .collect();
+            Arc::new(array.with_data_type(dt.clone()))
+        }
+        DataType::Utf8 => Arc::new(
+            items
+                .iter()
+                .map(|v| v.map(|v| std::str::from_utf8(v.variable())).transpose())
+                .collect::<std::result::Result<StringArray, _>>()
+                .map_err(|err| {
+                    DataFusionError::Execution(format!("invalid utf8 in unsafe row: {}", err))
+                })?,
+        ),
+        DataType::Binary => Arc::new(
+            items
+                .iter()
+                .map(|v| v.map(|v| v.variable()))
+                .collect::<BinaryArray>(),
+        ),
+        DataType::List(field) => {
+            let elem_size = element_size(field.data_type());
+            let mut offsets: Vec<i32> = vec![0];
+            let mut elements = vec![];
+            for item in items {
+                if let Some(v) = item {
+                    push_array_elements(v.variable(), elem_size, &mut elements);
+                }
+                offsets.push(elements.len() as i32);
+            }
+            let values = read_array(field.data_type(), &elements)?;
+            make_array(
+                ArrayData::builder(dt.clone())
+                    .len(items.len())
+                    .add_buffer(Buffer::from_slice_ref(&offsets))
+                    .add_child_data(values.to_data())
+                    .null_bit_buffer(Some(validity()))
+                    .build()?,
+            )
+        }
+        DataType::Struct(fields) => {
+            let fixed_pos = bitset_width(fields.len());
+            let children = fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let child_items = items
+                        .iter()
+                        .map(|v| {
+                            v.and_then(|v| {
+                                let row = v.variable();
+                                (!is_bit_set(row, 0, i)).then_some(ValueRef {
+                                    data: row,
+                                    slot: fixed_pos + i * 8,
+                                })
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    Ok(read_array(field.data_type(), &child_items)?.to_data())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            make_array(
+                ArrayData::builder(dt.clone())
+                    .len(items.len())
+                    .child_data(children)
+                    .null_bit_buffer(Some(validity()))
+                    .build()?,
+            )
+        }
+        DataType::Map(field, _) => {
+            let (key_type, value_type) = match field.data_type() {
+                DataType::Struct(kv) => (kv[0].data_type(), kv[1].data_type()),
+                other => {
+                    return Err(DataFusionError::Execution(format!(
+                        "invalid map entries type: {}",
+                        other
+                    )))
+                }
+            };
+            let mut offsets: Vec<i32> = vec![0];
+            let mut keys = vec![];
+            let mut values = vec![];
+            for item in items {
+                if let Some(v) = item {
+                    // map is written as: [key array size][key array][value array]
+                    let map = v.variable();
+                    let key_array_size = i64::from_le_bytes(map[0..8].try_into().unwrap()) as usize;
+                    let (key_array, value_array) = map[8..].split_at(key_array_size);
+                    push_array_elements(key_array, element_size(key_type), &mut keys);
+                    push_array_elements(value_array, element_size(value_type), &mut values);
+                }
+                offsets.push(keys.len() as i32);
+            }
+            let entries = ArrayData::builder(field.data_type().clone())
+                .len(keys.len())
+                .add_child_data(read_array(key_type, &keys)?.to_data())
+                .add_child_data(read_array(value_type, &values)?.to_data())
+                .build()?;
+            make_array(
+                ArrayData::builder(dt.clone())
+                    .len(items.len())
+                    .add_buffer(Buffer::from_slice_ref(&offsets))
+                    .add_child_data(entries)
+                    .null_bit_buffer(Some(validity()))
+                    .build()?,
+            )
+        }
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "unsupported data type in unsafe row conversion: {}",
+                other
+            )));
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::unsafe_row::{batch_to_unsafe_rows, is_unsafe_rows_schema, unsafe_rows_to_batch};
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use std::sync::Arc;
+
+    fn unsafe_rows(batch: &RecordBatch) -> Vec<Vec<u8>> {
+        let converted = batch_to_unsafe_rows(batch).unwrap();
+        let rows = as_generic_binary_array::<i32>(converted.column(0));
+        (0..rows.len()).map(|i| rows.value(i).to_vec()).collect()
+    }
+
+    #[test]
+    fn test_fixed_width_fields() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Int64, true),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+                Arc::new(Int64Array::from(vec![Some(-2), Some(3)])),
+            ],
+        )
+        .unwrap();
+        let rows = unsafe_rows(&batch);
+
+        // [null bits: 8][a: 8][b: 8]
+        let mut expected = vec![0u8; 24];
+        expected[8..12].copy_from_slice(&1i32.to_le_bytes());
+        expected[16..24].copy_from_slice(&(-2i64).to_le_bytes());
+        assert_eq!(rows[0], expected);
+
+        let mut expected = vec![0u8; 24];
+        expected[0] = 0b01;
+        expected[16..24].copy_from_slice(&3i64.to_le_bytes());
+        assert_eq!(rows[1], expected);
+    }
+
+    #[test]
+    fn test_variable_length_fields() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("s", DataType::Utf8, true),
+                Field::new("l", DataType::new_list(DataType::Int32, true), true),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["hello"])),
+                Arc::new(ListArray::from_iter_primitive::<
+                    arrow::datatypes::Int32Type,
+                    _,
+                    _,
+                >(vec![Some(vec![Some(7), None])])),
+            ],
+        )
+        .unwrap();
+        let rows = unsafe_rows(&batch);
+
+        // [null bits: 8][s: 8][l: 8]["hello" padded: 8][array: 8 + 8 + 8]
+        let mut expected = vec![0u8; 56];
+        expected[8..16].copy_from_slice(&((24u64 << 32) | 5).to_le_bytes());
+        expected[16..24].copy_from_slice(&((32u64 << 32) | 24).to_le_bytes());
+        expected[24..29].copy_from_slice(b"hello");
+        expected[32..40].copy_from_slice(&2i64.to_le_bytes());
+        expected[40] = 0b10;
+        expected[48..52].copy_from_slice(&7i32.to_le_bytes());
+        assert_eq!(rows[0], expected);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let list = ListArray::from_iter_primitive::<arrow::datatypes::Int64Type, _, _>(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            None,
+            Some(vec![]),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("b", DataType::Boolean, true),
+                Field::new("s", DataType::Utf8, true),
+                Field::new("d", DataType::Decimal128(10, 2), true),
+                Field::new("big_d", DataType::Decimal128(30, 2), true),
+                Field::new("l", list.data_type().clone(), true),
+            ])),
+            vec![
+                Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("long string value"),
+                    None,
+                ])),
+                Arc::new(
+                    Decimal128Array::from(vec![Some(12345), None, Some(-1)])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+                Arc::new(
+                    Decimal128Array::from(vec![
+                        Some(123456789012345678901234567),
+                        Some(-129),
+                        None,
+                    ])
+                    .with_precision_and_scale(30, 2)
+                    .unwrap(),
+                ),
+                Arc::new(list),
+            ],
+        )
+        .unwrap();
+
+        let rows = batch_to_unsafe_rows(&batch).unwrap();
+        assert!(is_unsafe_rows_schema(&rows.schema()));
+        let parsed = unsafe_rows_to_batch(rows.column(0), batch.schema()).unwrap();
+        assert_eq!(parsed, batch);
+    }
+}
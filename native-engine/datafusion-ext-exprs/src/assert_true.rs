@@ -0,0 +1,186 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use crate::raise_error::{raised_error, user_message_string};
+use arrow::array::{new_null_array, Array, BooleanArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// returns null if the condition is true, otherwise raises an error with the
+/// message of the first failed row, like spark's AssertTrue.
+#[derive(Debug, Hash)]
+pub struct AssertTrueExpr {
+    condition: Arc<dyn PhysicalExpr>,
+    message: Arc<dyn PhysicalExpr>,
+}
+
+impl PartialEq<dyn Any> for AssertTrueExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.condition.eq(&x.condition) && self.message.eq(&x.message))
+            .unwrap_or(false)
+    }
+}
+
+impl AssertTrueExpr {
+    pub fn new(condition: Arc<dyn PhysicalExpr>, message: Arc<dyn PhysicalExpr>) -> Self {
+        Self { condition, message }
+    }
+
+    pub fn condition(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.condition
+    }
+
+    pub fn message(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.message
+    }
+}
+
+impl Display for AssertTrueExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AssertTrue({}, {})", self.condition, self.message)
+    }
+}
+
+impl PhysicalExpr for AssertTrueExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Null)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let num_rows = batch.num_rows();
+        let failed_row = match self.condition.evaluate(batch)? {
+            ColumnarValue::Array(array) => {
+                let conditions =
+                    array
+                        .as_any()
+                        .downcast_ref::<BooleanArray>()
+                        .ok_or_else(|| {
+                            DataFusionError::Execution(format!(
+                                "assert_true: condition must be boolean, got {}",
+                                array.data_type(),
+                            ))
+                        })?;
+                conditions.iter().position(|v| v != Some(true))
+            }
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))) => None,
+            ColumnarValue::Scalar(ScalarValue::Boolean(_)) if num_rows > 0 => Some(0),
+            ColumnarValue::Scalar(ScalarValue::Boolean(_)) => None,
+            ColumnarValue::Scalar(other) => {
+                return Err(DataFusionError::Execution(format!(
+                    "assert_true: condition must be boolean, got {}",
+                    other.get_datatype(),
+                )));
+            }
+        };
+
+        if let Some(row_idx) = failed_row {
+            let message = match self.message.evaluate(batch)? {
+                ColumnarValue::Array(array) => ScalarValue::try_from_array(&array, row_idx)?,
+                ColumnarValue::Scalar(scalar) => scalar,
+            };
+            return Err(raised_error(&user_message_string(&message)?));
+        }
+        Ok(ColumnarValue::Array(new_null_array(
+            &DataType::Null,
+            num_rows,
+        )))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.condition.clone(), self.message.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            children[1].clone(),
+        )))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::assert_true::AssertTrueExpr;
+    use crate::raise_error::extract_raised_error_message;
+    use arrow::array::{Array, ArrayRef, BooleanArray, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use std::sync::Arc;
+
+    fn test_batch(conditions: Vec<Option<bool>>) -> RecordBatch {
+        let messages = (0..conditions.len())
+            .map(|i| Some(format!("row {} is not true!", i)))
+            .collect::<Vec<_>>();
+        let cond_array: ArrayRef = Arc::new(BooleanArray::from(conditions));
+        let msg_array: ArrayRef = Arc::new(StringArray::from(messages));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("cond", DataType::Boolean, true),
+            Field::new("msg", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(schema, vec![cond_array, msg_array]).unwrap()
+    }
+
+    #[test]
+    fn test_assert_true_passed() {
+        let batch = test_batch(vec![Some(true), Some(true), Some(true)]);
+        let expr = Arc::new(AssertTrueExpr::new(
+            phys_expr::col("cond", &batch.schema()).unwrap(),
+            phys_expr::col("msg", &batch.schema()).unwrap(),
+        ));
+        let ret = expr.evaluate(&batch).unwrap().into_array(3);
+        assert_eq!(ret.len(), 3);
+        assert_eq!(ret.data_type(), &DataType::Null);
+    }
+
+    #[test]
+    fn test_assert_true_failed() {
+        let batch = test_batch(vec![Some(true), None, Some(false)]);
+        let expr = Arc::new(AssertTrueExpr::new(
+            phys_expr::col("cond", &batch.schema()).unwrap(),
+            phys_expr::col("msg", &batch.schema()).unwrap(),
+        ));
+        let err = expr.evaluate(&batch).unwrap_err().to_string();
+        assert_eq!(
+            extract_raised_error_message(&err),
+            Some("row 1 is not true!")
+        );
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::*;
+use arrow::datatypes::{DataType, Float32Type, Float64Type, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use datafusion::common::{Result, ScalarValue};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use std::any::Any;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// IN-list of complex-typed (struct/list) literals, same as spark's In/InSet
+/// which compare values with the interpreted ordering: nested fields are
+/// compared field-wise with nulls equal to each other, NaNs are equal and -0.0
+/// equals 0.0. values are compared in row format, so names and nullability of
+/// nested fields need not to be exactly the same as the list items.
+#[derive(Debug)]
+pub struct ComplexInListExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<ScalarValue>,
+    negated: bool,
+    has_null: bool,
+    list_rows: HashSet<Box<[u8]>>,
+}
+
+impl PartialEq<dyn Any> for ComplexInListExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.expr.eq(&x.expr) && self.list == x.list && self.negated == x.negated)
+            .unwrap_or(false)
+    }
+}
+
+impl Hash for ComplexInListExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.list.hash(state);
+        self.negated.hash(state);
+    }
+}
+
+impl ComplexInListExpr {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        list: Vec<ScalarValue>,
+        negated: bool,
+    ) -> Result<Self> {
+        let has_null = list.iter().any(|value| value.is_null());
+        let mut list_rows = HashSet::new();
+        for value in list.iter().filter(|value| !value.is_null()) {
+            let converter = RowConverter::new(vec![SortField::new(value.get_datatype())])?;
+            let rows = converter.convert_columns(&[normalize_floats(&value.to_array())?])?;
+            list_rows.insert(Box::from(rows.row(0).as_ref()));
+        }
+        Ok(Self {
+            expr,
+            list,
+            negated,
+            has_null,
+            list_rows,
+        })
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn list(&self) -> &[ScalarValue] {
+        &self.list
+    }
+
+    pub fn negated(&self) -> bool {
+        self.negated
+    }
+
+    /// returns true if the data type is supported by this expr instead of
+    /// datafusion's InListExpr.
+    pub fn is_supported_type(data_type: &DataType) -> bool {
+        matches!(data_type, DataType::Struct(_) | DataType::List(_))
+    }
+}
+
+impl Display for ComplexInListExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = if self.negated { "NOT IN" } else { "IN" };
+        write!(f, "{} {} ({:?})", self.expr, op, self.list)
+    }
+}
+
+impl PhysicalExpr for ComplexInListExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        Ok(self.has_null || self.expr.nullable(input_schema)?)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let values = self.expr.evaluate(batch)?.into_array(batch.num_rows());
+        let converter = RowConverter::new(vec![SortField::new(values.data_type().clone())])?;
+        let rows = converter.convert_columns(&[normalize_floats(&values)?])?;
+
+        // same as spark: returns null if the value is null, or not found and
+        // there are null items in list
+        let found = (0..values.len()).map(|i| {
+            if values.is_null(i) {
+                return None;
+            }
+            match self.list_rows.contains(rows.row(i).as_ref()) {
+                true => Some(!self.negated),
+                false if self.has_null => None,
+                false => Some(self.negated),
+            }
+        });
+        Ok(ColumnarValue::Array(Arc::new(BooleanArray::from_iter(
+            found,
+        ))))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self {
+            expr: children[0].clone(),
+            list: self.list.clone(),
+            negated: self.negated,
+            has_null: self.has_null,
+            list_rows: self.list_rows.clone(),
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+// normalizes NaNs and -0.0 in nested floats, which are encoded to different
+// rows but are equal in spark's ordering
+fn normalize_floats(array: &ArrayRef) -> Result<ArrayRef> {
+    Ok(match array.data_type() {
+        DataType::Float32 => Arc::new(
+            as_primitive_array::<Float32Type>(array).unary::<_, Float32Type>(|v| match v {
+                v if v.is_nan() => f32::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        DataType::Float64 => Arc::new(
+            as_primitive_array::<Float64Type>(array).unary::<_, Float64Type>(|v| match v {
+                v if v.is_nan() => f64::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        DataType::Struct(fields) => {
+            let struct_ = as_struct_array(array);
+            Arc::new(StructArray::try_new(
+                fields.clone(),
+                struct_
+                    .columns()
+                    .iter()
+                    .map(normalize_floats)
+                    .collect::<Result<_>>()?,
+                struct_.nulls().cloned(),
+            )?)
+        }
+        DataType::List(field) => {
+            let list = as_list_array(array);
+            Arc::new(ListArray::try_new(
+                field.clone(),
+                list.offsets().clone(),
+                normalize_floats(list.values())?,
+                list.nulls().cloned(),
+            )?)
+        }
+        _ => array.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::complex_in_list::ComplexInListExpr;
+    use arrow::array::*;
+    use arrow::buffer::{NullBuffer, OffsetBuffer};
+    use arrow::datatypes::{DataType, Field, Fields, Int32Type, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::ScalarValue;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_struct_in_list() {
+        let fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Float64, true),
+        ]);
+        let struct_array: ArrayRef = Arc::new(StructArray::new(
+            fields,
+            vec![
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    None,
+                    Some(2),
+                    Some(3),
+                    Some(1),
+                ])),
+                Arc::new(Float64Array::from(vec![
+                    Some(-0.0),
+                    Some(1.0),
+                    Some(f64::NAN),
+                    Some(3.0),
+                    None,
+                ])),
+            ],
+            Some(NullBuffer::from(vec![true, true, true, true, false])),
+        ));
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "col",
+            struct_array.data_type().clone(),
+            true,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![struct_array]).expect("Error creating RecordBatch");
+
+        // literal field names differ from the column
+        let literal_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Float64, true),
+        ]);
+        let struct_value = |a: Option<i32>, b: Option<f64>| {
+            ScalarValue::Struct(
+                Some(vec![ScalarValue::Int32(a), ScalarValue::Float64(b)]),
+                literal_fields.clone(),
+            )
+        };
+        let eval = |list: Vec<ScalarValue>, negated| {
+            let expr = Arc::new(
+                ComplexInListExpr::try_new(
+                    phys_expr::col("col", &batch.schema()).unwrap(),
+                    list,
+                    negated,
+                )
+                .unwrap(),
+            );
+            expr.evaluate(&batch)
+                .expect("Error evaluating expr")
+                .into_array(batch.num_rows())
+        };
+        let expected = |values: Vec<Option<bool>>| Arc::new(BooleanArray::from(values)) as ArrayRef;
+
+        // nested nulls are equal, NaNs are equal and -0.0 equals 0.0
+        let list = vec![
+            struct_value(Some(1), Some(0.0)),
+            struct_value(None, Some(1.0)),
+            struct_value(Some(2), Some(f64::NAN)),
+        ];
+        assert_eq!(
+            &eval(list.clone(), false),
+            &expected(vec![Some(true), Some(true), Some(true), Some(false), None])
+        );
+        assert_eq!(
+            &eval(list, true),
+            &expected(vec![
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(true),
+                None
+            ])
+        );
+
+        // null items
+        let list = vec![
+            struct_value(Some(3), Some(3.0)),
+            ScalarValue::Struct(None, literal_fields.clone()),
+        ];
+        assert_eq!(
+            &eval(list, false),
+            &expected(vec![None, None, None, Some(true), None])
+        );
+    }
+
+    #[test]
+    fn test_list_in_list() {
+        let list_array: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new("item", DataType::Int32, true)),
+            OffsetBuffer::new(vec![0, 2, 3, 3, 3].into()),
+            Arc::new(PrimitiveArray::<Int32Type>::from(vec![
+                Some(1),
+                None,
+                Some(2),
+            ])),
+            Some(NullBuffer::from(vec![true, true, true, false])),
+        ));
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "col",
+            list_array.data_type().clone(),
+            true,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![list_array]).expect("Error creating RecordBatch");
+
+        let list_value = |values: Option<Vec<Option<i32>>>| {
+            ScalarValue::List(
+                values.map(|values| values.into_iter().map(ScalarValue::Int32).collect()),
+                Arc::new(Field::new("item", DataType::Int32, true)),
+            )
+        };
+        let expr = ComplexInListExpr::try_new(
+            phys_expr::col("col", &batch.schema()).unwrap(),
+            vec![list_value(Some(vec![Some(1), None])), list_value(Some(vec![]))],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            &expr
+                .evaluate(&batch)
+                .expect("Error evaluating expr")
+                .into_array(batch.num_rows()),
+            &(Arc::new(BooleanArray::from(vec![
+                Some(true),
+                Some(false),
+                Some(true),
+                None
+            ])) as ArrayRef)
+        );
+    }
+}
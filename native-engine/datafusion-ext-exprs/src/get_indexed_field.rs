@@ -30,16 +30,33 @@ use std::{any::Any, sync::Arc};
 use crate::down_cast_any_ref;
 
 /// expression to get a field of a list array.
+///
+/// list indices start from 1. with `allow_negative_index`, negative indices
+/// count from the end of the list and 0 is invalid (like spark's ElementAt),
+/// otherwise non-positive indices are out of bounds (like GetArrayItem).
+/// out-of-bounds indices produce nulls, or errors with `fail_on_error` (ansi).
 #[derive(Debug, Hash)]
 pub struct GetIndexedFieldExpr {
     arg: Arc<dyn PhysicalExpr>,
     key: ScalarValue,
+    allow_negative_index: bool,
+    fail_on_error: bool,
 }
 
 impl GetIndexedFieldExpr {
     /// Create new get field expression
-    pub fn new(arg: Arc<dyn PhysicalExpr>, key: ScalarValue) -> Self {
-        Self { arg, key }
+    pub fn new(
+        arg: Arc<dyn PhysicalExpr>,
+        key: ScalarValue,
+        allow_negative_index: bool,
+        fail_on_error: bool,
+    ) -> Self {
+        Self {
+            arg,
+            key,
+            allow_negative_index,
+            fail_on_error,
+        }
     }
 
     /// Get the input key
@@ -51,6 +68,22 @@ impl GetIndexedFieldExpr {
     pub fn arg(&self) -> &Arc<dyn PhysicalExpr> {
         &self.arg
     }
+
+    pub fn allow_negative_index(&self) -> bool {
+        self.allow_negative_index
+    }
+
+    pub fn fail_on_error(&self) -> bool {
+        self.fail_on_error
+    }
+
+    /// index of the accessed field if the key is a struct field index
+    pub fn struct_field_index(&self) -> Option<usize> {
+        match self.key {
+            ScalarValue::Int32(Some(k)) if k >= 0 => Some(k as usize),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for GetIndexedFieldExpr {
@@ -84,21 +117,49 @@ impl PhysicalExpr for GetIndexedFieldExpr {
             (DataType::List(lst), &ScalarValue::Int64(Some(idx))) => {
                 let as_list_array = as_list_array(&array)?;
 
-                if idx < 1 || as_list_array.is_empty() {
+                // all out of bounds
+                if as_list_array.is_empty()
+                    || (idx < 1 && !self.allow_negative_index && !self.fail_on_error)
+                {
                     let scalar_null: ScalarValue = lst.data_type().try_into()?;
                     return Ok(ColumnarValue::Scalar(scalar_null));
                 }
 
                 let list_len = as_list_array.len();
                 let mut take_indices_builder = Int32Builder::with_capacity(list_len);
-                for (i, array) in as_list_array.iter().enumerate() {
-                    match array {
-                        Some(array) if idx <= array.len() as i64 => {
-                            let base_offset = as_list_array.value_offsets()[i];
-                            let take_offset = base_offset + idx as i32 - 1;
-                            take_indices_builder.append_value(take_offset);
+                for i in 0..list_len {
+                    if as_list_array.is_null(i) {
+                        take_indices_builder.append_null();
+                        continue;
+                    }
+                    let base_offset = as_list_array.value_offsets()[i] as i64;
+                    let num_elements = as_list_array.value_length(i) as i64;
+                    let pos = match idx {
+                        0 if self.allow_negative_index => {
+                            return Err(DataFusionError::Execution(
+                                "SQL array indices start at 1".to_string(),
+                            ));
                         }
-                        _ => take_indices_builder.append_null(),
+                        idx if idx > 0 && idx <= num_elements => Some(idx - 1),
+                        idx if idx < 0 && self.allow_negative_index && -idx <= num_elements => {
+                            Some(num_elements + idx)
+                        }
+                        _ => None,
+                    };
+                    match pos {
+                        Some(pos) => take_indices_builder.append_value((base_offset + pos) as i32),
+                        None if self.fail_on_error => {
+                            // report index as seen by spark, which starts from 0 in GetArrayItem
+                            let spark_idx = if self.allow_negative_index {
+                                idx
+                            } else {
+                                idx - 1
+                            };
+                            return Err(DataFusionError::Execution(format!(
+                                "Invalid index: {spark_idx}, numElements: {num_elements}"
+                            )));
+                        }
+                        None => take_indices_builder.append_null(),
                     }
                 }
                 let taken = take(
@@ -140,6 +201,8 @@ impl PhysicalExpr for GetIndexedFieldExpr {
         Ok(Arc::new(GetIndexedFieldExpr::new(
             children[0].clone(),
             self.key.clone(),
+            self.allow_negative_index,
+            self.fail_on_error,
         )))
     }
 
@@ -153,7 +216,12 @@ impl PartialEq<dyn Any> for GetIndexedFieldExpr {
     fn eq(&self, other: &dyn Any) -> bool {
         down_cast_any_ref(other)
             .downcast_ref::<Self>()
-            .map(|x| self.arg.eq(&x.arg) && self.key == x.key)
+            .map(|x| {
+                self.arg.eq(&x.arg)
+                    && self.key == x.key
+                    && self.allow_negative_index == x.allow_negative_index
+                    && self.fail_on_error == x.fail_on_error
+            })
             .unwrap_or(false)
     }
 }
@@ -211,6 +279,8 @@ mod test {
         let get_indexed = Arc::new(GetIndexedFieldExpr::new(
             Arc::new(Column::new("cccccc1", 0)),
             ScalarValue::from(2_i64),
+            false,
+            false,
         ));
         let output_array = get_indexed.evaluate(&input_batch)?.into_array(0);
         let output_batch =
@@ -230,4 +300,63 @@ mod test {
         assert_batches_eq!(expected, &[output_batch]);
         Ok(())
     }
+
+    #[test]
+    fn test_list_out_of_bounds() -> Result<(), Box<dyn std::error::Error>> {
+        let array: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(100), Some(101), Some(102)]),
+            Some(vec![Some(200)]),
+            None,
+        ]));
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![("c1", array, true)])?;
+        let evaluate = |key: i64, allow_negative_index: bool, fail_on_error: bool| {
+            let get_indexed = GetIndexedFieldExpr::new(
+                Arc::new(Column::new("c1", 0)),
+                ScalarValue::from(key),
+                allow_negative_index,
+                fail_on_error,
+            );
+            get_indexed
+                .evaluate(&input_batch)
+                .map(|v| v.into_array(input_batch.num_rows()))
+        };
+        let to_vec = |array: ArrayRef| {
+            array
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>()
+        };
+
+        // negative indices count from the end (element_at)
+        assert_eq!(
+            to_vec(evaluate(-1, true, false)?),
+            vec![Some(102), Some(200), None]
+        );
+        assert_eq!(
+            to_vec(evaluate(-3, true, false)?),
+            vec![Some(100), None, None]
+        );
+
+        // negative indices are out of bounds (GetArrayItem)
+        assert_eq!(to_vec(evaluate(-1, false, false)?), vec![None, None, None]);
+        assert_eq!(
+            to_vec(evaluate(3, false, false)?),
+            vec![Some(102), None, None]
+        );
+
+        // ansi mode
+        assert!(evaluate(3, false, true).is_err());
+        assert!(evaluate(0, false, true).is_err());
+        assert!(evaluate(-2, true, true).is_err());
+        assert_eq!(
+            to_vec(evaluate(1, false, true)?),
+            vec![Some(100), Some(200), None]
+        );
+
+        // index 0 is always invalid in element_at
+        assert!(evaluate(0, true, false).is_err());
+        Ok(())
+    }
 }
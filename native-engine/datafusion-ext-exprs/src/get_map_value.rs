@@ -16,6 +16,7 @@ use crate::down_cast_any_ref;
 use arrow::array::*;
 use arrow::compute::{eq_dyn_binary_scalar, eq_dyn_bool_scalar, eq_dyn_scalar, eq_dyn_utf8_scalar};
 use arrow::datatypes::Field;
+use arrow::row::{RowConverter, SortField};
 use arrow::{
     datatypes::{DataType, Schema},
     record_batch::RecordBatch,
@@ -84,7 +85,13 @@ impl PhysicalExpr for GetMapValueExpr {
         let array = self.arg.evaluate(batch)?.into_array(1);
         match (array.data_type(), &self.key) {
             (DataType::Map(_, _), _) if self.key.is_null() => {
-                Err(DataFusionError::NotImplemented("map key not support Null Type".to_string()))
+                // spark returns null for null keys
+                let value_type = get_data_type_field(array.data_type())?.data_type().clone();
+                Ok(ColumnarValue::Array(new_null_array(&value_type, batch.num_rows())))
+            }
+            (DataType::Map(_, _), ScalarValue::Struct(..) | ScalarValue::List(..)) => {
+                let as_map_array = array.as_any().downcast_ref::<MapArray>().unwrap();
+                Ok(ColumnarValue::Array(get_complex_key_value(as_map_array, &self.key)?))
             }
             (DataType::Map(_, _), _) => {
                 let as_map_array = array.as_any().downcast_ref::<MapArray>().unwrap();
@@ -269,6 +276,30 @@ impl PhysicalExpr for GetMapValueExpr {
     }
 }
 
+/// finds values of complex-typed keys (like structs and lists) by comparing
+/// keys in row format. names and nullability of nested fields are not encoded
+/// in rows, so the key needs not to have exactly the same type as map keys.
+fn get_complex_key_value(map_array: &MapArray, key: &ScalarValue) -> Result<ArrayRef> {
+    let map_keys_converter = RowConverter::new(vec![SortField::new(map_array.key_type().clone())])?;
+    let map_keys = map_keys_converter.convert_columns(&[map_array.keys().clone()])?;
+    let key_converter = RowConverter::new(vec![SortField::new(key.get_datatype())])?;
+    let key_rows = key_converter.convert_columns(&[key.to_array()])?;
+    let key_row = key_rows.row(0);
+
+    let offsets = map_array.value_offsets();
+    let indices = (0..map_array.len())
+        .map(|i| {
+            if map_array.is_null(i) {
+                return None;
+            }
+            (offsets[i]..offsets[i + 1])
+                .find(|&j| map_keys.row(j as usize).as_ref() == key_row.as_ref())
+                .map(|j| j as u32)
+        })
+        .collect::<UInt32Array>();
+    Ok(arrow::compute::take(map_array.values(), &indices, None)?)
+}
+
 fn get_data_type_field(data_type: &DataType) -> Result<Field> {
     match data_type {
         DataType::Map(field, _) => {
@@ -291,7 +322,7 @@ mod test {
     use super::GetMapValueExpr;
     use arrow::array::*;
     use arrow::buffer::Buffer;
-    use arrow::datatypes::{DataType, Field, ToByteSlice};
+    use arrow::datatypes::{DataType, Field, Fields, ToByteSlice};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
     use datafusion::common::ScalarValue;
@@ -384,4 +415,73 @@ mod test {
         assert_batches_eq!(expected, &[output_batch]);
         Ok(())
     }
+
+    #[test]
+    fn test_map_struct_key() -> Result<(), Box<dyn std::error::Error>> {
+        // maps: [{(1,a): 10, (1,b): 11}, {(2,a): 20}, null]
+        let key_fields = Fields::from(vec![
+            Field::new("x", DataType::Int32, true),
+            Field::new("y", DataType::Utf8, true),
+        ]);
+        let keys = StructArray::new(
+            key_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 1, 2])),
+                Arc::new(StringArray::from(vec!["a", "b", "a"])),
+            ],
+            None,
+        );
+        let values = Int32Array::from(vec![10, 11, 20]);
+        let entry_struct = StructArray::from(vec![
+            (
+                Arc::new(Field::new("key", keys.data_type().clone(), false)),
+                Arc::new(keys) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("value", DataType::Int32, true)),
+                Arc::new(values) as ArrayRef,
+            ),
+        ]);
+        let map_data_type = DataType::Map(
+            Arc::new(Field::new(
+                "entries",
+                entry_struct.data_type().clone(),
+                false,
+            )),
+            false,
+        );
+        let map_data = ArrayData::builder(map_data_type)
+            .len(3)
+            .add_buffer(Buffer::from(&[0, 2, 3, 3].to_byte_slice()))
+            .add_child_data(entry_struct.into_data())
+            .null_bit_buffer(Some(Buffer::from(&[0b011])))
+            .build()?;
+        let map_array: ArrayRef = Arc::new(MapArray::from(map_data));
+        let input_batch = RecordBatch::try_from_iter_with_nullable(vec![("col", map_array, true)])?;
+
+        // key type differs from map keys in nullability
+        let key = ScalarValue::Struct(
+            Some(vec![ScalarValue::from(1_i32), ScalarValue::from("b")]),
+            Fields::from(vec![
+                Field::new("x", DataType::Int32, false),
+                Field::new("y", DataType::Utf8, false),
+            ]),
+        );
+        let get_indexed = Arc::new(GetMapValueExpr::new(Arc::new(Column::new("col", 0)), key));
+        let output_array = get_indexed.evaluate(&input_batch)?.into_array(0);
+        let output_batch =
+            RecordBatch::try_from_iter_with_nullable(vec![("col", output_array, true)])?;
+        let expected =
+            vec!["+-----+", "| col |", "+-----+", "| 11  |", "|     |", "|     |", "+-----+"];
+        assert_batches_eq!(expected, &[output_batch]);
+
+        // null keys produce nulls
+        let get_indexed = Arc::new(GetMapValueExpr::new(
+            Arc::new(Column::new("col", 0)),
+            ScalarValue::Int32(None),
+        ));
+        let output_array = get_indexed.evaluate(&input_batch)?.into_array(0);
+        assert_eq!(output_array.null_count(), 3);
+        Ok(())
+    }
 }
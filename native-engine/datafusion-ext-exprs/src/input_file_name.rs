@@ -0,0 +1,109 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// spark's InputFileName.
+///
+/// file paths are carried with batches as a column of the scan, and this expr
+/// is replaced with a reference to that column when optimizing the plan (see
+/// plan_optimizer in blaze-serde). plans where the input file is unknown (like
+/// reading from a shuffle) are not converted, so evaluating an unresolved expr
+/// is an error instead of silently producing empty strings.
+#[derive(Debug, Default, Hash)]
+pub struct InputFileNameExpr {}
+
+impl InputFileNameExpr {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Display for InputFileNameExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InputFileName()")
+    }
+}
+
+impl PartialEq<dyn Any> for InputFileNameExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other).is::<Self>()
+    }
+}
+
+impl PhysicalExpr for InputFileNameExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, _batch: &RecordBatch) -> Result<ColumnarValue> {
+        Err(DataFusionError::Execution(
+            "input_file_name() is not resolved to the input file column of a scan".to_string(),
+        ))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::input_file_name::InputFileNameExpr;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+    use datafusion::physical_expr::PhysicalExpr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_input_file_name() {
+        let batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::empty()),
+            vec![],
+            &RecordBatchOptions::new().with_row_count(Some(2)),
+        )
+        .unwrap();
+        let expr = InputFileNameExpr::new();
+        assert!(expr.evaluate(&batch).is_err());
+    }
+}
@@ -16,15 +16,24 @@ use datafusion::physical_expr::PhysicalExpr;
 use std::any::Any;
 use std::sync::Arc;
 
+pub mod assert_true;
 pub mod cast;
+pub mod complex_in_list;
 pub mod get_indexed_field;
 pub mod get_map_value;
+pub mod input_file_name;
+pub mod monotonically_increasing_id;
 pub mod named_struct;
+pub mod raise_error;
+pub mod rand;
+pub mod spark_partition_id;
 pub mod spark_scalar_subquery_wrapper;
 pub mod spark_udf_wrapper;
 pub mod string_contains;
 pub mod string_ends_with;
+pub mod string_like_multi;
 pub mod string_starts_with;
+pub mod uuid;
 
 fn down_cast_any_ref(any: &dyn Any) -> &dyn Any {
     if any.is::<Arc<dyn PhysicalExpr>>() {
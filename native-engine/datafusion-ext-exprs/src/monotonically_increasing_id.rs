@@ -0,0 +1,137 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::Int64Array;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::current_partition_id;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// generates monotonically increasing 64-bit ids like spark's
+/// MonotonicallyIncreasingID: the upper 31 bits are the partition id and the
+/// lower 33 bits are the record number within the partition.
+#[derive(Default)]
+pub struct MonotonicallyIncreasingIdExpr {
+    next_id: Mutex<Option<i64>>,
+}
+
+impl MonotonicallyIncreasingIdExpr {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for MonotonicallyIncreasingIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for MonotonicallyIncreasingIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MonotonicallyIncreasingID()")
+    }
+}
+
+impl Hash for MonotonicallyIncreasingIdExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        "MonotonicallyIncreasingID".hash(state);
+    }
+}
+
+impl PartialEq<dyn Any> for MonotonicallyIncreasingIdExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other).is::<Self>()
+    }
+}
+
+impl PhysicalExpr for MonotonicallyIncreasingIdExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Int64)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut next_id = self.next_id.lock();
+        if next_id.is_none() {
+            let partition_id = current_partition_id()? as i64;
+            *next_id = Some(partition_id << 33);
+        }
+        let start = next_id.unwrap();
+        let num_rows = batch.num_rows() as i64;
+        *next_id = Some(start + num_rows);
+
+        let ids: Int64Array = (start..start + num_rows).collect();
+        Ok(ColumnarValue::Array(Arc::new(ids)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::monotonically_increasing_id::MonotonicallyIncreasingIdExpr;
+    use arrow::array::{Array, Int64Array};
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+    use datafusion::physical_expr::PhysicalExpr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_monotonically_increasing_id() {
+        let expr = MonotonicallyIncreasingIdExpr::new();
+        let mut ids = vec![];
+        for num_rows in [3, 0, 2] {
+            let batch = RecordBatch::try_new_with_options(
+                Arc::new(Schema::empty()),
+                vec![],
+                &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+            )
+            .unwrap();
+            let ret = expr.evaluate(&batch).unwrap().into_array(num_rows);
+            let ret = ret.as_any().downcast_ref::<Int64Array>().unwrap();
+            ids.extend(ret.values().iter().copied());
+        }
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    }
+}
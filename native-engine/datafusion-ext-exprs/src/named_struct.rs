@@ -17,7 +17,7 @@ use crate::down_cast_any_ref;
 use datafusion::arrow::array::StructArray;
 
 use arrow::array::Array;
-use arrow::datatypes::{Field, Fields, SchemaRef};
+use arrow::datatypes::{Field, FieldRef, Fields, SchemaRef};
 use arrow::record_batch::RecordBatchOptions;
 use datafusion::arrow::{
     datatypes::{DataType, Schema},
@@ -56,6 +56,22 @@ impl NamedStructExpr {
             return_schema,
         })
     }
+
+    pub fn values(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.values
+    }
+
+    pub fn return_type(&self) -> &DataType {
+        &self.return_type
+    }
+
+    /// value expression and struct field at the specified index
+    pub fn field_value(&self, index: usize) -> Option<(&Arc<dyn PhysicalExpr>, &FieldRef)> {
+        Some((
+            self.values.get(index)?,
+            self.return_schema.fields().get(index)?,
+        ))
+    }
 }
 
 impl std::fmt::Display for NamedStructExpr {
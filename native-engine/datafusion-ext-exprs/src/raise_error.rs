@@ -0,0 +1,170 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::new_null_array;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// marks an error raised by user code (raise_error/assert_true), the message
+/// following this marker is passed to the jvm side without modification.
+const RAISED_ERROR_MARKER: &str = "[BLAZE_RAISED_ERROR]";
+
+/// creates an error carrying a user-supplied message.
+pub fn raised_error(message: &str) -> DataFusionError {
+    DataFusionError::Execution(format!("{RAISED_ERROR_MARKER}{message}"))
+}
+
+/// extracts the user-supplied message from a (possibly wrapped) error message.
+pub fn extract_raised_error_message(error_message: &str) -> Option<&str> {
+    error_message
+        .find(RAISED_ERROR_MARKER)
+        .map(|pos| &error_message[pos + RAISED_ERROR_MARKER.len()..])
+}
+
+/// evaluates the message of the first row and raises it as an error, like
+/// spark's RaiseError. empty batches (like the unselected branch of an
+/// `if(cond, null, raise_error(msg))`) are evaluated to an empty null array.
+#[derive(Debug, Hash)]
+pub struct RaiseErrorExpr {
+    message: Arc<dyn PhysicalExpr>,
+}
+
+impl PartialEq<dyn Any> for RaiseErrorExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.message.eq(&x.message))
+            .unwrap_or(false)
+    }
+}
+
+impl RaiseErrorExpr {
+    pub fn new(message: Arc<dyn PhysicalExpr>) -> Self {
+        Self { message }
+    }
+
+    pub fn message(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.message
+    }
+}
+
+impl Display for RaiseErrorExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RaiseError({})", self.message)
+    }
+}
+
+impl PhysicalExpr for RaiseErrorExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Null)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        if batch.num_rows() == 0 {
+            return Ok(ColumnarValue::Array(new_null_array(&DataType::Null, 0)));
+        }
+        let message = match self.message.evaluate(batch)? {
+            ColumnarValue::Array(array) => ScalarValue::try_from_array(&array, 0)?,
+            ColumnarValue::Scalar(scalar) => scalar,
+        };
+        Err(raised_error(&user_message_string(&message)?))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.message.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self::new(children[0].clone())))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+/// converts an evaluated message to string, null messages are displayed as
+/// "null" like spark does.
+pub(crate) fn user_message_string(message: &ScalarValue) -> Result<String> {
+    match message {
+        ScalarValue::Utf8(Some(s)) | ScalarValue::LargeUtf8(Some(s)) => Ok(s.clone()),
+        ScalarValue::Utf8(None) | ScalarValue::LargeUtf8(None) | ScalarValue::Null => {
+            Ok("null".to_string())
+        }
+        other => Err(DataFusionError::Execution(format!(
+            "raise_error: message must be string, got {}",
+            other.get_datatype(),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::raise_error::{extract_raised_error_message, RaiseErrorExpr};
+    use arrow::array::{Array, ArrayRef, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_raise_error() {
+        let message_array: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("custom error: id=1"),
+            Some("custom error: id=2"),
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("msg", DataType::Utf8, true)]));
+        let batch = RecordBatch::try_new(schema, vec![message_array]).unwrap();
+
+        let expr = Arc::new(RaiseErrorExpr::new(
+            phys_expr::col("msg", &batch.schema()).unwrap(),
+        ));
+        let err = expr.evaluate(&batch).unwrap_err();
+        let wrapped = format!("output_with_sender[Project] error: {}", err);
+        assert_eq!(
+            extract_raised_error_message(&wrapped),
+            Some("custom error: id=1")
+        );
+    }
+
+    #[test]
+    fn test_raise_error_empty_batch() {
+        let schema = Arc::new(Schema::new(vec![Field::new("msg", DataType::Utf8, true)]));
+        let batch = RecordBatch::new_empty(schema);
+        let expr = Arc::new(RaiseErrorExpr::new(phys_expr::lit("never raised")));
+        let ret = expr.evaluate(&batch).unwrap().into_array(0);
+        assert_eq!(ret.len(), 0);
+        assert_eq!(ret.data_type(), &DataType::Null);
+    }
+}
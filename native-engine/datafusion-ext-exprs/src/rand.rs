@@ -0,0 +1,171 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::Float64Array;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::current_partition_id;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use datafusion_ext_commons::spark_random::XorShiftRandom;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// generates random values like spark's Rand (uniformly distributed in [0, 1))
+/// and Randn (standard normal distributed). the generator is seeded with
+/// `seed + partitionIndex` so results are reproducible under task retries.
+pub struct RandExpr {
+    seed: i64,
+    gaussian: bool,
+    rng: Mutex<Option<XorShiftRandom>>,
+}
+
+impl RandExpr {
+    pub fn new(seed: i64, gaussian: bool) -> Self {
+        Self {
+            seed,
+            gaussian,
+            rng: Mutex::new(None),
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    pub fn gaussian(&self) -> bool {
+        self.gaussian
+    }
+}
+
+impl Debug for RandExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for RandExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.gaussian {
+            false => write!(f, "Rand({})", self.seed),
+            true => write!(f, "Randn({})", self.seed),
+        }
+    }
+}
+
+impl Hash for RandExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.seed.hash(state);
+        self.gaussian.hash(state);
+    }
+}
+
+impl PartialEq<dyn Any> for RandExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.seed == x.seed && self.gaussian == x.gaussian)
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for RandExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Float64)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut rng = self.rng.lock();
+        if rng.is_none() {
+            let partition_id = current_partition_id()? as i64;
+            *rng = Some(XorShiftRandom::new(self.seed.wrapping_add(partition_id)));
+        }
+        let rng = rng.as_mut().unwrap();
+        let values: Float64Array = match self.gaussian {
+            false => (0..batch.num_rows()).map(|_| rng.next_double()).collect(),
+            true => (0..batch.num_rows()).map(|_| rng.next_gaussian()).collect(),
+        };
+        Ok(ColumnarValue::Array(Arc::new(values)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rand::RandExpr;
+    use arrow::array::{Array, Float64Array};
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+    use datafusion::physical_expr::PhysicalExpr;
+    use std::sync::Arc;
+
+    fn evaluate_rand(expr: &RandExpr, num_rows: usize) -> Vec<f64> {
+        let batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::empty()),
+            vec![],
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )
+        .unwrap();
+        let ret = expr.evaluate(&batch).unwrap().into_array(num_rows);
+        let ret = ret.as_any().downcast_ref::<Float64Array>().unwrap();
+        ret.values().to_vec()
+    }
+
+    #[test]
+    fn test_rand() {
+        let expr = RandExpr::new(0, false);
+        let first_batch = evaluate_rand(&expr, 1);
+        let second_batch = evaluate_rand(&expr, 2);
+        assert_eq!(first_batch, vec![0.7604953758285915]);
+        assert_eq!(second_batch.len(), 2);
+
+        // same seed generates the same sequence
+        let values = evaluate_rand(&RandExpr::new(0, false), 3);
+        assert_eq!(values, [first_batch, second_batch].concat());
+    }
+
+    #[test]
+    fn test_randn() {
+        let expr = RandExpr::new(0, true);
+        assert_eq!(evaluate_rand(&expr, 1), vec![1.6034991609278433]);
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::current_partition_id;
+use datafusion::common::{Result, ScalarValue};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use once_cell::sync::OnceCell;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// returns the partition id of the current task, like spark's SparkPartitionID.
+#[derive(Default)]
+pub struct SparkPartitionIdExpr {
+    partition_id: OnceCell<i32>,
+}
+
+impl SparkPartitionIdExpr {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for SparkPartitionIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for SparkPartitionIdExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SparkPartitionID()")
+    }
+}
+
+impl Hash for SparkPartitionIdExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        "SparkPartitionID".hash(state);
+    }
+}
+
+impl PartialEq<dyn Any> for SparkPartitionIdExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other).is::<Self>()
+    }
+}
+
+impl PhysicalExpr for SparkPartitionIdExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Int32)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let partition_id = *self
+            .partition_id
+            .get_or_try_init(|| current_partition_id().map(|id| id as i32))?;
+        Ok(ColumnarValue::Array(
+            ScalarValue::Int32(Some(partition_id)).to_array_of_size(batch.num_rows()),
+        ))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
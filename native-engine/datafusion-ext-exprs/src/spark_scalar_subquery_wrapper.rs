@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use crate::spark_udf_wrapper::SparkUDFWrapperExpr;
+use arrow::array::UInt32Array;
+use arrow::compute::take;
 use arrow::datatypes::{DataType, Schema};
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use datafusion::common::{Result, ScalarValue};
@@ -84,25 +86,47 @@ impl PhysicalExpr for SparkScalarSubqueryWrapperExpr {
         Ok(self.return_nullable)
     }
 
-    fn evaluate(&self, _: &RecordBatch) -> Result<ColumnarValue> {
-        let result = self.cached_value.get_or_try_init(|| {
-            let expr = SparkUDFWrapperExpr::try_new(
-                self.serialized.clone(),
-                self.return_type.clone(),
-                self.return_nullable,
-                vec![],
-            )?;
-            let stub_batch = RecordBatch::try_new_with_options(
-                Arc::new(Schema::empty()),
-                vec![],
-                &RecordBatchOptions::new().with_row_count(Some(1)),
-            )?;
-            let result = expr.evaluate(&stub_batch)?.into_array(1);
-            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
-                &result, 0,
-            )?))
-        });
-        result.cloned()
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let result = self
+            .cached_value
+            .get_or_try_init(|| -> Result<ColumnarValue> {
+                let expr = SparkUDFWrapperExpr::try_new(
+                    self.serialized.clone(),
+                    self.return_type.clone(),
+                    self.return_nullable,
+                    vec![],
+                )?;
+                let stub_batch = RecordBatch::try_new_with_options(
+                    Arc::new(Schema::empty()),
+                    vec![],
+                    &RecordBatchOptions::new().with_row_count(Some(1)),
+                )?;
+                let result = expr.evaluate(&stub_batch)?.into_array(1);
+
+                // complex values (like maps) are not fully supported by ScalarValue,
+                // keep them as single-row arrays
+                if matches!(
+                    result.data_type(),
+                    DataType::List(_)
+                        | DataType::LargeList(_)
+                        | DataType::FixedSizeList(..)
+                        | DataType::Struct(_)
+                        | DataType::Map(..)
+                ) {
+                    return Ok(ColumnarValue::Array(result));
+                }
+                Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                    &result, 0,
+                )?))
+            })?;
+
+        match result {
+            ColumnarValue::Scalar(value) => Ok(ColumnarValue::Scalar(value.clone())),
+            ColumnarValue::Array(single_row) => {
+                let indices = UInt32Array::from(vec![0; batch.num_rows()]);
+                Ok(ColumnarValue::Array(take(single_row, &indices, None)?))
+            }
+        }
     }
 
     fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
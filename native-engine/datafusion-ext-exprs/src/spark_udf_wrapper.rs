@@ -18,14 +18,13 @@ use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use blaze_jni_bridge::{
-    is_task_running, jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref,
-    jni_new_object,
+    jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref, jni_new_object,
 };
-use datafusion::common::DataFusionError;
 use datafusion::error::Result;
 use datafusion::logical_expr::ColumnarValue;
 use datafusion::physical_expr::utils::expr_list_eq_any_order;
 use datafusion::physical_plan::PhysicalExpr;
+use datafusion_ext_commons::cancellation::{check_cancelled, thread_cancellation_token};
 
 use jni::objects::GlobalRef;
 use once_cell::sync::OnceCell;
@@ -120,11 +119,8 @@ impl PhysicalExpr for SparkUDFWrapperExpr {
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        if !is_task_running() {
-            return Err(DataFusionError::Execution(
-                "SparkUDFWrapper: is_task_running=false".to_string(),
-            ));
-        }
+        // evaluated in native worker threads, where the task token is bound
+        check_cancelled(&thread_cancellation_token())?;
 
         let batch_schema = batch.schema();
 
@@ -0,0 +1,303 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::{Array, BooleanArray, StringArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use regex::RegexSet;
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// spark's LIKE ALL/LIKE ANY/NOT LIKE ALL/NOT LIKE ANY, all patterns are
+/// matched in a single pass of each string.
+#[derive(Debug)]
+pub struct StringLikeMultiExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    patterns: Vec<String>,
+    has_null_pattern: bool,
+    is_all: bool,
+    negated: bool,
+    regex_set: RegexSet,
+}
+
+impl PartialEq<dyn Any> for StringLikeMultiExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| {
+                self.expr.eq(&x.expr)
+                    && self.patterns == x.patterns
+                    && self.has_null_pattern == x.has_null_pattern
+                    && self.is_all == x.is_all
+                    && self.negated == x.negated
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl Hash for StringLikeMultiExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.expr.hash(state);
+        self.patterns.hash(state);
+        self.has_null_pattern.hash(state);
+        self.is_all.hash(state);
+        self.negated.hash(state);
+    }
+}
+
+impl StringLikeMultiExpr {
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        patterns: Vec<String>,
+        has_null_pattern: bool,
+        is_all: bool,
+        negated: bool,
+    ) -> Result<Self> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| like_pattern_to_regex(pattern))
+            .collect::<Result<Vec<_>>>()?;
+        let regex_set = RegexSet::new(regexes)
+            .map_err(|err| DataFusionError::Plan(format!("invalid like patterns: {err}")))?;
+        Ok(Self {
+            expr,
+            patterns,
+            has_null_pattern,
+            is_all,
+            negated,
+            regex_set,
+        })
+    }
+
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn has_null_pattern(&self) -> bool {
+        self.has_null_pattern
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.is_all
+    }
+
+    pub fn negated(&self) -> bool {
+        self.negated
+    }
+
+    // same as spark's MultiLikeBase.matches()
+    fn matches(&self, s: &str) -> Option<bool> {
+        let matched = match (self.is_all, self.negated) {
+            (true, false) => self.regex_set.matches(s).matched_all(),
+            (false, false) => self.regex_set.is_match(s),
+            (true, true) => !self.regex_set.is_match(s),
+            (false, true) => self.regex_set.matches(s).iter().count() < self.patterns.len(),
+        };
+        match (self.is_all, matched) {
+            (true, true) | (false, false) if self.has_null_pattern => None,
+            _ => Some(matched),
+        }
+    }
+}
+
+impl Display for StringLikeMultiExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = match (self.is_all, self.negated) {
+            (true, false) => "LikeAll",
+            (false, false) => "LikeAny",
+            (true, true) => "NotLikeAll",
+            (false, true) => "NotLikeAny",
+        };
+        write!(f, "{}({}, {:?})", op, self.expr, self.patterns)
+    }
+}
+
+impl PhysicalExpr for StringLikeMultiExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let expr = self.expr.evaluate(batch)?;
+
+        match expr {
+            ColumnarValue::Array(array) => {
+                let string_array = array.as_any().downcast_ref::<StringArray>().unwrap();
+                let ret_array =
+                    Arc::new(BooleanArray::from_iter(string_array.iter().map(
+                        |maybe_string| maybe_string.and_then(|string| self.matches(string)),
+                    )));
+                Ok(ColumnarValue::Array(ret_array))
+            }
+            ColumnarValue::Scalar(ScalarValue::Utf8(maybe_string)) => {
+                let ret = maybe_string.and_then(|string| self.matches(&string));
+                Ok(ColumnarValue::Scalar(ScalarValue::Boolean(ret)))
+            }
+            expr => Err(DataFusionError::Plan(format!(
+                "like_multi: invalid expr: {:?}",
+                expr
+            ))),
+        }
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self {
+            expr: children[0].clone(),
+            patterns: self.patterns.clone(),
+            has_null_pattern: self.has_null_pattern,
+            is_all: self.is_all,
+            negated: self.negated,
+            regex_set: self.regex_set.clone(),
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+// same as spark's StringUtils.escapeLikeRegex() with the default escape char '\'
+fn like_pattern_to_regex(pattern: &str) -> Result<String> {
+    let mut translated = String::from("(?s)^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('_' | '%' | '\\')) => {
+                    translated.push_str(&regex::escape(escaped.encode_utf8(&mut [0; 4])))
+                }
+                Some(escaped) => {
+                    return Err(DataFusionError::Plan(format!(
+                        "the escape character is not allowed to precede '{escaped}'"
+                    )));
+                }
+                None => {
+                    return Err(DataFusionError::Plan(
+                        "it is not allowed to end with the escape character".to_string(),
+                    ));
+                }
+            },
+            '_' => translated.push('.'),
+            '%' => translated.push_str(".*"),
+            c => translated.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    translated.push('$');
+    Ok(translated)
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::array::{ArrayRef, BooleanArray, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::physical_expr::{expressions as phys_expr, PhysicalExpr};
+    use std::sync::Arc;
+
+    use crate::string_like_multi::StringLikeMultiExpr;
+
+    #[test]
+    fn test_ok() {
+        let string_array: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("foo"),
+            Some("bar"),
+            Some("foobar"),
+            Some("f_o%"),
+        ]));
+        let schema = Arc::new(Schema::new(vec![Field::new("col", DataType::Utf8, true)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![string_array]).expect("Error creating RecordBatch");
+
+        let eval = |patterns: &[&str], has_null_pattern, is_all, negated| {
+            let expr = Arc::new(
+                StringLikeMultiExpr::try_new(
+                    phys_expr::col("col", &batch.schema()).unwrap(),
+                    patterns.iter().map(|p| p.to_string()).collect(),
+                    has_null_pattern,
+                    is_all,
+                    negated,
+                )
+                .unwrap(),
+            );
+            expr.evaluate(&batch)
+                .expect("Error evaluating expr")
+                .into_array(batch.num_rows())
+        };
+        let expected = |values: Vec<Option<bool>>| Arc::new(BooleanArray::from(values)) as ArrayRef;
+
+        // like all
+        assert_eq!(
+            &eval(&["f%", "%o%"], false, true, false),
+            &expected(vec![None, Some(true), Some(false), Some(true), Some(true)])
+        );
+        // like any
+        assert_eq!(
+            &eval(&["%bar", "f\\_o\\%"], false, false, false),
+            &expected(vec![None, Some(false), Some(true), Some(true), Some(true)])
+        );
+        // not like all
+        assert_eq!(
+            &eval(&["%bar", "f_o"], false, true, true),
+            &expected(vec![
+                None,
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(true)
+            ])
+        );
+        // not like any
+        assert_eq!(
+            &eval(&["%bar", "f_o"], false, false, true),
+            &expected(vec![None, Some(true), Some(true), Some(true), Some(true)])
+        );
+
+        // null patterns
+        assert_eq!(
+            &eval(&["f%"], true, true, false),
+            &expected(vec![None, None, Some(false), None, None])
+        );
+        assert_eq!(
+            &eval(&["f%"], true, false, false),
+            &expected(vec![None, Some(true), None, Some(true), Some(true)])
+        );
+    }
+}
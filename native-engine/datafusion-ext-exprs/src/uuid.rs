@@ -0,0 +1,159 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::down_cast_any_ref;
+use arrow::array::StringArray;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::current_partition_id;
+use datafusion::common::Result;
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::physical_plan::PhysicalExpr;
+use datafusion_ext_commons::spark_random::MersenneTwister;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// generates version 4 uuid strings like spark's Uuid, using the same
+/// MersenneTwister seeded with `seed + partitionIndex` as spark's
+/// RandomUUIDGenerator, so the outputs are identical to spark's.
+pub struct UuidExpr {
+    seed: i64,
+    rng: Mutex<Option<MersenneTwister>>,
+}
+
+impl UuidExpr {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed,
+            rng: Mutex::new(None),
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+}
+
+impl Debug for UuidExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for UuidExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Uuid({})", self.seed)
+    }
+}
+
+impl Hash for UuidExpr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.seed.hash(state);
+    }
+}
+
+impl PartialEq<dyn Any> for UuidExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        down_cast_any_ref(other)
+            .downcast_ref::<Self>()
+            .map(|x| self.seed == x.seed)
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for UuidExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let mut rng = self.rng.lock();
+        if rng.is_none() {
+            let partition_id = current_partition_id()? as i64;
+            *rng = Some(MersenneTwister::new(self.seed.wrapping_add(partition_id)));
+        }
+        let rng = rng.as_mut().unwrap();
+        let uuids: StringArray = (0..batch.num_rows())
+            .map(|_| Some(next_uuid_string(rng)))
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(uuids)))
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        _children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(self)
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        let mut s = state;
+        self.hash(&mut s);
+    }
+}
+
+fn next_uuid_string(rng: &mut MersenneTwister) -> String {
+    // same as spark's RandomUUIDGenerator.getNextUUID()
+    let most_sig_bits = (rng.next_long() as u64 & 0xFFFFFFFFFFFF0FFF) | 0x0000000000004000;
+    let least_sig_bits = (rng.next_long() as u64 | 0x8000000000000000) & 0xBFFFFFFFFFFFFFFF;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        most_sig_bits >> 32,
+        (most_sig_bits >> 16) & 0xFFFF,
+        most_sig_bits & 0xFFFF,
+        least_sig_bits >> 48,
+        least_sig_bits & 0xFFFFFFFFFFFF,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::uuid::UuidExpr;
+    use arrow::array::{Array, StringArray};
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+    use datafusion::physical_expr::PhysicalExpr;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_uuid() {
+        let batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::empty()),
+            vec![],
+            &RecordBatchOptions::new().with_row_count(Some(2)),
+        )
+        .unwrap();
+        // expected values are spark's output of uuid(42) in partition 0
+        let expr = UuidExpr::new(42);
+        let ret = expr.evaluate(&batch).unwrap().into_array(2);
+        let ret = ret.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(ret.value(0), "6f155395-c8b9-436b-a39c-d247226bc2b2");
+        assert_eq!(ret.value(1), "92da2253-2186-4525-a440-17ad3083a275");
+    }
+}
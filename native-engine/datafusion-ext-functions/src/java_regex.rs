@@ -0,0 +1,453 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// compatibility layer translating java regex (java.util.regex.Pattern) to the
+// rust regex dialect, so that regex functions produce the same results as spark.
+// constructs requiring backtracking (lookarounds, atomic groups, possessive
+// quantifiers and backreferences) cannot be emulated and are rejected, these
+// expressions are not converted to native on the jvm side.
+
+use datafusion::common::{DataFusionError, Result};
+use regex::{Captures, Match, Regex};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const MAX_CACHED_REGEXES: usize = 64;
+
+// line terminators of java, used by `.` without DOTALL flag
+const JAVA_LINE_TERMINATORS: &str = "\\n\\r\\x{85}\\x{2028}\\x{2029}";
+const JAVA_VERTICAL_WHITESPACES: &str = "\\n\\x0B\\f\\r\\x{85}\\x{2028}\\x{2029}";
+const JAVA_HORIZONTAL_WHITESPACES: &str =
+    " \\t\\xA0\\x{1680}\\x{180E}\\x{2000}-\\x{200A}\\x{202F}\\x{205F}\\x{3000}";
+
+/// compiles a java regex, compiled regexes are cached in the current thread.
+pub fn compile_java_regex(pattern: &str) -> Result<Regex> {
+    thread_local! {
+        static CACHED_REGEXES: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+    }
+    CACHED_REGEXES.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        if let Some(regex) = cached.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(&translate_java_regex(pattern)?).map_err(|err| {
+            DataFusionError::Execution(format!("invalid regex pattern {pattern}: {err}"))
+        })?;
+        if cached.len() >= MAX_CACHED_REGEXES {
+            cached.clear();
+        }
+        cached.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    })
+}
+
+/// iterates matches like java's Matcher.find(), unlike rust regex, an empty
+/// match right after a non-empty match is also reported.
+pub fn java_find_iter<'a>(regex: &'a Regex, haystack: &'a str) -> impl Iterator<Item = Match<'a>> {
+    let mut start = Some(0);
+    std::iter::from_fn(move || {
+        let m = regex.find_at(haystack, start?)?;
+        start = next_search_start(haystack, m.start(), m.end());
+        Some(m)
+    })
+}
+
+/// same as `java_find_iter()`, returning captures of each match.
+pub fn java_captures_iter<'a>(
+    regex: &'a Regex,
+    haystack: &'a str,
+) -> impl Iterator<Item = Captures<'a>> {
+    let mut start = Some(0);
+    std::iter::from_fn(move || {
+        let captures = regex.captures_at(haystack, start?)?;
+        let m = captures.get(0).unwrap();
+        start = next_search_start(haystack, m.start(), m.end());
+        Some(captures)
+    })
+}
+
+// an empty match is never reported twice at the same position
+fn next_search_start(haystack: &str, match_start: usize, match_end: usize) -> Option<usize> {
+    if match_start < match_end {
+        return Some(match_end);
+    }
+    let next_char = haystack[match_end..].chars().next()?;
+    Some(match_end + next_char.len_utf8())
+}
+
+// java flags emulated in translation. like java, flags changed inside a group
+// are restored at the end of the group
+#[derive(Clone, Copy, Default)]
+struct JavaRegexFlags {
+    dotall: bool,
+    unix_lines: bool,
+    unicode_classes: bool,
+}
+
+/// translates java regex syntax to rust regex syntax.
+pub fn translate_java_regex(pattern: &str) -> Result<String> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut translated = String::with_capacity(pattern.len());
+    let mut class_depth = 0;
+    let mut flags = JavaRegexFlags::default();
+    let mut group_flags: Vec<JavaRegexFlags> = vec![];
+    let mut after_quantifier = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let quantified = std::mem::take(&mut after_quantifier);
+        i += 1;
+        match c {
+            '\\' => {
+                let escaped = *chars.get(i).ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "invalid regex pattern {pattern}: unexpected trailing backslash"
+                    ))
+                })?;
+                i += 1;
+                match escaped {
+                    // shorthand classes and word boundaries are ascii-only in java,
+                    // unless UNICODE_CHARACTER_CLASS (?U) is enabled
+                    'd' if !flags.unicode_classes => translated.push_str("[0-9]"),
+                    'D' if !flags.unicode_classes => translated.push_str("[^0-9]"),
+                    'w' if !flags.unicode_classes => translated.push_str("[0-9A-Za-z_]"),
+                    'W' if !flags.unicode_classes => translated.push_str("[^0-9A-Za-z_]"),
+                    's' if !flags.unicode_classes => translated.push_str("[\\t\\n\\x0B\\f\\r ]"),
+                    'S' if !flags.unicode_classes => translated.push_str("[^\\t\\n\\x0B\\f\\r ]"),
+                    'b' if class_depth == 0 && !flags.unicode_classes => {
+                        translated.push_str("(?-u:\\b)")
+                    }
+                    'B' if class_depth == 0 && !flags.unicode_classes => {
+                        translated.push_str("(?-u:\\B)")
+                    }
+                    'h' => translated.push_str(&format!("[{JAVA_HORIZONTAL_WHITESPACES}]")),
+                    'H' => translated.push_str(&format!("[^{JAVA_HORIZONTAL_WHITESPACES}]")),
+                    'v' => translated.push_str(&format!("[{JAVA_VERTICAL_WHITESPACES}]")),
+                    'V' => translated.push_str(&format!("[^{JAVA_VERTICAL_WHITESPACES}]")),
+                    'R' => {
+                        translated.push_str(&format!("(?:\\r\\n|[{JAVA_VERTICAL_WHITESPACES}])"))
+                    }
+                    'e' => translated.push_str("\\x1B"),
+                    'c' => {
+                        // control character \cX
+                        let x = *chars.get(i).ok_or_else(|| {
+                            DataFusionError::Execution(format!(
+                                "invalid regex pattern {pattern}: illegal control escape"
+                            ))
+                        })?;
+                        i += 1;
+                        translated.push_str(&format!("\\x{{{:X}}}", x as u32 ^ 0x40));
+                    }
+                    '0' => {
+                        // octal escape \0n, \0nn or \0mnn (m <= 3)
+                        let max_digits = match chars.get(i) {
+                            Some('0'..='3') => 3,
+                            _ => 2,
+                        };
+                        let num_digits = chars[i..]
+                            .iter()
+                            .take(max_digits)
+                            .take_while(|c| ('0'..='7').contains(c))
+                            .count();
+                        if num_digits == 0 {
+                            return Err(DataFusionError::Execution(format!(
+                                "invalid regex pattern {pattern}: illegal octal escape"
+                            )));
+                        }
+                        let octal = chars[i..i + num_digits].iter().collect::<String>();
+                        let value = u32::from_str_radix(&octal, 8).unwrap();
+                        translated.push_str(&format!("\\x{{{value:X}}}"));
+                        i += num_digits;
+                    }
+                    'Q' => {
+                        // quotation \Q...\E
+                        let end = (i..chars.len())
+                            .find(|&j| chars[j] == '\\' && chars.get(j + 1) == Some(&'E'))
+                            .unwrap_or(chars.len());
+                        let quoted = chars[i..end].iter().collect::<String>();
+                        translated.push_str(&regex::escape(&quoted));
+                        i = (end + 2).min(chars.len());
+                    }
+                    'p' | 'P' => {
+                        let name = match chars.get(i) {
+                            Some('{') => {
+                                let end = (i..chars.len()).find(|&j| chars[j] == '}').ok_or_else(
+                                    || {
+                                        DataFusionError::Execution(format!(
+                                            "invalid regex pattern {pattern}: unclosed property"
+                                        ))
+                                    },
+                                )?;
+                                let name = chars[i + 1..end].iter().collect::<String>();
+                                i = end + 1;
+                                name
+                            }
+                            Some(&single) => {
+                                i += 1;
+                                single.to_string()
+                            }
+                            None => {
+                                return Err(DataFusionError::Execution(format!(
+                                    "invalid regex pattern {pattern}: illegal property escape"
+                                )));
+                            }
+                        };
+                        let class = translate_java_property(&name, flags.unicode_classes)?;
+                        let negated = if escaped == 'P' { "^" } else { "" };
+                        translated.push_str(&format!("[{negated}{class}]"));
+                    }
+                    '1'..='9' | 'k' | 'G' | 'Z' | 'X' => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "unsupported regex construct \\{escaped} in pattern {pattern}"
+                        )));
+                    }
+                    escaped => {
+                        translated.push('\\');
+                        translated.push(escaped);
+                    }
+                }
+            }
+            '[' => {
+                class_depth += 1;
+                translated.push(c);
+            }
+            ']' if class_depth > 0 => {
+                class_depth -= 1;
+                translated.push(c);
+            }
+            '.' if class_depth == 0 && !flags.dotall => {
+                if flags.unix_lines {
+                    translated.push_str("[^\\n]");
+                } else {
+                    translated.push_str(&format!("[^{JAVA_LINE_TERMINATORS}]"));
+                }
+            }
+            '(' if class_depth == 0 && chars.get(i) == Some(&'?') => {
+                let flags_start = i + 1;
+                let flags_end = (flags_start..chars.len())
+                    .find(|&j| !chars[j].is_ascii_alphabetic() && chars[j] != '-')
+                    .unwrap_or(chars.len());
+                match chars.get(flags_end) {
+                    // inline flags (?flags) or (?flags:X)
+                    Some(&end @ (')' | ':')) if flags_end > flags_start => {
+                        // (?flags:X) is a group, flags are restored after X
+                        if end == ':' {
+                            group_flags.push(flags);
+                        }
+                        let mut rust_flags = String::new();
+                        let mut enabling = true;
+                        for &flag in &chars[flags_start..flags_end] {
+                            match flag {
+                                '-' => {
+                                    enabling = false;
+                                    rust_flags.push(flag);
+                                }
+                                'U' => flags.unicode_classes = enabling, // UNICODE_CHARACTER_CLASS
+                                'd' => flags.unix_lines = enabling,      // UNIX_LINES
+                                'u' => {} // UNICODE_CASE, always enabled in rust regex
+                                's' | 'i' | 'm' | 'x' => {
+                                    if flag == 's' {
+                                        flags.dotall = enabling;
+                                    }
+                                    rust_flags.push(flag);
+                                }
+                                _ => {
+                                    return Err(DataFusionError::Execution(format!(
+                                        "invalid regex pattern {pattern}: unknown flag {flag}"
+                                    )));
+                                }
+                            }
+                        }
+                        let rust_flags = rust_flags.trim_end_matches('-');
+                        match end {
+                            ')' if rust_flags.is_empty() => {}
+                            ')' => translated.push_str(&format!("(?{rust_flags})")),
+                            _ => translated.push_str(&format!("(?{rust_flags}:")),
+                        }
+                        i = flags_end + 1;
+                    }
+                    _ => match chars.get(flags_start) {
+                        Some('=' | '!' | '>') => {
+                            return Err(DataFusionError::NotImplemented(format!(
+                                "unsupported regex lookaround/atomic group in pattern {pattern}"
+                            )));
+                        }
+                        Some('<') if matches!(chars.get(flags_start + 1), Some('=' | '!')) => {
+                            return Err(DataFusionError::NotImplemented(format!(
+                                "unsupported regex lookbehind in pattern {pattern}"
+                            )));
+                        }
+                        _ => {
+                            group_flags.push(flags);
+                            translated.push(c);
+                        }
+                    },
+                }
+            }
+            '(' if class_depth == 0 => {
+                group_flags.push(flags);
+                translated.push(c);
+            }
+            ')' if class_depth == 0 => {
+                if let Some(outer_flags) = group_flags.pop() {
+                    flags = outer_flags;
+                }
+                translated.push(c);
+            }
+            '+' if class_depth == 0 && quantified => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "unsupported regex possessive quantifier in pattern {pattern}"
+                )));
+            }
+            '*' | '+' | '?' | '}' if class_depth == 0 => {
+                after_quantifier = true;
+                translated.push(c);
+            }
+            c => translated.push(c),
+        }
+    }
+    Ok(translated)
+}
+
+fn translate_java_property(name: &str, unicode_classes: bool) -> Result<String> {
+    let class = match (name, unicode_classes) {
+        // posix classes are ascii-only unless UNICODE_CHARACTER_CLASS is enabled
+        ("Lower", false) => "a-z",
+        ("Upper", false) => "A-Z",
+        ("ASCII", _) => "\\x00-\\x7F",
+        ("Alpha", false) => "a-zA-Z",
+        ("Digit", false) => "0-9",
+        ("Alnum", false) => "a-zA-Z0-9",
+        ("Punct", false) => "!-/:-@\\[-`{-~",
+        ("Graph", false) => "!-~",
+        ("Print", false) => " -~",
+        ("Blank", false) => " \\t",
+        ("Cntrl", false) => "\\x00-\\x1F\\x7F",
+        ("XDigit", false) => "0-9a-fA-F",
+        ("Space", false) => " \\t\\n\\x0B\\f\\r",
+        ("Lower", true) | ("javaLowerCase", _) => "\\p{Lowercase}",
+        ("Upper", true) | ("javaUpperCase", _) => "\\p{Uppercase}",
+        ("Alpha", true) | ("javaAlphabetic", _) => "\\p{Alphabetic}",
+        ("Digit", true) | ("javaDigit", _) => "\\p{Nd}",
+        ("Alnum", true) => "\\p{Alphabetic}\\p{Nd}",
+        ("Space", true) => "\\p{White_Space}",
+        ("Punct", true) => "\\p{P}",
+        ("Cntrl", true) => "\\p{Cc}",
+        ("XDigit", true) => "\\p{Nd}\\p{Hex_Digit}",
+        ("javaLetter", _) => "\\p{L}",
+        ("javaLetterOrDigit", _) => "\\p{L}\\p{Nd}",
+        (name, _) if name.starts_with("Is") => {
+            // scripts, categories and binary properties, like IsLatin/IsLu/IsAlphabetic
+            return Ok(format!("\\p{{{}}}", &name[2..]));
+        }
+        (name, _)
+            if !name.starts_with("In")
+                && !name.starts_with("java")
+                && !["Blank", "Graph", "Print"].contains(&name) =>
+        {
+            // general categories, like L/Lu/Nd
+            return Ok(format!("\\p{{{name}}}"));
+        }
+        (name, _) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "unsupported regex property \\p{{{name}}}"
+            )));
+        }
+    };
+    Ok(class.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::java_regex::{compile_java_regex, java_find_iter, translate_java_regex};
+    use datafusion::common::Result;
+
+    #[test]
+    fn test_translate_java_regex() -> Result<()> {
+        let is_match = |pattern: &str, s: &str| -> Result<bool> {
+            Ok(compile_java_regex(pattern)?.is_match(s))
+        };
+
+        // ascii-only shorthand classes
+        assert!(is_match("^\\d+$", "123")?);
+        assert!(!is_match("\\d", "\u{0663}")?);
+        assert!(is_match("(?U)\\d", "\u{0663}")?);
+        assert!(!is_match("\\w", "\u{00e9}")?);
+        assert!(is_match("\\bfoo\\b", "a foo b")?);
+
+        // `.` does not match line terminators without DOTALL
+        assert!(!is_match("a.b", "a\rb")?);
+        assert!(is_match("(?s)a.b", "a\rb")?);
+        assert!(is_match("(?d)a.b", "a\rb")?);
+
+        // posix and java properties
+        assert!(is_match("^\\p{Lower}+$", "abc")?);
+        assert!(!is_match("\\p{Lower}", "\u{00e9}")?);
+        assert!(is_match("\\p{javaLowerCase}", "\u{00e9}")?);
+        assert!(is_match("^[\\p{Alpha}\\p{Digit}]+$", "abc123")?);
+        assert!(is_match("^\\P{Digit}+$", "abc")?);
+        assert!(is_match("\\p{IsLatin}", "a")?);
+        assert!(is_match("\\p{Lu}", "A")?);
+
+        // quotations, escapes and inline flags
+        assert!(is_match("^\\Q.*+\\E$", ".*+")?);
+        assert!(!is_match("^\\Q.*+\\E$", "abc")?);
+        assert!(is_match("^\\0101\\cA\\e$", "A\u{1}\u{1b}")?);
+        assert!(is_match("\\h\\R", " \r\n")?);
+        assert!(is_match("(?iu)ABC", "abc")?);
+        assert!(is_match("(?u:ABC)", "ABC")?);
+
+        // scoped flags end with their groups
+        assert!(is_match("^(?s:a.)b$", "a\nb")?);
+        assert!(!is_match("^(?s:a.)b.$", "a\nb\r")?);
+        assert!(is_match("^(?U:\\d)$", "\u{0663}")?);
+        assert!(!is_match("^(?U:\\d)\\d$", "\u{0663}\u{0663}")?);
+        assert!(is_match("^(?d:a.)b$", "a\rb")?);
+        assert!(!is_match("^(?d:a.)b.$", "a\rb\r")?);
+        assert!(is_match("^(a(?s).)b$", "a\nb")?);
+        assert!(!is_match("^(a(?s).)b.$", "a\nb\r")?);
+
+        // constructs requiring backtracking are rejected
+        assert!(translate_java_regex("a(?=b)").is_err());
+        assert!(translate_java_regex("(?<!a)b").is_err());
+        assert!(translate_java_regex("(?>a)").is_err());
+        assert!(translate_java_regex("a++").is_err());
+        assert!(translate_java_regex("a{2}+").is_err());
+        assert!(translate_java_regex("(a)\\1").is_err());
+        assert!(translate_java_regex("\\p{InGreek}").is_err());
+
+        // but not quantifiers and named groups
+        assert!(is_match("^a\\++$", "a++")?);
+        assert!(is_match("^(?<x>a+)+$", "aa")?);
+        assert!(is_match("^[+]+$", "++")?);
+        assert!(is_match("^\\p{Lu}+$", "AB")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_java_find_iter() -> Result<()> {
+        let find_all = |pattern: &str, s: &str| -> Result<Vec<(usize, usize)>> {
+            let regex = compile_java_regex(pattern)?;
+            Ok(java_find_iter(&regex, s)
+                .map(|m| (m.start(), m.end()))
+                .collect())
+        };
+        assert_eq!(find_all("b*", "abc")?, vec![(0, 0), (1, 2), (2, 2), (3, 3)]);
+        assert_eq!(find_all("a*", "baaa")?, vec![(0, 0), (1, 4), (4, 4)]);
+        assert_eq!(find_all("", "\u{00e9}x")?, vec![(0, 0), (2, 2), (3, 3)]);
+        assert_eq!(find_all("[0-9]+", "a1b22")?, vec![(1, 2), (3, 5)]);
+        Ok(())
+    }
+}
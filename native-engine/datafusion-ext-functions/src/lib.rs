@@ -16,23 +16,37 @@ use datafusion::common::{DataFusionError, Result};
 use datafusion::logical_expr::ScalarFunctionImplementation;
 use std::sync::Arc;
 
+mod java_regex;
+pub mod native_udf;
+mod spark_char_varchar;
 mod spark_check_overflow;
 mod spark_get_json_object;
 mod spark_make_array;
 mod spark_make_decimal;
 mod spark_murmur3_hash;
-mod spark_null_if_zero;
+mod spark_null_if;
+mod spark_partition_hash;
+mod spark_regex;
 mod spark_strings;
 mod spark_unscaled_value;
+mod spark_width_bucket;
 
 pub fn create_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementation> {
+    create_builtin_spark_ext_function(name).or_else(|err| {
+        native_udf::get_native_udf(name).ok_or(err) // fall back to registered native udfs
+    })
+}
+
+fn create_builtin_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementation> {
     Ok(match name {
         "Placeholder" => Arc::new(|_| panic!("placeholder() should never be called")),
-        "NullIfZero" => Arc::new(spark_null_if_zero::spark_null_if_zero),
+        "NullIf" => Arc::new(spark_null_if::spark_null_if),
+        "NullIfZero" => Arc::new(spark_null_if::spark_null_if_zero),
         "UnscaledValue" => Arc::new(spark_unscaled_value::spark_unscaled_value),
         "MakeDecimal" => Arc::new(spark_make_decimal::spark_make_decimal),
         "CheckOverflow" => Arc::new(spark_check_overflow::spark_check_overflow),
         "Murmur3Hash" => Arc::new(spark_murmur3_hash::spark_murmur3_hash),
+        "spark_partition_hash" => Arc::new(spark_partition_hash::spark_partition_hash),
         "GetJsonObject" => Arc::new(spark_get_json_object::spark_get_json_object),
         "GetParsedJsonObject" => Arc::new(spark_get_json_object::spark_get_parsed_json_object),
         "ParseJson" => Arc::new(spark_get_json_object::spark_parse_json),
@@ -44,6 +58,13 @@ pub fn create_spark_ext_function(name: &str) -> Result<ScalarFunctionImplementat
         "StringConcatWs" => Arc::new(spark_strings::string_concat_ws),
         "StringLower" => Arc::new(spark_strings::string_lower),
         "StringUpper" => Arc::new(spark_strings::string_upper),
+        "RegexpLike" => Arc::new(spark_regex::regexp_like),
+        "RegexpReplace" => Arc::new(spark_regex::regexp_replace),
+        "RegexpExtract" => Arc::new(spark_regex::regexp_extract),
+        "WidthBucket" => Arc::new(spark_width_bucket::spark_width_bucket),
+        "CharTypeWriteSideCheck" => Arc::new(spark_char_varchar::char_type_write_side_check),
+        "VarcharTypeWriteSideCheck" => Arc::new(spark_char_varchar::varchar_type_write_side_check),
+        "ReadSidePadding" => Arc::new(spark_char_varchar::read_side_padding),
 
         _ => Err(DataFusionError::NotImplemented(format!(
             "spark ext function not implemented: {}",
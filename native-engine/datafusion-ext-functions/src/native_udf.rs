@@ -0,0 +1,108 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// rust scalar functions registered at engine startup, callable from spark side
+// as ext functions (ScalarFunction::SparkExtFunctions) with the registered
+// name. registration hooks added with `add_registration_hook` are invoked once
+// when initializing the native engine.
+
+use std::collections::HashMap;
+
+use datafusion::common::{DataFusionError, Result};
+use datafusion::logical_expr::ScalarFunctionImplementation;
+use once_cell::sync::{Lazy, OnceCell};
+use parking_lot::{Mutex, RwLock};
+
+pub type RegistrationHook = fn() -> Result<()>;
+
+static NATIVE_UDFS: Lazy<RwLock<HashMap<String, ScalarFunctionImplementation>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+static REGISTRATION_HOOKS: Lazy<Mutex<Vec<RegistrationHook>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// registers a rust scalar function, builtin ext functions cannot be replaced
+pub fn register_native_udf(name: &str, fun: ScalarFunctionImplementation) -> Result<()> {
+    if crate::create_builtin_spark_ext_function(name).is_ok() {
+        return Err(DataFusionError::Plan(format!(
+            "cannot register native udf {}: conflicts with builtin ext function",
+            name
+        )));
+    }
+    log::info!("registering native udf: {}", name);
+    NATIVE_UDFS.write().insert(name.to_string(), fun);
+    Ok(())
+}
+
+pub(crate) fn get_native_udf(name: &str) -> Option<ScalarFunctionImplementation> {
+    NATIVE_UDFS.read().get(name).cloned()
+}
+
+/// adds a hook registering native udfs, must be called before initializing
+/// the native engine
+pub fn add_registration_hook(hook: RegistrationHook) {
+    REGISTRATION_HOOKS.lock().push(hook);
+}
+
+/// runs all registration hooks, only the first call takes effect
+pub fn run_registration_hooks() -> Result<()> {
+    static HOOKS_DONE: OnceCell<()> = OnceCell::new();
+    HOOKS_DONE.get_or_try_init(|| {
+        for hook in REGISTRATION_HOOKS.lock().iter() {
+            hook()?;
+        }
+        Ok::<_, DataFusionError>(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::create_spark_ext_function;
+    use crate::native_udf::{add_registration_hook, register_native_udf, run_registration_hooks};
+    use arrow::array::{Array, Int32Array};
+    use datafusion::common::Result;
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    fn register_plus_one() -> Result<()> {
+        register_native_udf(
+            "TestPlusOne",
+            Arc::new(|args| {
+                let array = args[0].clone().into_array(1);
+                let array = array.as_any().downcast_ref::<Int32Array>().unwrap();
+                let result: Int32Array = array.iter().map(|v| v.map(|v| v + 1)).collect();
+                Ok(ColumnarValue::Array(Arc::new(result)))
+            }),
+        )
+    }
+
+    #[test]
+    fn test_native_udf() -> Result<()> {
+        assert!(create_spark_ext_function("TestPlusOne").is_err());
+        add_registration_hook(register_plus_one);
+        run_registration_hooks()?;
+
+        let fun = create_spark_ext_function("TestPlusOne")?;
+        let input = ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(1), None])));
+        let output = fun(&[input])?.into_array(2);
+        assert_eq!(
+            output.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(2), None]),
+        );
+
+        // builtin functions cannot be replaced
+        assert!(register_native_udf("NullIfZero", Arc::new(|args| Ok(args[0].clone()))).is_err());
+        Ok(())
+    }
+}
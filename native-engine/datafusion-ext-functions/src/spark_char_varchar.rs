@@ -0,0 +1,193 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_exprs::raise_error::raised_error;
+use std::sync::Arc;
+
+/// write-side check of CHAR(n): pads to n chars, or trims trailing spaces if
+/// longer, like spark's CharVarcharCodegenUtils.charTypeWriteSideCheck().
+pub fn char_type_write_side_check(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    map_strings_with_limit(args, |s, limit| {
+        let num_chars = s.chars().count();
+        if num_chars == limit {
+            Ok(s.to_string())
+        } else if num_chars < limit {
+            Ok(rpad_spaces(s, num_chars, limit))
+        } else {
+            trim_trailing_spaces(s, num_chars, limit)
+        }
+    })
+}
+
+/// write-side check of VARCHAR(n): trims trailing spaces if longer than n
+/// chars, like spark's CharVarcharCodegenUtils.varcharTypeWriteSideCheck().
+pub fn varchar_type_write_side_check(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    map_strings_with_limit(args, |s, limit| {
+        let num_chars = s.chars().count();
+        if num_chars <= limit {
+            Ok(s.to_string())
+        } else {
+            trim_trailing_spaces(s, num_chars, limit)
+        }
+    })
+}
+
+/// read-side padding of CHAR(n), like spark's
+/// CharVarcharCodegenUtils.readSidePadding().
+pub fn read_side_padding(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    map_strings_with_limit(args, |s, limit| {
+        let num_chars = s.chars().count();
+        if num_chars < limit {
+            Ok(rpad_spaces(s, num_chars, limit))
+        } else {
+            Ok(s.to_string())
+        }
+    })
+}
+
+fn map_strings_with_limit(
+    args: &[ColumnarValue],
+    f: impl Fn(&str, usize) -> Result<String>,
+) -> Result<ColumnarValue> {
+    let limit = match &args[1] {
+        ColumnarValue::Scalar(ScalarValue::Int32(Some(limit))) if *limit >= 0 => *limit as usize,
+        _ => {
+            return Err(DataFusionError::Execution(
+                "char/varchar length limit only supports non-negative literal int32".to_string(),
+            ));
+        }
+    };
+
+    match &args[0] {
+        ColumnarValue::Array(array) => {
+            let strings = as_string_array(array)?;
+            let mut builder =
+                StringBuilder::with_capacity(strings.len(), strings.value_data().len());
+            for s in strings {
+                match s {
+                    Some(s) => builder.append_value(f(s, limit)?),
+                    None => builder.append_null(),
+                }
+            }
+            Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+        }
+        ColumnarValue::Scalar(ScalarValue::Utf8(s)) => Ok(ColumnarValue::Scalar(
+            ScalarValue::Utf8(s.as_ref().map(|s| f(s, limit)).transpose()?),
+        )),
+        other => Err(DataFusionError::Execution(format!(
+            "char/varchar: unsupported input: {:?}",
+            other
+        ))),
+    }
+}
+
+fn rpad_spaces(s: &str, num_chars: usize, limit: usize) -> String {
+    let mut padded = String::with_capacity(s.len() + limit - num_chars);
+    padded.push_str(s);
+    padded.extend(std::iter::repeat(' ').take(limit - num_chars));
+    padded
+}
+
+fn trim_trailing_spaces(s: &str, num_chars: usize, limit: usize) -> Result<String> {
+    // only trailing spaces are allowed to be trimmed
+    let num_tail_spaces = s.bytes().rev().take_while(|&b| b == b' ').count();
+    let num_tail_spaces_to_trim = num_tail_spaces.min(num_chars - limit);
+    if num_chars - num_tail_spaces_to_trim > limit {
+        return Err(raised_error(&format!(
+            "Exceeds char/varchar type length limitation: {}",
+            limit
+        )));
+    }
+    Ok(s[..s.len() - num_tail_spaces_to_trim].to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_char_varchar::{
+        char_type_write_side_check, read_side_padding, varchar_type_write_side_check,
+    };
+    use arrow::array::{ArrayRef, StringArray};
+    use datafusion::common::ScalarValue;
+    use datafusion::logical_expr::ColumnarValue;
+    use datafusion_ext_exprs::raise_error::extract_raised_error_message;
+    use std::sync::Arc;
+
+    fn strings(values: Vec<Option<&str>>) -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(StringArray::from(values)))
+    }
+
+    fn limit(n: i32) -> ColumnarValue {
+        ColumnarValue::Scalar(ScalarValue::Int32(Some(n)))
+    }
+
+    #[test]
+    fn test_char_type_write_side_check() {
+        let result = char_type_write_side_check(&[
+            strings(vec![
+                Some("ab"),
+                Some("abc"),
+                Some("abc   "),
+                Some("中文"),
+                None,
+            ]),
+            limit(3),
+        ])
+        .unwrap()
+        .into_array(5);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("ab "),
+            Some("abc"),
+            Some("abc"),
+            Some("中文 "),
+            None,
+        ]));
+        assert_eq!(&result, &expected);
+
+        let err = char_type_write_side_check(&[strings(vec![Some("abcd")]), limit(3)])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            extract_raised_error_message(&err),
+            Some("Exceeds char/varchar type length limitation: 3")
+        );
+    }
+
+    #[test]
+    fn test_varchar_type_write_side_check() {
+        let result = varchar_type_write_side_check(&[
+            strings(vec![Some("ab"), Some("abc  "), Some("a b  ")]),
+            limit(3),
+        ])
+        .unwrap()
+        .into_array(3);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("ab"),
+            Some("abc"),
+            Some("a b"),
+        ]));
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_read_side_padding() {
+        let result = read_side_padding(&[strings(vec![Some("a"), Some("abcd")]), limit(3)])
+            .unwrap()
+            .into_array(2);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![Some("a  "), Some("abcd")]));
+        assert_eq!(&result, &expected);
+    }
+}
@@ -15,7 +15,7 @@
 use arrow::array::*;
 use datafusion::common::Result;
 use datafusion::physical_plan::ColumnarValue;
-use datafusion_ext_commons::spark_hash::create_hashes;
+use datafusion_ext_commons::spark_hash::create_murmur3_hashes;
 use std::sync::Arc;
 
 /// implements org.apache.spark.sql.catalyst.expressions.UnscaledValue
@@ -38,9 +38,7 @@ pub fn spark_murmur3_hash(args: &[ColumnarValue]) -> Result<ColumnarValue> {
         .collect::<Vec<_>>();
 
     // use identical seed as spark hash partition
-    let spark_murmur3_default_seed = 42u32;
-    let mut hash_buffer = vec![spark_murmur3_default_seed; len];
-    create_hashes(&arrays, &mut hash_buffer)?;
+    let hash_buffer = create_murmur3_hashes(&arrays, len)?;
 
     Ok(ColumnarValue::Array(Arc::new(
         Int32Array::from_iter_values(hash_buffer.into_iter().map(|hash| hash as i32)),
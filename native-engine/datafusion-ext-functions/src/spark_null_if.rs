@@ -0,0 +1,193 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use arrow::compute::*;
+use arrow::datatypes::*;
+use datafusion::common::Result;
+use datafusion::common::ScalarValue;
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+/// nullif() function compatible with spark: returns null if the args are
+/// equal, otherwise returns the first arg
+pub fn spark_null_if(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let num_rows = args
+        .iter()
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        })
+        .unwrap_or(1);
+    let left = args[0].clone().into_array(num_rows);
+    let right = args[1].clone().into_array(num_rows);
+
+    // null comparisons are not equal, so nulls in right are kept
+    let eq = eq_dyn(&normalize_floats(&left)?, &normalize_floats(&right)?)?;
+    let result = nullif(&left, &eq)?;
+
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+            &result, 0,
+        )?));
+    }
+    Ok(ColumnarValue::Array(result))
+}
+
+/// used to avoid DivideByZero error in divide/modulo
+pub fn spark_null_if_zero(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let zero = ScalarValue::new_zero(&args[0].data_type())?;
+    spark_null_if(&[args[0].clone(), ColumnarValue::Scalar(zero)])
+}
+
+// -0.0 equals 0.0 and NaNs are equal to each other in spark, but not in arrow's
+// total ordering of floats
+fn normalize_floats(array: &ArrayRef) -> Result<ArrayRef> {
+    Ok(match array.data_type() {
+        DataType::Float32 => Arc::new(
+            as_primitive_array::<Float32Type>(array).unary::<_, Float32Type>(|v| match v {
+                v if v.is_nan() => f32::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        DataType::Float64 => Arc::new(
+            as_primitive_array::<Float64Type>(array).unary::<_, Float64Type>(|v| match v {
+                v if v.is_nan() => f64::NAN,
+                v if v == 0.0 => 0.0,
+                v => v,
+            }),
+        ),
+        _ => array.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_null_if::{spark_null_if, spark_null_if_zero};
+    use arrow::array::{
+        ArrayRef, Date32Array, Decimal128Array, Float32Array, Float64Array, Int32Array, StringArray,
+    };
+    use datafusion::common::ScalarValue;
+    use datafusion::logical_expr::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_null_if_zero_int() {
+        let result = spark_null_if_zero(&vec![ColumnarValue::Array(Arc::new(Int32Array::from(
+            vec![Some(1), None, Some(-1), Some(0)],
+        )))])
+        .unwrap()
+        .into_array(4);
+
+        let expected = Int32Array::from(vec![Some(1), None, Some(-1), None]);
+        let expected: ArrayRef = Arc::new(expected);
+
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_null_if_zero_decimal() {
+        let result = spark_null_if_zero(&vec![ColumnarValue::Scalar(ScalarValue::Decimal128(
+            Some(1230427389124691),
+            20,
+            2,
+        ))])
+        .unwrap()
+        .into_array(1);
+
+        let expected = Decimal128Array::from(vec![Some(1230427389124691)])
+            .with_precision_and_scale(20, 2)
+            .unwrap();
+        let expected: ArrayRef = Arc::new(expected);
+
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_null_if_zero_float() {
+        let result = spark_null_if_zero(&vec![ColumnarValue::Scalar(ScalarValue::Float32(Some(
+            0.0,
+        )))])
+        .unwrap()
+        .into_array(1);
+
+        let expected = Float32Array::from(vec![None]);
+        let expected: ArrayRef = Arc::new(expected);
+
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_null_if_zero_negative_zero() {
+        let result = spark_null_if_zero(&vec![ColumnarValue::Array(Arc::new(Float64Array::from(
+            vec![Some(-0.0), Some(1.0), None],
+        )))])
+        .unwrap()
+        .into_array(3);
+
+        let expected = Float64Array::from(vec![None, Some(1.0), None]);
+        let expected: ArrayRef = Arc::new(expected);
+
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_null_if() {
+        let result = spark_null_if(&vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("b"),
+                None,
+                Some("c"),
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("c"),
+                Some("c"),
+                None,
+            ]))),
+        ])
+        .unwrap()
+        .into_array(4);
+
+        let expected = StringArray::from(vec![None, Some("b"), None, Some("c")]);
+        let expected: ArrayRef = Arc::new(expected);
+        assert_eq!(&result, &expected);
+
+        let result = spark_null_if(&vec![
+            ColumnarValue::Array(Arc::new(Date32Array::from(vec![Some(1), Some(2), None]))),
+            ColumnarValue::Scalar(ScalarValue::Date32(Some(2))),
+        ])
+        .unwrap()
+        .into_array(3);
+
+        let expected = Date32Array::from(vec![Some(1), None, None]);
+        let expected: ArrayRef = Arc::new(expected);
+        assert_eq!(&result, &expected);
+
+        let result = spark_null_if(&vec![
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(f64::NAN))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(-f64::NAN))),
+        ])
+        .unwrap();
+        match result {
+            ColumnarValue::Scalar(scalar) => assert_eq!(scalar, ScalarValue::Float64(None)),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_commons::spark_hash::{create_murmur3_hashes, create_partition_ids};
+use std::sync::Arc;
+
+/// spark_partition_hash(expr..., num_partitions), returns the partition id
+/// assigned to each row by spark's HashPartitioning (and native shuffle).
+pub fn spark_partition_hash(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let (num_partitions, args) = match args.split_last() {
+        Some((ColumnarValue::Scalar(num_partitions), args)) if !args.is_empty() => {
+            let num_partitions = match num_partitions {
+                ScalarValue::Int32(Some(n)) => *n as i64,
+                ScalarValue::Int64(Some(n)) => *n,
+                _ => -1,
+            };
+            if num_partitions <= 0 {
+                return Err(DataFusionError::Execution(format!(
+                    "spark_partition_hash: invalid num_partitions: {num_partitions}"
+                )));
+            }
+            (num_partitions as usize, args)
+        }
+        _ => {
+            return Err(DataFusionError::Execution(
+                "spark_partition_hash: expect exprs and a literal num_partitions".to_string(),
+            ));
+        }
+    };
+
+    let len = args
+        .iter()
+        .map(|arg| match arg {
+            ColumnarValue::Array(array) => array.len(),
+            ColumnarValue::Scalar(_) => 1,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let arrays = args
+        .iter()
+        .map(|arg| match arg {
+            ColumnarValue::Array(array) => array.clone(),
+            ColumnarValue::Scalar(scalar) => scalar.to_array_of_size(len),
+        })
+        .collect::<Vec<_>>();
+
+    let hashes = create_murmur3_hashes(&arrays, len)?;
+    let partition_ids = create_partition_ids(&hashes, num_partitions);
+    Ok(ColumnarValue::Array(Arc::new(
+        Int32Array::from_iter_values(
+            partition_ids
+                .into_iter()
+                .map(|partition_id| partition_id as i32),
+        ),
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_partition_hash::spark_partition_hash;
+    use arrow::array::{ArrayRef, Int32Array, Int64Array};
+    use datafusion::common::ScalarValue;
+    use datafusion::logical_expr::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_partition_hash_int64() {
+        let result = spark_partition_hash(&vec![
+            ColumnarValue::Array(Arc::new(Int64Array::from(vec![
+                Some(1),
+                Some(0),
+                Some(-1),
+                Some(i64::MAX),
+                Some(i64::MIN),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(200))),
+        ])
+        .unwrap()
+        .into_array(5);
+
+        // expected partition from Spark with n=200
+        let expected: ArrayRef = Arc::new(Int32Array::from(vec![69, 5, 193, 171, 115]));
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_partition_hash_invalid_num_partitions() {
+        let args = vec![
+            ColumnarValue::Array(Arc::new(Int64Array::from(vec![1, 2, 3]))),
+            ColumnarValue::Scalar(ScalarValue::Int32(Some(0))),
+        ];
+        assert!(spark_partition_hash(&args).is_err());
+    }
+}
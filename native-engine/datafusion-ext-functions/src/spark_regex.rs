@@ -0,0 +1,431 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::java_regex::{compile_java_regex, java_captures_iter};
+use arrow::array::{ArrayRef, BooleanArray, StringArray};
+use datafusion::common::cast::as_string_array;
+use datafusion::common::{DataFusionError, Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use regex::Regex;
+use std::sync::Arc;
+
+/// rlike() function compatible with spark (java regex find semantics)
+/// rlike('abc123', '\\d+') = true
+pub fn regexp_like(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let regex = match literal_string_arg(&args[1], "regexp_like", "pattern")? {
+        Some(pattern) => compile_java_regex(pattern)?,
+        None => return Ok(ColumnarValue::Scalar(ScalarValue::Boolean(None))),
+    };
+    map_string_arg(&args[0], |strings| {
+        Ok(Arc::new(BooleanArray::from_iter(
+            strings.iter().map(|s| s.map(|s| regex.is_match(s))),
+        )))
+    })
+}
+
+/// regexp_replace() function compatible with spark (java regex and replacement
+/// semantics), replacing starts at the 1-based position
+/// regexp_replace('a1b22', '(\\d+)', '<$1>') = 'a<1>b<22>'
+/// regexp_replace('a1b22', '\\d', '', 3) = 'a1b'
+pub fn regexp_replace(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let pattern = literal_string_arg(&args[1], "regexp_replace", "pattern")?;
+    let replacement = literal_string_arg(&args[2], "regexp_replace", "replacement")?;
+    let position = match args.get(3) {
+        None => 1,
+        Some(ColumnarValue::Scalar(ScalarValue::Int32(Some(position)))) if *position > 0 => {
+            *position as usize
+        }
+        Some(ColumnarValue::Scalar(ScalarValue::Int32(None))) => {
+            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
+        }
+        _ => {
+            return Err(DataFusionError::Execution(
+                "regexp_replace position only supports positive literal int32".to_string(),
+            ));
+        }
+    };
+    let (regex, replacement) = match (pattern, replacement) {
+        (Some(pattern), Some(replacement)) => {
+            let regex = compile_java_regex(pattern)?;
+            let replacement = parse_java_replacement(replacement, &regex)?;
+            (regex, replacement)
+        }
+        _ => return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None))),
+    };
+    map_string_arg(&args[0], |strings| {
+        Ok(Arc::new(StringArray::from_iter(strings.iter().map(|s| {
+            s.map(|s| java_replace(s, &regex, &replacement, position))
+        }))))
+    })
+}
+
+/// regexp_extract() function compatible with spark (java regex semantics),
+/// returns the specified group of the first match, or empty string if no match
+/// regexp_extract('a1b22', '([a-z])(\\d+)', 2) = '1'
+pub fn regexp_extract(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let pattern = literal_string_arg(&args[1], "regexp_extract", "pattern")?;
+    let group_idx = match &args[2] {
+        ColumnarValue::Scalar(ScalarValue::Int32(group_idx)) => *group_idx,
+        _ => {
+            return Err(DataFusionError::Execution(
+                "regexp_extract group index only supports literal int32".to_string(),
+            ));
+        }
+    };
+    let (regex, group_idx) = match (pattern, group_idx) {
+        (Some(pattern), Some(group_idx)) => {
+            let regex = compile_java_regex(pattern)?;
+            let group_count = regex.captures_len() - 1;
+            if group_idx < 0 {
+                return Err(DataFusionError::Execution(
+                    "the specified group index cannot be less than zero".to_string(),
+                ));
+            }
+            if group_idx as usize > group_count {
+                return Err(DataFusionError::Execution(format!(
+                    "regex group count is {group_count}, but the specified group index is {group_idx}"
+                )));
+            }
+            (regex, group_idx as usize)
+        }
+        _ => return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None))),
+    };
+    map_string_arg(&args[0], |strings| {
+        Ok(Arc::new(StringArray::from_iter(strings.iter().map(|s| {
+            s.map(|s| {
+                regex
+                    .captures(s)
+                    .and_then(|captures| captures.get(group_idx))
+                    .map(|m| m.as_str())
+                    .unwrap_or_default()
+            })
+        }))))
+    })
+}
+
+fn literal_string_arg<'a>(
+    arg: &'a ColumnarValue,
+    fn_name: &str,
+    arg_name: &str,
+) -> Result<Option<&'a str>> {
+    match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(value)) => Ok(value.as_deref()),
+        _ => Err(DataFusionError::Execution(format!(
+            "{fn_name} {arg_name} only supports literal string"
+        ))),
+    }
+}
+
+fn map_string_arg(
+    arg: &ColumnarValue,
+    f: impl Fn(&StringArray) -> Result<ArrayRef>,
+) -> Result<ColumnarValue> {
+    match arg {
+        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(f(as_string_array(array)?)?)),
+        ColumnarValue::Scalar(scalar) => {
+            let array = f(as_string_array(&scalar.to_array())?)?;
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &array, 0,
+            )?))
+        }
+    }
+}
+
+enum ReplacementPart {
+    Literal(String),
+    Group(usize),
+}
+
+// same as java's Matcher.appendReplacement(): `$n` and `${name}` refer to
+// captured groups, `\` escapes the next char
+fn parse_java_replacement(replacement: &str, regex: &Regex) -> Result<Vec<ReplacementPart>> {
+    let group_count = regex.captures_len() - 1;
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = replacement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => literal.push(chars.next().ok_or_else(|| {
+                DataFusionError::Execution(
+                    "character to be escaped is missing in replacement".to_string(),
+                )
+            })?),
+            '$' => {
+                let group_idx = match chars.next() {
+                    Some('{') => {
+                        let name = chars.by_ref().take_while(|&c| c != '}').collect::<String>();
+                        regex
+                            .capture_names()
+                            .position(|group_name| group_name == Some(name.as_str()))
+                            .ok_or_else(|| {
+                                DataFusionError::Execution(format!(
+                                    "no group with name {{{name}}} in replacement"
+                                ))
+                            })?
+                    }
+                    Some(digit @ '0'..='9') => {
+                        // the first digit is always part of the group reference, subsequent
+                        // digits are included if they form a legal group reference
+                        let mut group_idx = digit.to_digit(10).unwrap() as usize;
+                        if group_idx > group_count {
+                            return Err(DataFusionError::Execution(format!(
+                                "no group {group_idx} in replacement"
+                            )));
+                        }
+                        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                            let next_group_idx = group_idx * 10 + digit as usize;
+                            if next_group_idx > group_count {
+                                break;
+                            }
+                            group_idx = next_group_idx;
+                            chars.next();
+                        }
+                        group_idx
+                    }
+                    _ => {
+                        return Err(DataFusionError::Execution(
+                            "illegal group reference in replacement".to_string(),
+                        ));
+                    }
+                };
+                if !literal.is_empty() {
+                    parts.push(ReplacementPart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(ReplacementPart::Group(group_idx));
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(ReplacementPart::Literal(literal));
+    }
+    Ok(parts)
+}
+
+// same as spark's RegExpReplace, matching starts at the 1-based char position
+// and the string is returned unchanged if position exceeds its length (empty
+// strings are still matched from position 1)
+fn java_replace(
+    s: &str,
+    regex: &Regex,
+    replacement: &[ReplacementPart],
+    position: usize,
+) -> String {
+    let start = match s.char_indices().nth(position - 1) {
+        Some((start, _)) => start,
+        None if position == 1 => 0,
+        None => return s.to_string(),
+    };
+    let region = &s[start..];
+    let mut replaced = String::with_capacity(s.len());
+    let mut last_end = 0;
+
+    replaced.push_str(&s[..start]);
+    for captures in java_captures_iter(regex, region) {
+        let m = captures.get(0).unwrap();
+        replaced.push_str(&region[last_end..m.start()]);
+        for part in replacement {
+            match part {
+                ReplacementPart::Literal(literal) => replaced.push_str(literal),
+                ReplacementPart::Group(group_idx) => {
+                    replaced.push_str(captures.get(*group_idx).map_or("", |g| g.as_str()))
+                }
+            }
+        }
+        last_end = m.end();
+    }
+    replaced.push_str(&region[last_end..]);
+    replaced
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_regex::{regexp_extract, regexp_like, regexp_replace};
+    use arrow::array::{ArrayRef, BooleanArray, StringArray};
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::physical_plan::ColumnarValue;
+    use std::sync::Arc;
+
+    fn strings() -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(StringArray::from(vec![
+            Some("a1b22c333"),
+            Some("abc"),
+            Some("\u{0663}x\ny"),
+            Some(""),
+            None,
+        ])))
+    }
+
+    // expected values are outputs of spark's rlike/regexp_replace/regexp_extract
+    #[test]
+    fn test_regexp_like() -> Result<()> {
+        let rlike = |pattern: &str| -> Result<ArrayRef> {
+            Ok(
+                regexp_like(&[strings(), ColumnarValue::Scalar(ScalarValue::from(pattern))])?
+                    .into_array(5),
+            )
+        };
+        let expected = |values: Vec<Option<bool>>| Arc::new(BooleanArray::from(values)) as ArrayRef;
+
+        assert_eq!(
+            &rlike("\\d+")?,
+            &expected(vec![
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                None
+            ])
+        );
+        assert_eq!(
+            &rlike("x.y")?,
+            &expected(vec![
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(false),
+                None
+            ])
+        );
+        assert_eq!(
+            &rlike("(?s)x.y")?,
+            &expected(vec![
+                Some(false),
+                Some(false),
+                Some(true),
+                Some(false),
+                None
+            ])
+        );
+        assert_eq!(
+            &rlike("^\\p{Alpha}+$")?,
+            &expected(vec![
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(false),
+                None
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_regexp_replace() -> Result<()> {
+        let replace = |pattern: &str, replacement: &str, position: i32| -> Result<ArrayRef> {
+            Ok(regexp_replace(&[
+                strings(),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+                ColumnarValue::Scalar(ScalarValue::from(replacement)),
+                ColumnarValue::Scalar(ScalarValue::from(position)),
+            ])?
+            .into_array(5))
+        };
+        let expected = |values: Vec<Option<&str>>| Arc::new(StringArray::from(values)) as ArrayRef;
+
+        assert_eq!(
+            &replace("(\\d+)", "<$1>", 1)?,
+            &expected(vec![
+                Some("a<1>b<22>c<333>"),
+                Some("abc"),
+                Some("\u{0663}x\ny"),
+                Some(""),
+                None
+            ])
+        );
+        assert_eq!(
+            &replace("(?<d>\\d)\\d*", "${d}\\$", 4)?,
+            &expected(vec![
+                Some("a1b2$c3$"),
+                Some("abc"),
+                Some("\u{0663}x\ny"),
+                Some(""),
+                None
+            ])
+        );
+        assert_eq!(
+            &replace("b*", "-", 1)?,
+            &expected(vec![
+                Some("-a-1--2-2-c-3-3-3-"),
+                Some("-a--c-"),
+                Some("-\u{0663}-x-\n-y-"),
+                Some("-"),
+                None
+            ])
+        );
+        assert_eq!(
+            &replace("(a)", "$12", 1)?,
+            &expected(vec![
+                Some("a21b22c333"),
+                Some("a2bc"),
+                Some("\u{0663}x\ny"),
+                Some(""),
+                None
+            ])
+        );
+        assert!(replace("(a)", "$2", 1).is_err());
+        assert!(replace("(a)", "$x", 1).is_err());
+        assert!(replace("(a)", "\\", 1).is_err());
+
+        // null replacement
+        let r = regexp_replace(&[
+            strings(),
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Scalar(ScalarValue::Utf8(None)),
+        ])?;
+        assert_eq!(r.into_array(5).null_count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_regexp_extract() -> Result<()> {
+        let extract = |pattern: &str, group_idx: i32| -> Result<ArrayRef> {
+            Ok(regexp_extract(&[
+                strings(),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+                ColumnarValue::Scalar(ScalarValue::from(group_idx)),
+            ])?
+            .into_array(5))
+        };
+        let expected = |values: Vec<Option<&str>>| Arc::new(StringArray::from(values)) as ArrayRef;
+
+        assert_eq!(
+            &extract("([a-z])(\\d+)", 2)?,
+            &expected(vec![Some("1"), Some(""), Some(""), Some(""), None])
+        );
+        assert_eq!(
+            &extract("([a-z])(\\d+)", 0)?,
+            &expected(vec![Some("a1"), Some(""), Some(""), Some(""), None])
+        );
+        assert_eq!(
+            &extract("(\\d)?([a-z])", 1)?,
+            &expected(vec![Some(""), Some(""), Some(""), Some(""), None])
+        );
+        assert!(extract("([a-z])", 2).is_err());
+        assert!(extract("([a-z])", -1).is_err());
+
+        // scalar input
+        let r = regexp_extract(&[
+            ColumnarValue::Scalar(ScalarValue::from("x123")),
+            ColumnarValue::Scalar(ScalarValue::from("\\d+")),
+            ColumnarValue::Scalar(ScalarValue::from(0)),
+        ])?;
+        match r {
+            ColumnarValue::Scalar(scalar) => assert_eq!(scalar, ScalarValue::from("123")),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::java_regex::{compile_java_regex, java_find_iter};
 use arrow::array::{Array, ArrayRef, ListArray, ListBuilder, StringArray, StringBuilder};
 use arrow::datatypes::DataType;
 use datafusion::common::cast::{as_int32_array, as_list_array, as_string_array};
 use datafusion::common::{DataFusionError, Result, ScalarValue};
 use datafusion::physical_plan::ColumnarValue;
+use regex::Regex;
 use std::sync::Arc;
 
 pub fn string_lower(args: &[ColumnarValue]) -> Result<ColumnarValue> {
@@ -87,22 +89,35 @@ pub fn string_repeat(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(repeated_string_array))
 }
 
+/// split() function compatible with spark (java regex split semantics)
+/// split('a1b22c', '[0-9]+') = ['a', 'b', 'c']
+/// split('a1b22c', '[0-9]+', 2) = ['a', 'b22c']
 pub fn string_split(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     let string_array = args[0].clone().into_array(1);
     let pat = match &args[1] {
-        ColumnarValue::Scalar(ScalarValue::Utf8(Some(pat))) if !pat.is_empty() => pat,
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(pat))) => pat,
         _ => {
-            return Err(DataFusionError::Execution(format!(
-                "string_split pattern only supports non-empty literal string"
-            )));
+            return Err(DataFusionError::Execution(
+                "string_split pattern only supports literal string".to_string(),
+            ));
         }
     };
+    let limit = match args.get(2) {
+        None => -1,
+        Some(ColumnarValue::Scalar(ScalarValue::Int32(Some(limit)))) => *limit,
+        _ => {
+            return Err(DataFusionError::Execution(
+                "string_split limit only supports literal int32".to_string(),
+            ));
+        }
+    };
+    let regex = compile_java_regex(pat)?;
 
     let mut splitted_builder = ListBuilder::new(StringBuilder::new());
     for s in as_string_array(&string_array)? {
         match s {
             Some(s) => {
-                for segment in s.split(pat) {
+                for segment in java_split(s, pat.is_empty(), &regex, limit) {
                     splitted_builder.values().append_value(segment);
                 }
                 splitted_builder.append(true);
@@ -115,6 +130,43 @@ pub fn string_split(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(Arc::new(splitted_builder.finish())))
 }
 
+// same as spark's UTF8String.split(), which calls java's String.split() with
+// non-positive limit treated as -1 (trailing empty strings are kept), except
+// for empty patterns which never produce a trailing empty string
+fn java_split<'a>(s: &'a str, empty_pat: bool, regex: &Regex, limit: i32) -> Vec<&'a str> {
+    if empty_pat && !s.is_empty() {
+        let num_chars = s.chars().count();
+        let limit = if limit <= 0 || limit as usize > num_chars {
+            num_chars
+        } else {
+            limit as usize
+        };
+        let mut segments = s.char_indices().take(limit).collect::<Vec<_>>();
+        let (last_index, _) = segments.pop().unwrap_or_default();
+        let mut segments = segments
+            .into_iter()
+            .map(|(i, c)| &s[i..i + c.len_utf8()])
+            .collect::<Vec<_>>();
+        segments.push(&s[last_index..]);
+        return segments;
+    }
+
+    let mut segments = vec![];
+    let mut index = 0;
+    for m in java_find_iter(regex, s) {
+        if limit > 0 && segments.len() + 1 >= limit as usize {
+            break;
+        }
+        if m.end() == 0 {
+            continue; // zero-width match at the beginning never produces a leading empty string
+        }
+        segments.push(&s[index..m.start()]);
+        index = m.end();
+    }
+    segments.push(&s[index..]);
+    segments
+}
+
 /// concat() function compatible with spark (returns null if any param is null)
 /// concat('abcde', 2, 22) = 'abcde222
 /// concat('abcde', 2, NULL, 22) = NULL
@@ -456,6 +508,68 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_string_split_regex_with_limit() -> Result<()> {
+        let split = |pat: &str, limit: i32| -> Result<Vec<Vec<String>>> {
+            let r = string_split(&vec![
+                ColumnarValue::Array(Arc::new(StringArray::from_iter_values(vec![
+                    "a1b22c333",
+                    "oneword",
+                    "\u{0663}x y",
+                ]))),
+                ColumnarValue::Scalar(ScalarValue::from(pat)),
+                ColumnarValue::Scalar(ScalarValue::from(limit)),
+            ])?;
+            let list = r.into_array(3);
+            let list = as_list_array(&list)?;
+            Ok((0..list.len())
+                .map(|i| {
+                    let values = list.value(i);
+                    as_string_array(&values)
+                        .unwrap()
+                        .iter()
+                        .map(|v| v.unwrap().to_string())
+                        .collect()
+                })
+                .collect())
+        };
+
+        // expected values are outputs of spark's split()
+        assert_eq!(
+            split("\\d+", -1)?,
+            vec![
+                vec!["a", "b", "c", ""],
+                vec!["oneword"],
+                vec!["\u{0663}x y"], // \d does not match non-ascii digits
+            ]
+        );
+        assert_eq!(
+            split("\\d+", 0)?,
+            vec![vec!["a", "b", "c", ""], vec!["oneword"], vec!["\u{0663}x y"]]
+        );
+        assert_eq!(
+            split("[0-9]+", 2)?,
+            vec![vec!["a", "b22c333"], vec!["oneword"], vec!["\u{0663}x y"]]
+        );
+        assert_eq!(
+            split("\\s", -1)?,
+            vec![vec!["a1b22c333"], vec!["oneword"], vec!["\u{0663}x", "y"]]
+        );
+        assert_eq!(
+            split("", -1)?,
+            vec![
+                vec!["a", "1", "b", "2", "2", "c", "3", "3", "3"],
+                vec!["o", "n", "e", "w", "o", "r", "d"],
+                vec!["\u{0663}", "x", " ", "y"],
+            ]
+        );
+        assert_eq!(
+            split("", 3)?,
+            vec![vec!["a", "1", "b22c333"], vec!["o", "n", "eword"], vec!["\u{0663}", "x", " y"],]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_string_concat() -> Result<()> {
         // positive case
@@ -0,0 +1,151 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int64Type};
+use datafusion::common::{Result, ScalarValue};
+use datafusion::physical_plan::ColumnarValue;
+use std::sync::Arc;
+
+/// width_bucket(value, min_value, max_value, num_bucket), returns the bucket
+/// number of value in an equi-width histogram, like spark's WidthBucket.
+pub fn spark_width_bucket(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let num_rows = args
+        .iter()
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        })
+        .unwrap_or(1);
+    let float64_arg = |i: usize| -> Result<Float64Array> {
+        let array = cast(&args[i].clone().into_array(num_rows), &DataType::Float64)?;
+        Ok(as_primitive_array(&array).clone())
+    };
+    let values = float64_arg(0)?;
+    let min_values = float64_arg(1)?;
+    let max_values = float64_arg(2)?;
+    let num_buckets = cast(&args[3].clone().into_array(num_rows), &DataType::Int64)?;
+    let num_buckets = as_primitive_array::<Int64Type>(&num_buckets);
+
+    let bucket_numbers: Int64Array = (0..num_rows)
+        .map(|i| {
+            if values.is_null(i)
+                || min_values.is_null(i)
+                || max_values.is_null(i)
+                || num_buckets.is_null(i)
+            {
+                return None;
+            }
+            compute_bucket_number(
+                values.value(i),
+                min_values.value(i),
+                max_values.value(i),
+                num_buckets.value(i),
+            )
+        })
+        .collect();
+
+    let ret: ArrayRef = Arc::new(bucket_numbers);
+    let all_scalars = args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)));
+    if all_scalars {
+        return Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(&ret, 0)?));
+    }
+    Ok(ColumnarValue::Array(ret))
+}
+
+fn compute_bucket_number(value: f64, min: f64, max: f64, num_bucket: i64) -> Option<i64> {
+    if num_bucket <= 0
+        || num_bucket == i64::MAX
+        || value.is_nan()
+        || min == max
+        || !min.is_finite()
+        || !max.is_finite()
+    {
+        return None;
+    }
+
+    let lower = min.min(max);
+    let upper = min.max(max);
+    Some(if min < max {
+        if value < lower {
+            0
+        } else if value >= upper {
+            num_bucket + 1
+        } else {
+            (num_bucket as f64 * (value - lower) / (upper - lower)) as i64 + 1
+        }
+    } else if value > upper {
+        0
+    } else if value <= lower {
+        num_bucket + 1
+    } else {
+        (num_bucket as f64 * (upper - value) / (upper - lower)) as i64 + 1
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::spark_width_bucket::spark_width_bucket;
+    use arrow::array::{ArrayRef, Float64Array, Int64Array};
+    use datafusion::common::ScalarValue;
+    use datafusion::logical_expr::ColumnarValue;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_width_bucket() {
+        let values: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(5.3),
+            Some(-2.1),
+            Some(10.6),
+            None,
+            Some(f64::NAN),
+        ]));
+        let result = spark_width_bucket(&[
+            ColumnarValue::Array(values),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(0.2))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(10.6))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(5))),
+        ])
+        .unwrap()
+        .into_array(5);
+
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(3),
+            Some(0),
+            Some(6),
+            None,
+            None,
+        ]));
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_width_bucket_reversed_range() {
+        // select width_bucket(-0.9, 5.2, 0.5, 2)
+        let result = spark_width_bucket(&[
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(-0.9))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(5.2))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(0.5))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+        ])
+        .unwrap()
+        .into_array(1);
+
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![Some(3)]));
+        assert_eq!(&result, &expected);
+    }
+}
@@ -0,0 +1,188 @@
+#![feature(new_uninit)]
+#![feature(io_error_other)]
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+use arrow::array::*;
+use arrow::compute::SortOptions;
+use arrow::record_batch::RecordBatch;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use datafusion::logical_expr::JoinType;
+use datafusion::physical_expr::expressions::{col, Column};
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::{common, ExecutionPlan, Partitioning};
+use datafusion::prelude::SessionContext;
+use datafusion_ext_plans::agg::AggExecMode::HashAgg;
+use datafusion_ext_plans::agg::AggMode::Partial;
+use datafusion_ext_plans::agg::{create_agg, AggExpr, AggFunction, GroupingExpr};
+use datafusion_ext_plans::agg_exec::AggExec;
+use datafusion_ext_plans::common::memory_manager::MemManager;
+use datafusion_ext_plans::shuffle_writer_exec::ShuffleWriterExec;
+use datafusion_ext_plans::sort_merge_join_exec::SortMergeJoinExec;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+const NUM_BATCHES: usize = 10;
+const BATCH_SIZE: usize = 10000;
+const NUM_ROWS: usize = NUM_BATCHES * BATCH_SIZE;
+
+// batches of (key: int64 with num_keys distinct values, value: nullable
+// float64, name: utf8), sorted by key if required
+fn build_batches(num_keys: i64, sorted: bool, seed: u64) -> Vec<RecordBatch> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut keys = (0..NUM_ROWS)
+        .map(|_| rng.gen_range(0..num_keys))
+        .collect::<Vec<_>>();
+    if sorted {
+        keys.sort_unstable();
+    }
+
+    keys.chunks(BATCH_SIZE)
+        .map(|keys| {
+            let key: ArrayRef = Arc::new(Int64Array::from(keys.to_vec()));
+            let value: ArrayRef = Arc::new(Float64Array::from_iter(
+                (0..keys.len()).map(|_| rng.gen_bool(0.9).then(|| rng.gen_range(0.0..1000.0))),
+            ));
+            let name: ArrayRef = Arc::new(StringArray::from_iter_values(
+                keys.iter().map(|key| format!("name-{key:08}")),
+            ));
+            RecordBatch::try_from_iter(vec![("key", key), ("value", value), ("name", name)])
+                .unwrap()
+        })
+        .collect()
+}
+
+fn memory_input(batches: Vec<RecordBatch>) -> Arc<dyn ExecutionPlan> {
+    let schema = batches[0].schema();
+    Arc::new(MemoryExec::try_new(&[batches], schema, None).unwrap())
+}
+
+async fn execute(plan: Arc<dyn ExecutionPlan>) {
+    MemManager::init(4 << 30);
+    let task_ctx = SessionContext::new().task_ctx();
+    let output = plan.execute(0, task_ctx).unwrap();
+    common::collect(output).await.unwrap();
+}
+
+fn bench_hash_agg(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("hash_agg");
+    group.throughput(Throughput::Elements(NUM_ROWS as u64));
+
+    for num_keys in [100, 100000] {
+        let input = memory_input(build_batches(num_keys, false, 0));
+        let schema = input.schema();
+        let agg = |agg_function, field_name: &str| AggExpr {
+            field_name: field_name.to_string(),
+            mode: Partial,
+            agg: create_agg(agg_function, &[col("value", &schema).unwrap()], &schema).unwrap(),
+        };
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(
+            AggExec::try_new(
+                HashAgg,
+                vec![GroupingExpr {
+                    field_name: "key".to_string(),
+                    expr: Arc::new(Column::new("key", 0)),
+                }],
+                vec![
+                    agg(AggFunction::Sum, "sum"),
+                    agg(AggFunction::Count, "count"),
+                    agg(AggFunction::Max, "max"),
+                ],
+                0,
+                input,
+            )
+            .unwrap(),
+        );
+        group.bench_function(format!("keys={num_keys}"), |b| {
+            b.to_async(&rt).iter(|| execute(plan.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_sort_merge_join(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("sort_merge_join");
+    group.throughput(Throughput::Elements(2 * NUM_ROWS as u64));
+
+    let left = memory_input(build_batches(NUM_ROWS as i64, true, 0));
+    let right = memory_input(build_batches(NUM_ROWS as i64, true, 1));
+    let on = vec![(
+        Column::new_with_schema("key", &left.schema()).unwrap(),
+        Column::new_with_schema("key", &right.schema()).unwrap(),
+    )];
+    for join_type in [JoinType::Inner, JoinType::Left, JoinType::LeftSemi] {
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(
+            SortMergeJoinExec::try_new(
+                left.clone(),
+                right.clone(),
+                on.clone(),
+                join_type,
+                None,
+                vec![SortOptions::default()],
+            )
+            .unwrap(),
+        );
+        group.bench_function(format!("{join_type:?}"), |b| {
+            b.to_async(&rt).iter(|| execute(plan.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_shuffle_write(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("shuffle_write");
+    group.throughput(Throughput::Elements(NUM_ROWS as u64));
+
+    let input = memory_input(build_batches(NUM_ROWS as i64, false, 0));
+    let dir = tempfile::tempdir().unwrap();
+    let data_file = dir
+        .path()
+        .join("shuffle.data")
+        .to_string_lossy()
+        .to_string();
+    let index_file = dir
+        .path()
+        .join("shuffle.index")
+        .to_string_lossy()
+        .to_string();
+
+    // bucket repartitioner is used below 200 partitions, sort repartitioner otherwise
+    for num_partitions in [1, 100, 1000] {
+        let partitioning =
+            Partitioning::Hash(vec![col("key", &input.schema()).unwrap()], num_partitions);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(
+            ShuffleWriterExec::try_new(
+                input.clone(),
+                partitioning,
+                data_file.clone(),
+                index_file.clone(),
+            )
+            .unwrap(),
+        );
+        group.bench_function(format!("partitions={num_partitions}"), |b| {
+            b.to_async(&rt).iter(|| execute(plan.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_hash_agg,
+    bench_sort_merge_join,
+    bench_shuffle_write
+);
+criterion_main!(benches);
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::agg::histogram_numeric::NumericHistogram;
 use crate::common::slim_bytes::SlimBytes;
 use arrow::array::Array;
 use datafusion::common::{Result, ScalarValue};
@@ -140,6 +141,7 @@ pub enum AccumInitialValue {
     Scalar(ScalarValue),
     DynList,
     DynSet,
+    DynNumericHistogram,
 }
 
 pub fn create_agg_buf_from_initial_value(
@@ -212,6 +214,10 @@ pub fn create_agg_buf_from_initial_value(
                 addrs.push(make_dyn_addr(dyns.len()));
                 dyns.push(Box::new(AggDynSet::default()));
             }
+            AccumInitialValue::DynNumericHistogram => {
+                addrs.push(make_dyn_addr(dyns.len()));
+                dyns.push(Box::new(NumericHistogram::default()));
+            }
         }
     }
 
@@ -257,6 +263,7 @@ impl dyn AggDynValue {
         handle_dyn_type!(AggDynStr);
         handle_dyn_type!(AggDynList);
         handle_dyn_type!(AggDynSet);
+        handle_dyn_type!(NumericHistogram);
         unreachable!("unknown dyn value")
     }
 
@@ -273,6 +280,7 @@ impl dyn AggDynValue {
         handle_dyn_type!(AggDynStr);
         handle_dyn_type!(AggDynList);
         handle_dyn_type!(AggDynSet);
+        handle_dyn_type!(NumericHistogram);
         unreachable!("unknown dyn value")
     }
 }
@@ -14,21 +14,19 @@
 
 use ahash::RandomState;
 use std::hash::{BuildHasher, Hash, Hasher};
-use std::io::{BufReader, Read, Write};
+use std::io::Write;
 use std::mem::{size_of, ManuallyDrop};
 use std::sync::{Arc, Weak};
 
 use arrow::row::{RowConverter, Rows};
 use async_trait::async_trait;
 use datafusion::common::Result;
-use datafusion::error::DataFusionError;
 use datafusion::execution::context::TaskContext;
 
 use datafusion::physical_plan::metrics::BaselineMetrics;
 use futures::lock::Mutex;
 use hashbrown::hash_map::{Entry, RawEntryMut};
 use hashbrown::HashMap;
-use lz4_flex::frame::FrameDecoder;
 
 use datafusion_ext_commons::io::{read_bytes_slice, read_len, write_len};
 use datafusion_ext_commons::loser_tree::LoserTree;
@@ -37,10 +35,10 @@ use crate::agg::agg_buf::AggBuf;
 use crate::agg::agg_context::AggContext;
 use crate::common::bytes_arena::BytesArena;
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::common::onheap_spill::{try_new_spill, Spill};
 use crate::common::output::WrappedRecordBatchSender;
 use crate::common::rdxsort;
 use crate::common::slim_bytes::SlimBytes;
+use crate::common::spill_manager::{Spill, SpillManager, SpillReader};
 
 // reserve memory for each spill
 // estimated size: bufread=64KB + lz4dec.src=64KB + lz4dec.dest=64KB
@@ -59,16 +57,16 @@ pub struct AggTables {
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     in_mem: Mutex<InMemTable>,
     spills: Mutex<Vec<Box<dyn Spill>>>,
+    spill_manager: SpillManager,
     agg_ctx: Arc<AggContext>,
     context: Arc<TaskContext>,
-    metrics: BaselineMetrics,
 }
 
 impl AggTables {
     pub fn new(
         partition_id: usize,
         agg_ctx: Arc<AggContext>,
-        metrics: BaselineMetrics,
+        spill_manager: SpillManager,
         context: Arc<TaskContext>,
     ) -> Self {
         Self {
@@ -76,9 +74,9 @@ impl AggTables {
             mem_consumer_info: None,
             in_mem: Mutex::new(InMemTable::new(true)), // only the first im-mem table uses hash
             spills: Mutex::default(),
+            spill_manager,
             agg_ctx,
             context,
-            metrics,
         }
     }
 
@@ -152,7 +150,7 @@ impl AggTables {
         let mut cursors = vec![];
         if in_mem.num_records() > 0 {
             // spill staging records
-            if let Some(spill) = in_mem.try_into_spill()? {
+            if let Some(spill) = in_mem.try_into_spill(&self.spill_manager)? {
                 spills.push(spill);
             }
             self.update_mem_used(spills.len() * SPILL_OFFHEAP_MEM_COST)
@@ -227,13 +225,6 @@ impl AggTables {
         if !staging_records.is_empty() {
             flush_staging!();
         }
-
-        // update disk spill size
-        let spill_disk_usage = spills
-            .iter()
-            .map(|spill| spill.get_disk_usage().unwrap_or(0))
-            .sum::<u64>();
-        self.metrics.record_spill(spill_disk_usage as usize);
         self.update_mem_used(0).await?;
         Ok(())
     }
@@ -259,7 +250,10 @@ impl MemConsumer for AggTables {
         let mut in_mem = self.in_mem.lock().await;
         let mut spills = self.spills.lock().await;
 
-        spills.extend(std::mem::replace(&mut *in_mem, InMemTable::new(false)).try_into_spill()?);
+        spills.extend(
+            std::mem::replace(&mut *in_mem, InMemTable::new(false))
+                .try_into_spill(&self.spill_manager)?,
+        );
         drop(spills);
         drop(in_mem);
 
@@ -431,7 +425,7 @@ impl InMemTable {
         Ok(())
     }
 
-    fn try_into_spill(self) -> Result<Option<Box<dyn Spill>>> {
+    fn try_into_spill(self, spill_manager: &SpillManager) -> Result<Option<Box<dyn Spill>>> {
         if self.map.is_empty() && self.unsorted_values.is_empty() {
             return Ok(None);
         }
@@ -460,8 +454,7 @@ impl InMemTable {
         };
         let counts = rdxsort::radix_sort_u16_by(&mut sorted, |(h, _, _)| *h);
 
-        let spill = try_new_spill()?;
-        let mut writer = lz4_flex::frame::FrameEncoder::new(spill.get_buf_writer());
+        let mut writer = spill_manager.new_spill_writer()?;
         let mut beg = 0;
 
         for i in 0..65536 {
@@ -484,24 +477,19 @@ impl InMemTable {
         }
         write_len(65536, &mut writer)?; // EOF
         write_len(0, &mut writer)?;
-
-        writer
-            .finish()
-            .map_err(|err| DataFusionError::Execution(format!("{}", err)))?;
-        spill.complete()?;
-        Ok(Some(spill))
+        Ok(Some(writer.finish()?))
     }
 }
 
 struct SpillCursor {
     agg_ctx: Arc<AggContext>,
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
+    input: SpillReader,
     pub cur_bucket_idx: usize,
     pub cur_bucket_count: usize,
 }
 impl SpillCursor {
     fn try_from_spill(spill: &Box<dyn Spill>, agg_ctx: &Arc<AggContext>) -> Result<Self> {
-        let input = FrameDecoder::new(spill.get_buf_reader());
+        let input = SpillManager::read_spill(spill.as_ref());
         let mut cursor = SpillCursor {
             agg_ctx: agg_ctx.clone(),
             input,
@@ -0,0 +1,320 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::agg::agg_buf::{AccumInitialValue, AggBuf, AggDynValue};
+use crate::agg::Agg;
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::{Result, ScalarValue};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion_ext_commons::io::{read_len, write_len};
+use datafusion_ext_commons::spark_random::JavaRandom;
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Write};
+use std::mem::size_of;
+use std::sync::Arc;
+
+pub struct AggHistogramNumeric {
+    child: Arc<dyn PhysicalExpr>,
+    num_bins: usize,
+    data_type: DataType,
+}
+
+impl AggHistogramNumeric {
+    pub fn try_new(child: Arc<dyn PhysicalExpr>, num_bins: usize) -> Result<Self> {
+        Ok(Self {
+            child,
+            num_bins,
+            data_type: Self::return_type(),
+        })
+    }
+
+    pub fn return_type() -> DataType {
+        let bin_type = DataType::Struct(Fields::from(vec![
+            Field::new("x", DataType::Float64, true),
+            Field::new("y", DataType::Float64, true),
+        ]));
+        DataType::List(Arc::new(Field::new("item", bin_type, true)))
+    }
+
+    fn histogram<'a>(
+        &self,
+        agg_buf: &'a mut AggBuf,
+        agg_buf_addrs: &[u64],
+    ) -> &'a mut NumericHistogram {
+        agg_buf
+            .dyn_value_mut(agg_buf_addrs[0])
+            .as_any_mut()
+            .downcast_mut::<NumericHistogram>()
+            .unwrap()
+    }
+}
+
+impl Debug for AggHistogramNumeric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HistogramNumeric({:?}, {})", self.child, self.num_bins)
+    }
+}
+
+impl Agg for AggHistogramNumeric {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn exprs(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.child.clone()]
+    }
+
+    fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    fn nullable(&self) -> bool {
+        true
+    }
+
+    fn accums_initial(&self) -> &[AccumInitialValue] {
+        &[AccumInitialValue::DynNumericHistogram]
+    }
+
+    fn partial_update(
+        &self,
+        agg_buf: &mut AggBuf,
+        agg_buf_addrs: &[u64],
+        values: &[ArrayRef],
+        row_idx: usize,
+    ) -> Result<()> {
+        let values = as_primitive_array::<Float64Type>(&values[0]);
+        if values.is_valid(row_idx) {
+            self.histogram(agg_buf, agg_buf_addrs)
+                .add(values.value(row_idx), self.num_bins);
+        }
+        Ok(())
+    }
+
+    fn partial_update_all(
+        &self,
+        agg_buf: &mut AggBuf,
+        agg_buf_addrs: &[u64],
+        values: &[ArrayRef],
+    ) -> Result<()> {
+        let values = as_primitive_array::<Float64Type>(&values[0]);
+        let histogram = self.histogram(agg_buf, agg_buf_addrs);
+        for value in values.iter().flatten() {
+            histogram.add(value, self.num_bins);
+        }
+        Ok(())
+    }
+
+    fn partial_merge(
+        &self,
+        agg_buf: &mut AggBuf,
+        merging_agg_buf: &mut AggBuf,
+        agg_buf_addrs: &[u64],
+    ) -> Result<()> {
+        let merging_histogram = self.histogram(merging_agg_buf, agg_buf_addrs);
+        let merging_histogram = std::mem::take(merging_histogram);
+        self.histogram(agg_buf, agg_buf_addrs)
+            .merge(merging_histogram, self.num_bins);
+        Ok(())
+    }
+
+    fn final_merge(&self, agg_buf: &mut AggBuf, agg_buf_addrs: &[u64]) -> Result<ScalarValue> {
+        let histogram = self.histogram(agg_buf, agg_buf_addrs);
+        let bin_type = match &self.data_type {
+            DataType::List(field) => field.data_type().clone(),
+            _ => unreachable!(),
+        };
+        if histogram.bins.is_empty() {
+            return Ok(ScalarValue::new_list(None, bin_type));
+        }
+        let bin_fields = match &bin_type {
+            DataType::Struct(fields) => fields.clone(),
+            _ => unreachable!(),
+        };
+        let bins = std::mem::take(&mut histogram.bins)
+            .into_iter()
+            .map(|(x, y)| {
+                ScalarValue::Struct(
+                    Some(vec![
+                        ScalarValue::Float64(Some(x)),
+                        ScalarValue::Float64(Some(y)),
+                    ]),
+                    bin_fields.clone(),
+                )
+            })
+            .collect();
+        Ok(ScalarValue::new_list(Some(bins), bin_type))
+    }
+}
+
+/// streaming histogram of Ben-Haim & Tom-Tov, ported from spark's
+/// NumericHistogram so that the same bins are produced.
+#[derive(Clone, Default)]
+pub struct NumericHistogram {
+    bins: Vec<(f64, f64)>, // (x, y) sorted by x
+    prng: Option<JavaRandom>,
+}
+
+impl NumericHistogram {
+    pub fn add(&mut self, v: f64, num_bins: usize) {
+        // find the closest bin that v should go into
+        let mut bin = 0;
+        let (mut l, mut r) = (0, self.bins.len());
+        while l < r {
+            bin = (l + r) / 2;
+            if self.bins[bin].0 > v {
+                r = bin;
+            } else if self.bins[bin].0 < v {
+                bin += 1;
+                l = bin;
+            } else {
+                break;
+            }
+        }
+
+        if bin < self.bins.len() && self.bins[bin].0 == v {
+            self.bins[bin].1 += 1.0;
+        } else {
+            self.bins.insert(bin, (v, 1.0));
+            if self.bins.len() > num_bins {
+                self.trim(num_bins);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: NumericHistogram, num_bins: usize) {
+        if self.bins.is_empty() {
+            self.bins = other.bins;
+            return;
+        }
+        self.bins.extend(other.bins);
+        self.bins.sort_by(|a, b| a.0.total_cmp(&b.0)); // stable
+        self.trim(num_bins);
+    }
+
+    fn trim(&mut self, num_bins: usize) {
+        let prng = self.prng.get_or_insert_with(|| JavaRandom::new(31183));
+        while self.bins.len() > num_bins {
+            // find the closest pair of bins in terms of x coordinates, break ties randomly
+            let mut smallest_diff = self.bins[1].0 - self.bins[0].0;
+            let mut smallest_diff_loc = 0;
+            let mut smallest_diff_count = 1;
+            for i in 1..self.bins.len() - 1 {
+                let diff = self.bins[i + 1].0 - self.bins[i].0;
+                if diff < smallest_diff {
+                    smallest_diff = diff;
+                    smallest_diff_loc = i;
+                    smallest_diff_count = 1;
+                } else if diff == smallest_diff {
+                    smallest_diff_count += 1;
+                    if prng.next_double() <= 1.0 / smallest_diff_count as f64 {
+                        smallest_diff_loc = i;
+                    }
+                }
+            }
+
+            // merge the two closest bins into their average x location, weighted
+            // by their heights
+            let (x1, y1) = self.bins[smallest_diff_loc];
+            let (x2, y2) = self.bins.remove(smallest_diff_loc + 1);
+            let d = y1 + y2;
+            let mut x = x1 * (y1 / d);
+            x += x2 / d * y2;
+            self.bins[smallest_diff_loc] = (x, d);
+        }
+    }
+
+    pub fn load(&mut self, mut r: impl Read) -> Result<()> {
+        let num_bins = read_len(&mut r)?;
+        let mut buf = [0u8; 8];
+        self.bins.clear();
+        for _ in 0..num_bins {
+            r.read_exact(&mut buf)?;
+            let x = f64::from_le_bytes(buf);
+            r.read_exact(&mut buf)?;
+            let y = f64::from_le_bytes(buf);
+            self.bins.push((x, y));
+        }
+        Ok(())
+    }
+
+    pub fn save(&mut self, mut w: impl Write) -> Result<()> {
+        write_len(self.bins.len(), &mut w)?;
+        for (x, y) in std::mem::take(&mut self.bins) {
+            w.write_all(&x.to_le_bytes())?;
+            w.write_all(&y.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for NumericHistogram {
+    fn eq(&self, other: &Self) -> bool {
+        self.bins == other.bins
+    }
+}
+
+impl AggDynValue for NumericHistogram {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn mem_size(&self) -> usize {
+        size_of::<Self>() + self.bins.capacity() * size_of::<(f64, f64)>()
+    }
+
+    fn eq_boxed(&self, that: &Box<dyn AggDynValue>) -> bool {
+        match that.as_any().downcast_ref() {
+            Some(that) => self.eq(that),
+            None => false,
+        }
+    }
+
+    fn default_boxed(&self) -> Box<dyn AggDynValue> {
+        Box::<NumericHistogram>::default()
+    }
+
+    fn clone_boxed(&self) -> Box<dyn AggDynValue> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::agg::histogram_numeric::NumericHistogram;
+
+    #[test]
+    fn test_numeric_histogram() {
+        let mut histogram = NumericHistogram::default();
+        for v in [1.0, 2.0, 2.0, 10.0, 11.0] {
+            histogram.add(v, 3);
+        }
+        assert_eq!(histogram.bins, vec![(1.0, 1.0), (2.0, 2.0), (10.5, 2.0)]);
+
+        let mut other = NumericHistogram::default();
+        for v in [1.0, 20.0] {
+            other.add(v, 3);
+        }
+        histogram.merge(other, 3);
+        assert_eq!(histogram.bins.len(), 3);
+        assert_eq!(histogram.bins.iter().map(|b| b.1).sum::<f64>(), 7.0);
+        assert_eq!(histogram.bins[2], (20.0, 1.0));
+    }
+}
@@ -21,6 +21,7 @@ pub mod collect_set;
 pub mod count;
 pub mod first;
 pub mod first_ignores_null;
+pub mod histogram_numeric;
 pub mod maxmin;
 pub mod sum;
 
@@ -29,6 +30,7 @@ use arrow::array::*;
 use arrow::datatypes::*;
 use datafusion::common::{DataFusionError, Result, ScalarValue};
 use datafusion::logical_expr::aggregate_function;
+use datafusion::physical_expr::expressions::Literal;
 use datafusion::physical_expr::PhysicalExpr;
 use datafusion_ext_exprs::cast::TryCastExpr;
 use std::any::Any;
@@ -74,6 +76,7 @@ pub enum AggFunction {
     FirstIgnoresNull,
     CollectList,
     CollectSet,
+    HistogramNumeric,
 }
 
 #[derive(Debug, Clone)]
@@ -320,5 +323,24 @@ pub fn create_agg(
                 arg_type,
             )?)
         }
+        AggFunction::HistogramNumeric => {
+            let num_bins = match children[1]
+                .as_any()
+                .downcast_ref::<Literal>()
+                .map(|literal| literal.value())
+            {
+                Some(ScalarValue::Int32(Some(n))) if *n >= 2 => *n as usize,
+                _ => {
+                    return Err(DataFusionError::Plan(format!(
+                        "histogram_numeric: nb must be a literal integer >= 2, got {}",
+                        children[1],
+                    )));
+                }
+            };
+            Arc::new(histogram_numeric::AggHistogramNumeric::try_new(
+                Arc::new(TryCastExpr::new(children[0].clone(), DataType::Float64)),
+                num_bins,
+            )?)
+        }
     })
 }
@@ -37,9 +37,10 @@ use crate::agg::agg_context::AggContext;
 use crate::agg::agg_tables::AggTables;
 use crate::agg::{AggExecMode, AggExpr, GroupingExpr};
 use crate::common::batch_statisitcs::{stat_input, InputBatchStatistics};
-use crate::common::memory_manager::MemManager;
+use crate::common::memory_manager::{MemConsumer, MemManager};
 use crate::common::output::{output_bufferable_with_spill, output_with_sender};
 use crate::common::slim_bytes::SlimBytes;
+use crate::common::spill_manager::SpillManager;
 
 #[derive(Debug)]
 pub struct AggExec {
@@ -184,13 +185,15 @@ async fn execute_agg_with_grouping_hash(
     )?;
 
     // create tables
+    let spill_manager = SpillManager::new(&metrics, partition_id);
     let tables = Arc::new(AggTables::new(
         partition_id,
         agg_ctx.clone(),
-        BaselineMetrics::new(&metrics, partition_id),
+        spill_manager.clone(),
         context.clone(),
     ));
     MemManager::register_consumer(tables.clone(), true);
+    tables.register_peak_mem_used_metric(&metrics, partition_id);
     drop(timer);
 
     // start processing input batches
@@ -260,7 +263,7 @@ async fn execute_agg_with_grouping_hash(
 
     // if running in-memory, buffer output when memory usage is high
     if !has_spill {
-        return output_bufferable_with_spill(tables_cloned, context, output);
+        return output_bufferable_with_spill(tables_cloned, spill_manager, context, output);
     }
     Ok(output)
 }
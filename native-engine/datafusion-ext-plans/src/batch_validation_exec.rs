@@ -0,0 +1,231 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::MetricsSet;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    displayable, DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning,
+    SendableRecordBatchStream, Statistics,
+};
+use futures::StreamExt;
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// Validates all batches produced by the input operator, used for catching
+/// corrupted batches close to where they are produced.
+///
+/// this exec is transparent, it has no metrics of its own and is skipped when
+/// reporting metrics to spark.
+#[derive(Debug)]
+pub struct BatchValidationExec {
+    input: Arc<dyn ExecutionPlan>,
+    input_name: String,
+}
+
+impl BatchValidationExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        let input_name = displayable(input.as_ref())
+            .one_line()
+            .to_string()
+            .trim()
+            .to_string();
+        Self { input, input_name }
+    }
+
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// wraps every operator of the plan with batch validation
+    pub fn wrap_all(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            let new_children = children
+                .into_iter()
+                .map(Self::wrap_all)
+                .collect::<Result<Vec<_>>>()?;
+            plan.with_new_children(new_children)?
+        };
+        Ok(Arc::new(Self::new(plan)))
+    }
+}
+
+impl DisplayAs for BatchValidationExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "BatchValidationExec")
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for BatchValidationExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Plan(
+                "BatchValidationExec expects one children".to_string(),
+            ));
+        }
+        Ok(Arc::new(Self::new(children[0].clone())))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let schema = self.schema();
+        let input_name = self.input_name.clone();
+        let input = self.input.execute(partition, context)?;
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            schema.clone(),
+            input.map(move |batch_result| {
+                let batch = batch_result?;
+                validate_batch(&schema, &batch).map_err(|err| {
+                    DataFusionError::Execution(format!(
+                        "batch validation failed: invalid batch produced by {} (partition={}): {}",
+                        input_name, partition, err,
+                    ))
+                })?;
+                Ok(batch)
+            }),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        None
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+/// checks the batch matches the expected schema and all arrays are valid
+/// (offsets, null buffers, utf8, etc.)
+pub fn validate_batch(schema: &SchemaRef, batch: &RecordBatch) -> Result<()> {
+    if batch.num_columns() != schema.fields().len() {
+        return Err(DataFusionError::Execution(format!(
+            "expect {} columns, got {}",
+            schema.fields().len(),
+            batch.num_columns(),
+        )));
+    }
+
+    for (i, (field, column)) in schema.fields().iter().zip(batch.columns()).enumerate() {
+        if column.data_type() != field.data_type() {
+            return Err(DataFusionError::Execution(format!(
+                "column {} ({}): expect data type {}, got {}",
+                i,
+                field.name(),
+                field.data_type(),
+                column.data_type(),
+            )));
+        }
+        if column.len() != batch.num_rows() {
+            return Err(DataFusionError::Execution(format!(
+                "column {} ({}): expect {} rows, got {}",
+                i,
+                field.name(),
+                batch.num_rows(),
+                column.len(),
+            )));
+        }
+        column.to_data().validate_full().map_err(|err| {
+            DataFusionError::Execution(format!("column {} ({}): {}", i, field.name(), err))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::batch_validation_exec::validate_batch;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::buffer::Buffer;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_validate_batch() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ],
+        )?;
+        validate_batch(&schema, &batch)?;
+
+        // mismatched schema
+        let other_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+        assert!(validate_batch(&other_schema, &batch).is_err());
+
+        // non-monotonic offsets
+        let corrupted_data = StringArray::from(vec!["x", "y"])
+            .to_data()
+            .into_builder()
+            .buffers(vec![
+                Buffer::from_slice_ref([0i32, 1, 0]),
+                Buffer::from_slice_ref(b"xy"),
+            ]);
+        let corrupted = unsafe { corrupted_data.build_unchecked() };
+        let corrupted_batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+                arrow::array::make_array(corrupted),
+            ],
+        )?;
+        assert!(validate_batch(&schema, &corrupted_batch).is_err());
+        Ok(())
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::broadcast_cache::get_or_load_broadcast;
 use crate::sort_exec::SortExec;
 use crate::sort_merge_join_exec::SortMergeJoinExec;
 use arrow::datatypes::SchemaRef;
@@ -27,7 +28,9 @@ use datafusion::physical_plan::joins::utils::{
 };
 use datafusion::physical_plan::joins::{HashJoinExec, PartitionMode};
 use datafusion::physical_plan::memory::MemoryStream;
-use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
@@ -56,6 +59,8 @@ pub struct BroadcastJoinExec {
     join_filter: Option<JoinFilter>,
     /// The schema once the join is applied
     schema: SchemaRef,
+    /// Id of the broadcasted data, left side is cached in executor if specified
+    cached_build_id: Option<String>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -67,6 +72,7 @@ impl BroadcastJoinExec {
         on: JoinOn,
         join_type: JoinType,
         join_filter: Option<JoinFilter>,
+        cached_build_id: Option<String>,
     ) -> Result<Self> {
         if matches!(
             join_type,
@@ -92,9 +98,14 @@ impl BroadcastJoinExec {
             join_type,
             join_filter,
             schema,
+            cached_build_id,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    pub fn cached_build_id(&self) -> Option<&str> {
+        self.cached_build_id.as_deref()
+    }
 }
 
 impl ExecutionPlan for BroadcastJoinExec {
@@ -128,6 +139,7 @@ impl ExecutionPlan for BroadcastJoinExec {
             self.on.iter().cloned().collect(),
             self.join_type,
             self.join_filter.clone(),
+            self.cached_build_id.clone(),
         )?))
     }
 
@@ -144,7 +156,8 @@ impl ExecutionPlan for BroadcastJoinExec {
             self.on.clone(),
             self.join_type,
             self.join_filter.clone(),
-            BaselineMetrics::new(&self.metrics, partition),
+            self.cached_build_id.clone(),
+            self.metrics.clone(),
         );
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
@@ -164,7 +177,11 @@ impl ExecutionPlan for BroadcastJoinExec {
 
 impl DisplayAs for BroadcastJoinExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "BroadcastJoin")
+        write!(
+            f,
+            "BroadcastJoin: join_type={:?}, on={:?}",
+            self.join_type, self.on
+        )
     }
 }
 
@@ -176,8 +193,14 @@ async fn execute_broadcast_join(
     on: JoinOn,
     join_type: JoinType,
     join_filter: Option<JoinFilter>,
-    metrics: BaselineMetrics,
+    cached_build_id: Option<String>,
+    metrics: ExecutionPlanMetricsSet,
 ) -> Result<SendableRecordBatchStream> {
+    let baseline_metrics = BaselineMetrics::new(&metrics, partition);
+    let build_time = MetricBuilder::new(&metrics).subset_time("build_time", partition);
+    let probe_time = MetricBuilder::new(&metrics).subset_time("probe_time", partition);
+    let spill_count = MetricBuilder::new(&metrics).spill_count(partition);
+
     let enabled_fallback_to_smj: bool =
         jni_call_static!(BlazeConf.enableBhjFallbacksToSmj() -> jboolean)? == JNI_TRUE;
     let bhj_num_rows_limit: usize =
@@ -197,7 +220,35 @@ async fn execute_broadcast_join(
     let left_schema = left.schema();
     let mut left = left;
 
-    if enabled_fallback_to_smj {
+    if let Some(cached_build_id) = cached_build_id {
+        // broadcasted side is deserialized once and shared by all tasks in the executor
+        let cache_mem_size = jni_call_static!(BlazeConf.broadcastCacheMemSize() -> i64)?;
+        let left_batches = get_or_load_broadcast(
+            &cached_build_id,
+            cache_mem_size.max(0) as usize,
+            left.execute(0, context.clone())?.try_collect::<Vec<_>>(),
+        )
+        .await?;
+
+        let left_num_rows: usize = left_batches.iter().map(|b| b.num_rows()).sum();
+        let left_mem_size: usize = left_batches.iter().map(|b| b.get_array_memory_size()).sum();
+        if enabled_fallback_to_smj
+            && (left_num_rows > bhj_num_rows_limit || left_mem_size > bhj_mem_size_limit)
+        {
+            join_mode = JoinMode::SortMerge;
+        }
+
+        let left_stream: SendableRecordBatchStream = Box::pin(MemoryStream::try_new(
+            left_batches.as_ref().clone(),
+            left_schema.clone(),
+            None,
+        )?);
+        left = Arc::new(RecordBatchStreamsWrapperExec {
+            schema: left_schema.clone(),
+            stream: Mutex::new(Some(left_stream)),
+            output_partitioning: right.output_partitioning(),
+        });
+    } else if enabled_fallback_to_smj {
         let mut left_stream = left.execute(0, context.clone())?.fuse();
         let mut left_cached: Vec<RecordBatch> = vec![];
         let mut left_num_rows = 0;
@@ -254,20 +305,20 @@ async fn execute_broadcast_join(
                 .chain(futures::stream::poll_fn(move |_| {
                     // update metrics
                     let join_metrics = join.metrics().unwrap();
-                    metrics.record_output(join_metrics.output_rows().unwrap_or(0));
-                    metrics.elapsed_compute().add_duration(Duration::from_nanos(
-                        [
-                            join_metrics
-                                .sum_by_name("build_time")
-                                .map(|v| v.as_usize() as u64),
-                            join_metrics
-                                .sum_by_name("join_time")
-                                .map(|v| v.as_usize() as u64),
-                        ]
-                        .into_iter()
-                        .flatten()
-                        .sum(),
-                    ));
+                    let join_build_time = join_metrics
+                        .sum_by_name("build_time")
+                        .map(|v| v.as_usize() as u64)
+                        .unwrap_or(0);
+                    let join_probe_time = join_metrics
+                        .sum_by_name("join_time")
+                        .map(|v| v.as_usize() as u64)
+                        .unwrap_or(0);
+                    baseline_metrics.record_output(join_metrics.output_rows().unwrap_or(0));
+                    baseline_metrics
+                        .elapsed_compute()
+                        .add_duration(Duration::from_nanos(join_build_time + join_probe_time));
+                    build_time.add_duration(Duration::from_nanos(join_build_time));
+                    probe_time.add_duration(Duration::from_nanos(join_probe_time));
                     Poll::Ready(None)
                 }));
             Ok(Box::pin(RecordBatchStreamAdapter::new(
@@ -302,13 +353,20 @@ async fn execute_broadcast_join(
                     // update metrics
                     let right_sorted_metrics = right_sorted.metrics().unwrap();
                     let join_metrics = join.metrics().unwrap();
-                    metrics.record_output(join_metrics.output_rows().unwrap_or(0));
-                    metrics.elapsed_compute().add_duration(Duration::from_nanos(
-                        [right_sorted_metrics.elapsed_compute(), join_metrics.elapsed_compute()]
-                            .into_iter()
-                            .flatten()
-                            .sum::<usize>() as u64,
-                    ));
+                    let sort_time = right_sorted_metrics.elapsed_compute().unwrap_or(0) as u64;
+                    let join_time = join_metrics.elapsed_compute().unwrap_or(0) as u64;
+                    baseline_metrics.record_output(join_metrics.output_rows().unwrap_or(0));
+                    baseline_metrics
+                        .elapsed_compute()
+                        .add_duration(Duration::from_nanos(sort_time + join_time));
+                    // sorting the streamed side is accounted as build time
+                    build_time.add_duration(Duration::from_nanos(sort_time));
+                    probe_time.add_duration(Duration::from_nanos(join_time));
+
+                    // sorting probed side may spill
+                    spill_count.add(right_sorted_metrics.spill_count().unwrap_or(0));
+                    baseline_metrics
+                        .record_spill(right_sorted_metrics.spilled_bytes().unwrap_or(0));
                     Poll::Ready(None)
                 }));
             Ok(Box::pin(RecordBatchStreamAdapter::new(
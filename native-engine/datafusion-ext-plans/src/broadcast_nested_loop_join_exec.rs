@@ -66,7 +66,7 @@ impl BroadcastNestedLoopJoinExec {
 
 impl DisplayAs for BroadcastNestedLoopJoinExec {
     fn fmt_as(&self, _: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "BroadcastNestedLoopJoin")
+        write!(f, "BroadcastNestedLoopJoin: join_type={:?}", self.join_type)
     }
 }
 
@@ -0,0 +1,510 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use arrow::datatypes::{DataType, Field, Fields, Int64Type, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::common::{DataFusionError, Result, ScalarValue, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::Accumulator;
+use datafusion::physical_expr::expressions::{MaxAccumulator, MinAccumulator};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datafusion_ext_commons::cast::cast;
+use datafusion_ext_commons::hyperloglog::HyperLogLog;
+use futures::stream::once;
+use futures::StreamExt;
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+// precision of hll sketches for ndvs, about 0.8% relative error
+const NDV_HLL_PRECISION: u32 = 14;
+
+// number of fields of each column in partial statistics
+const NUM_PARTIAL_FIELDS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectStatsMode {
+    /// collects mergeable statistics of the input table, outputs one row with
+    /// num_rows followed by (null_count, ndv_sketch, min, max, total_len,
+    /// max_len) of each column
+    Partial,
+    /// merges partial statistics, outputs one row per column with
+    /// (column, num_rows, null_count, ndv, min, max, avg_len, max_len)
+    Final,
+}
+
+/// collects table/column statistics for ANALYZE TABLE. statistics of each
+/// partition are collected in partial mode, and merged in final mode.
+#[derive(Debug)]
+pub struct CollectStatsExec {
+    input: Arc<dyn ExecutionPlan>,
+    mode: CollectStatsMode,
+    schema: SchemaRef,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl CollectStatsExec {
+    pub fn try_new(input: Arc<dyn ExecutionPlan>, mode: CollectStatsMode) -> Result<Self> {
+        let schema = match mode {
+            CollectStatsMode::Partial => partial_stats_schema(&input.schema()),
+            CollectStatsMode::Final => {
+                column_fields_from_partial_schema(&input.schema())?;
+                final_stats_schema()
+            }
+        };
+        Ok(Self {
+            input,
+            mode,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    pub fn mode(&self) -> CollectStatsMode {
+        self.mode
+    }
+}
+
+impl DisplayAs for CollectStatsExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "CollectStatsExec(mode={:?})", self.mode)
+    }
+}
+
+impl ExecutionPlan for CollectStatsExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::try_new(children[0].clone(), self.mode)?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+        let input = self.input.execute(partition, context)?;
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(execute_collect_stats(
+                input,
+                self.mode,
+                self.schema(),
+                metrics,
+            )),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+async fn execute_collect_stats(
+    mut input: SendableRecordBatchStream,
+    mode: CollectStatsMode,
+    schema: SchemaRef,
+    metrics: BaselineMetrics,
+) -> Result<RecordBatch> {
+    let column_fields = match mode {
+        CollectStatsMode::Partial => input.schema().fields().clone(),
+        CollectStatsMode::Final => column_fields_from_partial_schema(&input.schema())?,
+    };
+    let mut num_rows = 0;
+    let mut col_stats = column_fields
+        .iter()
+        .map(|field| ColumnStats::new(field.data_type()))
+        .collect::<Vec<_>>();
+
+    while let Some(batch) = input.next().await.transpose()? {
+        let _timer = metrics.elapsed_compute().timer();
+        match mode {
+            CollectStatsMode::Partial => {
+                num_rows += batch.num_rows() as i64;
+                for (stats, col) in col_stats.iter_mut().zip(batch.columns()) {
+                    stats.update(col)?;
+                }
+            }
+            CollectStatsMode::Final => {
+                let partial_num_rows = as_primitive_array::<Int64Type>(batch.column(0));
+                num_rows += partial_num_rows.iter().flatten().sum::<i64>();
+                for (i, stats) in col_stats.iter_mut().enumerate() {
+                    let offset = 1 + i * NUM_PARTIAL_FIELDS;
+                    stats.merge(&batch.columns()[offset..][..NUM_PARTIAL_FIELDS])?;
+                }
+            }
+        }
+    }
+
+    let _timer = metrics.elapsed_compute().timer();
+    metrics.record_output(match mode {
+        CollectStatsMode::Partial => 1,
+        CollectStatsMode::Final => col_stats.len(),
+    });
+    match mode {
+        CollectStatsMode::Partial => {
+            let mut cols: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![num_rows]))];
+            for stats in &col_stats {
+                cols.extend(stats.partial_output()?);
+            }
+            Ok(RecordBatch::try_new(schema, cols)?)
+        }
+        CollectStatsMode::Final => {
+            let num_cols = col_stats.len();
+            let mut min_values = StringBuilder::new();
+            let mut max_values = StringBuilder::new();
+            for stats in &col_stats {
+                min_values.append_option(stats.min_value()?);
+                max_values.append_option(stats.max_value()?);
+            }
+            Ok(RecordBatch::try_new(
+                schema,
+                vec![
+                    Arc::new(StringArray::from_iter_values(
+                        column_fields.iter().map(|field| field.name()),
+                    )),
+                    Arc::new(Int64Array::from(vec![num_rows; num_cols])),
+                    Arc::new(Int64Array::from_iter_values(
+                        col_stats.iter().map(|stats| stats.null_count),
+                    )),
+                    Arc::new(Int64Array::from_iter(col_stats.iter().map(|stats| {
+                        stats.hll.as_ref().map(|hll| hll.estimate() as i64)
+                    }))),
+                    Arc::new(min_values.finish()),
+                    Arc::new(max_values.finish()),
+                    Arc::new(Int64Array::from_iter(
+                        col_stats.iter().map(|stats| stats.avg_len(num_rows)),
+                    )),
+                    Arc::new(Int64Array::from_iter_values(
+                        col_stats.iter().map(|stats| stats.max_len),
+                    )),
+                ],
+            )?)
+        }
+    }
+}
+
+fn partial_stats_schema(input_schema: &SchemaRef) -> SchemaRef {
+    let mut fields = vec![Field::new("num_rows", DataType::Int64, false)];
+    for field in input_schema.fields() {
+        let name = field.name();
+        fields.extend([
+            Field::new(format!("{name}#null_count"), DataType::Int64, false),
+            Field::new(format!("{name}#ndv_sketch"), DataType::Binary, true),
+            Field::new(format!("{name}#min"), field.data_type().clone(), true),
+            Field::new(format!("{name}#max"), field.data_type().clone(), true),
+            Field::new(format!("{name}#total_len"), DataType::Int64, false),
+            Field::new(format!("{name}#max_len"), DataType::Int64, false),
+        ]);
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// restores names and types of table columns from partial statistics schema
+fn column_fields_from_partial_schema(partial_schema: &SchemaRef) -> Result<Fields> {
+    let num_fields = partial_schema.fields().len();
+    if num_fields == 0 || (num_fields - 1) % NUM_PARTIAL_FIELDS != 0 {
+        return Err(DataFusionError::Plan(format!(
+            "CollectStatsExec: invalid partial statistics schema: {partial_schema:?}"
+        )));
+    }
+    Ok((1..num_fields)
+        .step_by(NUM_PARTIAL_FIELDS)
+        .map(|i| {
+            let min_field = partial_schema.field(i + 2);
+            let name = min_field.name().trim_end_matches("#min");
+            Field::new(name, min_field.data_type().clone(), true)
+        })
+        .collect())
+}
+
+fn final_stats_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("column", DataType::Utf8, false),
+        Field::new("num_rows", DataType::Int64, false),
+        Field::new("null_count", DataType::Int64, false),
+        Field::new("ndv", DataType::Int64, true),
+        Field::new("min", DataType::Utf8, true),
+        Field::new("max", DataType::Utf8, true),
+        Field::new("avg_len", DataType::Int64, true),
+        Field::new("max_len", DataType::Int64, false),
+    ]))
+}
+
+/// statistics of a column. ndv and min/max collecting is given up if the column
+/// type is not supported.
+struct ColumnStats {
+    data_type: DataType,
+    null_count: i64,
+    hll: Option<HyperLogLog>,
+    min_acc: Option<MinAccumulator>,
+    max_acc: Option<MaxAccumulator>,
+    total_len: i64,
+    max_len: i64,
+}
+
+impl ColumnStats {
+    fn new(data_type: &DataType) -> Self {
+        Self {
+            data_type: data_type.clone(),
+            null_count: 0,
+            hll: Some(HyperLogLog::new(NDV_HLL_PRECISION)),
+            min_acc: MinAccumulator::try_new(data_type).ok(),
+            max_acc: MaxAccumulator::try_new(data_type).ok(),
+            total_len: 0,
+            max_len: 0,
+        }
+    }
+
+    fn update(&mut self, values: &ArrayRef) -> Result<()> {
+        self.null_count += values.null_count() as i64;
+        if let Some(hll) = &mut self.hll {
+            if hll.update(values).is_err() {
+                self.hll = None;
+            }
+        }
+        self.update_min_max(values, values);
+
+        // lengths of non-null values, like spark's avgLen/maxLen
+        let (total_len, max_len) = match values.data_type() {
+            DataType::Utf8 => {
+                let values = as_string_array(values);
+                values
+                    .iter()
+                    .flatten()
+                    .fold((0, 0), |(t, m), v| (t + v.len(), m.max(v.len())))
+            }
+            DataType::Binary => {
+                let values = as_generic_binary_array::<i32>(values);
+                values
+                    .iter()
+                    .flatten()
+                    .fold((0, 0), |(t, m), v| (t + v.len(), m.max(v.len())))
+            }
+            data_type => {
+                let width = fixed_width(data_type);
+                let num_valid = values.len() - values.null_count();
+                (width * num_valid, if num_valid > 0 { width } else { 0 })
+            }
+        };
+        self.total_len += total_len as i64;
+        self.max_len = self.max_len.max(max_len as i64);
+        Ok(())
+    }
+
+    /// merges partial statistics, see `partial_output()`
+    fn merge(&mut self, partial: &[ArrayRef]) -> Result<()> {
+        let null_counts = as_primitive_array::<Int64Type>(&partial[0]);
+        let ndv_sketches = as_generic_binary_array::<i32>(&partial[1]);
+        let total_lens = as_primitive_array::<Int64Type>(&partial[4]);
+        let max_lens = as_primitive_array::<Int64Type>(&partial[5]);
+
+        self.null_count += null_counts.iter().flatten().sum::<i64>();
+        for sketch in ndv_sketches.iter() {
+            match (&mut self.hll, sketch) {
+                (Some(hll), Some(sketch)) => {
+                    hll.merge(&HyperLogLog::try_from_registers(sketch.to_vec())?);
+                }
+                _ => self.hll = None,
+            }
+        }
+        self.update_min_max(&partial[2], &partial[3]);
+        self.total_len += total_lens.iter().flatten().sum::<i64>();
+        self.max_len = self
+            .max_len
+            .max(max_lens.iter().flatten().max().unwrap_or(0));
+        Ok(())
+    }
+
+    fn update_min_max(&mut self, min_values: &ArrayRef, max_values: &ArrayRef) {
+        if let Some(min_acc) = &mut self.min_acc {
+            if min_acc.update_batch(&[min_values.clone()]).is_err() {
+                self.min_acc = None;
+            }
+        }
+        if let Some(max_acc) = &mut self.max_acc {
+            if max_acc.update_batch(&[max_values.clone()]).is_err() {
+                self.max_acc = None;
+            }
+        }
+    }
+
+    fn partial_output(&self) -> Result<Vec<ArrayRef>> {
+        let evaluate = |acc: Option<&dyn Accumulator>| -> Result<ArrayRef> {
+            let value = match acc {
+                Some(acc) => acc.evaluate()?,
+                None => ScalarValue::try_from(&self.data_type)?,
+            };
+            Ok(value.to_array())
+        };
+        Ok(vec![
+            Arc::new(Int64Array::from(vec![self.null_count])),
+            Arc::new(BinaryArray::from(vec![self
+                .hll
+                .as_ref()
+                .map(|hll| hll.registers())])),
+            evaluate(self.min_acc.as_ref().map(|acc| acc as &dyn Accumulator))?,
+            evaluate(self.max_acc.as_ref().map(|acc| acc as &dyn Accumulator))?,
+            Arc::new(Int64Array::from(vec![self.total_len])),
+            Arc::new(Int64Array::from(vec![self.max_len])),
+        ])
+    }
+
+    fn min_value(&self) -> Result<Option<String>> {
+        match &self.min_acc {
+            Some(acc) => scalar_to_string(acc.evaluate()?),
+            None => Ok(None),
+        }
+    }
+
+    fn max_value(&self) -> Result<Option<String>> {
+        match &self.max_acc {
+            Some(acc) => scalar_to_string(acc.evaluate()?),
+            None => Ok(None),
+        }
+    }
+
+    /// returns average length of non-null values, or the fixed width if all
+    /// values are null like spark
+    fn avg_len(&self, num_rows: i64) -> Option<i64> {
+        let num_valid = num_rows - self.null_count;
+        if num_valid > 0 {
+            return Some((self.total_len as f64 / num_valid as f64).round() as i64);
+        }
+        match fixed_width(&self.data_type) {
+            0 => None,
+            width => Some(width as i64),
+        }
+    }
+}
+
+fn scalar_to_string(value: ScalarValue) -> Result<Option<String>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let string_value = cast(&value.to_array(), &DataType::Utf8)?;
+    let string_value = as_string_array(&string_value);
+    Ok(string_value
+        .is_valid(0)
+        .then(|| string_value.value(0).to_string()))
+}
+
+/// returns byte width of fixed-width types, or zero for others
+fn fixed_width(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Boolean => 1,
+        data_type => data_type.primitive_width().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::collect_stats_exec::{CollectStatsExec, CollectStatsMode};
+    use crate::common::memory_manager::MemManager;
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_collect_stats() -> Result<()> {
+        MemManager::init(10000);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = |a: Vec<Option<i32>>, b: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(a)), Arc::new(StringArray::from(b))],
+            )
+        };
+        let partitions = vec![
+            vec![batch(
+                vec![Some(3), None, Some(1)],
+                vec![Some("xyz"), Some("x"), None],
+            )?],
+            vec![batch(vec![Some(7), Some(3)], vec![None, None])?],
+        ];
+
+        let input = Arc::new(MemoryExec::try_new(&partitions, schema, None)?);
+        let partial = Arc::new(CollectStatsExec::try_new(input, CollectStatsMode::Partial)?);
+        let task_ctx = SessionContext::new().task_ctx();
+        let mut partial_batches = vec![];
+        for partition in 0..2 {
+            let stream = partial.execute(partition, task_ctx.clone())?;
+            partial_batches.extend(common::collect(stream).await?);
+        }
+        assert_eq!(partial_batches.len(), 2);
+        assert_eq!(partial_batches[0].num_rows(), 1);
+
+        let merged = Arc::new(MemoryExec::try_new(
+            &[partial_batches],
+            partial.schema(),
+            None,
+        )?);
+        let final_stats = CollectStatsExec::try_new(merged, CollectStatsMode::Final)?;
+        let output = common::collect(final_stats.execute(0, task_ctx)?).await?;
+        assert_batches_eq!(
+            vec![
+                "+--------+----------+------------+-----+-----+-----+---------+---------+",
+                "| column | num_rows | null_count | ndv | min | max | avg_len | max_len |",
+                "+--------+----------+------------+-----+-----+-----+---------+---------+",
+                "| a      | 5        | 1          | 3   | 1   | 7   | 4       | 4       |",
+                "| b      | 5        | 3          | 2   | x   | xyz | 2       | 3       |",
+                "+--------+----------+------------+-----+-----+-----+---------+---------+",
+            ],
+            &output
+        );
+        Ok(())
+    }
+}
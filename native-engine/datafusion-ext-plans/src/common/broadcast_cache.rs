@@ -0,0 +1,219 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Weak};
+use tokio::sync::OnceCell;
+
+pub type BroadcastBatches = Arc<Vec<RecordBatch>>;
+
+/// process-level cache of deserialized broadcast data, so that tasks in the same
+/// executor share one copy instead of deserializing it in every task. memory of
+/// cached data is accounted to the mem manager as an executor-level consumer.
+static BROADCAST_CACHE: Lazy<Mutex<BroadcastCache>> =
+    Lazy::new(|| Mutex::new(BroadcastCache::default()));
+
+static BROADCAST_CACHE_MEM_CONSUMER: Lazy<Arc<BroadcastCacheMemConsumer>> = Lazy::new(|| {
+    let consumer = Arc::new(BroadcastCacheMemConsumer {
+        name: "BroadcastCache".to_string(),
+        mem_consumer_info: None,
+    });
+    MemManager::register_executor_consumer(consumer.clone(), false);
+    consumer
+});
+
+#[derive(Default)]
+struct BroadcastCache {
+    entries: HashMap<String, Arc<OnceCell<BroadcastBatches>>>,
+    loaded: VecDeque<(String, usize)>,
+    mem_used: usize,
+}
+
+impl BroadcastCache {
+    fn remove(&mut self, id: &str) -> bool {
+        self.entries.remove(id);
+        match self
+            .loaded
+            .iter()
+            .position(|(loaded_id, _)| loaded_id == id)
+        {
+            Some(pos) => {
+                let (_, mem_size) = self.loaded.remove(pos).unwrap();
+                self.mem_used -= mem_size;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct BroadcastCacheMemConsumer {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+}
+
+#[async_trait]
+impl MemConsumer for BroadcastCacheMemConsumer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+}
+
+/// gets cached broadcast data by id, loading it with `load` if not cached. concurrent
+/// tasks with the same id wait for the first loading one. least recently loaded
+/// entries are evicted once total memory exceeds `max_mem_size`, tasks still
+/// holding evicted data are not affected.
+pub async fn get_or_load_broadcast<F>(
+    id: &str,
+    max_mem_size: usize,
+    load: F,
+) -> Result<BroadcastBatches>
+where
+    F: Future<Output = Result<Vec<RecordBatch>>>,
+{
+    let cell = BROADCAST_CACHE
+        .lock()
+        .entries
+        .entry(id.to_string())
+        .or_default()
+        .clone();
+
+    let mut newly_loaded = false;
+    let batches = cell
+        .get_or_try_init(|| async {
+            newly_loaded = true;
+            Ok::<_, datafusion::error::DataFusionError>(Arc::new(load.await?))
+        })
+        .await?
+        .clone();
+
+    if newly_loaded {
+        let mem_size = batches.iter().map(|b| b.get_array_memory_size()).sum();
+        let mem_used = {
+            let mut cache = BROADCAST_CACHE.lock();
+            cache.loaded.push_back((id.to_string(), mem_size));
+            cache.mem_used += mem_size;
+            while cache.mem_used > max_mem_size && cache.loaded.len() > 1 {
+                let (evicted_id, evicted_mem_size) = cache.loaded.pop_front().unwrap();
+                cache.entries.remove(&evicted_id);
+                cache.mem_used -= evicted_mem_size;
+                log::info!("evicted cached broadcast: {}", evicted_id);
+            }
+            log::info!(
+                "cached broadcast: {}, mem_size={}, total_mem_used={}",
+                id,
+                mem_size,
+                cache.mem_used,
+            );
+            cache.mem_used
+        };
+        BROADCAST_CACHE_MEM_CONSUMER
+            .update_mem_used(mem_used)
+            .await?;
+    }
+    Ok(batches)
+}
+
+/// releases cached broadcast data by id, called when the broadcast is removed
+/// from the executor. tasks still holding the data are not affected.
+pub fn release_broadcast(id: &str) -> Result<()> {
+    let mem_used = {
+        let mut cache = BROADCAST_CACHE.lock();
+        if !cache.remove(id) {
+            return Ok(());
+        }
+        log::info!(
+            "released cached broadcast: {}, total_mem_used={}",
+            id,
+            cache.mem_used,
+        );
+        cache.mem_used
+    };
+
+    // shrinking memory never waits or spills
+    futures::executor::block_on(BROADCAST_CACHE_MEM_CONSUMER.update_mem_used(mem_used))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::broadcast_cache::{
+        get_or_load_broadcast, release_broadcast, BROADCAST_CACHE,
+    };
+    use crate::common::memory_manager::MemManager;
+    use arrow::array::Int32Array;
+    use arrow::record_batch::RecordBatch;
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![("a", Arc::new(Int32Array::from(vec![1, 2, 3])) as _)])
+            .unwrap()
+    }
+
+    // single test as the cache is shared by the whole process
+    #[tokio::test]
+    async fn test_broadcast_cache() {
+        MemManager::init(1 << 30);
+        let num_loads = AtomicUsize::new(0);
+        for _ in 0..3 {
+            let batches = get_or_load_broadcast("test_1", usize::MAX, async {
+                num_loads.fetch_add(1, SeqCst);
+                Ok(vec![batch()])
+            })
+            .await
+            .unwrap();
+            assert_eq!(batches.len(), 1);
+        }
+        assert_eq!(num_loads.load(SeqCst), 1);
+
+        // test_1 is evicted by test_2, and loaded again
+        for id in ["test_2", "test_1"] {
+            get_or_load_broadcast(id, 0, async {
+                num_loads.fetch_add(1, SeqCst);
+                Ok(vec![batch()])
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(num_loads.load(SeqCst), 3);
+
+        // released entries are loaded again
+        release_broadcast("test_1").unwrap();
+        assert_eq!(BROADCAST_CACHE.lock().mem_used, 0);
+        get_or_load_broadcast("test_1", usize::MAX, async {
+            num_loads.fetch_add(1, SeqCst);
+            Ok(vec![batch()])
+        })
+        .await
+        .unwrap();
+        assert_eq!(num_loads.load(SeqCst), 4);
+    }
+}
@@ -16,15 +16,23 @@ use arrow::array::{Array, ArrayRef, BooleanArray};
 use arrow::compute::{filter, filter_record_batch, prep_null_mask_filter};
 use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
 use datafusion::common::cast::as_boolean_array;
 use datafusion::common::tree_node::{Transformed, TreeNode};
 use datafusion::common::{Result, ScalarValue};
+use datafusion::logical_expr::Operator;
 use datafusion::physical_expr::expressions::{
-    CaseExpr, Column, Literal, NoOp, SCAndExpr, SCOrExpr,
+    BinaryExpr, CaseExpr, Column, InListExpr, IsNotNullExpr, IsNullExpr, Literal, NoOp, NotExpr,
+    SCAndExpr, SCOrExpr,
 };
 use datafusion::physical_expr::{scatter, PhysicalExpr, PhysicalExprRef};
+use datafusion::physical_plan::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, Time};
 use datafusion::physical_plan::ColumnarValue;
+use datafusion_ext_commons::error_context::{ErrorContext, WithErrorContext};
 use datafusion_ext_commons::uda::UserDefinedArray;
+use datafusion_ext_exprs::string_contains::StringContainsExpr;
+use datafusion_ext_exprs::string_ends_with::StringEndsWithExpr;
+use datafusion_ext_exprs::string_starts_with::StringStartsWithExpr;
 use itertools::Itertools;
 use parking_lot::Mutex;
 use std::any::Any;
@@ -34,10 +42,17 @@ use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// number of batches used for measuring filter predicates before reordering
+const ADAPTIVE_FILTER_SAMPLED_BATCHES: usize = 8;
 
 pub struct CachedExprsEvaluator {
+    filter_exprs: Vec<PhysicalExprRef>,
+    projection_exprs: Vec<PhysicalExprRef>,
     transformed_projection_exprs: Vec<PhysicalExprRef>,
     transformed_pruned_filter_exprs: Vec<(PhysicalExprRef, Vec<usize>)>,
+    filter_order: Mutex<FilterOrder>,
     cache: Cache,
 }
 
@@ -46,8 +61,30 @@ impl CachedExprsEvaluator {
         filter_exprs: Vec<PhysicalExprRef>,
         projection_exprs: Vec<PhysicalExprRef>,
     ) -> Result<Self> {
-        let (transformed_exprs, cache) =
-            transform_to_cached_exprs(&[filter_exprs.clone(), projection_exprs.clone()].concat())?;
+        Self::try_new_impl(filter_exprs, projection_exprs, None)
+    }
+
+    /// creates an evaluator with metrics of cached exprs (hits and elapsed
+    /// time of each cached expr) registered in the owning operator's metrics
+    pub fn try_new_with_metrics(
+        filter_exprs: Vec<PhysicalExprRef>,
+        projection_exprs: Vec<PhysicalExprRef>,
+        metrics: &ExecutionPlanMetricsSet,
+        partition: usize,
+    ) -> Result<Self> {
+        Self::try_new_impl(filter_exprs, projection_exprs, Some((metrics, partition)))
+    }
+
+    fn try_new_impl(
+        filter_exprs: Vec<PhysicalExprRef>,
+        projection_exprs: Vec<PhysicalExprRef>,
+        metrics: Option<(&ExecutionPlanMetricsSet, usize)>,
+    ) -> Result<Self> {
+        let (transformed_exprs, cache) = transform_to_cached_exprs(
+            &[filter_exprs.clone(), projection_exprs.clone()].concat(),
+            filter_exprs.len(),
+            metrics,
+        )?;
         let (transformed_filter_exprs, transformed_projection_exprs) =
             transformed_exprs.split_at(filter_exprs.len());
 
@@ -56,10 +93,21 @@ impl CachedExprsEvaluator {
             .map(|expr| prune_expr_cols(expr))
             .collect();
         let transformed_projection_exprs = transformed_projection_exprs.to_vec();
+        let adaptive_filter_order = is_jni_bridge_inited()
+            && jni_call_static!(BlazeConf.enableAdaptiveFilterOrder() -> bool)?;
+        let filter_order = Mutex::new(FilterOrder::new(
+            filter_exprs
+                .iter()
+                .map(|expr| adaptive_filter_order && is_reorderable(expr))
+                .collect(),
+        ));
 
         Ok(Self {
+            filter_exprs,
+            projection_exprs,
             transformed_projection_exprs,
             transformed_pruned_filter_exprs,
+            filter_order,
             cache,
         })
     }
@@ -78,9 +126,16 @@ impl CachedExprsEvaluator {
     }
 
     fn filter_impl(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let (order, sampling) = {
+            let filter_order = self.filter_order.lock();
+            (filter_order.order.clone(), filter_order.sampling())
+        };
+
         // filter
         let mut current_filtered = FilterStat::AllRetained;
-        for (filter_expr, proj) in &self.transformed_pruned_filter_exprs {
+        for i in order {
+            let (filter_expr, proj) = &self.transformed_pruned_filter_exprs[i];
+
             // save previous selected, used for scattering
             let previous_selected = if let FilterStat::Some(array) = &current_filtered {
                 Some(array.clone())
@@ -89,8 +144,25 @@ impl CachedExprsEvaluator {
             };
 
             // execute current filtering
-            current_filtered = filter_one_pred(batch, filter_expr, proj, current_filtered)?;
+            // errors are reported with the original expr, not the transformed one
+            let start_time = Instant::now();
+            let num_input_rows = current_filtered.num_selected_rows(batch.num_rows());
+            current_filtered = filter_one_pred(batch, filter_expr, proj, current_filtered)
+                .with_error_context(|| {
+                    ErrorContext::new("evaluating filter").with("expr", &self.filter_exprs[i])
+                })?;
+            if sampling {
+                self.filter_order.lock().update_stat(
+                    i,
+                    num_input_rows,
+                    current_filtered.num_selected_rows(batch.num_rows()),
+                    start_time.elapsed(),
+                );
+            }
             if let FilterStat::AllFiltered = &current_filtered {
+                if sampling {
+                    self.filter_order.lock().finish_batch();
+                }
                 return Ok(RecordBatch::new_empty(batch.schema()));
             }
             if let FilterStat::Some(selected) = &current_filtered {
@@ -117,6 +189,9 @@ impl CachedExprsEvaluator {
                 })?;
             }
         }
+        if sampling {
+            self.filter_order.lock().finish_batch();
+        }
         let batch = match current_filtered {
             FilterStat::AllFiltered => RecordBatch::new_empty(batch.schema()),
             FilterStat::AllRetained => batch.clone(),
@@ -140,9 +215,14 @@ impl CachedExprsEvaluator {
         let output_cols = self
             .transformed_projection_exprs
             .iter()
-            .map(|expr| {
+            .enumerate()
+            .map(|(i, expr)| {
                 expr.evaluate(&filtered_batch)
                     .map(|c| c.into_array(filtered_batch.num_rows()))
+                    .with_error_context(|| {
+                        ErrorContext::new("evaluating projection")
+                            .with("expr", &self.projection_exprs[i])
+                    })
             })
             .collect::<Result<Vec<ArrayRef>>>()?;
         Ok(RecordBatch::try_new_with_options(
@@ -153,7 +233,16 @@ impl CachedExprsEvaluator {
     }
 }
 
-fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalExprRef>, Cache)> {
+fn transform_to_cached_exprs(
+    exprs: &[PhysicalExprRef],
+    num_filter_exprs: usize,
+    metrics: Option<(&ExecutionPlanMetricsSet, usize)>,
+) -> Result<(Vec<PhysicalExprRef>, Cache)> {
+    // filter exprs are evaluated in adaptive order, so each of them has its own
+    // evaluation level. projection exprs are evaluated after all filters.
+    let projection_level = num_filter_exprs;
+    let level_of = |i: usize| i.min(projection_level);
+
     // count all children exprs
     fn count(expr: &PhysicalExprRef, expr_counts: &mut HashMap<ExprKey, usize>) {
         expr_counts
@@ -169,44 +258,72 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
         count(&expr, &mut expr_counts);
     }
 
+    // find levels of exprs which are evaluated on all rows
+    let mut unconditional_levels: HashMap<ExprKey, HashSet<usize>> = HashMap::new();
+    for (i, expr) in exprs.iter().enumerate() {
+        let mut unconditional = HashSet::new();
+        collect_unconditional(expr, &mut unconditional);
+        for expr_key in unconditional {
+            unconditional_levels
+                .entry(expr_key)
+                .or_default()
+                .insert(level_of(i));
+        }
+    }
+
+    // conditionally evaluated exprs can be cached only if they are evaluated
+    // on all rows in the same level (or in a former level)
+    let cachable_in_level = |expr_key: &ExprKey, level: usize| {
+        unconditional_levels
+            .get(expr_key)
+            .map(|levels| levels.contains(&level) || level == projection_level)
+            .unwrap_or(false)
+    };
+
     // find all duplicated exprs (which count is larger than its parent)
     fn collect_dups(
         expr: &PhysicalExprRef,
         parent_count: usize,
+        conditional: bool,
         expr_counts: &HashMap<ExprKey, usize>,
+        cachable: &dyn Fn(&ExprKey) -> bool,
         dups: &mut HashSet<ExprKey>,
     ) {
         // ignore trivial leaf exprs
-        if expr.as_any().downcast_ref::<NoOp>().is_some()
-            || expr.as_any().downcast_ref::<Column>().is_some()
-            || expr.as_any().downcast_ref::<Literal>().is_some()
-        {
+        if is_trivial(expr) {
             return;
         }
 
         // insert exprs with occurrences more than its parent
         let expr_key = ExprKey(expr.clone());
         let current_count = expr_counts.get(&expr_key).cloned().unwrap_or(0);
-        if current_count > parent_count {
+        if current_count > parent_count && (!conditional || cachable(&expr_key)) {
             dups.insert(expr_key);
         }
 
-        // traverse children, excluding exprs with short circuiting evaluation
-        if expr.as_any().downcast_ref::<CaseExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCAndExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
-        {
-            // short circuiting expression - only first child can be cached
-            collect_dups(&expr.children()[0], current_count, expr_counts, dups);
-        } else {
-            expr.children().iter().for_each(|child| {
-                collect_dups(child, current_count, expr_counts, dups);
-            });
+        // traverse children, children of short circuiting exprs may be
+        // evaluated conditionally
+        let unconditional_children = unconditional_children(expr);
+        for (i, child) in expr.children().iter().enumerate() {
+            let child_conditional = conditional
+                || unconditional_children
+                    .as_ref()
+                    .map(|indices| !indices.contains(&i))
+                    .unwrap_or(false);
+            collect_dups(
+                child,
+                current_count,
+                child_conditional,
+                expr_counts,
+                cachable,
+                dups,
+            );
         }
     }
     let mut dups = HashSet::new();
-    for expr in exprs {
-        collect_dups(&expr, 1, &expr_counts, &mut dups);
+    for (i, expr) in exprs.iter().enumerate() {
+        let cachable = |expr_key: &ExprKey| cachable_in_level(expr_key, level_of(i));
+        collect_dups(&expr, 1, false, &expr_counts, &cachable, &mut dups);
     }
 
     // generate cached expr ids
@@ -220,13 +337,11 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
     fn transform(
         expr: PhysicalExprRef,
         cached_expr_ids: &HashMap<ExprKey, usize>,
+        prefetchable: &dyn Fn(&ExprKey) -> bool,
         cache: &Cache,
     ) -> Result<PhysicalExprRef> {
         // ignore trivial leaf exprs
-        if expr.as_any().downcast_ref::<NoOp>().is_some()
-            || expr.as_any().downcast_ref::<Column>().is_some()
-            || expr.as_any().downcast_ref::<Literal>().is_some()
-        {
+        if is_trivial(&expr) {
             return Ok(expr);
         }
 
@@ -235,22 +350,58 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
         let current_cache_id = cached_expr_ids.get(&expr_key).cloned();
 
         // transform children
-        let transformed_expr = if expr.as_any().downcast_ref::<CaseExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCAndExpr>().is_some()
-            || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
-        {
-            // short circuiting expression - only first child can be cached
-            let mut children = expr.children().clone();
-            children[0] = transform(children[0].clone(), cached_expr_ids, cache)?;
-            expr.clone().with_new_children(children)?
-        } else {
-            expr.clone().with_new_children(
-                expr.children()
+        let unconditional_children = unconditional_children(&expr);
+        let mut prefetch_exprs = vec![];
+        let mut transformed_children: Vec<PhysicalExprRef> = vec![];
+        for (i, child) in expr.children().into_iter().enumerate() {
+            let orig_child = child.clone();
+            let transformed_child = transform(child, cached_expr_ids, prefetchable, cache)?;
+            let conditional = unconditional_children
+                .as_ref()
+                .map(|indices| !indices.contains(&i))
+                .unwrap_or(false);
+            if !conditional {
+                transformed_children.push(transformed_child);
+                continue;
+            }
+
+            // cached exprs in conditionally evaluated children are evaluated
+            // on selected rows, which are taken from cached values of all rows
+            let mut cached_exprs = vec![];
+            collect_cached_exprs(&orig_child, &transformed_child, &mut cached_exprs);
+            if cached_exprs.is_empty() {
+                transformed_children.push(transformed_child);
+                continue;
+            }
+            prefetch_exprs.extend(
+                cached_exprs
                     .into_iter()
-                    .map(|child| transform(child, cached_expr_ids, cache))
-                    .collect::<Result<_>>()?,
-            )?
-        };
+                    .filter(|(orig_expr, _)| prefetchable(&ExprKey(orig_expr.clone())))
+                    .map(|(_, cached_expr)| cached_expr),
+            );
+            transformed_children.push(Arc::new(ConditionalExpr {
+                cache: cache.clone(),
+                inner: transformed_child,
+            }));
+        }
+        let mut transformed_expr = expr.clone().with_new_children(transformed_children)?;
+
+        // prefetch cached exprs so that they are evaluated on all rows before
+        // being evaluated conditionally
+        if !prefetch_exprs.is_empty() {
+            transformed_expr = Arc::new(PrefetchExpr {
+                cache: cache.clone(),
+                inner: transformed_expr,
+                prefetch_exprs: prefetch_exprs
+                    .into_iter()
+                    .unique_by(|expr| {
+                        expr.as_any()
+                            .downcast_ref::<CachedExpr>()
+                            .map(|cached_expr| cached_expr.id)
+                    })
+                    .collect(),
+            });
+        }
 
         Ok(if let Some(cache_id) = current_cache_id {
             Arc::new(CachedExpr {
@@ -263,14 +414,142 @@ fn transform_to_cached_exprs(exprs: &[PhysicalExprRef]) -> Result<(Vec<PhysicalE
         })
     }
 
-    let cache = Cache::new(cached_expr_ids.len());
+    let cache_metrics = match metrics {
+        Some((metrics, partition)) => cached_expr_ids
+            .iter()
+            .sorted_by_key(|(_, id)| **id)
+            .map(|(expr_key, &id)| {
+                log::info!("cached expr #{id}: {}", expr_key.0);
+                CachedExprMetrics::new(metrics, partition, id)
+            })
+            .collect(),
+        None => vec![],
+    };
+    let cache = Cache::new(cached_expr_ids.len(), cache_metrics);
     let transformed_exprs = exprs
         .iter()
-        .map(|expr| Ok(transform(expr.clone(), &cached_expr_ids, &cache)?))
+        .enumerate()
+        .map(|(i, expr)| {
+            let prefetchable = |expr_key: &ExprKey| cachable_in_level(expr_key, level_of(i));
+            Ok(transform(
+                expr.clone(),
+                &cached_expr_ids,
+                &prefetchable,
+                &cache,
+            )?)
+        })
         .collect::<Result<_>>()?;
     Ok((transformed_exprs, cache))
 }
 
+fn is_trivial(expr: &PhysicalExprRef) -> bool {
+    expr.as_any().downcast_ref::<NoOp>().is_some()
+        || expr.as_any().downcast_ref::<Column>().is_some()
+        || expr.as_any().downcast_ref::<Literal>().is_some()
+}
+
+/// Get indices of children evaluated on all rows of short circuiting exprs,
+/// returns None for other exprs whose children are all evaluated
+fn unconditional_children(expr: &PhysicalExprRef) -> Option<Vec<usize>> {
+    if expr.as_any().downcast_ref::<CaseExpr>().is_some() {
+        // children of case expr: [base, when1, then1, ..., else], the base
+        // and the first when are evaluated on all rows
+        return Some(vec![0, 1]);
+    }
+    if expr.as_any().downcast_ref::<SCAndExpr>().is_some()
+        || expr.as_any().downcast_ref::<SCOrExpr>().is_some()
+    {
+        return Some(vec![0]);
+    }
+    None
+}
+
+/// Collect exprs evaluated on all rows when evaluating the expr
+fn collect_unconditional(expr: &PhysicalExprRef, unconditional: &mut HashSet<ExprKey>) {
+    unconditional.insert(ExprKey(expr.clone()));
+    let children = expr.children();
+    match unconditional_children(expr) {
+        None => children
+            .iter()
+            .for_each(|child| collect_unconditional(child, unconditional)),
+        Some(indices) => {
+            indices
+                .into_iter()
+                .for_each(|i| collect_unconditional(&children[i], unconditional));
+            unconditional.extend(collect_shared_by_all_branches(expr));
+        }
+    }
+}
+
+/// Collect exprs evaluated in every branch of a case expr. each branch only
+/// evaluates a part of rows, but these exprs are evaluated on all rows in total
+fn collect_shared_by_all_branches(expr: &PhysicalExprRef) -> HashSet<ExprKey> {
+    if expr.as_any().downcast_ref::<CaseExpr>().is_none() {
+        return HashSet::new();
+    }
+    let children = expr.children();
+    let num_branches = (children.len() - 2) / 2;
+    let collect = |expr: &PhysicalExprRef| {
+        let mut unconditional = HashSet::new();
+        collect_unconditional(expr, &mut unconditional);
+        unconditional
+    };
+
+    // rows of branch i are evaluated with when1..wheni and theni, other rows
+    // are evaluated with all whens and else
+    let mut evaluated_whens = HashSet::new();
+    let mut shared: Option<HashSet<ExprKey>> = None;
+    for i in 0..=num_branches {
+        if i > 0 && i < num_branches {
+            evaluated_whens.extend(collect(&children[1 + 2 * i]));
+        }
+        let branch_exprs = if i < num_branches {
+            collect(&children[2 + 2 * i])
+        } else {
+            collect(&children[1 + 2 * num_branches])
+        };
+        shared = Some(match shared {
+            None => branch_exprs,
+            Some(shared) => shared
+                .into_iter()
+                .filter(|expr_key| {
+                    evaluated_whens.contains(expr_key) || branch_exprs.contains(expr_key)
+                })
+                .collect(),
+        });
+    }
+    shared.unwrap_or_default()
+}
+
+/// Collect outermost cached exprs, along with their original exprs
+fn collect_cached_exprs(
+    orig_expr: &PhysicalExprRef,
+    transformed_expr: &PhysicalExprRef,
+    cached_exprs: &mut Vec<(PhysicalExprRef, PhysicalExprRef)>,
+) {
+    if transformed_expr
+        .as_any()
+        .downcast_ref::<CachedExpr>()
+        .is_some()
+    {
+        cached_exprs.push((orig_expr.clone(), transformed_expr.clone()));
+        return;
+    }
+    if let Some(prefetch) = transformed_expr.as_any().downcast_ref::<PrefetchExpr>() {
+        return collect_cached_exprs(orig_expr, &prefetch.inner, cached_exprs);
+    }
+    if let Some(conditional) = transformed_expr.as_any().downcast_ref::<ConditionalExpr>() {
+        return collect_cached_exprs(orig_expr, &conditional.inner, cached_exprs);
+    }
+    orig_expr
+        .children()
+        .iter()
+        .zip(transformed_expr.children().iter())
+        .for_each(|(orig_child, transformed_child)| {
+            collect_cached_exprs(orig_child, transformed_child, cached_exprs)
+        });
+}
+
 /// A physical expr wrapper to use in HashSet/HashMap
 #[derive(Clone, Debug, Hash)]
 struct ExprKey(PhysicalExprRef);
@@ -329,7 +608,8 @@ impl PhysicalExpr for CachedExpr {
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        self.cache.get(self.id, || self.orig_expr.evaluate(batch))
+        self.cache
+            .get(self.id, batch.num_rows(), || self.orig_expr.evaluate(batch))
     }
 
     fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
@@ -352,16 +632,202 @@ impl PhysicalExpr for CachedExpr {
     }
 }
 
+/// A physical expr wrapper of conditionally evaluated children of short
+/// circuiting exprs, which tracks the selected rows for cached exprs inside
+#[derive(Clone)]
+struct ConditionalExpr {
+    cache: Cache,
+    inner: PhysicalExprRef,
+}
+
+impl Display for ConditionalExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Debug for ConditionalExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Conditional(")?;
+        std::fmt::Debug::fmt(&self.inner, f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+impl PartialEq<dyn Any> for ConditionalExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map(|other| self.inner.eq(other.inner.as_any()))
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for ConditionalExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.inner.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.inner.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        // selected rows are unknown
+        self.cache
+            .with_selection(None, || self.inner.evaluate(batch))
+    }
+
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        self.cache.with_selection(Some(selection), || {
+            self.inner.evaluate_selection(batch, selection)
+        })
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.inner.children()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        Ok(Arc::new(Self {
+            cache: self.cache.clone(),
+            inner: self.inner.clone().with_new_children(children)?,
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        self.inner.dyn_hash(state);
+    }
+}
+
+/// A physical expr wrapper of short circuiting exprs, which evaluates cached
+/// exprs on all rows before they are evaluated conditionally
+#[derive(Clone)]
+struct PrefetchExpr {
+    cache: Cache,
+    inner: PhysicalExprRef,
+    prefetch_exprs: Vec<PhysicalExprRef>,
+}
+
+impl Display for PrefetchExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Debug for PrefetchExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Prefetch(")?;
+        std::fmt::Debug::fmt(&self.inner, f)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+impl PartialEq<dyn Any> for PrefetchExpr {
+    fn eq(&self, other: &dyn Any) -> bool {
+        other
+            .downcast_ref::<Self>()
+            .map(|other| self.inner.eq(other.inner.as_any()))
+            .unwrap_or(false)
+    }
+}
+
+impl PhysicalExpr for PrefetchExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        self.inner.data_type(input_schema)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        self.inner.nullable(input_schema)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        // values evaluated inside other conditional exprs are not cached, so
+        // only prefetch when evaluated on all rows
+        if self.cache.is_unconditional() {
+            for prefetch_expr in &self.prefetch_exprs {
+                prefetch_expr.evaluate(batch)?;
+            }
+        }
+        self.inner.evaluate(batch)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        // prefetch exprs are also children, so that they are transformed
+        // along with the inner expr (like in column pruning)
+        [self.inner.children(), self.prefetch_exprs.clone()].concat()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let (inner_children, prefetch_exprs) = children.split_at(self.inner.children().len());
+        Ok(Arc::new(Self {
+            cache: self.cache.clone(),
+            inner: self
+                .inner
+                .clone()
+                .with_new_children(inner_children.to_vec())?,
+            prefetch_exprs: prefetch_exprs.to_vec(),
+        }))
+    }
+
+    fn dyn_hash(&self, state: &mut dyn Hasher) {
+        self.inner.dyn_hash(state);
+    }
+}
+
 /// A struct holding all evaluated values of cachable expressions
+/// values are evaluated on all rows, conditionally evaluated exprs take the
+/// selected rows from them.
 #[derive(Clone)]
 struct Cache {
     values: Arc<Mutex<Vec<Option<ColumnarValue>>>>,
+    selections: Arc<Mutex<Vec<Option<BooleanArray>>>>,
+    metrics: Arc<Vec<CachedExprMetrics>>,
+}
+
+/// Metrics of a cached expr, empty if metrics are not registered
+#[derive(Clone)]
+struct CachedExprMetrics {
+    hits: Count,
+    elapsed_compute: Time,
+}
+
+impl CachedExprMetrics {
+    fn new(metrics: &ExecutionPlanMetricsSet, partition: usize, id: usize) -> Self {
+        Self {
+            hits: MetricBuilder::new(metrics).counter(format!("cached_expr_{id}_hits"), partition),
+            elapsed_compute: MetricBuilder::new(metrics)
+                .subset_time(format!("cached_expr_{id}_elapsed_compute"), partition),
+        }
+    }
 }
 
 impl Cache {
-    fn new(len: usize) -> Self {
+    fn new(len: usize, metrics: Vec<CachedExprMetrics>) -> Self {
         Self {
             values: Arc::new(Mutex::new(vec![None; len])),
+            selections: Arc::new(Mutex::new(vec![])),
+            metrics: Arc::new(metrics),
         }
     }
 
@@ -375,14 +841,44 @@ impl Cache {
     fn get(
         &self,
         id: usize,
+        num_rows: usize,
         evaluate_on_vacant: impl Fn() -> Result<ColumnarValue>,
     ) -> Result<ColumnarValue> {
-        if let Some(cached) = &self.values.lock()[id] {
-            return Ok(cached.clone());
+        let selections = self.selections.lock().clone();
+        let cached = self.values.lock()[id].clone();
+        let metrics = self.metrics.get(id);
+        if let Some(cached) = cached {
+            if let Some(selected) = select_cached(cached, &selections, num_rows)? {
+                if let Some(metrics) = metrics {
+                    metrics.hits.add(1);
+                }
+                return Ok(selected);
+            }
+        }
+
+        // only values of all rows are cached
+        let _timer = metrics.map(|metrics| metrics.elapsed_compute.timer());
+        let value = evaluate_on_vacant()?;
+        if selections.is_empty() {
+            self.values.lock()[id] = Some(value.clone());
         }
-        let cached = evaluate_on_vacant()?;
-        self.values.lock()[id] = Some(cached.clone());
-        Ok(cached)
+        Ok(value)
+    }
+
+    /// evaluates with selected rows (None if selected rows are unknown)
+    fn with_selection<T>(
+        &self,
+        selection: Option<&BooleanArray>,
+        func: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        self.selections.lock().push(selection.cloned());
+        let result = func();
+        self.selections.lock().pop();
+        result
+    }
+
+    fn is_unconditional(&self) -> bool {
+        self.selections.lock().is_empty()
     }
 
     fn update_all(
@@ -400,7 +896,31 @@ impl Cache {
 
     fn reset(&self) {
         self.values.lock().fill(None);
+        self.selections.lock().clear();
+    }
+}
+
+/// Take selected rows from a cached value of all rows
+fn select_cached(
+    cached: ColumnarValue,
+    selections: &[Option<BooleanArray>],
+    num_rows: usize,
+) -> Result<Option<ColumnarValue>> {
+    let mut array = match cached {
+        ColumnarValue::Scalar(_) => return Ok(Some(cached)),
+        ColumnarValue::Array(array) => array,
+    };
+    for selection in selections {
+        let Some(selection) = selection else {
+            return Ok(None);
+        };
+        array = if let Some(uda) = array.as_any().downcast_ref::<UserDefinedArray>() {
+            Arc::new(uda.filter(selection)?)
+        } else {
+            filter(&array, selection)?
+        };
     }
+    Ok((array.len() == num_rows).then(|| ColumnarValue::Array(array)))
 }
 
 /// A enum that represents filter result
@@ -410,6 +930,135 @@ pub enum FilterStat {
     Some(BooleanArray),
 }
 
+impl FilterStat {
+    fn num_selected_rows(&self, num_rows: usize) -> usize {
+        match self {
+            FilterStat::AllRetained => num_rows,
+            FilterStat::AllFiltered => 0,
+            FilterStat::Some(selected) => selected.true_count(),
+        }
+    }
+}
+
+/// Evaluation order of filter predicates. selectivity and cost of each
+/// predicate are measured over the first batches, then predicates are
+/// reordered so that cheap and selective ones are evaluated first. rows
+/// filtered by former predicates are never evaluated by latter ones.
+///
+/// only reorderable predicates (see `is_reorderable`) are moved, others stay
+/// in place and no predicate is moved across them, so rows seen by a
+/// predicate that may fail or is nondeterministic are never changed.
+struct FilterOrder {
+    order: Vec<usize>,
+    reorderable: Vec<bool>,
+    adaptive: bool,
+    stats: Vec<PredicateStat>,
+    num_sampled_batches: usize,
+}
+
+#[derive(Default, Clone, Copy)]
+struct PredicateStat {
+    num_input_rows: usize,
+    num_output_rows: usize,
+    elapsed: Duration,
+}
+
+impl PredicateStat {
+    /// expected cost of filtering out one row, lower is better
+    fn rank(&self) -> f64 {
+        if self.num_input_rows == 0 {
+            return f64::MAX;
+        }
+        let cost_per_row = self.elapsed.as_nanos().max(1) as f64 / self.num_input_rows as f64;
+        let filtered_ratio = 1.0 - self.num_output_rows as f64 / self.num_input_rows as f64;
+        cost_per_row / filtered_ratio.max(1e-6)
+    }
+}
+
+impl FilterOrder {
+    fn new(reorderable: Vec<bool>) -> Self {
+        let num_predicates = reorderable.len();
+
+        // sampling is needed only if any two adjacent predicates can be swapped
+        let adaptive = reorderable.split(|&r| !r).any(|run| run.len() > 1);
+        Self {
+            order: (0..num_predicates).collect(),
+            reorderable,
+            adaptive,
+            stats: vec![PredicateStat::default(); num_predicates],
+            num_sampled_batches: 0,
+        }
+    }
+
+    fn sampling(&self) -> bool {
+        self.adaptive && self.num_sampled_batches < ADAPTIVE_FILTER_SAMPLED_BATCHES
+    }
+
+    fn update_stat(
+        &mut self,
+        i: usize,
+        num_input_rows: usize,
+        num_output_rows: usize,
+        elapsed: Duration,
+    ) {
+        let stat = &mut self.stats[i];
+        stat.num_input_rows += num_input_rows;
+        stat.num_output_rows += num_output_rows;
+        stat.elapsed += elapsed;
+    }
+
+    fn finish_batch(&mut self) {
+        self.num_sampled_batches += 1;
+        if self.num_sampled_batches == ADAPTIVE_FILTER_SAMPLED_BATCHES {
+            // predicates not evaluated in sampling (rank=MAX) are kept last,
+            // stable sorting keeps the original order of tied predicates
+            let stats = &self.stats;
+            let reorderable = &self.reorderable;
+            for run in self.order.split_mut(|&i| !reorderable[i]) {
+                run.sort_by(|&i, &j| stats[i].rank().total_cmp(&stats[j].rank()));
+            }
+        }
+    }
+}
+
+/// returns true if the predicate is deterministic and cannot fail, so that
+/// changing its evaluation order never changes results or raised errors.
+/// anything else, like casts, arithmetics, functions, udfs, case-when (used by
+/// ansi guards), raise_error/assert_true and rand, is not reorderable.
+fn is_reorderable(expr: &PhysicalExprRef) -> bool {
+    let expr_any = expr.as_any();
+    let reorderable = expr_any.is::<Column>()
+        || expr_any.is::<Literal>()
+        || expr_any.is::<IsNullExpr>()
+        || expr_any.is::<IsNotNullExpr>()
+        || expr_any.is::<NotExpr>()
+        || expr_any.is::<InListExpr>()
+        || expr_any.is::<SCAndExpr>()
+        || expr_any.is::<SCOrExpr>()
+        || expr_any.is::<StringStartsWithExpr>()
+        || expr_any.is::<StringEndsWithExpr>()
+        || expr_any.is::<StringContainsExpr>()
+        || expr_any
+            .downcast_ref::<BinaryExpr>()
+            .map(|binary| {
+                matches!(
+                    binary.op(),
+                    Operator::Eq
+                        | Operator::NotEq
+                        | Operator::Lt
+                        | Operator::LtEq
+                        | Operator::Gt
+                        | Operator::GtEq
+                        | Operator::And
+                        | Operator::Or
+                        | Operator::IsDistinctFrom
+                        | Operator::IsNotDistinctFrom
+                )
+            })
+            .unwrap_or(false);
+    reorderable && expr.children().iter().all(is_reorderable)
+}
+
 /// Get pruned expr with minimal set of input columns
 fn prune_expr_cols(expr: &PhysicalExprRef) -> (PhysicalExprRef, Vec<usize>) {
     let used_cols: Rc<RefCell<HashMap<usize, usize>>> = Rc::new(RefCell::default());
@@ -471,3 +1120,131 @@ fn filter_one_pred(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::common::cached_exprs_evaluator::{
+        is_reorderable, CachedExprsEvaluator, FilterOrder, ADAPTIVE_FILTER_SAMPLED_BATCHES,
+    };
+    use arrow::array::{Array, Int32Array, Int64Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::logical_expr::Operator;
+    use datafusion::physical_expr::expressions::{BinaryExpr, CaseExpr, Column, Literal};
+    use datafusion::physical_expr::PhysicalExprRef;
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_adaptive_filter_order() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from_iter_values(0..1000))],
+        )?;
+        let pred = |op: Operator, value: i32| -> PhysicalExprRef {
+            Arc::new(BinaryExpr::new(
+                Arc::new(Column::new("a", 0)),
+                op,
+                Arc::new(Literal::new(ScalarValue::Int32(Some(value)))),
+            ))
+        };
+
+        // the first predicate filters nothing, the second filters most rows
+        let preds = vec![pred(Operator::GtEq, 0), pred(Operator::Lt, 10)];
+        let evaluator = CachedExprsEvaluator::try_new(preds.clone(), vec![])?;
+        *evaluator.filter_order.lock() =
+            FilterOrder::new(preds.iter().map(|p| is_reorderable(p)).collect());
+        for _ in 0..ADAPTIVE_FILTER_SAMPLED_BATCHES + 1 {
+            let filtered = evaluator.filter(&batch)?;
+            assert_eq!(filtered.num_rows(), 10);
+            assert_eq!(
+                filtered.column(0).as_any().downcast_ref::<Int32Array>(),
+                Some(&Int32Array::from_iter_values(0..10))
+            );
+        }
+        assert_eq!(evaluator.filter_order.lock().order, vec![1, 0]);
+
+        // predicates that may fail are never moved or moved across
+        let fallible: PhysicalExprRef = Arc::new(BinaryExpr::new(
+            Arc::new(BinaryExpr::new(
+                Arc::new(Literal::new(ScalarValue::Int32(Some(1000)))),
+                Operator::Divide,
+                Arc::new(Column::new("a", 0)),
+            )),
+            Operator::GtEq,
+            Arc::new(Literal::new(ScalarValue::Int32(Some(0)))),
+        ));
+        let preds = vec![pred(Operator::GtEq, 0), fallible, pred(Operator::Lt, 10)];
+        let mut filter_order = FilterOrder::new(preds.iter().map(|p| is_reorderable(p)).collect());
+        assert_eq!(filter_order.reorderable, vec![true, false, true]);
+        assert!(!filter_order.sampling());
+        for _ in 0..ADAPTIVE_FILTER_SAMPLED_BATCHES {
+            filter_order.update_stat(0, 1000, 1000, Duration::from_micros(10));
+            filter_order.update_stat(2, 1000, 10, Duration::from_micros(10));
+            filter_order.finish_batch();
+        }
+        assert_eq!(filter_order.order, vec![0, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_exprs_in_case_branches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..10))],
+        )?;
+        let a: PhysicalExprRef = Arc::new(Column::new("a", 0));
+        let lit = |value: i64| -> PhysicalExprRef {
+            Arc::new(Literal::new(ScalarValue::Int64(Some(value))))
+        };
+        let binary = |l: PhysicalExprRef, op: Operator, r: PhysicalExprRef| -> PhysicalExprRef {
+            Arc::new(BinaryExpr::new(l, op, r))
+        };
+
+        // case when a > 5 then (a + 1) * 2 when a > 2 then (a + 1) * 3 else a + 1 end
+        let a_plus_1 = binary(a.clone(), Operator::Plus, lit(1));
+        let case: PhysicalExprRef = Arc::new(CaseExpr::try_new(
+            None,
+            vec![
+                (
+                    binary(a.clone(), Operator::Gt, lit(5)),
+                    binary(a_plus_1.clone(), Operator::Multiply, lit(2)),
+                ),
+                (
+                    binary(a.clone(), Operator::Gt, lit(2)),
+                    binary(a_plus_1.clone(), Operator::Multiply, lit(3)),
+                ),
+            ],
+            Some(a_plus_1.clone()),
+        )?);
+
+        // a + 1 is shared by all branches and is evaluated only once
+        let metrics = ExecutionPlanMetricsSet::new();
+        let evaluator =
+            CachedExprsEvaluator::try_new_with_metrics(vec![], vec![case.clone()], &metrics, 0)?;
+        let transformed = format!("{:?}", evaluator.transformed_projection_exprs[0]);
+        assert!(transformed.starts_with("Prefetch("));
+        assert!(transformed.contains("Conditional(Cached("));
+
+        let output_schema = Arc::new(Schema::new(vec![Field::new("c", DataType::Int64, true)]));
+        let output = evaluator.filter_project(&batch, output_schema)?;
+        let expected = case.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(output.column(0).as_ref(), expected.as_ref());
+        assert_eq!(
+            output.column(0).as_any().downcast_ref::<Int64Array>(),
+            Some(&Int64Array::from(vec![1, 2, 3, 12, 15, 18, 14, 16, 18, 20]))
+        );
+
+        // evaluated once in prefetching, and hit in each branch
+        let hits = metrics
+            .clone_inner()
+            .sum_by_name("cached_expr_0_hits")
+            .map(|hits| hits.as_usize());
+        assert_eq!(hits, Some(3));
+        Ok(())
+    }
+}
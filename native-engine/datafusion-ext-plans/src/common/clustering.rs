@@ -0,0 +1,263 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::array::*;
+use arrow::datatypes::*;
+use datafusion::common::{DataFusionError, Result};
+use datafusion_ext_commons::cast::cast;
+use std::sync::Arc;
+
+/// space-filling curve used for clustering rows by multiple columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusteringMethod {
+    /// interleaves bits of all columns
+    ZOrder,
+    /// hilbert curve, which has better locality than z-order
+    Hilbert,
+}
+
+impl ClusteringMethod {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "zorder" | "z-order" => Ok(ClusteringMethod::ZOrder),
+            "hilbert" => Ok(ClusteringMethod::Hilbert),
+            _ => Err(DataFusionError::Plan(format!(
+                "unsupported clustering method: {s}"
+            ))),
+        }
+    }
+}
+
+/// computes clustering keys of rows, ordering rows by the keys (compared as
+/// bytes) clusters them along the space-filling curve over the given columns.
+pub fn compute_clustering_keys(cols: &[ArrayRef], method: ClusteringMethod) -> Result<ArrayRef> {
+    let num_rows = cols.first().map(|col| col.len()).unwrap_or(0);
+    let col_values = cols
+        .iter()
+        .map(|col| order_preserving_values(col))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut keys = BinaryBuilder::with_capacity(num_rows, num_rows * cols.len() * 8);
+    let mut coords = vec![0u64; cols.len()];
+    let mut key = vec![0u8; cols.len() * 8];
+    for row_idx in 0..num_rows {
+        for (coord, values) in coords.iter_mut().zip(&col_values) {
+            *coord = values[row_idx];
+        }
+        if method == ClusteringMethod::Hilbert && coords.len() > 1 {
+            hilbert_axes_to_transpose(&mut coords);
+        }
+        interleave_bits(&coords, &mut key);
+        keys.append_value(&key);
+    }
+    Ok(Arc::new(keys.finish()))
+}
+
+/// maps values to u64 with the same ordering, nulls are mapped to zero. only
+/// the first 8 bytes of strings/binaries are considered.
+fn order_preserving_values(col: &ArrayRef) -> Result<Vec<u64>> {
+    const SIGN: u64 = 1 << 63;
+    let map_nullable = |array: &dyn Array, f: &dyn Fn(usize) -> u64| {
+        (0..array.len())
+            .map(|i| if array.is_valid(i) { f(i) } else { 0 })
+            .collect::<Vec<_>>()
+    };
+    let prefix_to_u64 = |bytes: &[u8]| {
+        let mut prefix = [0u8; 8];
+        let len = bytes.len().min(8);
+        prefix[..len].copy_from_slice(&bytes[..len]);
+        u64::from_be_bytes(prefix)
+    };
+
+    Ok(match col.data_type() {
+        DataType::Boolean => {
+            let array = as_boolean_array(col);
+            map_nullable(array, &|i| array.value(i) as u64 + 1)
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            let casted = cast(col, &DataType::UInt64)?;
+            let array = as_primitive_array::<UInt64Type>(&casted);
+            map_nullable(array, &|i| array.value(i))
+        }
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(..) => {
+            let casted = cast(col, &DataType::Int64)?;
+            let array = as_primitive_array::<Int64Type>(&casted);
+            map_nullable(array, &|i| array.value(i) as u64 ^ SIGN)
+        }
+        DataType::Decimal128(..) => {
+            let array = as_primitive_array::<Decimal128Type>(col);
+            map_nullable(array, &|i| {
+                let value = array.value(i).clamp(i64::MIN as i128, i64::MAX as i128);
+                value as u64 ^ SIGN
+            })
+        }
+        DataType::Float32 | DataType::Float64 => {
+            let casted = cast(col, &DataType::Float64)?;
+            let array = as_primitive_array::<Float64Type>(&casted);
+            map_nullable(array, &|i| {
+                let bits = array.value(i).to_bits();
+                if bits & SIGN != 0 {
+                    !bits
+                } else {
+                    bits ^ SIGN
+                }
+            })
+        }
+        DataType::Utf8 => {
+            let array = as_string_array(col);
+            map_nullable(array, &|i| prefix_to_u64(array.value(i).as_bytes()))
+        }
+        DataType::Binary => {
+            let array = as_generic_binary_array::<i32>(col);
+            map_nullable(array, &|i| prefix_to_u64(array.value(i)))
+        }
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "clustering by column of type {other} is not supported"
+            )));
+        }
+    })
+}
+
+/// interleaves bits of coordinates from the most significant bit, the first
+/// coordinate takes the highest bit of each level.
+fn interleave_bits(coords: &[u64], output: &mut [u8]) {
+    output.fill(0);
+    let mut pos = 0;
+    for bit in (0..64).rev() {
+        for &coord in coords {
+            if coord >> bit & 1 != 0 {
+                output[pos / 8] |= 0x80 >> (pos % 8);
+            }
+            pos += 1;
+        }
+    }
+}
+
+/// transforms coordinates into the transposed hilbert index (skilling's
+/// algorithm), interleaving the transposed bits gives the hilbert index.
+fn hilbert_axes_to_transpose(x: &mut [u64]) {
+    let n = x.len();
+
+    // inverse undo excess work
+    let mut q = 1u64 << 63;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..n {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+
+    // gray encode
+    for i in 1..n {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    let mut q = 1u64 << 63;
+    while q > 1 {
+        if x[n - 1] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for xi in x.iter_mut() {
+        *xi ^= t;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::clustering::{compute_clustering_keys, ClusteringMethod};
+    use arrow::array::*;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    fn sorted_points(
+        xs: Vec<i32>,
+        ys: Vec<i32>,
+        method: ClusteringMethod,
+    ) -> Result<Vec<(i32, i32)>> {
+        let cols: Vec<ArrayRef> =
+            vec![Arc::new(Int32Array::from(xs.clone())), Arc::new(Int32Array::from(ys.clone()))];
+        let keys = compute_clustering_keys(&cols, method)?;
+        let keys = as_generic_binary_array::<i32>(&keys);
+        let mut points = (0..keys.len())
+            .map(|i| (keys.value(i), (xs[i], ys[i])))
+            .collect::<Vec<_>>();
+        points.sort();
+        Ok(points.into_iter().map(|(_, point)| point).collect())
+    }
+
+    #[test]
+    fn test_zorder() -> Result<()> {
+        let sorted = sorted_points(
+            vec![1, 1, 0, 0, 2, -1],
+            vec![1, 0, 1, 0, 0, 0],
+            ClusteringMethod::ZOrder,
+        )?;
+        assert_eq!(
+            sorted,
+            vec![(-1, 0), (0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hilbert() -> Result<()> {
+        let (xs, ys) = (0..16).map(|i| (i / 4, i % 4)).unzip();
+        let sorted = sorted_points(xs, ys, ClusteringMethod::Hilbert)?;
+
+        // adjacent points on the hilbert curve are neighbours in the grid
+        for w in sorted.windows(2) {
+            let distance = (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs();
+            assert_eq!(distance, 1, "{:?} -> {:?}", w[0], w[1]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_preserving_strings_and_floats() -> Result<()> {
+        let cols: Vec<ArrayRef> = vec![Arc::new(Float64Array::from(vec![
+            Some(1.5),
+            None,
+            Some(-2.0),
+            Some(0.0),
+        ]))];
+        let keys = compute_clustering_keys(&cols, ClusteringMethod::ZOrder)?;
+        let keys = as_generic_binary_array::<i32>(&keys);
+        assert!(keys.value(1) < keys.value(2));
+        assert!(keys.value(2) < keys.value(3));
+        assert!(keys.value(3) < keys.value(0));
+
+        let cols: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec!["b", "ab", "a"]))];
+        let keys = compute_clustering_keys(&cols, ClusteringMethod::ZOrder)?;
+        let keys = as_generic_binary_array::<i32>(&keys);
+        assert!(keys.value(2) < keys.value(1));
+        assert!(keys.value(1) < keys.value(0));
+        Ok(())
+    }
+}
@@ -13,8 +13,13 @@
 // limitations under the License.
 
 use async_trait::async_trait;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call, jni_call_static, jni_new_global_ref};
 use bytesize::ByteSize;
-use datafusion::common::Result;
+use datafusion::common::{DataFusionError, Result};
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, Gauge, MetricBuilder};
+use datafusion_ext_commons::cancellation::thread_cancellation_token;
+use jni::objects::GlobalRef;
+use jni::sys::jlong;
 use once_cell::sync::OnceCell;
 use parking_lot::{Condvar, Mutex};
 use std::sync::{Arc, Weak};
@@ -25,8 +30,12 @@ static MEM_MANAGER: OnceCell<Arc<MemManager>> = OnceCell::new();
 // never triggers waiting/spilling for consumers which use very little memory
 const MIN_TRIGGER_SIZE: usize = 1 << 24; // 16MB
 
+// acquires/releases memory from spark in chunks to reduce jni calls
+const SPARK_ACQUIRE_CHUNK_SIZE: usize = 1 << 20; // 1MB
+
 pub struct MemManager {
     total: usize,
+    options: MemManagerOptions,
     consumers: Mutex<Vec<Arc<MemConsumerInfo>>>,
     status: Mutex<MemManagerStatus>,
     cv: Condvar,
@@ -34,14 +43,20 @@ pub struct MemManager {
 
 impl MemManager {
     pub fn init(total: usize) {
+        Self::init_with_options(total, MemManagerOptions::default());
+    }
+
+    pub fn init_with_options(total: usize, options: MemManagerOptions) {
         MEM_MANAGER.get_or_init(|| {
             log::info!(
-                "mem manager initialized with total memory: {}",
+                "mem manager initialized with total memory: {}, options: {:?}",
                 ByteSize(total as u64),
+                options,
             );
 
             Arc::new(MemManager {
                 total,
+                options,
                 consumers: Mutex::default(),
                 status: Mutex::default(),
                 cv: Condvar::default(),
@@ -57,12 +72,34 @@ impl MemManager {
         self.consumers.lock().len()
     }
 
-    pub fn register_consumer(mut consumer: Arc<dyn MemConsumer>, spillable: bool) {
+    pub fn register_consumer(consumer: Arc<dyn MemConsumer>, spillable: bool) {
+        Self::register_consumer_impl(consumer, spillable, OnceCell::new());
+    }
+
+    /// registers a consumer shared by all tasks of the executor (like caches),
+    /// its memory is not acquired from spark, whose memory managers are bound to
+    /// single tasks.
+    pub fn register_executor_consumer(consumer: Arc<dyn MemConsumer>, spillable: bool) {
+        Self::register_consumer_impl(consumer, spillable, OnceCell::with_value(None));
+    }
+
+    fn register_consumer_impl(
+        mut consumer: Arc<dyn MemConsumer>,
+        spillable: bool,
+        spark_consumer: OnceCell<Option<GlobalRef>>,
+    ) {
         let consumer_info = Arc::new(MemConsumerInfo {
+            name: consumer.name().to_string(),
             status: Mutex::new(MemConsumerStatus {
                 mem_used: 0,
+                peak_mem_used: 0,
+                mem_limit: usize::MAX,
                 spillable,
+                num_spills: 0,
             }),
+            peak_mem_used_metric: OnceCell::new(),
+            spark_consumer,
+            spark_acquired: Mutex::new(0),
         });
         log::info!("mem manager registering consumer: {}", consumer.name());
 
@@ -106,7 +143,11 @@ impl MemManager {
         // remove consumer info
         for i in 0..mm_consumers.len() {
             if Arc::ptr_eq(&mm_consumers[i], &consumer_info) {
-                log::info!("mem manager deregistered consumer: {}", consumer.name());
+                log::info!(
+                    "mem manager deregistered consumer: {} (peak mem used: {})",
+                    consumer.name(),
+                    ByteSize(consumer_status.peak_mem_used as u64),
+                );
                 mm_consumers.swap_remove(i);
 
                 drop(mm_status);
@@ -116,6 +157,100 @@ impl MemManager {
         }
         unreachable!("deregistering non-registered memory consumer")
     }
+
+    /// formats memory usage of all consumers, ordered by used memory, for
+    /// diagnosing out-of-memory failures
+    pub fn dump_status(&self) -> String {
+        let consumer_statuses = self.consumer_statuses();
+        let total_used = self.status.lock().total_used;
+
+        let mut dump = format!(
+            "mem manager status: total used: {}/{}, num consumers: {}",
+            ByteSize(total_used as u64),
+            ByteSize(self.total as u64),
+            consumer_statuses.len(),
+        );
+        for (name, status) in &consumer_statuses {
+            dump.push_str(&format!(
+                "\n  {}: used={}, peak={}, limit={}, spillable={}, spills={}",
+                name,
+                ByteSize(status.mem_used as u64),
+                ByteSize(status.peak_mem_used as u64),
+                match status.mem_limit {
+                    usize::MAX => "none".to_string(),
+                    mem_limit => ByteSize(mem_limit as u64).to_string(),
+                },
+                status.spillable,
+                status.num_spills,
+            ));
+        }
+        dump
+    }
+
+    /// formats total usage and the top consumers in one line, attached to
+    /// out-of-memory errors
+    fn dump_status_compact(&self, max_consumers: usize) -> String {
+        let consumer_statuses = self.consumer_statuses();
+        let total_used = self.status.lock().total_used;
+        format!(
+            "total used: {}/{}, top consumers: [{}]",
+            ByteSize(total_used as u64),
+            ByteSize(self.total as u64),
+            consumer_statuses
+                .iter()
+                .take(max_consumers)
+                .map(|(name, status)| format!(
+                    "{}(used={}, peak={}, spills={})",
+                    name,
+                    ByteSize(status.mem_used as u64),
+                    ByteSize(status.peak_mem_used as u64),
+                    status.num_spills,
+                ))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    fn consumer_statuses(&self) -> Vec<(String, MemConsumerStatus)> {
+        let mut consumer_statuses = self
+            .consumers
+            .lock()
+            .iter()
+            .map(|consumer_info| (consumer_info.name.clone(), *consumer_info.status.lock()))
+            .collect::<Vec<_>>();
+        consumer_statuses.sort_by_key(|(_, status)| std::cmp::Reverse(status.mem_used));
+        consumer_statuses
+    }
+}
+
+impl Drop for MemConsumerInfo {
+    fn drop(&mut self) {
+        // return all memory acquired from spark
+        if let Some(Some(_)) = self.spark_consumer.get() {
+            if let Err(err) = self.update_spark_acquired(0) {
+                log::warn!("mem manager: error releasing memory to spark: {}", err);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MemManagerOptions {
+    /// if set, memory used by consumers is also acquired from spark's task
+    /// memory manager, so that native and jvm operators share the same budget
+    pub spark_managed: bool,
+
+    /// max fraction of total memory a single consumer can use before spilling
+    pub operator_mem_fraction: f64,
+}
+
+impl Default for MemManagerOptions {
+    fn default() -> Self {
+        Self {
+            spark_managed: false,
+            operator_mem_fraction: 1.0,
+        }
+    }
 }
 
 #[derive(Default, Clone, Copy)]
@@ -143,13 +278,82 @@ impl MemManagerStatus {
 
 #[derive(Debug)]
 pub struct MemConsumerInfo {
+    name: String,
     status: Mutex<MemConsumerStatus>,
+    spark_consumer: OnceCell<Option<GlobalRef>>,
+    spark_acquired: Mutex<usize>,
+    peak_mem_used_metric: OnceCell<Gauge>,
+}
+
+impl MemConsumerInfo {
+    /// returns the task's NativeMemoryConsumer, or None if memory is not managed
+    /// by spark (including running on driver side)
+    fn spark_consumer(&self) -> Result<Option<&GlobalRef>> {
+        let spark_consumer = self.spark_consumer.get_or_try_init(|| {
+            if !MemManager::get().options.spark_managed
+                || !is_jni_bridge_inited()
+                || jni_call_static!(JniBridge.isDriverSide() -> bool)?
+            {
+                return Ok::<_, DataFusionError>(None);
+            }
+            let spark_consumer =
+                jni_call_static!(JniBridge.getTaskNativeMemoryConsumer() -> JObject)?;
+            Ok(Some(jni_new_global_ref!(spark_consumer.as_obj())?))
+        })?;
+        Ok(spark_consumer.as_ref())
+    }
+
+    /// acquires/releases memory from spark to cover mem_used, returns true if
+    /// spark cannot grant enough memory
+    fn update_spark_acquired(&self, mem_used: usize) -> Result<bool> {
+        let spark_consumer = match self.spark_consumer()? {
+            Some(spark_consumer) => spark_consumer,
+            None => return Ok(false),
+        };
+        let mut spark_acquired = self.spark_acquired.lock();
+
+        if mem_used > *spark_acquired {
+            let required = (mem_used - *spark_acquired + SPARK_ACQUIRE_CHUNK_SIZE - 1)
+                / SPARK_ACQUIRE_CHUNK_SIZE
+                * SPARK_ACQUIRE_CHUNK_SIZE;
+            let acquired = jni_call!(BlazeNativeMemoryConsumer(spark_consumer.as_obj())
+                .acquire(required as jlong) -> jlong)? as usize;
+            *spark_acquired += acquired;
+        } else if *spark_acquired - mem_used >= SPARK_ACQUIRE_CHUNK_SIZE || mem_used == 0 {
+            let excess = if mem_used == 0 {
+                *spark_acquired
+            } else {
+                (*spark_acquired - mem_used) / SPARK_ACQUIRE_CHUNK_SIZE * SPARK_ACQUIRE_CHUNK_SIZE
+            };
+            if excess > 0 {
+                jni_call!(BlazeNativeMemoryConsumer(spark_consumer.as_obj())
+                    .release(excess as jlong) -> ())?;
+                *spark_acquired -= excess;
+            }
+        }
+        Ok(mem_used > *spark_acquired)
+    }
+
+    /// returns true if spark requested native consumers to spill
+    fn take_spark_spill_request(&self) -> Result<bool> {
+        Ok(match self.spark_consumer()? {
+            Some(spark_consumer) => {
+                jni_call!(BlazeNativeMemoryConsumer(spark_consumer.as_obj())
+                    .takeRequestedSpillSize() -> jlong)?
+                    > 0
+            }
+            None => false,
+        })
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 struct MemConsumerStatus {
     mem_used: usize,
+    peak_mem_used: usize,
+    mem_limit: usize,
     spillable: bool,
+    num_spills: usize,
 }
 
 #[async_trait]
@@ -173,6 +377,25 @@ pub trait MemConsumer: Send + Sync {
         mem_used as f64 / (total as f64 / num_consumers as f64)
     }
 
+    fn peak_mem_used(&self) -> usize {
+        self.consumer_info().status.lock().peak_mem_used
+    }
+
+    /// reports peak memory usage of this consumer to the operator's metrics
+    fn register_peak_mem_used_metric(&self, metrics: &ExecutionPlanMetricsSet, partition: usize) {
+        let consumer_info = self.consumer_info();
+        let metric = consumer_info
+            .peak_mem_used_metric
+            .get_or_init(|| MetricBuilder::new(metrics).gauge("mem_peak_used", partition));
+        metric.set(consumer_info.status.lock().peak_mem_used);
+    }
+
+    /// limits memory usage of this consumer, spillable consumers are forced to
+    /// spill when exceeding the limit
+    fn set_mem_limit(&self, mem_limit: usize) {
+        self.consumer_info().status.lock().mem_limit = mem_limit;
+    }
+
     fn set_spillable(&self, spillable: bool) {
         let consumer_info = self.consumer_info();
         let mut consumer_status = consumer_info.status.lock();
@@ -232,6 +455,7 @@ async fn update_consumer_mem_used_with_custom_updater(
     let mm = MemManager::get();
     let consumer_info = consumer.consumer_info();
     let total = mm.total;
+    let operator_mem_max = (total as f64 * mm.options.operator_mem_fraction) as usize;
 
     #[derive(Clone, Copy, PartialEq)]
     enum Operation {
@@ -240,13 +464,22 @@ async fn update_consumer_mem_used_with_custom_updater(
         Nothing, // do nothing
     }
 
-    let (mem_used, total_used, operation) = {
+    let (mem_used, total_used, spillable, operation) = {
         let mut mm_status = mm.status.lock();
         let mut consumer_status = consumer_info.status.lock();
 
         // update consumer info
         let (old_used, new_used) = updater(&mut consumer_status);
         let spillable = consumer_status.spillable;
+        let mem_limit = consumer_status.mem_limit.min(operator_mem_max);
+
+        // update peak memory usage
+        if new_used > consumer_status.peak_mem_used {
+            consumer_status.peak_mem_used = new_used;
+            if let Some(metric) = consumer_info.peak_mem_used_metric.get() {
+                metric.set(new_used);
+            }
+        }
         let diff_used = new_used as isize - old_used as isize;
 
         // update mm status
@@ -260,36 +493,50 @@ async fn update_consumer_mem_used_with_custom_updater(
 
         // consumer is unspillable/shrinking, no need to wait or spill
         if !spillable || new_used < old_used {
-            return Ok(());
-        }
-
-        // unlock
-        let num_spillables = mm_status.num_spillables;
-        let mem_spillables = mm_status.mem_spillables;
-        drop(consumer_status);
-        drop(mm_status);
-
-        let consumer_mem_max = (total - (total_used - mem_spillables)) / num_spillables;
-        let consumer_mem_min = consumer_mem_max / 8;
-
-        let total_overflowed = total_used > total;
-        let consumer_overflowed = new_used > consumer_mem_max;
-        let operation = if (total_overflowed || consumer_overflowed)
-            && new_used > MIN_TRIGGER_SIZE
-            && new_used > old_used
-        {
-            if spillable && new_used > consumer_mem_min {
-                Operation::Spill
-            } else {
-                Operation::Wait
-            }
+            (new_used, total_used, spillable, Operation::Nothing)
         } else {
-            Operation::Nothing
-        };
-        (new_used, total_used, operation)
+            // unlock
+            let num_spillables = mm_status.num_spillables;
+            let mem_spillables = mm_status.mem_spillables;
+            drop(consumer_status);
+            drop(mm_status);
+
+            let consumer_mem_max = (total - (total_used - mem_spillables)) / num_spillables;
+            let consumer_mem_min = consumer_mem_max / 8;
+
+            let total_overflowed = total_used > total;
+            let consumer_overflowed = new_used > consumer_mem_max || new_used > mem_limit;
+            let operation = if (total_overflowed || consumer_overflowed)
+                && new_used > MIN_TRIGGER_SIZE
+                && new_used > old_used
+            {
+                if spillable && new_used > consumer_mem_min {
+                    Operation::Spill
+                } else {
+                    Operation::Wait
+                }
+            } else {
+                Operation::Nothing
+            };
+            (new_used, total_used, spillable, operation)
+        }
     };
     let mut operation = operation;
 
+    // acquire/release memory from spark, spill if spark cannot grant enough
+    // memory or other spark consumers requested us to spill
+    if mm.options.spark_managed {
+        let spark_insufficient = consumer_info
+            .update_spark_acquired(mem_used)
+            .map_err(|err| oom_error(consumer, err))?;
+        if operation == Operation::Nothing && spillable && mem_used > MIN_TRIGGER_SIZE {
+            let spark_spill_requested = consumer_info.take_spark_spill_request()?;
+            if spark_insufficient || spark_spill_requested {
+                operation = Operation::Spill;
+            }
+        }
+    }
+
     // trigger waiting for resources
     if operation == Operation::Wait {
         const WAIT_TIME: Duration = Duration::from_millis(10000);
@@ -317,12 +564,38 @@ async fn update_consumer_mem_used_with_custom_updater(
             ByteSize(total_used as u64),
             ByteSize(mm.total as u64),
         );
-        consumer.spill().await?;
+        consumer_info.status.lock().num_spills += 1;
+        consumer
+            .spill()
+            .await
+            .map_err(|err| oom_error(consumer, err))?;
         return Ok(());
     }
     Ok(())
 }
 
+/// logs memory usage of all consumers and returns an error with a compact
+/// report, called when a consumer fails reserving memory
+fn oom_error(consumer: &dyn MemConsumer, err: DataFusionError) -> DataFusionError {
+    // failures caused by task cancellation are not real ooms
+    if thread_cancellation_token().is_cancelled() {
+        return err;
+    }
+    let mm = MemManager::get();
+    log::error!(
+        "mem manager: consumer {} failed reserving memory: {}\n{}",
+        consumer.name(),
+        err,
+        mm.dump_status(),
+    );
+    DataFusionError::ResourcesExhausted(format!(
+        "{} failed reserving memory ({}): {}",
+        consumer.name(),
+        mm.dump_status_compact(3),
+        err,
+    ))
+}
+
 #[allow(unused)]
 fn print_stats(by: &dyn MemConsumer, old_used: usize, new_used: usize) {
     // print log per every 100MBs
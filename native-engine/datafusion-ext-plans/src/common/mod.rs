@@ -19,14 +19,17 @@ use arrow::record_batch::{RecordBatch, RecordBatchOptions};
 use datafusion::common::Result;
 
 pub mod batch_statisitcs;
+pub mod broadcast_cache;
 pub mod bytes_arena;
 pub mod cached_exprs_evaluator;
+pub mod clustering;
 pub mod column_pruning;
 pub mod memory_manager;
 pub mod onheap_spill;
 pub mod output;
 pub mod rdxsort;
 pub mod slim_bytes;
+pub mod spill_manager;
 
 pub struct BatchTaker<'a>(pub &'a RecordBatch);
 
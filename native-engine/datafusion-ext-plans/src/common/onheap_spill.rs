@@ -12,72 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use blaze_jni_bridge::{
-    is_jni_bridge_inited, jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref,
-};
+use crate::common::spill_manager::Spill;
+use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_direct_byte_buffer, jni_new_global_ref};
 use datafusion::common::Result;
-use datafusion::parquet::file::reader::Length;
 use jni::objects::GlobalRef;
-use jni::sys::{jboolean, jlong, JNI_TRUE};
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use jni::sys::jlong;
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::sync::Arc;
 
-pub trait Spill: Send + Sync {
-    fn complete(&self) -> Result<()>;
-    fn get_disk_usage(&self) -> Result<u64>;
-    fn get_buf_reader(&self) -> BufReader<Box<dyn Read + Send>>;
-    fn get_buf_writer(&self) -> BufWriter<Box<dyn Write + Send>>;
-}
-
-pub fn try_new_spill() -> Result<Box<dyn Spill>> {
-    if !is_jni_bridge_inited()
-        || jni_call_static!(JniBridge.isDriverSide() -> jboolean)? == JNI_TRUE
-    {
-        Ok(Box::new(FileSpill::try_new()?))
-    } else {
-        Ok(Box::new(OnHeapSpill::try_new()?))
-    }
-}
-
-/// A spill structure which write data to temporary files
-/// used in driver side
-struct FileSpill(File);
-impl FileSpill {
-    fn try_new() -> Result<Self> {
-        let file = tempfile::tempfile()?;
-        Ok(Self(file))
-    }
-}
-
-impl Spill for FileSpill {
-    fn complete(&self) -> Result<()> {
-        let mut file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
-        file_cloned.sync_data()?;
-        file_cloned.rewind()?;
-        Ok(())
-    }
-
-    fn get_disk_usage(&self) -> Result<u64> {
-        Ok(self.0.len())
-    }
-
-    fn get_buf_reader(&self) -> BufReader<Box<dyn Read + Send>> {
-        let file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
-        BufReader::with_capacity(65536, Box::new(file_cloned))
-    }
-
-    fn get_buf_writer(&self) -> BufWriter<Box<dyn Write + Send>> {
-        let file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
-        BufWriter::with_capacity(65536, Box::new(file_cloned))
-    }
-}
-
 /// A spill structure which cooperates with BlazeOnHeapSpillManager
 /// used in executor side
-struct OnHeapSpill(Arc<RawOnHeapSpill>);
+pub(crate) struct OnHeapSpill(Arc<RawOnHeapSpill>);
 impl OnHeapSpill {
-    fn try_new() -> Result<Self> {
+    pub(crate) fn try_new() -> Result<Self> {
         let hsm = jni_call_static!(JniBridge.getTaskOnHeapSpillManager() -> JObject)?;
         let spill_id = jni_call!(BlazeOnHeapSpillManager(hsm.as_obj()).newSpill() -> i32)?;
 
@@ -13,25 +13,24 @@
 // limitations under the License.
 
 use std::future::Future;
-use std::io::{Cursor, Write};
 use std::panic::AssertUnwindSafe;
 use std::sync::{Arc, Weak};
 
 use crate::common::memory_manager::{MemConsumer, MemManager};
-use crate::common::onheap_spill::try_new_spill;
+use crate::common::spill_manager::SpillManager;
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
-use blaze_jni_bridge::is_task_running;
 use datafusion::common::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::ScopedTimerGuard;
 use datafusion::physical_plan::stream::RecordBatchReceiverStream;
 use datafusion::physical_plan::SendableRecordBatchStream;
-use datafusion_ext_commons::io::{read_one_batch, write_one_batch};
-use futures::{FutureExt, StreamExt, TryFutureExt};
+use datafusion_ext_commons::cancellation::{task_cancellation_token, task_deadline};
+use futures::{FutureExt, StreamExt};
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
 use tokio::sync::mpsc::Sender;
+use tracing::Instrument;
 
 fn working_senders() -> &'static Mutex<Vec<Weak<WrappedRecordBatchSender>>> {
     static WORKING_SENDERS: OnceCell<Mutex<Vec<Weak<WrappedRecordBatchSender>>>> = OnceCell::new();
@@ -98,25 +97,53 @@ pub fn output_with_sender<Fut: Future<Output = Result<()>> + Send>(
     let mut stream_builder = RecordBatchReceiverStream::builder(output_schema.clone(), 1);
     let sender = stream_builder.tx().clone();
     let err_sender = sender.clone();
+    let cancellation_token = task_cancellation_token(&task_context);
+    let task_deadline = task_deadline(&task_context);
 
-    stream_builder.spawn(async move {
+    let output_future = async move {
         let wrapped = WrappedRecordBatchSender::new(task_context, sender);
+        let output_cancellation_token = cancellation_token.clone();
+        let operator_timeout = task_deadline.operator_timeout();
+        let operator_deadline = async move {
+            match operator_timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => futures::future::pending().await,
+            }
+        };
         let result = AssertUnwindSafe(async move {
-            let task_running = is_task_running();
-            if !task_running {
+            if output_cancellation_token.is_cancelled() {
                 panic!(
                     "output_with_sender[{}] canceled due to task finished/killed",
                     desc
                 );
             }
-            output(wrapped)
-                .unwrap_or_else(|err| {
+
+            // stop outputting once the task is cancelled, resources held by the
+            // output future are released on dropping
+            tokio::select! {
+                result = output(wrapped) => result.unwrap_or_else(|err| {
                     panic!(
                         "output_with_sender[{}]: output() returns error: {}",
                         desc, err
                     );
-                })
-                .await
+                }),
+                _ = output_cancellation_token.cancelled() => panic!(
+                    "output_with_sender[{}] canceled due to task finished/killed",
+                    desc
+                ),
+
+                // abort the whole task once the operator exceeds its deadline
+                _ = operator_deadline => {
+                    let message = format!(
+                        "native operator {} timed out: exceeded deadline of {:?}",
+                        desc,
+                        operator_timeout.unwrap_or_default(),
+                    );
+                    task_deadline.set_exceeded(message.clone());
+                    output_cancellation_token.cancel();
+                    panic!("output_with_sender[{}]: {}", desc, message);
+                }
+            }
         })
         .catch_unwind()
         .await
@@ -131,8 +158,7 @@ pub fn output_with_sender<Fut: Future<Output = Result<()>> + Send>(
             let _ = err_sender.send(Err(err)).await;
 
             // panic current spawn
-            let task_running = is_task_running();
-            if !task_running {
+            if cancellation_token.is_cancelled() {
                 panic!(
                     "output_with_sender[{}] canceled due to task finished/killed",
                     desc
@@ -141,12 +167,17 @@ pub fn output_with_sender<Fut: Future<Output = Result<()>> + Send>(
                 panic!("output_with_sender[{}] error: {}", desc, err_message);
             }
         }
-    });
+    };
+
+    // inputs executed inside the output future are traced as children spans
+    let span = tracing::info_span!("native_operator", name = desc);
+    stream_builder.spawn(output_future.instrument(span));
     Ok(stream_builder.build())
 }
 
 pub fn output_bufferable_with_spill(
     mem_consumer: Arc<dyn MemConsumer>,
+    spill_manager: SpillManager,
     task_context: Arc<TaskContext>,
     mut stream: SendableRecordBatchStream,
 ) -> Result<SendableRecordBatchStream> {
@@ -162,23 +193,17 @@ pub fn output_bufferable_with_spill(
                 // to receive all of its outputs and release all memory.
                 // outputs can be read from spill later.
                 if MemManager::get().num_consumers() > 1 && mem_consumer.mem_used_percent() > 0.8 {
-                    let spill = try_new_spill()?;
-                    let mut spill_writer = spill.get_buf_writer();
+                    let mut spill_writer = spill_manager.new_spill_writer()?;
 
                     // write all batches to spill
                     while let Some(batch) = stream.next().await.transpose()? {
-                        let mut buf = vec![];
-                        write_one_batch(&batch, &mut Cursor::new(&mut buf), true, None)?;
-                        spill_writer.write_all(&buf)?;
+                        spill_writer.write_batch(&batch)?;
                     }
-                    spill_writer.flush()?;
-                    spill.complete()?;
+                    let spill = spill_writer.finish()?;
 
                     // read all batches from spill and output
-                    let mut spill_reader = spill.get_buf_reader();
-                    while let Some(batch) =
-                        read_one_batch(&mut spill_reader, Some(output_schema.clone()), true)?
-                    {
+                    let mut spill_reader = SpillManager::read_spill(spill.as_ref());
+                    while let Some(batch) = spill_reader.read_batch(output_schema.clone())? {
                         sender.send(Ok(batch), None).await;
                     }
                     return Ok(());
@@ -69,9 +69,42 @@ pub fn radix_sort_u16_by<T>(array: &mut [T], key: impl Fn(&T) -> u16) -> Vec<usi
     }
 }
 
+/// stable lsd radix sort by u64 keys with 8-bit digits, passes on digits
+/// shared by all keys are skipped
+pub fn radix_sort_u64_by<T: Copy>(array: &mut Vec<T>, key: impl Fn(&T) -> u64) {
+    // count all digits in one pass
+    let mut counts = vec![[0usize; 256]; 8];
+    for item in array.iter() {
+        let k = key(item);
+        for (digit, digit_counts) in counts.iter_mut().enumerate() {
+            digit_counts[(k >> (digit * 8)) as u8 as usize] += 1;
+        }
+    }
+
+    let mut buf = array.clone();
+    for (digit, digit_counts) in counts.iter().enumerate() {
+        if digit_counts.iter().any(|&count| count == array.len()) {
+            continue;
+        }
+
+        let mut offsets = [0usize; 256];
+        let mut beg = 0;
+        for (offset, &count) in offsets.iter_mut().zip(digit_counts.iter()) {
+            *offset = beg;
+            beg += count;
+        }
+        for item in array.iter() {
+            let d = (key(item) >> (digit * 8)) as u8 as usize;
+            buf[offsets[d]] = *item;
+            offsets[d] += 1;
+        }
+        std::mem::swap(array, &mut buf);
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::common::rdxsort::radix_sort_u16_by;
+    use crate::common::rdxsort::{radix_sort_u16_by, radix_sort_u64_by};
     use rand::Rng;
 
     #[test]
@@ -107,4 +140,27 @@ mod test {
 
         assert_eq!(array1, array2);
     }
+
+    #[test]
+    fn fuzzytest_u64() {
+        for n in [0, 1, 10, 1000, 100000] {
+            let mut array = vec![];
+            for i in 0..n {
+                // small keys share most of the digits
+                let key = match i % 3 {
+                    0 => rand::thread_rng().gen::<u64>(),
+                    _ => rand::thread_rng().gen::<u8>() as u64,
+                };
+                array.push((key, i));
+            }
+
+            let mut array1 = array.clone();
+            radix_sort_u64_by(&mut array1, |(key, _)| *key);
+
+            let mut array2 = array.clone();
+            array2.sort_by_key(|(key, _)| *key); // stable
+
+            assert_eq!(array1, array2);
+        }
+    }
 }
@@ -0,0 +1,257 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::onheap_spill::OnHeapSpill;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static, jni_get_string};
+use datafusion::common::{DataFusionError, Result};
+use datafusion::parquet::file::reader::Length;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder,
+};
+use datafusion_ext_commons::io::{read_one_batch, write_one_batch};
+use jni::sys::{jboolean, JNI_TRUE};
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+use once_cell::sync::OnceCell;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub trait Spill: Send + Sync {
+    fn complete(&self) -> Result<()>;
+    fn get_disk_usage(&self) -> Result<u64>;
+    fn get_buf_reader(&self) -> BufReader<Box<dyn Read + Send>>;
+    fn get_buf_writer(&self) -> BufWriter<Box<dyn Write + Send>>;
+}
+
+pub fn try_new_spill() -> Result<Box<dyn Spill>> {
+    if !is_jni_bridge_inited()
+        || jni_call_static!(JniBridge.isDriverSide() -> jboolean)? == JNI_TRUE
+    {
+        Ok(Box::new(FileSpill::try_new()?))
+    } else {
+        Ok(Box::new(OnHeapSpill::try_new()?))
+    }
+}
+
+/// Creates spills of an operator and reports uniform spill metrics.
+/// data written through SpillWriter is lz4 compressed, batches are encoded
+/// with batch_serde.
+#[derive(Clone)]
+pub struct SpillManager {
+    baseline_metrics: BaselineMetrics,
+    spill_count: Count,
+}
+
+impl SpillManager {
+    pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            baseline_metrics: BaselineMetrics::new(metrics, partition),
+            spill_count: MetricBuilder::new(metrics).spill_count(partition),
+        }
+    }
+
+    pub fn new_spill_writer(&self) -> Result<SpillWriter> {
+        let spill = try_new_spill()?;
+        let writer = FrameEncoder::new(spill.get_buf_writer());
+        Ok(SpillWriter {
+            spill,
+            writer,
+            spill_manager: self.clone(),
+        })
+    }
+
+    pub fn read_spill(spill: &dyn Spill) -> SpillReader {
+        SpillReader(FrameDecoder::new(spill.get_buf_reader()))
+    }
+
+    /// records a completed spill which is written with its own format
+    pub fn record_spill(&self, spill: &dyn Spill) -> Result<()> {
+        self.spill_count.add(1);
+        self.baseline_metrics
+            .record_spill(spill.get_disk_usage()? as usize);
+        Ok(())
+    }
+}
+
+pub struct SpillWriter {
+    spill: Box<dyn Spill>,
+    writer: FrameEncoder<BufWriter<Box<dyn Write + Send>>>,
+    spill_manager: SpillManager,
+}
+
+impl SpillWriter {
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let mut buf = vec![];
+        write_one_batch(batch, &mut Cursor::new(&mut buf), false, None)?;
+        self.writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// flushes all written data and completes the spill
+    pub fn finish(self) -> Result<Box<dyn Spill>> {
+        let mut buf_writer = self
+            .writer
+            .finish()
+            .map_err(|err| DataFusionError::Execution(format!("{}", err)))?;
+        buf_writer.flush()?;
+        drop(buf_writer);
+
+        self.spill.complete()?;
+        self.spill_manager.record_spill(self.spill.as_ref())?;
+        Ok(self.spill)
+    }
+}
+
+impl Write for SpillWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct SpillReader(FrameDecoder<BufReader<Box<dyn Read + Send>>>);
+
+impl SpillReader {
+    pub fn read_batch(&mut self, schema: SchemaRef) -> Result<Option<RecordBatch>> {
+        read_one_batch(&mut self.0, Some(schema), false)
+    }
+}
+
+impl Read for SpillReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+/// A spill structure which write data to temporary files
+/// used in driver side
+struct FileSpill(File);
+impl FileSpill {
+    fn try_new() -> Result<Self> {
+        let file = tempfile::tempfile_in(next_local_dir())?;
+        Ok(Self(file))
+    }
+}
+
+impl Spill for FileSpill {
+    fn complete(&self) -> Result<()> {
+        let mut file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
+        file_cloned.sync_data()?;
+        file_cloned.rewind()?;
+        Ok(())
+    }
+
+    fn get_disk_usage(&self) -> Result<u64> {
+        Ok(self.0.len())
+    }
+
+    fn get_buf_reader(&self) -> BufReader<Box<dyn Read + Send>> {
+        let file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
+        BufReader::with_capacity(65536, Box::new(file_cloned))
+    }
+
+    fn get_buf_writer(&self) -> BufWriter<Box<dyn Write + Send>> {
+        let file_cloned = self.0.try_clone().expect("File.try_clone() returns error");
+        BufWriter::with_capacity(65536, Box::new(file_cloned))
+    }
+}
+
+/// selects a spark local dir in round-robin order, falls back to system temp
+/// dir if no local dirs are configured
+fn next_local_dir() -> PathBuf {
+    static LOCAL_DIRS: OnceCell<Vec<PathBuf>> = OnceCell::new();
+    static NEXT_LOCAL_DIR_IDX: AtomicUsize = AtomicUsize::new(0);
+
+    let local_dirs = LOCAL_DIRS.get_or_init(|| {
+        let local_dirs = get_spark_local_dirs()
+            .unwrap_or_else(|err| {
+                log::warn!("error getting spark local dirs: {}", err);
+                vec![]
+            })
+            .into_iter()
+            .filter(|dir| dir.is_dir())
+            .collect::<Vec<_>>();
+        log::info!("spill manager using local dirs: {:?}", local_dirs);
+        local_dirs
+    });
+    if local_dirs.is_empty() {
+        return std::env::temp_dir();
+    }
+    let idx = NEXT_LOCAL_DIR_IDX.fetch_add(1, Ordering::Relaxed);
+    local_dirs[idx % local_dirs.len()].clone()
+}
+
+fn get_spark_local_dirs() -> Result<Vec<PathBuf>> {
+    if !is_jni_bridge_inited() {
+        return Ok(vec![]);
+    }
+    let local_dirs = jni_call_static!(JniBridge.getLocalDirs() -> JObject)?;
+    let local_dirs = jni_get_string!(local_dirs.as_obj().into())?;
+    Ok(local_dirs
+        .split(',')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::spill_manager::{Spill, SpillManager};
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+    use datafusion_ext_commons::io::{read_len, write_len};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_spill_manager() -> Result<()> {
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            (
+                "a",
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])) as ArrayRef,
+                true,
+            ),
+            (
+                "b",
+                Arc::new(StringArray::from(vec![Some("x"), Some("y"), None])) as ArrayRef,
+                true,
+            ),
+        ])?;
+
+        let metrics = ExecutionPlanMetricsSet::new();
+        let spill_manager = SpillManager::new(&metrics, 0);
+        let mut writer = spill_manager.new_spill_writer()?;
+        writer.write_batch(&batch)?;
+        write_len(12345, &mut writer)?;
+        writer.write_batch(&batch)?;
+        let spill = writer.finish()?;
+
+        let mut reader = SpillManager::read_spill(spill.as_ref());
+        assert_eq!(reader.read_batch(batch.schema())?, Some(batch.clone()));
+        assert_eq!(read_len(&mut reader)?, 12345);
+        assert_eq!(reader.read_batch(batch.schema())?, Some(batch.clone()));
+        assert_eq!(reader.read_batch(batch.schema())?, None);
+
+        assert_eq!(metrics.clone_inner().spill_count(), Some(1));
+        assert!(spill.get_disk_usage()? > 0);
+        Ok(())
+    }
+}
@@ -15,44 +15,216 @@
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
 
+use arrow::ipc::writer::FileWriter;
 use arrow::record_batch::RecordBatch;
 use arrow::util::pretty::pretty_format_batches;
 use datafusion::error::DataFusionError;
 use datafusion::error::Result;
 use datafusion::execution::context::TaskContext;
+use datafusion::parquet::arrow::ArrowWriter;
 use datafusion::physical_expr::PhysicalSortExpr;
 
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
 
 use datafusion::physical_plan::{
-    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    displayable, DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
     SendableRecordBatchStream, Statistics,
 };
 
 use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 
 use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt::Formatter;
+use std::fs::File;
+use std::path::PathBuf;
 
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugDumpFormat {
+    Ipc,
+    Parquet,
+}
+
+/// dumps every batch into a separated file named
+/// `{dir}/{debug_id}-{partition}-{batch_idx}.{arrow|parquet}`
+#[derive(Debug, Clone)]
+pub struct DebugDump {
+    pub format: DebugDumpFormat,
+    pub dir: String,
+}
+
+impl DebugDump {
+    fn dump_batch(
+        &self,
+        debug_id: &str,
+        partition: usize,
+        batch_idx: usize,
+        batch: &RecordBatch,
+    ) -> Result<PathBuf> {
+        let extension = match self.format {
+            DebugDumpFormat::Ipc => "arrow",
+            DebugDumpFormat::Parquet => "parquet",
+        };
+        std::fs::create_dir_all(&self.dir)?;
+        let path = PathBuf::from(&self.dir).join(format!(
+            "{}-{}-{}.{}",
+            debug_id, partition, batch_idx, extension
+        ));
+        let file = File::create(&path)?;
+
+        match self.format {
+            DebugDumpFormat::Ipc => {
+                let mut writer = FileWriter::try_new(file, &batch.schema())?;
+                writer.write(batch)?;
+                writer.finish()?;
+            }
+            DebugDumpFormat::Parquet => {
+                let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+                writer.write(batch)?;
+                writer.close()?;
+            }
+        }
+        Ok(path)
+    }
+}
+
+// max total size of captured batches, the oldest batches are dropped if exceeded
+const MAX_CAPTURED_MEM_SIZE: usize = 256 * 1024 * 1024;
+
+static DEBUG_CAPTURES: Lazy<Mutex<DebugCaptures>> =
+    Lazy::new(|| Mutex::new(DebugCaptures::default()));
+
+/// batches captured by DebugExec in capture mode, keyed by debug id. captured
+/// batches are kept in memory for inspection (like the flight debug server).
+#[derive(Default)]
+pub struct DebugCaptures {
+    captures: BTreeMap<String, (SchemaRef, VecDeque<RecordBatch>)>,
+    capture_order: VecDeque<String>,
+    mem_size: usize,
+}
+
+impl DebugCaptures {
+    pub fn get() -> &'static Mutex<DebugCaptures> {
+        &DEBUG_CAPTURES
+    }
+
+    pub fn capture(&mut self, debug_id: &str, batch: RecordBatch) {
+        self.mem_size += batch.get_array_memory_size();
+        self.captures
+            .entry(debug_id.to_string())
+            .or_insert_with(|| (batch.schema(), VecDeque::new()))
+            .1
+            .push_back(batch);
+        self.capture_order.push_back(debug_id.to_string());
+
+        while self.mem_size > MAX_CAPTURED_MEM_SIZE {
+            let Some(oldest_id) = self.capture_order.pop_front() else {
+                break;
+            };
+            if let Some((_, batches)) = self.captures.get_mut(&oldest_id) {
+                if let Some(batch) = batches.pop_front() {
+                    self.mem_size -= batch.get_array_memory_size();
+                }
+            }
+        }
+    }
+
+    /// returns (debug_id, schema, num_rows) of all captures
+    pub fn list(&self) -> Vec<(String, SchemaRef, usize)> {
+        self.captures
+            .iter()
+            .map(|(debug_id, (schema, batches))| {
+                let num_rows = batches.iter().map(|batch| batch.num_rows()).sum();
+                (debug_id.clone(), schema.clone(), num_rows)
+            })
+            .collect()
+    }
+
+    pub fn batches(&self, debug_id: &str) -> Option<(SchemaRef, Vec<RecordBatch>)> {
+        let (schema, batches) = self.captures.get(debug_id)?;
+        Some((schema.clone(), batches.iter().cloned().collect()))
+    }
+}
+
 #[derive(Debug)]
 pub struct DebugExec {
     input: Arc<dyn ExecutionPlan>,
     debug_id: String,
+    dump: Option<DebugDump>,
+    capture: bool,
     metrics: ExecutionPlanMetricsSet,
 }
 
 impl DebugExec {
-    pub fn new(input: Arc<dyn ExecutionPlan>, debug_id: String) -> Self {
+    pub fn new(input: Arc<dyn ExecutionPlan>, debug_id: String, dump: Option<DebugDump>) -> Self {
         Self {
             input,
             debug_id,
+            dump,
+            capture: false,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    /// captures output batches into `DebugCaptures` instead of logging them
+    pub fn with_capture(mut self) -> Self {
+        self.capture = true;
+        self
+    }
+
+    /// wraps operators with the given name (like `SortExec`) with DebugExec in
+    /// capture mode, debug ids are `{debug_id_prefix}-{operator_name}-{index}`
+    pub fn wrap_matching(
+        plan: Arc<dyn ExecutionPlan>,
+        operator_name: &str,
+        debug_id_prefix: &str,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        fn wrap(
+            plan: Arc<dyn ExecutionPlan>,
+            operator_name: &str,
+            debug_id_prefix: &str,
+            num_matched: &mut usize,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            let children = plan.children();
+            let plan = if children.is_empty() {
+                plan
+            } else {
+                let new_children = children
+                    .into_iter()
+                    .map(|child| wrap(child, operator_name, debug_id_prefix, num_matched))
+                    .collect::<Result<Vec<_>>>()?;
+                plan.with_new_children(new_children)?
+            };
+            let one_line = displayable(plan.as_ref()).one_line().to_string();
+            let name = one_line
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default();
+            if name != operator_name {
+                return Ok(plan);
+            }
+            let debug_id = format!("{debug_id_prefix}-{operator_name}-{num_matched}");
+            *num_matched += 1;
+            Ok(Arc::new(
+                DebugExec::new(plan, debug_id, None).with_capture(),
+            ))
+        }
+        wrap(plan, operator_name, debug_id_prefix, &mut 0)
+    }
+
+    pub fn debug_id(&self) -> &str {
+        &self.debug_id
+    }
+
+    pub fn dump(&self) -> Option<&DebugDump> {
+        self.dump.as_ref()
+    }
 }
 
 impl DisplayAs for DebugExec {
@@ -92,10 +264,14 @@ impl ExecutionPlan for DebugExec {
                 "DebugExec expects one children".to_string(),
             ));
         }
-        Ok(Arc::new(DebugExec::new(
-            self.input.clone(),
-            self.debug_id.clone(),
-        )))
+        Ok(Arc::new(DebugExec {
+            capture: self.capture,
+            ..DebugExec::new(
+                children[0].clone(),
+                self.debug_id.clone(),
+                self.dump.clone(),
+            )
+        }))
     }
 
     fn execute(
@@ -105,11 +281,21 @@ impl ExecutionPlan for DebugExec {
     ) -> Result<SendableRecordBatchStream> {
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
         let input = self.input.execute(partition, context)?;
+        log::info!(
+            "DebugExec[{}](partition={}) schema: {:?}",
+            self.debug_id,
+            partition,
+            input.schema(),
+        );
 
         Ok(Box::pin(DebugStream {
             partition,
             input,
             debug_id: self.debug_id.clone(),
+            dump: self.dump.clone(),
+            capture: self.capture,
+            num_batches: 0,
+            num_rows: 0,
             metrics: Arc::new(baseline_metrics),
         }))
     }
@@ -127,6 +313,10 @@ struct DebugStream {
     partition: usize,
     input: SendableRecordBatchStream,
     debug_id: String,
+    dump: Option<DebugDump>,
+    capture: bool,
+    num_batches: usize,
+    num_rows: usize,
     metrics: Arc<BaselineMetrics>,
 }
 
@@ -143,6 +333,30 @@ impl Stream for DebugStream {
         let metrics = self.metrics.clone();
         match metrics.record_poll(self.input.poll_next_unpin(cx))? {
             Poll::Ready(Some(batch)) => {
+                let batch_idx = self.num_batches;
+                self.num_batches += 1;
+                self.num_rows += batch.num_rows();
+
+                if let Some(dump) = &self.dump {
+                    let path =
+                        dump.dump_batch(&self.debug_id, self.partition, batch_idx, &batch)?;
+                    log::info!(
+                        "DebugExec[{}](partition={}): dumped batch {} ({} rows) to {}",
+                        self.debug_id,
+                        self.partition,
+                        batch_idx,
+                        batch.num_rows(),
+                        path.display(),
+                    );
+                    return Poll::Ready(Some(Ok(batch)));
+                }
+                if self.capture {
+                    DebugCaptures::get()
+                        .lock()
+                        .capture(&self.debug_id, batch.clone());
+                    return Poll::Ready(Some(Ok(batch)));
+                }
+
                 let mut batches = vec![batch];
                 let table_str = pretty_format_batches(&batches)?
                     .to_string()
@@ -151,8 +365,95 @@ impl Stream for DebugStream {
                 log::info!("DebugExec(partition={}):\n{}", self.partition, table_str);
                 Poll::Ready(Some(Ok(batches.pop().unwrap())))
             }
-            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(None) => {
+                log::info!(
+                    "DebugExec[{}](partition={}) finished: {} batches, {} rows",
+                    self.debug_id,
+                    self.partition,
+                    self.num_batches,
+                    self.num_rows,
+                );
+                Poll::Ready(None)
+            }
             Poll::Pending => Poll::Pending,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::debug_exec::{DebugCaptures, DebugDump, DebugDumpFormat, DebugExec};
+    use arrow::array::{ArrayRef, Int32Array};
+    use arrow::ipc::reader::FileReader;
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::SessionContext;
+    use std::fs::File;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_debug_dump_ipc() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone(), batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let dump_dir = tempfile::tempdir()?;
+        let debug = DebugExec::new(
+            input,
+            "test".to_string(),
+            Some(DebugDump {
+                format: DebugDumpFormat::Ipc,
+                dir: dump_dir.path().to_string_lossy().to_string(),
+            }),
+        );
+
+        let session_ctx = SessionContext::new();
+        let output = debug.execute(0, session_ctx.task_ctx())?;
+        let batches = common::collect(output).await?;
+        assert_eq!(batches.len(), 2);
+
+        for batch_idx in 0..2 {
+            let path = dump_dir.path().join(format!("test-0-{}.arrow", batch_idx));
+            let reader = FileReader::try_new(File::open(path)?, None)?;
+            let dumped = reader.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(dumped, vec![batch.clone()]);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_debug_capture() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let plan = Arc::new(CoalesceBatchesExec::new(input, 1024));
+        let wrapped = DebugExec::wrap_matching(plan, "CoalesceBatchesExec", "test_capture")?;
+        assert!(wrapped.as_any().downcast_ref::<DebugExec>().is_some());
+
+        let session_ctx = SessionContext::new();
+        let output = wrapped.execute(0, session_ctx.task_ctx())?;
+        common::collect(output).await?;
+
+        let captures = DebugCaptures::get().lock();
+        let (schema, captured) = captures
+            .batches("test_capture-CoalesceBatchesExec-0")
+            .expect("missing capture");
+        assert_eq!(schema, batch.schema());
+        assert_eq!(captured, vec![batch]);
+        Ok(())
+    }
+}
@@ -69,11 +69,15 @@ impl ExpandExec {
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    pub fn projections(&self) -> &[Vec<Arc<dyn PhysicalExpr>>] {
+        &self.projections
+    }
 }
 
 impl DisplayAs for ExpandExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "ExpandExec")
+        write!(f, "ExpandExec: projections={:?}", self.projections)
     }
 }
 
@@ -0,0 +1,186 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ffi_stream::FFI_ArrowArrayStream;
+use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_string};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::memory::MemoryStream;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+    Statistics,
+};
+use datafusion_ext_commons::cast::{decode_dictionary_columns, decoded_dictionary_schema};
+use datafusion_ext_commons::ffi::MpscBatchReader;
+use futures::{StreamExt, TryFutureExt, TryStreamExt};
+use jni::objects::JObject;
+use std::any::Any;
+use std::fmt::Formatter;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
+
+/// exports the input stream to the jvm side as an ArrowArrayStream (the inverse
+/// of FFIReaderExec), so that batches can be consumed as spark columnar batches
+/// without copying. the exporter provider returns the address of an allocated
+/// ArrowArrayStream struct to export into. output of this exec is always empty.
+#[derive(Debug)]
+pub struct FFIExporterExec {
+    input: Arc<dyn ExecutionPlan>,
+    exporter_resource_id: String,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl FFIExporterExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>, exporter_resource_id: String) -> Self {
+        Self {
+            input,
+            exporter_resource_id,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    pub fn exporter_resource_id(&self) -> &str {
+        &self.exporter_resource_id
+    }
+}
+
+impl DisplayAs for FFIExporterExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "FFIExporter")
+    }
+}
+
+impl ExecutionPlan for FFIExporterExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Plan(
+                "FFIExporterExec expects one children".to_string(),
+            ));
+        }
+        Ok(Arc::new(FFIExporterExec::new(
+            children[0].clone(),
+            self.exporter_resource_id.clone(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let exporter_provider = jni_call_static!(
+            JniBridge.getResource(
+                jni_new_string!(&self.exporter_resource_id)?.as_obj()) -> JObject
+        )?;
+        let stream_ptr_local =
+            jni_call!(ScalaFunction0(exporter_provider.as_obj()).apply() -> JObject)?;
+        let stream_ptr = jni_call!(JavaLong(stream_ptr_local.as_obj()).longValue() -> i64)?;
+        let input = self.input.execute(partition, context)?;
+
+        // export a reader of batches sent through the channel, jvm side pulls
+        // batches from it lazily
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let batch_reader = Box::new(MpscBatchReader {
+            schema: decoded_dictionary_schema(&input.schema()),
+            receiver,
+        });
+        unsafe {
+            std::ptr::write(
+                stream_ptr as *mut FFI_ArrowArrayStream,
+                FFI_ArrowArrayStream::new(batch_reader),
+            );
+        }
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            futures::stream::once(
+                export_batches(input, sender, baseline_metrics)
+                    .map_err(|e| ArrowError::ExternalError(Box::new(e))),
+            )
+            .try_flatten(),
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+async fn export_batches(
+    mut input: SendableRecordBatchStream,
+    sender: SyncSender<Option<Result<RecordBatch>>>,
+    metrics: BaselineMetrics,
+) -> Result<SendableRecordBatchStream> {
+    let schema = input.schema();
+    let decoded_schema = decoded_dictionary_schema(&schema);
+
+    while let Some(batch) = input.next().await {
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(err) => {
+                // also fail the jvm side reader, which is otherwise waiting forever
+                let message = err.to_string();
+                let _ = sender.send(Some(Err(DataFusionError::Execution(message))));
+                return Err(err);
+            }
+        };
+        metrics.record_output(batch.num_rows());
+
+        // dictionary arrays are not read by jvm side
+        let batch = decode_dictionary_columns(batch, &decoded_schema)?;
+        if sender.send(Some(Ok(batch))).is_err() {
+            // jvm side reader is closed, no more batches are needed
+            log::warn!("FFIExporterExec: exported stream closed by consumer");
+            break;
+        }
+    }
+    let _ = sender.send(None);
+
+    // ffi exporter always has empty output
+    Ok(Box::pin(MemoryStream::try_new(vec![], schema, None)?))
+}
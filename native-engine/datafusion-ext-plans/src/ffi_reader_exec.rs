@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use arrow::datatypes::SchemaRef;
+use arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
 use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_global_ref, jni_new_string};
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
@@ -25,7 +26,9 @@ use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
     Statistics,
 };
-use datafusion_ext_commons::streams::ffi_stream::FFIReaderStream;
+use datafusion_ext_commons::cancellation::task_cancellation_token;
+use datafusion_ext_commons::streams::cancellable_stream::CancellableStream;
+use datafusion_ext_commons::streams::ffi_stream::{FFIArrowStreamReaderStream, FFIReaderStream};
 use jni::objects::JObject;
 use std::any::Any;
 use std::fmt::{Debug, Formatter};
@@ -35,6 +38,7 @@ pub struct FFIReaderExec {
     num_partitions: usize,
     schema: SchemaRef,
     export_iter_provider_resource_id: String,
+    arrow_c_stream: bool,
     metrics: ExecutionPlanMetricsSet,
 }
 
@@ -43,14 +47,24 @@ impl FFIReaderExec {
         num_partitions: usize,
         export_iter_provider_resource_id: String,
         schema: SchemaRef,
+        arrow_c_stream: bool,
     ) -> FFIReaderExec {
         FFIReaderExec {
             num_partitions,
             export_iter_provider_resource_id,
             schema,
+            arrow_c_stream,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    pub fn export_iter_provider_resource_id(&self) -> &str {
+        &self.export_iter_provider_resource_id
+    }
+
+    pub fn arrow_c_stream(&self) -> bool {
+        self.arrow_c_stream
+    }
 }
 
 impl Debug for FFIReaderExec {
@@ -101,23 +115,42 @@ impl ExecutionPlan for FFIReaderExec {
     fn execute(
         &self,
         partition: usize,
-        _context: Arc<TaskContext>,
+        context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
         let resource_id = jni_new_string!(&self.export_iter_provider_resource_id)?;
         let export_iter_provider =
             jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
         let export_iter_local =
             jni_call!(ScalaFunction0(export_iter_provider.as_obj()).apply() -> JObject)?;
-        let export_iter = jni_new_global_ref!(export_iter_local.as_obj())?;
 
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
         let size_counter = MetricBuilder::new(&self.metrics).counter("size", partition);
 
-        Ok(Box::pin(FFIReaderStream::new(
-            self.schema.clone(),
-            export_iter,
-            baseline_metrics,
-            size_counter,
+        let stream: SendableRecordBatchStream = if self.arrow_c_stream {
+            // provider returns the address of an exported ArrowArrayStream, batches are
+            // fetched lazily through the stream's callbacks
+            let stream_ptr = jni_call!(JavaLong(export_iter_local.as_obj()).longValue() -> i64)?;
+            let reader = unsafe {
+                ArrowArrayStreamReader::from_raw(stream_ptr as *mut FFI_ArrowArrayStream)?
+            };
+            Box::pin(FFIArrowStreamReaderStream::new(
+                self.schema.clone(),
+                reader,
+                baseline_metrics,
+                size_counter,
+            ))
+        } else {
+            let export_iter = jni_new_global_ref!(export_iter_local.as_obj())?;
+            Box::pin(FFIReaderStream::new(
+                self.schema.clone(),
+                export_iter,
+                baseline_metrics,
+                size_counter,
+            ))
+        };
+        Ok(Box::pin(CancellableStream::new(
+            stream,
+            task_cancellation_token(&context),
         )))
     }
 
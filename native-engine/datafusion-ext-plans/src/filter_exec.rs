@@ -122,7 +122,12 @@ impl ExecutionPlan for FilterExec {
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
         let batch_size = context.session_config().batch_size();
-        let predicates = self.predicates.clone();
+        let cached_exprs_evaluator = CachedExprsEvaluator::try_new_with_metrics(
+            self.predicates.clone(),
+            vec![],
+            &self.metrics,
+            partition,
+        )?;
         let metrics = BaselineMetrics::new(&self.metrics, partition);
         let elapsed_compute = metrics.elapsed_compute().clone();
 
@@ -132,7 +137,13 @@ impl ExecutionPlan for FilterExec {
         )?;
         let filtered = Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
-            once(execute_filter(input, context, predicates, metrics)).try_flatten(),
+            once(execute_filter(
+                input,
+                context,
+                cached_exprs_evaluator,
+                metrics,
+            ))
+            .try_flatten(),
         ));
         let coalesced = Box::pin(CoalesceStream::new(filtered, batch_size, elapsed_compute));
         Ok(coalesced)
@@ -175,11 +186,9 @@ impl ExecuteWithColumnPruning for FilterExec {
 async fn execute_filter(
     mut input: SendableRecordBatchStream,
     context: Arc<TaskContext>,
-    predicates: Vec<PhysicalExprRef>,
+    cached_exprs_evaluator: CachedExprsEvaluator,
     metrics: BaselineMetrics,
 ) -> Result<SendableRecordBatchStream> {
-    let cached_exprs_evaluator = CachedExprsEvaluator::try_new(predicates, vec![])?;
-
     output_with_sender(
         "Filter",
         context,
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::shuffle::shuffle_compression_codec;
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
 use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_global_ref, jni_new_string};
@@ -27,6 +28,9 @@ use datafusion::physical_plan::Partitioning::UnknownPartitioning;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::physical_plan::Statistics;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType};
+use datafusion_ext_commons::cancellation::task_cancellation_token;
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use datafusion_ext_commons::streams::cancellable_stream::CancellableStream;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use datafusion_ext_commons::streams::ipc_stream::{IpcReadMode, IpcReaderStream};
 use jni::objects::JObject;
@@ -41,6 +45,8 @@ pub struct IpcReaderExec {
     pub ipc_provider_resource_id: String,
     pub schema: SchemaRef,
     pub mode: IpcReadMode,
+    pub codec: Option<IpcCompressionCodec>,
+    pub checksum: bool,
     pub metrics: ExecutionPlanMetricsSet,
 }
 impl IpcReaderExec {
@@ -49,12 +55,16 @@ impl IpcReaderExec {
         ipc_provider_resource_id: String,
         schema: SchemaRef,
         mode: IpcReadMode,
+        codec: Option<IpcCompressionCodec>,
+        checksum: bool,
     ) -> IpcReaderExec {
         IpcReaderExec {
             num_partitions,
             ipc_provider_resource_id,
             schema,
             mode,
+            codec,
+            checksum,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -119,12 +129,24 @@ impl ExecutionPlan for IpcReaderExec {
 
         let schema = self.schema.clone();
         let mode = self.mode;
-        let ipc_stream = Box::pin(IpcReaderStream::new(
-            schema,
-            segments,
-            mode,
-            baseline_metrics,
-            size_counter,
+        // shuffle data is written with codec specified in spark confs
+        let codec = match (self.codec, mode) {
+            (Some(codec), _) => codec,
+            (None, IpcReadMode::ChannelAndFileSegment) => shuffle_compression_codec()?,
+            (None, mode) => mode.default_codec(),
+        };
+        let checksum_stream_id = self.checksum.then(|| self.ipc_provider_resource_id.clone());
+        let ipc_stream = Box::pin(CancellableStream::new(
+            Box::pin(IpcReaderStream::new(
+                schema,
+                segments,
+                mode,
+                codec,
+                checksum_stream_id,
+                baseline_metrics,
+                size_counter,
+            )),
+            task_cancellation_token(&context),
         ));
         Ok(Box::pin(CoalesceStream::new(
             ipc_stream,
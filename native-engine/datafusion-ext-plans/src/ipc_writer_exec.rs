@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
@@ -24,14 +25,18 @@ use datafusion::error::Result;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::memory::MemoryStream;
-use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet, Time,
+};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
     Statistics,
 };
 use datafusion_ext_commons::concat_batches;
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{
+    append_ipc_checksum, write_one_batch_with_codec, IpcCompressionCodec,
+};
 
 use futures::StreamExt;
 use futures::TryFutureExt;
@@ -40,23 +45,66 @@ use jni::objects::{GlobalRef, JObject};
 use std::any::Any;
 use std::fmt::Formatter;
 use std::io::Cursor;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::{Arc, Weak};
+
+/// default in-memory size of batches written into one ipc chunk
+pub const DEFAULT_TARGET_CHUNK_SIZE: usize = 4 << 20;
+
+/// default number of chunks that can be written but not yet consumed by the
+/// jvm consumer
+pub const DEFAULT_MAX_INFLIGHT_CHUNKS: usize = 2;
 
 #[derive(Debug)]
 pub struct IpcWriterExec {
     input: Arc<dyn ExecutionPlan>,
     ipc_consumer_resource_id: String,
+    codec: IpcCompressionCodec,
+    target_chunk_size: usize,
+    max_inflight_chunks: usize,
+    checksum: bool,
     metrics: ExecutionPlanMetricsSet,
 }
 
 impl IpcWriterExec {
-    pub fn new(input: Arc<dyn ExecutionPlan>, ipc_consumer_resource_id: String) -> Self {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        ipc_consumer_resource_id: String,
+        codec: IpcCompressionCodec,
+        target_chunk_size: usize,
+        max_inflight_chunks: usize,
+        checksum: bool,
+    ) -> Self {
         Self {
             input,
             ipc_consumer_resource_id,
+            codec,
+            target_chunk_size,
+            max_inflight_chunks: max_inflight_chunks.max(1),
+            checksum,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    pub fn ipc_consumer_resource_id(&self) -> &str {
+        &self.ipc_consumer_resource_id
+    }
+
+    pub fn codec(&self) -> IpcCompressionCodec {
+        self.codec
+    }
+
+    pub fn target_chunk_size(&self) -> usize {
+        self.target_chunk_size
+    }
+
+    pub fn max_inflight_chunks(&self) -> usize {
+        self.max_inflight_chunks
+    }
+
+    pub fn checksum(&self) -> bool {
+        self.checksum
+    }
 }
 
 impl DisplayAs for IpcWriterExec {
@@ -97,8 +145,12 @@ impl ExecutionPlan for IpcWriterExec {
             ));
         }
         Ok(Arc::new(IpcWriterExec::new(
-            self.input.clone(),
+            children[0].clone(),
             self.ipc_consumer_resource_id.clone(),
+            self.codec,
+            self.target_chunk_size,
+            self.max_inflight_chunks,
+            self.checksum,
         )))
     }
 
@@ -108,21 +160,36 @@ impl ExecutionPlan for IpcWriterExec {
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let consumer_wait_time =
+            MetricBuilder::new(&self.metrics).subset_time("consumer_wait_time", partition);
         let ipc_consumer_local = jni_call_static!(
             JniBridge.getResource(
                 jni_new_string!(&self.ipc_consumer_resource_id)?.as_obj()) -> JObject
         )?;
         let ipc_consumer = jni_new_global_ref!(ipc_consumer_local.as_obj())?;
-        let input = self.input.execute(partition, context.clone())?;
+        let input = self.input.execute(partition, context)?;
+
+        let buffered = Arc::new(IpcWriterBuffered {
+            name: format!("IpcWriter[partition={}]", partition),
+            mem_consumer_info: None,
+            inflight_mem_used: AtomicUsize::new(0),
+        });
+        MemManager::register_consumer(buffered.clone(), false);
+        buffered.register_peak_mem_used_metric(&self.metrics, partition);
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
             futures::stream::once(
                 write_ipc(
                     input,
-                    context.session_config().batch_size(),
                     ipc_consumer,
+                    buffered,
+                    self.codec,
+                    self.target_chunk_size,
+                    self.max_inflight_chunks,
+                    self.checksum,
                     baseline_metrics,
+                    consumer_wait_time,
                 )
                 .map_err(|e| ArrowError::ExternalError(Box::new(e))),
             )
@@ -139,15 +206,80 @@ impl ExecutionPlan for IpcWriterExec {
     }
 }
 
-pub async fn write_ipc(
+/// tracks memory of staged batches and chunks that are written but not yet
+/// accepted by the jvm consumer. the buffer cannot be spilled, registering it
+/// lets other consumers spill (or the task fail) instead of silently exceeding
+/// the memory limit.
+struct IpcWriterBuffered {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    inflight_mem_used: AtomicUsize,
+}
+
+#[async_trait]
+impl MemConsumer for IpcWriterBuffered {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+}
+
+impl Drop for IpcWriterBuffered {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// writes input batches into ipc chunks of about `target_chunk_size` bytes
+/// and hands them to the jvm consumer.
+///
+/// chunks are delivered synchronously by a blocking thread. at most
+/// `max_inflight_chunks` chunks can be queued in front of it, the writer is
+/// suspended when the queue is full, so a slow consumer never causes
+/// unbounded buffering. staged batches and queued chunks are accounted to
+/// the mem manager.
+///
+/// if `checksum` is set, a crc32 checksum is appended to every chunk.
+pub(crate) async fn write_ipc(
     mut input: SendableRecordBatchStream,
-    batch_size: usize,
     ipc_consumer: GlobalRef,
+    buffered: Arc<IpcWriterBuffered>,
+    codec: IpcCompressionCodec,
+    target_chunk_size: usize,
+    max_inflight_chunks: usize,
+    checksum: bool,
     metrics: BaselineMetrics,
+    consumer_wait_time: Time,
 ) -> Result<SendableRecordBatchStream> {
     let schema = input.schema();
     let mut batches: Vec<RecordBatch> = vec![];
     let mut num_rows = 0;
+    let mut staging_mem_size = 0;
+
+    let (chunk_sender, mut chunk_receiver) =
+        tokio::sync::mpsc::channel::<Vec<u8>>(max_inflight_chunks.max(1));
+    let buffered_cloned = buffered.clone();
+    let consumer_handle = tokio::task::spawn_blocking(move || -> Result<()> {
+        while let Some(buffer) = chunk_receiver.blocking_recv() {
+            let buf = jni_new_direct_byte_buffer!(&buffer)?;
+            let _consumed = jni_call!(
+                ScalaFunction1(ipc_consumer.as_obj()).apply(buf.as_obj()) -> JObject
+            )?;
+            buffered_cloned
+                .inflight_mem_used
+                .fetch_sub(buffer.len(), SeqCst);
+        }
+        Ok(())
+    });
 
     macro_rules! flush_batches {
         () => {{
@@ -158,19 +290,24 @@ pub async fn write_ipc(
             num_rows = 0;
 
             let mut buffer = vec![];
-            write_one_batch(
-                &batch,
-                &mut Cursor::new(&mut buffer),
-                true,
-                None,
-            )?;
+            write_one_batch_with_codec(&batch, &mut Cursor::new(&mut buffer), codec, None)?;
+            if checksum {
+                append_ipc_checksum(&mut buffer);
+            }
             drop(timer);
 
-            let buf = jni_new_direct_byte_buffer!(&buffer)?;
-            let _consumed = jni_call!(
-                ScalaFunction1(ipc_consumer.as_obj()).apply(buf.as_obj()) -> JObject
-            )?;
-        }}
+            // staged batches are released, the chunk is accounted until the
+            // consumer thread has delivered it
+            buffered.inflight_mem_used.fetch_add(buffer.len(), SeqCst);
+            buffered
+                .update_mem_used(buffered.inflight_mem_used.load(SeqCst))
+                .await?;
+
+            // wait for a free slot. sending fails only if the consumer thread has
+            // exited, its error is returned after joining
+            let _timer = consumer_wait_time.timer();
+            chunk_sender.send(buffer).await.is_ok()
+        }};
     }
 
     while let Some(batch) = input.next().await {
@@ -179,16 +316,32 @@ pub async fn write_ipc(
         if batch.num_rows() == 0 {
             continue;
         }
-        if num_rows + batch.num_rows() > batch_size {
-            flush_batches!();
-        }
+        staging_mem_size += batch.get_array_memory_size();
         num_rows += batch.num_rows();
         batches.push(batch);
+        buffered
+            .update_mem_used(staging_mem_size + buffered.inflight_mem_used.load(SeqCst))
+            .await?;
+
+        if staging_mem_size >= target_chunk_size {
+            staging_mem_size = 0;
+            if !flush_batches!() {
+                break;
+            }
+        }
     }
     if num_rows > 0 {
         flush_batches!();
     }
     assert_eq!(num_rows, 0);
+    drop(chunk_sender);
+
+    // wait until all chunks are consumed
+    let _timer = consumer_wait_time.timer();
+    consumer_handle
+        .await
+        .map_err(|err| DataFusionError::Execution(format!("ipc consumer panicked: {err}")))??;
+    buffered.update_mem_used(0).await?;
 
     // ipc writer always has empty output
     Ok(Box::pin(MemoryStream::try_new(vec![], schema, None)?))
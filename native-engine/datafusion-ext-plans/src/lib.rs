@@ -17,12 +17,15 @@
 
 pub mod agg;
 pub mod agg_exec;
+pub mod batch_validation_exec;
 pub mod broadcast_join_exec;
 pub mod broadcast_nested_loop_join_exec;
+pub mod collect_stats_exec;
 pub mod common;
 pub mod debug_exec;
 pub mod empty_partitions_exec;
 pub mod expand_exec;
+pub mod ffi_exporter_exec;
 pub mod ffi_reader_exec;
 pub mod filter_exec;
 pub mod generate;
@@ -39,5 +42,6 @@ mod shuffle;
 pub mod shuffle_writer_exec;
 pub mod sort_exec;
 pub mod sort_merge_join_exec;
+pub mod text_sink_exec;
 pub mod window;
 pub mod window_exec;
@@ -15,26 +15,48 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+/// global limits only truncate the output stream, while local limits are
+/// applied to each partition and drop the input stream as soon as the limit
+/// is reached, so that upstream operators and scans stop early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitMode {
+    Global,
+    Local,
+}
+
 #[derive(Debug)]
 pub struct LimitExec {
     input: Arc<dyn ExecutionPlan>,
     limit: u64,
+    mode: LimitMode,
     pub metrics: ExecutionPlanMetricsSet,
 }
 
 impl LimitExec {
-    pub fn new(input: Arc<dyn ExecutionPlan>, limit: u64) -> Self {
+    pub fn new(input: Arc<dyn ExecutionPlan>, limit: u64, mode: LimitMode) -> Self {
         Self {
             input,
             limit,
+            mode,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn mode(&self) -> LimitMode {
+        self.mode
+    }
 }
 
 impl DisplayAs for LimitExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "LimitExec(limit={})", self.limit)
+        match self.mode {
+            LimitMode::Global => write!(f, "LimitExec(limit={})", self.limit),
+            LimitMode::Local => write!(f, "LimitExec(limit={}, mode=local)", self.limit),
+        }
     }
 }
 
@@ -64,7 +86,11 @@ impl ExecutionPlan for LimitExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(Self::new(children[0].clone(), self.limit))),
+            1 => Ok(Arc::new(Self::new(
+                children[0].clone(),
+                self.limit,
+                self.mode,
+            ))),
             _ => Err(DataFusionError::Internal(
                 "LimitExec wrong number of children".to_string(),
             )),
@@ -78,8 +104,10 @@ impl ExecutionPlan for LimitExec {
     ) -> Result<SendableRecordBatchStream> {
         let input_stream = self.input.execute(partition, context)?;
         Ok(Box::pin(LimitStream {
-            input_stream,
+            schema: input_stream.schema(),
+            input_stream: Some(input_stream),
             limit: self.limit,
+            mode: self.mode,
             cur: 0,
             baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
         }))
@@ -91,15 +119,17 @@ impl ExecutionPlan for LimitExec {
 }
 
 struct LimitStream {
-    input_stream: SendableRecordBatchStream,
+    schema: SchemaRef,
+    input_stream: Option<SendableRecordBatchStream>,
     limit: u64,
+    mode: LimitMode,
     cur: u64,
     baseline_metrics: BaselineMetrics,
 }
 
 impl RecordBatchStream for LimitStream {
     fn schema(&self) -> SchemaRef {
-        self.input_stream.schema()
+        self.schema.clone()
     }
 }
 
@@ -108,11 +138,12 @@ impl Stream for LimitStream {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let rest = self.limit.saturating_sub(self.cur);
-        if rest == 0 {
-            return Poll::Ready(None);
-        }
+        let input_stream = match &mut self.input_stream {
+            Some(input_stream) if rest > 0 => input_stream,
+            _ => return Poll::Ready(None),
+        };
 
-        match self.input_stream.poll_next_unpin(cx) {
+        match input_stream.poll_next_unpin(cx) {
             Poll::Pending => Poll::Pending,
             Poll::Ready(None) => Poll::Ready(None),
             Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
@@ -124,6 +155,12 @@ impl Stream for LimitStream {
                     self.cur += rest;
                     batch.slice(0, rest as usize)
                 };
+
+                // local limit reached, dropping the input stream cancels the
+                // pending upstream tasks
+                if self.mode == LimitMode::Local && self.cur >= self.limit {
+                    self.input_stream = None;
+                }
                 self.baseline_metrics
                     .record_poll(Poll::Ready(Some(Ok(batch))))
             }
@@ -134,15 +171,17 @@ impl Stream for LimitStream {
 #[cfg(test)]
 mod test {
     use crate::common::memory_manager::MemManager;
-    use crate::limit_exec::LimitExec;
+    use crate::limit_exec::{LimitExec, LimitMode, LimitStream};
     use arrow::array::Int32Array;
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
     use datafusion::assert_batches_eq;
     use datafusion::common::Result;
     use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
     use datafusion::physical_plan::{common, ExecutionPlan};
     use datafusion::prelude::SessionContext;
+    use futures::StreamExt;
     use std::sync::Arc;
 
     fn build_table_i32(
@@ -185,7 +224,7 @@ mod test {
             ("b", &vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
             ("c", &vec![5, 6, 7, 8, 9, 0, 1, 2, 3, 4]),
         );
-        let limit_exec = LimitExec::new(input, 2_u64);
+        let limit_exec = LimitExec::new(input, 2_u64, LimitMode::Global);
         let session_ctx = SessionContext::new();
         let task_ctx = session_ctx.task_ctx();
         let output = limit_exec.execute(0, task_ctx).unwrap();
@@ -202,4 +241,29 @@ mod test {
         assert_batches_eq!(expected, &batches);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_local_limit_stops_input() -> Result<()> {
+        MemManager::init(10000);
+        let batch = build_table_i32(("a", &vec![9, 8]), ("b", &vec![0, 1]), ("c", &vec![5, 6]));
+        let schema = batch.schema();
+        let input = MemoryExec::try_new(&[vec![batch; 3]], schema.clone(), None)?;
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+
+        let mut stream = LimitStream {
+            schema,
+            input_stream: Some(input.execute(0, task_ctx)?),
+            limit: 3,
+            mode: LimitMode::Local,
+            cur: 0,
+            baseline_metrics: BaselineMetrics::new(&ExecutionPlanMetricsSet::new(), 0),
+        };
+        assert_eq!(stream.next().await.unwrap()?.num_rows(), 2);
+        assert!(stream.input_stream.is_some());
+        assert_eq!(stream.next().await.unwrap()?.num_rows(), 1);
+        assert!(stream.input_stream.is_none());
+        assert!(stream.next().await.is_none());
+        Ok(())
+    }
 }
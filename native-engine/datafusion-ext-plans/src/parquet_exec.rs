@@ -19,25 +19,43 @@
 
 use fmt::Debug;
 use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::Formatter;
+use std::io::Read;
 use std::ops::Range;
 use std::sync::Arc;
 
 use arrow::array::ArrayRef;
-use arrow::datatypes::{DataType, SchemaRef};
-use datafusion::common::DataFusionError;
+use arrow::datatypes::{DataType, FieldRef, Fields, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{DataFusionError, ScalarValue};
+use datafusion::datasource::listing::{FileRange, PartitionedFile};
 use datafusion::datasource::physical_plan::parquet::page_filter::PagePruningPredicate;
 use datafusion::datasource::physical_plan::parquet::ParquetOpener;
 use datafusion::datasource::physical_plan::{
-    FileMeta, FileScanConfig, FileStream, OnError, ParquetFileMetrics, ParquetFileReaderFactory,
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileStream, OnError, ParquetFileMetrics,
+    ParquetFileReaderFactory,
 };
-use datafusion::parquet::arrow::async_reader::{fetch_parquet_metadata, AsyncFileReader};
+use datafusion::logical_expr::Operator;
+use datafusion::parquet::arrow::async_reader::{
+    fetch_parquet_metadata, AsyncFileReader, MetadataLoader,
+};
+use datafusion::parquet::arrow::parquet_to_arrow_schema;
+use datafusion::parquet::basic::{ConvertedType, LogicalType, Type as PhysicalType};
+use datafusion::parquet::bloom_filter::Sbbf;
+use datafusion::parquet::data_type::ByteArray;
 use datafusion::parquet::errors::ParquetError;
-use datafusion::parquet::file::metadata::ParquetMetaData;
+use datafusion::parquet::file::metadata::{FileMetaData, ParquetMetaData, RowGroupMetaData};
+use datafusion::parquet::file::reader::{ChunkReader, Length};
+use datafusion::parquet::schema::types::{SchemaDescriptor, Type as ParquetType, TypePtr};
+use datafusion::physical_expr::expressions::{BinaryExpr, Column, InListExpr, Literal};
+use datafusion::physical_expr::utils::split_conjunction;
 use datafusion::physical_optimizer::pruning::PruningPredicate;
 use datafusion::physical_plan::metrics::{BaselineMetrics, MetricValue, Time};
-use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::stream::{RecordBatchReceiverStream, RecordBatchStreamAdapter};
 use datafusion::physical_plan::{DisplayAs, Metric, PhysicalExpr, RecordBatchStream};
 use datafusion::{
     error::Result,
@@ -50,18 +68,35 @@ use datafusion::{
 };
 use futures::future::BoxFuture;
 use futures::stream::once;
-use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use futures::{FutureExt, StreamExt, TryStreamExt};
 use object_store::ObjectMeta;
 
 use base64::prelude::BASE64_URL_SAFE_NO_PAD;
 use base64::Engine;
 use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
 use bytes::Bytes;
-use datafusion_ext_commons::hadoop_fs::{FsDataInputStream, FsProvider};
+use datafusion_ext_commons::datetime_rebase::{
+    contains_datetime, has_ancient_datetime, rebase_julian_to_gregorian_array, RebaseMode,
+};
+use datafusion_ext_commons::error_context::ErrorContext;
+use datafusion_ext_commons::hadoop_fs::{FsDataInputStream, FsProvider, RetryPolicy};
+use datafusion_ext_exprs::cast::TryCastExpr;
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 
 use crate::common::output::output_with_sender;
 
+// files smaller than this are not split for parallel reading, since the footer is
+// read once for each range
+const PARALLEL_READ_MIN_RANGE_SIZE: usize = 32 * 1024 * 1024;
+
+// max number of decoded batches buffered for each range in parallel reading
+const PARALLEL_READ_BUFFER_SIZE: usize = 2;
+
+/// name of the column holding input file paths, see
+/// `ParquetExec::with_input_file_name_column()`
+pub const INPUT_FILE_NAME_COLUMN: &str = "__input_file_name__";
+
 #[no_mangle]
 fn schema_adapter_cast_column(
     col: &ArrayRef,
@@ -82,6 +117,11 @@ pub struct ParquetExec {
     predicate: Option<Arc<dyn PhysicalExpr>>,
     pruning_predicate: Option<Arc<PruningPredicate>>,
     page_pruning_predicate: Option<Arc<PagePruningPredicate>>,
+    bloom_filter_predicates: Arc<Vec<BloomFilterPredicate>>,
+    schema_matcher: Arc<FileSchemaMatcher>,
+    datetime_rebase_mode: RebaseMode,
+    int96_rebase_mode: RebaseMode,
+    bucket_partitioning: Option<Partitioning>,
 }
 
 impl ParquetExec {
@@ -90,6 +130,9 @@ impl ParquetExec {
         base_config: FileScanConfig,
         fs_resource_id: String,
         predicate: Option<Arc<dyn PhysicalExpr>>,
+        schema_match_mode: SchemaMatchMode,
+        datetime_rebase_mode: RebaseMode,
+        int96_rebase_mode: RebaseMode,
     ) -> Self {
         let metrics = ExecutionPlanMetricsSet::new();
         let predicate_creation_errors =
@@ -121,6 +164,18 @@ impl ParquetExec {
             }
         });
 
+        let bloom_filter_predicates = Arc::new(
+            predicate
+                .as_ref()
+                .map(|predicate| collect_bloom_filter_predicates(predicate, file_schema))
+                .unwrap_or_default(),
+        );
+
+        let schema_matcher = Arc::new(FileSchemaMatcher {
+            table_schema: file_schema.clone(),
+            mode: schema_match_mode,
+        });
+
         let (projected_schema, projected_statistics, projected_output_ordering) =
             base_config.project();
 
@@ -134,7 +189,178 @@ impl ParquetExec {
             predicate,
             pruning_predicate,
             page_pruning_predicate,
+            bloom_filter_predicates,
+            schema_matcher,
+            datetime_rebase_mode,
+            int96_rebase_mode,
+            bucket_partitioning: None,
+        }
+    }
+
+    /// declares the output as hash partitioned by bucket columns, for scanning
+    /// bucketed tables where each file group contains exactly one bucket
+    pub fn with_bucket_partitioning(mut self, partitioning: Partitioning) -> Result<Self> {
+        let num_file_groups = self.base_config.file_groups.len();
+        match &partitioning {
+            Partitioning::Hash(_, num_buckets) if *num_buckets == num_file_groups => {}
+            Partitioning::Hash(_, num_buckets) => {
+                return Err(DataFusionError::Plan(format!(
+                    "ParquetExec: number of buckets ({num_buckets}) does not match \
+                        number of file groups ({num_file_groups})"
+                )));
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "ParquetExec: bucket partitioning must be hash partitioning, got \
+                        {partitioning:?}"
+                )));
+            }
         }
+        self.bucket_partitioning = Some(partitioning);
+        Ok(self)
+    }
+}
+
+impl ParquetExec {
+    /// returns indices of output columns in file schema, followed by table
+    /// partition columns
+    pub fn projection(&self) -> Vec<usize> {
+        match &self.base_config.projection {
+            Some(projection) => projection.clone(),
+            None => (0..self.projected_schema.fields().len()).collect(),
+        }
+    }
+
+    /// creates a scan on the same files with another projection, see
+    /// `projection()`
+    pub fn with_projection(&self, projection: Vec<usize>) -> Self {
+        let mut base_config = self.base_config.clone();
+        base_config.projection = Some(projection);
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+
+        Self {
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            bucket_partitioning: self.bucket_partitioning.as_ref().and_then(|partitioning| {
+                reproject_bucket_partitioning(partitioning, &projected_schema)
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// returns max number of rows to read of each partition
+    pub fn limit(&self) -> Option<usize> {
+        self.base_config.limit
+    }
+
+    /// creates a scan on the same files which stops reading once `limit` rows
+    /// are read in each partition
+    pub fn with_limit(&self, limit: usize) -> Self {
+        let mut base_config = self.base_config.clone();
+        base_config.limit = Some(base_config.limit.map_or(limit, |l| l.min(limit)));
+        Self {
+            base_config,
+            ..self.clone()
+        }
+    }
+
+    /// creates a scan on the same files with an additional trailing utf8 column
+    /// named `INPUT_FILE_NAME_COLUMN`, holding the path of the file each row is
+    /// read from. the path is filled as a partition value of each file, so it
+    /// is carried with the batches through parallel reading and coalescing.
+    pub fn with_input_file_name_column(&self) -> Result<Self> {
+        let mut base_config = self.base_config.clone();
+        let input_file_column_index =
+            base_config.file_schema.fields().len() + base_config.table_partition_cols.len();
+        base_config
+            .table_partition_cols
+            .push((INPUT_FILE_NAME_COLUMN.to_string(), DataType::Utf8));
+        for file in base_config.file_groups.iter_mut().flatten() {
+            let input_file = decode_file_path(&file.object_meta)?;
+            file.partition_values
+                .push(ScalarValue::Utf8(Some(input_file)));
+        }
+        let mut projection = self.projection();
+        projection.push(input_file_column_index);
+        base_config.projection = Some(projection);
+
+        let (projected_schema, projected_statistics, projected_output_ordering) =
+            base_config.project();
+        Ok(Self {
+            base_config,
+            projected_schema,
+            projected_statistics,
+            projected_output_ordering,
+            ..self.clone()
+        })
+    }
+
+    /// reads files of the partition concurrently. files are split into ranges and
+    /// row groups of each range are decoded in a separated task, with outputs
+    /// buffered in bounded channels. outputs are reassembled in file order if
+    /// required, otherwise returned once available.
+    fn execute_parallel<F: FileOpener + Send + Sync + 'static>(
+        &self,
+        partition_index: usize,
+        opener: F,
+        parallelism: usize,
+        ordered: bool,
+        ignore_corrupted_files: bool,
+    ) -> Result<SendableRecordBatchStream> {
+        let schema = self.schema();
+        let opener = Arc::new(opener);
+        let base_config = self.base_config.clone();
+        let metrics = self.metrics.clone();
+        let file_ranges = split_file_ranges(
+            &self.base_config.file_groups[partition_index],
+            parallelism,
+            PARALLEL_READ_MIN_RANGE_SIZE,
+        );
+
+        let output_schema = schema.clone();
+        let range_streams = futures::stream::iter(file_ranges).map(move |file_range| {
+            let mut config = base_config.clone();
+            config.file_groups = vec![vec![]; partition_index + 1];
+            config.file_groups[partition_index] = vec![file_range];
+            let opener = SharedOpener(opener.clone());
+            let mut file_stream = FileStream::new(&config, partition_index, opener, &metrics)?;
+            if ignore_corrupted_files {
+                file_stream = file_stream.with_on_error(OnError::Skip);
+            }
+
+            let mut builder = RecordBatchReceiverStream::builder(
+                output_schema.clone(),
+                PARALLEL_READ_BUFFER_SIZE,
+            );
+            let tx = builder.tx();
+            builder.spawn(async move {
+                while let Some(batch) = file_stream.next().await {
+                    if tx.send(batch).await.is_err() {
+                        break; // receiver dropped
+                    }
+                }
+                Ok(())
+            });
+            Ok(builder.build())
+        });
+
+        Ok(if ordered {
+            Box::pin(RecordBatchStreamAdapter::new(
+                schema,
+                range_streams
+                    .map(futures::future::ready)
+                    .buffered(parallelism)
+                    .try_flatten(),
+            ))
+        } else {
+            Box::pin(RecordBatchStreamAdapter::new(
+                schema,
+                range_streams.try_flatten_unordered(parallelism),
+            ))
+        })
     }
 }
 
@@ -176,7 +402,11 @@ impl ExecutionPlan for ParquetExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        self.bucket_partitioning
+            .clone()
+            .unwrap_or(Partitioning::UnknownPartitioning(
+                self.base_config.file_groups.len(),
+            ))
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
@@ -218,7 +448,34 @@ impl ExecutionPlan for ParquetExec {
         // get fs object from jni bridge resource
         let resource_id = jni_new_string!(&self.fs_resource_id)?;
         let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
-        let fs_provider = Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time));
+        let fs_provider = Arc::new(
+            FsProvider::new(jni_new_global_ref!(fs.as_obj())?, &io_time)
+                .with_retry_policy(RetryPolicy::try_new_from_conf()?),
+        );
+        let metadata_cache = match jni_call_static!(BlazeConf.parquetMetadataCacheSize() -> i32)? {
+            capacity if capacity > 0 => Some(
+                PARQUET_METADATA_CACHE.get_or_init(|| ParquetMetadataCache::new(capacity as usize)),
+            ),
+            _ => None,
+        };
+        let bloom_filter_predicates = if jni_call_static!(BlazeConf.parquetEnableBloomFilter() -> bool)?
+        {
+            self.bloom_filter_predicates.clone()
+        } else {
+            Arc::default()
+        };
+
+        // row filters are evaluated on file columns before being adapted to the table
+        // schema, so columns are explicitly casted to table types in pushed-down filters
+        let pushdown_filters = jni_call_static!(BlazeConf.parquetEnableFilterPushdown() -> bool)?;
+        let enable_page_index = jni_call_static!(BlazeConf.parquetEnablePageFiltering() -> bool)?;
+        let predicate = match &self.predicate {
+            Some(predicate) if pushdown_filters => Some(cast_columns_to_table_types(
+                predicate,
+                &self.base_config.file_schema,
+            )?),
+            _ => self.predicate.clone(),
+        };
 
         let projection = match self.base_config.file_column_projection_indices() {
             Some(proj) => proj,
@@ -230,27 +487,55 @@ impl ExecutionPlan for ParquetExec {
             projection: Arc::from(projection),
             batch_size: context.session_config().batch_size(),
             limit: self.base_config.limit,
-            predicate: self.predicate.clone(),
+            predicate,
             pruning_predicate: self.pruning_predicate.clone(),
             page_pruning_predicate: self.page_pruning_predicate.clone(),
             table_schema: self.base_config.file_schema.clone(),
             metadata_size_hint: None,
             metrics: self.metrics.clone(),
-            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(fs_provider)),
-            pushdown_filters: false, // still buggy
-            reorder_filters: false,
-            enable_page_index: false,
+            parquet_file_reader_factory: Arc::new(FsReaderFactory::new(
+                fs_provider,
+                metadata_cache,
+                jni_call_static!(BlazeConf.fsReadAheadSize() -> i32)?.max(0) as usize,
+                jni_call_static!(BlazeConf.parquetReadRangesCoalesceGap() -> i32)?.max(0) as usize,
+                enable_page_index,
+                bloom_filter_predicates,
+                self.schema_matcher.clone(),
+            )),
+            pushdown_filters,
+            reorder_filters: pushdown_filters,
+            enable_page_index,
+        };
+        let opener = DatetimeRebaseOpener {
+            inner: opener,
+            datetime_rebase_mode: self.datetime_rebase_mode,
+            int96_rebase_mode: self.int96_rebase_mode,
         };
+        let opener = ErrorContextOpener { inner: opener };
         drop(timer);
 
+        let ignore_corrupted_files = jni_call_static!(BlazeConf.ignoreCorruptedFiles() -> bool)?;
+        let parallelism =
+            jni_call_static!(BlazeConf.parquetScanParallelism() -> i32)?.max(1) as usize;
+
         let baseline_metrics = BaselineMetrics::new(&self.metrics, partition_index);
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
-        let mut file_stream =
-            FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
-        if jni_call_static!(BlazeConf.ignoreCorruptedFiles() -> bool)? {
-            file_stream = file_stream.with_on_error(OnError::Skip);
-        }
-        let mut stream = Box::pin(file_stream);
+        let mut stream: SendableRecordBatchStream = if parallelism > 1 {
+            self.execute_parallel(
+                partition_index,
+                opener,
+                parallelism,
+                jni_call_static!(BlazeConf.parquetScanParallelOrdered() -> bool)?,
+                ignore_corrupted_files,
+            )?
+        } else {
+            let mut file_stream =
+                FileStream::new(&self.base_config, partition_index, opener, &self.metrics)?;
+            if ignore_corrupted_files {
+                file_stream = file_stream.with_on_error(OnError::Skip);
+            }
+            Box::pin(file_stream)
+        };
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
@@ -281,14 +566,391 @@ impl ExecutionPlan for ParquetExec {
     }
 }
 
+/// wraps columns in the predicate with casts to their types in table schema.
+fn cast_columns_to_table_types(
+    predicate: &Arc<dyn PhysicalExpr>,
+    table_schema: &SchemaRef,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    predicate
+        .clone()
+        .transform_up(&|expr: Arc<dyn PhysicalExpr>| {
+            Ok(match expr.as_any().downcast_ref::<Column>() {
+                Some(col) => {
+                    let data_type = table_schema.field(col.index()).data_type().clone();
+                    Transformed::Yes(Arc::new(TryCastExpr::new(expr, data_type)))
+                }
+                None => Transformed::No(expr),
+            })
+        })
+}
+
+/// wraps a file opener and adds the file path and batch index to errors of
+/// opening/reading the file.
+struct ErrorContextOpener<F: FileOpener> {
+    inner: F,
+}
+
+impl<F: FileOpener> FileOpener for ErrorContextOpener<F> {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let mut context = ErrorContext::new("ParquetScan")
+            .with("file", decode_file_path(&file_meta.object_meta)?);
+        if let Some(range) = &file_meta.range {
+            context = context.with("range", format!("{}..{}", range.start, range.end));
+        }
+        let open_future = self
+            .inner
+            .open(file_meta)
+            .map_err(|err| context.wrap(err))?;
+        Ok(Box::pin(async move {
+            let stream = open_future.await.map_err(|err| context.wrap(err))?;
+            let mut batch_idx = 0;
+            Ok(stream
+                .map(move |batch| {
+                    let batch_context = context.clone().with("batch", batch_idx);
+                    batch_idx += 1;
+                    batch.map_err(|err| batch_context.wrap_arrow(err))
+                })
+                .boxed())
+        }))
+    }
+}
+
+/// metadata of the opened file, published by the file reader once loaded.
+type FileMetadataCell = OnceCell<Arc<ParquetMetaData>>;
+
+/// wraps a file opener and rebases dates/timestamps read from the opened file.
+/// the file metadata is passed from the file reader through file extensions.
+struct DatetimeRebaseOpener<F: FileOpener> {
+    inner: F,
+    datetime_rebase_mode: RebaseMode,
+    int96_rebase_mode: RebaseMode,
+}
+
+impl<F: FileOpener> FileOpener for DatetimeRebaseOpener<F> {
+    fn open(&self, mut file_meta: FileMeta) -> Result<FileOpenFuture> {
+        let file_metadata = Arc::new(FileMetadataCell::new());
+        file_meta.extensions = Some(file_metadata.clone());
+        let datetime_rebase_mode = self.datetime_rebase_mode;
+        let int96_rebase_mode = self.int96_rebase_mode;
+        let open_future = self.inner.open(file_meta)?;
+        Ok(Box::pin(async move {
+            let stream = open_future.await?;
+            let mut rebaser = None;
+            Ok(stream
+                .map(move |batch| {
+                    let batch = batch?;
+                    let Some(metadata) = file_metadata.get() else {
+                        return Ok(batch);
+                    };
+                    rebaser
+                        .get_or_insert_with(|| {
+                            FileDatetimeRebaser::new(
+                                metadata,
+                                datetime_rebase_mode,
+                                int96_rebase_mode,
+                            )
+                        })
+                        .rebase_batch(batch)
+                        .map_err(|err| ArrowError::ExternalError(Box::new(err)))
+                })
+                .boxed())
+        }))
+    }
+}
+
+/// rebases dates/timestamps of batches read from a file, with rebase modes
+/// resolved from the file metadata like spark: files written by spark 3.x are
+/// rebased only if marked as legacy, other files use the configured modes.
+struct FileDatetimeRebaser {
+    datetime_rebase_mode: RebaseMode,
+    int96_rebase_mode: RebaseMode,
+    int96_cols: HashSet<String>,
+}
+
+impl FileDatetimeRebaser {
+    fn new(
+        metadata: &ParquetMetaData,
+        datetime_rebase_mode: RebaseMode,
+        int96_rebase_mode: RebaseMode,
+    ) -> Self {
+        let file_metadata = metadata.file_metadata();
+        let kvs: HashMap<&str, Option<&str>> = file_metadata
+            .key_value_metadata()
+            .into_iter()
+            .flatten()
+            .map(|kv| (kv.key.as_str(), kv.value.as_deref()))
+            .collect();
+        let resolve = |legacy_key: &str, legacy_version: &str, mode_by_conf: RebaseMode| match kvs
+            .get("org.apache.spark.version")
+        {
+            Some(version) => {
+                let version = version.unwrap_or_default();
+                if version < legacy_version || kvs.contains_key(legacy_key) {
+                    RebaseMode::Legacy
+                } else {
+                    RebaseMode::Corrected
+                }
+            }
+            None => mode_by_conf,
+        };
+
+        Self {
+            datetime_rebase_mode: resolve(
+                "org.apache.spark.legacyDateTime",
+                "3.0.0",
+                datetime_rebase_mode,
+            ),
+            int96_rebase_mode: resolve("org.apache.spark.legacyINT96", "3.1.0", int96_rebase_mode),
+            int96_cols: file_metadata
+                .schema()
+                .get_fields()
+                .iter()
+                .filter(|field| {
+                    field.is_primitive() && field.get_physical_type() == PhysicalType::INT96
+                })
+                .map(|field| field.name().to_string())
+                .collect(),
+        }
+    }
+
+    fn rebase_batch(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let mut rebased_cols = Vec::with_capacity(batch.num_columns());
+        for (field, col) in schema.fields().iter().zip(batch.columns()) {
+            if !contains_datetime(field.data_type()) {
+                rebased_cols.push(col.clone());
+                continue;
+            }
+            let (mode, mode_conf) = if self.int96_cols.contains(field.name()) {
+                (self.int96_rebase_mode, "int96RebaseModeInRead")
+            } else {
+                (self.datetime_rebase_mode, "datetimeRebaseModeInRead")
+            };
+            rebased_cols.push(match mode {
+                RebaseMode::Corrected => col.clone(),
+                RebaseMode::Legacy => rebase_julian_to_gregorian_array(col)?,
+                RebaseMode::Exception if has_ancient_datetime(col) => {
+                    return Err(DataFusionError::Execution(format!(
+                        "reading dates before 1582-10-15 or timestamps before \
+                            1900-01-01T00:00:00Z from parquet files can be ambiguous, as the \
+                            files may be written by spark 2.x or legacy versions of hive, \
+                            which use a legacy hybrid calendar. set \
+                            spark.sql.parquet.{mode_conf} to 'LEGACY' to rebase the values, \
+                            or 'CORRECTED' to read the values as they are"
+                    )));
+                }
+                RebaseMode::Exception => col.clone(),
+            });
+        }
+        Ok(RecordBatch::try_new(schema, rebased_cols)?)
+    }
+}
+
+/// shares a file opener between file streams of parallel reading.
+struct SharedOpener<F: FileOpener>(Arc<F>);
+
+impl<F: FileOpener> FileOpener for SharedOpener<F> {
+    fn open(&self, file_meta: FileMeta) -> Result<FileOpenFuture> {
+        self.0.open(file_meta)
+    }
+}
+
+/// rebinds bucket columns to another projected schema, returns None if any of
+/// them is projected out
+fn reproject_bucket_partitioning(
+    partitioning: &Partitioning,
+    projected_schema: &SchemaRef,
+) -> Option<Partitioning> {
+    let Partitioning::Hash(exprs, num_buckets) = partitioning else {
+        return None;
+    };
+    let exprs = exprs
+        .iter()
+        .map(|expr| {
+            let column = expr.as_any().downcast_ref::<Column>()?;
+            let index = projected_schema.index_of(column.name()).ok()?;
+            Some(Arc::new(Column::new(column.name(), index)) as Arc<dyn PhysicalExpr>)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Partitioning::Hash(exprs, *num_buckets))
+}
+
+/// encodes string partition columns as Dictionary(Int32, Utf8), so that the
+/// partition value is not materialized for every row
+pub fn dictionary_encode_partition_columns(mut base_config: FileScanConfig) -> FileScanConfig {
+    let dictionary_encoded = base_config
+        .table_partition_cols
+        .iter()
+        .map(|(_, data_type)| data_type == &DataType::Utf8)
+        .collect::<Vec<_>>();
+
+    for ((_, data_type), &encoded) in base_config
+        .table_partition_cols
+        .iter_mut()
+        .zip(&dictionary_encoded)
+    {
+        if encoded {
+            *data_type = DataType::Dictionary(
+                Box::new(DataType::Int32),
+                Box::new(std::mem::replace(data_type, DataType::Null)),
+            );
+        }
+    }
+    for file in base_config.file_groups.iter_mut().flatten() {
+        for (value, &encoded) in file.partition_values.iter_mut().zip(&dictionary_encoded) {
+            if encoded {
+                *value = ScalarValue::Dictionary(
+                    Box::new(DataType::Int32),
+                    Box::new(std::mem::replace(value, ScalarValue::Null)),
+                );
+            }
+        }
+    }
+    base_config
+}
+
+/// splits files into ranges for parallel reading, each file is split into at most
+/// max_splits ranges of at least min_range_size bytes. row groups are read by the
+/// range containing their midpoints.
+fn split_file_ranges(
+    files: &[PartitionedFile],
+    max_splits: usize,
+    min_range_size: usize,
+) -> Vec<PartitionedFile> {
+    files
+        .iter()
+        .flat_map(|file| {
+            let (start, end) = match &file.range {
+                Some(range) => (range.start, range.end),
+                None => (0, file.object_meta.size as i64),
+            };
+            let num_splits = ((end - start) as usize / min_range_size).clamp(1, max_splits);
+            let split_size = (end - start + num_splits as i64 - 1) / num_splits as i64;
+            (0..num_splits).map(move |i| {
+                let mut split = file.clone();
+                split.range = Some(FileRange {
+                    start: start + i as i64 * split_size,
+                    end: (start + (i as i64 + 1) * split_size).min(end),
+                });
+                split
+            })
+        })
+        .collect()
+}
+
+/// decodes the original file path, which is base64-encoded into the object meta
+/// location when deserializing the plan
+fn decode_file_path(meta: &ObjectMeta) -> Result<String> {
+    BASE64_URL_SAFE_NO_PAD
+        .decode(meta.location.filename().expect("missing filename"))
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .map_err(|_| {
+            DataFusionError::Execution(format!(
+                "cannot decode filename: {:?}",
+                meta.location.filename()
+            ))
+        })
+}
+
 #[derive(Clone)]
 pub struct FsReaderFactory {
     fs_provider: Arc<FsProvider>,
+    metadata_cache: Option<&'static ParquetMetadataCache>,
+    read_ahead_size: usize,
+    read_ranges_coalesce_gap: usize,
+    enable_page_index: bool,
+    bloom_filter_predicates: Arc<Vec<BloomFilterPredicate>>,
+    schema_matcher: Arc<FileSchemaMatcher>,
 }
 
 impl FsReaderFactory {
-    pub fn new(fs_provider: Arc<FsProvider>) -> Self {
-        Self { fs_provider }
+    pub fn new(
+        fs_provider: Arc<FsProvider>,
+        metadata_cache: Option<&'static ParquetMetadataCache>,
+        read_ahead_size: usize,
+        read_ranges_coalesce_gap: usize,
+        enable_page_index: bool,
+        bloom_filter_predicates: Arc<Vec<BloomFilterPredicate>>,
+        schema_matcher: Arc<FileSchemaMatcher>,
+    ) -> Self {
+        Self {
+            fs_provider,
+            metadata_cache,
+            read_ahead_size,
+            read_ranges_coalesce_gap,
+            enable_page_index,
+            bloom_filter_predicates,
+            schema_matcher,
+        }
+    }
+}
+
+static PARQUET_METADATA_CACHE: OnceCell<ParquetMetadataCache> = OnceCell::new();
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ParquetMetadataCacheKey {
+    location: String,
+    last_modified_ns: i64,
+    size: usize,
+}
+
+impl ParquetMetadataCacheKey {
+    fn new(meta: &ObjectMeta) -> Self {
+        Self {
+            location: meta.location.to_string(),
+            last_modified_ns: meta.last_modified.timestamp_nanos_opt().unwrap_or_default(),
+            size: meta.size,
+        }
+    }
+}
+
+/// executor-wide lru cache of raw parquet footers, so that footers are not
+/// fetched and parsed again in repeated scans of the same files.
+pub struct ParquetMetadataCache {
+    capacity: usize,
+    entries: Mutex<ParquetMetadataCacheEntries>,
+}
+
+#[derive(Default)]
+struct ParquetMetadataCacheEntries {
+    map: HashMap<ParquetMetadataCacheKey, (Arc<ParquetMetaData>, u64)>,
+    access_seq: u64,
+}
+
+impl ParquetMetadataCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::default(),
+        }
+    }
+
+    fn get(&self, key: &ParquetMetadataCacheKey) -> Option<Arc<ParquetMetaData>> {
+        let mut entries = self.entries.lock();
+        entries.access_seq += 1;
+        let access_seq = entries.access_seq;
+        let (metadata, last_access_seq) = entries.map.get_mut(key)?;
+        *last_access_seq = access_seq;
+        Some(metadata.clone())
+    }
+
+    fn put(&self, key: ParquetMetadataCacheKey, metadata: Arc<ParquetMetaData>) {
+        let mut entries = self.entries.lock();
+        entries.access_seq += 1;
+        let access_seq = entries.access_seq;
+
+        // evict the least recently used entry
+        if entries.map.len() >= self.capacity && !entries.map.contains_key(&key) {
+            let lru_key = entries
+                .map
+                .iter()
+                .min_by_key(|(_, (_, last_access_seq))| *last_access_seq)
+                .map(|(key, _)| key.clone());
+            if let Some(lru_key) = lru_key {
+                entries.map.remove(&lru_key);
+            }
+        }
+        entries.map.insert(key, (metadata, access_seq));
     }
 }
 
@@ -308,6 +970,13 @@ impl ParquetFileReaderFactory for FsReaderFactory {
     ) -> Result<Box<dyn AsyncFileReader + Send>> {
         let reader = ParquetFileReaderRef(Arc::new(ParquetFileReader {
             fs_provider: self.fs_provider.clone(),
+            metadata_cache: self.metadata_cache,
+            read_ahead_size: self.read_ahead_size,
+            read_ranges_coalesce_gap: self.read_ranges_coalesce_gap,
+            enable_page_index: self.enable_page_index,
+            bloom_filter_predicates: self.bloom_filter_predicates.clone(),
+            schema_matcher: self.schema_matcher.clone(),
+            file_metadata_cell: file_meta.extensions.clone(),
             input: OnceCell::new(),
             metrics: ParquetFileMetrics::new(
                 partition_index,
@@ -326,6 +995,13 @@ impl ParquetFileReaderFactory for FsReaderFactory {
 
 struct ParquetFileReader {
     fs_provider: Arc<FsProvider>,
+    metadata_cache: Option<&'static ParquetMetadataCache>,
+    read_ahead_size: usize,
+    read_ranges_coalesce_gap: usize,
+    enable_page_index: bool,
+    bloom_filter_predicates: Arc<Vec<BloomFilterPredicate>>,
+    schema_matcher: Arc<FileSchemaMatcher>,
+    file_metadata_cell: Option<Arc<dyn Any + Send + Sync>>,
     input: OnceCell<Arc<FsDataInputStream>>,
     meta: ObjectMeta,
     metrics: ParquetFileMetrics,
@@ -339,17 +1015,12 @@ impl ParquetFileReader {
         let input = self
             .input
             .get_or_try_init(|| {
-                let path = BASE64_URL_SAFE_NO_PAD
-                    .decode(self.meta.location.filename().expect("missing filename"))
-                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
-                    .map_err(|_| {
-                        DataFusionError::Execution(format!(
-                            "cannot decode filename: {:?}",
-                            self.meta.location.filename()
-                        ))
-                    })?;
+                let path = decode_file_path(&self.meta)?;
                 let fs = self.fs_provider.provide(&path)?;
-                Ok(Arc::new(fs.open(&path)?))
+                let input = fs
+                    .open(&path)?
+                    .with_read_ahead(self.meta.size as u64, self.read_ahead_size);
+                Ok(Arc::new(input))
             })
             .map_err(|e| ParquetError::External(e))?;
         Ok(input.clone())
@@ -378,26 +1049,1256 @@ impl AsyncFileReader for ParquetFileReaderRef {
         .boxed()
     }
 
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Vec<Bytes>>> {
+        let inner = self.0.clone();
+        async move {
+            // read coalesced ranges and slice the requested ranges from them, reading
+            // pages selected by page index usually produces many small ranges
+            let coalesced_ranges = coalesce_ranges(&ranges, inner.read_ranges_coalesce_gap);
+            let mut coalesced_bytes = Vec::with_capacity(coalesced_ranges.len());
+            for range in &coalesced_ranges {
+                inner.metrics.bytes_scanned.add(range.end - range.start);
+                coalesced_bytes.push(
+                    inner
+                        .read_fully(range.clone())
+                        .map_err(|e| ParquetError::External(Box::new(e)))?,
+                );
+            }
+            Ok(ranges
+                .iter()
+                .map(|range| {
+                    let idx = coalesced_ranges.partition_point(|r| r.start <= range.start) - 1;
+                    let offset = coalesced_ranges[idx].start;
+                    coalesced_bytes[idx].slice(range.start - offset..range.end - offset)
+                })
+                .collect())
+        }
+        .boxed()
+    }
+
     fn get_metadata(
         &mut self,
     ) -> BoxFuture<'_, datafusion::parquet::errors::Result<Arc<ParquetMetaData>>> {
         let inner = self.0.clone();
+        let mut reader = self.clone();
         let meta_size = inner.meta.size;
         let size_hint = Some(2097152);
-        fetch_parquet_metadata(
-            move |range| {
-                let inner = inner.clone();
-                inner.metrics.bytes_scanned.add(range.end - range.start);
-                async move {
-                    inner
-                        .read_fully(range)
-                        .map_err(|e| ParquetError::External(Box::new(e)))
+        async move {
+            let cache_key = ParquetMetadataCacheKey::new(&inner.meta);
+            let metadata_cache = inner.metadata_cache;
+            let enable_page_index = inner.enable_page_index;
+            let cached_metadata = metadata_cache.and_then(|cache| cache.get(&cache_key));
+            let mut metadata_updated = cached_metadata.is_none();
+            let mut metadata = match cached_metadata {
+                Some(metadata) => metadata.as_ref().clone(),
+                None => {
+                    fetch_parquet_metadata(
+                        move |range| {
+                            let inner = inner.clone();
+                            inner.metrics.bytes_scanned.add(range.end - range.start);
+                            async move {
+                                inner
+                                    .read_fully(range)
+                                    .map_err(|e| ParquetError::External(Box::new(e)))
+                            }
+                        },
+                        meta_size,
+                        size_hint,
+                    )
+                    .await?
                 }
-            },
-            meta_size,
-            size_hint,
-        )
-        .and_then(|metadata| futures::future::ok(Arc::new(metadata)))
+            };
+
+            // page indexes are loaded with the footer and cached together, so that
+            // they are not loaded again by the parquet reader on every opening
+            if enable_page_index && metadata.column_index().is_none() {
+                let mut loader = MetadataLoader::new(&mut reader, metadata);
+                loader.load_page_index(true, true).await?;
+                metadata = loader.finish();
+                metadata_updated |= metadata.column_index().is_some();
+            }
+            if metadata_updated {
+                if let Some(cache) = metadata_cache {
+                    cache.put(cache_key, Arc::new(metadata.clone()));
+                }
+            }
+
+            // file columns are renamed to the matched table columns, so that they
+            // can be found by name in later scanning
+            let metadata = reader.0.schema_matcher.rename_file_columns(metadata)?;
+
+            // nested fields not required by the table are removed from metadata, so
+            // they are never read
+            let metadata = reader.0.schema_matcher.prune_nested_columns(metadata)?;
+
+            // pruned row groups are removed from metadata, so they are never read
+            let metadata = Arc::new(reader.prune_row_groups_by_bloom_filter(metadata)?);
+
+            // publish metadata to the opener for rebasing dates/timestamps
+            if let Some(cell) = reader
+                .0
+                .file_metadata_cell
+                .as_ref()
+                .and_then(|cell| cell.downcast_ref::<FileMetadataCell>())
+            {
+                let _ = cell.set(metadata.clone());
+            }
+            Ok(metadata)
+        }
         .boxed()
     }
 }
+
+impl ParquetFileReaderRef {
+    fn prune_row_groups_by_bloom_filter(
+        &self,
+        metadata: ParquetMetaData,
+    ) -> datafusion::parquet::errors::Result<ParquetMetaData> {
+        let predicates = &self.0.bloom_filter_predicates;
+        if predicates.is_empty() {
+            return Ok(metadata);
+        }
+
+        // only top-level columns with the same types in file and table are supported
+        let schema_descr = metadata.file_metadata().schema_descr();
+        let file_arrow_schema = parquet_to_arrow_schema(schema_descr, None)?;
+        let predicate_cols = predicates
+            .iter()
+            .filter_map(|predicate| {
+                let field = file_arrow_schema
+                    .field_with_name(&predicate.col_name)
+                    .ok()?;
+                if field.data_type() != &predicate.data_type {
+                    return None;
+                }
+                let col_idx = schema_descr.columns().iter().position(|col| {
+                    let parts = col.path().parts();
+                    parts.len() == 1 && parts[0] == predicate.col_name
+                })?;
+                Some((col_idx, predicate))
+            })
+            .collect::<Vec<_>>();
+        if predicate_cols.is_empty() {
+            return Ok(metadata);
+        }
+
+        let mut row_groups = vec![];
+        let mut kept_row_groups = vec![];
+        for (row_group_idx, row_group) in metadata.row_groups().iter().enumerate() {
+            let mut pruned = false;
+            for &(col_idx, predicate) in &predicate_cols {
+                let column = row_group.column(col_idx);
+                if let Some(sbbf) = Sbbf::read_from_column_chunk(column, Arc::new(self.clone()))? {
+                    if !predicate.values.iter().any(|v| sbbf_may_contain(&sbbf, v)) {
+                        pruned = true;
+                        break;
+                    }
+                }
+            }
+            if pruned {
+                self.0.metrics.row_groups_pruned.add(1);
+            } else {
+                row_groups.push(row_group.clone());
+                kept_row_groups.push(row_group_idx);
+            }
+        }
+        Ok(rebuild_metadata(
+            &metadata,
+            metadata.file_metadata().clone(),
+            row_groups,
+            &kept_row_groups,
+            None,
+        ))
+    }
+}
+
+impl Length for ParquetFileReaderRef {
+    fn len(&self) -> u64 {
+        self.0.meta.size as u64
+    }
+}
+
+// used for reading bloom filters
+impl ChunkReader for ParquetFileReaderRef {
+    type T = FsRead;
+
+    fn get_read(&self, start: u64) -> datafusion::parquet::errors::Result<Self::T> {
+        Ok(FsRead {
+            reader: self.clone(),
+            pos: start as usize,
+        })
+    }
+
+    fn get_bytes(&self, start: u64, length: usize) -> datafusion::parquet::errors::Result<Bytes> {
+        let start = start as usize;
+        let end = (start + length).min(self.0.meta.size);
+        self.0.metrics.bytes_scanned.add(end - start);
+        self.0
+            .read_fully(start..end)
+            .map_err(|e| ParquetError::External(Box::new(e)))
+    }
+}
+
+struct FsRead {
+    reader: ParquetFileReaderRef,
+    pos: usize,
+}
+
+impl Read for FsRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.reader.0.meta.size - self.pos);
+        let bytes = self
+            .reader
+            .get_bytes(self.pos as u64, len)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        buf[..len].copy_from_slice(&bytes);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// how top-level columns in parquet files are matched with the table schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaMatchMode {
+    Name,
+    CaseInsensitiveName,
+    /// matches by parquet field ids of table columns, columns without field ids
+    /// are matched by name.
+    FieldId(Vec<Option<i32>>),
+}
+
+#[derive(Debug)]
+pub struct FileSchemaMatcher {
+    table_schema: SchemaRef,
+    mode: SchemaMatchMode,
+}
+
+impl FileSchemaMatcher {
+    fn rename_file_columns(
+        &self,
+        metadata: ParquetMetaData,
+    ) -> datafusion::parquet::errors::Result<ParquetMetaData> {
+        let file_metadata = metadata.file_metadata();
+        let root = file_metadata.schema_descr().root_schema();
+        let renames = self.match_column_names(root.get_fields());
+        if renames.iter().all(|rename| rename.is_none()) {
+            return Ok(metadata);
+        }
+
+        let fields = root
+            .get_fields()
+            .iter()
+            .zip(renames)
+            .map(|(field, rename)| match rename {
+                Some(name) => Ok(Arc::new(rename_parquet_type(field, &name)?)),
+                None => Ok(field.clone()),
+            })
+            .collect::<datafusion::parquet::errors::Result<Vec<_>>>()?;
+        let renamed_root = ParquetType::group_type_builder(root.name())
+            .with_fields(fields)
+            .build()?;
+        let renamed_file_metadata = FileMetaData::new(
+            file_metadata.version(),
+            file_metadata.num_rows(),
+            file_metadata.created_by().map(|s| s.to_string()),
+            file_metadata.key_value_metadata().cloned(),
+            Arc::new(SchemaDescriptor::new(Arc::new(renamed_root))),
+            file_metadata.column_orders().cloned(),
+        );
+        Ok(rebuild_metadata(
+            &metadata,
+            renamed_file_metadata,
+            metadata.row_groups().to_vec(),
+            &(0..metadata.num_row_groups()).collect::<Vec<_>>(),
+            None,
+        ))
+    }
+
+    /// removes nested fields which are not in the table schema (pruned by spark's
+    /// nested schema pruning) from file schema and row groups.
+    fn prune_nested_columns(
+        &self,
+        metadata: ParquetMetaData,
+    ) -> datafusion::parquet::errors::Result<ParquetMetaData> {
+        let file_metadata = metadata.file_metadata();
+        let root = file_metadata.schema_descr().root_schema();
+        let mut pruner = NestedColumnPruner {
+            case_insensitive: matches!(self.mode, SchemaMatchMode::CaseInsensitiveName),
+            leaf_offset: 0,
+            kept_leaves: vec![],
+            renamed: false,
+        };
+        let fields = root
+            .get_fields()
+            .iter()
+            .map(
+                |field| match self.table_schema.field_with_name(field.name()) {
+                    Ok(table_field) => pruner.prune(field, table_field.data_type()),
+                    Err(_) => Ok(pruner.keep(field)),
+                },
+            )
+            .collect::<datafusion::parquet::errors::Result<Vec<_>>>()?;
+        let kept_leaves = pruner.kept_leaves;
+        if kept_leaves.len() == file_metadata.schema_descr().num_columns() && !pruner.renamed {
+            return Ok(metadata);
+        }
+
+        let pruned_root = ParquetType::group_type_builder(root.name())
+            .with_fields(fields)
+            .build()?;
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(pruned_root)));
+        let pruned_file_metadata = FileMetaData::new(
+            file_metadata.version(),
+            file_metadata.num_rows(),
+            file_metadata.created_by().map(|s| s.to_string()),
+            file_metadata.key_value_metadata().cloned(),
+            schema_descr.clone(),
+            file_metadata
+                .column_orders()
+                .map(|orders| kept_leaves.iter().map(|&i| orders[i]).collect()),
+        );
+        let pruned_row_groups = metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| {
+                RowGroupMetaData::builder(schema_descr.clone())
+                    .set_num_rows(row_group.num_rows())
+                    .set_total_byte_size(row_group.total_byte_size())
+                    .set_column_metadata(
+                        kept_leaves
+                            .iter()
+                            .map(|&i| row_group.column(i).clone())
+                            .collect(),
+                    )
+                    .build()
+            })
+            .collect::<datafusion::parquet::errors::Result<Vec<_>>>()?;
+        Ok(rebuild_metadata(
+            &metadata,
+            pruned_file_metadata,
+            pruned_row_groups,
+            &(0..metadata.num_row_groups()).collect::<Vec<_>>(),
+            Some(&kept_leaves),
+        ))
+    }
+
+    /// returns new names of file columns, None if not renamed.
+    fn match_column_names(&self, file_fields: &[Arc<ParquetType>]) -> Vec<Option<String>> {
+        let table_fields = self.table_schema.fields();
+        match &self.mode {
+            SchemaMatchMode::Name => vec![None; file_fields.len()],
+            SchemaMatchMode::CaseInsensitiveName => file_fields
+                .iter()
+                .map(|file_field| {
+                    let name = file_field.name();
+                    if table_fields.iter().any(|f| f.name() == name) {
+                        return None;
+                    }
+                    // ambiguous columns are not matched
+                    let matched = table_fields
+                        .iter()
+                        .filter(|f| f.name().eq_ignore_ascii_case(name))
+                        .collect::<Vec<_>>();
+                    let matched_name = matched.first().map(|f| f.name()).filter(|matched_name| {
+                        matched.len() == 1 && file_fields.iter().all(|f| f.name() != *matched_name)
+                    });
+                    matched_name.cloned()
+                })
+                .collect(),
+            SchemaMatchMode::FieldId(field_ids) => file_fields
+                .iter()
+                .enumerate()
+                .map(|(i, file_field)| {
+                    let basic_info = file_field.get_basic_info();
+                    let file_field_id = basic_info.has_id().then(|| basic_info.id());
+                    let matched = file_field_id
+                        .and_then(|id| field_ids.iter().position(|&field_id| field_id == Some(id)));
+                    match matched {
+                        Some(idx) if table_fields[idx].name() != file_field.name() => {
+                            Some(table_fields[idx].name().clone())
+                        }
+                        Some(_) => None,
+                        None => {
+                            // table columns with field ids must not be matched by name
+                            let name_matched = table_fields
+                                .iter()
+                                .position(|f| f.name() == file_field.name())
+                                .filter(|&idx| field_ids.get(idx).cloned().flatten().is_some());
+                            name_matched.map(|_| format!("__unmatched_col_{i}"))
+                        }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn rename_parquet_type(
+    tp: &ParquetType,
+    name: &str,
+) -> datafusion::parquet::errors::Result<ParquetType> {
+    let basic_info = tp.get_basic_info();
+    let id = basic_info.has_id().then(|| basic_info.id());
+    match tp {
+        ParquetType::PrimitiveType {
+            physical_type,
+            type_length,
+            scale,
+            precision,
+            ..
+        } => ParquetType::primitive_type_builder(name, *physical_type)
+            .with_repetition(basic_info.repetition())
+            .with_converted_type(basic_info.converted_type())
+            .with_logical_type(basic_info.logical_type())
+            .with_length(*type_length)
+            .with_precision(*precision)
+            .with_scale(*scale)
+            .with_id(id)
+            .build(),
+        ParquetType::GroupType { fields, .. } => ParquetType::group_type_builder(name)
+            .with_repetition(basic_info.repetition())
+            .with_converted_type(basic_info.converted_type())
+            .with_logical_type(basic_info.logical_type())
+            .with_fields(fields.clone())
+            .with_id(id)
+            .build(),
+    }
+}
+
+/// prunes nested fields of parquet types by the corresponding table types, and
+/// collects indices of the kept leaf columns. fields matched case-insensitively
+/// are renamed to the table field names.
+struct NestedColumnPruner {
+    case_insensitive: bool,
+    leaf_offset: usize,
+    kept_leaves: Vec<usize>,
+    renamed: bool,
+}
+
+impl NestedColumnPruner {
+    fn keep(&mut self, tp: &TypePtr) -> TypePtr {
+        let num_leaves = num_parquet_leaves(tp);
+        self.kept_leaves
+            .extend(self.leaf_offset..self.leaf_offset + num_leaves);
+        self.leaf_offset += num_leaves;
+        tp.clone()
+    }
+
+    fn skip(&mut self, tp: &TypePtr) {
+        self.leaf_offset += num_parquet_leaves(tp);
+    }
+
+    fn prune(
+        &mut self,
+        tp: &TypePtr,
+        table_type: &DataType,
+    ) -> datafusion::parquet::errors::Result<TypePtr> {
+        if !tp.is_group() {
+            return Ok(self.keep(tp));
+        }
+        let basic_info = tp.get_basic_info();
+        let is_list = basic_info.converted_type() == ConvertedType::LIST
+            || basic_info.logical_type() == Some(LogicalType::List);
+        let is_map = matches!(
+            basic_info.converted_type(),
+            ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE
+        ) || basic_info.logical_type() == Some(LogicalType::Map);
+        let fields = tp.get_fields();
+
+        match table_type {
+            DataType::Struct(table_fields) if !is_list && !is_map => {
+                let matched = fields
+                    .iter()
+                    .map(|field| self.match_field(field.name(), table_fields))
+                    .collect::<Vec<_>>();
+                if matched.iter().all(|m| m.is_none()) {
+                    return Ok(self.keep(tp));
+                }
+                let mut pruned_fields = vec![];
+                for (field, table_field) in fields.iter().zip(matched) {
+                    match table_field {
+                        Some(table_field) => {
+                            let pruned = self.prune(field, table_field.data_type())?;
+                            if pruned.name() != table_field.name() {
+                                self.renamed = true;
+                                pruned_fields.push(Arc::new(rename_parquet_type(
+                                    &pruned,
+                                    table_field.name(),
+                                )?));
+                            } else {
+                                pruned_fields.push(pruned);
+                            }
+                        }
+                        None => self.skip(field),
+                    }
+                }
+                rebuild_parquet_group(tp, pruned_fields)
+            }
+            DataType::List(element_field) | DataType::LargeList(element_field) if is_list => {
+                // only standard 3-level lists are pruned
+                match fields.as_slice() {
+                    [repeated]
+                        if repeated.is_group()
+                            && repeated.get_fields().len() == 1
+                            && repeated.name() != "array"
+                            && !repeated.name().ends_with("_tuple") =>
+                    {
+                        let element =
+                            self.prune(&repeated.get_fields()[0], element_field.data_type())?;
+                        rebuild_parquet_group(
+                            tp,
+                            vec![rebuild_parquet_group(repeated, vec![element])?],
+                        )
+                    }
+                    _ => Ok(self.keep(tp)),
+                }
+            }
+            DataType::Map(entries_field, _) if is_map => {
+                match (fields.as_slice(), entries_field.data_type()) {
+                    ([key_value], DataType::Struct(entry_fields))
+                        if key_value.is_group() && key_value.get_fields().len() == 2 =>
+                    {
+                        let key = self.keep(&key_value.get_fields()[0]);
+                        let value =
+                            self.prune(&key_value.get_fields()[1], entry_fields[1].data_type())?;
+                        rebuild_parquet_group(
+                            tp,
+                            vec![rebuild_parquet_group(key_value, vec![key, value])?],
+                        )
+                    }
+                    _ => Ok(self.keep(tp)),
+                }
+            }
+            _ => Ok(self.keep(tp)),
+        }
+    }
+
+    fn match_field<'a>(&self, name: &str, table_fields: &'a Fields) -> Option<&'a FieldRef> {
+        if let Some(table_field) = table_fields.iter().find(|f| f.name() == name) {
+            return Some(table_field);
+        }
+        if self.case_insensitive {
+            // ambiguous fields are not matched
+            let matched = table_fields
+                .iter()
+                .filter(|f| f.name().eq_ignore_ascii_case(name))
+                .collect::<Vec<_>>();
+            if matched.len() == 1 {
+                return Some(matched[0]);
+            }
+        }
+        None
+    }
+}
+
+fn num_parquet_leaves(tp: &ParquetType) -> usize {
+    if tp.is_group() {
+        tp.get_fields().iter().map(|f| num_parquet_leaves(f)).sum()
+    } else {
+        1
+    }
+}
+
+fn rebuild_parquet_group(
+    tp: &ParquetType,
+    fields: Vec<TypePtr>,
+) -> datafusion::parquet::errors::Result<TypePtr> {
+    let basic_info = tp.get_basic_info();
+    let id = basic_info.has_id().then(|| basic_info.id());
+    Ok(Arc::new(
+        ParquetType::group_type_builder(tp.name())
+            .with_repetition(basic_info.repetition())
+            .with_converted_type(basic_info.converted_type())
+            .with_logical_type(basic_info.logical_type())
+            .with_fields(fields)
+            .with_id(id)
+            .build()?,
+    ))
+}
+
+/// equality/in-list predicate on a column, row groups can be pruned if none of
+/// the values are contained in the bloom filter.
+#[derive(Debug, Clone)]
+pub struct BloomFilterPredicate {
+    col_name: String,
+    data_type: DataType,
+    values: Vec<ScalarValue>,
+}
+
+fn collect_bloom_filter_predicates(
+    predicate: &Arc<dyn PhysicalExpr>,
+    table_schema: &SchemaRef,
+) -> Vec<BloomFilterPredicate> {
+    let mut predicates = vec![];
+    for expr in split_conjunction(predicate) {
+        let (col, value_exprs) = if let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() {
+            if *binary.op() != Operator::Eq {
+                continue;
+            }
+            if binary.left().as_any().is::<Column>() {
+                (binary.left(), vec![binary.right().clone()])
+            } else {
+                (binary.right(), vec![binary.left().clone()])
+            }
+        } else if let Some(in_list) = expr.as_any().downcast_ref::<InListExpr>() {
+            if in_list.negated() {
+                continue;
+            }
+            (in_list.expr(), in_list.list().to_vec())
+        } else {
+            continue;
+        };
+
+        let Some(col) = col.as_any().downcast_ref::<Column>() else {
+            continue;
+        };
+        let data_type = table_schema.field(col.index()).data_type();
+        let values = value_exprs
+            .iter()
+            .map(|expr| {
+                let literal = expr.as_any().downcast_ref::<Literal>()?;
+                (&literal.value().get_datatype() == data_type).then(|| literal.value().clone())
+            })
+            .collect::<Option<Vec<_>>>();
+
+        // floats are not supported because -0.0 and 0.0 are hashed differently
+        let supported = matches!(
+            data_type,
+            DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::Date32
+                | DataType::Utf8
+                | DataType::Binary
+        );
+        if let Some(values) = values.filter(|_| supported) {
+            predicates.push(BloomFilterPredicate {
+                col_name: col.name().to_string(),
+                data_type: data_type.clone(),
+                values,
+            });
+        }
+    }
+    predicates
+}
+
+/// checks whether the value may be contained in the bloom filter, values are
+/// hashed as their parquet physical types.
+fn sbbf_may_contain(sbbf: &Sbbf, value: &ScalarValue) -> bool {
+    match value {
+        ScalarValue::Int8(Some(v)) => sbbf.check(&(*v as i32)),
+        ScalarValue::Int16(Some(v)) => sbbf.check(&(*v as i32)),
+        ScalarValue::Int32(Some(v)) | ScalarValue::Date32(Some(v)) => sbbf.check(v),
+        ScalarValue::Int64(Some(v)) => sbbf.check(v),
+        ScalarValue::Utf8(Some(v)) => sbbf.check(&ByteArray::from(v.as_bytes().to_vec())),
+        ScalarValue::Binary(Some(v)) => sbbf.check(&ByteArray::from(v.clone())),
+        _ if value.is_null() => false, // null never equals to any value
+        _ => true,
+    }
+}
+
+/// rebuilds metadata with new file metadata and row groups. page indexes of
+/// the kept row groups and leaf columns are carried over, otherwise they would
+/// be dropped and loaded again by the parquet reader.
+fn rebuild_metadata(
+    metadata: &ParquetMetaData,
+    file_metadata: FileMetaData,
+    row_groups: Vec<RowGroupMetaData>,
+    kept_row_groups: &[usize],
+    kept_leaves: Option<&[usize]>,
+) -> ParquetMetaData {
+    ParquetMetaData::new_with_page_index(
+        file_metadata,
+        row_groups,
+        metadata
+            .column_index()
+            .map(|indexes| select_page_indexes(indexes, kept_row_groups, kept_leaves)),
+        metadata
+            .offset_index()
+            .map(|indexes| select_page_indexes(indexes, kept_row_groups, kept_leaves)),
+    )
+}
+
+/// selects page indexes (indexed by row group and leaf column) of the kept row
+/// groups and leaf columns.
+fn select_page_indexes<T: Clone>(
+    indexes: &[Vec<T>],
+    kept_row_groups: &[usize],
+    kept_leaves: Option<&[usize]>,
+) -> Vec<Vec<T>> {
+    kept_row_groups
+        .iter()
+        .map(|&row_group_idx| match kept_leaves {
+            Some(kept_leaves) => kept_leaves
+                .iter()
+                .map(|&leaf_idx| indexes[row_group_idx][leaf_idx].clone())
+                .collect(),
+            None => indexes[row_group_idx].clone(),
+        })
+        .collect()
+}
+
+/// merges byte ranges with gaps not greater than max_gap, the output ranges are
+/// sorted and not overlapped.
+fn coalesce_ranges(ranges: &[Range<usize>], max_gap: usize) -> Vec<Range<usize>> {
+    let mut sorted_ranges = ranges.to_vec();
+    sorted_ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut coalesced: Vec<Range<usize>> = Vec::with_capacity(sorted_ranges.len());
+    for range in sorted_ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end + max_gap => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+    coalesced
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parquet_exec::{
+        cast_columns_to_table_types, coalesce_ranges, collect_bloom_filter_predicates,
+        dictionary_encode_partition_columns, split_file_ranges, FileDatetimeRebaser,
+        FileSchemaMatcher, ParquetExec, ParquetMetadataCache, ParquetMetadataCacheKey,
+        SchemaMatchMode,
+    };
+    use arrow::array::{
+        as_boolean_array, Array, BooleanArray, Date32Array, Int32Array, TimestampMicrosecondArray,
+    };
+    use arrow::datatypes::{DataType, Field, Fields, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::{Result, ScalarValue};
+    use datafusion::datasource::listing::{FileRange, PartitionedFile};
+    use datafusion::datasource::object_store::ObjectStoreUrl;
+    use datafusion::datasource::physical_plan::FileScanConfig;
+    use datafusion::logical_expr::Operator;
+    use datafusion::parquet::file::metadata::{
+        ColumnChunkMetaData, FileMetaData, KeyValue, ParquetMetaData, RowGroupMetaData,
+    };
+    use datafusion::parquet::schema::parser::parse_message_type;
+    use datafusion::parquet::schema::types::SchemaDescriptor;
+    use datafusion::physical_expr::expressions::{BinaryExpr, Column, InListExpr, Literal};
+    use datafusion::physical_expr::PhysicalExpr;
+    use datafusion::physical_plan::{ExecutionPlan, Partitioning, Statistics};
+    use datafusion_ext_commons::datetime_rebase::RebaseMode;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        assert_eq!(coalesce_ranges(&[], 10), vec![]);
+        assert_eq!(
+            coalesce_ranges(&[50..60, 0..10, 15..20, 5..8, 100..120], 10),
+            vec![0..20, 50..60, 100..120]
+        );
+        assert_eq!(coalesce_ranges(&[0..10, 20..30], 0), vec![0..10, 20..30]);
+        assert_eq!(coalesce_ranges(&[0..10, 10..30], 0), vec![0..30]);
+    }
+
+    #[test]
+    fn test_bucket_partitioning() -> Result<()> {
+        let file_groups = (0..4)
+            .map(|i| vec![PartitionedFile::new(format!("bucket_{i}"), 10)])
+            .collect();
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("b", DataType::Int32, true),
+            ])),
+            file_groups,
+            statistics: Statistics::default(),
+            projection: None,
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+            infinite_source: false,
+        };
+        let scan = ParquetExec::new(
+            base_config,
+            "fs".to_string(),
+            None,
+            SchemaMatchMode::Name,
+            RebaseMode::Corrected,
+            RebaseMode::Corrected,
+        );
+        assert!(matches!(
+            scan.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+        assert!(scan
+            .clone()
+            .with_bucket_partitioning(Partitioning::Hash(vec![Arc::new(Column::new("b", 1))], 8))
+            .is_err());
+
+        let scan = scan
+            .with_bucket_partitioning(Partitioning::Hash(vec![Arc::new(Column::new("b", 1))], 4))?;
+        let Partitioning::Hash(exprs, 4) = scan.output_partitioning() else {
+            panic!("expect hash partitioning");
+        };
+        assert_eq!(
+            exprs[0].as_any().downcast_ref::<Column>(),
+            Some(&Column::new("b", 1))
+        );
+
+        // bucket columns are rebinded after reprojection
+        let reprojected = scan.with_projection(vec![1]);
+        let Partitioning::Hash(exprs, 4) = reprojected.output_partitioning() else {
+            panic!("expect hash partitioning");
+        };
+        assert_eq!(
+            exprs[0].as_any().downcast_ref::<Column>(),
+            Some(&Column::new("b", 0))
+        );
+
+        // partitioning is unknown when bucket columns are projected out
+        let reprojected = scan.with_projection(vec![0]);
+        assert!(matches!(
+            reprojected.output_partitioning(),
+            Partitioning::UnknownPartitioning(4)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dictionary_encode_partition_columns() {
+        let mut file = PartitionedFile::new("file".to_string(), 10);
+        file.partition_values =
+            vec![ScalarValue::Utf8(Some("2024-01-01".to_string())), ScalarValue::Int32(Some(1))];
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::local_filesystem(),
+            file_schema: Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)])),
+            file_groups: vec![vec![file]],
+            statistics: Statistics::default(),
+            projection: None,
+            limit: None,
+            table_partition_cols: vec![
+                ("dt".to_string(), DataType::Utf8),
+                ("hour".to_string(), DataType::Int32),
+            ],
+            output_ordering: vec![],
+            infinite_source: false,
+        };
+        let dict_type = |value_type: DataType| {
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(value_type))
+        };
+
+        let encoded = dictionary_encode_partition_columns(base_config);
+        assert_eq!(
+            encoded.table_partition_cols,
+            vec![
+                ("dt".to_string(), dict_type(DataType::Utf8)),
+                ("hour".to_string(), DataType::Int32),
+            ]
+        );
+        assert_eq!(
+            encoded.file_groups[0][0].partition_values,
+            vec![
+                ScalarValue::Dictionary(
+                    Box::new(DataType::Int32),
+                    Box::new(ScalarValue::Utf8(Some("2024-01-01".to_string()))),
+                ),
+                ScalarValue::Int32(Some(1)),
+            ]
+        );
+        let (projected_schema, _, _) = encoded.project();
+        assert_eq!(
+            projected_schema.field(1).data_type(),
+            &dict_type(DataType::Utf8)
+        );
+    }
+
+    #[test]
+    fn test_select_page_indexes() {
+        let indexes = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+        assert_eq!(
+            select_page_indexes(&indexes, &[0, 2], None),
+            vec![vec![1, 2, 3], vec![7, 8, 9]]
+        );
+        assert_eq!(
+            select_page_indexes(&indexes, &[1, 2], Some(&[0, 2])),
+            vec![vec![4, 6], vec![7, 9]]
+        );
+        assert_eq!(
+            select_page_indexes(&indexes, &[], Some(&[1])),
+            Vec::<Vec<i32>>::new()
+        );
+    }
+
+    #[test]
+    fn test_split_file_ranges() {
+        let ranges = |files: &[PartitionedFile], max_splits, min_range_size| {
+            split_file_ranges(files, max_splits, min_range_size)
+                .into_iter()
+                .map(|file| file.range.map(|range| (range.start, range.end)))
+                .collect::<Vec<_>>()
+        };
+        let small = PartitionedFile::new("small".to_string(), 10);
+        let large = PartitionedFile::new("large".to_string(), 100);
+        let mut ranged = PartitionedFile::new("ranged".to_string(), 1000);
+        ranged.range = Some(FileRange {
+            start: 100,
+            end: 150,
+        });
+
+        assert_eq!(ranges(&[small.clone()], 4, 20), vec![Some((0, 10))]);
+        assert_eq!(
+            ranges(&[small, large.clone()], 4, 20),
+            vec![Some((0, 10)), Some((0, 25)), Some((25, 50)), Some((50, 75)), Some((75, 100)),]
+        );
+        assert_eq!(
+            ranges(&[large], 4, 40),
+            vec![Some((0, 50)), Some((50, 100))]
+        );
+        assert_eq!(
+            ranges(&[ranged], 4, 20),
+            vec![Some((100, 125)), Some((125, 150))]
+        );
+    }
+
+    #[test]
+    fn test_cast_columns_to_table_types() -> Result<()> {
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let predicate: Arc<dyn PhysicalExpr> = Arc::new(BinaryExpr::new(
+            Arc::new(Column::new("a", 0)),
+            Operator::Gt,
+            Arc::new(Literal::new(ScalarValue::Int64(Some(1)))),
+        ));
+        let casted = cast_columns_to_table_types(&predicate, &table_schema)?;
+
+        // file column "a" is stored as int32
+        let file_batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)])),
+            vec![Arc::new(Int32Array::from(vec![Some(0), Some(2), None]))],
+        )?;
+        let result = casted
+            .evaluate(&file_batch)?
+            .into_array(file_batch.num_rows());
+        assert_eq!(
+            as_boolean_array(&result),
+            &BooleanArray::from(vec![Some(false), Some(true), None])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_bloom_filter_predicates() -> Result<()> {
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Float64, true),
+        ]));
+        let col = |name: &str| -> Arc<dyn PhysicalExpr> {
+            Arc::new(Column::new_with_schema(name, &table_schema).unwrap())
+        };
+        let lit = |value: ScalarValue| -> Arc<dyn PhysicalExpr> { Arc::new(Literal::new(value)) };
+        let and = |l: Arc<dyn PhysicalExpr>, r: Arc<dyn PhysicalExpr>| -> Arc<dyn PhysicalExpr> {
+            Arc::new(BinaryExpr::new(l, Operator::And, r))
+        };
+
+        let predicate = and(
+            and(
+                Arc::new(BinaryExpr::new(
+                    lit(ScalarValue::Int32(Some(1))),
+                    Operator::Eq,
+                    col("a"),
+                )),
+                Arc::new(InListExpr::new(
+                    col("b"),
+                    vec![
+                        lit(ScalarValue::Utf8(Some("x".to_string()))),
+                        lit(ScalarValue::Utf8(Some("y".to_string()))),
+                    ],
+                    false,
+                    None,
+                )),
+            ),
+            and(
+                Arc::new(BinaryExpr::new(
+                    col("c"),
+                    Operator::Eq,
+                    lit(ScalarValue::Float64(Some(1.0))),
+                )),
+                Arc::new(BinaryExpr::new(
+                    col("a"),
+                    Operator::Gt,
+                    lit(ScalarValue::Int32(Some(1))),
+                )),
+            ),
+        );
+        let predicates = collect_bloom_filter_predicates(&predicate, &table_schema);
+        let predicates = predicates
+            .iter()
+            .map(|p| (p.col_name.as_str(), p.values.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            predicates,
+            vec![
+                ("a", vec![ScalarValue::Int32(Some(1))]),
+                (
+                    "b",
+                    vec![
+                        ScalarValue::Utf8(Some("x".to_string())),
+                        ScalarValue::Utf8(Some("y".to_string())),
+                    ]
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_column_names() -> Result<()> {
+        let file_schema = parse_message_type(
+            "message spark_schema {
+                optional int32 ID = 1;
+                optional binary Name (UTF8) = 2;
+                optional binary name (UTF8) = 3;
+                optional int64 v = 4;
+                optional int64 score = 5;
+            }",
+        )?;
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Int64, true),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let match_column_names = |mode| {
+            let matcher = FileSchemaMatcher {
+                table_schema: table_schema.clone(),
+                mode,
+            };
+            matcher.match_column_names(file_schema.get_fields())
+        };
+
+        assert_eq!(
+            match_column_names(SchemaMatchMode::Name),
+            vec![None, None, None, None, None]
+        );
+        assert_eq!(
+            match_column_names(SchemaMatchMode::CaseInsensitiveName),
+            vec![Some("id".to_string()), None, None, None, None]
+        );
+        assert_eq!(
+            match_column_names(SchemaMatchMode::FieldId(vec![
+                Some(1),
+                Some(2),
+                Some(4),
+                Some(5)
+            ])),
+            vec![
+                Some("id".to_string()),
+                Some("name".to_string()),
+                Some("__unmatched_col_2".to_string()),
+                Some("score".to_string()),
+                Some("value".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_metadata_cache() -> Result<()> {
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(parse_message_type(
+            "message spark_schema { optional int32 a; }",
+        )?)));
+        let metadata = Arc::new(ParquetMetaData::new(
+            FileMetaData::new(1, 0, None, None, schema_descr, None),
+            vec![],
+        ));
+        let key = |location: &str, size| ParquetMetadataCacheKey {
+            location: location.to_string(),
+            last_modified_ns: 0,
+            size,
+        };
+
+        let cache = ParquetMetadataCache::new(2);
+        cache.put(key("a", 10), metadata.clone());
+        cache.put(key("b", 10), metadata.clone());
+        assert!(cache.get(&key("a", 10)).is_some());
+        assert!(cache.get(&key("a", 20)).is_none()); // file changed
+
+        // b is the least recently used entry
+        cache.put(key("c", 10), metadata.clone());
+        assert!(cache.get(&key("a", 10)).is_some());
+        assert!(cache.get(&key("b", 10)).is_none());
+        assert!(cache.get(&key("c", 10)).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_datetime_rebaser() -> Result<()> {
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(parse_message_type(
+            "message spark_schema {
+                optional int32 d (DATE);
+                optional int96 ts;
+            }",
+        )?)));
+        let file_metadata = |kvs: &[(&str, &str)]| {
+            let kvs = kvs
+                .iter()
+                .map(|(k, v)| KeyValue::new(k.to_string(), v.to_string()))
+                .collect();
+            ParquetMetaData::new(
+                FileMetaData::new(1, 0, None, Some(kvs), schema_descr.clone(), None),
+                vec![],
+            )
+        };
+
+        // 0001-01-01 in the hybrid calendar
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("d", DataType::Date32, true),
+                Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+            ])),
+            vec![
+                Arc::new(Date32Array::from(vec![-719164])),
+                Arc::new(TimestampMicrosecondArray::from(vec![0])),
+            ],
+        )?;
+
+        // written by spark 2.x, always rebased
+        let rebaser = FileDatetimeRebaser::new(
+            &file_metadata(&[("org.apache.spark.version", "2.4.8")]),
+            RebaseMode::Exception,
+            RebaseMode::Exception,
+        );
+        assert_eq!(rebaser.datetime_rebase_mode, RebaseMode::Legacy);
+        assert_eq!(rebaser.int96_rebase_mode, RebaseMode::Legacy);
+        assert_eq!(rebaser.int96_cols, HashSet::from(["ts".to_string()]));
+        let rebased = rebaser.rebase_batch(batch.clone())?;
+        assert_eq!(
+            rebased.column(0).as_ref(),
+            &Date32Array::from(vec![-719162]) as &dyn Array,
+        );
+
+        // written by spark 3.x, rebased only if marked as legacy
+        let rebaser = FileDatetimeRebaser::new(
+            &file_metadata(&[
+                ("org.apache.spark.version", "3.0.1"),
+                ("org.apache.spark.legacyDateTime", ""),
+            ]),
+            RebaseMode::Exception,
+            RebaseMode::Corrected,
+        );
+        assert_eq!(rebaser.datetime_rebase_mode, RebaseMode::Legacy);
+        assert_eq!(rebaser.int96_rebase_mode, RebaseMode::Legacy);
+        let rebaser = FileDatetimeRebaser::new(
+            &file_metadata(&[("org.apache.spark.version", "3.3.2")]),
+            RebaseMode::Legacy,
+            RebaseMode::Legacy,
+        );
+        assert_eq!(rebaser.datetime_rebase_mode, RebaseMode::Corrected);
+        assert_eq!(rebaser.int96_rebase_mode, RebaseMode::Corrected);
+        assert_eq!(rebaser.rebase_batch(batch.clone())?, batch);
+
+        // written by other writers, fails on ancient dates in EXCEPTION mode
+        let rebaser = FileDatetimeRebaser::new(
+            &file_metadata(&[]),
+            RebaseMode::Exception,
+            RebaseMode::Corrected,
+        );
+        assert!(rebaser.rebase_batch(batch.clone()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_nested_columns() -> Result<()> {
+        let schema_descr = Arc::new(SchemaDescriptor::new(Arc::new(parse_message_type(
+            "message spark_schema {
+                optional int32 id;
+                optional group event {
+                    optional int64 ts;
+                    optional group payload {
+                        optional binary user_id (UTF8);
+                        optional binary content (UTF8);
+                    }
+                }
+                optional group tags (LIST) {
+                    repeated group list {
+                        optional group element {
+                            optional binary k (UTF8);
+                            optional binary v (UTF8);
+                        }
+                    }
+                }
+            }",
+        )?)));
+        let row_group = RowGroupMetaData::builder(schema_descr.clone())
+            .set_num_rows(1)
+            .set_column_metadata(
+                schema_descr
+                    .columns()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, col)| {
+                        ColumnChunkMetaData::builder(col.clone())
+                            .set_data_page_offset(i as i64)
+                            .build()
+                    })
+                    .collect::<datafusion::parquet::errors::Result<_>>()?,
+            )
+            .build()?;
+        let metadata = ParquetMetaData::new(
+            FileMetaData::new(1, 1, None, None, schema_descr, None),
+            vec![row_group],
+        );
+
+        let struct_type = |fields: Vec<Field>| DataType::Struct(Fields::from(fields));
+        let table_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new(
+                "event",
+                struct_type(vec![Field::new(
+                    "payload",
+                    struct_type(vec![Field::new("USER_ID", DataType::Utf8, true)]),
+                    true,
+                )]),
+                true,
+            ),
+            Field::new(
+                "tags",
+                DataType::List(Arc::new(Field::new(
+                    "element",
+                    struct_type(vec![Field::new("v", DataType::Utf8, true)]),
+                    true,
+                ))),
+                true,
+            ),
+        ]));
+        let matcher = FileSchemaMatcher {
+            table_schema,
+            mode: SchemaMatchMode::CaseInsensitiveName,
+        };
+        let pruned = matcher.prune_nested_columns(metadata)?;
+        let pruned_cols = pruned
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|col| col.path().string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pruned_cols,
+            vec!["id", "event.payload.USER_ID", "tags.list.element.v"]
+        );
+        let pruned_offsets = pruned
+            .row_group(0)
+            .columns()
+            .iter()
+            .map(|col| col.data_page_offset())
+            .collect::<Vec<_>>();
+        assert_eq!(pruned_offsets, vec![0, 2, 5]);
+        Ok(())
+    }
+}
@@ -15,42 +15,148 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use arrow::datatypes::SchemaRef;
+use crate::broadcast_join_exec::RecordBatchStreamsWrapperExec;
+use crate::common::clustering::{compute_clustering_keys, ClusteringMethod};
+use crate::common::BatchTaker;
+use crate::sort_exec::SortExec;
+use arrow::array::{
+    as_primitive_array, as_string_array, Array, ArrayRef, Int32Array, Int64Array, Int64Builder,
+    ListBuilder, StringArray, StringBuilder,
+};
+use arrow::compute::SortOptions;
+use arrow::datatypes::{DataType, Field, Int32Type, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
-use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
+use blaze_jni_bridge::{jni_call, jni_call_static, jni_new_global_ref, jni_new_string};
 use datafusion::common::{DataFusionError, Result, Statistics};
 use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::Accumulator;
 use datafusion::parquet::arrow::{parquet_to_arrow_schema, ArrowWriter};
 use datafusion::parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
-use datafusion::parquet::file::properties::{WriterProperties, WriterVersion};
+use datafusion::parquet::file::metadata::KeyValue;
+use datafusion::parquet::file::properties::{
+    EnabledStatistics, WriterProperties, WriterPropertiesBuilder, WriterVersion,
+};
 use datafusion::parquet::schema::parser::parse_message_type;
-use datafusion::parquet::schema::types::SchemaDescriptor;
-use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::parquet::schema::types::{ColumnPath, SchemaDescriptor};
+use datafusion::physical_expr::expressions::{Column, MaxAccumulator, MinAccumulator};
+use datafusion::physical_expr::{PhysicalExpr, PhysicalSortExpr};
 use datafusion::physical_plan::metrics::{
     BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricValue, MetricsSet, Time,
 };
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
-    DisplayAs, DisplayFormatType, EmptyRecordBatchStream, ExecutionPlan, Metric, Partitioning,
-    SendableRecordBatchStream,
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, SendableRecordBatchStream,
 };
 use datafusion_ext_commons::cast::cast;
-use datafusion_ext_commons::hadoop_fs::{FsDataOutputStream, FsProvider};
+use datafusion_ext_commons::datetime_rebase::{
+    contains_datetime, has_ancient_datetime, rebase_gregorian_to_julian_array, RebaseMode,
+};
+use datafusion_ext_commons::hadoop_fs::{FsDataOutputStream, FsProvider, RetryPolicy};
+use datafusion_ext_commons::hyperloglog::HyperLogLog;
+use datafusion_ext_commons::spark_hash::{
+    create_hive_hashes, create_murmur3_hashes, create_partition_ids,
+};
 use futures::stream::once;
 use futures::{StreamExt, TryStreamExt};
-use once_cell::sync::OnceCell;
+use hashbrown::{HashMap, HashSet};
+use jni::objects::{GlobalRef, JObject};
 use parking_lot::Mutex;
 use std::any::Any;
 use std::fmt::Formatter;
-use std::io::Write;
 use std::sync::Arc;
 
+// partition name of null or empty partition values, same as hive
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+// precision of hll sketches for ndvs in file index, about 3% relative error
+const FILE_INDEX_HLL_PRECISION: u32 = 10;
+
+/// bucketing of the written data, rows are written to one file per bucket
+/// (in each partition), optionally sorted within the bucket.
+#[derive(Debug, Clone)]
+pub struct SinkBucketSpec {
+    pub bucket_exprs: Vec<Arc<dyn PhysicalExpr>>,
+    pub num_buckets: usize,
+    pub hash_function: BucketHashFunction,
+    pub sort_exprs: Vec<PhysicalSortExpr>,
+}
+
+/// clustering of the written data, rows are sorted by keys of a space-filling
+/// curve over the clustering columns, so that each file/row group covers small
+/// ranges of all these columns. configured by sink props:
+///  parquet.blaze.clustering.columns: comma-separated data column names
+///  parquet.blaze.clustering.method: zorder (default) or hilbert
+#[derive(Debug, Clone)]
+struct SinkClustering {
+    col_indices: Vec<usize>,
+    method: ClusteringMethod,
+}
+
+impl SinkClustering {
+    /// parses clustering from props, columns are resolved by names in the hive
+    /// schema (case-insensitively like hive), which is ordered as data columns
+    fn try_from_props(props: &[(String, String)], hive_schema: &SchemaRef) -> Result<Option<Self>> {
+        let prop = |name: &str| {
+            props
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim())
+        };
+        let col_indices = match prop("parquet.blaze.clustering.columns") {
+            Some(columns) if !columns.is_empty() => columns
+                .split(',')
+                .map(|name| {
+                    let name = name.trim();
+                    hive_schema
+                        .fields()
+                        .iter()
+                        .position(|field| field.name().eq_ignore_ascii_case(name))
+                        .ok_or_else(|| {
+                            DataFusionError::Plan(format!("clustering column not found: {name}"))
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => return Ok(None),
+        };
+        let method = match prop("parquet.blaze.clustering.method") {
+            Some(method) => ClusteringMethod::parse(method)?,
+            None => ClusteringMethod::ZOrder,
+        };
+        Ok(Some(Self {
+            col_indices,
+            method,
+        }))
+    }
+
+    fn compute_keys(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let cols = self
+            .col_indices
+            .iter()
+            .map(|&i| batch.column(i).clone())
+            .collect::<Vec<_>>();
+        compute_clustering_keys(&cols, self.method)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketHashFunction {
+    /// spark bucketing: pmod(murmur3_hash(cols), num_buckets)
+    Murmur3,
+    /// hive-compatible bucketing: (hive_hash(cols) & Int.MaxValue) % num_buckets
+    HiveHash,
+}
+
 #[derive(Debug)]
 pub struct ParquetSinkExec {
     fs_resource_id: String,
     path: String,
     input: Arc<dyn ExecutionPlan>,
     props: Vec<(String, String)>,
+    dyn_part_cols: Vec<String>,
+    bucket_spec: Option<SinkBucketSpec>,
+    partition_consumer_resource_id: String,
+    datetime_rebase_mode: RebaseMode,
+    key_value_metadata: Vec<(String, String)>,
     metrics: ExecutionPlanMetricsSet,
 }
 
@@ -60,12 +166,22 @@ impl ParquetSinkExec {
         fs_resource_id: String,
         path: String,
         props: Vec<(String, String)>,
+        dyn_part_cols: Vec<String>,
+        bucket_spec: Option<SinkBucketSpec>,
+        partition_consumer_resource_id: String,
+        datetime_rebase_mode: RebaseMode,
+        key_value_metadata: Vec<(String, String)>,
     ) -> Self {
         Self {
             input,
             fs_resource_id,
             path,
             props,
+            dyn_part_cols,
+            bucket_spec,
+            partition_consumer_resource_id,
+            datetime_rebase_mode,
+            key_value_metadata,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
@@ -73,7 +189,18 @@ impl ParquetSinkExec {
 
 impl DisplayAs for ParquetSinkExec {
     fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "ParquetSink [path={}]", self.path)
+        write!(f, "ParquetSink [path={}", self.path)?;
+        if !self.dyn_part_cols.is_empty() {
+            write!(f, ", dyn_part_cols={:?}", self.dyn_part_cols)?;
+        }
+        if let Some(bucket_spec) = &self.bucket_spec {
+            write!(
+                f,
+                ", num_buckets={}, hash_function={:?}",
+                bucket_spec.num_buckets, bucket_spec.hash_function
+            )?;
+        }
+        write!(f, "]")
     }
 }
 
@@ -83,7 +210,7 @@ impl ExecutionPlan for ParquetSinkExec {
     }
 
     fn schema(&self) -> SchemaRef {
-        self.input.schema()
+        file_stats_schema()
     }
 
     fn output_partitioning(&self) -> Partitioning {
@@ -91,7 +218,7 @@ impl ExecutionPlan for ParquetSinkExec {
     }
 
     fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
-        self.input.output_ordering()
+        None
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -107,6 +234,11 @@ impl ExecutionPlan for ParquetSinkExec {
             self.fs_resource_id.clone(),
             self.path.clone(),
             self.props.clone(),
+            self.dyn_part_cols.clone(),
+            self.bucket_spec.clone(),
+            self.partition_consumer_resource_id.clone(),
+            self.datetime_rebase_mode,
+            self.key_value_metadata.clone(),
         )))
     }
 
@@ -115,9 +247,6 @@ impl ExecutionPlan for ParquetSinkExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> Result<SendableRecordBatchStream> {
-        let fs_resource_id = self.fs_resource_id.clone();
-        let path = self.path.clone();
-        let props = self.props.clone();
         let metrics = BaselineMetrics::new(&self.metrics, partition);
 
         // register io_time metric
@@ -142,17 +271,48 @@ impl ExecutionPlan for ParquetSinkExec {
         ));
         self.metrics.register(bytes_written_metric);
 
+        // written partitions are reported to this consumer for metastore updates
+        let partition_consumer = if !self.partition_consumer_resource_id.is_empty() {
+            let resource_id = jni_new_string!(&self.partition_consumer_resource_id)?;
+            let consumer =
+                jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
+            Some(jni_new_global_ref!(consumer.as_obj())?)
+        } else {
+            None
+        };
+        let max_open_writers = if !self.dyn_part_cols.is_empty() || self.bucket_spec.is_some() {
+            jni_call_static!(BlazeConf.parquetSinkMaxOpenWriters() -> i32)?.max(1) as usize
+        } else {
+            1
+        };
+        let collect_file_index = jni_call_static!(BlazeConf.parquetSinkFileIndexEnabled() -> bool)?;
+        let writers = PartitionedWriters::try_new(
+            self.fs_resource_id.clone(),
+            self.path.clone(),
+            &self.props,
+            &self.key_value_metadata,
+            self.datetime_rebase_mode,
+            max_open_writers,
+            collect_file_index,
+            self.bucket_spec.as_ref().map(|spec| spec.hash_function),
+            partition_consumer,
+            io_time,
+            bytes_written,
+        )?;
+
+        let clustering = SinkClustering::try_from_props(&self.props, &writers.hive_schema)?;
         let input = self.input.execute(partition, context.clone())?;
         let output = Box::pin(RecordBatchStreamAdapter::new(
             self.schema(),
             once(execute_parquet_sink(
-                fs_resource_id,
-                path,
                 input,
-                props,
+                Arc::new(Mutex::new(writers)),
+                self.dyn_part_cols.clone(),
+                self.bucket_spec.clone(),
+                clustering,
                 metrics,
-                io_time,
-                bytes_written,
+                partition,
+                context,
             ))
             .try_flatten(),
         ));
@@ -169,92 +329,757 @@ impl ExecutionPlan for ParquetSinkExec {
 }
 
 async fn execute_parquet_sink(
-    fs_resource_id: String,
-    path: String,
     mut input: SendableRecordBatchStream,
-    props: Vec<(String, String)>,
+    writers: Arc<Mutex<PartitionedWriters>>,
+    dyn_part_cols: Vec<String>,
+    bucket_spec: Option<SinkBucketSpec>,
+    clustering: Option<SinkClustering>,
     metrics: BaselineMetrics,
-    io_time: Time,
-    bytes_written: Count,
+    partition: usize,
+    context: Arc<TaskContext>,
 ) -> Result<SendableRecordBatchStream> {
-    let mut timer = metrics.elapsed_compute().timer();
+    let schema = input.schema();
+    let num_data_cols = schema.fields().len() - dyn_part_cols.len();
+    let mut sorted = false;
 
-    // parse hive_schema from props
-    let hive_schema = props
-        .iter()
-        .find(|(key, _)| key == "parquet.hive.schema")
-        .map(|(_, value)| value)
-        .and_then(|value| parse_message_type(value.as_str()).ok())
-        .and_then(|tp| parquet_to_arrow_schema(&SchemaDescriptor::new(Arc::new(tp)), None).ok())
-        .map(Arc::new)
-        .ok_or(DataFusionError::Execution(format!(
-            "missing parquet.hive.schema"
-        )))?;
-
-    // parse row group byte size from props
-    let block_size = props
-        .iter()
-        .find(|(key, _)| key == "parquet.block.size")
-        .and_then(|(_, value)| value.parse::<usize>().ok())
-        .unwrap_or(128 * 1024 * 1024);
+    // bucket ids are appended to the input batches as the last column
+    if let Some(bucket_spec) = bucket_spec.clone() {
+        let bucketed_schema = Arc::new(Schema::new(
+            [
+                schema.fields().to_vec(),
+                vec![Arc::new(Field::new("__bucket_id__", DataType::Int32, false))],
+            ]
+            .concat(),
+        ));
+        let bucketed_schema_cloned = bucketed_schema.clone();
+        input = Box::pin(RecordBatchStreamAdapter::new(
+            bucketed_schema,
+            input.map(move |batch| {
+                let batch = batch?;
+                let bucket_ids = compute_bucket_ids(&batch, &bucket_spec)?;
+                let mut cols = batch.columns().to_vec();
+                cols.push(bucket_ids);
+                Ok(RecordBatch::try_new(bucketed_schema_cloned.clone(), cols)?)
+            }),
+        ));
+    }
 
-    let schema = input.schema();
-    let props = parse_writer_props(&props);
-    let parquet_writer: Arc<Mutex<OnceCell<ArrowWriter<FSDataWriter>>>> = Arc::default();
-    timer.stop();
+    // clustering keys are appended to the input batches as the last column, they
+    // are removed after sorting
+    if let Some(clustering) = clustering.clone() {
+        let clustered_schema = Arc::new(Schema::new(
+            [
+                input.schema().fields().to_vec(),
+                vec![Arc::new(Field::new("__clustering_key__", DataType::Binary, false))],
+            ]
+            .concat(),
+        ));
+        let clustered_schema_cloned = clustered_schema.clone();
+        input = Box::pin(RecordBatchStreamAdapter::new(
+            clustered_schema,
+            input.map(move |batch| {
+                let batch = batch?;
+                let clustering_keys = clustering.compute_keys(&batch)?;
+                let mut cols = batch.columns().to_vec();
+                cols.push(clustering_keys);
+                Ok(RecordBatch::try_new(clustered_schema_cloned.clone(), cols)?)
+            }),
+        ));
+    }
+
+    // sort within buckets and by clustering keys, only one writer is kept open at
+    // a time
+    let sort_exprs = bucket_spec
+        .as_ref()
+        .map(|bucket_spec| bucket_spec.sort_exprs.clone())
+        .unwrap_or_default();
+    if !sort_exprs.is_empty() || clustering.is_some() {
+        input = sort_for_writing(
+            input,
+            num_data_cols,
+            &sort_exprs,
+            clustering.is_some(),
+            partition,
+            &context,
+        )?;
+        writers.lock().max_open_writers = 1;
+        sorted = true;
+    }
 
     // write parquet data
     while let Some(batch) = input.next().await.transpose()? {
-        timer.restart();
+        let mut timer = metrics.elapsed_compute().timer();
 
-        // adapt batch to output schema
-        let batch = adapt_schema(batch, &hive_schema)?;
-
-        // init parquet writer after first batch is received
-        // to avoid creating empty file
-        parquet_writer.lock().get_or_try_init(|| {
-            create_parquet_writer(
-                &fs_resource_id,
-                &path,
-                &hive_schema,
-                &props,
-                &io_time,
-                &bytes_written,
-            )
-        })?;
-
-        let parquet_writer = parquet_writer.clone();
-        let metrics = metrics.clone();
-        let fut = tokio::task::spawn_blocking(move || {
-            let num_rows = batch.num_rows();
-            let mut parquet_writer_locked = parquet_writer.lock();
-            let parquet_writer = parquet_writer_locked.get_mut().unwrap();
-
-            parquet_writer.write(&batch)?;
-            if parquet_writer.in_progress_size() >= block_size {
-                parquet_writer.flush()?;
+        // too many partitions are written concurrently, close all writers and
+        // sort the remaining data by partition values (with spilling), so that
+        // only one writer is kept open at a time
+        let num_evicted = writers.lock().num_evicted;
+        if !sorted && num_evicted >= writers.lock().max_open_writers {
+            log::info!(
+                "ParquetSink: too many dynamic partitions, fallback to sorted writing, \
+                    num_evicted_writers={num_evicted}"
+            );
+            with_writers_blocking(&writers, |writers| writers.close_all()).await?;
+            writers.lock().max_open_writers = 1;
+            let remaining = Box::pin(RecordBatchStreamAdapter::new(
+                input.schema(),
+                once(async move { Ok(batch) }).chain(input),
+            ));
+            input = sort_for_writing(remaining, num_data_cols, &[], false, partition, &context)?;
+            sorted = true;
+            continue;
+        }
+
+        // partition batch by dynamic partition values and bucket ids (init parquet
+        // writers lazily to avoid creating empty files)
+        let num_rows = batch.num_rows();
+        let partitioned = split_dyn_partitions(&batch, &dyn_part_cols, bucket_spec.is_some())?;
+        with_writers_blocking(&writers, move |writers| {
+            for (writer_key, part_batch) in partitioned {
+                writers.write(&writer_key, part_batch)?;
             }
-            metrics.record_output(num_rows);
-            Ok::<_, DataFusionError>(())
-        });
-        fut.await
-            .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+            Ok(())
+        })
+        .await?;
+        metrics.record_output(num_rows);
         timer.stop();
     }
 
-    timer.restart();
-    let maybe_writer: Option<ArrowWriter<FSDataWriter>> = parquet_writer.lock().take();
-    if let Some(w) = maybe_writer {
-        let fut = tokio::task::spawn_blocking(move || {
-            w.close()?;
-            Ok::<_, DataFusionError>(())
+    let _timer = metrics.elapsed_compute().timer();
+    with_writers_blocking(&writers, |writers| writers.close_all()).await?;
+
+    // output statistics of all written files
+    let file_stats = std::mem::take(&mut writers.lock().written_files);
+    let file_stats_batch = file_stats_to_batch(&file_stats)?;
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        file_stats_schema(),
+        once(async move { Ok(file_stats_batch) }),
+    )))
+}
+
+/// schema of the sink output, one record per written file. min/max values are
+/// stringified and ordered as the output columns, null if not available. null
+/// counts and approximate ndvs of columns form the file-level data-skipping
+/// index, which are empty if not collected.
+pub(crate) fn file_stats_schema() -> SchemaRef {
+    let string_list = DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+    let int64_list = DataType::List(Arc::new(Field::new("item", DataType::Int64, true)));
+    Arc::new(Schema::new(vec![
+        Field::new("path", DataType::Utf8, false),
+        Field::new("partition", DataType::Utf8, false),
+        Field::new("num_rows", DataType::Int64, false),
+        Field::new("num_bytes", DataType::Int64, false),
+        Field::new("min_values", string_list.clone(), false),
+        Field::new("max_values", string_list, false),
+        Field::new("null_counts", int64_list.clone(), false),
+        Field::new("ndvs", int64_list, false),
+    ]))
+}
+
+/// statistics of a written file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileStats {
+    pub path: String,
+    pub partition: String,
+    pub num_rows: usize,
+    pub num_bytes: usize,
+    pub min_values: Vec<Option<String>>,
+    pub max_values: Vec<Option<String>>,
+    pub null_counts: Vec<usize>,
+    pub ndvs: Vec<usize>,
+}
+
+pub(crate) fn file_stats_to_batch(file_stats: &[FileStats]) -> Result<RecordBatch> {
+    let mut min_values_builder = ListBuilder::new(StringBuilder::new());
+    let mut max_values_builder = ListBuilder::new(StringBuilder::new());
+    let mut null_counts_builder = ListBuilder::new(Int64Builder::new());
+    let mut ndvs_builder = ListBuilder::new(Int64Builder::new());
+    for stats in file_stats {
+        min_values_builder.append_value(stats.min_values.iter().map(|v| v.as_deref()));
+        max_values_builder.append_value(stats.max_values.iter().map(|v| v.as_deref()));
+        null_counts_builder.append_value(stats.null_counts.iter().map(|&v| Some(v as i64)));
+        ndvs_builder.append_value(stats.ndvs.iter().map(|&v| Some(v as i64)));
+    }
+    Ok(RecordBatch::try_new(
+        file_stats_schema(),
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                file_stats.iter().map(|stats| &stats.path),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                file_stats.iter().map(|stats| &stats.partition),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                file_stats.iter().map(|stats| stats.num_rows as i64),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                file_stats.iter().map(|stats| stats.num_bytes as i64),
+            )),
+            Arc::new(min_values_builder.finish()),
+            Arc::new(max_values_builder.finish()),
+            Arc::new(null_counts_builder.finish()),
+            Arc::new(ndvs_builder.finish()),
+        ],
+    )?)
+}
+
+/// collects min/max values of a column. collecting is given up if the column
+/// type is not supported.
+struct ColumnMinMax {
+    min_acc: Option<MinAccumulator>,
+    max_acc: Option<MaxAccumulator>,
+}
+
+impl ColumnMinMax {
+    fn new(data_type: &DataType) -> Self {
+        Self {
+            min_acc: MinAccumulator::try_new(data_type).ok(),
+            max_acc: MaxAccumulator::try_new(data_type).ok(),
+        }
+    }
+
+    fn update(&mut self, values: &ArrayRef) {
+        let values = &[values.clone()];
+        if let Some(min_acc) = &mut self.min_acc {
+            if min_acc.update_batch(values).is_err() {
+                self.min_acc = None;
+            }
+        }
+        if let Some(max_acc) = &mut self.max_acc {
+            if max_acc.update_batch(values).is_err() {
+                self.max_acc = None;
+            }
+        }
+    }
+
+    fn min_value(&self) -> Option<String> {
+        Self::evaluate_to_string(self.min_acc.as_ref()?)
+    }
+
+    fn max_value(&self) -> Option<String> {
+        Self::evaluate_to_string(self.max_acc.as_ref()?)
+    }
+
+    fn evaluate_to_string(acc: &dyn Accumulator) -> Option<String> {
+        let value = acc.evaluate().ok()?;
+        if value.is_null() {
+            return None;
+        }
+        let string_value = cast(&value.to_array(), &DataType::Utf8).ok()?;
+        let string_value = as_string_array(&string_value);
+        string_value
+            .is_valid(0)
+            .then(|| string_value.value(0).to_string())
+    }
+}
+
+/// collects null count and approximate ndv of a column for the file-level
+/// data-skipping index. ndv collecting is given up if the column type cannot be
+/// hashed.
+struct ColumnIndexCollector {
+    null_count: usize,
+    hll: Option<HyperLogLog>,
+}
+
+impl ColumnIndexCollector {
+    fn new() -> Self {
+        Self {
+            null_count: 0,
+            hll: Some(HyperLogLog::new(FILE_INDEX_HLL_PRECISION)),
+        }
+    }
+
+    fn update(&mut self, values: &ArrayRef) {
+        self.null_count += values.null_count();
+        if let Some(hll) = &mut self.hll {
+            if hll.update(values).is_err() {
+                self.hll = None;
+            }
+        }
+    }
+
+    /// returns ndv, or zero if not available
+    fn ndv(&self) -> usize {
+        self.hll
+            .as_ref()
+            .map(|hll| hll.estimate() as usize)
+            .unwrap_or(0)
+    }
+}
+
+// runs blocking writer operations outside of the async runtime
+async fn with_writers_blocking<T: Send + 'static>(
+    writers: &Arc<Mutex<PartitionedWriters>>,
+    f: impl FnOnce(&mut PartitionedWriters) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let writers = writers.clone();
+    tokio::task::spawn_blocking(move || f(&mut writers.lock()))
+        .await
+        .map_err(|err| DataFusionError::Execution(format!("{err}")))?
+}
+
+/// sorts input by dynamic partition columns, bucket id, sort columns in buckets
+/// and clustering keys. data columns are the first num_data_cols columns of the
+/// input, clustering keys (if clustered) are the last column and are removed
+/// from the output.
+fn sort_for_writing(
+    input: SendableRecordBatchStream,
+    num_data_cols: usize,
+    sort_exprs_in_bucket: &[PhysicalSortExpr],
+    clustered: bool,
+    partition: usize,
+    context: &Arc<TaskContext>,
+) -> Result<SendableRecordBatchStream> {
+    let schema = input.schema();
+    let num_cols = schema.fields().len() - clustered as usize;
+    let column_sort_expr = |i: usize| PhysicalSortExpr {
+        expr: Arc::new(Column::new(schema.field(i).name(), i)),
+        options: SortOptions::default(),
+    };
+    let sort_exprs = (num_data_cols..num_cols)
+        .map(column_sort_expr)
+        .chain(sort_exprs_in_bucket.iter().cloned())
+        .chain(clustered.then(|| column_sort_expr(num_cols)))
+        .collect();
+    let sort_exec = SortExec::new(
+        Arc::new(RecordBatchStreamsWrapperExec {
+            schema: schema.clone(),
+            stream: Mutex::new(Some(input)),
+            output_partitioning: Partitioning::UnknownPartitioning(1),
+        }),
+        sort_exprs,
+        None,
+    );
+    let sorted = sort_exec.execute(partition, context.clone())?;
+    if !clustered {
+        return Ok(sorted);
+    }
+
+    let projection = (0..num_cols).collect::<Vec<_>>();
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        Arc::new(schema.project(&projection)?),
+        sorted.map(move |batch| Ok(batch?.project(&projection)?)),
+    )))
+}
+
+fn compute_bucket_ids(batch: &RecordBatch, bucket_spec: &SinkBucketSpec) -> Result<ArrayRef> {
+    let num_rows = batch.num_rows();
+    let num_buckets = bucket_spec.num_buckets;
+    let bucket_cols = bucket_spec
+        .bucket_exprs
+        .iter()
+        .map(|expr| Ok(expr.evaluate(batch)?.into_array(num_rows)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let bucket_ids: Int32Array = match bucket_spec.hash_function {
+        BucketHashFunction::Murmur3 => {
+            let hashes = create_murmur3_hashes(&bucket_cols, num_rows)?;
+            create_partition_ids(&hashes, num_buckets)
+                .into_iter()
+                .map(|bucket_id| bucket_id as i32)
+                .collect()
+        }
+        BucketHashFunction::HiveHash => {
+            let mut hashes = vec![0; num_rows];
+            create_hive_hashes(&bucket_cols, &mut hashes)?;
+            hashes
+                .into_iter()
+                .map(|hash| (hash & i32::MAX) % num_buckets as i32)
+                .collect()
+        }
+    };
+    Ok(Arc::new(bucket_ids))
+}
+
+/// identifies the file to write, by partition path and bucket id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct WriterKey {
+    part_path: String,
+    bucket_id: Option<usize>,
+}
+
+/// splits batch into (writer_key, data_batch) pairs. dynamic partition columns
+/// and the bucket id column (if bucketed) are the last columns of the input and
+/// are excluded from data batches.
+fn split_dyn_partitions(
+    batch: &RecordBatch,
+    dyn_part_cols: &[String],
+    bucketed: bool,
+) -> Result<Vec<(WriterKey, RecordBatch)>> {
+    let num_data_cols = batch.num_columns() - dyn_part_cols.len() - bucketed as usize;
+    let data_batch = batch.project(&(0..num_data_cols).collect::<Vec<_>>())?;
+    if dyn_part_cols.is_empty() && !bucketed {
+        let writer_key = WriterKey {
+            part_path: String::new(),
+            bucket_id: None,
+        };
+        return Ok(vec![(writer_key, data_batch)]);
+    }
+
+    let part_values = batch.columns()[num_data_cols..][..dyn_part_cols.len()]
+        .iter()
+        .map(|col| cast(col, &DataType::Utf8))
+        .collect::<Result<Vec<_>>>()?;
+    let part_values = part_values
+        .iter()
+        .map(|values| as_string_array(values))
+        .collect::<Vec<_>>();
+    let bucket_ids =
+        bucketed.then(|| as_primitive_array::<Int32Type>(batch.column(batch.num_columns() - 1)));
+
+    let escaped_names = dyn_part_cols
+        .iter()
+        .map(|name| escape_path_name(name))
+        .collect::<Vec<_>>();
+
+    let mut writer_keys: Vec<WriterKey> = vec![];
+    let mut writer_indices: Vec<Vec<u32>> = vec![];
+    let mut writer_ids: HashMap<WriterKey, usize> = HashMap::new();
+    for row_idx in 0..batch.num_rows() {
+        let part_path = escaped_names
+            .iter()
+            .zip(&part_values)
+            .map(|(name, values)| {
+                if values.is_valid(row_idx) && !values.value(row_idx).is_empty() {
+                    format!("{name}={}", escape_path_name(values.value(row_idx)))
+                } else {
+                    format!("{name}={HIVE_DEFAULT_PARTITION}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let writer_key = WriterKey {
+            part_path,
+            bucket_id: bucket_ids.map(|bucket_ids| bucket_ids.value(row_idx) as usize),
+        };
+
+        let writer_id = *writer_ids
+            .entry(writer_key)
+            .or_insert_with_key(|writer_key| {
+                writer_keys.push(writer_key.clone());
+                writer_indices.push(vec![]);
+                writer_keys.len() - 1
+            });
+        writer_indices[writer_id].push(row_idx as u32);
+    }
+
+    writer_keys
+        .into_iter()
+        .zip(writer_indices)
+        .map(|(writer_key, indices)| Ok((writer_key, BatchTaker(&data_batch).take(indices)?)))
+        .collect()
+}
+
+/// escapes a partition name/value like hive's FileUtils.escapePathName.
+fn escape_path_name(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{01}'..='\u{1F}'
+            | '"'
+            | '#'
+            | '%'
+            | '\''
+            | '*'
+            | '/'
+            | ':'
+            | '='
+            | '?'
+            | '\\'
+            | '\u{7F}'
+            | '{'
+            | '['
+            | ']'
+            | '^' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// encodes an escaped partition path to be used in a java URI.
+fn encode_uri_path(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '%' | ' ' | '<' | '>' | '`' | '|' | '}' => {
+                encoded.push_str(&format!("%{:02X}", c as u32))
+            }
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// manages one parquet writer per (dynamic) partition and bucket. at most
+/// max_open_writers writers are kept open, the least recently used one is closed
+/// if exceeded.
+struct PartitionedWriters {
+    fs_resource_id: String,
+    path: String,
+    hive_schema: SchemaRef,
+    props: WriterProperties,
+    datetime_rebase_mode: RebaseMode,
+    block_size: usize,
+    max_open_writers: usize,
+    collect_file_index: bool,
+    bucket_hash_function: Option<BucketHashFunction>,
+    partition_consumer: Option<GlobalRef>,
+    io_time: Time,
+    bytes_written: Count,
+    open_writers: HashMap<WriterKey, OpenWriter>,
+    num_files: HashMap<WriterKey, usize>,
+    written_partitions: HashSet<String>,
+    written_files: Vec<FileStats>,
+    num_evicted: usize,
+    access_seq: usize,
+}
+
+impl PartitionedWriters {
+    fn try_new(
+        fs_resource_id: String,
+        path: String,
+        props: &[(String, String)],
+        key_value_metadata: &[(String, String)],
+        datetime_rebase_mode: RebaseMode,
+        max_open_writers: usize,
+        collect_file_index: bool,
+        bucket_hash_function: Option<BucketHashFunction>,
+        partition_consumer: Option<GlobalRef>,
+        io_time: Time,
+        bytes_written: Count,
+    ) -> Result<Self> {
+        // encrypted files cannot be written with the underlying parquet writer, fail
+        // instead of writing plaintext files
+        if let Some((key, _)) = props.iter().find(|(key, _)| is_encryption_prop(key)) {
+            return Err(DataFusionError::NotImplemented(format!(
+                "parquet modular encryption is not supported: {key}"
+            )));
+        }
+
+        // parse hive_schema from props
+        let hive_schema = props
+            .iter()
+            .find(|(key, _)| key == "parquet.hive.schema")
+            .map(|(_, value)| value)
+            .and_then(|value| parse_message_type(value.as_str()).ok())
+            .and_then(|tp| parquet_to_arrow_schema(&SchemaDescriptor::new(Arc::new(tp)), None).ok())
+            .map(Arc::new)
+            .ok_or(DataFusionError::Execution(format!(
+                "missing parquet.hive.schema"
+            )))?;
+
+        // parse row group byte size from props
+        let block_size = props
+            .iter()
+            .find(|(key, _)| key == "parquet.block.size")
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(128 * 1024 * 1024);
+
+        Ok(Self {
+            fs_resource_id,
+            path,
+            hive_schema,
+            props: parse_writer_props(props, key_value_metadata),
+            datetime_rebase_mode,
+            block_size,
+            max_open_writers,
+            collect_file_index,
+            bucket_hash_function,
+            partition_consumer,
+            io_time,
+            bytes_written,
+            open_writers: HashMap::new(),
+            num_files: HashMap::new(),
+            written_partitions: HashSet::new(),
+            written_files: vec![],
+            num_evicted: 0,
+            access_seq: 0,
+        })
+    }
+
+    fn write(&mut self, writer_key: &WriterKey, batch: RecordBatch) -> Result<()> {
+        // adapt batch to output schema
+        let batch = adapt_schema(batch, &self.hive_schema)?;
+
+        if !self.open_writers.contains_key(writer_key) {
+            if self.open_writers.len() >= self.max_open_writers {
+                self.evict_lru_writer()?;
+            }
+            let writer = self.create_writer(writer_key)?;
+            self.open_writers.insert(writer_key.clone(), writer);
+        }
+        self.access_seq += 1;
+
+        let writer = self.open_writers.get_mut(writer_key).unwrap();
+        writer.last_access = self.access_seq;
+        writer.num_rows += batch.num_rows();
+        for (col_min_max, col) in writer.col_min_max.iter_mut().zip(batch.columns()) {
+            col_min_max.update(col);
+        }
+        for (col_index, col) in writer.col_index.iter_mut().zip(batch.columns()) {
+            col_index.update(col);
+        }
+        let batch = rebase_datetime_for_writing(batch, self.datetime_rebase_mode)?;
+        writer.parquet_writer.write(&batch)?;
+        if writer.parquet_writer.in_progress_size() >= self.block_size {
+            writer.parquet_writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn evict_lru_writer(&mut self) -> Result<()> {
+        let lru_writer_key = self
+            .open_writers
+            .iter()
+            .min_by_key(|(_, writer)| writer.last_access)
+            .map(|(writer_key, _)| writer_key.clone());
+
+        if let Some(writer_key) = lru_writer_key {
+            let writer = self.open_writers.remove(&writer_key).unwrap();
+            self.close_writer(writer)?;
+            self.num_evicted += 1;
+        }
+        Ok(())
+    }
+
+    fn close_all(&mut self) -> Result<()> {
+        let writers = self
+            .open_writers
+            .drain()
+            .map(|(_, writer)| writer)
+            .collect::<Vec<_>>();
+        for writer in writers {
+            self.close_writer(writer)?;
+        }
+        Ok(())
+    }
+
+    fn close_writer(&mut self, writer: OpenWriter) -> Result<()> {
+        writer.parquet_writer.close()?;
+        writer.fout.close()?;
+        let num_bytes = writer.fout.num_bytes();
+        self.bytes_written.add(num_bytes);
+        self.written_files.push(FileStats {
+            path: writer.file_path,
+            partition: writer.part_path,
+            num_rows: writer.num_rows,
+            num_bytes,
+            min_values: writer.col_min_max.iter().map(|c| c.min_value()).collect(),
+            max_values: writer.col_min_max.iter().map(|c| c.max_value()).collect(),
+            null_counts: writer.col_index.iter().map(|c| c.null_count).collect(),
+            ndvs: writer.col_index.iter().map(|c| c.ndv()).collect(),
         });
-        fut.await
-            .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+        Ok(())
+    }
+
+    fn create_writer(&mut self, writer_key: &WriterKey) -> Result<OpenWriter> {
+        // files of dynamic partitions are written to {dir}/{part_path}/{file_name},
+        // reopened partitions (after being evicted) are written to new files
+        let part_path = &writer_key.part_path;
+        let num_files = self.num_files.entry(writer_key.clone()).or_default();
+        let (dir, file_name) = match self.path.rsplit_once('/') {
+            Some((dir, file_name)) => (format!("{dir}/"), file_name),
+            None => (String::new(), self.path.as_str()),
+        };
+        let mut file_path = dir;
+        if !part_path.is_empty() {
+            file_path.push_str(&encode_uri_path(part_path));
+            file_path.push('/');
+        }
+        match (self.bucket_hash_function, writer_key.bucket_id) {
+            (Some(hash_function), Some(bucket_id)) => {
+                file_path.push_str(&bucket_file_name(file_name, bucket_id, hash_function));
+            }
+            _ => file_path.push_str(file_name),
+        }
+        if *num_files > 0 {
+            file_path.push_str(&format!(".{num_files}"));
+        }
+        *num_files += 1;
+
+        let fout = create_fs_output_stream(&self.fs_resource_id, &file_path, &self.io_time)?;
+        let parquet_writer = ArrowWriter::try_new(
+            fout.clone(),
+            self.hive_schema.clone(),
+            Some(self.props.clone()),
+        )?;
+
+        // report newly written partitions
+        if !part_path.is_empty() && self.written_partitions.insert(part_path.clone()) {
+            if let Some(partition_consumer) = &self.partition_consumer {
+                let _consumed = jni_call!(
+                    ScalaFunction1(partition_consumer.as_obj())
+                        .apply(jni_new_string!(part_path)?.as_obj()) -> JObject
+                )?;
+            }
+        }
+        Ok(OpenWriter {
+            parquet_writer,
+            fout,
+            last_access: self.access_seq,
+            file_path,
+            part_path: part_path.clone(),
+            num_rows: 0,
+            col_min_max: self
+                .hive_schema
+                .fields()
+                .iter()
+                .map(|field| ColumnMinMax::new(field.data_type()))
+                .collect(),
+            col_index: if self.collect_file_index {
+                self.hive_schema
+                    .fields()
+                    .iter()
+                    .map(|_| ColumnIndexCollector::new())
+                    .collect()
+            } else {
+                vec![]
+            },
+        })
     }
+}
+
+struct OpenWriter {
+    parquet_writer: ArrowWriter<FsDataOutputStream>,
+    fout: FsDataOutputStream,
+    last_access: usize,
+    file_path: String,
+    part_path: String,
+    num_rows: usize,
+    col_min_max: Vec<ColumnMinMax>,
+    col_index: Vec<ColumnIndexCollector>,
+}
 
-    // parquet sink does not provide any output records
-    Ok(Box::pin(EmptyRecordBatchStream::new(schema)))
+/// properties enabling parquet-mr's modular encryption: the crypto factory, and
+/// the footer/column keys used by its properties-driven factory.
+fn is_encryption_prop(key: &str) -> bool {
+    matches!(
+        key,
+        "parquet.crypto.factory.class"
+            | "parquet.encryption.footer.key"
+            | "parquet.encryption.column.keys"
+            | "parquet.encryption.uniform.key"
+    )
+}
+
+/// names bucket files like spark does, hive-compatible bucket files are prefixed
+/// with "{bucket_id}_0_", others are suffixed with "_{bucket_id}" before extension.
+fn bucket_file_name(
+    file_name: &str,
+    bucket_id: usize,
+    hash_function: BucketHashFunction,
+) -> String {
+    match hash_function {
+        BucketHashFunction::HiveHash => format!("{bucket_id:05}_0_{file_name}"),
+        BucketHashFunction::Murmur3 => match file_name.find('.') {
+            Some(pos) => {
+                let (name, ext) = file_name.split_at(pos);
+                format!("{name}_{bucket_id:05}{ext}")
+            }
+            None => format!("{file_name}_{bucket_id:05}"),
+        },
+    }
 }
 
 fn adapt_schema(batch: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
@@ -267,13 +1092,52 @@ fn adapt_schema(batch: RecordBatch, schema: &SchemaRef) -> Result<RecordBatch> {
     Ok(RecordBatch::try_new(schema.clone(), casted_cols)?)
 }
 
-fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
-    let mut builder = WriterProperties::builder().set_created_by(format!("blaze-engine"));
+/// rebases dates/timestamps to the hybrid calendar in LEGACY mode like spark, or
+/// fails on ambiguous values in EXCEPTION mode.
+fn rebase_datetime_for_writing(batch: RecordBatch, mode: RebaseMode) -> Result<RecordBatch> {
+    if mode == RebaseMode::Corrected
+        || !batch
+            .schema()
+            .fields()
+            .iter()
+            .any(|field| contains_datetime(field.data_type()))
+    {
+        return Ok(batch);
+    }
+    let rebased_cols = batch
+        .columns()
+        .iter()
+        .map(|col| match mode {
+            RebaseMode::Legacy => rebase_gregorian_to_julian_array(col),
+            _ if has_ancient_datetime(col) => Err(DataFusionError::Execution(format!(
+                "writing dates before 1582-10-15 or timestamps before 1900-01-01T00:00:00Z \
+                    into parquet files can be ambiguous, as the files may be read by spark 2.x \
+                    or legacy versions of hive later, which use a legacy hybrid calendar. set \
+                    spark.sql.parquet.datetimeRebaseModeInWrite to 'LEGACY' to rebase the \
+                    values, or 'CORRECTED' to write the values as they are"
+            ))),
+            _ => Ok(col.clone()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), rebased_cols)?)
+}
+
+/// parses parquet writer properties. column-specific properties are specified
+/// like parquet-mr, with a "#{column_path}" suffix (e.g. parquet.compression#col).
+fn parse_writer_props(
+    prop_kvs: &[(String, String)],
+    key_value_metadata: &[(String, String)],
+) -> WriterProperties {
+    // page-level statistics are enabled by default, so that column index and offset
+    // index are written like parquet-mr
+    let mut builder = WriterProperties::builder()
+        .set_created_by(format!("blaze-engine"))
+        .set_statistics_enabled(EnabledStatistics::Page);
 
     macro_rules! setprop {
-        ($key:expr, $value:expr, $tnum:ty, $setfn:ident) => {{
+        ($key:expr, $value:expr, $tnum:ty, $setfn:ident $(, $col:expr)?) => {{
             if let Ok(value) = $value.parse::<$tnum>() {
-                builder.$setfn(value)
+                builder.$setfn($($col.clone(),)? value)
             } else {
                 log::warn!("invalid parquet prop value: {}={}", $key, $value);
                 builder
@@ -281,97 +1145,550 @@ fn parse_writer_props(prop_kvs: &[(String, String)]) -> WriterProperties {
         }};
     }
 
+    // codec levels are applied to all columns with the corresponding codec
+    let zstd_level = prop_kvs
+        .iter()
+        .find(|(key, _)| key == "parquet.compression.codec.zstd.level")
+        .and_then(|(_, value)| value.parse::<i32>().ok())
+        .and_then(|level| ZstdLevel::try_new(level).ok())
+        .unwrap_or_default();
+
     for (key, value) in prop_kvs {
-        builder = match key.as_ref() {
-            "parquet.page.size" => setprop!(key, value, usize, set_data_page_size_limit),
-            "parquet.enable.dictionary" => setprop!(key, value, bool, set_dictionary_enabled),
-            "parquet.dictionary.page.size" => {
+        let (prop_name, col) = match key.split_once('#') {
+            Some((prop_name, col)) => (prop_name, Some(parse_column_path(col))),
+            None => (key.as_str(), None),
+        };
+        builder = match (prop_name, col) {
+            ("parquet.page.size", None) => setprop!(key, value, usize, set_data_page_size_limit),
+            ("parquet.page.row.count.limit", None) => {
+                setprop!(key, value, usize, set_data_page_row_count_limit)
+            }
+            ("parquet.enable.dictionary", None) => {
+                setprop!(key, value, bool, set_dictionary_enabled)
+            }
+            ("parquet.enable.dictionary", Some(col)) => {
+                setprop!(key, value, bool, set_column_dictionary_enabled, col)
+            }
+            ("parquet.dictionary.page.size", None) => {
                 setprop!(key, value, usize, set_dictionary_page_size_limit)
             }
-            "parquet.statistics.truncate.length" => {
+            ("parquet.statistics.truncate.length", None) => {
                 setprop!(key, value, usize, set_max_statistics_size)
             }
-            "parquet.writer.version" => {
+            ("parquet.columnindex.truncate.length", None) => match value.parse::<usize>() {
+                Ok(value) => builder.set_column_index_truncate_length(Some(value)),
+                Err(_) => {
+                    log::warn!("invalid parquet prop value: {}={}", key, value);
+                    builder
+                }
+            },
+            ("parquet.page.index.enabled", None) => {
+                builder.set_statistics_enabled(parse_statistics_enabled(key, value))
+            }
+            ("parquet.page.index.enabled", Some(col)) => {
+                builder.set_column_statistics_enabled(col, parse_statistics_enabled(key, value))
+            }
+            ("parquet.writer.version", None) => {
                 builder.set_writer_version(match value.to_ascii_uppercase().as_ref() {
-                    "PARQUET_1_0" => WriterVersion::PARQUET_1_0,
-                    "PARQUET_2_0" => WriterVersion::PARQUET_2_0,
+                    "PARQUET_1_0" | "V1" => WriterVersion::PARQUET_1_0,
+                    "PARQUET_2_0" | "V2" => WriterVersion::PARQUET_2_0,
                     _ => {
                         log::warn!("unsupported parquet writer version: {}", value);
                         WriterVersion::PARQUET_2_0
                     }
                 })
             }
-            "parquet.compression" => {
-                builder.set_compression(match value.to_ascii_uppercase().as_ref() {
-                    "UNCOMPRESSED" => Compression::UNCOMPRESSED,
-                    "SNAPPY" => Compression::SNAPPY,
-                    "GZIP" => Compression::GZIP(GzipLevel::default()),
-                    "LZO" => Compression::LZO,
-                    "BROTLI" => Compression::BROTLI(BrotliLevel::default()),
-                    "LZ4" => Compression::LZ4,
-                    "ZSTD" => Compression::ZSTD(ZstdLevel::default()),
-                    _ => {
-                        log::warn!("unsupported parquet compression: {}", value);
-                        Compression::UNCOMPRESSED
-                    }
-                })
+            ("parquet.compression", None) => {
+                builder.set_compression(parse_compression(value, zstd_level))
+            }
+            ("parquet.compression", Some(col)) => {
+                builder.set_column_compression(col, parse_compression(value, zstd_level))
             }
             _ => builder,
         }
     }
+    builder = set_bloom_filter_props(builder, prop_kvs);
+    if !key_value_metadata.is_empty() {
+        builder = builder.set_key_value_metadata(Some(
+            key_value_metadata
+                .iter()
+                .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+                .collect(),
+        ));
+    }
     builder.build()
 }
 
-fn create_parquet_writer(
+/// sets bloom filter properties like parquet-mr, bloom filters are only written for
+/// enabled columns, expected ndv and fpp are applied to enabled columns.
+fn set_bloom_filter_props(
+    mut builder: WriterPropertiesBuilder,
+    prop_kvs: &[(String, String)],
+) -> WriterPropertiesBuilder {
+    let mut enabled = None;
+    let mut col_enabled: HashMap<&str, bool> = HashMap::new();
+    let mut col_ndv: HashMap<&str, u64> = HashMap::new();
+    let mut col_fpp: HashMap<&str, f64> = HashMap::new();
+
+    macro_rules! parse_value {
+        ($key:expr, $value:expr, $tnum:ty) => {{
+            match $value.parse::<$tnum>() {
+                Ok(value) => value,
+                Err(_) => {
+                    log::warn!("invalid parquet prop value: {}={}", $key, $value);
+                    continue;
+                }
+            }
+        }};
+    }
+
+    for (key, value) in prop_kvs {
+        let (prop_name, col) = key.split_once('#').unwrap_or((key.as_str(), ""));
+        match prop_name {
+            "parquet.bloom.filter.enabled" if col.is_empty() => {
+                enabled = Some(parse_value!(key, value, bool))
+            }
+            "parquet.bloom.filter.enabled" => {
+                col_enabled.insert(col, parse_value!(key, value, bool));
+            }
+            "parquet.bloom.filter.expected.ndv" => {
+                col_ndv.insert(col, parse_value!(key, value, u64));
+            }
+            "parquet.bloom.filter.fpp" => {
+                col_fpp.insert(col, parse_value!(key, value, f64));
+            }
+            _ => {}
+        }
+    }
+
+    // default ndv/fpp are applied to all columns with bloom filters enabled
+    // note that setting ndv/fpp implicitly enables bloom filter in parquet-rs
+    if enabled == Some(true) {
+        builder = builder.set_bloom_filter_enabled(true);
+        if let Some(&ndv) = col_ndv.get("") {
+            builder = builder.set_bloom_filter_ndv(ndv);
+        }
+        if let Some(&fpp) = col_fpp.get("") {
+            builder = builder.set_bloom_filter_fpp(fpp);
+        }
+    }
+
+    let cols = col_enabled
+        .keys()
+        .chain(col_ndv.keys())
+        .chain(col_fpp.keys())
+        .filter(|col| !col.is_empty())
+        .cloned()
+        .collect::<HashSet<_>>();
+    for col in cols {
+        let col_path = parse_column_path(col);
+        let enabled = col_enabled.get(col).cloned().or(enabled).unwrap_or(false);
+        if !enabled {
+            builder = builder.set_column_bloom_filter_enabled(col_path, false);
+            continue;
+        }
+        builder = builder.set_column_bloom_filter_enabled(col_path.clone(), true);
+        if let Some(&ndv) = col_ndv.get(col) {
+            builder = builder.set_column_bloom_filter_ndv(col_path.clone(), ndv);
+        }
+        if let Some(&fpp) = col_fpp.get(col) {
+            builder = builder.set_column_bloom_filter_fpp(col_path.clone(), fpp);
+        }
+    }
+    builder
+}
+
+/// page-level statistics are required for writing column index.
+fn parse_statistics_enabled(key: &str, value: &str) -> EnabledStatistics {
+    match value.parse::<bool>() {
+        Ok(true) => EnabledStatistics::Page,
+        Ok(false) => EnabledStatistics::Chunk,
+        Err(_) => {
+            log::warn!("invalid parquet prop value: {}={}", key, value);
+            EnabledStatistics::Page
+        }
+    }
+}
+
+fn parse_compression(value: &str, zstd_level: ZstdLevel) -> Compression {
+    match value.to_ascii_uppercase().as_ref() {
+        "UNCOMPRESSED" | "NONE" => Compression::UNCOMPRESSED,
+        "SNAPPY" => Compression::SNAPPY,
+        "GZIP" => Compression::GZIP(GzipLevel::default()),
+        "LZO" => Compression::LZO,
+        "BROTLI" => Compression::BROTLI(BrotliLevel::default()),
+        "LZ4" => Compression::LZ4,
+        "LZ4_RAW" => Compression::LZ4_RAW,
+        "ZSTD" => Compression::ZSTD(zstd_level),
+        _ => {
+            log::warn!("unsupported parquet compression: {}", value);
+            Compression::UNCOMPRESSED
+        }
+    }
+}
+
+/// parses a dot-separated column path like parquet-mr's ColumnPath.fromDotString.
+fn parse_column_path(col: &str) -> ColumnPath {
+    ColumnPath::new(col.split('.').map(|part| part.to_string()).collect())
+}
+
+pub(crate) fn create_fs_output_stream(
     fs_resource_id: &str,
     path: &str,
-    schema: &SchemaRef,
-    props: &WriterProperties,
     io_time: &Time,
-    bytes_written: &Count,
-) -> Result<ArrowWriter<FSDataWriter>> {
+) -> Result<FsDataOutputStream> {
     // get fs object from jni bridge resource
     let fs_provider = {
         let resource_id = jni_new_string!(&fs_resource_id)?;
         let fs = jni_call_static!(JniBridge.getResource(resource_id.as_obj()) -> JObject)?;
-        Arc::new(FsProvider::new(jni_new_global_ref!(fs.as_obj())?, io_time))
+        FsProvider::new(jni_new_global_ref!(fs.as_obj())?, io_time)
+            .with_retry_policy(RetryPolicy::try_new_from_conf()?)
     };
 
     // create FSDataOutputStream
-    let fs = fs_provider.provide(&path)?;
-    let fout = fs.create(&path)?;
-    let parquet_writer = ArrowWriter::try_new(
-        FSDataWriter::new(fout, bytes_written),
-        schema.clone(),
-        Some(props.clone()),
-    )?;
-    Ok(parquet_writer)
-}
-
-// AsyncWrite wrapper for FSDataOutputStream
-struct FSDataWriter {
-    inner: Arc<FsDataOutputStream>,
-    bytes_written: Count,
+    fs_provider.provide(path)?.create(path)
 }
 
-impl FSDataWriter {
-    pub fn new(inner: FsDataOutputStream, bytes_written: &Count) -> Self {
-        Self {
-            inner: Arc::new(inner),
-            bytes_written: bytes_written.clone(),
-        }
+#[cfg(test)]
+mod test {
+    use crate::common::clustering::ClusteringMethod;
+    use crate::parquet_sink_exec::{
+        bucket_file_name, escape_path_name, file_stats_to_batch, is_encryption_prop,
+        parse_writer_props, rebase_datetime_for_writing, split_dyn_partitions, BucketHashFunction,
+        ColumnIndexCollector, ColumnMinMax, FileStats, SinkClustering,
+    };
+    use arrow::array::{ArrayRef, BinaryArray, Date32Array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::common::Result;
+    use datafusion::parquet::basic::{Compression, ZstdLevel};
+    use datafusion::parquet::file::properties::{EnabledStatistics, WriterVersion};
+    use datafusion::parquet::schema::types::ColumnPath;
+    use datafusion_ext_commons::datetime_rebase::RebaseMode;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_escape_path_name() {
+        assert_eq!(escape_path_name("2023-01-01"), "2023-01-01");
+        assert_eq!(escape_path_name("a/b:c=d"), "a%2Fb%3Ac%3Dd");
+        assert_eq!(escape_path_name("100%"), "100%25");
+        assert_eq!(escape_path_name("中文 x"), "中文 x");
     }
-}
-impl Write for FSDataWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.inner
-            .write_fully(&buf)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
-        self.bytes_written.add(buf.len());
-        Ok(buf.len())
+
+    #[test]
+    fn test_is_encryption_prop() {
+        assert!(is_encryption_prop("parquet.crypto.factory.class"));
+        assert!(is_encryption_prop("parquet.encryption.footer.key"));
+        assert!(is_encryption_prop("parquet.encryption.column.keys"));
+        assert!(!is_encryption_prop("parquet.encryption.kms.client.class"));
+        assert!(!is_encryption_prop("parquet.compression"));
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
+    #[test]
+    fn test_split_dyn_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("v", DataType::Int32, false),
+            Field::new("p1", DataType::Utf8, true),
+            Field::new("p2", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])) as ArrayRef,
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    Some("b"),
+                    Some("a"),
+                    None,
+                    Some(""),
+                ])),
+                Arc::new(Int32Array::from(vec![
+                    Some(1),
+                    Some(1),
+                    Some(1),
+                    Some(2),
+                    None,
+                ])),
+            ],
+        )?;
+
+        let partitioned =
+            split_dyn_partitions(&batch, &["p1".to_string(), "p2".to_string()], false)?;
+        let part_paths = partitioned
+            .iter()
+            .map(|(writer_key, _)| writer_key.part_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            part_paths,
+            vec![
+                "p1=a/p2=1",
+                "p1=b/p2=1",
+                "p1=__HIVE_DEFAULT_PARTITION__/p2=2",
+                "p1=__HIVE_DEFAULT_PARTITION__/p2=__HIVE_DEFAULT_PARTITION__",
+            ]
+        );
+        assert_batches_eq!(
+            vec!["+---+", "| v |", "+---+", "| 1 |", "| 3 |", "+---+",],
+            &[partitioned[0].1.clone()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_buckets() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("v", DataType::Int32, false),
+            Field::new("p", DataType::Utf8, true),
+            Field::new("__bucket_id__", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+                Arc::new(StringArray::from(vec!["a", "a", "b", "a"])),
+                Arc::new(Int32Array::from(vec![0, 1, 0, 0])),
+            ],
+        )?;
+
+        let partitioned = split_dyn_partitions(&batch, &["p".to_string()], true)?;
+        let writer_keys = partitioned
+            .iter()
+            .map(|(writer_key, _)| (writer_key.part_path.as_str(), writer_key.bucket_id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            writer_keys,
+            vec![("p=a", Some(0)), ("p=a", Some(1)), ("p=b", Some(0))]
+        );
+        assert_batches_eq!(
+            vec!["+---+", "| v |", "+---+", "| 1 |", "| 4 |", "+---+",],
+            &[partitioned[0].1.clone()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sink_clustering_from_props() -> Result<()> {
+        let hive_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Int64, true),
+        ]));
+        let props = |kvs: &[(&str, &str)]| {
+            kvs.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(SinkClustering::try_from_props(&props(&[]), &hive_schema)?.is_none());
+
+        let clustering = SinkClustering::try_from_props(
+            &props(&[("parquet.blaze.clustering.columns", "C, a")]),
+            &hive_schema,
+        )?
+        .unwrap();
+        assert_eq!(clustering.col_indices, vec![2, 0]);
+        assert_eq!(clustering.method, ClusteringMethod::ZOrder);
+
+        let clustering = SinkClustering::try_from_props(
+            &props(&[
+                ("parquet.blaze.clustering.columns", "b"),
+                ("parquet.blaze.clustering.method", "hilbert"),
+            ]),
+            &hive_schema,
+        )?
+        .unwrap();
+        assert_eq!(clustering.col_indices, vec![1]);
+        assert_eq!(clustering.method, ClusteringMethod::Hilbert);
+
+        assert!(SinkClustering::try_from_props(
+            &props(&[("parquet.blaze.clustering.columns", "d")]),
+            &hive_schema,
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_file_name() {
+        assert_eq!(
+            bucket_file_name("part-00000-abc.c000", 3, BucketHashFunction::HiveHash),
+            "00003_0_part-00000-abc.c000"
+        );
+        assert_eq!(
+            bucket_file_name("part-00000-abc.c000", 3, BucketHashFunction::Murmur3),
+            "part-00000-abc_00003.c000"
+        );
+    }
+
+    #[test]
+    fn test_column_min_max() {
+        let mut int_min_max = ColumnMinMax::new(&DataType::Int32);
+        int_min_max.update(&(Arc::new(Int32Array::from(vec![Some(3), None, Some(-1)])) as _));
+        int_min_max.update(&(Arc::new(Int32Array::from(vec![Some(7)])) as _));
+        assert_eq!(int_min_max.min_value(), Some("-1".to_string()));
+        assert_eq!(int_min_max.max_value(), Some("7".to_string()));
+
+        let mut date_min_max = ColumnMinMax::new(&DataType::Date32);
+        date_min_max.update(&(Arc::new(Date32Array::from(vec![19358, 19000])) as _));
+        assert_eq!(date_min_max.min_value(), Some("2022-01-08".to_string()));
+        assert_eq!(date_min_max.max_value(), Some("2023-01-01".to_string()));
+
+        let mut null_min_max = ColumnMinMax::new(&DataType::Utf8);
+        null_min_max.update(&(Arc::new(StringArray::from(vec![None::<&str>])) as _));
+        assert_eq!(null_min_max.min_value(), None);
+
+        let mut binary_min_max = ColumnMinMax::new(&DataType::Binary);
+        binary_min_max.update(&(Arc::new(BinaryArray::from(vec![&[0xffu8][..]])) as _));
+        assert_eq!(binary_min_max.max_value(), None);
+    }
+
+    #[test]
+    fn test_column_index_collector() {
+        let mut col_index = ColumnIndexCollector::new();
+        col_index.update(&(Arc::new(Int32Array::from(vec![Some(3), None, Some(3)])) as _));
+        col_index.update(&(Arc::new(Int32Array::from(vec![None, Some(7)])) as _));
+        assert_eq!(col_index.null_count, 2);
+        assert_eq!(col_index.ndv(), 2);
+    }
+
+    #[test]
+    fn test_file_stats_to_batch() -> Result<()> {
+        let batch = file_stats_to_batch(&[
+            FileStats {
+                path: "hdfs://tbl/p=a/part-0.c000".to_string(),
+                partition: "p=a".to_string(),
+                num_rows: 10,
+                num_bytes: 1024,
+                min_values: vec![Some("1".to_string()), None],
+                max_values: vec![Some("9".to_string()), None],
+                null_counts: vec![0, 10],
+                ndvs: vec![9, 0],
+            },
+            FileStats {
+                path: "hdfs://tbl/p=b/part-0.c000".to_string(),
+                partition: "p=b".to_string(),
+                num_rows: 1,
+                num_bytes: 512,
+                min_values: vec![Some("5".to_string()), Some("x".to_string())],
+                max_values: vec![Some("5".to_string()), Some("x".to_string())],
+                null_counts: vec![],
+                ndvs: vec![],
+            },
+        ])?;
+        assert_batches_eq!(
+            vec![
+                "+----------------------------+-----------+----------+-----------+------------+------------+-------------+--------+",
+                "| path                       | partition | num_rows | num_bytes | min_values | max_values | null_counts | ndvs   |",
+                "+----------------------------+-----------+----------+-----------+------------+------------+-------------+--------+",
+                "| hdfs://tbl/p=a/part-0.c000 | p=a       | 10       | 1024      | [1, ]      | [9, ]      | [0, 10]     | [9, 0] |",
+                "| hdfs://tbl/p=b/part-0.c000 | p=b       | 1        | 512       | [5, x]     | [5, x]     | []          | []     |",
+                "+----------------------------+-----------+----------+-----------+------------+------------+-------------+--------+",
+            ],
+            &[batch]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_writer_props() {
+        let props = parse_writer_props(
+            &[
+                ("parquet.compression", "snappy"),
+                ("parquet.compression#b", "zstd"),
+                ("parquet.compression.codec.zstd.level", "9"),
+                ("parquet.enable.dictionary", "true"),
+                ("parquet.enable.dictionary#c.d", "false"),
+                ("parquet.page.size", "65536"),
+                ("parquet.writer.version", "v2"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+            &[],
+        );
+        let col_a = ColumnPath::from("a");
+        let col_b = ColumnPath::from("b");
+        let col_cd = ColumnPath::new(vec!["c".to_string(), "d".to_string()]);
+        assert_eq!(props.compression(&col_a), Compression::SNAPPY);
+        assert_eq!(
+            props.compression(&col_b),
+            Compression::ZSTD(ZstdLevel::try_new(9).unwrap())
+        );
+        assert!(props.dictionary_enabled(&col_a));
+        assert!(!props.dictionary_enabled(&col_cd));
+        assert_eq!(props.data_page_size_limit(), 65536);
+        assert_eq!(props.writer_version(), WriterVersion::PARQUET_2_0);
+    }
+
+    #[test]
+    fn test_parse_bloom_filter_props() {
+        let props = parse_writer_props(
+            &[
+                ("parquet.bloom.filter.enabled#a", "true"),
+                ("parquet.bloom.filter.expected.ndv#a", "1000"),
+                ("parquet.bloom.filter.fpp#a", "0.01"),
+                ("parquet.bloom.filter.expected.ndv#b", "1000"),
+                ("parquet.bloom.filter.enabled", "false"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+            &[],
+        );
+        let bloom_filter_props = props.bloom_filter_properties(&ColumnPath::from("a"));
+        assert_eq!(bloom_filter_props.map(|props| props.ndv), Some(1000));
+        assert_eq!(bloom_filter_props.map(|props| props.fpp), Some(0.01));
+        assert!(props
+            .bloom_filter_properties(&ColumnPath::from("b"))
+            .is_none());
+        assert!(props
+            .bloom_filter_properties(&ColumnPath::from("c"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_page_index_props() {
+        let props = parse_writer_props(
+            &[
+                ("parquet.page.index.enabled#b", "false"),
+                ("parquet.columnindex.truncate.length", "32"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+            &[],
+        );
+        assert_eq!(
+            props.statistics_enabled(&ColumnPath::from("a")),
+            EnabledStatistics::Page
+        );
+        assert_eq!(
+            props.statistics_enabled(&ColumnPath::from("b")),
+            EnabledStatistics::Chunk
+        );
+        assert_eq!(props.column_index_truncate_length(), Some(32));
+    }
+
+    #[test]
+    fn test_rebase_datetime_for_writing() -> Result<()> {
+        // 0001-01-01 in the proleptic gregorian calendar
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int32, true),
+                Field::new("d", DataType::Date32, true),
+            ])),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Date32Array::from(vec![Some(-719162), None])),
+            ],
+        )?;
+
+        let rebased = rebase_datetime_for_writing(batch.clone(), RebaseMode::Legacy)?;
+        assert_eq!(rebased.column(0), batch.column(0));
+        assert_eq!(
+            rebased.column(1).as_ref(),
+            &Date32Array::from(vec![Some(-719164), None]) as &dyn arrow::array::Array,
+        );
+        assert_eq!(
+            rebase_datetime_for_writing(batch.clone(), RebaseMode::Corrected)?,
+            batch
+        );
+        assert!(rebase_datetime_for_writing(batch.clone(), RebaseMode::Exception).is_err());
         Ok(())
     }
 }
@@ -71,6 +71,10 @@ impl ProjectExec {
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    pub fn exprs(&self) -> &[(PhysicalExprRef, String)] {
+        &self.expr
+    }
 }
 
 impl DisplayAs for ProjectExec {
@@ -210,7 +214,8 @@ async fn execute_project_with_filtering(
         .skip(num_exprs)
         .cloned()
         .collect::<Vec<PhysicalExprRef>>();
-    let cached_expr_evaluator = CachedExprsEvaluator::try_new(filters, exprs)?;
+    let cached_expr_evaluator =
+        CachedExprsEvaluator::try_new_with_metrics(filters, exprs, &metrics, partition)?;
 
     let mut input = stat_input(
         InputBatchStatistics::from_metrics_set_and_blaze_conf(&metrics, partition)?,
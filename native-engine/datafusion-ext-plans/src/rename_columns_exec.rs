@@ -19,7 +19,8 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::agg::AGG_BUF_COLUMN_NAME;
-use arrow::datatypes::{Field, Fields, Schema, SchemaRef};
+use arrow::array::{make_array, Array, ArrayData, ArrayRef};
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use datafusion::error::DataFusionError;
@@ -33,10 +34,20 @@ use datafusion::physical_plan::{
 };
 use futures::{Stream, StreamExt};
 
+/// renames a nested struct field, the field is located by the input column
+/// index and the path of struct field names (before renaming) in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NestedFieldRename {
+    pub column_index: usize,
+    pub field_path: Vec<String>,
+    pub new_name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct RenameColumnsExec {
     input: Arc<dyn ExecutionPlan>,
     renamed_column_names: Vec<String>,
+    nested_renames: Vec<NestedFieldRename>,
     renamed_schema: SchemaRef,
     metrics: ExecutionPlanMetricsSet,
 }
@@ -45,6 +56,14 @@ impl RenameColumnsExec {
     pub fn try_new(
         input: Arc<dyn ExecutionPlan>,
         renamed_column_names: Vec<String>,
+    ) -> Result<Self> {
+        Self::try_new_with_nested_renames(input, renamed_column_names, vec![])
+    }
+
+    pub fn try_new_with_nested_renames(
+        input: Arc<dyn ExecutionPlan>,
+        renamed_column_names: Vec<String>,
+        nested_renames: Vec<NestedFieldRename>,
     ) -> Result<Self> {
         let input_schema = input.schema();
         let mut new_names = vec![];
@@ -65,12 +84,41 @@ impl RenameColumnsExec {
             )));
         }
         let renamed_column_names = new_names;
+
+        let mut renamed_data_types = input_schema
+            .fields()
+            .iter()
+            .map(|field| field.data_type().clone())
+            .collect::<Vec<_>>();
+        for nested_rename in &nested_renames {
+            let data_type = renamed_data_types
+                .get_mut(nested_rename.column_index)
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "invalid column index of nested rename: {:?}",
+                        nested_rename,
+                    ))
+                })?;
+            *data_type = rename_nested_field(
+                data_type,
+                &nested_rename.field_path,
+                &nested_rename.new_name,
+            )
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "invalid field path of nested rename: {:?}, input schema: {}",
+                    nested_rename, input_schema,
+                ))
+            })?;
+        }
+
         let renamed_schema = Arc::new(Schema::new(
             renamed_column_names
                 .iter()
+                .zip(renamed_data_types)
                 .zip(input_schema.fields())
-                .map(|(new_name, field)| {
-                    Field::new(new_name, field.data_type().clone(), field.is_nullable())
+                .map(|((new_name, data_type), field)| {
+                    Field::new(new_name, data_type, field.is_nullable())
                 })
                 .collect::<Fields>(),
         ));
@@ -78,10 +126,69 @@ impl RenameColumnsExec {
         Ok(Self {
             input,
             renamed_column_names,
+            nested_renames,
             renamed_schema,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
+
+    pub fn nested_renames(&self) -> &[NestedFieldRename] {
+        &self.nested_renames
+    }
+}
+
+/// renames the struct field located by `path` in `data_type`, returns None if
+/// the field is not found
+fn rename_nested_field(data_type: &DataType, path: &[String], new_name: &str) -> Option<DataType> {
+    let fields = match data_type {
+        DataType::Struct(fields) => fields,
+        _ => return None,
+    };
+    let (name, rest_path) = path.split_first()?;
+    let idx = fields.iter().position(|field| field.name() == name)?;
+    let field = &fields[idx];
+    let renamed_field = if rest_path.is_empty() {
+        field.as_ref().clone().with_name(new_name)
+    } else {
+        let renamed_data_type = rename_nested_field(field.data_type(), rest_path, new_name)?;
+        field.as_ref().clone().with_data_type(renamed_data_type)
+    };
+
+    let mut renamed_fields = fields.iter().cloned().collect::<Vec<_>>();
+    renamed_fields[idx] = Arc::new(renamed_field);
+    Some(DataType::Struct(renamed_fields.into()))
+}
+
+/// replaces data type of the array with an equivalent one which differs only
+/// in nested field names, without copying any buffers
+fn rename_array_fields(array: &ArrayRef, data_type: &DataType) -> ArrayRef {
+    fn rename_data_fields(data: &ArrayData, data_type: &DataType) -> ArrayData {
+        if data.data_type() == data_type {
+            return data.clone();
+        }
+        let child_data = match data_type {
+            DataType::Struct(fields) => data
+                .child_data()
+                .iter()
+                .zip(fields)
+                .map(|(child, field)| rename_data_fields(child, field.data_type()))
+                .collect(),
+            DataType::List(field) | DataType::LargeList(field) | DataType::Map(field, _) => {
+                vec![rename_data_fields(&data.child_data()[0], field.data_type())]
+            }
+            _ => data.child_data().to_vec(),
+        };
+
+        // safety: only field names are changed, the data layout is identical
+        unsafe {
+            data.clone()
+                .into_builder()
+                .data_type(data_type.clone())
+                .child_data(child_data)
+                .build_unchecked()
+        }
+    }
+    make_array(rename_data_fields(&array.to_data(), data_type))
 }
 
 impl DisplayAs for RenameColumnsExec {
@@ -121,9 +228,10 @@ impl ExecutionPlan for RenameColumnsExec {
                 "RenameColumnsExec expects one children".to_string(),
             ));
         }
-        Ok(Arc::new(RenameColumnsExec::try_new(
+        Ok(Arc::new(RenameColumnsExec::try_new_with_nested_renames(
             children[0].clone(),
             self.renamed_column_names.clone(),
+            self.nested_renames.clone(),
         )?))
     }
 
@@ -184,10 +292,21 @@ impl Stream for RenameColumnsStream {
             Poll::Pending => Poll::Pending,
             Poll::Ready(None) => Poll::Ready(None),
             Poll::Ready(Some(batch)) => {
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .zip(self.schema.fields())
+                    .map(|(column, field)| {
+                        if column.data_type() != field.data_type() {
+                            return rename_array_fields(column, field.data_type());
+                        }
+                        column.clone()
+                    })
+                    .collect();
                 self.baseline_metrics
                     .record_poll(Poll::Ready(Some(Ok(RecordBatch::try_new(
                         self.schema.clone(),
-                        batch.columns().to_vec(),
+                        columns,
                     )?))))
             }
         }
@@ -197,3 +316,89 @@ impl Stream for RenameColumnsStream {
         self.input.size_hint()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::rename_columns_exec::{NestedFieldRename, RenameColumnsExec};
+    use arrow::array::{as_struct_array, Array, ArrayRef, Int32Array, ListArray, StructArray};
+    use arrow::datatypes::{DataType, Field, Fields, Int32Type};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::common::Result;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::physical_plan::{common, ExecutionPlan};
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_nested_renames() -> Result<()> {
+        // s: struct<A: int, B: struct<C: list<int>>>
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            None,
+        ]));
+        let inner = StructArray::from(vec![(
+            Arc::new(Field::new("C", list.data_type().clone(), true)),
+            list,
+        )]);
+        let outer: ArrayRef = Arc::new(StructArray::from(vec![
+            (
+                Arc::new(Field::new("A", DataType::Int32, false)),
+                Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+            ),
+            (
+                Arc::new(Field::new("B", inner.data_type().clone(), false)),
+                Arc::new(inner) as ArrayRef,
+            ),
+        ]));
+        let batch = RecordBatch::try_from_iter(vec![("S", outer)])?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+
+        let rename = |path: &[&str], new_name: &str| NestedFieldRename {
+            column_index: 0,
+            field_path: path.iter().map(|s| s.to_string()).collect(),
+            new_name: new_name.to_string(),
+        };
+        let rename_columns = RenameColumnsExec::try_new_with_nested_renames(
+            input.clone(),
+            vec!["s".to_string()],
+            vec![rename(&["A"], "a"), rename(&["B", "C"], "c"), rename(&["B"], "b")],
+        )?;
+
+        let inner_type = DataType::Struct(Fields::from(vec![Field::new(
+            "c",
+            DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+            true,
+        )]));
+        let expected_type = DataType::Struct(Fields::from(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", inner_type, false),
+        ]));
+        assert_eq!(rename_columns.schema().field(0).name(), "s");
+        assert_eq!(rename_columns.schema().field(0).data_type(), &expected_type);
+
+        let session_ctx = SessionContext::new();
+        let output = rename_columns.execute(0, session_ctx.task_ctx())?;
+        let batches = common::collect(output).await?;
+        assert_eq!(batches[0].column(0).data_type(), &expected_type);
+        let renamed = as_struct_array(batches[0].column(0));
+        let original = as_struct_array(batch.column(0));
+        assert_eq!(renamed.column(0), original.column(0));
+        assert_eq!(
+            as_struct_array(renamed.column(1)).column(0),
+            as_struct_array(original.column(1)).column(0)
+        );
+
+        // invalid path
+        let err = RenameColumnsExec::try_new_with_nested_renames(
+            input,
+            vec!["s".to_string()],
+            vec![rename(&["A", "x"], "y")],
+        );
+        assert!(err.is_err());
+        Ok(())
+    }
+}
@@ -25,11 +25,14 @@ use datafusion::arrow::error::ArrowError;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
 
-use crate::common::memory_manager::MemManager;
+use crate::common::memory_manager::{MemConsumer, MemManager};
+use crate::shuffle::rss::RssWriter;
 use crate::shuffle::rss_bucket_repartitioner::RssBucketShuffleRepartitioner;
 use crate::shuffle::rss_single_repartitioner::RssSingleShuffleRepartitioner;
 use crate::shuffle::rss_sort_repartitioner::RssSortShuffleRepartitioner;
-use crate::shuffle::{can_use_bucket_repartitioner, ShuffleRepartitioner};
+use crate::shuffle::{
+    can_use_bucket_repartitioner, shuffle_compression_codec, ShuffleRepartitioner,
+};
 use blaze_jni_bridge::{jni_call_static, jni_new_global_ref, jni_new_string};
 use datafusion::physical_plan::expressions::PhysicalSortExpr;
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
@@ -40,8 +43,11 @@ use datafusion::physical_plan::Partitioning;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::physical_plan::Statistics;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType};
+use datafusion_ext_commons::cancellation::task_cancellation_token;
+use datafusion_ext_commons::streams::cancellable_stream::CancellableStream;
 use futures::stream::once;
 use futures::{TryFutureExt, TryStreamExt};
+use tracing::Instrument;
 
 /// The rss shuffle writer operator maps each input partition to M output partitions based on a
 /// partitioning scheme. No guarantees are made about the order of the resulting partitions.
@@ -116,15 +122,20 @@ impl ExecutionPlan for RssShuffleWriterExec {
         let rss_partition_writer_local = jni_call_static!(
             JniBridge.getResource(resource_id.as_obj()) -> JObject
         )?;
-        let rss_partition_writer = jni_new_global_ref!(rss_partition_writer_local.as_obj())?;
+        let rss_writer = RssWriter::new(jni_new_global_ref!(rss_partition_writer_local.as_obj())?);
 
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
+        let codec = shuffle_compression_codec()?;
 
-        let input = self.input.execute(partition, context.clone())?;
+        let input = Box::pin(CancellableStream::new(
+            self.input.execute(partition, context.clone())?,
+            task_cancellation_token(&context),
+        ));
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(RssSingleShuffleRepartitioner::new(
-                rss_partition_writer,
+                rss_writer,
+                codec,
                 data_size_metric,
             )),
             p @ Partitioning::Hash(_, _)
@@ -133,25 +144,29 @@ impl ExecutionPlan for RssShuffleWriterExec {
             {
                 let partitioner = Arc::new(RssBucketShuffleRepartitioner::new(
                     partition,
-                    rss_partition_writer,
+                    rss_writer,
                     self.schema(),
                     self.partitioning.clone(),
+                    codec,
                     data_size_metric,
                     context.clone(),
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
+                partitioner.register_peak_mem_used_metric(&self.metrics, partition);
                 partitioner
             }
             Partitioning::Hash(_, _) => {
                 let partitioner = Arc::new(RssSortShuffleRepartitioner::new(
                     partition,
-                    rss_partition_writer,
+                    rss_writer,
                     self.schema(),
                     self.partitioning.clone(),
+                    codec,
                     data_size_metric,
                     context.clone(),
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
+                partitioner.register_peak_mem_used_metric(&self.metrics, partition);
                 partitioner
             }
             p => unreachable!("unsupported partitioning: {:?}", p),
@@ -164,6 +179,10 @@ impl ExecutionPlan for RssShuffleWriterExec {
                 context.session_config().batch_size(),
                 BaselineMetrics::new(&self.metrics, partition),
             )
+            .instrument(tracing::info_span!(
+                "native_operator",
+                name = "RssShuffleWriter"
+            ))
             .map_err(|e| ArrowError::ExternalError(Box::new(e)));
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
@@ -15,8 +15,8 @@
 //! Defines the sort-based shuffle writer
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::common::onheap_spill::{try_new_spill, Spill};
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner, ShuffleSpill};
+use crate::common::spill_manager::{try_new_spill, Spill};
+use crate::shuffle::{evaluate_hashes, ShuffleRepartitioner, ShuffleSpill};
 use arrow::array::*;
 use arrow::datatypes::*;
 use arrow::error::Result as ArrowResult;
@@ -28,7 +28,8 @@ use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
 use datafusion::physical_plan::Partitioning;
 use datafusion_ext_commons::array_builder::{builder_extend, make_batch, new_array_builders};
 use datafusion_ext_commons::concat_batches;
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch_with_codec, IpcCompressionCodec};
+use datafusion_ext_commons::spark_hash::create_partition_ids;
 use futures::lock::Mutex;
 use itertools::Itertools;
 use std::fs::{File, OpenOptions};
@@ -45,6 +46,7 @@ pub struct BucketShuffleRepartitioner {
     partitioning: Partitioning,
     num_output_partitions: usize,
     metrics: BaselineMetrics,
+    spill_count_metric: Count,
 }
 
 impl BucketShuffleRepartitioner {
@@ -55,8 +57,10 @@ impl BucketShuffleRepartitioner {
         output_index_file: String,
         schema: SchemaRef,
         partitioning: Partitioning,
+        codec: IpcCompressionCodec,
         metrics: BaselineMetrics,
         data_size_metric: Count,
+        spill_count_metric: Count,
         context: Arc<TaskContext>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
@@ -70,7 +74,12 @@ impl BucketShuffleRepartitioner {
             buffered_partitions: Mutex::new(
                 (0..num_output_partitions)
                     .map(|_| {
-                        PartitionBuffer::new(schema.clone(), batch_size, data_size_metric.clone())
+                        PartitionBuffer::new(
+                            schema.clone(),
+                            batch_size,
+                            codec,
+                            data_size_metric.clone(),
+                        )
                     })
                     .collect::<Vec<_>>(),
             ),
@@ -78,6 +87,7 @@ impl BucketShuffleRepartitioner {
             partitioning,
             num_output_partitions,
             metrics,
+            spill_count_metric,
         }
     }
 }
@@ -88,7 +98,7 @@ impl ShuffleRepartitioner for BucketShuffleRepartitioner {
         // compute partition ids
         let num_output_partitions = self.num_output_partitions;
         let hashes = evaluate_hashes(&self.partitioning, &input)?;
-        let partition_ids = evaluate_partition_ids(&hashes, num_output_partitions);
+        let partition_ids = create_partition_ids(&hashes, num_output_partitions);
 
         // count each partition size
         let mut partition_counters = vec![0usize; num_output_partitions];
@@ -234,6 +244,7 @@ impl ShuffleRepartitioner for BucketShuffleRepartitioner {
             .map(|spill| spill.get_disk_usage().unwrap_or(0))
             .sum::<u64>();
         self.metrics.record_spill(spill_disk_usage as usize);
+        self.spill_count_metric.add(raw_spills.len());
         self.update_mem_used(0).await?;
         Ok(())
     }
@@ -324,11 +335,17 @@ struct PartitionBuffer {
     num_staging_rows: usize,
     batch_size: usize,
     staging_size: usize,
+    codec: IpcCompressionCodec,
     data_size_metric: Count,
 }
 
 impl PartitionBuffer {
-    fn new(schema: SchemaRef, batch_size: usize, data_size_metric: Count) -> Self {
+    fn new(
+        schema: SchemaRef,
+        batch_size: usize,
+        codec: IpcCompressionCodec,
+        data_size_metric: Count,
+    ) -> Self {
         let staging_size = batch_size / (batch_size as f64 + 1.0).log2() as usize;
         Self {
             schema,
@@ -340,6 +357,7 @@ impl PartitionBuffer {
             num_staging_rows: 0,
             batch_size,
             staging_size,
+            codec,
             data_size_metric,
         }
     }
@@ -446,10 +464,10 @@ impl PartitionBuffer {
         self.data_size_metric
             .add(frozen_batch.get_array_memory_size());
         let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
+        write_one_batch_with_codec(
             &frozen_batch,
             &mut cursor,
-            true,
+            self.codec,
             Some(&mut num_bytes_written_uncompressed),
         )?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -12,28 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::onheap_spill::Spill;
 use crate::common::output::output_with_sender;
+use crate::common::spill_manager::Spill;
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static, jni_get_string};
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::BaselineMetrics;
 use datafusion::physical_plan::{Partitioning, SendableRecordBatchStream};
 use datafusion_ext_commons::array_builder::has_array_builder_supported;
-use datafusion_ext_commons::spark_hash::{create_hashes, pmod};
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use datafusion_ext_commons::spark_hash::create_murmur3_hashes;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use futures::StreamExt;
+use jni::objects::JObject;
 use std::sync::Arc;
 
 pub mod bucket_repartitioner;
 pub mod single_repartitioner;
 pub mod sort_repartitioner;
 
-mod rss;
+pub mod rss;
 pub mod rss_bucket_repartitioner;
 pub mod rss_single_repartitioner;
 pub mod rss_sort_repartitioner;
@@ -45,6 +48,29 @@ pub fn can_use_bucket_repartitioner(schema: &SchemaRef) -> bool {
         .all(|field| has_array_builder_supported(field.data_type()))
 }
 
+/// codec of shuffle data, following spark.shuffle.compress and
+/// spark.io.compression.codec. lz4 is used if not running in spark (like
+/// in benchmarks).
+pub fn shuffle_compression_codec() -> Result<IpcCompressionCodec> {
+    if !is_jni_bridge_inited() {
+        return Ok(IpcCompressionCodec::Lz4);
+    }
+    let codec = jni_call_static!(BlazeConf.shuffleCompressionCodec() -> JObject)?;
+    let codec = jni_get_string!(codec.as_obj().into())?;
+    Ok(match codec.as_str() {
+        "uncompressed" => IpcCompressionCodec::Uncompressed,
+        "lz4" => IpcCompressionCodec::Lz4,
+        "zstd" => IpcCompressionCodec::Zstd(jni_call_static!(
+            BlazeConf.shuffleCompressionZstdLevel() -> i32
+        )?),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "unsupported shuffle compression codec: {other}"
+            )))
+        }
+    })
+}
+
 #[async_trait]
 pub trait ShuffleRepartitioner: Send + Sync {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()>;
@@ -94,26 +120,14 @@ struct ShuffleSpill {
 fn evaluate_hashes(partitioning: &Partitioning, batch: &RecordBatch) -> ArrowResult<Vec<u32>> {
     match partitioning {
         Partitioning::Hash(exprs, _) => {
-            let mut hashes_buf = vec![];
             let arrays = exprs
                 .iter()
                 .map(|expr| Ok(expr.evaluate(batch)?.into_array(batch.num_rows())))
                 .collect::<Result<Vec<_>>>()?;
 
             // use identical seed as spark hash partition
-            hashes_buf.resize(arrays[0].len(), 42);
-
-            // compute hash array
-            create_hashes(&arrays, &mut hashes_buf)?;
-            Ok(hashes_buf)
+            Ok(create_murmur3_hashes(&arrays, batch.num_rows())?)
         }
         _ => unreachable!("unsupported partitioning: {:?}", partitioning),
     }
 }
-
-fn evaluate_partition_ids(hashes: &[u32], num_partitions: usize) -> Vec<u32> {
-    hashes
-        .iter()
-        .map(|hash| pmod(*hash, num_partitions) as u32)
-        .collect()
-}
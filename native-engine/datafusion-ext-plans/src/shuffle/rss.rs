@@ -13,36 +13,88 @@
 // limitations under the License.
 
 use arrow::record_batch::RecordBatch;
+use blaze_jni_bridge::batched_calls::JniCallBatcher;
 use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::common::Result;
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch_with_codec, IpcCompressionCodec};
 use jni::objects::GlobalRef;
+use parking_lot::Mutex;
 use std::io::Cursor;
+use std::sync::Arc;
 
-pub fn rss_write_batch(
+// limits of pushes batched into one jni call
+const RSS_BATCHED_MAX_WRITES: usize = 64;
+const RSS_BATCHED_MAX_BYTES: usize = 1048576;
+
+/// writes batches to rss partition writer, small writes are coalesced and
+/// pushed in one `writeBatched()` call.
+#[derive(Clone)]
+pub struct RssWriter {
+    rss_partition_writer: GlobalRef,
+    batcher: Arc<Mutex<JniCallBatcher<(usize, Vec<u8>)>>>,
+}
+
+impl RssWriter {
+    pub fn new(rss_partition_writer: GlobalRef) -> Self {
+        let writer = rss_partition_writer.clone();
+        let batcher = JniCallBatcher::new(
+            RSS_BATCHED_MAX_WRITES,
+            RSS_BATCHED_MAX_BYTES,
+            move |writes| rss_write_batched(&writer, writes),
+        );
+        Self {
+            rss_partition_writer,
+            batcher: Arc::new(Mutex::new(batcher)),
+        }
+    }
+
+    pub fn write_batch(
+        &self,
+        partition_id: usize,
+        batch: RecordBatch,
+        codec: IpcCompressionCodec,
+        uncompressed_size: &mut usize,
+    ) -> Result<()> {
+        let mut data = vec![];
+        write_one_batch_with_codec(
+            &batch,
+            &mut Cursor::new(&mut data),
+            codec,
+            Some(uncompressed_size),
+        )?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        let data_len = data.len();
+        self.batcher.lock().push((partition_id, data), data_len)
+    }
+
+    /// pushes all pending writes and flushes the rss partition writer.
+    pub fn flush(&self) -> Result<()> {
+        self.batcher.lock().flush()?;
+        jni_call!(BlazeRssPartitionWriterBase(self.rss_partition_writer.as_obj()).flush() -> ())?;
+        Ok(())
+    }
+}
+
+// index buffer contains (partition_id, length) pairs of i32 in little endian,
+// data buffer contains all data concatenated in the same order.
+fn rss_write_batched(
     rss_partition_writer: &GlobalRef,
-    partition_id: usize,
-    batch: RecordBatch,
-    uncompressed_size: &mut usize,
+    writes: Vec<(usize, Vec<u8>)>,
 ) -> Result<()> {
-    let mut data = vec![];
-
-    write_one_batch(
-        &batch,
-        &mut Cursor::new(&mut data),
-        true,
-        Some(uncompressed_size),
-    )?;
-    let data_len = data.len();
-    let buf = jni_new_direct_byte_buffer!(&data)?;
+    let mut index = Vec::with_capacity(writes.len() * 8);
+    let mut data = Vec::with_capacity(writes.iter().map(|(_, w)| w.len()).sum());
+    for (partition_id, write) in &writes {
+        index.extend_from_slice(&(*partition_id as i32).to_le_bytes());
+        index.extend_from_slice(&(write.len() as i32).to_le_bytes());
+        data.extend_from_slice(write);
+    }
+    let index_buf = jni_new_direct_byte_buffer!(&index)?;
+    let data_buf = jni_new_direct_byte_buffer!(&data)?;
     jni_call!(
         BlazeRssPartitionWriterBase(rss_partition_writer.as_obj())
-        .write(partition_id as i32, buf.as_obj(), data_len as i32) -> ()
+        .writeBatched(index_buf.as_obj(), data_buf.as_obj()) -> ()
     )?;
     Ok(())
 }
-
-pub fn rss_flush(rss_partition_writer: &GlobalRef) -> Result<()> {
-    jni_call!(BlazeRssPartitionWriterBase(rss_partition_writer.as_obj()).flush() -> ())?;
-    Ok(())
-}
@@ -15,8 +15,8 @@
 //! Defines the rss bucket shuffle repartitioner
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::shuffle::rss::{rss_flush, rss_write_batch};
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner};
+use crate::shuffle::rss::RssWriter;
+use crate::shuffle::{evaluate_hashes, ShuffleRepartitioner};
 use async_trait::async_trait;
 use datafusion::arrow::array::*;
 use datafusion::arrow::datatypes::*;
@@ -27,9 +27,10 @@ use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::Count;
 use datafusion::physical_plan::Partitioning;
 use datafusion_ext_commons::array_builder::{builder_extend, make_batch, new_array_builders};
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use datafusion_ext_commons::spark_hash::create_partition_ids;
 use futures::lock::Mutex;
 use itertools::Itertools;
-use jni::objects::GlobalRef;
 use std::sync::{Arc, Weak};
 
 pub struct RssBucketShuffleRepartitioner {
@@ -37,7 +38,7 @@ pub struct RssBucketShuffleRepartitioner {
     mem_consumer_info: Option<Weak<MemConsumerInfo>>,
     buffered_partitions: Mutex<Vec<PartitionBuffer>>,
     partitioning: Partitioning,
-    rss_partition_writer: GlobalRef,
+    rss_writer: RssWriter,
     num_output_partitions: usize,
 }
 
@@ -45,9 +46,10 @@ impl RssBucketShuffleRepartitioner {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        rss_writer: RssWriter,
         schema: SchemaRef,
         partitioning: Partitioning,
+        codec: IpcCompressionCodec,
         data_size_metric: Count,
         context: Arc<TaskContext>,
     ) -> Self {
@@ -60,7 +62,8 @@ impl RssBucketShuffleRepartitioner {
                         schema.clone(),
                         batch_size,
                         i,
-                        rss_partition_writer.clone(),
+                        rss_writer.clone(),
+                        codec,
                         data_size_metric.clone(),
                     )
                 })
@@ -71,7 +74,7 @@ impl RssBucketShuffleRepartitioner {
             mem_consumer_info: None,
             buffered_partitions,
             partitioning,
-            rss_partition_writer,
+            rss_writer,
             num_output_partitions,
         }
     }
@@ -96,7 +99,7 @@ impl ShuffleRepartitioner for RssBucketShuffleRepartitioner {
         // compute partition ids
         let num_output_partitions = self.num_output_partitions;
         let hashes = evaluate_hashes(&self.partitioning, &input)?;
-        let partition_ids = evaluate_partition_ids(&hashes, num_output_partitions);
+        let partition_ids = create_partition_ids(&hashes, num_output_partitions);
 
         // count each partition size
         let mut partition_counters = vec![0usize; num_output_partitions];
@@ -185,7 +188,7 @@ impl MemConsumer for RssBucketShuffleRepartitioner {
         for i in 0..self.num_output_partitions {
             partitions[i].flush_to_rss()?;
         }
-        rss_flush(&self.rss_partition_writer)?;
+        self.rss_writer.flush()?;
         drop(partitions);
         self.update_mem_used(0).await?;
         Ok(())
@@ -200,11 +203,12 @@ impl Drop for RssBucketShuffleRepartitioner {
 
 struct PartitionBuffer {
     partition_id: usize,
-    rss_partition_writer: GlobalRef,
+    rss_writer: RssWriter,
     schema: SchemaRef,
     active: Vec<Box<dyn ArrayBuilder>>,
     num_active_rows: usize,
     rss_batch_size: usize,
+    codec: IpcCompressionCodec,
     data_size_metric: Count,
 }
 
@@ -213,18 +217,20 @@ impl PartitionBuffer {
         schema: SchemaRef,
         batch_size: usize,
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        rss_writer: RssWriter,
+        codec: IpcCompressionCodec,
         data_size_metric: Count,
     ) -> Self {
         // use smaller batch size for rss to trigger more flushes
         let rss_batch_size = batch_size / (batch_size as f64 + 1.0).log2() as usize;
         Self {
             partition_id,
-            rss_partition_writer,
+            rss_writer,
             schema,
             active: vec![],
             num_active_rows: 0,
             rss_batch_size,
+            codec,
             data_size_metric,
         }
     }
@@ -265,10 +271,10 @@ impl PartitionBuffer {
     /// it does not affect the shuffle output result.
     fn append_batch(&mut self, batch: RecordBatch) -> Result<()> {
         let mut num_bytes_written_uncompressed = 0;
-        rss_write_batch(
-            &self.rss_partition_writer,
+        self.rss_writer.write_batch(
             self.partition_id,
             batch,
+            self.codec,
             &mut num_bytes_written_uncompressed,
         )?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -285,10 +291,10 @@ impl PartitionBuffer {
 
         let batch = make_batch(self.schema.clone(), active)?;
         let mut num_bytes_written_uncompressed = 0;
-        rss_write_batch(
-            &self.rss_partition_writer,
+        self.rss_writer.write_batch(
             self.partition_id,
             batch,
+            self.codec,
             &mut num_bytes_written_uncompressed,
         )?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -12,25 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::shuffle::rss::RssWriter;
 use crate::shuffle::ShuffleRepartitioner;
 use async_trait::async_trait;
-use blaze_jni_bridge::{jni_call, jni_new_direct_byte_buffer};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::Result;
 use datafusion::physical_plan::metrics::Count;
-use datafusion_ext_commons::io::write_one_batch;
-use jni::objects::GlobalRef;
-use std::io::Cursor;
+use datafusion_ext_commons::io::IpcCompressionCodec;
 
 pub struct RssSingleShuffleRepartitioner {
-    rss_partition_writer: GlobalRef,
+    rss_writer: RssWriter,
+    codec: IpcCompressionCodec,
     data_size_metric: Count,
 }
 
 impl RssSingleShuffleRepartitioner {
-    pub fn new(rss_partition_writer: GlobalRef, data_size_metric: Count) -> Self {
+    pub fn new(rss_writer: RssWriter, codec: IpcCompressionCodec, data_size_metric: Count) -> Self {
         Self {
-            rss_partition_writer,
+            rss_writer,
+            codec,
             data_size_metric,
         }
     }
@@ -39,28 +39,14 @@ impl RssSingleShuffleRepartitioner {
 #[async_trait]
 impl ShuffleRepartitioner for RssSingleShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
-        let mut cursor = Cursor::new(Vec::<u8>::new());
         let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
-            &input,
-            &mut cursor,
-            true,
-            Some(&mut num_bytes_written_uncompressed),
-        )?;
+        self.rss_writer
+            .write_batch(0, input, self.codec, &mut num_bytes_written_uncompressed)?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
-
-        let rss_data = cursor.into_inner();
-        let length = rss_data.len();
-        let rss_buffer = jni_new_direct_byte_buffer!(&rss_data)?;
-
-        if length != 0 {
-            jni_call!(BlazeRssPartitionWriterBase(self.rss_partition_writer.as_obj())
-                .write(0_i32, rss_buffer.as_obj(), length as i32) -> ())?;
-        }
         Ok(())
     }
 
     async fn shuffle_write(&self) -> Result<()> {
-        Ok(())
+        self.rss_writer.flush()
     }
 }
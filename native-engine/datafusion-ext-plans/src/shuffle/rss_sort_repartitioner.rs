@@ -14,9 +14,9 @@
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use crate::common::BatchesInterleaver;
-use crate::shuffle::rss::{rss_flush, rss_write_batch};
+use crate::shuffle::rss::RssWriter;
 use crate::shuffle::sort_repartitioner::PI;
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner};
+use crate::shuffle::{evaluate_hashes, ShuffleRepartitioner};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
@@ -24,8 +24,9 @@ use datafusion::common::Result;
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::Count;
 use datafusion::physical_plan::Partitioning;
+use datafusion_ext_commons::io::IpcCompressionCodec;
+use datafusion_ext_commons::spark_hash::create_partition_ids;
 use futures::lock::Mutex;
-use jni::objects::GlobalRef;
 use std::mem::size_of;
 use std::sync::{Arc, Weak};
 
@@ -35,18 +36,20 @@ pub struct RssSortShuffleRepartitioner {
     schema: SchemaRef,
     buffered_batches: Mutex<Vec<RecordBatch>>,
     partitioning: Partitioning,
-    rss_partition_writer: GlobalRef,
+    rss_writer: RssWriter,
     num_output_partitions: usize,
     batch_size: usize,
+    codec: IpcCompressionCodec,
     data_size_metric: Count,
 }
 
 impl RssSortShuffleRepartitioner {
     pub fn new(
         partition_id: usize,
-        rss_partition_writer: GlobalRef,
+        rss_writer: RssWriter,
         schema: SchemaRef,
         partitioning: Partitioning,
+        codec: IpcCompressionCodec,
         data_size_metric: Count,
         context: Arc<TaskContext>,
     ) -> Self {
@@ -59,9 +62,10 @@ impl RssSortShuffleRepartitioner {
             schema,
             buffered_batches: Mutex::default(),
             partitioning,
-            rss_partition_writer,
+            rss_writer,
             num_output_partitions,
             batch_size,
+            codec,
             data_size_metric,
         }
     }
@@ -76,7 +80,7 @@ impl RssSortShuffleRepartitioner {
         let mut pi_vec = Vec::with_capacity(num_buffered_rows);
         for (batch_idx, batch) in buffered_batches.iter().enumerate() {
             let hashes = evaluate_hashes(&self.partitioning, batch)?;
-            let partition_ids = evaluate_partition_ids(&hashes, self.num_output_partitions);
+            let partition_ids = create_partition_ids(&hashes, self.num_output_partitions);
 
             // compute partition ids and sorted indices
             pi_vec.extend(
@@ -112,10 +116,10 @@ impl RssSortShuffleRepartitioner {
                     .collect::<Vec<_>>();
                 let sub_batch = interleaver.interleave(&sub_indices)?;
                 let mut num_bytes_written_uncompressed = 0;
-                rss_write_batch(
-                    &self.rss_partition_writer,
+                self.rss_writer.write_batch(
                     cur_partition_id,
                     sub_batch,
+                    self.codec,
                     &mut num_bytes_written_uncompressed,
                 )?;
                 self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -164,7 +168,7 @@ impl MemConsumer for RssSortShuffleRepartitioner {
         if !batches.is_empty() {
             self.write_buffered_batches_to_rss(&batches)?;
         }
-        rss_flush(&self.rss_partition_writer)?;
+        self.rss_writer.flush()?;
         self.update_mem_used(0).await?;
         Ok(())
     }
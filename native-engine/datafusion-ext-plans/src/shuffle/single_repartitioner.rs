@@ -18,7 +18,7 @@ use async_trait::async_trait;
 use datafusion::common::Result;
 use datafusion::error::DataFusionError;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch_with_codec, IpcCompressionCodec};
 use once_cell::sync::OnceCell;
 use std::fs::{File, OpenOptions};
 use std::io::{Seek, Write};
@@ -27,6 +27,7 @@ pub struct SingleShuffleRepartitioner {
     output_data_file: String,
     output_index_file: String,
     output_data: OnceCell<File>,
+    codec: IpcCompressionCodec,
     metrics: BaselineMetrics,
     data_size_metric: Count,
 }
@@ -35,6 +36,7 @@ impl SingleShuffleRepartitioner {
     pub fn new(
         output_data_file: String,
         output_index_file: String,
+        codec: IpcCompressionCodec,
         metrics: BaselineMetrics,
         data_size_metric: Count,
     ) -> Self {
@@ -42,6 +44,7 @@ impl SingleShuffleRepartitioner {
             output_data_file,
             output_index_file,
             output_data: OnceCell::new(),
+            codec,
             metrics,
             data_size_metric,
         }
@@ -65,10 +68,10 @@ impl ShuffleRepartitioner for SingleShuffleRepartitioner {
     async fn insert_batch(&self, input: RecordBatch) -> Result<()> {
         let _timer = self.metrics.elapsed_compute().timer();
         let mut num_bytes_written_uncompressed = 0;
-        write_one_batch(
+        write_one_batch_with_codec(
             &input,
             &mut self.get_output_data()?.try_clone()?,
-            true,
+            self.codec,
             Some(&mut num_bytes_written_uncompressed),
         )?;
         self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -13,9 +13,9 @@
 // limitations under the License.
 
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::common::onheap_spill::{try_new_spill, Spill};
+use crate::common::spill_manager::{try_new_spill, Spill};
 use crate::common::BatchesInterleaver;
-use crate::shuffle::{evaluate_hashes, evaluate_partition_ids, ShuffleRepartitioner, ShuffleSpill};
+use crate::shuffle::{evaluate_hashes, ShuffleRepartitioner, ShuffleSpill};
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
@@ -23,8 +23,9 @@ use datafusion::common::{DataFusionError, Result};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_plan::metrics::{BaselineMetrics, Count};
 use datafusion::physical_plan::Partitioning;
-use datafusion_ext_commons::io::write_one_batch;
+use datafusion_ext_commons::io::{write_one_batch_with_codec, IpcCompressionCodec};
 use datafusion_ext_commons::loser_tree::LoserTree;
+use datafusion_ext_commons::spark_hash::create_partition_ids;
 use derivative::Derivative;
 use futures::lock::Mutex;
 use std::fs::{File, OpenOptions};
@@ -42,19 +43,24 @@ pub struct SortShuffleRepartitioner {
     partitioning: Partitioning,
     num_output_partitions: usize,
     batch_size: usize,
+    codec: IpcCompressionCodec,
     metrics: BaselineMetrics,
     data_size_metric: Count,
+    spill_count_metric: Count,
 }
 
 impl SortShuffleRepartitioner {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         partition_id: usize,
         output_data_file: String,
         output_index_file: String,
         schema: SchemaRef,
         partitioning: Partitioning,
+        codec: IpcCompressionCodec,
         metrics: BaselineMetrics,
         data_size_metric: Count,
+        spill_count_metric: Count,
         context: Arc<TaskContext>,
     ) -> Self {
         let num_output_partitions = partitioning.partition_count();
@@ -71,8 +77,10 @@ impl SortShuffleRepartitioner {
             partitioning,
             num_output_partitions,
             batch_size,
+            codec,
             metrics,
             data_size_metric,
+            spill_count_metric,
         }
     }
 
@@ -86,7 +94,7 @@ impl SortShuffleRepartitioner {
         let mut pi_vec = Vec::with_capacity(num_buffered_rows);
         for (batch_idx, batch) in buffered_batches.iter().enumerate() {
             let hashes = evaluate_hashes(&self.partitioning, batch)?;
-            let partition_ids = evaluate_partition_ids(&hashes, self.num_output_partitions);
+            let partition_ids = create_partition_ids(&hashes, self.num_output_partitions);
 
             // compute partition ids and sorted indices
             pi_vec.extend(
@@ -130,10 +138,10 @@ impl SortShuffleRepartitioner {
 
                 let mut buf = vec![];
                 let mut num_bytes_written_uncompressed = 0;
-                write_one_batch(
+                write_one_batch_with_codec(
                     &sub_batch,
                     &mut Cursor::new(&mut buf),
-                    true,
+                    self.codec,
                     Some(&mut num_bytes_written_uncompressed),
                 )?;
                 self.data_size_metric.add(num_bytes_written_uncompressed);
@@ -301,6 +309,7 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
         }
 
         // write current buffered batches into a spill
+        let num_spills = spills.len();
         spills.push(self.spill_buffered_batches(&batches)?);
         batches.clear();
 
@@ -389,6 +398,7 @@ impl ShuffleRepartitioner for SortShuffleRepartitioner {
             .map(|spill| spill.get_disk_usage().unwrap_or(0))
             .sum::<u64>();
         self.metrics.record_spill(spill_disk_usage as usize);
+        self.spill_count_metric.add(num_spills);
         self.update_mem_used(0).await?;
         Ok(())
     }
@@ -19,11 +19,13 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use crate::common::batch_statisitcs::{stat_input, InputBatchStatistics};
-use crate::common::memory_manager::MemManager;
+use crate::common::memory_manager::{MemConsumer, MemManager};
 use crate::shuffle::bucket_repartitioner::BucketShuffleRepartitioner;
 use crate::shuffle::single_repartitioner::SingleShuffleRepartitioner;
 use crate::shuffle::sort_repartitioner::SortShuffleRepartitioner;
-use crate::shuffle::{can_use_bucket_repartitioner, ShuffleRepartitioner};
+use crate::shuffle::{
+    can_use_bucket_repartitioner, shuffle_compression_codec, ShuffleRepartitioner,
+};
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use async_trait::async_trait;
@@ -38,8 +40,11 @@ use datafusion::physical_plan::Partitioning;
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion::physical_plan::Statistics;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType};
+use datafusion_ext_commons::cancellation::task_cancellation_token;
+use datafusion_ext_commons::streams::cancellable_stream::CancellableStream;
 use futures::stream::once;
 use futures::{TryFutureExt, TryStreamExt};
+use tracing::Instrument;
 
 /// The shuffle writer operator maps each input partition to M output partitions based on a
 /// partitioning scheme. No guarantees are made about the order of the resulting partitions.
@@ -111,11 +116,14 @@ impl ExecutionPlan for ShuffleWriterExec {
     ) -> Result<SendableRecordBatchStream> {
         // record uncompressed data size
         let data_size_metric = MetricBuilder::new(&self.metrics).counter("data_size", partition);
+        let spill_count_metric = MetricBuilder::new(&self.metrics).spill_count(partition);
+        let codec = shuffle_compression_codec()?;
 
         let repartitioner: Arc<dyn ShuffleRepartitioner> = match &self.partitioning {
             p if p.partition_count() == 1 => Arc::new(SingleShuffleRepartitioner::new(
                 self.output_data_file.clone(),
                 self.output_index_file.clone(),
+                codec,
                 BaselineMetrics::new(&self.metrics, partition),
                 data_size_metric,
             )),
@@ -129,11 +137,14 @@ impl ExecutionPlan for ShuffleWriterExec {
                     self.output_index_file.clone(),
                     self.schema(),
                     self.partitioning.clone(),
+                    codec,
                     BaselineMetrics::new(&self.metrics, partition),
                     data_size_metric,
+                    spill_count_metric,
                     context.clone(),
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
+                partitioner.register_peak_mem_used_metric(&self.metrics, partition);
                 partitioner
             }
             Partitioning::Hash(_, _) => {
@@ -143,11 +154,14 @@ impl ExecutionPlan for ShuffleWriterExec {
                     self.output_index_file.clone(),
                     self.schema(),
                     self.partitioning.clone(),
+                    codec,
                     BaselineMetrics::new(&self.metrics, partition),
                     data_size_metric,
+                    spill_count_metric,
                     context.clone(),
                 ));
                 MemManager::register_consumer(partitioner.clone(), true);
+                partitioner.register_peak_mem_used_metric(&self.metrics, partition);
                 partitioner
             }
             p => unreachable!("unsupported partitioning: {:?}", p),
@@ -157,6 +171,10 @@ impl ExecutionPlan for ShuffleWriterExec {
             InputBatchStatistics::from_metrics_set_and_blaze_conf(&self.metrics, partition)?,
             self.input.execute(partition, context.clone())?,
         )?;
+        let input = Box::pin(CancellableStream::new(
+            input,
+            task_cancellation_token(&context),
+        ));
         let stream = repartitioner
             .execute(
                 context.clone(),
@@ -164,6 +182,10 @@ impl ExecutionPlan for ShuffleWriterExec {
                 context.session_config().batch_size(),
                 BaselineMetrics::new(&self.metrics, partition),
             )
+            .instrument(tracing::info_span!(
+                "native_operator",
+                name = "ShuffleWriter"
+            ))
             .map_err(|e| ArrowError::ExternalError(Box::new(e)));
 
         Ok(Box::pin(RecordBatchStreamAdapter::new(
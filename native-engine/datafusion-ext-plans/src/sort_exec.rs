@@ -18,14 +18,15 @@ use crate::common::batch_statisitcs::{stat_input, InputBatchStatistics};
 use crate::common::bytes_arena::BytesArena;
 use crate::common::column_pruning::ExecuteWithColumnPruning;
 use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
-use crate::common::onheap_spill::{try_new_spill, Spill};
 use crate::common::output::{
     output_bufferable_with_spill, output_with_sender, WrappedRecordBatchSender,
 };
+use crate::common::rdxsort::radix_sort_u64_by;
 use crate::common::slim_bytes::SlimBytes;
+use crate::common::spill_manager::{Spill, SpillManager, SpillReader};
 use crate::common::{BatchTaker, BatchesInterleaver};
-use arrow::array::ArrayRef;
-use arrow::datatypes::SchemaRef;
+use arrow::array::{as_primitive_array, Array, ArrayRef};
+use arrow::datatypes::*;
 use arrow::record_batch::RecordBatch;
 use arrow::row::{RowConverter, SortField};
 use async_trait::async_trait;
@@ -37,29 +38,29 @@ use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
 };
-use datafusion_ext_commons::io::{
-    read_bytes_slice, read_len, read_one_batch, write_len, write_one_batch,
-};
+use datafusion_ext_commons::io::{read_bytes_slice, read_len, write_len};
 use datafusion_ext_commons::loser_tree::LoserTree;
 use datafusion_ext_commons::streams::coalesce_stream::CoalesceStream;
 use futures::lock::Mutex;
 use futures::stream::once;
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
-use lz4_flex::frame::FrameDecoder;
 use parking_lot::Mutex as SyncMutex;
 use std::any::Any;
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::fmt::Formatter;
-use std::io::{BufReader, Cursor, Read, Write};
+use std::io::Write;
 use std::mem::size_of;
 use std::sync::{Arc, Weak};
+use tokio::task::JoinHandle;
 
 const NUM_LEVELS: usize = 64;
 
 // reserve memory for each spill
 // estimated size: bufread=64KB + lz4dec.src=64KB + lz4dec.dest=64KB + batches=~100KB
-const SPILL_OFFHEAP_MEM_COST: usize = 300000;
+// + prefetched block=~100KB (each cursor decodes one block ahead while merging)
+const SPILL_OFFHEAP_MEM_COST: usize = 400000;
 
 #[derive(Debug)]
 pub struct SortExec {
@@ -83,6 +84,14 @@ impl SortExec {
             metrics,
         }
     }
+
+    pub fn exprs(&self) -> &[PhysicalSortExpr] {
+        &self.exprs
+    }
+
+    pub fn fetch(&self) -> Option<usize> {
+        self.fetch
+    }
 }
 
 impl DisplayAs for SortExec {
@@ -158,6 +167,7 @@ struct ExternalSorter {
     sort_row_converter: SyncMutex<RowConverter>,
     levels: Mutex<Vec<Option<SortedBatches>>>,
     spills: Mutex<Vec<Box<dyn Spill>>>,
+    spill_manager: SpillManager,
     baseline_metrics: BaselineMetrics,
     projection: Vec<usize>,
 }
@@ -234,9 +244,11 @@ impl ExecuteWithColumnPruning for SortExec {
             sort_row_converter: SyncMutex::new(sort_row_converter),
             levels: Mutex::new((0..NUM_LEVELS).map(|_| None).collect()),
             spills: Default::default(),
+            spill_manager: SpillManager::new(&self.metrics, partition),
             baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
         });
         MemManager::register_consumer(external_sorter.clone(), true);
+        external_sorter.register_peak_mem_used_metric(&self.metrics, partition);
 
         let input = stat_input(
             InputBatchStatistics::from_metrics_set_and_blaze_conf(&self.metrics, partition)?,
@@ -298,7 +310,8 @@ async fn external_sort(
 
     // if running in-memory, buffer output when memory usage is high
     if !has_spill {
-        return output_bufferable_with_spill(sorter_cloned, context, output);
+        let spill_manager = sorter_cloned.spill_manager.clone();
+        return output_bufferable_with_spill(sorter_cloned, spill_manager, context, output);
     }
     Ok(output)
 }
@@ -416,19 +429,23 @@ impl ExternalSorter {
         self.update_mem_used(spills.len() * SPILL_OFFHEAP_MEM_COST)
             .await?;
 
+        // start reading all spills before waiting for the first keys, so that
+        // the first blocks are decoded in parallel
+        let mut spill_cursors = spills
+            .iter()
+            .enumerate()
+            .map(|(id, spill)| SpillCursor::new(id, self.clone(), &spill))
+            .collect::<Vec<_>>();
+        for cursor in &mut spill_cursors {
+            cursor.next_key().await?; // load first record into current
+        }
+
         // use loser tree to merge all spills
-        let mut cursors: LoserTree<SpillCursor> = LoserTree::new_by(
-            spills
-                .iter()
-                .enumerate()
-                .map(|(id, spill)| SpillCursor::try_from_spill(id, self.clone(), &spill))
-                .collect::<Result<_>>()?,
-            |c1, c2| {
-                let key1 = (c1.finished, &c1.cur_key);
-                let key2 = (c2.finished, &c2.cur_key);
-                key1 < key2
-            },
-        );
+        let mut cursors: LoserTree<SpillCursor> = LoserTree::new_by(spill_cursors, |c1, c2| {
+            let key1 = (c1.finished, c1.cur_key_prefix, &c1.cur_key);
+            let key2 = (c2.finished, c2.cur_key_prefix, &c2.cur_key);
+            key1 < key2
+        });
 
         let mut num_total_output_rows = 0;
         let mut staging_cursor_ids = Vec::with_capacity(self.sub_batch_size);
@@ -479,7 +496,7 @@ impl ExternalSorter {
                 break;
             }
             staging_cursor_ids.push(min_cursor.id);
-            min_cursor.next_key()?;
+            min_cursor.next_key().await?;
             drop(min_cursor);
 
             if staging_cursor_ids.len() >= self.sub_batch_size {
@@ -493,24 +510,94 @@ impl ExternalSorter {
         if !staging_cursor_ids.is_empty() {
             flush_staging!();
         }
-
-        // update disk spill size
-        let spill_disk_usage = spills
-            .iter()
-            .map(|spill| spill.get_disk_usage().unwrap_or(0))
-            .sum::<u64>();
-        self.baseline_metrics
-            .record_spill(spill_disk_usage as usize);
         self.update_mem_used(0).await?;
         Ok(())
     }
 }
 
+/// normalized key prefix: leading bytes of a sort key packed into a big-endian
+/// u64. sort keys are memcmp-comparable, so keys with different prefixes are
+/// ordered by their prefixes and full keys are compared only on ties.
+#[inline]
+fn key_prefix(key: &[u8]) -> u64 {
+    let mut prefix = [0u8; 8];
+    let len = key.len().min(8);
+    prefix[..len].copy_from_slice(&key[..len]);
+    u64::from_be_bytes(prefix)
+}
+
+/// sorts row indices with radix sort if the sort key is a single integral
+/// column, returns None if not applicable (including when a fetch limit is
+/// present, in which case partial comparison sort is cheaper)
+fn radix_sorted_indices(
+    key_cols: &[ArrayRef],
+    exprs: &[PhysicalSortExpr],
+    limit: usize,
+) -> Option<Vec<u32>> {
+    if key_cols.len() != 1 || limit != usize::MAX {
+        return None;
+    }
+    let key_col = &key_cols[0];
+    let options = exprs[0].options;
+
+    macro_rules! radix_sort_primitive {
+        ($ty:ty) => {{
+            let array = as_primitive_array::<$ty>(key_col);
+            let mut null_indices = vec![];
+            let mut keyed_indices = Vec::with_capacity(array.len() - array.null_count());
+            for (idx, value) in array.iter().enumerate() {
+                match value {
+                    Some(value) => {
+                        // flip sign bit so that unsigned order matches signed order
+                        let key = (value as i64 as u64) ^ (1 << 63);
+                        let key = if options.descending { !key } else { key };
+                        keyed_indices.push((key, idx as u32));
+                    }
+                    None => null_indices.push(idx as u32),
+                }
+            }
+            radix_sort_u64_by(&mut keyed_indices, |&(key, _)| key);
+
+            let sorted_indices = keyed_indices.into_iter().map(|(_, idx)| idx);
+            Some(if options.nulls_first {
+                null_indices.into_iter().chain(sorted_indices).collect()
+            } else {
+                sorted_indices.chain(null_indices).collect()
+            })
+        }};
+    }
+    match key_col.data_type() {
+        DataType::Int8 => radix_sort_primitive!(Int8Type),
+        DataType::Int16 => radix_sort_primitive!(Int16Type),
+        DataType::Int32 => radix_sort_primitive!(Int32Type),
+        DataType::Int64 => radix_sort_primitive!(Int64Type),
+        DataType::Date32 => radix_sort_primitive!(Date32Type),
+        DataType::Date64 => radix_sort_primitive!(Date64Type),
+        DataType::Timestamp(TimeUnit::Second, _) => radix_sort_primitive!(TimestampSecondType),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            radix_sort_primitive!(TimestampMillisecondType)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            radix_sort_primitive!(TimestampMicrosecondType)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            radix_sort_primitive!(TimestampNanosecondType)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SortKey {
+    prefix: u64,
+    addr: u64,
+}
+
 struct SortedBatches {
     sorter: Arc<ExternalSorter>,
     batches: Vec<RecordBatch>,
     batches_mem_size: usize,
-    keys: Vec<u64>,
+    keys: Vec<SortKey>,
     key_data: BytesArena,
 }
 
@@ -539,16 +626,35 @@ impl SortedBatches {
 
         // sort keys
         let mut key_data = BytesArena::default();
-        let (indices, keys): (Vec<u32>, Vec<u64>) = sorter
+        let rows = sorter
             .sort_row_converter
             .lock()
-            .convert_columns(&key_cols)?
-            .iter()
-            .enumerate()
-            .sorted_unstable_by(|(_, row1), (_, row2)| row1.cmp(row2))
-            .take(sorter.limit)
-            .map(|(idx, row)| (idx as u32, key_data.add(row.as_ref())))
-            .unzip();
+            .convert_columns(&key_cols)?;
+        let (indices, keys): (Vec<u32>, Vec<SortKey>) =
+            match radix_sorted_indices(&key_cols, &sorter.exprs, sorter.limit) {
+                Some(sorted_indices) => sorted_indices
+                    .into_iter()
+                    .map(|idx| {
+                        let row = rows.row(idx as usize);
+                        let prefix = key_prefix(row.as_ref());
+                        let addr = key_data.add(row.as_ref());
+                        (idx, SortKey { prefix, addr })
+                    })
+                    .unzip(),
+                None => rows
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, row)| (key_prefix(row.as_ref()), idx, row))
+                    .sorted_unstable_by(|(prefix1, _, row1), (prefix2, _, row2)| {
+                        prefix1.cmp(prefix2).then_with(|| row1.cmp(row2))
+                    })
+                    .take(sorter.limit)
+                    .map(|(prefix, idx, row)| {
+                        let addr = key_data.add(row.as_ref());
+                        (idx as u32, SortKey { prefix, addr })
+                    })
+                    .unzip(),
+            };
 
         // get projected sorted batch
         let batch = BatchTaker(&batch.project(&sorter.projection)?).take(indices)?;
@@ -567,7 +673,7 @@ impl SortedBatches {
     fn mem_size(&self) -> usize {
         // keys and batches are doubled during merging
         self.key_data.mem_size()
-            + self.keys.capacity() * size_of::<u64>() * 2
+            + self.keys.capacity() * size_of::<SortKey>() * 2
             + self.batches_mem_size * 2
     }
 
@@ -589,12 +695,12 @@ impl SortedBatches {
         a: SortedBatches,
         b: SortedBatches,
         key_data: Option<&'a mut BytesArena>,
-    ) -> Box<dyn Iterator<Item = Result<(RecordBatch, Vec<u64>)>> + Send + 'a> {
+    ) -> Box<dyn Iterator<Item = Result<(RecordBatch, Vec<SortKey>)>> + Send + 'a> {
         struct InputCursor {
             batches: VecDeque<RecordBatch>,
             batch_idx: usize,
             row_idx: usize,
-            keys: VecDeque<u64>,
+            keys: VecDeque<SortKey>,
             key_data: BytesArena,
         }
 
@@ -608,7 +714,7 @@ impl SortedBatches {
         }
 
         impl Iterator for MergeCursor<'_> {
-            type Item = Result<(RecordBatch, Vec<u64>)>;
+            type Item = Result<(RecordBatch, Vec<SortKey>)>;
 
             fn next(&mut self) -> Option<Self::Item> {
                 if self.cursors[0].keys.is_empty() && self.cursors[1].keys.is_empty() {
@@ -636,11 +742,14 @@ impl SortedBatches {
                     let key_a = self.cursors[0].keys.front();
                     let key_b = self.cursors[1].keys.front();
                     let min_cursor_id = match (key_a, key_b) {
-                        (Some(a), Some(b)) => {
-                            let key_a = self.cursors[0].key_data.get(*a);
-                            let key_b = self.cursors[1].key_data.get(*b);
-                            (key_b < key_a) as usize
-                        }
+                        (Some(a), Some(b)) => match b.prefix.cmp(&a.prefix) {
+                            Ordering::Equal => {
+                                let key_a = self.cursors[0].key_data.get(a.addr);
+                                let key_b = self.cursors[1].key_data.get(b.addr);
+                                (key_b < key_a) as usize
+                            }
+                            ord => (ord == Ordering::Less) as usize,
+                        },
                         (Some(_), None) => 0,
                         (None, Some(_)) => 1,
                         (None, None) => break,
@@ -651,10 +760,15 @@ impl SortedBatches {
                     indices.push((min_cursor.batch_idx * 2 + min_cursor_id, min_cursor.row_idx));
 
                     // append current key
-                    let key_addr = min_cursor.keys.pop_front().unwrap();
-                    let key = min_cursor.key_data.specialized_get_and_drop_last(key_addr);
+                    let sort_key = min_cursor.keys.pop_front().unwrap();
+                    let key = min_cursor
+                        .key_data
+                        .specialized_get_and_drop_last(sort_key.addr);
                     if let Some(key_data) = self.key_data.as_mut() {
-                        keys.push(key_data.add(key));
+                        keys.push(SortKey {
+                            prefix: sort_key.prefix,
+                            addr: key_data.add(key),
+                        });
                     }
 
                     // move to next record
@@ -729,94 +843,90 @@ impl SortedBatches {
             return Ok(None);
         }
 
-        let spill = try_new_spill()?;
-        let mut writer = lz4_flex::frame::FrameEncoder::new(spill.get_buf_writer());
+        let mut writer = self.sorter.spill_manager.new_spill_writer()?;
         let mut key_idx = 0;
 
         // write batch1 + keys1, batch2 + keys2, ...
         for batch in self.batches {
-            let mut buf = vec![];
-            write_one_batch(&batch, &mut Cursor::new(&mut buf), true, None)?;
-            writer.write_all(&buf)?;
+            writer.write_batch(&batch)?;
 
             for _ in 0..batch.num_rows() {
                 let key = self
                     .key_data
-                    .specialized_get_and_drop_last(self.keys[key_idx]);
+                    .specialized_get_and_drop_last(self.keys[key_idx].addr);
                 key_idx += 1;
                 write_len(key.len(), &mut writer)?;
                 writer.write_all(key)?;
             }
         }
-        writer
-            .finish()
-            .map_err(|err| DataFusionError::Execution(format!("{}", err)))?;
-        spill.complete()?;
-        Ok(Some(spill))
+        Ok(Some(writer.finish()?))
     }
 }
 
+/// a batch read from spill, with sort keys of its rows
+type SpillBlock = (RecordBatch, VecDeque<SlimBytes>);
+
+/// reading of the next spill block, the reader is handed back along with the
+/// block so that no blocking thread is held between reads
+type SpillPrefetch = JoinHandle<Result<(SpillReader, Option<SpillBlock>)>>;
+
 struct SpillCursor {
     id: usize,
-    sorter: Arc<ExternalSorter>,
-    input: FrameDecoder<BufReader<Box<dyn Read + Send>>>,
-    cur_batch_num_rows: usize,
-    cur_loaded_num_rows: usize,
+    schema: SchemaRef,
+    prefetch: Option<SpillPrefetch>,
+    cur_keys: VecDeque<SlimBytes>,
     cur_batches: Vec<RecordBatch>,
     cur_batch_idx: usize,
     cur_row_idx: usize,
+    cur_key_prefix: u64,
     cur_key: SlimBytes,
     finished: bool,
 }
 
 impl SpillCursor {
-    fn try_from_spill(
-        id: usize,
-        sorter: Arc<ExternalSorter>,
-        spill: &Box<dyn Spill>,
-    ) -> Result<Self> {
-        let buf_reader = spill.get_buf_reader();
-        let mut iter = SpillCursor {
+    fn new(id: usize, sorter: Arc<ExternalSorter>, spill: &Box<dyn Spill>) -> Self {
+        let schema = sorter.input_projected_schema.clone();
+        let input = SpillManager::read_spill(spill.as_ref());
+        SpillCursor {
             id,
-            sorter,
-            input: FrameDecoder::new(buf_reader),
-            cur_batch_num_rows: 0,
-            cur_loaded_num_rows: 0,
+            schema: schema.clone(),
+            prefetch: Some(prefetch_spill_block(input, schema)),
+            cur_keys: VecDeque::new(),
             cur_batches: vec![],
             cur_batch_idx: 0,
             cur_row_idx: 0,
+            cur_key_prefix: 0,
             cur_key: Default::default(),
             finished: false,
-        };
-        iter.next_key()?; // load first record into current
-        Ok(iter)
+        }
     }
 
-    fn next_key(&mut self) -> Result<()> {
+    async fn next_key(&mut self) -> Result<()> {
         assert!(
             !self.finished,
             "calling next_key() on finished sort spill cursor"
         );
 
-        if self.cur_loaded_num_rows >= self.cur_batch_num_rows && !self.load_next_batch()? {
+        if self.cur_keys.is_empty() && !self.load_next_block().await? {
             return Ok(());
         }
-        let sorted_row_len = read_len(&mut self.input)?;
-        self.cur_key = read_bytes_slice(&mut self.input, sorted_row_len)?.into();
-        self.cur_loaded_num_rows += 1;
+        self.cur_key = self.cur_keys.pop_front().unwrap();
+        self.cur_key_prefix = key_prefix(&self.cur_key);
         Ok(())
     }
 
-    fn load_next_batch(&mut self) -> Result<bool> {
-        if let Some(batch) = read_one_batch(
-            &mut self.input,
-            Some(self.sorter.input_projected_schema.clone()),
-            true,
-        )? {
-            self.cur_batch_num_rows = batch.num_rows();
-            self.cur_loaded_num_rows = 0;
-            self.cur_batches.push(batch);
-            return Ok(true);
+    async fn load_next_block(&mut self) -> Result<bool> {
+        if let Some(prefetch) = self.prefetch.take() {
+            let (input, block) = prefetch
+                .await
+                .map_err(|err| DataFusionError::Execution(format!("{err}")))??;
+            if let Some((batch, keys)) = block {
+                // read the following block while this one is being merged
+                self.prefetch = Some(prefetch_spill_block(input, self.schema.clone()));
+                self.cur_keys = keys;
+                self.cur_batches.push(batch);
+                return Ok(true);
+            }
         }
         self.finished = true;
         Ok(false)
@@ -842,6 +952,27 @@ impl SpillCursor {
     }
 }
 
+fn prefetch_spill_block(mut input: SpillReader, schema: SchemaRef) -> SpillPrefetch {
+    tokio::task::spawn_blocking(move || {
+        let block = read_spill_block(&mut input, schema)?;
+        Ok((input, block))
+    })
+}
+
+fn read_spill_block(input: &mut SpillReader, schema: SchemaRef) -> Result<Option<SpillBlock>> {
+    let batch = match input.read_batch(schema)? {
+        Some(batch) => batch,
+        None => return Ok(None),
+    };
+    let keys = (0..batch.num_rows())
+        .map(|_| {
+            let sorted_row_len = read_len(input)?;
+            Ok(read_bytes_slice(input, sorted_row_len)?.into())
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some((batch, keys)))
+}
+
 fn max_level_id(levels: &[Option<SortedBatches>]) -> Option<usize> {
     levels
         .iter()
@@ -853,8 +984,8 @@ fn max_level_id(levels: &[Option<SortedBatches>]) -> Option<usize> {
 
 #[cfg(test)]
 mod test {
-    use crate::sort_exec::SortExec;
-    use arrow::array::Int32Array;
+    use crate::sort_exec::{key_prefix, SortExec};
+    use arrow::array::{ArrayRef, Int32Array, Int64Array, StringArray};
     use arrow::compute::SortOptions;
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
@@ -865,6 +996,7 @@ mod test {
     use datafusion::physical_plan::memory::MemoryExec;
     use datafusion::physical_plan::{common, ExecutionPlan};
     use datafusion::prelude::SessionContext;
+    use datafusion_ext_commons::concat_batches;
     use std::sync::Arc;
 
     fn build_table_i32(
@@ -932,6 +1064,114 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_radix_sort_with_nulls() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let k: ArrayRef = Arc::new(Int64Array::from_iter((0..1000i64).map(|i| {
+            let v = (i * 7919) % 1000 - 500;
+            (v % 7 != 0).then_some(v * 1000003)
+        })));
+        let v: ArrayRef = Arc::new(Int32Array::from_iter_values(0..1000));
+        let batch = RecordBatch::try_from_iter(vec![("k", k), ("v", v)])?;
+        let schema = batch.schema();
+
+        for (descending, nulls_first) in
+            [(false, false), (false, true), (true, false), (true, true)]
+        {
+            let sort_exprs = vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("k", 0)),
+                options: SortOptions {
+                    descending,
+                    nulls_first,
+                },
+            }];
+            let input = Arc::new(MemoryExec::try_new(
+                &[vec![batch.clone()]],
+                schema.clone(),
+                None,
+            )?);
+            let sort = Arc::new(SortExec::new(input, sort_exprs.clone(), None));
+            let output = datafusion::physical_plan::collect(sort, task_ctx.clone()).await?;
+            let a = concat_batches(&schema, &output, 1000)?;
+
+            let input = Arc::new(MemoryExec::try_new(
+                &[vec![batch.clone()]],
+                schema.clone(),
+                None,
+            )?);
+            let sort = Arc::new(datafusion::physical_plan::sorts::sort::SortExec::new(
+                sort_exprs, input,
+            ));
+            let output = datafusion::physical_plan::collect(sort, task_ctx.clone()).await?;
+            let b = concat_batches(&schema, &output, 1000)?;
+
+            // null keys are not unique, only compare the keys
+            assert_eq!(a.column(0), b.column(0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        assert_eq!(key_prefix(b""), 0);
+        assert_eq!(key_prefix(b"\x01\x02"), 0x0102000000000000);
+        assert_eq!(
+            key_prefix(b"\x01\x02\x03\x04\x05\x06\x07\x08\x09"),
+            0x0102030405060708
+        );
+
+        // shorter keys are less than longer keys with the same leading bytes
+        assert!(key_prefix(b"ab") <= key_prefix(b"ab\x00"));
+        assert!(key_prefix(b"ab") < key_prefix(b"ab\x01"));
+    }
+
+    #[tokio::test]
+    async fn test_sort_strings_with_common_prefix() -> Result<()> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let k1: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..1000).map(|i| format!("common_prefix_{}", (i * 7919) % 13)),
+        ));
+        let k2: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..1000).map(|i| format!("common_prefix_{}", (i * 104729) % 1000)),
+        ));
+        let batch = RecordBatch::try_from_iter(vec![("k1", k1), ("k2", k2)])?;
+        let schema = batch.schema();
+        let sort_exprs = vec![
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("k1", 0)),
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("k2", 1)),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+        ];
+
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()]],
+            schema.clone(),
+            None,
+        )?);
+        let sort = Arc::new(SortExec::new(input, sort_exprs.clone(), None));
+        let output = datafusion::physical_plan::collect(sort, task_ctx.clone()).await?;
+        let a = concat_batches(&schema, &output, 1000)?;
+
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let sort = Arc::new(datafusion::physical_plan::sorts::sort::SortExec::new(
+            sort_exprs, input,
+        ));
+        let output = datafusion::physical_plan::collect(sort, task_ctx).await?;
+        let b = concat_batches(&schema, &output, 1000)?;
+
+        assert!(a == b);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
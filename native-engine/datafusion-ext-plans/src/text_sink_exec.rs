@@ -0,0 +1,934 @@
+// Copyright 2022 The Blaze Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution plan for writing CSV/JSON files, the output is compatible with
+//! spark's CSVFileFormat/JsonFileFormat.
+
+use crate::parquet_sink_exec::{
+    create_fs_output_stream, file_stats_schema, file_stats_to_batch, FileStats,
+};
+use arrow::array::timezone::Tz;
+use arrow::array::*;
+use arrow::datatypes::{DataType, Fields, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike};
+use datafusion::common::{DataFusionError, Result, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricValue, MetricsSet, Time,
+};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, Metric, Partitioning, SendableRecordBatchStream,
+};
+use datafusion_ext_commons::hadoop_fs::FsDataOutputStream;
+use futures::stream::once;
+use futures::{StreamExt, TryStreamExt};
+use std::any::Any;
+use std::fmt::{Formatter, Write as FmtWrite};
+use std::sync::Arc;
+
+/// output format of text sink.
+#[derive(Debug, Clone)]
+pub enum TextSinkFormat {
+    Csv(CsvWriteOptions),
+    Json(JsonWriteOptions),
+}
+
+/// options of csv writing, same as spark's CSVOptions.
+#[derive(Debug, Clone)]
+pub struct CsvWriteOptions {
+    pub delimiter: String,
+    /// values are never quoted if quote char is not specified
+    pub quote: Option<char>,
+    pub escape: char,
+    pub quote_all: bool,
+    pub escape_quotes: bool,
+    pub header: bool,
+    pub null_value: String,
+    pub empty_value: String,
+    pub ignore_leading_white_space: bool,
+    pub ignore_trailing_white_space: bool,
+}
+
+/// options of json writing, same as spark's JSONOptions.
+#[derive(Debug, Clone)]
+pub struct JsonWriteOptions {
+    pub ignore_null_fields: bool,
+}
+
+#[derive(Debug)]
+pub struct TextSinkExec {
+    input: Arc<dyn ExecutionPlan>,
+    fs_resource_id: String,
+    path: String,
+    format: TextSinkFormat,
+    line_sep: String,
+    date_format: String,
+    timestamp_format: String,
+    timezone: String,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl TextSinkExec {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        fs_resource_id: String,
+        path: String,
+        format: TextSinkFormat,
+        line_sep: String,
+        date_format: String,
+        timestamp_format: String,
+        timezone: String,
+    ) -> Self {
+        Self {
+            input,
+            fs_resource_id,
+            path,
+            format,
+            line_sep,
+            date_format,
+            timestamp_format,
+            timezone,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for TextSinkExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> std::fmt::Result {
+        let format_name = match &self.format {
+            TextSinkFormat::Csv(_) => "csv",
+            TextSinkFormat::Json(_) => "json",
+        };
+        write!(f, "TextSink [path={}, format={}]", self.path, format_name)
+    }
+}
+
+impl ExecutionPlan for TextSinkExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        file_stats_schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self::new(
+            children[0].clone(),
+            self.fs_resource_id.clone(),
+            self.path.clone(),
+            self.format.clone(),
+            self.line_sep.clone(),
+            self.date_format.clone(),
+            self.timestamp_format.clone(),
+            self.timezone.clone(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let metrics = BaselineMetrics::new(&self.metrics, partition);
+
+        // register io_time metric
+        let io_time = Time::default();
+        let io_time_metric = Arc::new(Metric::new(
+            MetricValue::Time {
+                name: "io_time".into(),
+                time: io_time.clone(),
+            },
+            Some(partition),
+        ));
+        self.metrics.register(io_time_metric);
+
+        // register bytes_written metric
+        let bytes_written = Count::default();
+        let bytes_written_metric = Arc::new(Metric::new(
+            MetricValue::Count {
+                name: "bytes_written".into(),
+                count: bytes_written.clone(),
+            },
+            Some(partition),
+        ));
+        self.metrics.register(bytes_written_metric);
+
+        let formatter = TextFormatter::try_new(
+            self.format.clone(),
+            &self.line_sep,
+            &self.date_format,
+            &self.timestamp_format,
+            &self.timezone,
+        )?;
+
+        let fout = create_fs_output_stream(&self.fs_resource_id, &self.path, &io_time)?;
+
+        let input = self.input.execute(partition, context)?;
+        let output = Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            once(execute_text_sink(
+                input,
+                fout,
+                formatter,
+                self.path.clone(),
+                bytes_written,
+                metrics,
+            ))
+            .try_flatten(),
+        ));
+        Ok(output)
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        todo!()
+    }
+}
+
+async fn execute_text_sink(
+    mut input: SendableRecordBatchStream,
+    fout: FsDataOutputStream,
+    formatter: TextFormatter,
+    path: String,
+    bytes_written: Count,
+    metrics: BaselineMetrics,
+) -> Result<SendableRecordBatchStream> {
+    let mut buf = vec![];
+    let mut num_rows = 0;
+    formatter.write_header(&input.schema(), &mut buf);
+
+    while let Some(batch) = input.next().await.transpose()? {
+        let _timer = metrics.elapsed_compute().timer();
+        formatter.write_batch(&batch, &mut buf)?;
+        fout.write_fully(&buf)?;
+        buf.clear();
+        num_rows += batch.num_rows();
+        metrics.record_output(batch.num_rows());
+    }
+    fout.write_fully(&buf)?;
+    fout.close()?;
+    bytes_written.add(fout.num_bytes());
+
+    // output statistics of the written file
+    let file_stats_batch = file_stats_to_batch(&[FileStats {
+        path,
+        partition: String::new(),
+        num_rows,
+        num_bytes: fout.num_bytes(),
+        min_values: vec![],
+        max_values: vec![],
+        null_counts: vec![],
+        ndvs: vec![],
+    }])?;
+    Ok(Box::pin(RecordBatchStreamAdapter::new(
+        file_stats_schema(),
+        once(async move { Ok(file_stats_batch) }),
+    )))
+}
+
+/// formats record batches into csv/json lines.
+struct TextFormatter {
+    format: TextSinkFormat,
+    line_sep: String,
+    date_pattern: DatetimePattern,
+    timestamp_pattern: DatetimePattern,
+    timezone: Tz,
+}
+
+impl TextFormatter {
+    fn try_new(
+        format: TextSinkFormat,
+        line_sep: &str,
+        date_format: &str,
+        timestamp_format: &str,
+        timezone: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            format,
+            line_sep: line_sep.to_string(),
+            date_pattern: DatetimePattern::try_new(date_format)?,
+            timestamp_pattern: DatetimePattern::try_new(timestamp_format)?,
+            timezone: timezone.parse()?,
+        })
+    }
+
+    fn write_header(&self, schema: &SchemaRef, buf: &mut Vec<u8>) {
+        if let TextSinkFormat::Csv(options) = &self.format {
+            if options.header {
+                let mut line = String::new();
+                for (i, field) in schema.fields().iter().enumerate() {
+                    if i > 0 {
+                        line.push_str(&options.delimiter);
+                    }
+                    write_csv_field(Some(field.name()), options, &mut line);
+                }
+                line.push_str(&self.line_sep);
+                buf.extend_from_slice(line.as_bytes());
+            }
+        }
+    }
+
+    fn write_batch(&self, batch: &RecordBatch, buf: &mut Vec<u8>) -> Result<()> {
+        let schema = batch.schema();
+        let mut line = String::new();
+        for row in 0..batch.num_rows() {
+            match &self.format {
+                TextSinkFormat::Csv(options) => {
+                    for (i, column) in batch.columns().iter().enumerate() {
+                        if i > 0 {
+                            line.push_str(&options.delimiter);
+                        }
+                        let value = self.format_value(column, row)?;
+                        write_csv_field(value.as_deref(), options, &mut line);
+                    }
+                }
+                TextSinkFormat::Json(options) => {
+                    self.write_json_object(
+                        schema.fields(),
+                        batch.columns(),
+                        row,
+                        options,
+                        &mut line,
+                    )?;
+                }
+            }
+            line.push_str(&self.line_sep);
+            buf.extend_from_slice(line.as_bytes());
+            line.clear();
+        }
+        Ok(())
+    }
+
+    /// formats a primitive value like spark's cast to string, returns None for null.
+    fn format_value(&self, array: &dyn Array, row: usize) -> Result<Option<String>> {
+        if array.is_null(row) {
+            return Ok(None);
+        }
+        Ok(Some(match array.data_type() {
+            DataType::Float32 => {
+                let value = as_primitive_array::<Float32Type>(array).value(row);
+                format_java_float(value as f64, &format!("{:e}", value))
+            }
+            DataType::Float64 => {
+                let value = as_primitive_array::<Float64Type>(array).value(row);
+                format_java_float(value, &format!("{:e}", value))
+            }
+            DataType::Date32 => {
+                let days = as_primitive_array::<Date32Type>(array).value(row);
+                let date =
+                    NaiveDate::from_num_days_from_ce_opt(days + 719163).ok_or_else(|| {
+                        DataFusionError::Execution(format!("date out of range: {days}"))
+                    })?;
+                let mut s = String::new();
+                self.date_pattern
+                    .format(&date.and_hms_opt(0, 0, 0).unwrap(), None, &mut s)?;
+                s
+            }
+            DataType::Timestamp(unit, _) => {
+                let micros = timestamp_micros(array, row, unit);
+                let datetime = NaiveDateTime::from_timestamp_micros(micros)
+                    .map(|utc| self.timezone.from_utc_datetime(&utc))
+                    .ok_or_else(|| {
+                        DataFusionError::Execution(format!("timestamp out of range: {micros}"))
+                    })?;
+                let offset_secs = datetime.offset().fix().local_minus_utc();
+                let mut s = String::new();
+                self.timestamp_pattern.format(
+                    &datetime.naive_local(),
+                    Some(offset_secs),
+                    &mut s,
+                )?;
+                s
+            }
+            DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Decimal128(..) => array_value_to_string(array, row)?,
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "TextSink: unsupported data type: {other}"
+                )));
+            }
+        }))
+    }
+
+    fn write_json_object(
+        &self,
+        fields: &Fields,
+        columns: &[ArrayRef],
+        row: usize,
+        options: &JsonWriteOptions,
+        s: &mut String,
+    ) -> Result<()> {
+        s.push('{');
+        let mut first = true;
+        for (field, column) in fields.iter().zip(columns) {
+            if column.is_null(row) && options.ignore_null_fields {
+                continue;
+            }
+            if !first {
+                s.push(',');
+            }
+            first = false;
+            write_json_string(field.name(), s);
+            s.push(':');
+            self.write_json_value(column, row, options, s)?;
+        }
+        s.push('}');
+        Ok(())
+    }
+
+    fn write_json_value(
+        &self,
+        array: &dyn Array,
+        row: usize,
+        options: &JsonWriteOptions,
+        s: &mut String,
+    ) -> Result<()> {
+        if array.is_null(row) {
+            s.push_str("null");
+            return Ok(());
+        }
+        match array.data_type() {
+            DataType::Struct(fields) => {
+                let struct_array = as_struct_array(array);
+                self.write_json_object(fields, struct_array.columns(), row, options, s)?;
+            }
+            DataType::List(_) => {
+                let values = as_list_array(array).value(row);
+                s.push('[');
+                for i in 0..values.len() {
+                    if i > 0 {
+                        s.push(',');
+                    }
+                    self.write_json_value(&values, i, options, s)?;
+                }
+                s.push(']');
+            }
+            DataType::Map(..) => {
+                // null values in maps are always written
+                let map_array = as_map_array(array);
+                let offsets = map_array.value_offsets();
+                let (start, end) = (offsets[row] as usize, offsets[row + 1] as usize);
+                s.push('{');
+                for i in start..end {
+                    if i > start {
+                        s.push(',');
+                    }
+                    let key = self.format_value(map_array.keys(), i)?.unwrap_or_default();
+                    write_json_string(&key, s);
+                    s.push(':');
+                    self.write_json_value(map_array.values(), i, options, s)?;
+                }
+                s.push('}');
+            }
+            DataType::Binary => {
+                let value = as_generic_binary_array::<i32>(array).value(row);
+                write_json_string(&BASE64_STANDARD.encode(value), s);
+            }
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Date32 | DataType::Timestamp(..) => {
+                write_json_string(&self.format_value(array, row)?.unwrap_or_default(), s);
+            }
+            _ => {
+                // non-finite floats are quoted like jackson
+                let value = self.format_value(array, row)?.unwrap_or_default();
+                if matches!(value.as_str(), "NaN" | "Infinity" | "-Infinity") {
+                    write_json_string(&value, s);
+                } else {
+                    s.push_str(&value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn timestamp_micros(array: &dyn Array, row: usize, unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => as_primitive_array::<TimestampSecondType>(array).value(row) * 1000000,
+        TimeUnit::Millisecond => {
+            as_primitive_array::<TimestampMillisecondType>(array).value(row) * 1000
+        }
+        TimeUnit::Microsecond => as_primitive_array::<TimestampMicrosecondType>(array).value(row),
+        TimeUnit::Nanosecond => as_primitive_array::<TimestampNanosecondType>(array)
+            .value(row)
+            .div_euclid(1000),
+    }
+}
+
+/// writes a csv field like spark's univocity based csv writer.
+fn write_csv_field(value: Option<&str>, options: &CsvWriteOptions, s: &mut String) {
+    let value = match value {
+        None => return s.push_str(&options.null_value),
+        Some("") => return s.push_str(&options.empty_value),
+        Some(value) => value,
+    };
+    let mut value = value;
+    if options.ignore_leading_white_space {
+        value = value.trim_start_matches(|c: char| c <= ' ');
+    }
+    if options.ignore_trailing_white_space {
+        value = value.trim_end_matches(|c: char| c <= ' ');
+    }
+
+    let quote = match options.quote {
+        Some(quote) => quote,
+        None => return s.push_str(value),
+    };
+    let need_quote = options.quote_all
+        || value.contains(options.delimiter.as_str())
+        || value.contains(['\r', '\n'])
+        || (options.escape_quotes && value.contains(quote));
+    if !need_quote {
+        return s.push_str(value);
+    }
+    s.push(quote);
+    for c in value.chars() {
+        if c == quote {
+            s.push(options.escape);
+        }
+        s.push(c);
+    }
+    s.push(quote);
+}
+
+/// writes a json string literal with escaping, same as jackson.
+fn write_json_string(value: &str, s: &mut String) {
+    s.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            '\u{08}' => s.push_str("\\b"),
+            '\u{0c}' => s.push_str("\\f"),
+            c if c < ' ' => {
+                let _ = write!(s, "\\u{:04X}", c as u32);
+            }
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+/// formats a float value like java's Double.toString/Float.toString, sci is the
+/// shortest representation in scientific notation (like "1.25e-3").
+fn format_java_float(value: f64, sci: &str) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() {
+            "-0.0"
+        } else {
+            "0.0"
+        }
+        .to_string();
+    }
+
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((sci, "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+
+    // decimal notation for values in [1e-3, 1e7), otherwise computerized
+    // scientific notation
+    if (0..7).contains(&exp) {
+        let int_len = exp as usize + 1;
+        let digits = format!("{:0<width$}", digits, width = int_len + 1);
+        format!("{sign}{}.{}", &digits[..int_len], &digits[int_len..])
+    } else if (-3..0).contains(&exp) {
+        let zeros = "0".repeat((-exp - 1) as usize);
+        format!("{sign}0.{zeros}{digits}")
+    } else {
+        let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+        format!("{sign}{}.{fraction}E{exp}", &digits[..1])
+    }
+}
+
+/// a datetime pattern in java's DateTimeFormatter syntax, only commonly used
+/// pattern letters are supported.
+#[derive(Debug, Clone, PartialEq)]
+struct DatetimePattern(Vec<PatternToken>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternToken {
+    Literal(String),
+    Field(char, usize),
+}
+
+impl DatetimePattern {
+    fn try_new(pattern: &str) -> Result<Self> {
+        let mut tokens = vec![];
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                // quoted literal, '' is a single quote
+                '\'' => {
+                    let mut literal = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\'') if chars.peek() == Some(&'\'') => {
+                                chars.next();
+                                literal.push('\'');
+                            }
+                            Some('\'') => break,
+                            Some(c) => literal.push(c),
+                            None => break,
+                        }
+                    }
+                    if literal.is_empty() {
+                        literal.push('\'');
+                    }
+                    tokens.push(PatternToken::Literal(literal));
+                }
+
+                // optional sections are always printed in formatting
+                '[' | ']' => {}
+
+                c if c.is_ascii_alphabetic() => {
+                    let mut count = 1;
+                    while chars.peek() == Some(&c) {
+                        chars.next();
+                        count += 1;
+                    }
+                    let supported = match c {
+                        'y' | 'u' | 'd' | 'H' | 'h' | 'm' | 's' | 'a' | 'E' => count <= 4,
+                        'M' | 'L' => count <= 4,
+                        'S' => count <= 9,
+                        'X' | 'x' => count <= 5,
+                        'Z' => count <= 3 || count == 5,
+                        _ => false,
+                    };
+                    if !supported {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "unsupported datetime pattern: {pattern}"
+                        )));
+                    }
+                    tokens.push(PatternToken::Field(c, count));
+                }
+                c => tokens.push(PatternToken::Literal(c.to_string())),
+            }
+        }
+        Ok(Self(tokens))
+    }
+
+    fn format(&self, dt: &NaiveDateTime, offset_secs: Option<i32>, s: &mut String) -> Result<()> {
+        for token in &self.0 {
+            let (c, count) = match token {
+                PatternToken::Literal(literal) => {
+                    s.push_str(literal);
+                    continue;
+                }
+                PatternToken::Field(c, count) => (*c, *count),
+            };
+            let _ = match c {
+                'y' | 'u' if count == 2 => write!(s, "{:02}", dt.year().rem_euclid(100)),
+                'y' | 'u' => write!(s, "{:0width$}", dt.year(), width = count),
+                'M' | 'L' if count == 3 => write!(s, "{}", dt.format("%b")),
+                'M' | 'L' if count == 4 => write!(s, "{}", dt.format("%B")),
+                'M' | 'L' => write!(s, "{:0width$}", dt.month(), width = count),
+                'd' => write!(s, "{:0width$}", dt.day(), width = count),
+                'E' if count == 4 => write!(s, "{}", dt.format("%A")),
+                'E' => write!(s, "{}", dt.format("%a")),
+                'H' => write!(s, "{:0width$}", dt.hour(), width = count),
+                'h' => write!(s, "{:0width$}", dt.hour12().1, width = count),
+                'a' => write!(s, "{}", if dt.hour12().0 { "PM" } else { "AM" }),
+                'm' => write!(s, "{:0width$}", dt.minute(), width = count),
+                's' => write!(s, "{:0width$}", dt.second(), width = count),
+                'S' => write!(
+                    s,
+                    "{}",
+                    &format!("{:09}", dt.nanosecond() % 1000000000)[..count]
+                ),
+                'X' | 'x' | 'Z' => {
+                    let offset_secs = offset_secs.ok_or_else(|| {
+                        DataFusionError::Execution("cannot format offset of date".to_string())
+                    })?;
+                    format_offset(c, count, offset_secs, s);
+                    Ok(())
+                }
+                _ => unreachable!(),
+            };
+        }
+        Ok(())
+    }
+}
+
+fn format_offset(c: char, count: usize, offset_secs: i32, s: &mut String) {
+    if offset_secs == 0 && (c == 'X' || c == 'Z' && count == 5) {
+        s.push('Z');
+        return;
+    }
+    let sign = if offset_secs < 0 { '-' } else { '+' };
+    let abs_secs = offset_secs.abs();
+    let (hours, minutes, seconds) = (abs_secs / 3600, abs_secs / 60 % 60, abs_secs % 60);
+    let _ = match (c, count) {
+        ('X' | 'x', 1) if minutes == 0 => write!(s, "{sign}{hours:02}"),
+        ('X' | 'x', 1 | 2) | ('Z', _) if count <= 3 => write!(s, "{sign}{hours:02}{minutes:02}"),
+        ('X' | 'x', 3) | ('Z', _) => write!(s, "{sign}{hours:02}:{minutes:02}"),
+        ('X' | 'x', 4) => write!(s, "{sign}{hours:02}{minutes:02}{seconds:02}"),
+        _ => write!(s, "{sign}{hours:02}:{minutes:02}:{seconds:02}"),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::text_sink_exec::{
+        format_java_float, CsvWriteOptions, DatetimePattern, JsonWriteOptions, TextFormatter,
+        TextSinkFormat,
+    };
+    use arrow::array::*;
+    use arrow::datatypes::{DataType, Field, Fields, Schema};
+    use arrow::record_batch::RecordBatch;
+    use chrono::NaiveDate;
+    use datafusion::common::Result;
+    use std::sync::Arc;
+
+    fn csv_options() -> CsvWriteOptions {
+        CsvWriteOptions {
+            delimiter: ",".to_string(),
+            quote: Some('"'),
+            escape: '\\',
+            quote_all: false,
+            escape_quotes: true,
+            header: true,
+            null_value: "".to_string(),
+            empty_value: "\"\"".to_string(),
+            ignore_leading_white_space: true,
+            ignore_trailing_white_space: true,
+        }
+    }
+
+    #[test]
+    fn test_format_java_float() {
+        let f64_to_string = |v: f64| format_java_float(v, &format!("{:e}", v));
+        assert_eq!(f64_to_string(1.0), "1.0");
+        assert_eq!(f64_to_string(-123.45), "-123.45");
+        assert_eq!(f64_to_string(1000000.0), "1000000.0");
+        assert_eq!(f64_to_string(10000000.0), "1.0E7");
+        assert_eq!(f64_to_string(0.001), "0.001");
+        assert_eq!(f64_to_string(0.0001234), "1.234E-4");
+        assert_eq!(f64_to_string(-0.0), "-0.0");
+        assert_eq!(f64_to_string(f64::NAN), "NaN");
+        assert_eq!(f64_to_string(f64::NEG_INFINITY), "-Infinity");
+
+        let f32_value = 0.1f32;
+        assert_eq!(
+            format_java_float(f32_value as f64, &format!("{:e}", f32_value)),
+            "0.1"
+        );
+    }
+
+    #[test]
+    fn test_datetime_pattern() -> Result<()> {
+        let dt = NaiveDate::from_ymd_opt(2023, 1, 2)
+            .unwrap()
+            .and_hms_micro_opt(3, 4, 5, 123456)
+            .unwrap();
+        let format = |pattern: &str, offset_secs: Option<i32>| -> Result<String> {
+            let mut s = String::new();
+            DatetimePattern::try_new(pattern)?.format(&dt, offset_secs, &mut s)?;
+            Ok(s)
+        };
+        assert_eq!(format("yyyy-MM-dd", None)?, "2023-01-02");
+        assert_eq!(
+            format("yyyy-MM-dd'T'HH:mm:ss.SSSXXX", Some(0))?,
+            "2023-01-02T03:04:05.123Z"
+        );
+        assert_eq!(
+            format("uuuu-MM-dd'T'HH:mm:ss[.SSS][XXX]", Some(28800))?,
+            "2023-01-02T03:04:05.123+08:00"
+        );
+        assert_eq!(
+            format("dd MMM yy hh:mm a Z", Some(-16200))?,
+            "02 Jan 23 03:04 AM -0430"
+        );
+        assert_eq!(
+            format("yyyy-MM-dd HH:mm:ss.SSSSSS", None)?,
+            "2023-01-02 03:04:05.123456"
+        );
+        assert!(DatetimePattern::try_new("yyyy-MM-dd G").is_err());
+        assert!(format("XXX", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_csv() -> Result<()> {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "s",
+                Arc::new(StringArray::from(vec![
+                    Some("abc"),
+                    Some(""),
+                    None,
+                    Some("a,b"),
+                    Some("say \"hi\""),
+                    Some("  x  "),
+                ])) as ArrayRef,
+            ),
+            (
+                "d",
+                Arc::new(Float64Array::from(vec![
+                    Some(1.5),
+                    Some(1e10),
+                    None,
+                    Some(-0.5),
+                    Some(f64::NAN),
+                    Some(100.0),
+                ])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    Some(0),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(1500),
+                ])) as ArrayRef,
+            ),
+        ])?;
+        let formatter = TextFormatter::try_new(
+            TextSinkFormat::Csv(csv_options()),
+            "\n",
+            "yyyy-MM-dd",
+            "yyyy-MM-dd'T'HH:mm:ss.SSSXXX",
+            "Asia/Shanghai",
+        )?;
+        let mut buf = vec![];
+        formatter.write_header(&batch.schema(), &mut buf);
+        formatter.write_batch(&batch, &mut buf)?;
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            concat!(
+                "s,d,ts\n",
+                "abc,1.5,1970-01-01T08:00:00.000+08:00\n",
+                "\"\",1.0E10,\n",
+                ",,\n",
+                "\"a,b\",-0.5,\n",
+                "\"say \\\"hi\\\"\",NaN,\n",
+                "x,100.0,1970-01-01T08:00:00.001+08:00\n",
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_json() -> Result<()> {
+        let struct_fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Date32, true),
+        ]);
+        let structs = StructArray::new(
+            struct_fields.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+                Arc::new(Date32Array::from(vec![Some(19359), Some(0)])),
+            ],
+            None,
+        );
+        let mut list_builder = ListBuilder::new(StringBuilder::new());
+        list_builder.append_value([Some("x\"y"), None]);
+        list_builder.append_null();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("st", DataType::Struct(struct_fields), true),
+            Field::new(
+                "l",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                true,
+            ),
+            Field::new("f", DataType::Float32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(structs),
+                Arc::new(list_builder.finish()),
+                Arc::new(Float32Array::from(vec![Some(f32::INFINITY), Some(0.25)])),
+            ],
+        )?;
+
+        let format = |ignore_null_fields| -> Result<String> {
+            let formatter = TextFormatter::try_new(
+                TextSinkFormat::Json(JsonWriteOptions { ignore_null_fields }),
+                "\n",
+                "yyyy-MM-dd",
+                "yyyy-MM-dd'T'HH:mm:ss.SSSXXX",
+                "UTC",
+            )?;
+            let mut buf = vec![];
+            formatter.write_batch(&batch, &mut buf)?;
+            Ok(String::from_utf8(buf).unwrap())
+        };
+        assert_eq!(
+            format(true)?,
+            concat!(
+                "{\"st\":{\"a\":1,\"b\":\"2023-01-02\"},\"l\":[\"x\\\"y\",null],\"f\":\"Infinity\"}\n",
+                "{\"st\":{\"b\":\"1970-01-01\"},\"f\":0.25}\n",
+            )
+        );
+        assert_eq!(
+            format(false)?,
+            concat!(
+                "{\"st\":{\"a\":1,\"b\":\"2023-01-02\"},\"l\":[\"x\\\"y\",null],\"f\":\"Infinity\"}\n",
+                "{\"st\":{\"a\":null,\"b\":\"1970-01-01\"},\"l\":null,\"f\":0.25}\n",
+            )
+        );
+        Ok(())
+    }
+}
@@ -112,6 +112,17 @@ impl WindowContext {
         })
     }
 
+    /// creates a context of the same window with separated row converters, which
+    /// can be used concurrently without contention
+    pub fn clone_with_new_converters(&self) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self::try_new(
+            self.input_schema.clone(),
+            self.window_exprs.clone(),
+            self.partition_spec.clone(),
+            self.order_spec.clone(),
+        )?))
+    }
+
     pub fn has_partition(&self) -> bool {
         !self.partition_schema.fields().is_empty()
     }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::memory_manager::{MemConsumer, MemConsumerInfo, MemManager};
 use crate::common::output::output_with_sender;
 use crate::window::window_context::WindowContext;
 use crate::window::{WindowExpr, WindowFunctionProcessor};
@@ -19,7 +20,10 @@ use arrow::array::ArrayRef;
 use arrow::datatypes::SchemaRef;
 use arrow::error::ArrowError;
 use arrow::record_batch::{RecordBatch, RecordBatchOptions};
-use datafusion::common::{Result, Statistics};
+use arrow::row::OwnedRow;
+use async_trait::async_trait;
+use blaze_jni_bridge::{is_jni_bridge_inited, jni_call_static};
+use datafusion::common::{DataFusionError, Result, Statistics};
 use datafusion::execution::context::TaskContext;
 use datafusion::physical_expr::PhysicalSortExpr;
 use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
@@ -33,7 +37,8 @@ use futures::stream::once;
 use futures::{StreamExt, TryFutureExt, TryStreamExt};
 use std::any::Any;
 use std::fmt::Formatter;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::{Arc, Weak};
 
 #[derive(Debug)]
 pub struct WindowExec {
@@ -117,11 +122,15 @@ impl ExecutionPlan for WindowExec {
                 .clone(),
         ));
 
+        let (parallelism, memory_limit) = window_parallelism()?;
         let stream = execute_window(
             coalesced,
             context.clone(),
             self.context.clone(),
             BaselineMetrics::new(&self.metrics, partition),
+            parallelism,
+            memory_limit,
+            partition,
         )
         .map_err(|e| ArrowError::ExternalError(Box::new(e)));
 
@@ -140,17 +149,40 @@ impl ExecutionPlan for WindowExec {
     }
 }
 
+/// number of window partition groups evaluated concurrently and max bytes of
+/// input buffered for concurrent evaluation. windows are evaluated sequentially
+/// if not running in spark (like in benchmarks).
+fn window_parallelism() -> Result<(usize, usize)> {
+    if !is_jni_bridge_inited() {
+        return Ok((1, 0));
+    }
+    Ok((
+        jni_call_static!(BlazeConf.windowParallelism() -> i32)?.max(1) as usize,
+        jni_call_static!(BlazeConf.windowParallelMemoryLimit() -> i32)?.max(0) as usize,
+    ))
+}
+
 async fn execute_window(
     mut input: SendableRecordBatchStream,
     task_context: Arc<TaskContext>,
     context: Arc<WindowContext>,
     metrics: BaselineMetrics,
+    parallelism: usize,
+    memory_limit: usize,
+    partition: usize,
 ) -> Result<SendableRecordBatchStream> {
-    let mut processors: Vec<Box<dyn WindowFunctionProcessor>> = context
-        .window_exprs
-        .iter()
-        .map(|expr: &WindowExpr| expr.create_processor(&context))
-        .collect::<Result<_>>()?;
+    if parallelism > 1 && context.has_partition() {
+        return execute_window_parallel(
+            input,
+            task_context,
+            context,
+            metrics,
+            parallelism,
+            memory_limit,
+            partition,
+        );
+    }
+    let mut worker = WindowWorker::try_new(context.clone())?;
 
     // start processing input batches
     output_with_sender(
@@ -162,45 +194,282 @@ async fn execute_window(
                 let elapsed_time = metrics.elapsed_compute().clone();
                 let mut timer = elapsed_time.timer();
 
-                let window_cols: Vec<ArrayRef> = processors
-                    .iter_mut()
-                    .map(|processor| {
-                        if context.partition_spec.is_empty() {
-                            processor.process_batch_without_partitions(&context, &batch)
-                        } else {
-                            processor.process_batch(&context, &batch)
-                        }
-                    })
-                    .collect::<Result<_>>()?;
+                let output_batch = worker.process_batch(&batch)?;
+                metrics.record_output(output_batch.num_rows());
+                sender.send(Ok(output_batch), Some(&mut timer)).await;
+            }
+            Ok(())
+        },
+    )
+}
+
+/// input batches buffered for parallel window evaluation. the buffer is
+/// accounted to the mem manager, spilling is done by evaluating the buffered
+/// batches earlier (with less parallelism), or the task fails with an oom
+/// error if memory cannot be granted.
+struct WindowBuffered {
+    name: String,
+    mem_consumer_info: Option<Weak<MemConsumerInfo>>,
+    flush_requested: AtomicBool,
+}
+
+#[async_trait]
+impl MemConsumer for WindowBuffered {
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-                let output_cols = [batch.columns().to_vec(), window_cols].concat();
-                let output_batch = RecordBatch::try_new_with_options(
-                    context.output_schema.clone(),
-                    output_cols,
-                    &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+    fn set_consumer_info(&mut self, consumer_info: Weak<MemConsumerInfo>) {
+        self.mem_consumer_info = Some(consumer_info);
+    }
+
+    fn get_consumer_info(&self) -> &Weak<MemConsumerInfo> {
+        self.mem_consumer_info
+            .as_ref()
+            .expect("consumer info not set")
+    }
+
+    async fn spill(&self) -> Result<()> {
+        // buffered batches are evaluated right after this insertion
+        self.flush_requested.store(true, SeqCst);
+        Ok(())
+    }
+}
+
+impl Drop for WindowBuffered {
+    fn drop(&mut self) {
+        MemManager::deregister_consumer(self);
+    }
+}
+
+/// evaluates independent window partitions concurrently. input batches are
+/// buffered until exceeding the memory limit (or the mem manager requests to
+/// spill), then split into groups at partition boundaries and each group is
+/// evaluated in a blocking task.
+/// the first group may continue the last partition of the previous round, so
+/// it is evaluated by the worker of the previous round's last group.
+fn execute_window_parallel(
+    mut input: SendableRecordBatchStream,
+    task_context: Arc<TaskContext>,
+    context: Arc<WindowContext>,
+    metrics: BaselineMetrics,
+    parallelism: usize,
+    memory_limit: usize,
+    partition: usize,
+) -> Result<SendableRecordBatchStream> {
+    let buffered = Arc::new(WindowBuffered {
+        name: format!("WindowParallel[partition={}]", partition),
+        mem_consumer_info: None,
+        flush_requested: AtomicBool::new(false),
+    });
+    MemManager::register_consumer(buffered.clone(), true);
+
+    output_with_sender(
+        "Window",
+        task_context,
+        context.output_schema.clone(),
+        move |sender| async move {
+            let mut last_worker = WindowWorker::try_new(context.clone_with_new_converters()?)?;
+            let mut idle_workers = vec![];
+            let mut staging_batches = vec![];
+            let mut staging_mem_size = 0;
+
+            loop {
+                let batch = input.next().await.transpose()?;
+                let finished = batch.is_none();
+                if let Some(batch) = batch {
+                    staging_mem_size += batch.get_array_memory_size();
+                    staging_batches.push(batch);
+                    buffered.update_mem_used(staging_mem_size).await?;
+                    if staging_mem_size < memory_limit && !buffered.flush_requested.load(SeqCst) {
+                        continue;
+                    }
+                }
+                if staging_batches.is_empty() {
+                    break;
+                }
+
+                let elapsed_time = metrics.elapsed_compute().clone();
+                let mut timer = elapsed_time.timer();
+                let groups = split_partition_groups(
+                    &context,
+                    std::mem::take(&mut staging_batches),
+                    parallelism,
                 )?;
+                staging_mem_size = 0;
+                buffered.flush_requested.store(false, SeqCst);
 
-                metrics.record_output(output_batch.num_rows());
-                sender.send(Ok(output_batch), Some(&mut timer)).await;
+                // groups other than the first one start with new partitions,
+                // so they are evaluated with reset workers
+                let mut workers = vec![last_worker];
+                for _ in 1..groups.len() {
+                    let mut worker = match idle_workers.pop() {
+                        Some(worker) => worker,
+                        None => WindowWorker::try_new(context.clone_with_new_converters()?)?,
+                    };
+                    worker.reset()?;
+                    workers.push(worker);
+                }
+                let handles = groups
+                    .into_iter()
+                    .zip(workers)
+                    .map(|(group, mut worker)| {
+                        tokio::task::spawn_blocking(move || {
+                            let output_batches = group
+                                .iter()
+                                .map(|batch| worker.process_batch(batch))
+                                .collect::<Result<Vec<_>>>();
+                            (worker, output_batches)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut workers = vec![];
+                for handle in handles {
+                    let (worker, output_batches) = handle
+                        .await
+                        .map_err(|err| DataFusionError::Execution(format!("{err}")))?;
+                    for output_batch in output_batches? {
+                        metrics.record_output(output_batch.num_rows());
+                        sender.send(Ok(output_batch), Some(&mut timer)).await;
+                    }
+                    workers.push(worker);
+                }
+                last_worker = workers.pop().expect("at least one worker");
+                idle_workers.extend(workers);
+                buffered.update_mem_used(0).await?;
+
+                if finished {
+                    break;
+                }
             }
             Ok(())
         },
     )
 }
 
+/// splits batches into at most `num_groups` groups with similar number of
+/// rows. all groups except the first one start with a new window partition.
+fn split_partition_groups(
+    context: &WindowContext,
+    batches: Vec<RecordBatch>,
+    num_groups: usize,
+) -> Result<Vec<Vec<RecordBatch>>> {
+    let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+    let target_group_rows = ((num_rows + num_groups - 1) / num_groups).max(1);
+    let mut groups = vec![vec![]];
+    let mut group_rows = 0;
+    let mut last_partition_row: Option<OwnedRow> = None;
+
+    for batch in batches {
+        let partition_rows = context.get_partition_rows(&batch)?;
+        let mut start = 0;
+        for row_idx in 0..batch.num_rows() {
+            let partition_row = partition_rows.row(row_idx);
+            let is_new_partition = if row_idx == 0 {
+                last_partition_row
+                    .as_ref()
+                    .map(|last| last.row() != partition_row)
+                    .unwrap_or(true)
+            } else {
+                partition_rows.row(row_idx - 1) != partition_row
+            };
+            if is_new_partition && group_rows >= target_group_rows {
+                if row_idx > start {
+                    groups
+                        .last_mut()
+                        .unwrap()
+                        .push(batch.slice(start, row_idx - start));
+                }
+                groups.push(vec![]);
+                group_rows = 0;
+                start = row_idx;
+            }
+            group_rows += 1;
+        }
+        if batch.num_rows() > start {
+            groups
+                .last_mut()
+                .unwrap()
+                .push(batch.slice(start, batch.num_rows() - start));
+        }
+        if batch.num_rows() > 0 {
+            last_partition_row = Some(partition_rows.row(batch.num_rows() - 1).owned());
+        }
+    }
+    Ok(groups)
+}
+
+/// evaluates window functions of batches in order, keeping states of the
+/// current partition between batches.
+struct WindowWorker {
+    context: Arc<WindowContext>,
+    processors: Vec<Box<dyn WindowFunctionProcessor>>,
+}
+
+impl WindowWorker {
+    fn try_new(context: Arc<WindowContext>) -> Result<Self> {
+        let processors = create_processors(&context)?;
+        Ok(Self {
+            context,
+            processors,
+        })
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        self.processors = create_processors(&self.context)?;
+        Ok(())
+    }
+
+    fn process_batch(&mut self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let context = &self.context;
+        let window_cols: Vec<ArrayRef> = self
+            .processors
+            .iter_mut()
+            .map(|processor| {
+                if context.partition_spec.is_empty() {
+                    processor.process_batch_without_partitions(context, batch)
+                } else {
+                    processor.process_batch(context, batch)
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        let output_cols = [batch.columns().to_vec(), window_cols].concat();
+        Ok(RecordBatch::try_new_with_options(
+            context.output_schema.clone(),
+            output_cols,
+            &RecordBatchOptions::new().with_row_count(Some(batch.num_rows())),
+        )?)
+    }
+}
+
+fn create_processors(
+    context: &Arc<WindowContext>,
+) -> Result<Vec<Box<dyn WindowFunctionProcessor>>> {
+    context
+        .window_exprs
+        .iter()
+        .map(|expr: &WindowExpr| expr.create_processor(context))
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use crate::agg::AggFunction;
+    use crate::common::memory_manager::MemManager;
     use crate::window::{WindowExpr, WindowFunction, WindowRankType};
-    use crate::window_exec::WindowExec;
+    use crate::window_exec::{execute_window, WindowExec};
     use arrow::array::*;
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
+    use arrow::util::pretty::pretty_format_batches;
     use datafusion::assert_batches_eq;
     use datafusion::physical_expr::expressions::Column;
     use datafusion::physical_expr::PhysicalSortExpr;
     use datafusion::physical_plan::memory::MemoryExec;
-    use datafusion::physical_plan::ExecutionPlan;
+    use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet};
+    use datafusion::physical_plan::{common, ExecutionPlan};
     use datafusion::prelude::SessionContext;
     use std::sync::Arc;
 
@@ -348,4 +617,77 @@ mod test {
         assert_batches_eq!(expected, &batches);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_window_parallel() -> Result<(), Box<dyn std::error::Error>> {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+
+        // partitions spanning multiple batches
+        let batches = vec![
+            build_table_i32(
+                ("a1", &vec![1, 1, 2, 2, 2]),
+                ("b1", &vec![1, 2, 1, 1, 2]),
+                ("c1", &vec![0, 0, 0, 0, 0]),
+            ),
+            build_table_i32(
+                ("a1", &vec![2, 3, 4, 4, 5]),
+                ("b1", &vec![3, 1, 1, 2, 1]),
+                ("c1", &vec![0, 0, 0, 0, 0]),
+            ),
+            build_table_i32(
+                ("a1", &vec![5, 5, 6, 7, 7]),
+                ("b1", &vec![1, 2, 1, 1, 1]),
+                ("c1", &vec![0, 0, 0, 0, 0]),
+            ),
+        ];
+        let schema = batches[0].schema();
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(MemoryExec::try_new(&[batches], schema, None)?);
+        let window = WindowExec::try_new(
+            input.clone(),
+            vec![
+                WindowExpr::new(
+                    WindowFunction::RankLike(WindowRankType::RowNumber),
+                    vec![],
+                    Arc::new(Field::new("b1_row_number", DataType::Int32, false)),
+                ),
+                WindowExpr::new(
+                    WindowFunction::RankLike(WindowRankType::Rank),
+                    vec![],
+                    Arc::new(Field::new("b1_rank", DataType::Int32, false)),
+                ),
+                WindowExpr::new(
+                    WindowFunction::Agg(AggFunction::Sum),
+                    vec![Arc::new(Column::new("b1", 1))],
+                    Arc::new(Field::new("b1_sum", DataType::Int64, false)),
+                ),
+            ],
+            vec![Arc::new(Column::new("a1", 0))],
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("b1", 1)),
+                options: Default::default(),
+            }],
+        )?;
+        let expected = common::collect(window.execute(0, task_ctx.clone())?).await?;
+        let expected = pretty_format_batches(&expected)?.to_string();
+
+        // (parallelism, memory_limit)
+        MemManager::init(10000);
+        for (parallelism, memory_limit) in [(2, 0), (3, usize::MAX), (16, 1)] {
+            let stream = execute_window(
+                input.execute(0, task_ctx.clone())?,
+                task_ctx.clone(),
+                window.context.clone(),
+                BaselineMetrics::new(&ExecutionPlanMetricsSet::new(), 0),
+                parallelism,
+                memory_limit,
+                0,
+            )
+            .await?;
+            let batches = common::collect(stream).await?;
+            assert_eq!(pretty_format_batches(&batches)?.to_string(), expected);
+        }
+        Ok(())
+    }
 }
@@ -31,10 +31,14 @@ import org.apache.spark.shuffle.IndexShuffleBlockResolver
 import org.apache.spark.shuffle.ShuffleHandle
 import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
+import org.apache.spark.sql.catalyst.expressions.AssertTrue
+import org.apache.spark.sql.catalyst.expressions.ElementAt
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.GetArrayItem
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
 import org.apache.spark.sql.catalyst.expressions.Like
 import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.catalyst.expressions.RegExpReplace
 import org.apache.spark.sql.catalyst.expressions.StringSplit
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
@@ -61,7 +65,9 @@ import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeLike
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.adaptive.BroadcastQueryStageExec
+import org.apache.spark.sql.types.ArrayType
 import org.apache.spark.sql.types.IntegerType
+import org.apache.spark.sql.internal.SQLConf
 import org.apache.spark.sql.types.StringType
 import org.apache.spark.storage.BlockManagerId
 import org.apache.spark.storage.FileSegment
@@ -76,6 +82,9 @@ import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.JoinType
 import org.apache.spark.sql.execution.blaze.plan.ConvertToNativeBase
 import org.apache.spark.sql.execution.blaze.plan.ConvertToNativeExec
+import org.apache.spark.sql.execution.blaze.plan.Helper
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkFileStats
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkWriteTaskStats
 import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase
 import org.apache.spark.sql.execution.blaze.plan.NativeAggBase.AggExecMode
@@ -108,11 +117,15 @@ import org.apache.spark.sql.execution.blaze.plan.NativeSortMergeJoinBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortMergeJoinExec
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedExec
+import org.apache.spark.sql.execution.blaze.plan.NativeTextInsertIntoHadoopFsRelationBase
+import org.apache.spark.sql.execution.blaze.plan.NativeTextInsertIntoHadoopFsRelationExec
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionExec
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowBase
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowExec
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.PartitionedFile
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
 import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
@@ -250,6 +263,11 @@ class ShimsImpl extends Shims with Logging {
       metrics: Map[String, SQLMetric]): NativePartialTakeOrderedBase =
     NativePartialTakeOrderedExec(limit, sortOrder, child, metrics)
 
+  override def createNativeTextInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeTextInsertIntoHadoopFsRelationBase =
+    NativeTextInsertIntoHadoopFsRelationExec(cmd, child)
+
   override def createNativeUnionExec(children: Seq[SparkPlan]): NativeUnionBase =
     NativeUnionExec(children)
 
@@ -317,11 +335,12 @@ class ShimsImpl extends Shims with Logging {
   }
 
   override def createBasicWriteTaskStats(params: Map[String, Any]): BasicWriteTaskStats = {
-    BasicWriteTaskStats(
+    new NativeParquetSinkWriteTaskStats(
       params.get("numPartitions").map(_.asInstanceOf[Int]).getOrElse(0),
       params.get("numFiles").map(_.asInstanceOf[Int]).getOrElse(0),
       params.get("numBytes").map(_.asInstanceOf[Long]).getOrElse(0),
-      params.get("numRows").map(_.asInstanceOf[Long]).getOrElse(0))
+      params.get("numRows").map(_.asInstanceOf[Long]).getOrElse(0),
+      params.get("files").map(_.asInstanceOf[Seq[ParquetSinkFileStats]]).getOrElse(Nil))
   }
 
   override def getRDDShuffleReadFull(rdd: RDD[_]): Boolean = true
@@ -380,26 +399,32 @@ class ShimsImpl extends Shims with Logging {
 
   override def convertExpr(e: Expression): Option[pb.PhysicalExprNode] = {
     e match {
-      case StringSplit(str, pat @ Literal(_, StringType), Literal(-1, IntegerType))
-          // native StringSplit implementation does not support regex, so only most frequently
-          // used cases without regex are supported
-          if Seq(",", ", ", ":", ";", "#", "@", "_", "-", "\\|", "\\.").contains(pat.value) =>
-        val nativePat = pat.value match {
-          case "\\|" => "|"
-          case "\\." => "."
-          case other => other
-        }
+      // element_at(array, index), out-of-bounds indices are not checked in spark 3.0
+      case ElementAt(array, Literal(index: Number, _))
+          if array.dataType.isInstanceOf[ArrayType] =>
+        Some(
+          NativeConverters
+            .buildGetArrayElementExpr(array, index.longValue(), failOnError = false))
+
+      case StringSplit(str, Literal(regex, StringType), Literal(limit: Int, IntegerType))
+          if regex != null && NativeConverters.isNativeRegexSupported(regex.toString) =>
+        Some(NativeConverters.buildStringSplitExpr(str, regex.toString, limit))
+
+      case RegExpReplace(str, Literal(regex, StringType), Literal(rep, StringType))
+          if regex != null && rep != null &&
+            NativeConverters.isNativeRegexSupported(regex.toString) =>
+        Some(NativeConverters.buildRegexpReplaceExpr(str, regex.toString, rep.toString, 1))
+
+      case AssertTrue(child) =>
+        val message = s"'${child.simpleString(SQLConf.get.maxToStringFields)}' is not true!"
         Some(
           pb.PhysicalExprNode
             .newBuilder()
-            .setScalarFunction(
-              pb.PhysicalScalarFunctionNode
+            .setAssertTrueExpr(
+              pb.PhysicalAssertTrueExprNode
                 .newBuilder()
-                .setFun(pb.ScalarFunction.SparkExtFunctions)
-                .setName("StringSplit")
-                .addArgs(NativeConverters.convertExpr(str))
-                .addArgs(NativeConverters.convertExpr(Literal(nativePat)))
-                .setReturnType(NativeConverters.convertDataType(StringType)))
+                .setCondition(NativeConverters.convertExpr(child))
+                .setMessage(NativeConverters.convertExpr(Literal(message))))
             .build())
 
       case _ => None
@@ -410,6 +435,8 @@ class ShimsImpl extends Shims with Logging {
     expr.asInstanceOf[Like].escapeChar
   }
 
+  override def getArrayItemFailOnError(expr: GetArrayItem): Boolean = false
+
   override def convertAggregateExpr(e: AggregateExpression): Option[pb.PhysicalExprNode] = {
     assert(getAggregateExpressionFilter(e).isEmpty)
     val aggBuilder = pb.PhysicalAggExprNode.newBuilder()
@@ -520,6 +547,9 @@ class ShimsImpl extends Shims with Logging {
 
   override def getSqlContext(sparkPlan: SparkPlan): SQLContext = sparkPlan.sqlContext
 
+  // modification times are not available in partitioned files of spark 3.0
+  override def getPartitionedFileModificationTime(file: PartitionedFile): Long = 0L
+
   override def createBasicWriteJobStatsTrackerForNativeParquetSink(
       serializableHadoopConf: SerializableConfiguration,
       metrics: Map[String, SQLMetric]): BasicWriteJobStatsTracker = {
@@ -535,6 +565,11 @@ class ShimsImpl extends Shims with Logging {
         }
         new StatsTracker(serializableHadoopConf.value)
       }
+
+      override def processStats(stats: Seq[WriteTaskStats]): Unit = {
+        super.processStats(stats)
+        Helper.processFileStats(stats)
+      }
     }
   }
 
@@ -546,6 +581,15 @@ class ShimsImpl extends Shims with Logging {
   }
 }
 
+class NativeParquetSinkWriteTaskStats(
+    numPartitions: Int,
+    numFiles: Int,
+    numBytes: Long,
+    numRows: Long,
+    override val files: Seq[ParquetSinkFileStats])
+    extends BasicWriteTaskStats(numPartitions, numFiles, numBytes, numRows)
+    with ParquetSinkWriteTaskStats
+
 case class ForceNativeExecutionWrapper(override val child: SparkPlan)
     extends ForceNativeExecutionWrapperBase(child) {
   override protected def doCanonicalize(): SparkPlan = child.canonicalized
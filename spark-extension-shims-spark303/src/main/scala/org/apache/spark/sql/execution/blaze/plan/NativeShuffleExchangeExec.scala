@@ -49,7 +49,7 @@ case class NativeShuffleExchangeExec(
     mutable.LinkedHashMap(
       NativeHelper
         .getDefaultNativeMetrics(sparkContext)
-        .filterKeys(Set("spilled_bytes"))
+        .filterKeys(Set("spilled_bytes", "spill_count", "mem_peak_used"))
         .toSeq: _*)).toMap
 
   lazy val readMetrics: Map[String, SQLMetric] =
@@ -0,0 +1,73 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.spark.SparkConf
+import org.apache.spark.serializer.JavaSerializer
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.execution.blaze.plan.Helper
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkFileStats
+import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.WriteTaskStats
+import org.apache.spark.util.SerializableConfiguration
+import org.scalatest.funsuite.AnyFunSuite
+
+class NativeParquetSinkWriteTaskStatsSuite extends AnyFunSuite {
+
+  test("file stats of native parquet sink are received by the driver") {
+    val spark = SparkSession.builder().master("local[1]").getOrCreate()
+    try {
+      val shims = new ShimsImpl
+      val files = Seq(
+        ParquetSinkFileStats(
+          "file:/tmp/t/p=1/part-00000.parquet",
+          "p=1",
+          10,
+          1024,
+          Seq(Some("1"), None),
+          Seq(Some("9"), None),
+          Seq(0, 10),
+          Seq(10, 0)))
+      val taskStats = shims.createBasicWriteTaskStats(
+        Map(
+          "numPartitions" -> 1,
+          "numFiles" -> 1,
+          "numBytes" -> 1024L,
+          "numRows" -> 10L,
+          "files" -> files))
+
+      // task stats are sent to the driver within the serialized task commit message
+      val serializer = new JavaSerializer(new SparkConf()).newInstance()
+      val received = serializer.deserialize[WriteTaskStats](serializer.serialize(taskStats))
+
+      var receivedFiles: Seq[ParquetSinkFileStats] = Nil
+      val listener = (files: Seq[ParquetSinkFileStats]) => receivedFiles = files
+      Helper.addFileStatsListener(listener)
+      try {
+        val tracker = shims.createBasicWriteJobStatsTrackerForNativeParquetSink(
+          new SerializableConfiguration(new Configuration()),
+          BasicWriteJobStatsTracker.metrics)
+        tracker.processStats(Seq(received))
+      } finally {
+        Helper.removeFileStatsListener(listener)
+      }
+      assert(receivedFiles == files)
+    } finally {
+      spark.stop()
+    }
+  }
+}
@@ -18,6 +18,8 @@ package org.apache.spark.sql.blaze
 import java.io.File
 import java.util.UUID
 
+import scala.collection.JavaConverters._
+
 import org.apache.commons.lang3.reflect.FieldUtils
 import org.apache.hadoop.conf.Configuration
 import org.apache.spark.ShuffleDependency
@@ -30,12 +32,25 @@ import org.apache.spark.scheduler.MapStatus
 import org.apache.spark.shuffle.IndexShuffleBlockResolver
 import org.apache.spark.shuffle.ShuffleHandle
 import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
+import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
+import org.apache.spark.sql.catalyst.expressions.ElementAt
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.GetArrayItem
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
+import org.apache.spark.sql.catalyst.expressions.aggregate.HistogramNumeric
 import org.apache.spark.sql.catalyst.expressions.Like
+import org.apache.spark.sql.catalyst.expressions.LikeAll
+import org.apache.spark.sql.catalyst.expressions.LikeAny
 import org.apache.spark.sql.catalyst.expressions.Literal
+import org.apache.spark.sql.catalyst.expressions.NotLikeAll
+import org.apache.spark.sql.catalyst.expressions.NotLikeAny
+import org.apache.spark.sql.catalyst.expressions.RaiseError
+import org.apache.spark.sql.catalyst.expressions.RegExpReplace
 import org.apache.spark.sql.catalyst.expressions.StringSplit
+import org.apache.spark.sql.catalyst.expressions.WidthBucket
+import org.apache.spark.sql.catalyst.expressions.objects.StaticInvoke
+import org.apache.spark.sql.catalyst.util.CharVarcharCodegenUtils
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStats
@@ -60,6 +75,7 @@ import org.apache.spark.sql.execution.blaze.plan.NativeBroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeLike
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.adaptive.BroadcastQueryStageExec
+import org.apache.spark.sql.types.ArrayType
 import org.apache.spark.sql.types.IntegerType
 import org.apache.spark.sql.types.StringType
 import org.apache.spark.storage.BlockManagerId
@@ -99,14 +115,21 @@ import org.apache.spark.sql.execution.blaze.plan.NativeSortBase
 import org.apache.spark.sql.execution.blaze.plan.NativeSortExec
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedBase
 import org.apache.spark.sql.execution.blaze.plan.NativeTakeOrderedExec
+import org.apache.spark.sql.execution.blaze.plan.NativeTextInsertIntoHadoopFsRelationBase
+import org.apache.spark.sql.execution.blaze.plan.NativeTextInsertIntoHadoopFsRelationExec
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionExec
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowBase
 import org.apache.spark.sql.execution.blaze.plan.NativeWindowExec
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.CoalescedMapperPartitionSpec
+import org.apache.spark.sql.execution.blaze.plan.Helper
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkFileStats
+import org.apache.spark.sql.execution.blaze.plan.Helper.ParquetSinkWriteTaskStats
 import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
 import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.PartitionedFile
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStatsTracker
 import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.datasources.WriteTaskStatsTracker
@@ -115,7 +138,11 @@ import org.apache.spark.sql.execution.joins.blaze.plan.NativeBroadcastNestedLoop
 import org.apache.spark.sql.execution.joins.blaze.plan.NativeSortMergeJoinExec
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 import org.apache.spark.sql.types.DataType
+import org.apache.spark.sql.types.DoubleType
+import org.apache.spark.sql.types.LongType
+import org.apache.spark.sql.types.NumericType
 import org.apache.spark.util.SerializableConfiguration
+import org.apache.spark.unsafe.types.UTF8String
 import org.blaze.{protobuf => pb}
 
 class ShimsImpl extends Shims with Logging {
@@ -249,6 +276,11 @@ class ShimsImpl extends Shims with Logging {
       metrics: Map[String, SQLMetric]): NativePartialTakeOrderedBase =
     NativePartialTakeOrderedExec(limit, sortOrder, child, metrics)
 
+  override def createNativeTextInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeTextInsertIntoHadoopFsRelationBase =
+    NativeTextInsertIntoHadoopFsRelationExec(cmd, child)
+
   override def createNativeUnionExec(children: Seq[SparkPlan]): NativeUnionBase =
     NativeUnionExec(children)
 
@@ -317,11 +349,15 @@ class ShimsImpl extends Shims with Logging {
   }
 
   override def createBasicWriteTaskStats(params: Map[String, Any]): BasicWriteTaskStats = {
-    BasicWriteTaskStats(
-      Nil, // FIXME: use real partition values
+    new NativeParquetSinkWriteTaskStats(
+      params
+        .get("partitions")
+        .map(_.asInstanceOf[Seq[String]].map(p => InternalRow(UTF8String.fromString(p))))
+        .getOrElse(Nil),
       params.get("numFiles").map(_.asInstanceOf[Int]).getOrElse(0),
       params.get("numBytes").map(_.asInstanceOf[Long]).getOrElse(0),
-      params.get("numRows").map(_.asInstanceOf[Long]).getOrElse(0))
+      params.get("numRows").map(_.asInstanceOf[Long]).getOrElse(0),
+      params.get("files").map(_.asInstanceOf[Seq[ParquetSinkFileStats]]).getOrElse(Nil))
   }
 
   override def getRDDShuffleReadFull(rdd: RDD[_]): Boolean = true
@@ -382,15 +418,53 @@ class ShimsImpl extends Shims with Logging {
 
   override def convertExpr(e: Expression): Option[pb.PhysicalExprNode] = {
     e match {
-      case StringSplit(str, pat @ Literal(_, StringType), Literal(-1, IntegerType))
-          // native StringSplit implementation does not support regex, so only most frequently
-          // used cases without regex are supported
-          if Seq(",", ", ", ":", ";", "#", "@", "_", "-", "\\|", "\\.").contains(pat.value) =>
-        val nativePat = pat.value match {
-          case "\\|" => "|"
-          case "\\." => "."
-          case other => other
-        }
+      // element_at(array, index)
+      case ElementAt(array, Literal(index: Number, _), None, failOnError)
+          if array.dataType.isInstanceOf[ArrayType] =>
+        Some(NativeConverters.buildGetArrayElementExpr(array, index.longValue(), failOnError))
+
+      case LikeAll(child, patterns) =>
+        Some(
+          NativeConverters
+            .buildStringLikeMultiExpr(child, patterns, isAll = true, negated = false))
+      case LikeAny(child, patterns) =>
+        Some(
+          NativeConverters
+            .buildStringLikeMultiExpr(child, patterns, isAll = false, negated = false))
+      case NotLikeAll(child, patterns) =>
+        Some(
+          NativeConverters
+            .buildStringLikeMultiExpr(child, patterns, isAll = true, negated = true))
+      case NotLikeAny(child, patterns) =>
+        Some(
+          NativeConverters
+            .buildStringLikeMultiExpr(child, patterns, isAll = false, negated = true))
+
+      case StringSplit(str, Literal(regex, StringType), Literal(limit: Int, IntegerType))
+          if regex != null && NativeConverters.isNativeRegexSupported(regex.toString) =>
+        Some(NativeConverters.buildStringSplitExpr(str, regex.toString, limit))
+
+      case RegExpReplace(
+            str,
+            Literal(regex, StringType),
+            Literal(rep, StringType),
+            Literal(pos: Int, IntegerType))
+          if regex != null && rep != null && pos > 0 &&
+            NativeConverters.isNativeRegexSupported(regex.toString) =>
+        Some(NativeConverters.buildRegexpReplaceExpr(str, regex.toString, rep.toString, pos))
+
+      // AssertTrue is replaced with If(cond, null, RaiseError(msg)) by optimizer
+      case e: RaiseError =>
+        Some(
+          pb.PhysicalExprNode
+            .newBuilder()
+            .setRaiseErrorExpr(
+              pb.PhysicalRaiseErrorExprNode
+                .newBuilder()
+                .setMessage(NativeConverters.convertExpr(e.child)))
+            .build())
+
+      case e: WidthBucket if e.value.dataType == DoubleType && e.numBucket.dataType == LongType =>
         Some(
           pb.PhysicalExprNode
             .newBuilder()
@@ -398,9 +472,25 @@ class ShimsImpl extends Shims with Logging {
               pb.PhysicalScalarFunctionNode
                 .newBuilder()
                 .setFun(pb.ScalarFunction.SparkExtFunctions)
-                .setName("StringSplit")
-                .addArgs(NativeConverters.convertExpr(str))
-                .addArgs(NativeConverters.convertExpr(Literal(nativePat)))
+                .setName("WidthBucket")
+                .addAllArgs(e.children.map(NativeConverters.convertExpr).asJava)
+                .setReturnType(NativeConverters.convertDataType(e.dataType)))
+            .build())
+
+      // char/varchar padding and length checks
+      case e: StaticInvoke
+          if e.staticObject == classOf[CharVarcharCodegenUtils]
+            && charVarcharFunctions.contains(e.functionName)
+            && e.arguments(1).isInstanceOf[Literal] =>
+        Some(
+          pb.PhysicalExprNode
+            .newBuilder()
+            .setScalarFunction(
+              pb.PhysicalScalarFunctionNode
+                .newBuilder()
+                .setFun(pb.ScalarFunction.SparkExtFunctions)
+                .setName(charVarcharFunctions(e.functionName))
+                .addAllArgs(e.arguments.map(NativeConverters.convertExpr).asJava)
                 .setReturnType(NativeConverters.convertDataType(StringType)))
             .build())
 
@@ -408,10 +498,17 @@ class ShimsImpl extends Shims with Logging {
     }
   }
 
+  private val charVarcharFunctions = Map(
+    "charTypeWriteSideCheck" -> "CharTypeWriteSideCheck",
+    "varcharTypeWriteSideCheck" -> "VarcharTypeWriteSideCheck",
+    "readSidePadding" -> "ReadSidePadding")
+
   override def getLikeEscapeChar(expr: Expression): Char = {
     expr.asInstanceOf[Like].escapeChar
   }
 
+  override def getArrayItemFailOnError(expr: GetArrayItem): Boolean = expr.failOnError
+
   override def convertAggregateExpr(e: AggregateExpression): Option[pb.PhysicalExprNode] = {
     assert(getAggregateExpressionFilter(e).isEmpty)
     val aggBuilder = pb.PhysicalAggExprNode.newBuilder()
@@ -426,6 +523,13 @@ class ShimsImpl extends Shims with Logging {
         aggBuilder.addChildren(NativeConverters.convertExpr(child))
         Some(pb.PhysicalExprNode.newBuilder().setAggExpr(aggBuilder).build())
 
+      case e: HistogramNumeric
+          if e.child.dataType.isInstanceOf[NumericType] && e.nBins.foldable =>
+        aggBuilder.setAggFunction(pb.AggFunction.HISTOGRAM_NUMERIC)
+        aggBuilder.addChildren(NativeConverters.convertExpr(e.child))
+        aggBuilder.addChildren(NativeConverters.convertExpr(Literal(e.nBins.eval(), IntegerType)))
+        Some(pb.PhysicalExprNode.newBuilder().setAggExpr(aggBuilder).build())
+
       case _ => None
     }
   }
@@ -533,6 +637,9 @@ class ShimsImpl extends Shims with Logging {
   override def getSqlContext(sparkPlan: SparkPlan): SQLContext =
     sparkPlan.session.sqlContext
 
+  override def getPartitionedFileModificationTime(file: PartitionedFile): Long =
+    file.modificationTime
+
   override def createBasicWriteJobStatsTrackerForNativeParquetSink(
       serializableHadoopConf: SerializableConfiguration,
       metrics: Map[String, SQLMetric]): BasicWriteJobStatsTracker = {
@@ -549,6 +656,11 @@ class ShimsImpl extends Shims with Logging {
         }
         new StatsTracker(serializableHadoopConf.value)
       }
+
+      override def processStats(stats: Seq[WriteTaskStats], jobCommitTime: Long): Unit = {
+        super.processStats(stats, jobCommitTime)
+        Helper.processFileStats(stats)
+      }
     }
   }
 
@@ -560,6 +672,15 @@ class ShimsImpl extends Shims with Logging {
   }
 }
 
+class NativeParquetSinkWriteTaskStats(
+    partitions: Seq[InternalRow],
+    numFiles: Int,
+    numBytes: Long,
+    numRows: Long,
+    override val files: Seq[ParquetSinkFileStats])
+    extends BasicWriteTaskStats(partitions, numFiles, numBytes, numRows)
+    with ParquetSinkWriteTaskStats
+
 case class ForceNativeExecutionWrapper(override val child: SparkPlan)
     extends ForceNativeExecutionWrapperBase(child) {
   override def withNewChildInternal(newChild: SparkPlan): SparkPlan =
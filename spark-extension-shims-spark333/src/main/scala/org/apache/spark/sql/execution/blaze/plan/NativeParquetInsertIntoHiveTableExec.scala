@@ -15,6 +15,7 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import org.apache.spark.sql.catalyst.catalog.BucketSpec
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
 
@@ -22,6 +23,10 @@ case class NativeParquetInsertIntoHiveTableExec(
     cmd: InsertIntoHiveTable,
     override val child: SparkPlan)
     extends NativeParquetInsertIntoHiveTableBase(cmd, child) {
+
+  // spark 3.3 writes hive-compatible bucketed files for parquet tables
+  override protected def hiveBucketSpec: Option[BucketSpec] = cmd.table.bucketSpec
+
   override protected def withNewChildInternal(newChild: SparkPlan): SparkPlan =
     copy(child = newChild)
 }
@@ -15,6 +15,8 @@
  */
 package org.apache.spark.sql.blaze;
 
+import java.util.Locale;
+
 import org.apache.spark.SparkConf;
 import org.apache.spark.SparkEnv$;
 
@@ -30,6 +32,17 @@ public class BlazeConf {
         return doubleConf("spark.blaze.memoryFraction", 0.6);
     }
 
+    /// acquires native memory from spark's task memory manager, so that native and jvm operators
+    /// share the execution memory and spill each other.
+    public static boolean sparkManagedMemory() {
+        return booleanConf("spark.blaze.memory.sparkManaged", false);
+    }
+
+    /// max fraction of native memory a single operator can use before being forced to spill.
+    public static double operatorMemoryFraction() {
+        return doubleConf("spark.blaze.memory.operatorFraction", 1.0);
+    }
+
     /// translates inequality smj to native. improves performance in most cases, however some
     /// issues are found in special cases, like tpcds q72.
     public static boolean enableSmjInequalityJoin() {
@@ -71,10 +84,258 @@ public class BlazeConf {
         return booleanConf("spark.files.ignoreCorruptFiles", false);
     }
 
+    /// max number of parquet writers kept open when writing dynamic partitions. the least recently
+    /// used writer is closed when exceeded, and remaining data is sorted by partition values if too
+    /// many writers are closed.
+    public static int parquetSinkMaxOpenWriters() {
+        return intConf("spark.blaze.parquetSink.maxOpenWriters", 20);
+    }
+
+    /// collects null counts and approximate ndvs of columns for each file written by native
+    /// parquet sink, which are reported with file stats as a file-level data-skipping index.
+    public static boolean parquetSinkFileIndexEnabled() {
+        return booleanConf("spark.blaze.parquetSink.fileIndex.enabled", false);
+    }
+
+    /// enable page-level pruning with parquet page index (column index and offset index) in native
+    /// parquet scan, pages not matching the pushed-down filters are skipped.
+    public static boolean parquetEnablePageFiltering() {
+        return booleanConf("spark.blaze.parquet.enable.pageFiltering", false);
+    }
+
+    /// enable row-level filter pushdown (late materialization) in native parquet scan, filter
+    /// columns are decoded and evaluated first, other columns are decoded only for matched rows.
+    public static boolean parquetEnableFilterPushdown() {
+        return booleanConf("spark.blaze.parquet.enable.filterPushdown", false);
+    }
+
+    /// enable row group pruning with parquet bloom filters in native parquet scan, used for equality
+    /// and in-list filters.
+    public static boolean parquetEnableBloomFilter() {
+        return booleanConf("spark.blaze.parquet.enable.bloomFilter", true);
+    }
+
+    /// number of concurrent row group readers of each native parquet scan partition, large files are
+    /// split into ranges read in parallel. 1 to disable parallel reading.
+    public static int parquetScanParallelism() {
+        return intConf("spark.blaze.parquet.scan.parallelism", 1);
+    }
+
+    /// whether to keep the original order of output batches when parquet scan is read in parallel.
+    public static boolean parquetScanParallelOrdered() {
+        return booleanConf("spark.blaze.parquet.scan.parallel.ordered", true);
+    }
+
+    /// max number of parquet footers cached in each executor, footers are reused in repeated scans
+    /// of the same files. 0 to disable caching.
+    public static int parquetMetadataCacheSize() {
+        return intConf("spark.blaze.parquet.metadataCache.size", 1000);
+    }
+
+    /// bytes read ahead in background on sequential access of hadoop input streams, reducing round
+    /// trips of small reads. 0 to disable reading ahead.
+    public static int fsReadAheadSize() {
+        return intConf("spark.blaze.fs.readAhead.size", 4 * 1024 * 1024);
+    }
+
+    /// max gap in bytes between two column chunk/page ranges to be merged into one read in native
+    /// parquet scan.
+    public static int parquetReadRangesCoalesceGap() {
+        return intConf("spark.blaze.parquet.readRanges.coalesceGap", 1024 * 1024);
+    }
+
+    /// max attempts of native hadoop filesystem operations failed with transient errors, like
+    /// datanode failures. 1 to disable retrying.
+    public static int fsRetryMaxAttempts() {
+        return intConf("spark.blaze.fs.retry.maxAttempts", 3);
+    }
+
+    /// initial backoff in milliseconds before retrying a failed filesystem operation, doubled on
+    /// each retry.
+    public static int fsRetryInitialBackoffMs() {
+        return intConf("spark.blaze.fs.retry.initialBackoffMs", 100);
+    }
+
+    /// max backoff in milliseconds before retrying a failed filesystem operation.
+    public static int fsRetryMaxBackoffMs() {
+        return intConf("spark.blaze.fs.retry.maxBackoffMs", 5000);
+    }
+
+    /// number of worker threads of the tokio runtime driving native streams of each task.
+    /// 0 uses the number of cpu cores.
+    public static int tokioWorkerThreads() {
+        return intConf("spark.blaze.tokio.workerThreads", 0);
+    }
+
+    /// name prefix of native worker threads, suffixed with partition id and thread index.
+    public static String tokioThreadNamePrefix() {
+        return stringConf("spark.blaze.tokio.threadNamePrefix", "blaze-native");
+    }
+
+    /// pins native worker threads to cpu cores in round-robin order (linux only).
+    public static boolean tokioPinCores() {
+        return booleanConf("spark.blaze.tokio.pinCores", false);
+    }
+
+    /// wall-clock timeout of native execution of each task in milliseconds, exceeding tasks
+    /// are aborted with a timeout error. 0 means no timeout.
+    public static long taskTimeoutMs() {
+        return longConf("spark.blaze.task.timeoutMs", 0L);
+    }
+
+    /// wall-clock timeout of each native operator in milliseconds, from starting to finishing
+    /// producing its output. the task is aborted with a timeout error once any operator
+    /// exceeds it. 0 means no timeout.
+    /// only operators producing output in a spawned task (like sorts, aggregations, joins and
+    /// shuffle writes) have their own deadlines. streaming operators (like scans, projections
+    /// and filters) are driven by their consumers and only covered by the task deadline.
+    public static long operatorTimeoutMs() {
+        return longConf("spark.blaze.operator.timeoutMs", 0L);
+    }
+
+    /// validates every batch produced by native operators (offsets, null buffers, utf8 and
+    /// schema), failing with the producing operator's name. for debugging only, this is slow.
+    public static boolean enableBatchValidation() {
+        return booleanConf("spark.blaze.enableBatchValidation", false);
+    }
+
+    /// port of the arrow flight debug server in native side, which exposes output batches of
+    /// operators selected by spark.blaze.debug.flight.operator as flight streams. 0 uses a
+    /// random free port. requires native library built with the flight-debug feature.
+    public static int debugFlightPort() {
+        return intConf("spark.blaze.debug.flight.port", 0);
+    }
+
+    /// bind address of the arrow flight debug server in native side. captured batches are exposed
+    /// without authentication, so the server only listens on loopback by default.
+    public static String debugFlightHost() {
+        return stringConf("spark.blaze.debug.flight.host", "127.0.0.1");
+    }
+
+    /// name of native operators (like SortExec) whose output batches are captured and exposed
+    /// by the arrow flight debug server. empty disables the debug server. for debugging only.
+    public static String debugFlightOperator() {
+        return stringConf("spark.blaze.debug.flight.operator", "");
+    }
+
+    /// logs a per-operator metrics summary (like EXPLAIN ANALYZE) of native execution after
+    /// each task is finished.
+    public static boolean enableMetricsSummary() {
+        return booleanConf("spark.blaze.enableMetricsSummary", false);
+    }
+
+    /// hands rows converted to native as a single exported ArrowArrayStream (arrow c stream
+    /// interface) instead of exporting each batch through a jni call.
+    public static boolean enableArrowCStream() {
+        return booleanConf("spark.blaze.enableArrowCStream", true);
+    }
+
+    /// converts native output batches to UnsafeRows in native side, instead of converting
+    /// each row with UnsafeProjection in jvm side.
+    public static boolean enableNativeColumnarToRow() {
+        return booleanConf("spark.blaze.enableNativeColumnarToRow", true);
+    }
+
+    /// hands rows converted to native as UnsafeRow bytes, which are parsed into arrow
+    /// columns in native side. only takes effect with enableArrowCStream.
+    public static boolean enableNativeRowToColumnar() {
+        return booleanConf("spark.blaze.enableNativeRowToColumnar", true);
+    }
+
+    /// caches broadcasted data in native side of each executor, which is shared by all
+    /// broadcast join tasks instead of being deserialized in every task. cached data is
+    /// accounted to native memory and released once the broadcast is removed.
+    public static boolean enableBroadcastCache() {
+        return booleanConf("spark.blaze.broadcastCache.enable", false);
+    }
+
+    /// max memory of broadcasted data cached in native side of each executor.
+    public static long broadcastCacheMemSize() {
+        return longConf("spark.blaze.broadcastCacheMemSize", 256L << 20);
+    }
+
+    /// compression codec of broadcasted data (uncompressed/lz4/zstd). broadcasted data is
+    /// written once and read by many tasks, so a stronger codec than shuffle is used.
+    public static String broadcastCompressionCodec() {
+        return stringConf("spark.blaze.broadcast.compression.codec", "zstd");
+    }
+
+    /// compression level of broadcasted data, only used by zstd codec.
+    public static int broadcastCompressionLevel() {
+        return intConf("spark.blaze.broadcast.compression.level", 3);
+    }
+
+    /// target in-memory size of batches written into one ipc chunk by native ipc writers.
+    public static long ipcWriterTargetChunkSize() {
+        return longConf("spark.blaze.ipcWriter.targetChunkSize", 4L << 20);
+    }
+
+    /// max number of ipc chunks written by native ipc writers but not yet consumed by jvm.
+    public static int ipcWriterMaxInflightChunks() {
+        return intConf("spark.blaze.ipcWriter.maxInflightChunks", 2);
+    }
+
+    /// appends checksums to broadcasted ipc data and verifies them when reading, to detect
+    /// corruption when transferring between native and jvm.
+    public static boolean enableBroadcastChecksum() {
+        return booleanConf("spark.blaze.broadcast.checksum", false);
+    }
+
+    /// compression codec of native shuffle data (uncompressed/lz4/zstd), following
+    /// spark.shuffle.compress and spark.io.compression.codec. codecs not supported in native
+    /// side (snappy/lzf) are replaced with lz4.
+    public static String shuffleCompressionCodec() {
+        if (!booleanConf("spark.shuffle.compress", true)) {
+            return "uncompressed";
+        }
+        String codec = stringConf("spark.io.compression.codec", "lz4").toLowerCase(Locale.ROOT);
+        if (codec.equals("zstd") || codec.endsWith(".zstdcompressioncodec")) {
+            return "zstd";
+        }
+        return "lz4";
+    }
+
+    /// compression level of native shuffle data with zstd codec.
+    public static int shuffleCompressionZstdLevel() {
+        return intConf("spark.io.compression.zstd.level", 1);
+    }
+
+    /// whether column names are resolved case-sensitively in native plans.
+    public static boolean caseSensitive() {
+        return booleanConf("spark.sql.caseSensitive", false);
+    }
+
+    /// emits string partition columns of parquet scans as dictionary arrays in native side,
+    /// avoiding repeating the same partition value for every row.
+    public static boolean parquetDictionaryPartitionColumns() {
+        return booleanConf("spark.blaze.parquet.dictionaryPartitionColumns", false);
+    }
+
+    /// number of window partition groups evaluated concurrently in each native window task, which
+    /// speeds up tasks with many small window partitions. 1 to disable parallel evaluation.
+    public static int windowParallelism() {
+        return intConf("spark.blaze.window.parallelism", 1);
+    }
+
+    /// max bytes of input batches buffered in each native window task for parallel evaluation.
+    public static int windowParallelMemoryLimit() {
+        return intConf("spark.blaze.window.parallel.memoryLimit", 64 * 1024 * 1024);
+    }
+
+    /// reorders deterministic and infallible filter predicates by their selectivity and cost measured
+    /// over the first batches of each native filter.
+    public static boolean enableAdaptiveFilterOrder() {
+        return booleanConf("spark.blaze.filter.adaptiveOrder.enabled", false);
+    }
+
     private static int intConf(String key, int defaultValue) {
         return conf().getInt(key, defaultValue);
     }
 
+    private static long longConf(String key, long defaultValue) {
+        return conf().getLong(key, defaultValue);
+    }
+
     private static double doubleConf(String key, double defaultValue) {
         return conf().getDouble(key, defaultValue);
     }
@@ -83,6 +344,10 @@ public class BlazeConf {
         return conf().getBoolean(key, defaultValue);
     }
 
+    private static String stringConf(String key, String defaultValue) {
+        return conf().get(key, defaultValue);
+    }
+
     private static SparkConf conf() {
         return SparkEnv$.MODULE$.get().conf();
     }
@@ -16,21 +16,45 @@
 package org.apache.spark.sql.blaze;
 
 import java.util.concurrent.ConcurrentHashMap;
+import org.apache.spark.SparkEnv;
 import org.apache.spark.TaskContext;
 import org.apache.spark.TaskContext$;
+import org.apache.spark.sql.blaze.memory.NativeMemoryConsumer;
+import org.apache.spark.sql.blaze.memory.NativeMemoryConsumer$;
 import org.apache.spark.sql.blaze.memory.OnHeapSpillManager;
 import org.apache.spark.sql.blaze.memory.OnHeapSpillManager$;
+import org.apache.spark.util.Utils;
+import org.slf4j.Logger;
+import org.slf4j.LoggerFactory;
+import org.slf4j.MDC;
 
 @SuppressWarnings("unused")
 public class JniBridge {
     public static final ConcurrentHashMap<String, Object> resourcesMap = new ConcurrentHashMap<>();
 
+    private static final Logger nativeLogger = LoggerFactory.getLogger("org.apache.spark.sql.blaze.native");
+
     public static native void initNative(long nativeMemory);
 
     public static native long callNative(BlazeCallNativeWrapper wrapper);
 
     public static native void finalizeNative(long ptr);
 
+    /// returns bytes allocated by the native allocator, or -1 if not supported.
+    public static native long getNativeAllocatedMemory();
+
+    /// returns bytes resident in pages mapped by the native allocator, or -1 if not supported.
+    public static native long getNativeResidentMemory();
+
+    /// renders the serialized native plan into the specified format ("json" or "dot").
+    public static native String renderNativePlan(byte[] plan, String format);
+
+    /// returns the plan protocol version supported by the native engine.
+    public static native int getPlanProtocolVersion();
+
+    /// releases native cached data of a broadcast, called once the broadcast is removed.
+    public static native void releaseCachedBroadcast(String id);
+
     public static ClassLoader getContextClassLoader() {
         return Thread.currentThread().getContextClassLoader();
     }
@@ -55,6 +79,18 @@ public class JniBridge {
         return OnHeapSpillManager$.MODULE$.current();
     }
 
+    public static NativeMemoryConsumer getTaskNativeMemoryConsumer() {
+        return NativeMemoryConsumer$.MODULE$.current();
+    }
+
+    public static String getLocalDirs() {
+        SparkEnv sparkEnv = SparkEnv.get();
+        if (sparkEnv == null) {
+            return "";
+        }
+        return String.join(",", Utils.getConfiguredLocalDirs(sparkEnv.conf()));
+    }
+
     public static boolean isTaskRunning() {
         TaskContext tc = getTaskContext();
         if (tc == null) { // driver is always running
@@ -67,4 +103,64 @@ public class JniBridge {
         TaskContext tc = getTaskContext();
         return tc == null;
     }
+
+    public static int getPartitionId() {
+        TaskContext tc = getTaskContext();
+        if (tc == null) { // driver side
+            return 0;
+        }
+        return tc.partitionId();
+    }
+
+    /// returns the most verbose level enabled for native logs (1=error .. 5=trace, 0=off).
+    public static int getNativeLogLevel() {
+        if (nativeLogger.isTraceEnabled()) {
+            return 5;
+        } else if (nativeLogger.isDebugEnabled()) {
+            return 4;
+        } else if (nativeLogger.isInfoEnabled()) {
+            return 3;
+        } else if (nativeLogger.isWarnEnabled()) {
+            return 2;
+        } else if (nativeLogger.isErrorEnabled()) {
+            return 1;
+        }
+        return 0;
+    }
+
+    /// writes a native log record, with stage/partition/task attempt id of the current task
+    /// attached as MDC fields.
+    public static void nativeLog(int level, String target, String message) {
+        TaskContext tc = getTaskContext();
+        if (tc != null) {
+            MDC.put("blaze.stageId", String.valueOf(tc.stageId()));
+            MDC.put("blaze.partitionId", String.valueOf(tc.partitionId()));
+            MDC.put("blaze.taskAttemptId", String.valueOf(tc.taskAttemptId()));
+        }
+        try {
+            switch (level) {
+                case 1:
+                    nativeLogger.error("[{}] {}", target, message);
+                    break;
+                case 2:
+                    nativeLogger.warn("[{}] {}", target, message);
+                    break;
+                case 3:
+                    nativeLogger.info("[{}] {}", target, message);
+                    break;
+                case 4:
+                    nativeLogger.debug("[{}] {}", target, message);
+                    break;
+                default:
+                    nativeLogger.trace("[{}] {}", target, message);
+                    break;
+            }
+        } finally {
+            if (tc != null) {
+                MDC.remove("blaze.stageId");
+                MDC.remove("blaze.partitionId");
+                MDC.remove("blaze.taskAttemptId");
+            }
+        }
+    }
 }
@@ -23,12 +23,14 @@ import java.util.concurrent.atomic.AtomicReference
 
 import org.apache.spark.InterruptibleIterator
 import org.apache.spark.Partition
+import org.apache.spark.SparkEnv
 import org.apache.spark.TaskContext
 import org.blaze.protobuf.PartitionId
 import org.blaze.protobuf.PhysicalPlanNode
 import org.blaze.protobuf.TaskDefinition
 
 import org.apache.spark.internal.Logging
+import org.apache.spark.sql.blaze.memory.NativeMemorySource
 import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIStreamImportIterator
 import org.apache.spark.util.CompletionIterator
@@ -44,6 +46,10 @@ case class BlazeCallNativeWrapper(
   BlazeCallNativeWrapper.initNative()
 
   private val error: AtomicReference[Throwable] = new AtomicReference(null)
+  private val deadlineMillis: Long = BlazeConf.taskTimeoutMs match {
+    case timeoutMs if timeoutMs > 0 => System.currentTimeMillis() + timeoutMs
+    case _ => 0L
+  }
   private var arrowFFIStreamPtr = 0L
 
   logInfo(s"Start executing native plan")
@@ -70,6 +76,13 @@ case class BlazeCallNativeWrapper(
     this.error.set(error)
   }
 
+  protected def setMetricsSummary(summary: String): Unit = {
+    val stageId = context.map(_.stageId()).getOrElse(0)
+    logInfo(
+      s"Native execution metrics summary " +
+        s"[stage=$stageId, partition=${partition.index}]:\n$summary")
+  }
+
   protected def checkError(): Unit = {
     val throwable = error.getAndSet(null)
     if (throwable != null) {
@@ -93,6 +106,9 @@ case class BlazeCallNativeWrapper(
       .newBuilder()
       .setTaskId(partitionId)
       .setPlan(nativePlan)
+      .setDeadlineMillis(deadlineMillis)
+      .setOperatorTimeoutMillis(BlazeConf.operatorTimeoutMs.max(0L))
+      .setPlanVersion(NativeHelper.planProtocolVersion)
       .build()
     taskDefinition.toByteArray
   }
@@ -131,6 +147,11 @@ object BlazeCallNativeWrapper extends Logging {
 
     BlazeCallNativeWrapper.loadLibBlaze()
     JniBridge.initNative(NativeHelper.nativeMemory)
+
+    // report native allocator statistics within executor metrics
+    if (SparkEnv.get != null) {
+      SparkEnv.get.metricsSystem.registerSource(NativeMemorySource)
+    }
   }
 
   private def loadLibBlaze(): Unit = {
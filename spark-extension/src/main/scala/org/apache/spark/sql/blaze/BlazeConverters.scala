@@ -18,6 +18,7 @@ package org.apache.spark.sql.blaze
 import java.util.UUID
 
 import scala.annotation.tailrec
+import scala.collection.JavaConverters._
 import scala.collection.mutable
 import scala.collection.mutable.ArrayBuffer
 
@@ -32,6 +33,7 @@ import org.apache.spark.sql.catalyst.expressions.Alias
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.AttributeReference
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.InputFileName
 import org.apache.spark.sql.catalyst.expressions.NamedExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Final
 import org.apache.spark.sql.catalyst.expressions.aggregate.Partial
@@ -58,6 +60,9 @@ import org.apache.spark.sql.execution.blaze.plan.NativeProjectBase
 import org.apache.spark.sql.execution.blaze.plan.NativeUnionBase
 import org.apache.spark.sql.execution.blaze.plan.Util
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.csv.CSVFileFormat
+import org.apache.spark.sql.execution.datasources.json.JsonFileFormat
 import org.apache.spark.sql.execution.datasources.parquet.ParquetFileFormat
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeExec
 import org.apache.spark.sql.execution.exchange.ShuffleExchangeExec
@@ -224,6 +229,7 @@ object BlazeConverters extends Logging {
   def tryConvert[T <: SparkPlan](exec: T, convert: T => SparkPlan): SparkPlan = {
     try {
       exec.setTagValue(convertibleTag, true)
+      assertInputFileNameResolvable(exec)
       convert(exec)
 
     } catch {
@@ -235,6 +241,29 @@ object BlazeConverters extends Logging {
     }
   }
 
+  // input_file_name() is resolved in native side to the input file column of a
+  // parquet scan, which is only passed through projects, filters, limits and
+  // renames. fall back otherwise, instead of silently producing empty strings.
+  private def assertInputFileNameResolvable(exec: SparkPlan): Unit = {
+    if (exec.expressions.exists(_.find(_.isInstanceOf[InputFileName]).isDefined)) {
+      @tailrec
+      def isResolvable(plan: SparkPlan): Boolean =
+        plan match {
+          case _: NativeParquetScanBase => true
+          case p: NativeProjectBase => isResolvable(p.child)
+          case p: NativeFilterBase => isResolvable(p.child)
+          case p: NativeLocalLimitBase => isResolvable(p.child)
+          case p: NativeGlobalLimitBase => isResolvable(p.child)
+          case p: NativeRenameColumnsBase => isResolvable(p.child)
+          case _ => false
+        }
+      assert(
+        (exec.isInstanceOf[ProjectExec] || exec.isInstanceOf[FilterExec]) &&
+          isResolvable(exec.children.head),
+        "input_file_name() is only supported above native parquet scans")
+    }
+  }
+
   def convertShuffleExchangeExec(exec: ShuffleExchangeExec): SparkPlan = {
     val (outputPartitioning, child) = (exec.outputPartitioning, exec.child)
     logDebug(s"Converting ShuffleExchangeExec: ${Shims.get.simpleStringWithNodeId(exec)}")
@@ -777,12 +806,40 @@ object BlazeConverters extends Logging {
       case DataWritingCommandExec(cmd: InsertIntoHiveTable, child)
           if cmd.table.storage.outputFormat.contains(
             classOf[MapredParquetOutputFormat].getName) =>
+        // parquet modular encryption (encrypted footer and columns) is not supported by
+        // native parquet sink, fall back to parquet-mr based writing when encryption is
+        // configured in table properties or in the effective hadoop conf
+        val sparkSession = Shims.get.getSqlContext(exec).sparkSession
+        val hadoopConf = sparkSession.sessionState.newHadoopConfWithOptions(
+          cmd.table.properties ++ cmd.table.storage.properties)
+        val encryptionProps = hadoopConf.asScala
+          .map(_.getKey.stripPrefix("spark.hadoop."))
+          .filter(isParquetEncryptionProp)
+          .toSeq
+          .distinct
+        if (encryptionProps.nonEmpty) {
+          throw new NotImplementedError(
+            s"parquet encryption is not supported: ${encryptionProps.mkString(", ")}")
+        }
         Shims.get.createNativeParquetInsertIntoHiveTableExec(cmd, child)
+      case DataWritingCommandExec(cmd: InsertIntoHadoopFsRelationCommand, child)
+          if cmd.fileFormat.isInstanceOf[CSVFileFormat] ||
+            cmd.fileFormat.isInstanceOf[JsonFileFormat] =>
+        Shims.get.createNativeTextInsertIntoHadoopFsRelationExec(cmd, child)
       case _ =>
         throw new NotImplementedError("unsupported DataWritingCommandExec")
     }
   }
 
+  // properties enabling parquet-mr's encryption: the crypto factory, and the footer/column
+  // keys used by its properties-driven factory
+  private def isParquetEncryptionProp(key: String): Boolean = {
+    key == "parquet.crypto.factory.class" ||
+    key == "parquet.encryption.footer.key" ||
+    key == "parquet.encryption.column.keys" ||
+    key == "parquet.encryption.uniform.key"
+  }
+
   def convertToNative(exec: SparkPlan): SparkPlan = {
     exec match {
       case exec if NativeHelper.isNative(exec) => exec
@@ -75,6 +75,10 @@ case class BlazeColumnarOverrides(sparkSession: SparkSession) extends ColumnarRu
           return sparkPlan // skip useless local table scan (generated by set, addjar, etc)
         }
 
+        if (!NativeHelper.isPlanProtocolSupported) {
+          return sparkPlan // performs no conversion if native engine is incompatible
+        }
+
         // generate convert strategy
         BlazeConvertStrategy.apply(sparkPlan)
         logInfo("Blaze convert strategy for current stage:")
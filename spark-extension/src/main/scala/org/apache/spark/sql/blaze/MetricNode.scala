@@ -15,6 +15,10 @@
  */
 package org.apache.spark.sql.blaze
 
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
+import java.nio.charset.StandardCharsets
+
 import org.apache.spark.internal.Logging
 import org.apache.spark.sql.execution.metric.SQLMetric
 
@@ -31,4 +35,15 @@ case class MetricNode(
     metrics.get(metricName).foreach(_.add(v))
     metricValueHandler.foreach(_.apply(metricName, v))
   }
+
+  // batched version of add() called by native side, buffer contains
+  // (nameLength: int32, name: utf8, value: int64) entries in little endian
+  def addAll(buffer: ByteBuffer): Unit = {
+    val buf = buffer.duplicate().order(ByteOrder.LITTLE_ENDIAN)
+    while (buf.hasRemaining) {
+      val nameBytes = new Array[Byte](buf.getInt())
+      buf.get(nameBytes)
+      add(new String(nameBytes, StandardCharsets.UTF_8), buf.getLong())
+    }
+  }
 }
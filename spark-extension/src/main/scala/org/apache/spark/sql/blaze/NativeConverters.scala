@@ -27,7 +27,8 @@ import com.google.protobuf.ByteString
 import org.apache.spark.SparkEnv
 import org.blaze.{protobuf => pb}
 import org.apache.spark.internal.Logging
-import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, Asin, Atan, AttributeReference, BitwiseAnd, BitwiseOr, BoundReference, CaseWhen, Cast, Ceil, CheckOverflow, Coalesce, Concat, ConcatWs, Contains, Cos, CreateArray, CreateNamedStruct, Divide, EndsWith, EqualTo, Exp, Expression, Floor, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, If, In, InSet, IsNotNull, IsNull, Length, LessThan, LessThanOrEqual, Like, Literal, Log, Log10, Log2, Lower, MakeDecimal, Md5, Multiply, Murmur3Hash, Not, NullIf, OctetLength, Or, Pmod, PromotePrecision, Remainder, Sha2, ShiftLeft, ShiftRight, Signum, Sin, Sqrt, StartsWith, StringRepeat, StringSpace, StringTrim, StringTrimLeft, StringTrimRight, Substring, Subtract, Tan, TruncDate, Unevaluable, UnscaledValue, Upper}
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.{Abs, Acos, Add, Alias, And, Asin, Atan, AttributeReference, BitwiseAnd, BitwiseOr, BoundReference, CaseWhen, Cast, Ceil, CheckOverflow, Coalesce, Concat, ConcatWs, Contains, Cos, CreateArray, CreateNamedStruct, Divide, EndsWith, EqualTo, Exp, Expression, Floor, GetArrayItem, GetMapValue, GetStructField, GreaterThan, GreaterThanOrEqual, If, In, InSet, IsNotNull, IsNull, Length, LessThan, LessThanOrEqual, Like, Literal, Log, Log10, Log2, Lower, MakeDecimal, Md5, Multiply, Murmur3Hash, Not, NullIf, OctetLength, Or, Pmod, PromotePrecision, RLike, RegExpExtract, Remainder, Sha2, ShiftLeft, ShiftRight, Signum, Sin, Sqrt, StartsWith, StringRepeat, StringSpace, StringTrim, StringTrimLeft, StringTrimRight, Substring, Subtract, Tan, TruncDate, Unevaluable, UnscaledValue, Upper}
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.expressions.aggregate.Average
 import org.apache.spark.sql.catalyst.expressions.aggregate.CollectList
@@ -38,6 +39,13 @@ import org.apache.spark.sql.catalyst.expressions.aggregate.Min
 import org.apache.spark.sql.catalyst.expressions.aggregate.Sum
 import org.apache.spark.sql.catalyst.expressions.Attribute
 import org.apache.spark.sql.catalyst.expressions.BinaryArithmetic
+import org.apache.spark.sql.catalyst.expressions.InputFileName
+import org.apache.spark.sql.catalyst.expressions.MonotonicallyIncreasingID
+import org.apache.spark.sql.catalyst.expressions.Nondeterministic
+import org.apache.spark.sql.catalyst.expressions.Rand
+import org.apache.spark.sql.catalyst.expressions.Randn
+import org.apache.spark.sql.catalyst.expressions.SparkPartitionID
+import org.apache.spark.sql.catalyst.expressions.Uuid
 import org.apache.spark.sql.catalyst.expressions.aggregate.First
 import org.apache.spark.sql.catalyst.plans.FullOuter
 import org.apache.spark.sql.catalyst.plans.Inner
@@ -227,6 +235,15 @@ object NativeConverters extends Logging {
               values.addValues(convertValue(value, at.elementType))
             })
         scalarValueBuilder.setListValue(values)
+
+      case st: StructType =>
+        val row = sparkValue.asInstanceOf[InternalRow]
+        val values = pb.ScalarStructValue.newBuilder()
+        st.fields.zipWithIndex.foreach { case (field, i) =>
+          values.addFields(convertField(field))
+          values.addValues(convertValue(row.get(i, field.dataType), field.dataType))
+        }
+        scalarValueBuilder.setStructValue(values)
     }
     scalarValueBuilder.build()
   }
@@ -389,11 +406,112 @@ object NativeConverters extends Logging {
     buildFn(pb.PhysicalExprNode.newBuilder()).build()
   }
 
+  // element_at(array, index), index starts from 1 and negative index counts from the end
+  def buildGetArrayElementExpr(
+      array: Expression,
+      index: Long,
+      failOnError: Boolean): pb.PhysicalExprNode = {
+    buildExprNode {
+      _.setGetIndexedFieldExpr(
+        pb.PhysicalGetIndexedFieldExprNode
+          .newBuilder()
+          .setExpr(convertExpr(array))
+          .setKey(convertValue(index, LongType))
+          .setAllowNegativeIndex(true)
+          .setFailOnError(failOnError))
+    }
+  }
+
+  // split(str, regex, limit) with java's String.split() semantics
+  def buildStringSplitExpr(str: Expression, regex: String, limit: Int): pb.PhysicalExprNode = {
+    buildExprNode {
+      _.setScalarFunction(
+        pb.PhysicalScalarFunctionNode
+          .newBuilder()
+          .setFun(pb.ScalarFunction.SparkExtFunctions)
+          .setName("StringSplit")
+          .addArgs(convertExpr(str))
+          .addArgs(convertExpr(Literal(regex)))
+          .addArgs(convertExpr(Literal(limit)))
+          .setReturnType(convertDataType(ArrayType(StringType))))
+    }
+  }
+
+  // regexp_replace(str, regex, rep, pos) with java's Matcher.appendReplacement() semantics
+  def buildRegexpReplaceExpr(
+      str: Expression,
+      regex: String,
+      rep: String,
+      pos: Int): pb.PhysicalExprNode = {
+    buildExprNode {
+      _.setScalarFunction(
+        pb.PhysicalScalarFunctionNode
+          .newBuilder()
+          .setFun(pb.ScalarFunction.SparkExtFunctions)
+          .setName("RegexpReplace")
+          .addArgs(convertExpr(str))
+          .addArgs(convertExpr(Literal(regex)))
+          .addArgs(convertExpr(Literal(rep)))
+          .addArgs(convertExpr(Literal(pos)))
+          .setReturnType(convertDataType(StringType)))
+    }
+  }
+
+  // java regex constructs are translated to rust regex in native side, except
+  // constructs requiring backtracking (lookarounds, atomic groups, backreferences
+  // and possessive quantifiers) and some rarely used escapes/properties
+  def isNativeRegexSupported(regex: String): Boolean = {
+    val unsupportedConstructs = Seq(
+      "(?=", "(?!", "(?<=", "(?<!", "(?>", // lookarounds and atomic groups
+      "*+", "++", "?+", // possessive quantifiers
+      "\\G", "\\Z", "\\X", "\\k<")
+    val unsupportedPatterns = Seq(
+      "(?s).*\\\\[1-9].*", // backreferences
+      "(?s).*\\{\\d+(,\\d*)?}\\+.*", // possessive quantifiers
+      "(?s).*\\\\[pP]\\{(In|java(?!LowerCase|UpperCase|Alphabetic|Digit|Letter)).*",
+      "(?s).*\\(\\?[a-zA-Z-]*U.*\\\\[pP]\\{(Blank|Graph|Print)}.*")
+    !unsupportedConstructs.exists(regex.contains) && !unsupportedPatterns.exists(regex.matches)
+  }
+
+  // like all/like any/not like all/not like any, null patterns are passed as a flag
+  def buildStringLikeMultiExpr(
+      child: Expression,
+      patterns: Seq[UTF8String],
+      isAll: Boolean,
+      negated: Boolean): pb.PhysicalExprNode = {
+    buildExprNode {
+      _.setStringLikeMultiExpr(
+        pb.StringLikeMultiExprNode
+          .newBuilder()
+          .setExpr(convertExpr(child))
+          .addAllPatterns(patterns.filter(_ != null).map(_.toString).asJava)
+          .setHasNullPattern(patterns.contains(null))
+          .setIsAll(isAll)
+          .setNegated(negated))
+    }
+  }
+
+  // nondeterministic expressions supported in native side, which generate the
+  // same results as spark within each partition
+  private def isNativeNondeterministic(sparkExpr: Expression): Boolean = {
+    sparkExpr match {
+      case _: Rand | _: Randn | _: MonotonicallyIncreasingID => true
+      case _: SparkPartitionID | _: InputFileName => true
+      case e: Uuid => e.randomSeed.isDefined
+      case _: Nondeterministic => false
+      case e =>
+        e.children.exists(!_.deterministic) &&
+        e.children.forall(child => child.deterministic || isNativeNondeterministic(child))
+    }
+  }
+
   private def convertExprWithFallback(
       sparkExpr: Expression,
       isPruningExpr: Boolean,
       fallback: Expression => pb.PhysicalExprNode): pb.PhysicalExprNode = {
-    assert(sparkExpr.deterministic, s"nondeterministic expression not supported: $sparkExpr")
+    assert(
+      sparkExpr.deterministic || isNativeNondeterministic(sparkExpr),
+      s"nondeterministic expression not supported: $sparkExpr")
 
     def buildBinaryExprNode(
         left: Expression,
@@ -445,6 +563,13 @@ object NativeConverters extends Logging {
       Cast(expr, dataType)
     }
 
+    def randSeed(seedExpr: Expression): Long =
+      seedExpr match {
+        case Literal(s, IntegerType) => s.asInstanceOf[Int]
+        case Literal(s, LongType) => s.asInstanceOf[Long]
+        case _ => throw new NotImplementedError(s"unsupported random seed: $seedExpr")
+      }
+
     def unpackBinaryTypeCast(expr: Expression) =
       expr match {
         case expr: Cast if expr.dataType == BinaryType => expr.child
@@ -552,13 +677,9 @@ object NativeConverters extends Logging {
             pb.PhysicalInListNode
               .newBuilder()
               .setExpr(convertExprWithFallback(value, isPruningExpr, fallback))
-              .addAllList(set.map {
-                case utf8string: UTF8String =>
-                  convertExprWithFallback(
-                    Literal(utf8string, StringType),
-                    isPruningExpr,
-                    fallback)
-                case v => convertExprWithFallback(Literal.apply(v), isPruningExpr, fallback)
+              .addAllList(set.map { v =>
+                // set values are in internal representation, like UTF8String/InternalRow/ArrayData
+                convertExprWithFallback(Literal(v, value.dataType), isPruningExpr, fallback)
               }.asJava))
         }
 
@@ -695,8 +816,8 @@ object NativeConverters extends Logging {
                   .setOp("Modulo"))
             }
         }
-      case e: Like =>
-        assert(Shims.get.getLikeEscapeChar(e) == '\\')
+      // custom escape chars are supported only with literal patterns
+      case e: Like if Shims.get.getLikeEscapeChar(e) == '\\' || e.right.isInstanceOf[Literal] =>
         buildExprNode {
           _.setLikeExpr(
             pb.PhysicalLikeExprNode
@@ -704,7 +825,8 @@ object NativeConverters extends Logging {
               .setNegated(false)
               .setCaseInsensitive(false)
               .setExpr(convertExprWithFallback(e.left, isPruningExpr, fallback))
-              .setPattern(convertExprWithFallback(e.right, isPruningExpr, fallback)))
+              .setPattern(convertExprWithFallback(e.right, isPruningExpr, fallback))
+              .setEscapeChar(Shims.get.getLikeEscapeChar(e).toString))
         }
 
       // if rhs is complex in and/or operators, use short-circuiting implementation
@@ -791,8 +913,8 @@ object NativeConverters extends Logging {
         buildScalarFunction(pb.ScalarFunction.Ltrim, e.srcStr +: e.trimStr.toSeq, e.dataType)
       case e: StringTrimRight =>
         buildScalarFunction(pb.ScalarFunction.Rtrim, e.srcStr +: e.trimStr.toSeq, e.dataType)
-      case e @ NullIf(left, right, _) =>
-        buildScalarFunction(pb.ScalarFunction.NullIf, left :: right :: Nil, e.dataType)
+      case e @ NullIf(left, right, _) if left.dataType == right.dataType =>
+        buildExtScalarFunction("NullIf", left :: right :: Nil, e.dataType)
       case e: TruncDate =>
         buildScalarFunction(pb.ScalarFunction.DateTrunc, e.children, e.dataType)
       case Md5(_1) =>
@@ -861,6 +983,14 @@ object NativeConverters extends Logging {
       case StringRepeat(str, n @ Literal(_, IntegerType)) =>
         buildExtScalarFunction("StringRepeat", str :: n :: Nil, StringType)
 
+      case RLike(str, regex @ Literal(pattern, StringType))
+          if pattern == null || isNativeRegexSupported(pattern.toString) =>
+        buildExtScalarFunction("RegexpLike", str :: regex :: Nil, BooleanType)
+
+      case RegExpExtract(str, regex @ Literal(pattern, StringType), idx @ Literal(_, IntegerType))
+          if pattern == null || isNativeRegexSupported(pattern.toString) =>
+        buildExtScalarFunction("RegexpExtract", str :: regex :: idx :: Nil, StringType)
+
       case e: Concat if e.children.forall(_.dataType == StringType) =>
         buildExtScalarFunction("StringConcat", e.children, e.dataType)
 
@@ -925,6 +1055,41 @@ object NativeConverters extends Logging {
 
       case e: CreateArray => buildExtScalarFunction("MakeArray", e.children, e.dataType)
 
+      // nondeterministic expressions, initialized with partitionIndex in native side
+      case e: Rand =>
+        buildExprNode {
+          _.setRandExpr(
+            pb.PhysicalRandExprNode
+              .newBuilder()
+              .setSeed(randSeed(e.child))
+              .setGaussian(false))
+        }
+      case e: Randn =>
+        buildExprNode {
+          _.setRandExpr(
+            pb.PhysicalRandExprNode
+              .newBuilder()
+              .setSeed(randSeed(e.child))
+              .setGaussian(true))
+        }
+      case e: Uuid if e.randomSeed.isDefined =>
+        buildExprNode {
+          _.setUuidExpr(pb.PhysicalUuidExprNode.newBuilder().setSeed(e.randomSeed.get))
+        }
+      case _: MonotonicallyIncreasingID =>
+        buildExprNode {
+          _.setMonotonicallyIncreasingIdExpr(
+            pb.PhysicalMonotonicallyIncreasingIdExprNode.newBuilder())
+        }
+      case _: SparkPartitionID =>
+        buildExprNode {
+          _.setSparkPartitionIdExpr(pb.PhysicalSparkPartitionIdExprNode.newBuilder())
+        }
+      case _: InputFileName =>
+        buildExprNode {
+          _.setInputFileNameExpr(pb.PhysicalInputFileNameExprNode.newBuilder())
+        }
+
       case e: CreateNamedStruct =>
         buildExprNode {
           _.setNamedStruct(
@@ -949,7 +1114,8 @@ object NativeConverters extends Logging {
               .setExpr(convertExprWithFallback(e.child, isPruningExpr, fallback))
               .setKey(convertValue(
                 ordinalValue.longValue() + 1, // NOTE: data-fusion index starts from 1
-                LongType)))
+                LongType))
+              .setFailOnError(Shims.get.getArrayItemFailOnError(e)))
         }
 
       case e: GetMapValue if e.key.isInstanceOf[Literal] =>
@@ -15,6 +15,8 @@
  */
 package org.apache.spark.sql.blaze
 
+import java.util.UUID
+
 import scala.collection.immutable.TreeMap
 
 import org.apache.hadoop.security.UserGroupInformation
@@ -23,14 +25,19 @@ import org.apache.spark.SparkConf
 import org.apache.spark.SparkContext
 import org.apache.spark.SparkEnv
 import org.apache.spark.TaskContext
+import org.apache.arrow.c.ArrowArrayStream
+import org.blaze.protobuf.FFIExporterExecNode
 import org.blaze.protobuf.PhysicalPlanNode
 
 import org.apache.spark.internal.Logging
 import org.apache.spark.internal.config
 import org.apache.spark.sql.catalyst.InternalRow
 import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIStreamColumnarImportIterator
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.sql.vectorized.ColumnarBatch
 
 object NativeHelper extends Logging {
   val currentUser: UserGroupInformation = UserGroupInformation.getCurrentUser
@@ -57,6 +64,22 @@ object NativeHelper extends Logging {
     }
   }
 
+  // plan protocol version of the jvm side, increased together with
+  // PLAN_PROTOCOL_VERSION in blaze-serde when new plan/expr nodes are added
+  val planProtocolVersion: Int = 7
+
+  // native engine older than the jvm side may not support generated plans
+  lazy val isPlanProtocolSupported: Boolean = {
+    BlazeCallNativeWrapper.initNative()
+    val nativeVersion = JniBridge.getPlanProtocolVersion
+    if (nativeVersion < planProtocolVersion) {
+      logWarning(
+        s"Native plan protocol version $nativeVersion is older than " +
+          s"jvm side's version $planProtocolVersion, falling back to spark execution")
+    }
+    nativeVersion >= planProtocolVersion
+  }
+
   def isNative(exec: SparkPlan): Boolean =
     Shims.get.isNative(exec)
 
@@ -78,13 +101,55 @@ object NativeHelper extends Logging {
     BlazeCallNativeWrapper(nativePlan, partition, context, metrics).getRowIterator
   }
 
+  // executes the native plan and returns its output as arrow-backed columnar batches,
+  // exported by FFIExporterExec without converting to rows
+  def executeNativePlanColumnar(
+      nativePlan: PhysicalPlanNode,
+      metrics: MetricNode,
+      partition: Partition,
+      context: Option[TaskContext]): Iterator[ColumnarBatch] = {
+    if (nativePlan == null) {
+      return Iterator.empty
+    }
+    BlazeCallNativeWrapper.initNative()
+    val stream = ArrowArrayStream.allocateNew(ArrowUtils.rootAllocator)
+    val resourceId = s"FFIExporterExec:${UUID.randomUUID().toString}"
+    JniBridge.resourcesMap.put(resourceId, () => java.lang.Long.valueOf(stream.memoryAddress()))
+
+    val exporterPlan = PhysicalPlanNode
+      .newBuilder()
+      .setFfiExporter(
+        FFIExporterExecNode
+          .newBuilder()
+          .setInput(nativePlan)
+          .setExporterResourceId(resourceId)
+          .build())
+      .build()
+
+    // output of the exporter is always empty, draining it completes the native
+    // execution and reports errors after all batches are consumed
+    val rowIter = executeNativePlan(exporterPlan, metrics, partition, context)
+    new ArrowFFIStreamColumnarImportIterator(context, stream, () => assert(rowIter.isEmpty))
+  }
+
+  // renders the native plan into "json" or graphviz "dot", for inspecting what the
+  // native engine will run
+  def renderNativePlan(nativePlan: PhysicalPlanNode, format: String = "json"): String = {
+    BlazeCallNativeWrapper.initNative()
+    JniBridge.renderNativePlan(nativePlan.toByteArray, format)
+  }
+
   def getDefaultNativeMetrics(sc: SparkContext): Map[String, SQLMetric] = {
     var metrics = TreeMap(
       "output_rows" -> SQLMetrics.createMetric(sc, "Native.output_rows"),
       "output_batches" -> SQLMetrics.createMetric(sc, "Native.output_batches"),
       "elapsed_compute" -> SQLMetrics.createNanoTimingMetric(sc, "Native.elapsed_compute"),
       "join_time" -> SQLMetrics.createNanoTimingMetric(sc, "Native.join_time"),
-      "spilled_bytes" -> SQLMetrics.createSizeMetric(sc, "Native.spilled_bytes"))
+      "build_time" -> SQLMetrics.createNanoTimingMetric(sc, "Native.build_time"),
+      "probe_time" -> SQLMetrics.createNanoTimingMetric(sc, "Native.probe_time"),
+      "spilled_bytes" -> SQLMetrics.createSizeMetric(sc, "Native.spilled_bytes"),
+      "spill_count" -> SQLMetrics.createMetric(sc, "Native.spill_count"),
+      "mem_peak_used" -> SQLMetrics.createSizeMetric(sc, "Native.mem_peak_used"))
 
     if (BlazeConf.enableInputBatchStatistics()) {
       metrics ++= TreeMap(
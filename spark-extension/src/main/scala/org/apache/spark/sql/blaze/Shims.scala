@@ -26,6 +26,7 @@ import org.apache.spark.shuffle.IndexShuffleBlockResolver
 import org.apache.spark.shuffle.ShuffleHandle
 import org.apache.spark.shuffle.ShuffleWriteMetricsReporter
 import org.apache.spark.sql.catalyst.expressions.Expression
+import org.apache.spark.sql.catalyst.expressions.GetArrayItem
 import org.apache.spark.sql.catalyst.expressions.aggregate.AggregateExpression
 import org.apache.spark.sql.catalyst.plans.physical.BroadcastMode
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
@@ -34,6 +35,8 @@ import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.blaze.plan._
 import org.apache.spark.sql.execution.blaze.shuffle.RssPartitionWriterBase
 import org.apache.spark.sql.execution.datasources.BasicWriteTaskStats
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.PartitionedFile
 import org.apache.spark.sql.execution.exchange.BroadcastExchangeLike
 import org.apache.spark.sql.SQLContext
 import org.apache.spark.sql.catalyst.expressions.Attribute
@@ -148,6 +151,10 @@ abstract class Shims {
       child: SparkPlan,
       metrics: Map[String, SQLMetric]): NativePartialTakeOrderedBase
 
+  def createNativeTextInsertIntoHadoopFsRelationExec(
+      cmd: InsertIntoHadoopFsRelationCommand,
+      child: SparkPlan): NativeTextInsertIntoHadoopFsRelationBase
+
   def createNativeUnionExec(children: Seq[SparkPlan]): NativeUnionBase
 
   def createNativeWindowExec(
@@ -188,6 +195,9 @@ abstract class Shims {
 
   def getLikeEscapeChar(expr: Expression): Char
 
+  // whether GetArrayItem raises errors on out-of-bounds indices (ansi mode)
+  def getArrayItemFailOnError(expr: GetArrayItem): Boolean
+
   def getAggregateExpressionFilter(expr: Expression): Option[Expression]
 
   def createFileSegment(file: File, offset: Long, length: Long, numRecords: Long): FileSegment
@@ -220,6 +230,8 @@ abstract class Shims {
 
   def getSqlContext(sparkPlan: SparkPlan): SQLContext
 
+  def getPartitionedFileModificationTime(file: PartitionedFile): Long
+
   def createBasicWriteJobStatsTrackerForNativeParquetSink(
       serializableHadoopConf: SerializableConfiguration,
       metrics: Map[String, SQLMetric]): BasicWriteJobStatsTracker
@@ -0,0 +1,102 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze.memory
+
+import java.util.concurrent.atomic.AtomicLong
+
+import scala.collection.mutable
+
+import org.apache.spark.SparkEnv
+import org.apache.spark.TaskContext
+import org.apache.spark.internal.Logging
+import org.apache.spark.memory.MemoryConsumer
+import org.apache.spark.memory.MemoryMode
+import org.apache.spark.util.Utils
+
+/**
+ * acquires execution memory from spark's TaskMemoryManager on behalf of native memory consumers
+ * of a task, so native and jvm operators share the same memory budget.
+ */
+class NativeMemoryConsumer(taskContext: TaskContext, mode: MemoryMode)
+    extends MemoryConsumer(
+      taskContext.taskMemoryManager,
+      taskContext.taskMemoryManager.pageSizeBytes(),
+      mode)
+    with Logging {
+  import org.apache.spark.sql.blaze.memory.NativeMemoryConsumer._
+
+  private val requestedSpillSize = new AtomicLong(0)
+
+  // release all acquired memory on task completion
+  taskContext.addTaskCompletionListener { _ =>
+    synchronized {
+      if (getUsed > 0) {
+        logInfo(s"task completed, releasing native memory: ${Utils.bytesToString(getUsed)}")
+        freeMemory(getUsed)
+      }
+      all.synchronized(all.remove(taskContext.taskAttemptId()))
+    }
+  }
+
+  /**
+   * acquires memory from spark, other consumers may be spilled to satisfy the request
+   * @return
+   *   acquired size, which may be less than requested
+   */
+  def acquire(size: Long): Long = synchronized {
+    acquireMemory(size)
+  }
+
+  def release(size: Long): Unit = synchronized {
+    freeMemory(math.min(size, getUsed))
+  }
+
+  /**
+   * returns and resets the size requested to spill by other consumers. native consumers check
+   * this on updating their memory usage and spill themselves.
+   */
+  def takeRequestedSpillSize(): Long = requestedSpillSize.getAndSet(0)
+
+  // native consumers cannot be spilled synchronously in the caller thread, they are
+  // requested to spill on their next memory update instead
+  override def spill(size: Long, trigger: MemoryConsumer): Long = {
+    if ((trigger ne this) && getUsed > 0) {
+      logInfo(s"requesting native consumers to spill, size=${Utils.bytesToString(size)}")
+      requestedSpillSize.addAndGet(size)
+    }
+    0L
+  }
+}
+
+object NativeMemoryConsumer {
+  val all: mutable.Map[Long, NativeMemoryConsumer] = mutable.Map()
+
+  def current: NativeMemoryConsumer = all.synchronized {
+    val taskContext = TaskContext.get
+    all.getOrElseUpdate(
+      taskContext.taskAttemptId(),
+      new NativeMemoryConsumer(taskContext, memoryMode))
+  }
+
+  // native memory is allocated outside of jvm heap, use off-heap execution memory if enabled
+  private def memoryMode: MemoryMode = {
+    if (SparkEnv.get.conf.getBoolean("spark.memory.offHeap.enabled", defaultValue = false)) {
+      MemoryMode.OFF_HEAP
+    } else {
+      MemoryMode.ON_HEAP
+    }
+  }
+}
@@ -0,0 +1,42 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.blaze.memory
+
+import com.codahale.metrics.Gauge
+import com.codahale.metrics.MetricRegistry
+import org.apache.spark.metrics.source.Source
+import org.apache.spark.sql.blaze.JniBridge
+
+/**
+ * reports memory statistics of the native allocator to spark's metrics system, helps diagnosing
+ * native memory fragmentation on long-running executors.
+ */
+object NativeMemorySource extends Source {
+  override val sourceName: String = "blazeNativeMemory"
+  override val metricRegistry: MetricRegistry = new MetricRegistry()
+
+  metricRegistry.register(
+    MetricRegistry.name("allocatedBytes"),
+    new Gauge[Long] {
+      override def getValue: Long = JniBridge.getNativeAllocatedMemory
+    })
+
+  metricRegistry.register(
+    MetricRegistry.name("residentBytes"),
+    new Gauge[Long] {
+      override def getValue: Long = JniBridge.getNativeResidentMemory
+    })
+}
@@ -0,0 +1,79 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.arrowio
+
+import org.apache.arrow.c.ArrowArrayStream
+import org.apache.arrow.c.Data
+import org.apache.spark.TaskContext
+
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
+import org.apache.spark.sql.vectorized.ColumnarBatch
+
+/**
+ * imports batches exported by native FFIExporterExec as columnar batches without
+ * copying. each returned batch is only valid until the next call of hasNext.
+ */
+class ArrowFFIStreamColumnarImportIterator(
+    taskContext: Option[TaskContext],
+    stream: ArrowArrayStream,
+    onFinish: () => Unit = () => Unit)
+    extends Iterator[ColumnarBatch] {
+
+  private var reader = Data.importArrayStream(ArrowUtils.rootAllocator, stream)
+  private var hasLoadedBatch = false
+
+  taskContext.foreach(_.addTaskCompletionListener[Unit](_ => close()))
+
+  override def hasNext: Boolean = {
+    if (reader == null) { // closed?
+      return false
+    }
+    if (hasLoadedBatch) {
+      return true
+    }
+
+    var hasNextBatch = false
+    try {
+      hasNextBatch = reader.loadNextBatch()
+    } catch {
+      case _ if taskContext.exists(tc => tc.isCompleted() || tc.isInterrupted()) =>
+        hasNextBatch = false
+    }
+    if (!hasNextBatch) {
+      close()
+      onFinish()
+      return false
+    }
+    hasLoadedBatch = true
+    true
+  }
+
+  override def next(): ColumnarBatch = {
+    if (!hasNext) {
+      throw new NoSuchElementException("no more exported batches")
+    }
+    hasLoadedBatch = false
+    ColumnarHelper.rootAsBatch(reader.getVectorSchemaRoot)
+  }
+
+  def close(): Unit = {
+    if (reader != null) {
+      reader.close()
+      reader = null
+      stream.close()
+    }
+  }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.arrowio
+
+import scala.collection.JavaConverters._
+
+import org.apache.arrow.vector.VarBinaryVector
+import org.apache.arrow.vector.VectorSchemaRoot
+import org.apache.arrow.vector.ipc.ArrowReader
+import org.apache.arrow.vector.types.pojo.ArrowType
+import org.apache.arrow.vector.types.pojo.Field
+import org.apache.arrow.vector.types.pojo.FieldType
+import org.apache.arrow.vector.types.pojo.Schema
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.expressions.UnsafeProjection
+import org.apache.spark.sql.catalyst.expressions.UnsafeRow
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowWriter
+import org.apache.spark.sql.types.StructType
+import org.apache.spark.TaskContext
+import org.apache.spark.sql.blaze.BlazeConf
+
+/**
+ * converts rows to arrow batches lazily, to be exported to native side as a whole
+ * ArrowArrayStream (with Data.exportArrayStream) instead of per-batch exports.
+ *
+ * with unsafeRows enabled, rows are exported as UnsafeRow bytes in a single binary
+ * column and parsed into arrow columns natively.
+ */
+class ArrowFFIStreamExportReader(
+    rowIter: Iterator[InternalRow],
+    schema: StructType,
+    taskContext: TaskContext,
+    recordBatchSize: Int = BlazeConf.batchSize,
+    unsafeRows: Boolean = false)
+    extends ArrowReader(ArrowUtils.rootAllocator) {
+
+  private val arrowSchema = if (unsafeRows) {
+    val metadata = Map("blaze.unsafe_row.num_fields" -> schema.length.toString).asJava
+    val fieldType = new FieldType(false, ArrowType.Binary.INSTANCE, null, metadata)
+    new Schema(Seq(new Field("unsafe_row", fieldType, null)).asJava)
+  } else {
+    ArrowUtils.toArrowSchema(schema)
+  }
+  private var arrowWriter: ArrowWriter = _
+  private var bytesWritten = 0L
+
+  private lazy val toUnsafe: UnsafeProjection = {
+    val toUnsafe = UnsafeProjection.create(schema)
+    toUnsafe.initialize(taskContext.partitionId())
+    toUnsafe
+  }
+
+  override def loadNextBatch(): Boolean = {
+    if (taskContext.isCompleted() || !rowIter.hasNext) {
+      return false
+    }
+    val root = getVectorSchemaRoot
+    if (unsafeRows) {
+      return loadNextUnsafeRows(root)
+    }
+    if (arrowWriter == null) {
+      arrowWriter = ArrowWriter.create(root)
+    } else {
+      root.allocateNew()
+      arrowWriter.reset()
+    }
+    var rowCount = 0
+
+    while (rowIter.hasNext && rowCount < recordBatchSize) {
+      arrowWriter.write(rowIter.next())
+      rowCount += 1
+    }
+    arrowWriter.finish()
+    bytesWritten += root.getFieldVectors.asScala.map(_.getBufferSize.toLong).sum
+    true
+  }
+
+  private def loadNextUnsafeRows(root: VectorSchemaRoot): Boolean = {
+    val vector = root.getVector(0).asInstanceOf[VarBinaryVector]
+    vector.reset()
+    var rowCount = 0
+
+    while (rowIter.hasNext && rowCount < recordBatchSize) {
+      val unsafeRow = rowIter.next() match {
+        case row: UnsafeRow => row
+        case row => toUnsafe(row)
+      }
+      vector.setSafe(rowCount, unsafeRow.getBytes)
+      rowCount += 1
+    }
+    root.setRowCount(rowCount)
+    bytesWritten += vector.getBufferSize
+    true
+  }
+
+  override def bytesRead(): Long = bytesWritten
+
+  override def closeReadSource(): Unit = {}
+
+  override def readSchema(): Schema = arrowSchema
+}
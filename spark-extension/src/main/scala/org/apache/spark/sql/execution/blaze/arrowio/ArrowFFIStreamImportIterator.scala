@@ -17,6 +17,7 @@ package org.apache.spark.sql.execution.blaze.arrowio
 
 import org.apache.arrow.c.ArrowArrayStream
 import org.apache.arrow.c.Data
+import org.apache.arrow.vector.VarBinaryVector
 import org.apache.spark.TaskContext
 
 import org.apache.spark.sql.catalyst.InternalRow
@@ -59,6 +60,15 @@ class ArrowFFIStreamImportIterator(
       return false
     }
 
+    if (unsafeRowNumFields.isDefined) {
+      try {
+        currentRows = readUnsafeRows(unsafeRowNumFields.get)
+      } finally {
+        reader.getVectorSchemaRoot.clear()
+      }
+      return hasNext
+    }
+
     val currentBatch = ColumnarHelper.rootAsBatch(reader.getVectorSchemaRoot)
     try {
       // convert batch to persisted row iterator
@@ -84,6 +94,32 @@ class ArrowFFIStreamImportIterator(
     currentRows.next()
   }
 
+  // batches already converted to UnsafeRows by native side carry the number of
+  // fields in metadata of the single binary field
+  private lazy val unsafeRowNumFields: Option[Int] = {
+    val fields = reader.getVectorSchemaRoot.getSchema.getFields
+    if (fields.size() == 1) {
+      Option(fields.get(0).getMetadata.get("blaze.unsafe_row.num_fields")).map(_.toInt)
+    } else {
+      None
+    }
+  }
+
+  private def readUnsafeRows(numFields: Int): Iterator[InternalRow] = {
+    val vector = reader.getVectorSchemaRoot.getVector(0).asInstanceOf[VarBinaryVector]
+    val dataAddress = vector.getDataBuffer.memoryAddress()
+    val rows = new Array[UnsafeRow](vector.getValueCount)
+    var i = 0
+    while (i < rows.length) {
+      val startOffset = vector.getStartOffset(i)
+      val row = new UnsafeRow(numFields)
+      row.pointTo(null, dataAddress + startOffset, vector.getStartOffset(i + 1) - startOffset)
+      rows(i) = row.copy()
+      i += 1
+    }
+    rows.iterator
+  }
+
   private lazy val toUnsafe: UnsafeProjection = {
     val localOutput = ArrowUtils
       .fromArrowSchema(reader.getVectorSchemaRoot.getSchema)
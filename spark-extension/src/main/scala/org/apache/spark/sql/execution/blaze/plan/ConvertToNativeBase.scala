@@ -17,6 +17,8 @@ package org.apache.spark.sql.execution.blaze.plan
 
 import java.util.UUID
 
+import org.apache.arrow.c.ArrowArrayStream
+import org.apache.arrow.c.Data
 import org.apache.spark.InterruptibleIterator
 import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.catalyst.expressions.Attribute
@@ -30,6 +32,8 @@ import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIExportIterator
+import org.apache.spark.sql.execution.blaze.arrowio.ArrowFFIStreamExportReader
+import org.apache.spark.sql.execution.blaze.arrowio.util.ArrowUtils
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.OneToOneDependency
 import org.apache.spark.sql.blaze.BlazeConf
@@ -70,21 +74,42 @@ abstract class ConvertToNativeBase(override val child: SparkPlan)
       (partition, context) => {
         val inputRowIter = inputRDD.compute(partition, context)
         val resourceId = s"ConvertToNativeExec:${UUID.randomUUID().toString}"
-        JniBridge.resourcesMap.put(
-          resourceId,
-          () => {
-            // FIXME:
-            //  arrow-rs batch.get_array_memory_size() cannot work on ffi
-            //  batches. so we use a smaller batch size to force batch coalesce
-            //  in native side
-            val exportIter =
-              new ArrowFFIExportIterator(
-                inputRowIter,
+        val arrowCStream = BlazeConf.enableArrowCStream
+        if (arrowCStream) {
+          JniBridge.resourcesMap.put(
+            resourceId,
+            () => {
+              val reader = new ArrowFFIStreamExportReader(
+                new InterruptibleIterator(context, inputRowIter),
                 renamedSchema,
                 context,
-                recordBatchSize = BlazeConf.batchSize / 4)
-            new InterruptibleIterator(context, exportIter)
-          })
+                recordBatchSize = BlazeConf.batchSize / 4,
+                unsafeRows = BlazeConf.enableNativeRowToColumnar)
+              val stream = ArrowArrayStream.allocateNew(ArrowUtils.rootAllocator)
+              Data.exportArrayStream(ArrowUtils.rootAllocator, reader, stream)
+
+              // the stream is moved into native side and released there, only the
+              // struct itself is freed here
+              context.addTaskCompletionListener[Unit](_ => stream.close())
+              java.lang.Long.valueOf(stream.memoryAddress())
+            })
+        } else {
+          JniBridge.resourcesMap.put(
+            resourceId,
+            () => {
+              // FIXME:
+              //  arrow-rs batch.get_array_memory_size() cannot work on ffi
+              //  batches. so we use a smaller batch size to force batch coalesce
+              //  in native side
+              val exportIter =
+                new ArrowFFIExportIterator(
+                  inputRowIter,
+                  renamedSchema,
+                  context,
+                  recordBatchSize = BlazeConf.batchSize / 4)
+              new InterruptibleIterator(context, exportIter)
+            })
+        }
 
         PhysicalPlanNode
           .newBuilder()
@@ -94,6 +119,7 @@ abstract class ConvertToNativeBase(override val child: SparkPlan)
               .setSchema(nativeSchema)
               .setNumPartitions(numInputPartitions)
               .setExportIterProviderResourceId(resourceId)
+              .setArrowCStream(arrowCStream)
               .build())
           .build()
       },
@@ -19,6 +19,7 @@ import java.io.ByteArrayInputStream
 import java.io.ByteArrayOutputStream
 import java.nio.ByteBuffer
 import java.nio.channels.Channels
+import java.util.Locale
 import java.util.UUID
 import java.util.concurrent.Future
 import java.util.concurrent.TimeoutException
@@ -150,6 +151,8 @@ abstract class NativeBroadcastExchangeBase(mode: BroadcastMode, override val chi
               .setNumPartitions(1)
               .setIpcProviderResourceId(resourceId)
               .setMode(pb.IpcReadMode.CHANNEL)
+              .setCodec(NativeBroadcastExchangeBase.broadcastCompressionCodec)
+              .setChecksum(BlazeConf.enableBroadcastChecksum)
               .build())
           .build()
       },
@@ -191,6 +194,11 @@ abstract class NativeBroadcastExchangeBase(mode: BroadcastMode, override val chi
                 .newBuilder()
                 .setInput(input)
                 .setIpcConsumerResourceId(resourceId)
+                .setCodec(NativeBroadcastExchangeBase.broadcastCompressionCodec)
+                .setCompressionLevel(BlazeConf.broadcastCompressionLevel)
+                .setTargetChunkSize(BlazeConf.ipcWriterTargetChunkSize)
+                .setMaxInflightChunks(BlazeConf.ipcWriterMaxInflightChunks)
+                .setChecksum(BlazeConf.enableBroadcastChecksum)
                 .build())
             .build()
 
@@ -260,6 +268,15 @@ object NativeBroadcastExchangeBase {
 
   def nativeExecutionTag: TreeNodeTag[Boolean] = TreeNodeTag("arrowBroadcastNativeExecution")
 
+  def broadcastCompressionCodec: pb.IpcCompressionCodec =
+    BlazeConf.broadcastCompressionCodec.toLowerCase(Locale.ROOT) match {
+      case "uncompressed" | "none" => pb.IpcCompressionCodec.UNCOMPRESSED
+      case "lz4" => pb.IpcCompressionCodec.LZ4
+      case "zstd" => pb.IpcCompressionCodec.ZSTD
+      case codec =>
+        throw new IllegalArgumentException(s"unsupported broadcast compression codec: $codec")
+    }
+
   def buildBroadcastData(
       collectedData: Array[Array[Byte]],
       keys: Seq[Expression],
@@ -275,6 +292,8 @@ object NativeBroadcastExchangeBase {
       .setSchema(nativeSchema)
       .setIpcProviderResourceId(readerIpcProviderResourceId)
       .setMode(pb.IpcReadMode.CHANNEL)
+      .setCodec(broadcastCompressionCodec)
+      .setChecksum(BlazeConf.enableBroadcastChecksum)
 
     val sortExec = pb.SortExecNode
       .newBuilder()
@@ -300,6 +319,11 @@ object NativeBroadcastExchangeBase {
       .newBuilder()
       .setInput(pb.PhysicalPlanNode.newBuilder().setSort(sortExec))
       .setIpcConsumerResourceId(writerIpcProviderResourceId)
+      .setCodec(broadcastCompressionCodec)
+      .setCompressionLevel(BlazeConf.broadcastCompressionLevel)
+      .setTargetChunkSize(BlazeConf.ipcWriterTargetChunkSize)
+      .setMaxInflightChunks(BlazeConf.ipcWriterMaxInflightChunks)
+      .setChecksum(BlazeConf.enableBroadcastChecksum)
 
     // build native sorter
     val exec = pb.PhysicalPlanNode
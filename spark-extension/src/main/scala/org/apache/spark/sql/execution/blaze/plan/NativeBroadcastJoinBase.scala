@@ -15,16 +15,22 @@
  */
 package org.apache.spark.sql.execution.blaze.plan
 
+import java.util.concurrent.ConcurrentHashMap
+
 import scala.collection.JavaConverters._
 
 import org.apache.spark.OneToOneDependency
 import org.apache.spark.Partition
+import org.apache.spark.SparkEnv
+import org.apache.spark.internal.Logging
 import org.apache.spark.sql.blaze.BlazeConf
+import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.blaze.MetricNode
 import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.expressions.Expression
 import org.apache.spark.sql.catalyst.plans.JoinType
 import org.apache.spark.sql.catalyst.plans.LeftAnti
@@ -33,6 +39,7 @@ import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.BinaryExecNode
+import org.apache.spark.storage.BroadcastBlockId
 import org.blaze.{protobuf => pb}
 
 abstract class NativeBroadcastJoinBase(
@@ -58,7 +65,13 @@ abstract class NativeBroadcastJoinBase(
   override lazy val metrics: Map[String, SQLMetric] = Map(
     NativeHelper
       .getDefaultNativeMetrics(sparkContext)
-      .filterKeys(Set("output_rows", "elapsed_compute"))
+      .filterKeys(Set(
+        "output_rows",
+        "elapsed_compute",
+        "build_time",
+        "probe_time",
+        "spilled_bytes",
+        "spill_count"))
       .toSeq: _*)
 
   private def nativeJoinOn = leftKeys.zip(rightKeys).map { case (leftKey, rightKey) =>
@@ -97,6 +110,17 @@ abstract class NativeBroadcastJoinBase(
     val nativeJoinFilter = this.nativeJoinFilter
     val partitions = rightRDD.partitions
 
+    // broadcasted side is the same in all tasks, so it can be cached in executors
+    val cachedBroadcastId = if (BlazeConf.enableBroadcastCache()) {
+      Shims.get.getUnderlyingBroadcast(left) match {
+        case exec: NativeBroadcastExchangeBase =>
+          Some(exec.doExecuteBroadcastNative[Any]().id)
+        case _ => None
+      }
+    } else {
+      None
+    }
+
     new NativeRDD(
       sparkContext,
       nativeMetrics,
@@ -116,9 +140,40 @@ abstract class NativeBroadcastJoinBase(
           .setJoinType(nativeJoinType)
           .addAllOn(nativeJoinOn.asJava)
 
+        cachedBroadcastId.foreach { broadcastId =>
+          NativeBroadcastJoinBase.registerCachedBroadcast(broadcastId)
+          broadcastJoinExec.setCachedBuildId(
+            NativeBroadcastJoinBase.cachedBuildId(broadcastId))
+        }
         nativeJoinFilter.foreach(joinFilter => broadcastJoinExec.setJoinFilter(joinFilter))
         pb.PhysicalPlanNode.newBuilder().setBroadcastJoin(broadcastJoinExec).build()
       },
       friendlyName = "NativeRDD.BroadcastJoin")
   }
 }
+
+object NativeBroadcastJoinBase extends Logging {
+  // broadcasts with data cached in native side of this executor
+  private val cachedBroadcastIds = ConcurrentHashMap.newKeySet[Long]()
+
+  def cachedBuildId(broadcastId: Long): String = s"NativeBroadcastJoin:$broadcastId"
+
+  /**
+   * Registers a broadcast cached by the current task, and releases cached data of
+   * broadcasts that have been removed from this executor (destroyed, or cleaned up
+   * after their query finished).
+   */
+  def registerCachedBroadcast(broadcastId: Long): Unit = {
+    cachedBroadcastIds.add(broadcastId)
+
+    val blockManager = SparkEnv.get.blockManager
+    for (id <- cachedBroadcastIds.asScala.toSeq if id != broadcastId) {
+      if (blockManager.getStatus(BroadcastBlockId(id)).isEmpty) {
+        if (cachedBroadcastIds.remove(id)) {
+          logInfo(s"releasing native cached data of removed broadcast: $id")
+          JniBridge.releaseCachedBroadcast(cachedBuildId(id))
+        }
+      }
+    }
+  }
+}
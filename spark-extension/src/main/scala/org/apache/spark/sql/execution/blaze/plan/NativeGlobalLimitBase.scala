@@ -28,6 +28,7 @@ import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.OneToOneDependency
 import org.blaze.protobuf.LimitExecNode
+import org.blaze.protobuf.LimitMode
 import org.blaze.protobuf.PhysicalPlanNode
 
 import org.apache.spark.sql.blaze.NativeSupports
@@ -63,6 +64,7 @@ abstract class NativeGlobalLimitBase(limit: Long, override val child: SparkPlan)
           .newBuilder()
           .setInput(inputRDD.nativePlan(inputPartition, taskContext))
           .setLimit(limit)
+          .setMode(LimitMode.GLOBAL)
           .build()
         PhysicalPlanNode.newBuilder().setLimit(nativeLimitExec).build()
       },
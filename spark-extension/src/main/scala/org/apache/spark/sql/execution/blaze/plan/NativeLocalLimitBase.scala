@@ -26,6 +26,7 @@ import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.OneToOneDependency
 import org.blaze.protobuf.LimitExecNode
+import org.blaze.protobuf.LimitMode
 import org.blaze.protobuf.PhysicalPlanNode
 
 import org.apache.spark.sql.blaze.NativeSupports
@@ -60,6 +61,7 @@ abstract class NativeLocalLimitBase(limit: Long, override val child: SparkPlan)
           .newBuilder()
           .setInput(inputRDD.nativePlan(inputPartition, taskContext))
           .setLimit(limit)
+          .setMode(LimitMode.LOCAL)
           .build()
         PhysicalPlanNode.newBuilder().setLimit(nativeLimitExec).build()
       },
@@ -19,12 +19,14 @@ import java.net.URI
 import java.security.PrivilegedExceptionAction
 import java.util.Locale
 import java.util.Properties
+import java.util.concurrent.CopyOnWriteArrayList
 
 import scala.collection.JavaConverters._
 import scala.collection.mutable
 
 import org.apache.hadoop.conf.Configuration
 import org.apache.hadoop.fs.FileSystem
+import org.apache.hadoop.fs.Path
 import org.apache.hadoop.hive.ql.io.parquet.MapredParquetOutputFormat
 import org.apache.hadoop.hive.ql.io.parquet.serde.ParquetHiveSerDe
 import org.apache.hadoop.hive.ql.io.parquet.write.ParquetRecordWriterWrapper
@@ -39,26 +41,37 @@ import org.apache.hadoop.mapred.Reporter
 import org.apache.hadoop.util.Progressable
 import org.apache.parquet.hadoop.ParquetOutputFormat
 import org.apache.spark.Partition
+import org.apache.spark.SPARK_VERSION_SHORT
 import org.apache.spark.TaskContext
+import org.blaze.protobuf.BucketHashFunction
 import org.blaze.protobuf.ParquetProp
+import org.blaze.protobuf.ParquetRebaseMode
+import org.blaze.protobuf.ParquetSinkBucketSpec
 import org.blaze.protobuf.ParquetSinkExecNode
+import org.blaze.protobuf.PhysicalExprNode
 import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.PhysicalSortExprNode
 
 import org.apache.spark.rdd.RDD
 import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeConverters
 import org.apache.spark.sql.blaze.NativeHelper
 import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.Literal
 import org.apache.spark.sql.catalyst.expressions.SortOrder
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.catalog.BucketSpec
 import org.apache.spark.sql.catalyst.catalog.CatalogTable
 import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
+import org.apache.spark.sql.catalyst.util.ArrayData
 import org.apache.spark.sql.execution.SparkPlan
 import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.WriteTaskStats
 import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.blaze.plan.Helper.InputPlanInfo
 import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
@@ -66,6 +79,7 @@ import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.sql.execution.metric.SQLMetrics
 import org.apache.spark.sql.hive.execution.InsertIntoHiveTable
+import org.apache.spark.sql.internal.SQLConf
 import org.apache.spark.util.SerializableConfiguration
 
 abstract class NativeParquetInsertIntoHiveTableBase(
@@ -88,8 +102,6 @@ abstract class NativeParquetInsertIntoHiveTableBase(
   def check(): Unit = {
     val hadoopConf = sparkContext.hadoopConfiguration
     val tblStorage = cmd.table.storage
-    assert(!cmd.partition.values.exists(_.isEmpty), "dynamic partitions not supported")
-
     val outputFormatClassName = tblStorage.outputFormat.getOrElse("").toLowerCase(Locale.ROOT)
     assert(outputFormatClassName.endsWith("mapredparquetoutputformat"), "not parquet format")
 
@@ -118,7 +130,45 @@ abstract class NativeParquetInsertIntoHiveTableBase(
       cmd.overwrite,
       cmd.ifPartitionNotExists,
       cmd.outputColumnNames)
-    DataWritingCommandExec(transformedCmd, PreSinkExec(child, metrics))
+    DataWritingCommandExec(
+      transformedCmd,
+      PreSinkExec(child, dynamicPartitionColumns, nativeBucketSpec, metrics))
+  }
+
+  // dynamic partition columns are the trailing columns of the child output
+  @transient
+  lazy val dynamicPartitionColumns: Seq[String] =
+    cmd.table.partitionColumnNames.filter(col => cmd.partition.get(col).forall(_.isEmpty))
+
+  // bucketing of the output table, only enabled in spark versions which write
+  // hive-compatible bucketed tables
+  protected def hiveBucketSpec: Option[BucketSpec] = None
+
+  @transient
+  lazy val nativeBucketSpec: Option[ParquetSinkBucketSpec] = hiveBucketSpec.map { bucketSpec =>
+    val resolver = conf.resolver
+    val outputAttrs = cmd.outputColumnNames.zip(child.output)
+    def attr(name: String): Attribute = outputAttrs.find(a => resolver(a._1, name)).get._2
+
+    ParquetSinkBucketSpec
+      .newBuilder()
+      .addAllBucketExpr(
+        bucketSpec.bucketColumnNames.map(name => NativeConverters.convertExpr(attr(name))).asJava)
+      .setNumBuckets(bucketSpec.numBuckets)
+      .setHashFunction(BucketHashFunction.HIVE_HASH)
+      .addAllSortExpr(bucketSpec.sortColumnNames.map { name =>
+        PhysicalExprNode
+          .newBuilder()
+          .setSort(
+            PhysicalSortExprNode
+              .newBuilder()
+              .setExpr(NativeConverters.convertExpr(attr(name)))
+              .setAsc(true)
+              .setNullsFirst(true)
+              .build())
+          .build()
+      }.asJava)
+      .build()
   }
 
   override def output: Seq[Attribute] = wrapped.output
@@ -140,6 +190,8 @@ abstract class NativeParquetInsertIntoHiveTableBase(
 
 case class PreSinkExec(
     override val child: SparkPlan,
+    dynamicPartitionColumns: Seq[String],
+    bucketSpec: Option[ParquetSinkBucketSpec],
     override val metrics: Map[String, SQLMetric])
     extends UnaryExecNode {
 
@@ -157,9 +209,23 @@ case class PreSinkExec(
         val inputPlanResourceId = Helper.getTaskResourceId("inputPlan")
         val inputPartition = inputRDD.partitions(split.index)
         val inputPlan = inputRDD.nativePlan(inputPartition, context)
-        val inputPlanInfo = InputPlanInfo(inputPlan, inputRDD.metrics, metrics, split, context)
+        val inputPlanInfo = InputPlanInfo(
+          inputPlan,
+          inputRDD.metrics,
+          metrics,
+          split,
+          context,
+          dynamicPartitionColumns,
+          bucketSpec)
         JniBridge.resourcesMap.put(inputPlanResourceId, inputPlanInfo)
-        Iterator.single(InternalRow())
+
+        if (dynamicPartitionColumns.isEmpty) {
+          Iterator.single(InternalRow())
+        } else {
+          // with dynamic partitions, spark creates a record writer for the partition of
+          // this dummy row. the real partitions are written by the native sink
+          Iterator.single(InternalRow.fromSeq(output.map(a => Literal.default(a.dataType).value)))
+        }
       }
     }
   }
@@ -263,12 +329,61 @@ class BlazeParquetRecordWriter(
       })
       .asJava
 
+    // rebase dates/timestamps and write spark metadata into file footers like spark
+    val sqlConf = SQLConf.get
+    val rebaseModeConf = "parquet.datetimeRebaseModeInWrite"
+    val rebaseMode = ParquetRebaseMode.valueOf(
+      sqlConf
+        .getConfString(
+          s"spark.sql.$rebaseModeConf",
+          sqlConf.getConfString(s"spark.sql.legacy.$rebaseModeConf", "EXCEPTION"))
+        .toUpperCase(Locale.ROOT))
+    val keyValueMetadata =
+      mutable.LinkedHashMap("org.apache.spark.version" -> SPARK_VERSION_SHORT)
+    if (rebaseMode == ParquetRebaseMode.LEGACY) {
+      keyValueMetadata += "org.apache.spark.legacyDateTime" -> ""
+    }
+
+    // with dynamic partitions, outputPath is located in the directory of the dummy
+    // partition, files of real partitions are written to {baseDir}/{partition}/{fileName}.
+    // with buckets, the bucket id of the dummy row is also included in the file name
+    val numDynParts = inputPlanInfo.dynamicPartitionColumns.length
+    val bucketed = inputPlanInfo.bucketSpec.isDefined
+    val dummyFilePath = new Path(outputPath)
+    val baseDir =
+      (0 until numDynParts).foldLeft(dummyFilePath.getParent)((dir, _) => dir.getParent)
+    val fileName =
+      if (bucketed) dummyFilePath.getName.replaceFirst("^\\d+_0_", "") else dummyFilePath.getName
+    val sinkPath =
+      if (numDynParts > 0 || bucketed) new Path(baseDir, fileName).toString else outputPath
+
+    // the dummy file is created by the underlying parquet writer and may not be a real
+    // output file, remove it before native sink writes the real ones
+    val fs = dummyFilePath.getFileSystem(job)
+    if (numDynParts > 0 || bucketed) {
+      fs.delete(dummyFilePath, false)
+    }
+
+    // collect written partitions reported by native sink
+    val writtenPartitions = mutable.LinkedHashSet[String]()
+    val partitionConsumerResourceId = Helper.getTaskResourceId("partitionConsumer")
+    JniBridge.resourcesMap.put(
+      partitionConsumerResourceId,
+      (partition: String) => writtenPartitions.synchronized(writtenPartitions += partition))
+
     val parquetSink = ParquetSinkExecNode
       .newBuilder()
       .setInput(inputPlanInfo.inputPlan)
-      .setPath(outputPath)
+      .setPath(sinkPath)
       .addAllProp(props)
       .setFsResourceId(fsResourceId)
+      .addAllDynPartCol(inputPlanInfo.dynamicPartitionColumns.asJava)
+      .setPartitionConsumerResourceId(partitionConsumerResourceId)
+      .setDatetimeRebaseMode(rebaseMode)
+      .addAllKeyValueMetadata(keyValueMetadata.map { case (key, value) =>
+        ParquetProp.newBuilder().setKey(key).setValue(value).build()
+      }.asJava)
+    inputPlanInfo.bucketSpec.foreach(parquetSink.setBucketSpec)
     val plan = PhysicalPlanNode.newBuilder().setParquetSink(parquetSink).build()
     val executed = NativeHelper.executeNativePlan(
       plan,
@@ -283,15 +398,30 @@ class BlazeParquetRecordWriter(
       inputPlanInfo.partition,
       Some(TaskContext.get))
 
-    assert(executed.isEmpty) // native parquet sink always outputs no records
+    // native parquet sink outputs statistics of each written file
+    val fileStats = executed.map(Helper.ParquetSinkFileStats.fromRow).toList
+
+    // remove directories of the dummy partition if not written by native sink
+    var dir = dummyFilePath.getParent
+    while (dir != baseDir && fs.listStatus(dir).isEmpty) {
+      fs.delete(dir, false)
+      dir = dir.getParent
+    }
 
     // collect WriteTaskStats
+    val numPartitions = if (numDynParts > 0) writtenPartitions.size else 1
+    val numRows = fileStats.map(_.numRows).sum
+    val numBytes = fileStats.map(_.numBytes).sum
+    outputMetrics.setRecordsWritten(numRows)
+    outputMetrics.setBytesWritten(numBytes)
     val taskStats = Shims.get.createBasicWriteTaskStats(
       Map(
-        "numPartitions" -> 1,
-        "numFiles" -> 1,
-        "numBytes" -> outputMetrics.bytesWritten,
-        "numRows" -> outputMetrics.recordsWritten))
+        "partitions" -> writtenPartitions.toSeq,
+        "numPartitions" -> numPartitions,
+        "numFiles" -> fileStats.length,
+        "numBytes" -> numBytes,
+        "numRows" -> numRows,
+        "files" -> fileStats))
     JniBridge.resourcesMap.put(getTaskResourceId("taskStats"), taskStats)
   }
 }
@@ -302,7 +432,67 @@ object Helper {
       inputMetricNode: MetricNode,
       metrics: Map[String, SQLMetric],
       partition: Partition,
-      taskContext: TaskContext)
+      taskContext: TaskContext,
+      dynamicPartitionColumns: Seq[String],
+      bucketSpec: Option[ParquetSinkBucketSpec])
+
+  // statistics of a file written by native parquet sink, min/max values are ordered
+  // as the table columns and are None if not available. null counts and ndvs form the
+  // file-level data-skipping index, which are empty if not collected (see
+  // BlazeConf.parquetSinkFileIndexEnabled). the driver receives them through
+  // addFileStatsListener() when the write job is committed
+  case class ParquetSinkFileStats(
+      path: String,
+      partition: String,
+      numRows: Long,
+      numBytes: Long,
+      minValues: Seq[Option[String]],
+      maxValues: Seq[Option[String]],
+      nullCounts: Seq[Long],
+      ndvs: Seq[Long])
+
+  object ParquetSinkFileStats {
+    def fromRow(row: InternalRow): ParquetSinkFileStats = {
+      def stringValues(array: ArrayData): Seq[Option[String]] = {
+        (0 until array.numElements()).map { i =>
+          if (array.isNullAt(i)) None else Some(array.getUTF8String(i).toString)
+        }
+      }
+      ParquetSinkFileStats(
+        row.getUTF8String(0).toString,
+        row.getUTF8String(1).toString,
+        row.getLong(2),
+        row.getLong(3),
+        stringValues(row.getArray(4)),
+        stringValues(row.getArray(5)),
+        row.getArray(6).toLongArray(),
+        row.getArray(7).toLongArray())
+    }
+  }
+
+  // WriteTaskStats created by the shims for native parquet sink, carrying the file
+  // statistics to the driver within the task commit message
+  trait ParquetSinkWriteTaskStats {
+    def files: Seq[ParquetSinkFileStats]
+  }
+
+  // listeners receiving statistics of all files written by native parquet sink,
+  // called on the driver when the write job is committed
+  private val fileStatsListeners = new CopyOnWriteArrayList[Seq[ParquetSinkFileStats] => Unit]()
+
+  def addFileStatsListener(listener: Seq[ParquetSinkFileStats] => Unit): Unit =
+    fileStatsListeners.add(listener)
+
+  def removeFileStatsListener(listener: Seq[ParquetSinkFileStats] => Unit): Unit =
+    fileStatsListeners.remove(listener)
+
+  def processFileStats(stats: Seq[WriteTaskStats]): Unit = {
+    val files = stats.flatMap {
+      case s: ParquetSinkWriteTaskStats => s.files
+      case _ => Nil
+    }
+    fileStatsListeners.asScala.foreach(_(files))
+  }
 
   def getTaskResourceId(name: String): String = {
     val taskContext = TaskContext.get()
@@ -17,6 +17,7 @@ package org.apache.spark.sql.execution.blaze.plan
 
 import java.net.URI
 import java.security.PrivilegedExceptionAction
+import java.util.Locale
 import java.util.UUID
 
 import scala.collection.JavaConverters._
@@ -27,6 +28,7 @@ import org.apache.spark.Partition
 import org.apache.spark.TaskContext
 import org.blaze.{protobuf => pb}
 import org.apache.spark.rdd.MapPartitionsRDD
+import org.apache.spark.sql.blaze.BlazeConf
 import org.apache.spark.sql.blaze.JniBridge
 import org.apache.spark.sql.blaze.MetricNode
 import org.apache.spark.sql.blaze.NativeConverters
@@ -35,6 +37,7 @@ import org.apache.spark.sql.blaze.NativeRDD
 import org.apache.spark.sql.blaze.NativeSupports
 import org.apache.spark.sql.blaze.Shims
 import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.plans.physical.HashPartitioning
 import org.apache.spark.sql.catalyst.plans.physical.Partitioning
 import org.apache.spark.sql.execution.FileSourceScanExec
 import org.apache.spark.sql.execution.LeafExecNode
@@ -63,6 +66,10 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
           .createMetric(sparkContext, "Native.predicate_evaluation_errors")) :+
         ("row_groups_pruned", SQLMetrics
           .createMetric(sparkContext, "Native.row_groups_pruned")) :+
+        ("page_index_rows_filtered", SQLMetrics
+          .createMetric(sparkContext, "Native.page_index_rows_pruned")) :+
+        ("pushdown_rows_filtered", SQLMetrics
+          .createMetric(sparkContext, "Native.pushdown_rows_pruned")) :+
         ("bytes_scanned", SQLMetrics.createSizeMetric(sparkContext, "Native.bytes_scanned")) :+
         ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time")) :+
         ("io_time_getfs", SQLMetrics
@@ -90,18 +97,70 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
   private def nativePruningPredicateFilters = basedFileScan.dataFilters
     .map(expr => NativeConverters.convertScanPruningExpr(expr))
 
+  // nested fields of required columns may be pruned in the required schema, the
+  // pruned types are used so that only required nested fields are read
   private def nativeFileSchema =
-    NativeConverters.convertSchema(StructType(basedFileScan.relation.dataSchema.map {
-      case field if basedFileScan.requiredSchema.exists(_.name == field.name) =>
-        field.copy(nullable = true)
-      case field =>
-        // avoid converting unsupported type in non-used fields
-        StructField(field.name, NullType, nullable = true)
+    NativeConverters.convertSchema(StructType(basedFileScan.relation.dataSchema.map { field =>
+      basedFileScan.requiredSchema.find(_.name == field.name) match {
+        case Some(requiredField) =>
+          field.copy(dataType = requiredField.dataType, nullable = true)
+        case None =>
+          // avoid converting unsupported type in non-used fields
+          StructField(field.name, NullType, nullable = true)
+      }
     }))
 
   private def nativePartitionSchema =
     NativeConverters.convertSchema(partitionSchema)
 
+  // bucketed scans read one bucket in each partition, so the output is already
+  // hash partitioned by bucket columns
+  private def nativeBucketPartitioning = outputPartitioning match {
+    case HashPartitioning(expressions, numPartitions) if basedFileScan.bucketedScan =>
+      Some(
+        pb.PhysicalHashRepartition
+          .newBuilder()
+          .setPartitionCount(numPartitions)
+          .addAllHashExpr(
+            expressions.map(expr => NativeConverters.convertScanPruningExpr(expr)).asJava)
+          .build())
+    case _ => None
+  }
+
+  // field ids of file schema columns, -1 if absent
+  private def nativeFieldIds = basedFileScan.relation.dataSchema.map { field =>
+    val fieldIdMetadataKey = "parquet.field.id"
+    if (field.metadata.contains(fieldIdMetadataKey)) {
+      field.metadata.getLong(fieldIdMetadataKey).toInt
+    } else {
+      -1
+    }
+  }
+
+  // match file columns like spark: by field ids if enabled, otherwise by names (case
+  // insensitive if spark.sql.caseSensitive=false)
+  private def nativeSchemaMatchMode = {
+    val sqlConf = Shims.get.getSqlContext(basedFileScan).sparkSession.sessionState.conf
+    val fieldIdReadEnabled =
+      sqlConf.getConfString("spark.sql.parquet.fieldId.read.enabled", "false").toBoolean
+    if (fieldIdReadEnabled && nativeFieldIds.exists(_ >= 0)) {
+      pb.ParquetSchemaMatchMode.FIELD_ID
+    } else if (!sqlConf.caseSensitiveAnalysis) {
+      pb.ParquetSchemaMatchMode.CASE_INSENSITIVE_NAME
+    } else {
+      pb.ParquetSchemaMatchMode.NAME
+    }
+  }
+
+  // rebase modes of dates/timestamps in files not written by spark 3.x, configs are
+  // named spark.sql.legacy.parquet.* in older spark versions
+  private def nativeRebaseMode(name: String) = {
+    val sqlConf = Shims.get.getSqlContext(basedFileScan).sparkSession.sessionState.conf
+    val legacyMode = sqlConf.getConfString(s"spark.sql.legacy.parquet.$name", "EXCEPTION")
+    val mode = sqlConf.getConfString(s"spark.sql.parquet.$name", legacyMode)
+    pb.ParquetRebaseMode.valueOf(mode.toUpperCase(Locale.ROOT))
+  }
+
   private def nativeFileGroups = (partition: FilePartition) => {
     // list input file statuses
     val nativePartitionedFile = (file: PartitionedFile) => {
@@ -115,7 +174,7 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
         .setPath(file.filePath)
         .setSize(fileSizes(file.filePath))
         .addAllPartitionValues(nativePartitionValues.asJava)
-        .setLastModifiedNs(0)
+        .setLastModifiedNs(Shims.get.getPartitionedFileModificationTime(file) * 1000000L)
         .setRange(
           pb.FileRange
             .newBuilder()
@@ -154,6 +213,12 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
     val nativeFileSchema = this.nativeFileSchema
     val nativeFileGroups = this.nativeFileGroups
     val nativePartitionSchema = this.nativePartitionSchema
+    val nativeFieldIds = this.nativeFieldIds
+    val nativeSchemaMatchMode = this.nativeSchemaMatchMode
+    val nativeDatetimeRebaseMode = nativeRebaseMode("datetimeRebaseModeInRead")
+    val nativeInt96RebaseMode = nativeRebaseMode("int96RebaseModeInRead")
+    val dictionaryPartitionColumns = BlazeConf.parquetDictionaryPartitionColumns()
+    val nativeBucketPartitioning = this.nativeBucketPartitioning
 
     val projection = schema.map(field => basedFileScan.relation.schema.fieldIndex(field.name))
     val sparkSession = Shims.get.getSqlContext(basedFileScan).sparkSession
@@ -203,6 +268,12 @@ abstract class NativeParquetScanBase(basedFileScan: FileSourceScanExec)
           .setBaseConf(nativeParquetScanConf)
           .setFsResourceId(resourceId)
           .addAllPruningPredicates(nativePruningPredicateFilters.asJava)
+          .setSchemaMatchMode(nativeSchemaMatchMode)
+          .addAllFieldIds(nativeFieldIds.map(Integer.valueOf).asJava)
+          .setDatetimeRebaseMode(nativeDatetimeRebaseMode)
+          .setInt96RebaseMode(nativeInt96RebaseMode)
+          .setDictionaryPartitionColumns(dictionaryPartitionColumns)
+        nativeBucketPartitioning.foreach(nativeParquetScanExecBuilder.setBucketPartitioning)
 
         pb.PhysicalPlanNode
           .newBuilder()
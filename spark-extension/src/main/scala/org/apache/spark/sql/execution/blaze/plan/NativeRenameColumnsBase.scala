@@ -28,6 +28,7 @@ import org.apache.spark.sql.execution.UnaryExecNode
 import org.apache.spark.sql.execution.blaze.plan.NativeRenameColumnsBase.buildRenameColumnsExec
 import org.apache.spark.sql.execution.metric.SQLMetric
 import org.apache.spark.OneToOneDependency
+import org.blaze.protobuf.NestedFieldRename
 import org.blaze.protobuf.PhysicalPlanNode
 import org.blaze.protobuf.RenameColumnsExecNode
 
@@ -74,16 +75,28 @@ abstract class NativeRenameColumnsBase(
 }
 
 object NativeRenameColumnsBase {
+  /**
+   * nestedRenames: (column index, path of struct field names, new field name)
+   */
   def buildRenameColumnsExec(
       input: PhysicalPlanNode,
-      newColumnNames: Seq[String]): PhysicalPlanNode = {
+      newColumnNames: Seq[String],
+      nestedRenames: Seq[(Int, Seq[String], String)] = Nil): PhysicalPlanNode = {
     PhysicalPlanNode
       .newBuilder()
       .setRenameColumns(
         RenameColumnsExecNode
           .newBuilder()
           .setInput(input)
-          .addAllRenamedColumnNames(newColumnNames.asJava))
+          .addAllRenamedColumnNames(newColumnNames.asJava)
+          .addAllNestedRenames(nestedRenames.map { case (columnIndex, fieldPath, newName) =>
+            NestedFieldRename
+              .newBuilder()
+              .setColumnIndex(columnIndex)
+              .addAllFieldPath(fieldPath.asJava)
+              .setNewName(newName)
+              .build()
+          }.asJava))
       .build()
   }
 }
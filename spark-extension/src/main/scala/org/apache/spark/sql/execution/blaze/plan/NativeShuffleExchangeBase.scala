@@ -179,6 +179,8 @@ abstract class NativeShuffleExchangeBase(
           val shuffleWriteMetrics = TaskContext.get.taskMetrics().shuffleWriteMetrics
           new SQLShuffleWriteMetricsReporter(shuffleWriteMetrics, metrics).incWriteTime(v)
         case ("spilled_bytes", v) => metrics("spilled_bytes").add(v)
+        case ("spill_count", v) => metrics("spill_count").add(v)
+        case ("mem_peak_used", v) => metrics("mem_peak_used").add(v)
         case _ =>
       }))
     val nativeHashExprs = this.nativeHashExprs
@@ -54,6 +54,8 @@ abstract class NativeSortBase(
         "output_rows",
         "elapsed_compute",
         "spilled_bytes",
+        "spill_count",
+        "mem_peak_used",
         "input_batch_count",
         "input_batch_mem_size_total",
         "input_batch_mem_size_avg",
@@ -0,0 +1,391 @@
+/*
+ * Copyright 2022 The Blaze Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.spark.sql.execution.blaze.plan
+
+import java.net.URI
+import java.security.PrivilegedExceptionAction
+import java.util.Locale
+
+import scala.collection.JavaConverters._
+import scala.collection.mutable
+
+import org.apache.hadoop.conf.Configuration
+import org.apache.hadoop.fs.FileSystem
+import org.apache.hadoop.fs.FileStatus
+import org.apache.hadoop.fs.Path
+import org.apache.hadoop.mapreduce.Job
+import org.apache.hadoop.mapreduce.TaskAttemptContext
+import org.apache.spark.TaskContext
+import org.blaze.protobuf.CsvWriteOptions
+import org.blaze.protobuf.JsonWriteOptions
+import org.blaze.protobuf.PhysicalPlanNode
+import org.blaze.protobuf.RenameColumnsExecNode
+import org.blaze.protobuf.TextSinkExecNode
+
+import org.apache.spark.rdd.RDD
+import org.apache.spark.sql.SaveMode
+import org.apache.spark.sql.SparkSession
+import org.apache.spark.sql.blaze.JniBridge
+import org.apache.spark.sql.blaze.MetricNode
+import org.apache.spark.sql.blaze.NativeHelper
+import org.apache.spark.sql.blaze.NativeRDD
+import org.apache.spark.sql.blaze.NativeSupports
+import org.apache.spark.sql.blaze.Shims
+import org.apache.spark.sql.catalyst.InternalRow
+import org.apache.spark.sql.catalyst.catalog.BucketSpec
+import org.apache.spark.sql.catalyst.catalog.CatalogTable
+import org.apache.spark.sql.catalyst.expressions.Attribute
+import org.apache.spark.sql.catalyst.expressions.SortOrder
+import org.apache.spark.sql.catalyst.plans.logical.LogicalPlan
+import org.apache.spark.sql.catalyst.plans.physical.Partitioning
+import org.apache.spark.sql.catalyst.util.CaseInsensitiveMap
+import org.apache.spark.sql.execution.SparkPlan
+import org.apache.spark.sql.execution.UnaryExecNode
+import org.apache.spark.sql.execution.blaze.plan.Helper.InputPlanInfo
+import org.apache.spark.sql.execution.blaze.plan.Helper.getTaskResourceId
+import org.apache.spark.sql.execution.command.DataWritingCommandExec
+import org.apache.spark.sql.execution.datasources.BasicWriteJobStatsTracker
+import org.apache.spark.sql.execution.datasources.FileFormat
+import org.apache.spark.sql.execution.datasources.FileIndex
+import org.apache.spark.sql.execution.datasources.InsertIntoHadoopFsRelationCommand
+import org.apache.spark.sql.execution.datasources.OutputWriter
+import org.apache.spark.sql.execution.datasources.OutputWriterFactory
+import org.apache.spark.sql.execution.datasources.csv.CSVFileFormat
+import org.apache.spark.sql.execution.datasources.json.JsonFileFormat
+import org.apache.spark.sql.execution.metric.SQLMetric
+import org.apache.spark.sql.execution.metric.SQLMetrics
+import org.apache.spark.sql.internal.SQLConf
+import org.apache.spark.sql.types._
+import org.apache.spark.util.SerializableConfiguration
+
+abstract class NativeTextInsertIntoHadoopFsRelationBase(
+    @transient cmd: InsertIntoHadoopFsRelationCommand,
+    override val child: SparkPlan)
+    extends UnaryExecNode
+    with NativeSupports {
+
+  override lazy val metrics: Map[String, SQLMetric] = mutable
+    .LinkedHashMap(
+      NativeHelper
+        .getDefaultNativeMetrics(sparkContext)
+        .filterKeys(Set("output_rows", "elapsed_compute"))
+        .toSeq
+        :+ ("io_time", SQLMetrics.createNanoTimingMetric(sparkContext, "Native.io_time"))
+        :+ ("bytes_written", SQLMetrics
+          .createSizeMetric(sparkContext, "Native.bytes_written")): _*)
+    .toMap
+
+  @transient
+  lazy val textSinkTemplate: TextSinkExecNode =
+    NativeTextInsertIntoHadoopFsRelationBase.buildTextSinkTemplate(
+      cmd.fileFormat,
+      cmd.options,
+      child.output.map(_.dataType),
+      conf)
+
+  @transient
+  val wrapped: DataWritingCommandExec = {
+    assert(cmd.partitionColumns.isEmpty, "not supported writing partitioned relation")
+    assert(cmd.bucketSpec.isEmpty, "not supported writing bucketed relation")
+
+    val transformedCmd = new BlazeInsertIntoHadoopFsRelationCommand(
+      cmd.outputPath,
+      cmd.staticPartitions,
+      cmd.ifPartitionNotExists,
+      cmd.partitionColumns,
+      cmd.bucketSpec,
+      new BlazeTextFileFormat(textSinkTemplate, cmd.outputColumnNames, extension),
+      cmd.options,
+      cmd.query,
+      cmd.mode,
+      cmd.catalogTable,
+      cmd.fileIndex,
+      cmd.outputColumnNames)
+    DataWritingCommandExec(transformedCmd, PreSinkExec(child, Nil, None, metrics))
+  }
+
+  private def extension: String = cmd.fileFormat match {
+    case _: CSVFileFormat => ".csv"
+    case _: JsonFileFormat => ".json"
+  }
+
+  override def output: Seq[Attribute] = wrapped.output
+  override def outputPartitioning: Partitioning = wrapped.outputPartitioning
+  override def outputOrdering: Seq[SortOrder] = wrapped.outputOrdering
+  override def doExecute(): RDD[InternalRow] = wrapped.execute()
+
+  override def executeCollect(): Array[InternalRow] = wrapped.executeCollect()
+  override def executeTake(n: Int): Array[InternalRow] = wrapped.executeTake(n)
+  override def executeToIterator(): Iterator[InternalRow] = wrapped.executeToIterator()
+
+  override def doExecuteNative(): NativeRDD = {
+    throw new RuntimeException("DataWritingCommandExec.doExecuteNative should not be called")
+  }
+
+  override def nodeName: String =
+    s"NativeTextInsert${extension.stripPrefix(".").toUpperCase(Locale.ROOT)} ${cmd.outputPath}"
+}
+
+object NativeTextInsertIntoHadoopFsRelationBase {
+  private val supportedPatternLetters = "yudHhmsaEMLSXxZ".toSet
+
+  // builds the format options of native text sink, options not supported by native
+  // text sink are rejected and the writing falls back to spark
+  def buildTextSinkTemplate(
+      fileFormat: FileFormat,
+      options: Map[String, String],
+      dataTypes: Seq[DataType],
+      sqlConf: SQLConf): TextSinkExecNode = {
+    val params = CaseInsensitiveMap(options)
+
+    params.get("compression").orElse(params.get("codec")).foreach { codec =>
+      assert(
+        Seq("none", "uncompressed").contains(codec.toLowerCase(Locale.ROOT)),
+        s"not supported compression: $codec")
+    }
+    params.get("encoding").orElse(params.get("charset")).foreach { charset =>
+      assert(
+        Seq("utf-8", "utf8").contains(charset.toLowerCase(Locale.ROOT)),
+        s"not supported encoding: $charset")
+    }
+
+    val dateFormat = params.getOrElse("dateFormat", "yyyy-MM-dd")
+    val timestampFormat = params.getOrElse("timestampFormat", "yyyy-MM-dd'T'HH:mm:ss.SSSXXX")
+    assert(isSupportedDatetimePattern(dateFormat), s"not supported dateFormat: $dateFormat")
+    assert(
+      isSupportedDatetimePattern(timestampFormat),
+      s"not supported timestampFormat: $timestampFormat")
+
+    val builder = TextSinkExecNode
+      .newBuilder()
+      .setLineSep(params.getOrElse("lineSep", "\n"))
+      .setDateFormat(dateFormat)
+      .setTimestampFormat(timestampFormat)
+      .setTimezone(params.getOrElse("timeZone", sqlConf.sessionLocalTimeZone))
+
+    fileFormat match {
+      case _: CSVFileFormat =>
+        dataTypes.foreach(dt => assert(isSupportedCsvType(dt), s"not supported type: $dt"))
+        assert(!params.contains("charToEscapeQuoteEscaping"), "not supported option")
+
+        def bool(key: String, default: Boolean): Boolean =
+          params.get(key).map(_.toBoolean).getOrElse(default)
+        builder.setCsv(
+          CsvWriteOptions
+            .newBuilder()
+            .setDelimiter(toDelimiterStr(params.get("sep").orElse(params.get("delimiter"))))
+            .setQuote(params.getOrElse("quote", "\""))
+            .setEscape(params.getOrElse("escape", "\\"))
+            .setQuoteAll(bool("quoteAll", default = false))
+            .setEscapeQuotes(bool("escapeQuotes", default = true))
+            .setHeader(bool("header", default = false))
+            .setNullValue(params.getOrElse("nullValue", ""))
+            .setEmptyValue(params.getOrElse("emptyValue", "\"\""))
+            .setIgnoreLeadingWhiteSpace(bool("ignoreLeadingWhiteSpace", default = true))
+            .setIgnoreTrailingWhiteSpace(bool("ignoreTrailingWhiteSpace", default = true)))
+
+      case _: JsonFileFormat =>
+        dataTypes.foreach(dt => assert(isSupportedJsonType(dt), s"not supported type: $dt"))
+        val ignoreNullFields = params
+          .get("ignoreNullFields")
+          .getOrElse(sqlConf.getConfString("spark.sql.jsonGenerator.ignoreNullFields", "true"))
+          .toBoolean
+        builder.setJson(JsonWriteOptions.newBuilder().setIgnoreNullFields(ignoreNullFields))
+
+      case other =>
+        throw new NotImplementedError(s"not supported file format: $other")
+    }
+    builder.build()
+  }
+
+  // same as CSVExprUtils.toDelimiterStr, only common escaped delimiters are supported
+  private def toDelimiterStr(delimiter: Option[String]): String = delimiter match {
+    case None => ","
+    case Some("\\t") => "\t"
+    case Some(s) if s.startsWith("\\") && s != "\\\\" =>
+      throw new NotImplementedError(s"not supported delimiter: $s")
+    case Some("\\\\") => "\\"
+    case Some(s) => s
+  }
+
+  private def isSupportedDatetimePattern(pattern: String): Boolean = {
+    // literals in quotes are removed before checking pattern letters
+    val unquoted = pattern.split("'", -1).zipWithIndex.filter(_._2 % 2 == 0).map(_._1).mkString
+    unquoted.filter(_.isLetter).forall(supportedPatternLetters.contains)
+  }
+
+  private def isSupportedCsvType(dataType: DataType): Boolean = dataType match {
+    case BooleanType | ByteType | ShortType | IntegerType | LongType => true
+    case FloatType | DoubleType | StringType | DateType | TimestampType => true
+    case _: DecimalType => true
+    case _ => false
+  }
+
+  private def isSupportedJsonType(dataType: DataType): Boolean = dataType match {
+    case BinaryType => true
+    case ArrayType(elementType, _) => isSupportedJsonType(elementType)
+    case MapType(keyType, valueType, _) =>
+      isSupportedCsvType(keyType) && isSupportedJsonType(valueType)
+    case StructType(fields) => fields.forall(field => isSupportedJsonType(field.dataType))
+    case dt => isSupportedCsvType(dt)
+  }
+}
+
+// extend InsertIntoHadoopFsRelationCommand with customized StatsTracker
+class BlazeInsertIntoHadoopFsRelationCommand(
+    outputPath: Path,
+    staticPartitions: Map[String, String],
+    ifPartitionNotExists: Boolean,
+    partitionColumns: Seq[Attribute],
+    bucketSpec: Option[BucketSpec],
+    fileFormat: FileFormat,
+    options: Map[String, String],
+    query: LogicalPlan,
+    mode: SaveMode,
+    catalogTable: Option[CatalogTable],
+    fileIndex: Option[FileIndex],
+    outputColumnNames: Seq[String])
+    extends InsertIntoHadoopFsRelationCommand(
+      outputPath,
+      staticPartitions,
+      ifPartitionNotExists,
+      partitionColumns,
+      bucketSpec,
+      fileFormat,
+      options,
+      query,
+      mode,
+      catalogTable,
+      fileIndex,
+      outputColumnNames) {
+  override def basicWriteJobStatsTracker(hadoopConf: Configuration): BasicWriteJobStatsTracker = {
+    val serializableHadoopConf = new SerializableConfiguration(hadoopConf)
+    Shims.get.createBasicWriteJobStatsTrackerForNativeParquetSink(serializableHadoopConf, metrics)
+  }
+}
+
+// a file format which writes nothing through spark, the real output file is written
+// by native text sink in BlazeTextOutputWriter.close()
+class BlazeTextFileFormat(
+    textSinkTemplate: TextSinkExecNode,
+    outputColumnNames: Seq[String],
+    extension: String)
+    extends FileFormat {
+
+  override def inferSchema(
+      sparkSession: SparkSession,
+      options: Map[String, String],
+      files: Seq[FileStatus]): Option[StructType] = None
+
+  override def prepareWrite(
+      sparkSession: SparkSession,
+      job: Job,
+      options: Map[String, String],
+      dataSchema: StructType): OutputWriterFactory = {
+    val template = textSinkTemplate
+    val columnNames = outputColumnNames
+    val ext = extension
+    new OutputWriterFactory {
+      override def getFileExtension(context: TaskAttemptContext): String = ext
+
+      override def newInstance(
+          path: String,
+          dataSchema: StructType,
+          context: TaskAttemptContext): OutputWriter = {
+        new BlazeTextOutputWriter(path, template, columnNames, context.getConfiguration)
+      }
+    }
+  }
+
+  override def toString: String = s"BlazeText(${extension.stripPrefix(".")})"
+}
+
+class BlazeTextOutputWriter(
+    outputPath: String,
+    textSinkTemplate: TextSinkExecNode,
+    outputColumnNames: Seq[String],
+    hadoopConf: Configuration)
+    extends OutputWriter {
+
+  // not declared as override, OutputWriter.path is only available in newer spark versions
+  def path: String = outputPath
+
+  override def write(row: InternalRow): Unit = {
+    // nothing to write
+  }
+
+  override def close(): Unit = {
+    val inputPlanResourceId = Helper.getTaskResourceId("inputPlan")
+    val inputPlanInfo = JniBridge.getResource(inputPlanResourceId).asInstanceOf[InputPlanInfo]
+    val outputMetrics = TaskContext.get().taskMetrics().outputMetrics
+
+    // init hadoop fs
+    val fsResourceId = Helper.getTaskResourceId("fs")
+    JniBridge.resourcesMap.put(
+      fsResourceId,
+      (location: String) => {
+        NativeHelper.currentUser.doAs(new PrivilegedExceptionAction[FileSystem] {
+          override def run(): FileSystem = FileSystem.get(new URI(location), hadoopConf)
+        })
+      })
+
+    // native column names may differ from the output columns, which are written
+    // into csv headers and json field names
+    val renamedInput = PhysicalPlanNode
+      .newBuilder()
+      .setRenameColumns(
+        RenameColumnsExecNode
+          .newBuilder()
+          .setInput(inputPlanInfo.inputPlan)
+          .addAllRenamedColumnNames(outputColumnNames.asJava))
+      .build()
+    val textSink = textSinkTemplate.toBuilder
+      .setInput(renamedInput)
+      .setPath(outputPath)
+      .setFsResourceId(fsResourceId)
+    val plan = PhysicalPlanNode.newBuilder().setTextSink(textSink).build()
+    val executed = NativeHelper.executeNativePlan(
+      plan,
+      MetricNode(
+        inputPlanInfo.metrics,
+        inputPlanInfo.inputMetricNode :: Nil,
+        Some({
+          case ("bytes_written", v) => outputMetrics.setBytesWritten(v)
+          case ("output_rows", v) => outputMetrics.setRecordsWritten(v)
+          case _ =>
+        })),
+      inputPlanInfo.partition,
+      Some(TaskContext.get))
+
+    // native text sink outputs statistics of the written file
+    val fileStats = executed.map(Helper.ParquetSinkFileStats.fromRow).toList
+
+    // collect WriteTaskStats
+    val numRows = fileStats.map(_.numRows).sum
+    val numBytes = fileStats.map(_.numBytes).sum
+    outputMetrics.setRecordsWritten(numRows)
+    outputMetrics.setBytesWritten(numBytes)
+    val taskStats = Shims.get.createBasicWriteTaskStats(
+      Map(
+        "partitions" -> Seq[String](),
+        "numPartitions" -> 1,
+        "numFiles" -> fileStats.length,
+        "numBytes" -> numBytes,
+        "numRows" -> numRows,
+        "files" -> fileStats))
+    JniBridge.resourcesMap.put(getTaskResourceId("taskStats"), taskStats)
+  }
+}
@@ -16,9 +16,28 @@
 package org.apache.spark.sql.execution.blaze.shuffle
 
 import java.nio.ByteBuffer
+import java.nio.ByteOrder
 
 trait RssPartitionWriterBase {
   def write(partitionId: Int, buffer: ByteBuffer, length: Int): Unit
+
+  // writes coalesced by native side in one call. index contains
+  // (partitionId, length) pairs of little-endian int32, data contains
+  // all buffers concatenated in the same order.
+  def writeBatched(index: ByteBuffer, data: ByteBuffer): Unit = {
+    val indexLE = index.duplicate().order(ByteOrder.LITTLE_ENDIAN)
+    var offset = 0
+    while (indexLE.remaining() >= 8) {
+      val partitionId = indexLE.getInt()
+      val length = indexLE.getInt()
+      val buffer = data.duplicate()
+      buffer.position(offset)
+      buffer.limit(offset + length)
+      write(partitionId, buffer.slice(), length)
+      offset += length
+    }
+  }
+
   def flush(): Unit
   def close(): Unit
   def getPartitionLengthMap: Array[Long]